@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::str::FromStr;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use polymarket_client_sdk::types::Decimal;
+use polymarket_copy_rust::config::{
+    calculate_order_size, AdaptiveTail, MultiplierTier, SizingInputs,
+};
+use polymarket_copy_rust::order_templates::{parse_token_id, TickRegime};
+use polymarket_copy_rust::trading_state;
+use polymarket_copy_rust::utils::{JournalEntry, JournalStatus, MarketContext};
+use polymarket_copy_rust::{CopyStrategy, CopyStrategyConfig, RtdsActivity};
+
+const RTDS_MESSAGE: &str = r#"{"topic":"activity","type":"trades","timestamp":1760000000123,"payload":{"proxyWallet":"0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b","timestamp":1760000000,"conditionId":"0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1","type":"TRADE","size":152.5,"usdcSize":88.45,"price":0.58,"asset":"71321045679252212594626385532706912750332728571942532289631379312455583992563","side":"BUY","outcomeIndex":0,"title":"Will the Fed cut rates in December?","slug":"fed-cut-december","icon":"https://polymarket-upload.s3.us-east-2.amazonaws.com/fed.png","eventSlug":"fed-december","outcome":"Yes","name":"whale","transactionHash":"0x9a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9"}}"#;
+
+fn sizing_config() -> CopyStrategyConfig {
+    CopyStrategyConfig {
+        strategy: CopyStrategy::Adaptive,
+        copy_size: 10.0,
+        max_order_size_usd: 100.0,
+        min_order_size_usd: 1.0,
+        max_position_size_usd: Some(500.0),
+        max_daily_volume_usd: Some(2000.0),
+        adaptive_min_percent: Some(5.0),
+        adaptive_max_percent: Some(20.0),
+        adaptive_threshold: Some(500.0),
+        adaptive_tail: AdaptiveTail::Plateau,
+        tiered_multipliers: Some(vec![
+            MultiplierTier {
+                min: 0.0,
+                max: Some(100.0),
+                multiplier: 2.0,
+            },
+            MultiplierTier {
+                min: 100.0,
+                max: Some(1000.0),
+                multiplier: 1.0,
+            },
+            MultiplierTier {
+                min: 1000.0,
+                max: None,
+                multiplier: 0.5,
+            },
+        ]),
+        trade_multiplier: Some(1.0),
+    }
+}
+
+fn journal_entry() -> JournalEntry {
+    JournalEntry {
+        schema_version: 1,
+        timestamp: 1_760_000_000,
+        status: JournalStatus::Executed,
+        trader: "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b".to_string(),
+        trader_member: None,
+        slug: Some("fed-cut-december".to_string()),
+        condition_id: Some(
+            "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1".to_string(),
+        ),
+        asset: Some(
+            "71321045679252212594626385532706912750332728571942532289631379312455583992563"
+                .to_string(),
+        ),
+        side: Some("BUY".to_string()),
+        position_action: None,
+        trader_usd: Some(88.45),
+        trader_usd_reported: Some(88.45),
+        trader_usd_derived: Some(88.5),
+        my_usd: 8.7,
+        my_tokens: 15.0,
+        tx_hash: Some(
+            "0x9a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9".to_string(),
+        ),
+        reason: None,
+        skip_reason: None,
+        degraded_balance: false,
+        market: Some(MarketContext {
+            best_bid: Some(0.57),
+            best_ask: Some(0.59),
+            spread: Some(0.02),
+            bid_depth: 4200.0,
+            ask_depth: 3100.0,
+            last_trade_price: Some(0.58),
+            price_change_1h: Some(0.012),
+        }),
+        rebalance_id: None,
+        time_to_end_secs: Some(86_400),
+        rule_trace: vec![
+            "stale:allow".to_string(),
+            "balance:allow".to_string(),
+            "open_positions:allow".to_string(),
+        ],
+        shadow: false,
+        sizing_steps: Vec::new(),
+        manual: false,
+        prefetch: None,
+        order_ids: Vec::new(),
+        sizing_reasoning: None,
+    }
+}
+
+fn rtds_parsing(c: &mut Criterion) {
+    c.bench_function("rtds_parse_and_validate", |b| {
+        b.iter(|| {
+            let parsed: serde_json::Value = serde_json::from_str(black_box(RTDS_MESSAGE)).unwrap();
+            let activity: RtdsActivity =
+                serde_json::from_value(parsed.get("payload").unwrap().clone()).unwrap();
+            activity.validate(1_760_000_060).unwrap();
+            activity
+        })
+    });
+}
+
+fn sizing(c: &mut Criterion) {
+    let config = sizing_config();
+    let inputs = SizingInputs::default();
+    c.bench_function("sizing_all_stages", |b| {
+        b.iter(|| {
+            calculate_order_size(
+                &config,
+                &inputs,
+                black_box(88.45),
+                black_box(750.0),
+                black_box(120.0),
+            )
+        })
+    });
+}
+
+fn dedup(c: &mut Criterion) {
+    let keys: Vec<String> = (0..1000)
+        .map(|i| format!("0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b:0x{:064x}", i))
+        .collect();
+    c.bench_function("dedup_insert_check_1000", |b| {
+        b.iter(|| {
+            let mut seen = HashSet::new();
+            for key in &keys {
+                if !seen.contains(key) {
+                    seen.insert(key.clone());
+                }
+            }
+            seen.len()
+        })
+    });
+}
+
+fn journal_serialization(c: &mut Criterion) {
+    let entry = journal_entry();
+    c.bench_function("journal_row_serialize", |b| {
+        b.iter(|| serde_json::to_string(black_box(&entry)).unwrap())
+    });
+}
+
+/// What an order goes through between sizing and signing: the trading state check, the token
+/// id, and the price put on its tick and checked, across both tick regimes.
+fn order_preflight(c: &mut Criterion) {
+    let asset = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+    let ticks = TickRegime::new(0.01);
+    let prices = [0.5874, 0.0437, 0.9612, 0.31];
+    c.bench_function("order_preflight_validation", |b| {
+        b.iter(|| {
+            trading_state::check(black_box("BUY")).unwrap();
+            let token_id = parse_token_id(black_box(asset)).unwrap();
+            for price in prices {
+                let formatted = ticks.format_price(black_box(price));
+                ticks.check_price(price, formatted.parse().unwrap()).unwrap();
+                black_box(Decimal::from_str(&formatted).unwrap());
+            }
+            token_id
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    rtds_parsing,
+    sizing,
+    dedup,
+    journal_serialization,
+    order_preflight
+);
+criterion_main!(benches);
@@ -0,0 +1,391 @@
+//! Advisories: conditions that need a person rather than the bot (a setting that will skip
+//! most copies, an old build, a lopsided portfolio). Each has a stable id and is raised at
+//! most once per `ADVISORY_REPEAT_HOURS` while it holds, and again as soon as its payload
+//! changes. Acknowledging one (`POST /advisories/<id>/ack` on the status endpoint, or
+//! `advisories ack <id>`) silences it until its payload changes. Kept in
+//! `STATE_DIR/advisories.json` with when each was raised and acknowledged.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::alerts::{self, AlertKind};
+use crate::config::{self, EnvConfig};
+use crate::status;
+use crate::status_api;
+use crate::utils::{load_json, save_json, state_path, Logger};
+
+const ADVISORIES_FILE: &str = "advisories.json";
+
+pub const USAGE: &str = "Usage: advisories [list]\n       advisories ack <id>";
+
+static REGISTRY: Mutex<Option<AdvisoryRegistry>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdvisorySeverity {
+    Info,
+    Warning,
+    /// Sent on the critical alert path while unacknowledged.
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub severity: AdvisorySeverity,
+    pub message: String,
+    /// The facts behind the advisory; a different payload is a material change.
+    pub payload: Value,
+    pub first_raised_at: i64,
+    pub last_raised_at: i64,
+    pub times_raised: u32,
+    pub acknowledged_at: Option<i64>,
+    /// Payload when acknowledged: the advisory stays quiet while it is unchanged.
+    #[serde(default)]
+    pub acknowledged_payload: Option<Value>,
+}
+
+impl Advisory {
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledged_at.is_some()
+    }
+}
+
+/// What `raise` did with an advisory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Raised {
+    New,
+    /// Still holding after the repeat period.
+    Repeated,
+    /// The payload moved; an acknowledgment no longer covers it.
+    Changed,
+    /// Raised within the repeat period, or acknowledged and unchanged.
+    Suppressed,
+}
+
+#[derive(Debug, Default)]
+pub struct AdvisoryRegistry {
+    advisories: BTreeMap<String, Advisory>,
+    repeat_secs: i64,
+    path: Option<PathBuf>,
+}
+
+impl AdvisoryRegistry {
+    pub fn new(repeat_secs: i64) -> Self {
+        Self {
+            repeat_secs,
+            ..Self::default()
+        }
+    }
+
+    pub fn load(state_dir: &str, repeat_secs: i64) -> Self {
+        let path = state_path(state_dir, ADVISORIES_FILE);
+        Self {
+            advisories: load_json(&path).unwrap_or_default(),
+            repeat_secs,
+            path: Some(path),
+        }
+    }
+
+    pub fn raise(
+        &mut self,
+        id: &str,
+        severity: AdvisorySeverity,
+        message: &str,
+        payload: Value,
+        now: i64,
+    ) -> Raised {
+        let repeat_secs = self.repeat_secs;
+        let outcome = match self.advisories.get_mut(id) {
+            None => {
+                self.advisories.insert(
+                    id.to_string(),
+                    Advisory {
+                        id: id.to_string(),
+                        severity,
+                        message: message.to_string(),
+                        payload,
+                        first_raised_at: now,
+                        last_raised_at: now,
+                        times_raised: 1,
+                        acknowledged_at: None,
+                        acknowledged_payload: None,
+                    },
+                );
+                Raised::New
+            }
+            Some(a) => {
+                let outcome = match &a.acknowledged_payload {
+                    Some(acked) if *acked == payload => Raised::Suppressed,
+                    Some(_) => Raised::Changed,
+                    None if a.payload != payload => Raised::Changed,
+                    None if now - a.last_raised_at >= repeat_secs => Raised::Repeated,
+                    None => Raised::Suppressed,
+                };
+                a.severity = severity;
+                a.message = message.to_string();
+                a.payload = payload;
+                if outcome != Raised::Suppressed {
+                    a.last_raised_at = now;
+                    a.times_raised += 1;
+                    a.acknowledged_at = None;
+                    a.acknowledged_payload = None;
+                }
+                outcome
+            }
+        };
+        if outcome != Raised::Suppressed {
+            self.save();
+        }
+        outcome
+    }
+
+    /// The condition is gone: the next occurrence is raised as new.
+    pub fn resolve(&mut self, id: &str) -> bool {
+        let removed = self.advisories.remove(id).is_some();
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    /// Resolves every advisory under `prefix` that isn't in `current`.
+    pub fn resolve_missing(&mut self, prefix: &str, current: &[String]) {
+        let gone: Vec<String> = self
+            .advisories
+            .keys()
+            .filter(|id| id.starts_with(prefix) && !current.contains(id))
+            .cloned()
+            .collect();
+        for id in gone {
+            self.resolve(&id);
+        }
+    }
+
+    pub fn acknowledge(&mut self, id: &str, now: i64) -> bool {
+        let Some(a) = self.advisories.get_mut(id) else {
+            return false;
+        };
+        a.acknowledged_at = Some(now);
+        a.acknowledged_payload = Some(a.payload.clone());
+        self.save();
+        true
+    }
+
+    /// Most severe first, then most recent.
+    pub fn list(&self) -> Vec<&Advisory> {
+        let mut list: Vec<&Advisory> = self.advisories.values().collect();
+        list.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then(b.last_raised_at.cmp(&a.last_raised_at))
+        });
+        list
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, &self.advisories) {
+                Logger::warning(&format!("Failed to persist advisories: {}", e));
+            }
+        }
+    }
+}
+
+fn with_registry<T>(f: impl FnOnce(&mut AdvisoryRegistry) -> T) -> Option<T> {
+    let mut registry = REGISTRY.lock().ok()?;
+    Some(f(registry.get_or_insert_with(AdvisoryRegistry::default)))
+}
+
+/// Loads the advisories saved by the last run, lists them under `/status` and accepts
+/// acknowledgments on the status API.
+pub fn init(config: &EnvConfig) {
+    let repeat_secs = config.advisory_repeat_hours as i64 * 3600;
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *registry = Some(AdvisoryRegistry::load(&config.state_dir, repeat_secs));
+    }
+    status::register("advisories", || {
+        with_registry(|r| serde_json::to_value(r.list()).unwrap_or_default()).unwrap_or_default()
+    });
+    status::register_action("advisories", |rest| {
+        let id = rest
+            .strip_suffix("/ack")
+            .ok_or("expected /advisories/<id>/ack")?;
+        if acknowledge(id) {
+            Ok(serde_json::json!({ "acknowledged": id }))
+        } else {
+            Err(format!("no advisory {}", id))
+        }
+    });
+}
+
+/// Raises `id`, notifying unless it is suppressed; callers log it themselves. Unacknowledged
+/// high-severity advisories go out on the critical alert path.
+pub fn raise(id: &str, severity: AdvisorySeverity, message: &str, payload: Value) {
+    let now = chrono::Utc::now().timestamp();
+    let Some(outcome) = with_registry(|r| r.raise(id, severity, message, payload, now)) else {
+        return;
+    };
+    if outcome == Raised::Suppressed {
+        return;
+    }
+    match severity {
+        AdvisorySeverity::High => alerts::critical(AlertKind::Advisory, message),
+        _ => alerts::notify(&format!("Advisory [{}]: {}", id, message)),
+    }
+}
+
+pub fn resolve(id: &str) {
+    with_registry(|r| r.resolve(id));
+}
+
+pub fn resolve_missing(prefix: &str, current: &[String]) {
+    with_registry(|r| r.resolve_missing(prefix, current));
+}
+
+pub fn acknowledge(id: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    with_registry(|r| r.acknowledge(id, now)).unwrap_or(false)
+}
+
+fn format_time(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// `advisories [list]` prints the saved advisories; `advisories ack <id>` acknowledges one
+/// through the running bot's status endpoint, or in the state file when it isn't running.
+pub async fn run(args: &[String]) -> Result<()> {
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    let config = EnvConfig::parse()?;
+    match (args.first().map(String::as_str), args.get(1)) {
+        (None | Some("list"), _) => {
+            let registry = AdvisoryRegistry::load(&config.state_dir, 0);
+            let list = registry.list();
+            if list.is_empty() {
+                println!("No advisories.");
+            }
+            for a in list {
+                let state = match a.acknowledged_at {
+                    Some(at) => format!("acknowledged {}", format_time(at)),
+                    None => "open".to_string(),
+                };
+                println!(
+                    "[{:?}] {} - {}\n    raised {}x, last {}, {}",
+                    a.severity,
+                    a.id,
+                    a.message,
+                    a.times_raised,
+                    format_time(a.last_raised_at),
+                    state
+                );
+            }
+            Ok(())
+        }
+        (Some("ack"), Some(id)) => {
+            let path = format!("/advisories/{}/ack", id);
+            if let Some(response) = status_api::post_action(&config, &path).await {
+                let ok = response.status().is_success();
+                println!("{}", response.text().await.unwrap_or_default());
+                return if ok {
+                    Ok(())
+                } else {
+                    anyhow::bail!("acknowledgment rejected")
+                };
+            }
+            let now = chrono::Utc::now().timestamp();
+            let mut registry = AdvisoryRegistry::load(&config.state_dir, 0);
+            if !registry.acknowledge(id, now) {
+                anyhow::bail!("No advisory {}", id);
+            }
+            println!(
+                "Acknowledged {} (bot not running; saved to the state file)",
+                id
+            );
+            Ok(())
+        }
+        _ => anyhow::bail!("{}", USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HOUR: i64 = 3600;
+
+    #[test]
+    fn repeats_wait_for_the_period_unless_the_payload_moves() {
+        let mut registry = AdvisoryRegistry::new(24 * HOUR);
+        let warn = AdvisorySeverity::Warning;
+        let share = |pct: u32| json!({ "share_pct": pct });
+        assert_eq!(registry.raise("c", warn, "40%", share(40), 0), Raised::New);
+        assert_eq!(
+            registry.raise("c", warn, "40%", share(40), HOUR),
+            Raised::Suppressed
+        );
+        assert_eq!(
+            registry.raise("c", warn, "45%", share(45), 2 * HOUR),
+            Raised::Changed
+        );
+        assert_eq!(
+            registry.raise("c", warn, "45%", share(45), 25 * HOUR),
+            Raised::Suppressed
+        );
+        assert_eq!(
+            registry.raise("c", warn, "45%", share(45), 26 * HOUR),
+            Raised::Repeated
+        );
+        assert_eq!(registry.list()[0].times_raised, 3);
+    }
+
+    #[test]
+    fn acknowledgment_holds_until_a_material_change() {
+        let mut registry = AdvisoryRegistry::new(HOUR);
+        let high = AdvisorySeverity::High;
+        registry.raise("b", high, "old build", json!({ "build": "a1" }), 0);
+        assert!(registry.acknowledge("b", 10));
+        assert!(!registry.acknowledge("missing", 10));
+        // Past the repeat period but unchanged: still quiet.
+        assert_eq!(
+            registry.raise("b", high, "old build", json!({ "build": "a1" }), 10 * HOUR),
+            Raised::Suppressed
+        );
+        assert!(registry.list()[0].is_acknowledged());
+        // A different payload re-raises and clears the acknowledgment.
+        assert_eq!(
+            registry.raise("b", high, "old build", json!({ "build": "b2" }), 11 * HOUR),
+            Raised::Changed
+        );
+        assert!(!registry.list()[0].is_acknowledged());
+    }
+
+    #[test]
+    fn resolved_advisories_come_back_as_new_and_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let mut registry = AdvisoryRegistry::load(state_dir, HOUR);
+        let info = AdvisorySeverity::Info;
+        registry.raise("concentration:event:a", info, "a", json!(1), 0);
+        registry.raise("concentration:event:b", info, "b", json!(1), 0);
+        registry.raise("stale_build", AdvisorySeverity::High, "old", json!(1), 0);
+        registry.acknowledge("stale_build", 5);
+        registry.resolve_missing("concentration:", &["concentration:event:b".to_string()]);
+
+        let mut reloaded = AdvisoryRegistry::load(state_dir, HOUR);
+        let ids: Vec<&str> = reloaded.list().iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["stale_build", "concentration:event:b"]);
+        assert_eq!(reloaded.list()[0].acknowledged_at, Some(5));
+        assert_eq!(
+            reloaded.raise("concentration:event:a", info, "a", json!(1), 10),
+            Raised::New
+        );
+    }
+}
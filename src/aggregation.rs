@@ -0,0 +1,654 @@
+//! Per-trader aggregation windows learned from the journal. Some traders complete an intent in
+//! a couple of seconds, others trickle fills in over minutes; with
+//! `TRADE_AGGREGATION_ADAPTIVE` each trader's window is a percentile of the gaps between their
+//! consecutive fills of the same asset and side, bounded by the configured min/max.
+//!
+//! `PendingFills` is the window itself: the executor holds a trader's fills of one asset and
+//! side there and copies them as one merged trade when the window closes. Every fill held and
+//! every bucket copied is appended to `STATE_DIR/aggregation_buckets.jsonl`, so buckets open
+//! when the bot crashed are restored at the next start instead of being lost.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::{AdaptiveWindowConfig, EnvConfig};
+use crate::trading_state;
+use crate::types::{ActivityKind, RtdsActivity};
+use crate::utils::{read_journal, state_path, JournalEntry, Logger};
+
+const BUCKET_LOG_FILE: &str = "aggregation_buckets.jsonl";
+
+/// Gaps needed before a learned window replaces the static one.
+pub const MIN_GAP_SAMPLES: usize = 10;
+/// Journal rows older than this are not learned from.
+const LOOKBACK_SECS: i64 = 30 * 86_400;
+const REFRESH_INTERVAL: Duration = Duration::from_secs(86_400);
+
+/// Learned window per logical trader, in seconds.
+static LEARNED: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// One of a trader's fills as the journal saw it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub asset: String,
+    pub side: String,
+    pub timestamp: i64,
+}
+
+/// The window for one trader's fills: the `percentile` of the gaps between consecutive fills
+/// of the same asset and side, clamped to `min_secs..=max_secs`. Gaps longer than `max_secs`
+/// start a new intent and are not samples. `None` until `MIN_GAP_SAMPLES` gaps exist.
+pub fn learn_window(fills: &[Fill], settings: &AdaptiveWindowConfig) -> Option<u64> {
+    let mut bursts: HashMap<(&str, &str), Vec<i64>> = HashMap::new();
+    for fill in fills {
+        bursts
+            .entry((fill.asset.as_str(), fill.side.as_str()))
+            .or_default()
+            .push(fill.timestamp);
+    }
+    let mut gaps: Vec<i64> = Vec::new();
+    for times in bursts.values_mut() {
+        times.sort_unstable();
+        gaps.extend(
+            times
+                .windows(2)
+                .map(|pair| pair[1] - pair[0])
+                .filter(|gap| *gap <= settings.max_secs as i64),
+        );
+    }
+    if gaps.len() < MIN_GAP_SAMPLES {
+        return None;
+    }
+    gaps.sort_unstable();
+    // Nearest rank: the smallest gap with at least `percentile` of the samples at or below it.
+    let rank = ((settings.percentile * gaps.len() as f64).ceil() as usize).clamp(1, gaps.len());
+    let window = gaps[rank - 1].max(0) as u64;
+    Some(window.clamp(settings.min_secs, settings.max_secs))
+}
+
+/// Each trader's recent fills from journal rows. Shadow and manual rows are not trader fills.
+pub fn fills_by_trader(entries: &[JournalEntry], now: i64) -> HashMap<String, Vec<Fill>> {
+    let mut fills: HashMap<String, Vec<Fill>> = HashMap::new();
+    for entry in entries {
+        if entry.shadow || entry.manual || now - entry.timestamp > LOOKBACK_SECS {
+            continue;
+        }
+        let (Some(asset), Some(side)) = (&entry.asset, &entry.side) else {
+            continue;
+        };
+        fills.entry(entry.trader.clone()).or_default().push(Fill {
+            asset: asset.clone(),
+            side: side.to_uppercase(),
+            timestamp: entry.timestamp,
+        });
+    }
+    fills
+}
+
+/// Re-learns every trader's window from the journal. Traders without enough samples lose any
+/// previous window and fall back to the static one.
+pub fn refresh(config: &EnvConfig) {
+    let Some(settings) = config
+        .features
+        .aggregation
+        .as_ref()
+        .and_then(|a| a.adaptive.as_ref())
+    else {
+        return;
+    };
+    let entries = match read_journal(Path::new(&config.trade_log_path)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            Logger::warning(&format!("Could not learn aggregation windows: {}", e));
+            return;
+        }
+    };
+    let now = chrono::Utc::now().timestamp();
+    let learned: BTreeMap<String, u64> = fills_by_trader(&entries, now)
+        .into_iter()
+        .filter_map(|(trader, fills)| Some((trader, learn_window(&fills, settings)?)))
+        .collect();
+    if let Ok(mut windows) = LEARNED.lock() {
+        *windows = learned;
+    }
+}
+
+/// The learned window for `trader`, if there is one.
+pub fn learned_window(trader: &str) -> Option<u64> {
+    LEARNED.lock().ok()?.get(trader).copied()
+}
+
+/// The window aggregation uses for `trader`: learned when available, otherwise
+/// `TRADE_AGGREGATION_WINDOW_SECONDS`.
+pub fn window_secs(config: &EnvConfig, trader: &str) -> u64 {
+    learned_window(trader).unwrap_or_else(|| config.trade_aggregation_window_seconds())
+}
+
+/// Refreshes the learned windows once a day.
+pub async fn run_refresh(config: std::sync::Arc<EnvConfig>) {
+    while !trading_state::is_draining() {
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+        refresh(&config);
+    }
+}
+
+/// Where `PendingFills` logs its buckets.
+pub fn bucket_log(state_dir: &str) -> PathBuf {
+    state_path(state_dir, BUCKET_LOG_FILE)
+}
+
+/// One line of the bucket log. Replaying the lines in order rebuilds the open buckets.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum BucketEvent {
+    /// A fill went into the bucket; `opened_at` and `window_secs` are the bucket's.
+    Held {
+        trader: String,
+        window_secs: u64,
+        opened_at: i64,
+        fill: Box<RtdsActivity>,
+    },
+    /// The bucket was copied (or handed over at shutdown) and is gone.
+    Settled {
+        trader: String,
+        asset: String,
+        side: String,
+    },
+}
+
+/// A trader's fills of one asset and side, held until the window closes.
+#[derive(Debug)]
+pub struct HeldFills {
+    pub trader: String,
+    pub fills: Vec<RtdsActivity>,
+    usd: f64,
+    deadline: Instant,
+    window_secs: u64,
+    /// Unix time of the first fill, so a restart can tell how much of the window is left.
+    opened_at: i64,
+}
+
+impl HeldFills {
+    /// One trade for all the fills: sizes and USDC summed, the price weighted by size, and
+    /// the latest fill's timestamp and transaction hash.
+    pub fn merged(&self) -> RtdsActivity {
+        let last = self
+            .fills
+            .iter()
+            .max_by_key(|f| f.timestamp)
+            .cloned()
+            .unwrap_or_default();
+        let shares: f64 = self.fills.iter().filter_map(|f| f.size).sum();
+        let notional: f64 = self
+            .fills
+            .iter()
+            .map(|f| f.size.unwrap_or(0.0) * f.price.unwrap_or(0.0))
+            .sum();
+        let reported: Option<f64> = self.fills.iter().map(|f| f.reported_usdc_size).sum();
+        RtdsActivity {
+            size: Some(shares),
+            price: (shares > 0.0).then(|| notional / shares).or(last.price),
+            reported_usdc_size: reported,
+            ..last
+        }
+    }
+}
+
+type BucketKey = (String, String, String);
+
+/// Trades held per (trader, asset, side) for the trader's window after the first fill.
+/// `flush_usd` (0 = off) releases a bucket early once its fills add up to that much.
+#[derive(Default)]
+pub struct PendingFills {
+    held: HashMap<BucketKey, HeldFills>,
+    log: Option<PathBuf>,
+}
+
+impl PendingFills {
+    /// Reopens the buckets logged at `path`. A bucket whose window ran out while the bot was
+    /// down is due at once; the rest resume with what was left of their window. The log is
+    /// rewritten with just the open buckets.
+    pub fn restore(path: impl Into<PathBuf>, now: i64) -> Self {
+        let path = path.into();
+        let mut pending = Self::default();
+        for event in read_log(&path) {
+            match event {
+                BucketEvent::Held {
+                    trader,
+                    window_secs,
+                    opened_at,
+                    fill,
+                } => pending.insert(&trader, window_secs, opened_at, now, *fill),
+                BucketEvent::Settled {
+                    trader,
+                    asset,
+                    side,
+                } => {
+                    pending.held.remove(&(trader, asset, side));
+                }
+            }
+        }
+        let open: Vec<BucketEvent> = pending
+            .held
+            .values()
+            .flat_map(|held| {
+                held.fills.iter().map(|fill| BucketEvent::Held {
+                    trader: held.trader.clone(),
+                    window_secs: held.window_secs,
+                    opened_at: held.opened_at,
+                    fill: Box::new(fill.clone()),
+                })
+            })
+            .collect();
+        if path.exists() {
+            if let Err(e) = rewrite_log(&path, &open) {
+                Logger::warning(&format!(
+                    "Failed to compact aggregation buckets in {}: {}",
+                    path.display(),
+                    e
+                ));
+            }
+        }
+        if !pending.is_empty() {
+            Logger::info(&format!(
+                "Restored {} aggregation bucket(s) open before the last stop",
+                pending.held.len()
+            ));
+        }
+        pending.log = Some(path);
+        pending
+    }
+
+    /// Holds `fill`, or hands it back when it can't be merged (not a trade, or no asset or
+    /// side to key it by).
+    pub fn hold(
+        &mut self,
+        window_secs: u64,
+        trader: &str,
+        fill: RtdsActivity,
+    ) -> Option<RtdsActivity> {
+        let (Some(asset), Some(side)) = (fill.asset.clone(), fill.side.clone()) else {
+            return Some(fill);
+        };
+        if fill.kind() != ActivityKind::Trade {
+            return Some(fill);
+        }
+        let now = chrono::Utc::now().timestamp();
+        let key = (trader.to_string(), asset, side);
+        let (window_secs, opened_at) = self
+            .held
+            .get(&key)
+            .map_or((window_secs, now), |h| (h.window_secs, h.opened_at));
+        self.append(&BucketEvent::Held {
+            trader: trader.to_string(),
+            window_secs,
+            opened_at,
+            fill: Box::new(fill.clone()),
+        });
+        self.insert(trader, window_secs, opened_at, now, fill);
+        None
+    }
+
+    fn insert(
+        &mut self,
+        trader: &str,
+        window_secs: u64,
+        opened_at: i64,
+        now: i64,
+        fill: RtdsActivity,
+    ) {
+        let (Some(asset), Some(side)) = (fill.asset.clone(), fill.side.clone()) else {
+            return;
+        };
+        let held = self
+            .held
+            .entry((trader.to_string(), asset, side))
+            .or_insert_with(|| {
+                let left = (opened_at + window_secs as i64 - now).max(0) as u64;
+                HeldFills {
+                    trader: trader.to_string(),
+                    fills: Vec::new(),
+                    usd: 0.0,
+                    deadline: Instant::now() + Duration::from_secs(left),
+                    window_secs,
+                    opened_at,
+                }
+            });
+        held.usd += fill.size.unwrap_or(0.0) * fill.price.unwrap_or(0.0);
+        held.fills.push(fill);
+    }
+
+    /// Logs that `held` was dealt with, so a restart doesn't bring it back. Called after the
+    /// merged trade ran: a crash in between restores the bucket, and the processed-trade
+    /// dedupe stops the identical merged trade from being copied twice.
+    pub fn settle(&self, held: &HeldFills) {
+        let Some(first) = held.fills.first() else {
+            return;
+        };
+        self.append(&BucketEvent::Settled {
+            trader: held.trader.clone(),
+            asset: first.asset.clone().unwrap_or_default(),
+            side: first.side.clone().unwrap_or_default(),
+        });
+    }
+
+    fn append(&self, event: &BucketEvent) {
+        let Some(path) = &self.log else {
+            return;
+        };
+        if let Err(e) = append_log(path, event) {
+            Logger::warning(&format!(
+                "Failed to log aggregation bucket to {}: {}",
+                path.display(),
+                e
+            ));
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.held.values().map(|h| h.deadline).min()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Removes and returns every bucket whose window has closed or that reached
+    /// `flush_usd`, all of them if `all`, oldest first.
+    pub fn take_ready(&mut self, all: bool, flush_usd: f64) -> Vec<HeldFills> {
+        let now = Instant::now();
+        let ready: Vec<_> = self
+            .held
+            .iter()
+            .filter(|(_, h)| all || h.deadline <= now || (flush_usd > 0.0 && h.usd >= flush_usd))
+            .map(|(k, _)| k.clone())
+            .collect();
+        let mut taken: Vec<HeldFills> = ready
+            .into_iter()
+            .filter_map(|k| self.held.remove(&k))
+            .collect();
+        taken.sort_by_key(|h| h.deadline);
+        taken
+    }
+}
+
+/// Transaction hashes of every fill in the log at `path`, for catch-up to leave alone:
+/// open buckets are restored, settled ones are in the journal.
+pub fn logged_tx_hashes(path: &Path) -> HashSet<String> {
+    read_log(path)
+        .into_iter()
+        .filter_map(|event| match event {
+            BucketEvent::Held { fill, .. } => fill.transaction_hash,
+            BucketEvent::Settled { .. } => None,
+        })
+        .map(|tx| tx.to_lowercase())
+        .collect()
+}
+
+/// The logged events at `path`; a torn last line from a crash mid-write is skipped.
+fn read_log(path: &Path) -> Vec<BucketEvent> {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn append_log(path: &Path, event: &BucketEvent) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    Ok(())
+}
+
+/// Replaces the log via a temp file and rename, like `save_json`.
+fn rewrite_log(path: &Path, events: &[BucketEvent]) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let mut body = String::new();
+    for event in events {
+        body.push_str(&serde_json::to_string(event)?);
+        body.push('\n');
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, body)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processed_trades::ProcessedTrades;
+
+    fn settings() -> AdaptiveWindowConfig {
+        AdaptiveWindowConfig {
+            min_secs: 2,
+            max_secs: 120,
+            percentile: 0.9,
+        }
+    }
+
+    /// `bursts` intents an hour apart, each `fills` fills spaced by `gap` seconds.
+    fn bursts(asset: &str, bursts: usize, fills: usize, gap: i64) -> Vec<Fill> {
+        (0..bursts)
+            .flat_map(|b| {
+                (0..fills).map(move |f| Fill {
+                    asset: asset.to_string(),
+                    side: "BUY".to_string(),
+                    timestamp: 1_000_000 + b as i64 * 3_600 + f as i64 * gap,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fast_and_slow_traders_get_their_own_windows() {
+        let quick = bursts("a", 5, 4, 1);
+        assert_eq!(learn_window(&quick, &settings()), Some(2));
+        let trickle = bursts("a", 5, 4, 40);
+        assert_eq!(learn_window(&trickle, &settings()), Some(40));
+        let glacial = bursts("a", 5, 4, 100);
+        assert_eq!(
+            learn_window(&glacial, &AdaptiveWindowConfig { max_secs: 60, ..settings() }),
+            None
+        );
+    }
+
+    #[test]
+    fn percentile_ignores_the_odd_straggler() {
+        let mut fills = bursts("a", 10, 2, 5);
+        fills.extend(bursts("b", 1, 2, 90));
+        assert_eq!(learn_window(&fills, &settings()), Some(5));
+        assert_eq!(
+            learn_window(&fills, &AdaptiveWindowConfig { percentile: 1.0, ..settings() }),
+            Some(90)
+        );
+    }
+
+    #[test]
+    fn too_few_samples_fall_back() {
+        let fills = bursts("a", 3, 3, 10);
+        assert_eq!(learn_window(&fills, &settings()), None);
+    }
+
+    #[test]
+    fn other_assets_and_sides_do_not_form_gaps() {
+        let mut fills = Vec::new();
+        for i in 0..12 {
+            fills.push(Fill {
+                asset: format!("asset{}", i),
+                side: if i % 2 == 0 { "BUY" } else { "SELL" }.to_string(),
+                timestamp: 1_000_000 + i,
+            });
+        }
+        assert_eq!(learn_window(&fills, &settings()), None);
+    }
+
+    fn fill(asset: &str, side: &str, size: f64, price: f64, timestamp: i64) -> RtdsActivity {
+        RtdsActivity {
+            activity_type: Some("TRADE".to_string()),
+            asset: Some(asset.to_string()),
+            side: Some(side.to_string()),
+            size: Some(size),
+            price: Some(price),
+            timestamp: Some(timestamp),
+            transaction_hash: Some(format!("0x{}", timestamp)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn overlapping_windows_for_different_assets_close_separately() {
+        let mut pending = PendingFills::default();
+        assert!(pending.hold(10, "t", fill("a", "BUY", 100.0, 0.40, 1)).is_none());
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(pending.hold(10, "t", fill("b", "BUY", 50.0, 0.20, 2)).is_none());
+        assert!(pending.hold(10, "t", fill("a", "BUY", 300.0, 0.44, 3)).is_none());
+        assert!(pending.hold(10, "t", fill("a", "SELL", 10.0, 0.45, 4)).is_none());
+        assert!(pending.take_ready(false, 0.0).is_empty());
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let first = pending.take_ready(false, 0.0);
+        assert_eq!(first.len(), 1);
+        let merged = first[0].merged();
+        assert_eq!(merged.asset.as_deref(), Some("a"));
+        assert_eq!(merged.size, Some(400.0));
+        assert!((merged.price.unwrap() - 0.43).abs() < 1e-9);
+        assert_eq!(merged.timestamp, Some(3));
+        assert_eq!(merged.transaction_hash.as_deref(), Some("0x3"));
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let rest = pending.take_ready(false, 0.0);
+        let assets: Vec<_> = rest.iter().map(|h| h.merged()).collect();
+        assert_eq!(assets.len(), 2);
+        assert!(assets.iter().any(|m| m.asset.as_deref() == Some("b")));
+        assert!(assets.iter().any(|m| m.side.as_deref() == Some("SELL")));
+        assert!(pending.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn size_threshold_and_shutdown_release_buckets_early() {
+        let mut pending = PendingFills::default();
+        pending.hold(60, "t", fill("a", "BUY", 10.0, 0.5, 1));
+        pending.hold(60, "u", fill("a", "BUY", 10.0, 0.5, 1));
+        assert!(pending.take_ready(false, 10.0).is_empty());
+        pending.hold(60, "t", fill("a", "BUY", 10.0, 0.5, 2));
+        let ready = pending.take_ready(false, 10.0);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].trader, "t");
+        assert_eq!(ready[0].merged().size, Some(20.0));
+
+        let left = pending.take_ready(true, 0.0);
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].trader, "u");
+    }
+
+    #[test]
+    fn only_trades_with_an_asset_and_side_are_held() {
+        let mut pending = PendingFills::default();
+        let redeem = RtdsActivity {
+            activity_type: Some("REDEEM".to_string()),
+            ..fill("a", "BUY", 1.0, 0.5, 1)
+        };
+        assert!(pending.hold(10, "t", redeem).is_some());
+        let no_side = RtdsActivity {
+            side: None,
+            ..fill("a", "BUY", 1.0, 0.5, 1)
+        };
+        assert!(pending.hold(10, "t", no_side).is_some());
+        assert!(pending.is_empty());
+    }
+
+    /// The executor's side of a bucket: dedupe the merged trade, then settle the bucket.
+    /// Returns whether the order went out.
+    fn copy(pending: &PendingFills, held: &HeldFills, processed: &mut ProcessedTrades) -> bool {
+        let sent = processed.record(&held.merged().dedupe_key(&held.trader), 0);
+        pending.settle(held);
+        sent
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn crash_mid_window_restores_the_bucket_and_copies_it_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = bucket_log(dir.path().to_str().unwrap());
+        let now = chrono::Utc::now().timestamp();
+
+        let mut before = PendingFills::restore(&log, now);
+        before.hold(60, "t", fill("a", "BUY", 100.0, 0.40, 1));
+        before.hold(60, "t", fill("a", "BUY", 100.0, 0.50, 2));
+        drop(before);
+
+        let mut after = PendingFills::restore(&log, now + 10);
+        assert!(after.take_ready(false, 0.0).is_empty());
+        after.hold(60, "t", fill("a", "BUY", 200.0, 0.45, 3));
+        tokio::time::advance(Duration::from_secs(51)).await;
+        let ready = after.take_ready(false, 0.0);
+        assert_eq!(ready.len(), 1);
+        let merged = ready[0].merged();
+        assert_eq!(merged.size, Some(400.0));
+        assert!((merged.price.unwrap() - 0.45).abs() < 1e-9);
+
+        // Crash again after the order went out but before the bucket was settled.
+        let mut processed = ProcessedTrades::in_memory(24);
+        assert!(processed.record(&merged.dedupe_key("t"), 0));
+        drop(after);
+
+        let mut again = PendingFills::restore(&log, now + 120);
+        let ready = again.take_ready(false, 0.0);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].merged().size, Some(400.0));
+        assert!(!copy(&again, &ready[0], &mut processed));
+        drop(again);
+
+        assert!(PendingFills::restore(&log, now + 180).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn window_that_ran_out_while_down_is_due_at_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = bucket_log(dir.path().to_str().unwrap());
+        let now = chrono::Utc::now().timestamp();
+        let mut before = PendingFills::restore(&log, now);
+        before.hold(10, "t", fill("a", "BUY", 10.0, 0.5, 1));
+        before.hold(300, "t", fill("b", "SELL", 10.0, 0.5, 2));
+        drop(before);
+
+        let mut after = PendingFills::restore(&log, now + 60);
+        let ready = after.take_ready(false, 0.0);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].merged().asset.as_deref(), Some("a"));
+        let mut processed = ProcessedTrades::in_memory(24);
+        assert!(copy(&after, &ready[0], &mut processed));
+        assert!(!after.is_empty());
+    }
+
+    #[test]
+    fn catch_up_skips_fills_already_in_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = bucket_log(dir.path().to_str().unwrap());
+        let mut pending = PendingFills::restore(&log, 0);
+        pending.hold(60, "t", fill("a", "BUY", 10.0, 0.5, 1));
+        pending.hold(60, "t", fill("a", "BUY", 10.0, 0.5, 2));
+        let hashes = logged_tx_hashes(&log);
+        assert_eq!(hashes, ["0x1".to_string(), "0x2".to_string()].into());
+        assert!(logged_tx_hashes(&dir.path().join("missing.jsonl")).is_empty());
+    }
+}
@@ -0,0 +1,716 @@
+//! Outbound alerts over Telegram and/or Discord. Copy summaries go out as normal
+//! notifications to every channel, each through its own paced queue; Discord lays executed
+//! copies out as an embed. A short whitelist of critical events (`AlertKind`) has its own
+//! queue, sending task and limiter, so it is never stuck behind trade chatter. Critical sends
+//! are retried harder and fall back to the second channel when one is set. Either way a send
+//! happens on its own task: a channel outage never holds up a copy.
+
+use anyhow::Result;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+
+use crate::config::{AlertConfig, EnvConfig};
+use crate::supervisor::{supervisor, STOP_LAST};
+use crate::types::UserActivity;
+use crate::utils::{format_copy_summary, CopySummary, Logger};
+
+/// Normal notifications waiting to be sent; more are dropped.
+const NORMAL_QUEUE: usize = 1_000;
+const CRITICAL_QUEUE: usize = 16;
+/// Normal pace: a burst of 5, then one every 3s (Telegram allows about 20 a minute per chat).
+const NORMAL_BURST: u32 = 5;
+const NORMAL_PER_SEC: f64 = 1.0 / 3.0;
+/// Critical pace: a burst of 3, then one every 10s.
+const CRITICAL_BURST: u32 = 3;
+const CRITICAL_PER_SEC: f64 = 0.1;
+/// Attempts per channel for a critical alert.
+const CRITICAL_ATTEMPTS: u32 = 4;
+const CRITICAL_RETRY_BASE: Duration = Duration::from_millis(250);
+/// Attempts for a normal notification; a retry only holds up the notifications behind it.
+const NORMAL_ATTEMPTS: u32 = 3;
+const NORMAL_RETRY_BASE: Duration = Duration::from_secs(2);
+/// Discord rejects messages over 2000 characters.
+const DISCORD_MAX_CHARS: usize = 2_000;
+/// Discord's limits on an embed's title and field values.
+const EMBED_TITLE_MAX_CHARS: usize = 256;
+const EMBED_FIELD_MAX_CHARS: usize = 1_024;
+const EMBED_GREEN: u32 = 0x2e_cc_71;
+const EMBED_RED: u32 = 0xe7_4c_3c;
+/// Longest `retry_after` a rate-limited Discord send waits out before retrying once.
+const DISCORD_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The only events allowed on the critical path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    /// The wallet watchdog saw a trade the bot didn't make (possible key compromise).
+    ForeignWalletActivity,
+    /// Trading moved into a paused state.
+    TradingPaused,
+    /// A background task gave up after its restarts.
+    TaskFailed,
+    /// An unacknowledged high-severity advisory.
+    Advisory,
+    /// The disk is full and journal rows are being lost.
+    DiskFull,
+}
+
+impl AlertKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlertKind::ForeignWalletActivity => "foreign wallet activity",
+            AlertKind::TradingPaused => "trading paused",
+            AlertKind::TaskFailed => "task failed",
+            AlertKind::Advisory => "advisory",
+            AlertKind::DiskFull => "disk full",
+        }
+    }
+}
+
+/// An executed copy, for channels that can show more than text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyCard {
+    pub market: String,
+    /// The market's image (`icon` on the activity).
+    pub icon: Option<String>,
+    pub url: Option<String>,
+    /// `BUY` or `SELL`.
+    pub side: String,
+    pub outcome: Option<String>,
+    pub my_usd: f64,
+    pub price: f64,
+    pub trader_usd: Option<f64>,
+    /// How the order was sized (`OrderSizeCalculation::reasoning`); BUYs only.
+    pub reasoning: Option<String>,
+}
+
+/// What a channel is asked to send: text every channel can show, and an executed copy's
+/// details for the channels that lay them out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub text: String,
+    pub copy: Option<CopyCard>,
+}
+
+impl Notification {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            copy: None,
+        }
+    }
+}
+
+/// Somewhere a message can be sent, so tests can stand in for Telegram and Discord; a new
+/// backend is one more implementation.
+pub trait AlertChannel: Clone + Send + Sync + 'static {
+    fn name(&self) -> &'static str;
+    fn send(&self, note: &Notification) -> impl Future<Output = Result<()>> + Send;
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    text.chars().take(max).collect()
+}
+
+/// The webhook body: an embed for an executed copy, the text otherwise.
+fn discord_payload(note: &Notification) -> serde_json::Value {
+    let Some(card) = &note.copy else {
+        return serde_json::json!({ "content": truncate(&note.text, DISCORD_MAX_CHARS) });
+    };
+    let side = match &card.outcome {
+        Some(outcome) => format!("{} {}", card.side, outcome.to_uppercase()),
+        None => card.side.clone(),
+    };
+    let mut fields = vec![
+        serde_json::json!({ "name": "Side", "value": side, "inline": true }),
+        serde_json::json!({
+            "name": "My size",
+            "value": format!("${:.2} @ {:.1}¢", card.my_usd, card.price * 100.0),
+            "inline": true,
+        }),
+    ];
+    if let Some(trader_usd) = card.trader_usd {
+        fields.push(serde_json::json!({
+            "name": "Trader size",
+            "value": format!("${:.2}", trader_usd),
+            "inline": true,
+        }));
+    }
+    if let Some(reasoning) = &card.reasoning {
+        fields.push(serde_json::json!({
+            "name": "Sizing",
+            "value": truncate(reasoning, EMBED_FIELD_MAX_CHARS),
+        }));
+    }
+    let mut embed = serde_json::json!({
+        "title": truncate(&card.market, EMBED_TITLE_MAX_CHARS),
+        "description": truncate(&note.text, DISCORD_MAX_CHARS),
+        "color": if card.side == "SELL" { EMBED_RED } else { EMBED_GREEN },
+        "fields": fields,
+    });
+    if let Some(url) = &card.url {
+        embed["url"] = url.as_str().into();
+    }
+    if let Some(icon) = &card.icon {
+        embed["thumbnail"] = serde_json::json!({ "url": icon });
+    }
+    serde_json::json!({ "embeds": [embed] })
+}
+
+/// Seconds Discord asked us to wait in a 429 body.
+fn discord_retry_after(body: &serde_json::Value) -> Option<Duration> {
+    let secs = body.get("retry_after")?.as_f64()?;
+    Some(Duration::from_secs_f64(secs.max(0.0)).min(DISCORD_MAX_RETRY_AFTER))
+}
+
+#[derive(Clone)]
+pub enum Channel {
+    Telegram {
+        http: reqwest::Client,
+        bot_token: String,
+        chat_id: String,
+    },
+    Discord {
+        http: reqwest::Client,
+        webhook_url: String,
+    },
+}
+
+impl AlertChannel for Channel {
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::Telegram { .. } => "telegram",
+            Channel::Discord { .. } => "discord",
+        }
+    }
+
+    async fn send(&self, note: &Notification) -> Result<()> {
+        match self {
+            Channel::Telegram {
+                http,
+                bot_token,
+                chat_id,
+            } => {
+                http.post(format!("https://api.telegram.org/bot{}/sendMessage", bot_token))
+                    .json(&serde_json::json!({
+                        "chat_id": chat_id,
+                        "text": note.text,
+                        "disable_web_page_preview": true,
+                    }))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            Channel::Discord { http, webhook_url } => {
+                let payload = discord_payload(note);
+                let response = http.post(webhook_url).json(&payload).send().await?;
+                if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    // Wait out the rate limit once rather than burn the retries on it.
+                    let body = response.json().await.unwrap_or_default();
+                    let wait = discord_retry_after(&body).unwrap_or(DISCORD_MAX_RETRY_AFTER);
+                    tokio::time::sleep(wait).await;
+                    http.post(webhook_url)
+                        .json(&payload)
+                        .send()
+                        .await?
+                        .error_for_status()?;
+                } else {
+                    response.error_for_status()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Classic token bucket: up to `capacity` sends at once, refilled at `per_sec`.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, per_sec: f64) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            per_sec,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.last = now;
+    }
+
+    /// Waits until a send is allowed and takes it.
+    pub async fn acquire(&mut self) {
+        loop {
+            self.refill();
+            if self.tokens >= 1.0 {
+                self.tokens -= 1.0;
+                return;
+            }
+            let wait = (1.0 - self.tokens) / self.per_sec;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
+
+/// Sends normal notifications at the normal pace, with a few retries each; until `rx`
+/// closes.
+pub async fn run_normal<C: AlertChannel>(
+    channel: C,
+    rx: &mut mpsc::Receiver<Notification>,
+    mut limiter: TokenBucket,
+) {
+    while let Some(note) = rx.recv().await {
+        limiter.acquire().await;
+        if let Err(e) = send_with_retries(&channel, &note, NORMAL_ATTEMPTS, NORMAL_RETRY_BASE).await
+        {
+            Logger::warning(&format!("Notification via {} failed: {}", channel.name(), e));
+        }
+    }
+}
+
+/// Sends critical alerts as soon as their own limiter allows, with retries and the fallback
+/// channel; until `rx` closes.
+pub async fn run_critical<C: AlertChannel>(
+    primary: C,
+    fallback: Option<C>,
+    rx: &mut mpsc::Receiver<Notification>,
+    mut limiter: TokenBucket,
+) {
+    while let Some(note) = rx.recv().await {
+        limiter.acquire().await;
+        if let Err(e) = deliver_critical(&primary, fallback.as_ref(), &note).await {
+            Logger::error(&format!("Critical alert not delivered: {} ({})", e, note.text));
+        }
+    }
+}
+
+/// Up to `attempts` sends, doubling the wait from `base` after each failure.
+async fn send_with_retries<C: AlertChannel>(
+    channel: &C,
+    note: &Notification,
+    attempts: u32,
+    base: Duration,
+) -> Result<()> {
+    let mut last_err = None;
+    for attempt in 0..attempts {
+        if attempt > 0 {
+            tokio::time::sleep(base * 2u32.pow(attempt - 1)).await;
+        }
+        match channel.send(note).await {
+            Ok(()) => return Ok(()),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("{} send failed", channel.name())))
+}
+
+/// Tries `primary`, then `fallback`; returns the channel that delivered.
+async fn deliver_critical<C: AlertChannel>(
+    primary: &C,
+    fallback: Option<&C>,
+    note: &Notification,
+) -> Result<&'static str> {
+    let primary_err = match send_with_retries(primary, note, CRITICAL_ATTEMPTS, CRITICAL_RETRY_BASE)
+        .await
+    {
+        Ok(()) => return Ok(primary.name()),
+        Err(e) => e,
+    };
+    let Some(fallback) = fallback else {
+        return Err(primary_err);
+    };
+    Logger::warning(&format!(
+        "Critical alert via {} failed ({}); trying {}",
+        primary.name(),
+        primary_err,
+        fallback.name()
+    ));
+    send_with_retries(fallback, note, CRITICAL_ATTEMPTS, CRITICAL_RETRY_BASE)
+        .await
+        .map(|()| fallback.name())
+}
+
+struct Queues {
+    /// One per channel.
+    normal: Vec<mpsc::Sender<Notification>>,
+    critical: mpsc::Sender<Notification>,
+    /// `NOTIFY_MIN_ORDER_USD`: smaller copies are not announced.
+    min_order_usd: f64,
+}
+
+static QUEUES: OnceLock<Queues> = OnceLock::new();
+
+fn channels(alerts: &AlertConfig, http: &reqwest::Client) -> Vec<Channel> {
+    let mut channels = Vec::new();
+    let telegram = (&alerts.telegram_bot_token, &alerts.telegram_chat_id);
+    if let (Some(bot_token), Some(chat_id)) = telegram {
+        channels.push(Channel::Telegram {
+            http: http.clone(),
+            bot_token: bot_token.clone(),
+            chat_id: chat_id.clone(),
+        });
+    }
+    if let Some(webhook_url) = &alerts.discord_webhook_url {
+        channels.push(Channel::Discord {
+            http: http.clone(),
+            webhook_url: webhook_url.clone(),
+        });
+    }
+    channels
+}
+
+/// Starts a normal sending task per configured channel and the critical one. Telegram is
+/// the primary channel for critical alerts when both are set; Discord is the fallback.
+pub fn start(config: &EnvConfig) {
+    let Some(alerts) = config.alerts() else {
+        return;
+    };
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .build()
+        .unwrap_or_default();
+    let all = channels(alerts, &http);
+    let mut channels = all.iter().cloned();
+    let Some(primary) = channels.next() else {
+        return;
+    };
+    let fallback = channels.next();
+    let (normal_txs, normal_rxs): (Vec<_>, Vec<_>) =
+        all.iter().map(|_| mpsc::channel(NORMAL_QUEUE)).unzip();
+    let (critical_tx, critical_rx) = mpsc::channel(CRITICAL_QUEUE);
+    if QUEUES
+        .set(Queues {
+            normal: normal_txs,
+            critical: critical_tx,
+            min_order_usd: alerts.notify_min_order_usd,
+        })
+        .is_err()
+    {
+        return;
+    }
+    Logger::info(&format!(
+        "Alerts via {}{}",
+        primary.name(),
+        fallback
+            .as_ref()
+            .map(|f| format!(" (critical fallback: {})", f.name()))
+            .unwrap_or_default()
+    ));
+
+    for (channel, normal_rx) in all.into_iter().zip(normal_rxs) {
+        let normal_rx = Arc::new(Mutex::new(normal_rx));
+        let task = match channel {
+            Channel::Telegram { .. } => "alerts-telegram",
+            Channel::Discord { .. } => "alerts-discord",
+        };
+        supervisor().spawn(task, STOP_LAST, 5, move || {
+            let (channel, rx) = (channel.clone(), normal_rx.clone());
+            async move {
+                let mut rx = rx.lock().await;
+                let limiter = TokenBucket::new(NORMAL_BURST, NORMAL_PER_SEC);
+                run_normal(channel, &mut rx, limiter).await;
+                Ok(())
+            }
+        });
+    }
+    let critical_rx = Arc::new(Mutex::new(critical_rx));
+    supervisor().spawn("alerts-critical", STOP_LAST, 5, move || {
+        let (primary, fallback, rx) = (primary.clone(), fallback.clone(), critical_rx.clone());
+        async move {
+            let mut rx = rx.lock().await;
+            let limiter = TokenBucket::new(CRITICAL_BURST, CRITICAL_PER_SEC);
+            run_critical(primary, fallback, &mut rx, limiter).await;
+            Ok(())
+        }
+    });
+}
+
+fn queue(note: Notification) {
+    if let Some(queues) = QUEUES.get() {
+        for normal in &queues.normal {
+            let _ = normal.try_send(note.clone());
+        }
+    }
+}
+
+/// Queues a normal notification on every channel; dropped when alerts are off or the queue
+/// is full.
+pub fn notify(text: &str) {
+    queue(Notification::plain(text));
+}
+
+/// Queues a warning: an error or an outage rather than trade chatter.
+pub fn warn(text: &str) {
+    queue(Notification::plain(format!("⚠️ {}", text)));
+}
+
+/// Queues the notification for a placed copy, unless it is under `NOTIFY_MIN_ORDER_USD`.
+pub fn copy_placed(note: Notification) {
+    let usd = note.copy.as_ref().map_or(0.0, |c| c.my_usd);
+    if QUEUES.get().is_some_and(|q| usd >= q.min_order_usd) {
+        queue(note);
+    }
+}
+
+fn market_link(trade: &UserActivity) -> Option<String> {
+    let slug = trade.event_slug.as_ref().or(trade.slug.as_ref())?;
+    Some(format!("https://polymarket.com/event/{}", slug))
+}
+
+fn market_name(trade: &UserActivity) -> &str {
+    trade
+        .title
+        .as_deref()
+        .or(trade.asset.as_deref())
+        .unwrap_or("unknown market")
+}
+
+/// A placed copy: the day's copy line, whose trade it copied and where, and the market link;
+/// `reasoning` is how the order was sized.
+pub fn copy_message(
+    trader: &str,
+    trade: &UserActivity,
+    summary: &CopySummary,
+    reasoning: Option<String>,
+) -> Notification {
+    let mut lines = vec![
+        format_copy_summary(summary, false),
+        format!(
+            "Copied {} on {}",
+            Logger::format_address(trader),
+            market_name(trade)
+        ),
+    ];
+    lines.extend(market_link(trade));
+    Notification {
+        text: lines.join("\n"),
+        copy: Some(CopyCard {
+            market: market_name(trade).to_string(),
+            icon: trade.icon.clone(),
+            url: market_link(trade),
+            side: summary.side.clone(),
+            outcome: summary.outcome.clone(),
+            my_usd: summary.usd,
+            price: summary.price,
+            trader_usd: trade.usdc_size,
+            reasoning,
+        }),
+    }
+}
+
+/// A copy that placed nothing because every order attempt failed.
+pub fn failure_message(trader: &str, trade: &UserActivity, side: &str, reason: &str) -> String {
+    let mut lines = vec![
+        format!(
+            "Copy failed: {} on {} for {}",
+            side.to_uppercase(),
+            market_name(trade),
+            Logger::format_address(trader)
+        ),
+        reason.to_string(),
+    ];
+    lines.extend(market_link(trade));
+    lines.join("\n")
+}
+
+/// Queues a critical alert on the priority path.
+pub fn critical(kind: AlertKind, text: &str) {
+    let Some(queues) = QUEUES.get() else {
+        return;
+    };
+    let message = Notification::plain(format!("🚨 {}: {}", kind.label(), text));
+    if queues.critical.try_send(message).is_err() {
+        Logger::warning(&format!("Critical alert queue full, dropped: {}", text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Records what it delivers; a failing one only counts attempts.
+    #[derive(Clone)]
+    struct MockChannel {
+        name: &'static str,
+        fail: bool,
+        attempts: Arc<AtomicU32>,
+        sent: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MockChannel {
+        fn new(name: &'static str, fail: bool) -> Self {
+            Self {
+                name,
+                fail,
+                attempts: Arc::default(),
+                sent: Arc::default(),
+            }
+        }
+
+        fn sent(&self) -> Vec<String> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    impl AlertChannel for MockChannel {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn send(&self, note: &Notification) -> Result<()> {
+            self.attempts.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                anyhow::bail!("{} is down", self.name);
+            }
+            self.sent.lock().unwrap().push(note.text.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn critical_alert_overtakes_queued_chatter() {
+        let channel = MockChannel::new("telegram", false);
+        let (normal_tx, mut normal_rx) = mpsc::channel(NORMAL_QUEUE);
+        let (critical_tx, mut critical_rx) = mpsc::channel(CRITICAL_QUEUE);
+        for i in 0..500 {
+            normal_tx.try_send(Notification::plain(format!("Copy #{} today", i))).unwrap();
+        }
+        critical_tx.try_send(Notification::plain("🚨 foreign wallet activity")).unwrap();
+
+        let normal = channel.clone();
+        tokio::spawn(async move {
+            let limiter = TokenBucket::new(NORMAL_BURST, NORMAL_PER_SEC);
+            run_normal(normal, &mut normal_rx, limiter).await;
+        });
+        let critical = channel.clone();
+        tokio::spawn(async move {
+            let limiter = TokenBucket::new(CRITICAL_BURST, CRITICAL_PER_SEC);
+            run_critical(critical, None, &mut critical_rx, limiter).await;
+        });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let sent = channel.sent();
+        let at = sent.iter().position(|m| m.starts_with("🚨")).unwrap();
+        // At most the normal burst got out first; the rest of the chatter is still queued.
+        assert!(at <= NORMAL_BURST as usize, "critical alert sent at {}", at);
+        assert!(sent.len() < 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn critical_alert_falls_back_after_retries() {
+        let telegram = MockChannel::new("telegram", true);
+        let discord = MockChannel::new("discord", false);
+        let paused = Notification::plain("paused");
+        let via = deliver_critical(&telegram, Some(&discord), &paused).await.unwrap();
+        assert_eq!(via, "discord");
+        assert_eq!(telegram.attempts.load(Ordering::SeqCst), CRITICAL_ATTEMPTS);
+        assert_eq!(discord.sent(), vec!["paused"]);
+
+        let broken = MockChannel::new("discord", true);
+        assert!(deliver_critical(&telegram, Some(&broken), &paused).await.is_err());
+        assert!(deliver_critical(&telegram, None, &paused).await.is_err());
+    }
+
+    #[test]
+    fn copy_messages_name_the_trader_and_link_the_market() {
+        let trade = UserActivity {
+            title: Some("Will it rain?".to_string()),
+            slug: Some("will-it-rain-june".to_string()),
+            event_slug: Some("rain".to_string()),
+            icon: Some("https://example.com/rain.png".to_string()),
+            usdc_size: Some(400.0),
+            ..Default::default()
+        };
+        let summary = CopySummary {
+            number: 3,
+            side: "BUY".to_string(),
+            outcome: Some("Yes".to_string()),
+            usd: 23.5,
+            price: 0.41,
+            deployed_usd: 100.0,
+            skipped: 0,
+            balance: None,
+        };
+        let trader = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+        let note = copy_message(trader, &trade, &summary, Some("5% of trader".to_string()));
+        assert_eq!(
+            note.text,
+            "Copy #3 today | $23.50 BUY YES @ 41¢ | day: $100 deployed, 0 skipped\n\
+             Copied 0x7c3db7…5c6b on Will it rain?\n\
+             https://polymarket.com/event/rain"
+        );
+
+        let embed = &discord_payload(&note)["embeds"][0];
+        assert_eq!(embed["title"], "Will it rain?");
+        assert_eq!(embed["url"], "https://polymarket.com/event/rain");
+        assert_eq!(embed["thumbnail"]["url"], "https://example.com/rain.png");
+        assert_eq!(embed["color"], EMBED_GREEN);
+        let fields: Vec<(String, String)> = embed["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| (f["name"].as_str().unwrap().into(), f["value"].as_str().unwrap().into()))
+            .collect();
+        let field = |name: &str| fields.iter().find(|f| f.0 == name).map(|f| f.1.as_str());
+        assert_eq!(field("Side"), Some("BUY YES"));
+        assert_eq!(field("My size"), Some("$23.50 @ 41.0¢"));
+        assert_eq!(field("Trader size"), Some("$400.00"));
+        assert_eq!(field("Sizing"), Some("5% of trader"));
+
+        let sell = CopySummary {
+            side: "SELL".to_string(),
+            ..summary
+        };
+        let note = copy_message(trader, &trade, &sell, None);
+        assert_eq!(discord_payload(&note)["embeds"][0]["color"], EMBED_RED);
+        assert_eq!(discord_payload(&Notification::plain("down"))["content"], "down");
+        let failed = failure_message(trader, &UserActivity::default(), "sell", "rejected");
+        assert_eq!(failed, "Copy failed: SELL on unknown market for 0x7c3db7…5c6b\nrejected");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn normal_notification_is_retried() {
+        let channel = MockChannel::new("telegram", true);
+        let (tx, mut rx) = mpsc::channel(NORMAL_QUEUE);
+        tx.try_send(Notification::plain("Copy #1 today")).unwrap();
+        drop(tx);
+        let limiter = TokenBucket::new(NORMAL_BURST, NORMAL_PER_SEC);
+        run_normal(channel.clone(), &mut rx, limiter).await;
+        assert_eq!(channel.attempts.load(Ordering::SeqCst), NORMAL_ATTEMPTS);
+    }
+
+    #[test]
+    fn discord_rate_limit_wait_is_capped() {
+        let wait = |body: serde_json::Value| discord_retry_after(&body);
+        let rate_limited = |secs: f64| serde_json::json!({ "retry_after": secs });
+        assert_eq!(wait(rate_limited(1.5)), Some(Duration::from_millis(1_500)));
+        assert_eq!(wait(rate_limited(3_600.0)), Some(DISCORD_MAX_RETRY_AFTER));
+        assert_eq!(wait(serde_json::json!({})), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_bucket_paces_after_the_burst() {
+        let mut bucket = TokenBucket::new(CRITICAL_BURST, CRITICAL_PER_SEC);
+        let started = Instant::now();
+        for _ in 0..CRITICAL_BURST {
+            bucket.acquire().await;
+        }
+        assert_eq!(started.elapsed(), Duration::ZERO);
+        bucket.acquire().await;
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+    }
+}
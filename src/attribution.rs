@@ -0,0 +1,313 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::SizingStep;
+use crate::digest;
+use crate::utils::{flush_journal, read_journal, JournalEntry, JournalStatus, Logger};
+
+/// Sizing stages that can be switched off to see what they contributed.
+pub const MODIFIERS: &[&str] = &[
+    "adaptive",
+    "multiplier",
+    "inactivity_decay",
+    "trial",
+    "prefetch_degraded",
+    "max_order_cap",
+    "position_cap",
+    "market_share_cap",
+    "daily_volume_cap",
+    "balance_cap",
+    "min_order",
+];
+
+const MIN_LOT_TOKENS: f64 = 1e-6;
+
+/// A copied BUY fully sold again, with the sizing steps that produced it.
+#[derive(Debug, Clone)]
+pub struct ClosedLot {
+    pub asset: String,
+    pub trader: String,
+    pub cost_usd: f64,
+    pub proceeds_usd: f64,
+    pub steps: Vec<SizingStep>,
+}
+
+impl ClosedLot {
+    pub fn pnl(&self) -> f64 {
+        self.proceeds_usd - self.cost_usd
+    }
+}
+
+struct OpenLot {
+    tokens_left: f64,
+    cost_usd: f64,
+    proceeds_usd: f64,
+    trader: String,
+    steps: Vec<SizingStep>,
+}
+
+/// Matches executed live BUY and SELL rows per asset, first in first out. Lots closed by
+/// redemption don't appear in the journal and stay open.
+pub fn closed_lots(entries: &[JournalEntry]) -> Vec<ClosedLot> {
+    let mut rows: Vec<&JournalEntry> = entries
+        .iter()
+        .filter(|e| e.status == JournalStatus::Executed && !e.shadow && e.my_tokens > 0.0)
+        .collect();
+    rows.sort_by_key(|e| e.timestamp);
+
+    let mut open: HashMap<&str, VecDeque<OpenLot>> = HashMap::new();
+    let mut closed = Vec::new();
+    for row in rows {
+        let Some(asset) = row.asset.as_deref() else {
+            continue;
+        };
+        let lots = open.entry(asset).or_default();
+        if row.side.as_deref() == Some("BUY") {
+            lots.push_back(OpenLot {
+                tokens_left: row.my_tokens,
+                cost_usd: row.my_usd,
+                proceeds_usd: 0.0,
+                trader: row.trader.clone(),
+                steps: row.sizing_steps.clone(),
+            });
+            continue;
+        }
+        let price = row.my_usd / row.my_tokens;
+        let mut to_sell = row.my_tokens;
+        while to_sell > MIN_LOT_TOKENS {
+            let Some(lot) = lots.front_mut() else {
+                break;
+            };
+            let take = to_sell.min(lot.tokens_left);
+            lot.tokens_left -= take;
+            lot.proceeds_usd += take * price;
+            to_sell -= take;
+            if lot.tokens_left <= MIN_LOT_TOKENS {
+                let lot = lots.pop_front().expect("front lot exists");
+                closed.push(ClosedLot {
+                    asset: asset.to_string(),
+                    trader: lot.trader,
+                    cost_usd: lot.cost_usd,
+                    proceeds_usd: lot.proceeds_usd,
+                    steps: lot.steps,
+                });
+            }
+        }
+    }
+    closed
+}
+
+/// Order size the recorded steps would have produced without `skip`. Scaling stages apply
+/// their ratio; caps apply only where they bound, since that is the only limit recorded.
+pub fn size_without(steps: &[SizingStep], skip: &str) -> f64 {
+    let Some(first) = steps.first() else {
+        return 0.0;
+    };
+    let mut size = first.output_usd;
+    for step in &steps[1..] {
+        if step.name == skip {
+            continue;
+        }
+        match step.name.as_str() {
+            "adaptive" | "multiplier" | "inactivity_decay" | "trial" | "prefetch_degraded"
+                if step.input_usd > 0.0 =>
+            {
+                size *= step.output_usd / step.input_usd;
+            }
+            "max_order_cap" | "position_cap" | "market_share_cap" | "daily_volume_cap"
+            | "balance_cap"
+                if step.output_usd < step.input_usd =>
+            {
+                size = size.min(step.output_usd);
+            }
+            "min_order" if step.output_usd > step.input_usd => {
+                size = size.max(step.output_usd);
+            }
+            _ => {}
+        }
+    }
+    size
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModifierAttribution {
+    pub name: &'static str,
+    /// Closed lots where the modifier changed the size.
+    pub lots: usize,
+    /// Realized PnL minus the PnL had the modifier been off. Lot PnL scales linearly with size.
+    pub pnl_delta: f64,
+}
+
+pub fn attribute(lots: &[ClosedLot]) -> Vec<ModifierAttribution> {
+    MODIFIERS
+        .iter()
+        .map(|&name| {
+            let mut attribution = ModifierAttribution {
+                name,
+                ..Default::default()
+            };
+            for lot in lots {
+                let actual = size_without(&lot.steps, "");
+                if actual <= 0.0 {
+                    continue;
+                }
+                let without = size_without(&lot.steps, name);
+                if (without - actual).abs() < 1e-9 {
+                    continue;
+                }
+                attribution.lots += 1;
+                attribution.pnl_delta += lot.pnl() * (1.0 - without / actual);
+            }
+            attribution
+        })
+        .collect()
+}
+
+/// Digest line naming each modifier that changed a closed lot, with its PnL delta.
+pub fn digest_line(attributions: &[ModifierAttribution]) -> Option<String> {
+    let parts: Vec<String> = attributions
+        .iter()
+        .filter(|m| m.lots > 0)
+        .map(|m| format!("{} {:+.2}", m.name, m.pnl_delta))
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(format!("sizing PnL: {}", parts.join(", ")))
+    }
+}
+
+/// Re-reads the journal every `interval_mins` and keeps the digest's attribution note current.
+pub async fn run_digest_note(path: PathBuf, interval_mins: u64) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_mins * 60));
+    loop {
+        ticker.tick().await;
+        flush_journal().await;
+        let path = path.clone();
+        let entries = match tokio::task::spawn_blocking(move || read_journal(&path)).await {
+            Ok(Ok(entries)) => entries,
+            Ok(Err(e)) => {
+                Logger::warning(&format!("Attribution: failed to read the journal: {}", e));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        if let Some(line) = digest_line(&attribute(&closed_lots(&entries))) {
+            digest::note("attribution", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        calculate_order_size, AdaptiveTail, CopyStrategy, CopyStrategyConfig, SizingInputs,
+    };
+    use crate::utils::parse_journal_row;
+
+    fn row(timestamp: i64, side: &str, usd: f64, tokens: f64) -> JournalEntry {
+        parse_journal_row(&format!(
+            r#"{{"timestamp":{},"status":"executed","trader":"0xabc","asset":"123","side":"{}","trader_usd":0,"my_usd":{},"my_tokens":{}}}"#,
+            timestamp, side, usd, tokens
+        ))
+        .unwrap()
+    }
+
+    fn step(name: &str, input_usd: f64, output_usd: f64) -> SizingStep {
+        SizingStep {
+            name: name.to_string(),
+            input_usd,
+            output_usd,
+        }
+    }
+
+    /// Base $10, doubled by the multiplier, then capped at $15.
+    fn capped_steps() -> Vec<SizingStep> {
+        vec![
+            step("base", 100.0, 10.0),
+            step("multiplier", 10.0, 20.0),
+            step("max_order_cap", 20.0, 15.0),
+        ]
+    }
+
+    #[test]
+    fn sells_close_the_oldest_lot_first() {
+        let entries = [
+            row(1, "BUY", 5.0, 10.0),
+            row(2, "BUY", 6.0, 10.0),
+            row(3, "SELL", 12.0, 15.0),
+        ];
+        let lots = closed_lots(&entries);
+        assert_eq!(lots.len(), 1);
+        assert!((lots[0].cost_usd - 5.0).abs() < 1e-9);
+        assert!((lots[0].pnl() - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn size_without_replays_the_other_steps() {
+        let steps = capped_steps();
+        assert_eq!(size_without(&steps, ""), 15.0);
+        assert_eq!(size_without(&steps, "multiplier"), 10.0);
+        assert_eq!(size_without(&steps, "max_order_cap"), 20.0);
+        assert_eq!(size_without(&[], "multiplier"), 0.0);
+    }
+
+    #[test]
+    fn lot_pnl_is_split_by_what_each_modifier_changed() {
+        let lot = ClosedLot {
+            asset: "123".to_string(),
+            trader: "0xabc".to_string(),
+            cost_usd: 15.0,
+            proceeds_usd: 18.0,
+            steps: capped_steps(),
+        };
+        let attributions = attribute(&[lot]);
+        let by_name: HashMap<&str, &ModifierAttribution> =
+            attributions.iter().map(|m| (m.name, m)).collect();
+        assert!((by_name["multiplier"].pnl_delta - 1.0).abs() < 1e-9);
+        assert!((by_name["max_order_cap"].pnl_delta + 1.0).abs() < 1e-9);
+        assert_eq!(by_name["adaptive"].lots, 0);
+        assert_eq!(
+            digest_line(&attributions).as_deref(),
+            Some("sizing PnL: multiplier +1.00, max_order_cap -1.00")
+        );
+        assert_eq!(digest_line(&attribute(&[])), None);
+    }
+
+    #[test]
+    fn order_sizing_records_every_stage() {
+        let config = CopyStrategyConfig {
+            strategy: CopyStrategy::Percentage,
+            copy_size: 10.0,
+            max_order_size_usd: 15.0,
+            min_order_size_usd: 1.0,
+            max_position_size_usd: None,
+            max_daily_volume_usd: None,
+            adaptive_min_percent: None,
+            adaptive_max_percent: None,
+            adaptive_threshold: None,
+            adaptive_tail: AdaptiveTail::Plateau,
+            tiered_multipliers: None,
+            trade_multiplier: Some(2.0),
+        };
+        let calc = calculate_order_size(&config, &SizingInputs::default(), 100.0, 1_000.0, 0.0);
+        let names: Vec<&str> = calc.steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "base",
+                "multiplier",
+                "max_order_cap",
+                "position_cap",
+                "market_share_cap",
+                "daily_volume_cap",
+                "balance_cap",
+                "min_order"
+            ]
+        );
+        assert_eq!(calc.steps[..3], capped_steps()[..]);
+        assert_eq!(size_without(&calc.steps, ""), calc.final_amount);
+    }
+}
@@ -0,0 +1,248 @@
+//! Backfill after an RTDS reconnect (`MAX_BACKFILL_MINUTES`): RTDS only streams trades made
+//! while subscribed, so on every reconnect the monitor asks the data API for each tracked
+//! address's trades since the last one it received and sends the ones it never saw to the
+//! executor, tagged `backfilled`. The executor's usual age and dedupe checks apply to them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::catch_up;
+use crate::config::EnvConfig;
+use crate::executor::fetch_activity_since;
+use crate::types::{RtdsActivity, UserActivity};
+use crate::utils::Logger;
+
+/// What the monitor has received from RTDS since it started, across reconnects.
+#[derive(Debug, Default)]
+pub struct StreamHistory {
+    /// Latest trade timestamp received per tracked address (lowercased).
+    last_trade: HashMap<String, i64>,
+    /// Transaction hashes received or backfilled, with their trade time.
+    seen: HashMap<String, i64>,
+    /// When the first connection came up; trades before it are catch-up's concern.
+    first_connected: Option<i64>,
+}
+
+impl StreamHistory {
+    /// Notes a connection at `now`. True when an earlier connection came up before, so the
+    /// gap since it dropped needs a backfill.
+    pub fn connected(&mut self, now: i64) -> bool {
+        let reconnect = self.first_connected.is_some();
+        self.first_connected.get_or_insert(now);
+        reconnect
+    }
+
+    /// Records a trade `address` made, received live or backfilled. Hashes older than
+    /// `max_backfill_secs` are forgotten: no backfill reaches that far back.
+    pub fn observe(&mut self, address: &str, activity: &RtdsActivity, max_backfill_secs: i64) {
+        let Some(ts) = activity.timestamp else {
+            return;
+        };
+        let last = self.last_trade.entry(address.to_lowercase()).or_insert(ts);
+        *last = (*last).max(ts);
+        if let Some(tx) = activity.transaction_hash.as_deref() {
+            self.seen.insert(tx.to_lowercase(), ts);
+        }
+        let newest = self.seen.values().copied().max().unwrap_or(ts);
+        self.seen.retain(|_, t| *t >= newest - max_backfill_secs);
+    }
+
+    /// Where the backfill for `address` starts: just before its last received trade (the
+    /// seen hashes weed out that one), else the first connection, but never further back
+    /// than `max_backfill_secs`.
+    pub fn since(&self, address: &str, now: i64, max_backfill_secs: i64) -> i64 {
+        let from = self
+            .last_trade
+            .get(&address.to_lowercase())
+            .map(|ts| ts - 1)
+            .or(self.first_connected)
+            .unwrap_or(now);
+        from.max(now - max_backfill_secs)
+    }
+
+    pub fn has_seen(&self, tx: &str) -> bool {
+        self.seen.contains_key(&tx.to_lowercase())
+    }
+
+    fn seen_hashes(&self) -> HashSet<String> {
+        self.seen.keys().cloned().collect()
+    }
+}
+
+/// The trades in `activity` between `since` and `until` (the reconnect) that `history`
+/// hasn't seen, one signal per transaction, oldest first. Later trades come in live.
+pub fn missed(
+    activity: &[UserActivity],
+    since: i64,
+    until: i64,
+    history: &StreamHistory,
+) -> Vec<RtdsActivity> {
+    catch_up::missed_signals(activity, since, &history.seen_hashes())
+        .into_iter()
+        .filter(|signal| signal.timestamp.is_some_and(|ts| ts <= until))
+        .map(|signal| RtdsActivity {
+            backfilled: true,
+            ..signal
+        })
+        .collect()
+}
+
+/// Backfills the gap before a reconnect at `reconnected_at` for every tracked address.
+/// Returns the trades sent.
+pub async fn run(
+    config: &EnvConfig,
+    http_client: &reqwest::Client,
+    history: &Arc<Mutex<StreamHistory>>,
+    reconnected_at: i64,
+    tx: &tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+) -> usize {
+    let max_secs = config.max_backfill_minutes as i64 * 60;
+    let sent = send_missed(config, http_client, history, max_secs, reconnected_at, true, tx).await;
+    if sent > 0 {
+        Logger::info(&format!(
+            "Backfill: {} trade(s) made while RTDS was disconnected sent to the executor",
+            sent
+        ));
+    }
+    sent
+}
+
+/// Sends every tracked address's trades up to `until` that `history` hasn't seen, looking
+/// back at most `max_secs`, tagged `backfilled` or not. Shared by the reconnect backfill and
+/// the polling monitor, whose trades aren't late. Returns the trades sent.
+pub async fn send_missed(
+    config: &EnvConfig,
+    http_client: &reqwest::Client,
+    history: &Arc<Mutex<StreamHistory>>,
+    max_secs: i64,
+    until: i64,
+    backfilled: bool,
+    tx: &tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+) -> usize {
+    let label = if backfilled { "Backfill" } else { "Polling" };
+    let mut sent = 0;
+    for address in &config.user_addresses {
+        let address = address.to_lowercase();
+        let since = history.lock().await.since(&address, until, max_secs);
+        if since >= until {
+            continue;
+        }
+        let activity = match fetch_activity_since(http_client, config, &address, since).await {
+            Ok(activity) => activity,
+            Err(e) => {
+                Logger::warning(&format!(
+                    "{}: activity of {} unavailable: {}",
+                    label,
+                    Logger::format_address(&address),
+                    e
+                ));
+                continue;
+            }
+        };
+        let signals = missed(&activity, since, until, &*history.lock().await);
+        for mut signal in signals {
+            signal.backfilled = backfilled;
+            {
+                // A live frame may have delivered it while the request was out.
+                let mut history = history.lock().await;
+                if signal
+                    .transaction_hash
+                    .as_deref()
+                    .is_some_and(|h| history.has_seen(h))
+                {
+                    continue;
+                }
+                history.observe(&address, &signal, max_secs);
+            }
+            if tx.send((signal, address.clone())).await.is_err() {
+                return sent;
+            }
+            sent += 1;
+        }
+    }
+    sent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_000_000;
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+
+    fn live(tx: &str, ts: i64) -> RtdsActivity {
+        RtdsActivity {
+            activity_type: Some("TRADE".to_string()),
+            transaction_hash: Some(tx.to_string()),
+            timestamp: Some(ts),
+            ..RtdsActivity::default()
+        }
+    }
+
+    fn api(tx: &str, ts: i64) -> UserActivity {
+        UserActivity {
+            timestamp: Some(ts),
+            activity_type: Some("TRADE".to_string()),
+            transaction_hash: Some(tx.to_string()),
+            asset: Some("111".to_string()),
+            side: Some("BUY".to_string()),
+            size: Some(10.0),
+            price: Some(0.5),
+            ..UserActivity::default()
+        }
+    }
+
+    #[test]
+    fn only_reconnects_need_a_backfill() {
+        let mut history = StreamHistory::default();
+        assert!(!history.connected(NOW));
+        assert!(history.connected(NOW + 60));
+        // A quiet trader is backfilled from the first connection.
+        assert_eq!(history.since(TRADER, NOW + 60, 600), NOW);
+    }
+
+    #[test]
+    fn since_starts_at_the_last_trade_within_the_cap() {
+        let mut history = StreamHistory::default();
+        history.connected(NOW - 7200);
+        history.observe(&TRADER.to_uppercase(), &live("0xa", NOW - 300), 600);
+        assert_eq!(history.since(TRADER, NOW, 600), NOW - 301);
+        assert_eq!(history.since(TRADER, NOW, 120), NOW - 120);
+        assert_eq!(history.since("0xother", NOW, 600), NOW - 600);
+    }
+
+    #[test]
+    fn trades_seen_before_the_disconnect_are_not_sent_again() {
+        let mut history = StreamHistory::default();
+        history.connected(NOW - 600);
+        history.observe(TRADER, &live("0xA", NOW - 100), 600);
+        let activity = vec![
+            api("0xa", NOW - 100),
+            api("0xb", NOW - 100),
+            api("0xc", NOW - 50),
+            api("0xc", NOW - 50),
+            api("0xlive", NOW + 5),
+        ];
+        let since = history.since(TRADER, NOW, 600);
+        let signals = missed(&activity, since, NOW, &history);
+        let txs: Vec<_> = signals
+            .iter()
+            .map(|s| s.transaction_hash.as_deref().unwrap())
+            .collect();
+        assert_eq!(txs, vec!["0xb", "0xc"]);
+        assert!(signals.iter().all(|s| s.backfilled));
+        assert_eq!(signals[1].size, Some(20.0));
+        assert_eq!(signals[1].timestamp, Some(NOW - 50));
+    }
+
+    #[test]
+    fn old_hashes_are_forgotten() {
+        let mut history = StreamHistory::default();
+        history.observe(TRADER, &live("0xold", NOW - 1000), 600);
+        history.observe(TRADER, &live("0xnew", NOW), 600);
+        assert!(!history.has_seen("0xold"));
+        assert!(history.has_seen("0xNEW"));
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::SizingBalance;
+use crate::position_builder;
+use crate::resting_orders;
+
+/// Outcome of a balance lookup once the last known good value is taken into account.
+#[derive(Debug, Clone, Copy)]
+pub enum BalanceReading {
+    Fresh(f64),
+    /// RPC failed; this is the last known balance, `age_secs` old.
+    Degraded {
+        balance: f64,
+        age_secs: i64,
+    },
+    /// RPC failed and there is no last known balance recent enough to trade against.
+    Unavailable,
+}
+
+/// Wallet USDC split into what open orders have committed, the reserve, and what is free.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct BalanceBreakdown {
+    pub wallet: f64,
+    pub locked: f64,
+    pub reserve: f64,
+    /// `wallet - locked - reserve`, never below zero.
+    pub free: f64,
+}
+
+impl BalanceBreakdown {
+    pub fn new(wallet: f64, locked: f64, reserve: f64) -> Self {
+        Self {
+            wallet,
+            locked,
+            reserve,
+            free: (wallet - locked - reserve).max(0.0),
+        }
+    }
+
+    /// The figure `SIZING_BALANCE` sizes copies against.
+    pub fn for_sizing(&self, mode: SizingBalance) -> f64 {
+        match mode {
+            SizingBalance::Available => self.free,
+            SizingBalance::Wallet => (self.wallet - self.reserve).max(0.0),
+        }
+    }
+}
+
+/// USDC our open orders have committed: the unfilled part of resting BUYs and of active
+/// position build slices. Both trackers drop an order's lock as it fills or leaves the book.
+pub fn locked_in_orders(state_dir: &str) -> f64 {
+    resting_orders::locked_usd(state_dir) + position_builder::locked_usd(state_dir)
+}
+
+/// Remembers the last balance the RPC returned so a short outage doesn't zero out sizing,
+/// and what of it is spoken for.
+#[derive(Debug, Default)]
+pub struct BalanceTracker {
+    last: Option<(f64, i64)>,
+    locked_usd: f64,
+    reserve_usd: f64,
+}
+
+impl BalanceTracker {
+    pub fn with_reserve(reserve_usd: f64) -> Self {
+        Self {
+            reserve_usd,
+            ..Self::default()
+        }
+    }
+
+    /// Takes in the collateral open orders hold now (see `locked_in_orders`).
+    pub fn set_locked(&mut self, locked_usd: f64) {
+        self.locked_usd = locked_usd.max(0.0);
+    }
+
+    pub fn breakdown(&self, wallet: f64) -> BalanceBreakdown {
+        BalanceBreakdown::new(wallet, self.locked_usd, self.reserve_usd)
+    }
+
+    /// What can go into new orders out of `wallet`.
+    pub fn available_for_trading(&self, wallet: f64) -> f64 {
+        self.breakdown(wallet).free
+    }
+
+    pub fn observe(&mut self, fetched: Result<f64>, max_staleness_secs: u64) -> BalanceReading {
+        self.observe_at(fetched, max_staleness_secs, chrono::Utc::now().timestamp())
+    }
+
+    /// `observe` with the clock supplied.
+    pub fn observe_at(
+        &mut self,
+        fetched: Result<f64>,
+        max_staleness_secs: u64,
+        now: i64,
+    ) -> BalanceReading {
+        match fetched {
+            Ok(balance) => {
+                self.last = Some((balance, now));
+                BalanceReading::Fresh(balance)
+            }
+            Err(_) => match self.last {
+                Some((balance, at)) if now - at <= max_staleness_secs as i64 => {
+                    BalanceReading::Degraded {
+                        balance,
+                        age_secs: now - at,
+                    }
+                }
+                _ => BalanceReading::Unavailable,
+            },
+        }
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.last.map(|(balance, _)| balance)
+    }
+
+    /// Applies our own fill to the remembered balance until the next RPC reading replaces it.
+    pub fn apply_fill(&mut self, delta_usd: f64) {
+        if let Some((balance, _)) = self.last.as_mut() {
+            *balance = (*balance + delta_usd).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STALENESS: u64 = 300;
+
+    fn rpc_down() -> Result<f64> {
+        Err(anyhow::anyhow!("all RPC endpoints unhealthy"))
+    }
+
+    #[test]
+    fn outage_phases() {
+        let mut tracker = BalanceTracker::default();
+
+        // Healthy: the RPC answers.
+        let reading = tracker.observe_at(Ok(200.0), STALENESS, 1_000);
+        assert!(matches!(reading, BalanceReading::Fresh(b) if b == 200.0));
+
+        // Outage within the staleness window: the last known balance, with its age.
+        let reading = tracker.observe_at(rpc_down(), STALENESS, 1_000 + 120);
+        assert!(matches!(
+            reading,
+            BalanceReading::Degraded { balance, age_secs: 120 } if balance == 200.0
+        ));
+
+        // Outage past the window: nothing to trade against.
+        let reading = tracker.observe_at(rpc_down(), STALENESS, 1_000 + STALENESS as i64 + 1);
+        assert!(matches!(reading, BalanceReading::Unavailable));
+
+        // Recovery: a fresh reading replaces the stale one and restarts the window.
+        let reading = tracker.observe_at(Ok(180.0), STALENESS, 2_000);
+        assert!(matches!(reading, BalanceReading::Fresh(b) if b == 180.0));
+        assert!(matches!(
+            tracker.observe_at(rpc_down(), STALENESS, 2_010),
+            BalanceReading::Degraded { balance, age_secs: 10 } if balance == 180.0
+        ));
+    }
+
+    #[test]
+    fn outage_before_any_reading_is_unavailable() {
+        let mut tracker = BalanceTracker::default();
+        assert!(matches!(
+            tracker.observe_at(rpc_down(), STALENESS, 1_000),
+            BalanceReading::Unavailable
+        ));
+    }
+
+    #[test]
+    fn locked_and_reserve_come_off_the_free_balance() {
+        let mut tracker = BalanceTracker::with_reserve(20.0);
+        tracker.set_locked(30.0);
+        let b = tracker.breakdown(100.0);
+        assert_eq!(b, BalanceBreakdown::new(100.0, 30.0, 20.0));
+        assert_eq!(b.free, 50.0);
+        assert_eq!(b.for_sizing(SizingBalance::Available), 50.0);
+        assert_eq!(b.for_sizing(SizingBalance::Wallet), 80.0);
+
+        tracker.set_locked(95.0);
+        assert_eq!(tracker.available_for_trading(100.0), 0.0);
+        tracker.set_locked(0.0);
+        assert_eq!(tracker.available_for_trading(100.0), 80.0);
+    }
+}
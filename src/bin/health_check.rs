@@ -1,11 +1,13 @@
 use anyhow::Result;
 use polymarket_copy_rust::{
-    get_usdc_balance, perform_health_check, utils::theme::colors, EnvConfig, Logger,
+    ctf_approval, get_usdc_balance, perform_health_check, usdc_approval, utils::theme::colors,
+    DataApiClient, EnvConfig, Logger,
 };
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
+    polymarket_copy_rust::config::load_config_file()?;
 
     println!();
     println!(
@@ -26,18 +28,32 @@ async fn main() -> Result<()> {
         &config.proxy_wallet,
     )
     .await;
-    let polymarket_ok = polymarket_copy_rust::fetch_data(
-        &reqwest::Client::new(),
-        "https://data-api.polymarket.com/positions?user=0x0000000000000000000000000000000000000000",
-        config.request_timeout_ms,
-        config.network_retry_limit,
-    )
-    .await
-    .is_ok();
+    let polymarket_ok = DataApiClient::from_config(&config, &reqwest::Client::new())
+        .ping()
+        .await
+        .is_ok();
 
-    let health = perform_health_check(&config.rpc_url, balance, polymarket_ok).await;
+    let ctf_missing = ctf_approval::missing_approvals(&config.rpc_url, &config.proxy_wallet).await;
+    let usdc_short = usdc_approval::short_allowances(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+        config.min_usdc_allowance_usd,
+    )
+    .await;
+    let health = perform_health_check(
+        &config,
+        balance,
+        polymarket_ok,
+        ctf_missing,
+        usdc_short,
+    )
+    .await;
 
     Logger::separator();
+    if config.dry_run {
+        Logger::header("DRY RUN - PAPER TRADING, NO ORDERS ARE PLACED");
+    }
     Logger::header("SYSTEM CHECK");
     let overall = if health.healthy {
         "All systems go"
@@ -60,6 +76,27 @@ async fn main() -> Result<()> {
         &health.checks.polymarket_api.status,
         &health.checks.polymarket_api.message,
     );
+    Logger::health_line(
+        "CTF approval",
+        &health.checks.ctf_approval.status,
+        &health.checks.ctf_approval.message,
+    );
+    Logger::health_line(
+        "USDC allowance",
+        &health.checks.usdc_allowance.status,
+        &health.checks.usdc_allowance.message,
+    );
+    Logger::health_line(
+        "CLOB auth",
+        &health.checks.clob_auth.status,
+        &health.checks.clob_auth.message,
+    );
+    Logger::health_line(
+        "RTDS WebSocket",
+        &health.checks.websocket.status,
+        &health.checks.websocket.message,
+    );
+    Logger::health_line("Gas (POL)", &health.checks.gas.status, &health.checks.gas.message);
     Logger::separator();
 
     if health.healthy {
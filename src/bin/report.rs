@@ -0,0 +1,78 @@
+use anyhow::Result;
+use std::path::Path;
+
+use polymarket_copy_rust::attribution::{attribute, closed_lots};
+use polymarket_copy_rust::overflow::OverflowBook;
+use polymarket_copy_rust::utils::read_journal;
+use polymarket_copy_rust::{EnvConfig, Logger};
+
+const USAGE: &str = "Usage: report attribution|overflow";
+
+/// Offline reports over the trade journal.
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    polymarket_copy_rust::config::load_config_file()?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("attribution") => attribution(&EnvConfig::parse()?),
+        Some("overflow") => overflow(&EnvConfig::parse()?),
+        _ => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// Realized PnL on closed lots, split by what each sizing modifier added or cost.
+fn attribution(config: &EnvConfig) -> Result<()> {
+    let entries = read_journal(Path::new(&config.trade_log_path))?;
+    let lots = closed_lots(&entries);
+    Logger::header("SIZING ATTRIBUTION");
+    if lots.is_empty() {
+        Logger::info(&format!(
+            "No closed lots in {} yet (a lot closes when the copied BUY is fully sold)",
+            config.trade_log_path
+        ));
+        return Ok(());
+    }
+    let realized: f64 = lots.iter().map(|l| l.pnl()).sum();
+    let without_steps = lots.iter().filter(|l| l.steps.is_empty()).count();
+    Logger::info(&format!(
+        "{} closed lots, realized PnL ${:.2}",
+        lots.len(),
+        realized
+    ));
+    if without_steps > 0 {
+        Logger::info(&format!(
+            "{} lots predate sizing-step records and are not attributed",
+            without_steps
+        ));
+    }
+    println!("    {:<16} {:>6} {:>12}", "modifier", "lots", "PnL delta");
+    for m in attribute(&lots) {
+        println!("    {:<16} {:>6} {:>12.2}", m.name, m.lots, m.pnl_delta);
+    }
+    Ok(())
+}
+
+/// Markets where the position cap is keeping the trader's adds out of my copies.
+fn overflow(config: &EnvConfig) -> Result<()> {
+    let book = OverflowBook::load(&config.state_dir);
+    let mut markets: Vec<_> = book.markets().collect();
+    Logger::header("POSITION CAP OVERFLOW");
+    if markets.is_empty() {
+        Logger::info("No trader adds have been blocked by MAX_POSITION_SIZE_USD");
+        return Ok(());
+    }
+    markets.sort_by(|a, b| b.1.ignored_trader_usd.total_cmp(&a.1.ignored_trader_usd));
+    println!("    {:<40} {:>10} {:>10} {:>8}", "market", "ignored", "blocked", "cap");
+    for (cid, m) in markets {
+        let title: String = m.title.as_deref().unwrap_or(cid).chars().take(40).collect();
+        println!(
+            "    {:<40} {:>10.2} {:>10.2} {:>8.0}",
+            title, m.ignored_trader_usd, m.blocked_copy_usd, m.cap_usd
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use polymarket_copy_rust::shadow::SHADOW_UNDETERMINED;
+use polymarket_copy_rust::skip_reason::SkipReason;
+use polymarket_copy_rust::utils::{read_journal, JournalEntry, JournalStatus};
+use polymarket_copy_rust::{EnvConfig, Logger};
+
+const ENV_PATH: &str = ".env";
+/// Size changes below this fraction of the live size are not reported as resized.
+const RESIZE_TOLERANCE: f64 = 0.05;
+const MAX_LISTED: usize = 20;
+
+/// Compares the journaled shadow decisions with the live ones for the same signals.
+/// `--apply` writes the `SHADOW_CONFIG_FILE` settings into `.env` once they look right.
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    polymarket_copy_rust::config::load_config_file()?;
+    let config = EnvConfig::parse()?;
+    if std::env::args().skip(1).any(|a| a == "--apply") {
+        return apply(&config);
+    }
+
+    let entries = read_journal(Path::new(&config.trade_log_path))?;
+    let mut pairs: HashMap<String, (Option<&JournalEntry>, Option<&JournalEntry>)> = HashMap::new();
+    for entry in &entries {
+        let key = format!(
+            "{}:{}:{}",
+            entry.trader,
+            entry.tx_hash.as_deref().unwrap_or(""),
+            entry.asset.as_deref().unwrap_or("")
+        );
+        let pair = pairs.entry(key).or_default();
+        if entry.shadow {
+            pair.1 = Some(entry);
+        } else {
+            pair.0 = Some(entry);
+        }
+    }
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut resized = Vec::new();
+    let mut undetermined = 0;
+    let mut compared = 0;
+    for (live, shadow) in pairs.values().filter_map(|(l, s)| Some(((*l)?, (*s)?))) {
+        compared += 1;
+        // Rows from before `skip_reason` was journaled only carry the text.
+        if shadow.skip_reason == Some(SkipReason::Undetermined)
+            || shadow.reason.as_deref() == Some(SHADOW_UNDETERMINED)
+        {
+            undetermined += 1;
+            continue;
+        }
+        let label = format!(
+            "{} {} ({})",
+            live.side.as_deref().unwrap_or("?"),
+            live.slug.as_deref().unwrap_or("?"),
+            live.trader
+        );
+        match (live.status, shadow.status) {
+            (JournalStatus::Skipped, JournalStatus::Executed) => added.push(format!(
+                "{}: live skipped ({})",
+                label,
+                live.reason.as_deref().unwrap_or("?")
+            )),
+            (JournalStatus::Executed, JournalStatus::Skipped) => removed.push(format!(
+                "{}: shadow skips ({})",
+                label,
+                shadow.reason.as_deref().unwrap_or("?")
+            )),
+            (JournalStatus::Executed, JournalStatus::Executed)
+                if live.my_usd > 0.0
+                    && shadow.my_usd > 0.0
+                    && (shadow.my_usd - live.my_usd).abs() > live.my_usd * RESIZE_TOLERANCE =>
+            {
+                resized.push(format!(
+                    "{}: ${:.2} -> ${:.2}",
+                    label, live.my_usd, shadow.my_usd
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Logger::header("SHADOW CONFIG COMPARISON");
+    Logger::info(&format!(
+        "{} signals with both decisions in {}",
+        compared, config.trade_log_path
+    ));
+    for (title, rows) in [
+        ("Added", &added),
+        ("Removed", &removed),
+        ("Resized", &resized),
+    ] {
+        Logger::info(&format!("{}: {}", title, rows.len()));
+        for row in rows.iter().take(MAX_LISTED) {
+            println!("    {}", row);
+        }
+        if rows.len() > MAX_LISTED {
+            println!("    … {} more", rows.len() - MAX_LISTED);
+        }
+    }
+    if undetermined > 0 {
+        Logger::info(&format!(
+            "{} signals the shadow could not decide: live stopped before the balance check",
+            undetermined
+        ));
+    }
+    Ok(())
+}
+
+/// Merges the shadow file's settings into `.env`, replacing keys already set there.
+fn apply(config: &EnvConfig) -> Result<()> {
+    let path = config
+        .shadow_config_file
+        .as_deref()
+        .context("SHADOW_CONFIG_FILE is not set; nothing to apply")?;
+    EnvConfig::parse_with_overrides(path)?;
+    let overrides: Vec<(String, String)> = dotenvy::from_path_iter(path)
+        .with_context(|| format!("Failed to read {}", path))?
+        .collect::<std::result::Result<_, _>>()?;
+
+    let current = std::fs::read_to_string(ENV_PATH).unwrap_or_default();
+    let mut lines: Vec<String> = current.lines().map(str::to_string).collect();
+    for (key, value) in &overrides {
+        let line = format!("{}={}", key, value);
+        let prefix = format!("{}=", key);
+        match lines
+            .iter()
+            .position(|l| l.trim_start().starts_with(&prefix))
+        {
+            Some(i) => lines[i] = line,
+            None => lines.push(line),
+        }
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    std::fs::write(ENV_PATH, out).with_context(|| format!("Failed to write {}", ENV_PATH))?;
+    Logger::success(&format!(
+        "Applied {} settings from {} to {}. Remove SHADOW_CONFIG_FILE and restart the bot.",
+        overrides.len(),
+        path,
+        ENV_PATH
+    ));
+    Ok(())
+}
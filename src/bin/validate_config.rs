@@ -0,0 +1,62 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use polymarket_copy_rust::config::{validate_full, ConfigSource, Severity};
+use polymarket_copy_rust::Logger;
+
+/// Checks a configuration without starting the bot and exits non-zero when it has errors.
+/// `validate_config [--json] [--online] [ENV_FILE | bot.toml]`; without a file, `.env`,
+/// `bot.toml` and the process environment are checked as the bot would read them.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args.iter().any(|a| a == "--json");
+    let online = args.iter().any(|a| a == "--online");
+    let source = match args.iter().find(|a| !a.starts_with("--")) {
+        Some(path) if path.ends_with(".toml") => {
+            dotenvy::dotenv().ok();
+            ConfigSource::ConfigFile(PathBuf::from(path))
+        }
+        Some(path) => ConfigSource::EnvFile(PathBuf::from(path)),
+        None => {
+            dotenvy::dotenv().ok();
+            polymarket_copy_rust::config::load_config_file()?;
+            ConfigSource::Environment
+        }
+    };
+
+    let report = validate_full(&source, online).await;
+    if json {
+        let out = serde_json::json!({
+            "valid": report.is_valid(),
+            "issues": report.issues,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        for issue in &report.issues {
+            let line = if issue.key.is_empty() {
+                format!("[{}] {}", issue.code, issue.message)
+            } else {
+                format!("[{}] {}: {}", issue.code, issue.key, issue.message)
+            };
+            match issue.severity {
+                Severity::Error => Logger::error(&line),
+                Severity::Warning => Logger::warning(&line),
+            }
+        }
+        let errors = report.errors().count();
+        let warnings = report.warnings().count();
+        if errors == 0 {
+            Logger::success(&format!("Configuration is valid ({} warnings)", warnings));
+        } else {
+            Logger::error(&format!(
+                "{} errors, {} warnings: the bot would not start",
+                errors, warnings
+            ));
+        }
+    }
+    if !report.is_valid() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Build metadata embedded by `build.rs`.
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_HASH: &str = env!("BUILD_GIT_HASH");
+pub const RUSTC_VERSION: &str = env!("BUILD_RUSTC_VERSION");
+pub const FEATURES: &str = env!("BUILD_FEATURES");
+const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
+
+/// Unix seconds at which this binary was built.
+pub fn build_timestamp() -> i64 {
+    BUILD_TIMESTAMP.parse().unwrap_or(0)
+}
+
+pub fn build_time() -> String {
+    chrono::DateTime::from_timestamp(build_timestamp(), 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn build_age_days() -> i64 {
+    (chrono::Utc::now().timestamp() - build_timestamp()) / 86_400
+}
+
+/// One-line summary for the startup log, e.g. `v0.1.0 (3f2a9c1d04be, built 2026-10-16 09:12 UTC)`.
+pub fn summary() -> String {
+    format!("v{} ({}, built {})", VERSION, GIT_HASH, build_time())
+}
+
+/// Multi-line report for `--version --verbose`.
+pub fn verbose() -> String {
+    format!(
+        "polymarket-copy-rust {}\ncommit:   {}\nbuilt:    {}\nrustc:    {}\nfeatures: {}",
+        VERSION,
+        GIT_HASH,
+        build_time(),
+        RUSTC_VERSION,
+        if FEATURES.is_empty() { "none" } else { FEATURES }
+    )
+}
+
+/// The same fields as `verbose`, for `/status`, diagnose bundles and the log file header.
+pub fn to_json() -> serde_json::Value {
+    serde_json::json!({
+        "version": VERSION,
+        "commit": GIT_HASH,
+        "built": build_time(),
+        "rustc": RUSTC_VERSION,
+        "features": FEATURES,
+    })
+}
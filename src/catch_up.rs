@@ -0,0 +1,327 @@
+//! Catch-up after downtime (`CATCHUP_MAX_USD`): trades the traders made while the bot was
+//! down are found at startup and copied best-first instead of oldest-first. Exits go first;
+//! BUYs are scored by freshness, whether the trader still holds the position, and how far
+//! the price has moved from the trader's fill, and run in score order until the catch-up
+//! budget is spent. Whatever is left is journaled as skipped with its score.
+
+use std::collections::HashSet;
+use std::future::Future;
+
+use crate::copy_now::signal_from_tx;
+use crate::types::{RtdsActivity, UserActivity};
+
+/// A price this far above the trader's fill (relative) scores 0 on price.
+const MAX_ADVERSE_MOVE: f64 = 0.25;
+const FRESHNESS_WEIGHT: f64 = 0.4;
+const HOLDING_WEIGHT: f64 = 0.3;
+const PRICE_WEIGHT: f64 = 0.3;
+/// Score given for a factor that couldn't be looked up.
+const UNKNOWN: f64 = 0.5;
+
+/// A trade made while the bot was down, with what was looked up about it since.
+#[derive(Debug, Clone)]
+pub struct Missed {
+    pub activity: RtdsActivity,
+    /// The member address that traded.
+    pub address: String,
+    /// Whether the trader still holds the outcome, when their positions could be fetched.
+    pub trader_holds: Option<bool>,
+    /// What a BUY would pay now (best ask, else the mid).
+    pub current_price: Option<f64>,
+}
+
+impl Missed {
+    fn is_buy(&self) -> bool {
+        self.activity.side.as_deref() == Some("BUY")
+    }
+}
+
+/// A BUY's catch-up priority; each factor is in [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+    pub total: f64,
+    pub freshness: f64,
+    pub holding: f64,
+    pub price: f64,
+}
+
+impl Score {
+    pub fn describe(&self) -> String {
+        format!(
+            "score {:.2} (fresh {:.2}, held {:.2}, price {:.2})",
+            self.total, self.freshness, self.holding, self.price
+        )
+    }
+}
+
+pub fn score(missed: &Missed, now: i64, lookback_secs: i64) -> Score {
+    let age = (now - missed.activity.timestamp.unwrap_or(now)).max(0) as f64;
+    let freshness = (1.0 - age / lookback_secs.max(1) as f64).clamp(0.0, 1.0);
+    let holding = match missed.trader_holds {
+        Some(true) => 1.0,
+        Some(false) => 0.0,
+        None => UNKNOWN,
+    };
+    let fill = missed.activity.price.filter(|p| *p > 0.0);
+    let price = match (fill, missed.current_price) {
+        (Some(fill), Some(now_price)) => {
+            let adverse = ((now_price - fill) / fill).max(0.0);
+            1.0 - (adverse / MAX_ADVERSE_MOVE).min(1.0)
+        }
+        _ => UNKNOWN,
+    };
+    Score {
+        total: FRESHNESS_WEIGHT * freshness + HOLDING_WEIGHT * holding + PRICE_WEIGHT * price,
+        freshness,
+        holding,
+        price,
+    }
+}
+
+/// Where to start looking for missed trades: the last signal the bot journaled, but no
+/// further back than the lookback. `None` when there is nothing to look at.
+pub fn since(last_seen: Option<i64>, now: i64, lookback_secs: i64) -> Option<i64> {
+    last_seen
+        .map(|t| t.max(now - lookback_secs))
+        .filter(|s| *s < now)
+}
+
+/// The trader's trades after `since` that the journal doesn't know, one signal per
+/// transaction (fills merged), oldest first. Transactions mixing outcomes or sides are left
+/// out.
+pub fn missed_signals(
+    activity: &[UserActivity],
+    since: i64,
+    seen: &HashSet<String>,
+) -> Vec<RtdsActivity> {
+    let mut txs: Vec<(i64, String)> = activity
+        .iter()
+        .filter(|a| a.activity_type.as_deref().unwrap_or("TRADE") == "TRADE")
+        .filter_map(|a| Some((a.timestamp?, a.transaction_hash.clone()?.to_lowercase())))
+        .filter(|(ts, tx)| *ts > since && !seen.contains(tx))
+        .collect();
+    txs.sort();
+    txs.dedup_by(|a, b| a.1 == b.1);
+    txs.into_iter()
+        .filter_map(|(ts, tx)| signal_from_tx(activity, &tx, ts).ok())
+        .collect()
+}
+
+/// Exits first, oldest first and unscored; then BUYs best score first.
+pub fn prioritize(
+    missed: Vec<Missed>,
+    now: i64,
+    lookback_secs: i64,
+) -> Vec<(Missed, Option<Score>)> {
+    let (buys, mut exits): (Vec<Missed>, Vec<Missed>) =
+        missed.into_iter().partition(Missed::is_buy);
+    exits.sort_by_key(|m| m.activity.timestamp.unwrap_or(0));
+    let mut scored: Vec<(Missed, Option<Score>)> = buys
+        .into_iter()
+        .map(|m| {
+            let s = score(&m, now, lookback_secs);
+            (m, Some(s))
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        let (sa, sb) = (a.1.map_or(0.0, |s| s.total), b.1.map_or(0.0, |s| s.total));
+        sb.total_cmp(&sa)
+    });
+    exits.into_iter().map(|m| (m, None)).chain(scored).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Sent through the copy pipeline; BUYs report what they spent.
+    Ran {
+        spent_usd: f64,
+    },
+    Skipped(String),
+}
+
+/// Runs the prioritized signals. `execute` gets each signal with the budget left as its cap
+/// (`None` for exits, which are not budgeted) and returns the USD it spent. BUYs stop once
+/// less than `min_order_usd` is left.
+pub async fn run_plan<F, Fut>(
+    planned: Vec<(Missed, Option<Score>)>,
+    budget_usd: f64,
+    min_order_usd: f64,
+    mut execute: F,
+) -> Vec<(Missed, Option<Score>, Decision)>
+where
+    F: FnMut(&Missed, Option<f64>) -> Fut,
+    Fut: Future<Output = f64>,
+{
+    let mut spent = 0.0;
+    let mut decisions = Vec::new();
+    for (missed, score) in planned {
+        if !missed.is_buy() {
+            execute(&missed, None).await;
+            decisions.push((missed, score, Decision::Ran { spent_usd: 0.0 }));
+            continue;
+        }
+        let left = budget_usd - spent;
+        let decision = if left < min_order_usd {
+            let score = score
+                .map(|s| format!(", {}", s.describe()))
+                .unwrap_or_default();
+            Decision::Skipped(format!(
+                "catch-up budget ${:.2} used up{}",
+                budget_usd, score
+            ))
+        } else {
+            let spent_usd = execute(&missed, Some(left)).await;
+            spent += spent_usd;
+            Decision::Ran { spent_usd }
+        };
+        decisions.push((missed, score, decision));
+    }
+    decisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const NOW: i64 = 1_760_000_000;
+    const LOOKBACK: i64 = 3600;
+
+    fn missed(tx: &str, side: &str, age: i64, fill: f64, now_price: f64, holds: bool) -> Missed {
+        Missed {
+            activity: RtdsActivity {
+                timestamp: Some(NOW - age),
+                side: Some(side.to_string()),
+                price: Some(fill),
+                size: Some(100.0),
+                transaction_hash: Some(tx.to_string()),
+                ..RtdsActivity::default()
+            },
+            address: "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b".to_string(),
+            trader_holds: Some(holds),
+            current_price: Some(now_price),
+        }
+    }
+
+    #[test]
+    fn score_rewards_fresh_held_and_unmoved() {
+        let best = score(&missed("a", "BUY", 0, 0.5, 0.5, true), NOW, LOOKBACK);
+        assert!((best.total - 1.0).abs() < 1e-9);
+
+        let stale = score(&missed("b", "BUY", 1800, 0.5, 0.5, true), NOW, LOOKBACK);
+        assert!((stale.freshness - 0.5).abs() < 1e-9);
+
+        let exited = score(&missed("c", "BUY", 0, 0.5, 0.5, false), NOW, LOOKBACK);
+        assert_eq!(exited.holding, 0.0);
+
+        // 10% above the trader's fill is 40% of the way to a zero price score.
+        let moved = score(&missed("d", "BUY", 0, 0.5, 0.55, true), NOW, LOOKBACK);
+        assert!((moved.price - 0.6).abs() < 1e-9);
+        let cheaper = score(&missed("e", "BUY", 0, 0.5, 0.4, true), NOW, LOOKBACK);
+        assert_eq!(cheaper.price, 1.0);
+
+        let mut unknown = missed("f", "BUY", 0, 0.5, 0.5, true);
+        unknown.trader_holds = None;
+        unknown.current_price = None;
+        let s = score(&unknown, NOW, LOOKBACK);
+        assert_eq!((s.holding, s.price), (UNKNOWN, UNKNOWN));
+    }
+
+    #[test]
+    fn since_is_bounded_by_the_lookback() {
+        assert_eq!(since(Some(NOW - 600), NOW, LOOKBACK), Some(NOW - 600));
+        assert_eq!(
+            since(Some(NOW - 86_400), NOW, LOOKBACK),
+            Some(NOW - LOOKBACK)
+        );
+        assert_eq!(since(None, NOW, LOOKBACK), None);
+        assert_eq!(since(Some(NOW + 5), NOW, LOOKBACK), None);
+    }
+
+    #[test]
+    fn missed_signals_merge_fills_and_skip_known_trades() {
+        let fill = |tx: &str, ts: i64, size: f64| UserActivity {
+            timestamp: Some(ts),
+            activity_type: Some("TRADE".to_string()),
+            transaction_hash: Some(tx.to_string()),
+            asset: Some("111".to_string()),
+            side: Some("BUY".to_string()),
+            size: Some(size),
+            price: Some(0.5),
+            ..UserActivity::default()
+        };
+        let activity = vec![
+            fill("0xnew", NOW - 100, 10.0),
+            fill("0xnew", NOW - 100, 30.0),
+            fill("0xseen", NOW - 200, 10.0),
+            fill("0xold", NOW - 5000, 10.0),
+            fill("0xolder", NOW - 300, 10.0),
+        ];
+        let seen: HashSet<String> = ["0xseen".to_string()].into();
+        let signals = missed_signals(&activity, NOW - 1000, &seen);
+        let txs: Vec<_> = signals
+            .iter()
+            .map(|s| s.transaction_hash.clone().unwrap())
+            .collect();
+        assert_eq!(txs, vec!["0xolder", "0xnew"]);
+        assert_eq!(signals[1].size, Some(40.0));
+        assert_eq!(signals[1].timestamp, Some(NOW - 100));
+    }
+
+    /// A synthetic outage backlog: exits run first, BUYs in score order, and the budget is
+    /// never overrun even when a copy would have spent more than what was left.
+    #[tokio::test]
+    async fn outage_backlog_runs_by_priority_within_budget() {
+        let backlog = vec![
+            missed("stale", "BUY", 3000, 0.5, 0.5, true),
+            missed("exit", "SELL", 2500, 0.5, 0.5, false),
+            missed("fresh", "BUY", 60, 0.5, 0.5, true),
+            missed("moved", "BUY", 60, 0.5, 0.65, true),
+            missed("abandoned", "BUY", 120, 0.5, 0.5, false),
+        ];
+        let planned = prioritize(backlog, NOW, LOOKBACK);
+        let order: Vec<_> = planned
+            .iter()
+            .map(|(m, _)| m.activity.transaction_hash.clone().unwrap())
+            .collect();
+        assert_eq!(order, vec!["exit", "fresh", "moved", "abandoned", "stale"]);
+
+        // Every BUY would like $40; the pipeline caps each at the budget left.
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let log = calls.clone();
+        let decisions = run_plan(planned, 100.0, 5.0, move |m, cap| {
+            let tx = m.activity.transaction_hash.clone().unwrap();
+            log.lock().unwrap().push((tx, cap));
+            let spent = cap.map_or(0.0, |c| c.min(40.0));
+            async move { spent }
+        })
+        .await;
+
+        let spent: f64 = decisions
+            .iter()
+            .map(|(_, _, d)| match d {
+                Decision::Ran { spent_usd } => *spent_usd,
+                Decision::Skipped(_) => 0.0,
+            })
+            .sum();
+        assert!((spent - 100.0).abs() < 1e-9);
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                ("exit".to_string(), None),
+                ("fresh".to_string(), Some(100.0)),
+                ("moved".to_string(), Some(60.0)),
+                ("abandoned".to_string(), Some(20.0)),
+            ]
+        );
+        let (missed, score, decision) = &decisions[4];
+        assert_eq!(missed.activity.transaction_hash.as_deref(), Some("stale"));
+        assert!(score.is_some());
+        match decision {
+            Decision::Skipped(reason) => {
+                assert!(reason.starts_with("catch-up budget $100.00 used up, score "))
+            }
+            other => panic!("expected a skip, got {:?}", other),
+        }
+    }
+}
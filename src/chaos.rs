@@ -0,0 +1,222 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_util::{Stream, StreamExt};
+use polymarket_client_sdk::clob::types::response::PostOrderResponse;
+use polymarket_client_sdk::clob::types::SignedOrder;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+
+use crate::config::{ChaosConfig, EnvConfig};
+use crate::prefetch::MarketData;
+use crate::utils::{Logger, MarketContext, OrderGateway};
+
+/// Seeded fault injector. Every decision draws from one xorshift stream, so a run with the
+/// same `CHAOS_SEED` and the same input sequence makes the same choices.
+///
+/// It is not called from the code paths themselves: `ChaosMarketData`, `ChaosGateway` and
+/// `ChaosStream` wrap the market lookups, the order gateway and the RTDS stream.
+pub struct Chaos {
+    config: ChaosConfig,
+    state: Mutex<u64>,
+}
+
+/// Logs what chaos mode is about to do to this run.
+pub fn announce(config: &ChaosConfig) {
+    Logger::warning(&format!(
+        "CHAOS MODE (seed {}): up to {}ms latency, {:.0}% request failures, {:.1}% disconnects, {:.1}% malformed payloads. No orders will be placed.",
+        config.seed,
+        config.max_latency_ms,
+        config.request_failure_rate * 100.0,
+        config.disconnect_rate * 100.0,
+        config.malformed_rate * 100.0
+    ));
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig) -> Self {
+        // xorshift must never be seeded with zero.
+        let state = Mutex::new(config.seed.max(1));
+        Self { config, state }
+    }
+
+    /// An injector for `config`'s chaos settings, if chaos mode is on. The monitor and the
+    /// executor each take their own, so one's traffic doesn't shift the other's draws.
+    pub fn from_config(config: &EnvConfig) -> Option<Arc<Self>> {
+        config.chaos().map(|c| Arc::new(Self::new(c.clone())))
+    }
+
+    fn next_f64(&self) -> f64 {
+        let Ok(mut x) = self.state.lock() else {
+            return 1.0;
+        };
+        *x ^= *x << 13;
+        *x ^= *x >> 7;
+        *x ^= *x << 17;
+        (*x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Sleeps for a random delay up to `CHAOS_MAX_LATENCY_MS`.
+    pub async fn delay(&self) {
+        let ms = (self.next_f64() * self.config.max_latency_ms as f64) as u64;
+        if ms > 0 {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+        }
+    }
+
+    pub fn fail_request(&self) -> bool {
+        self.next_f64() < self.config.request_failure_rate
+    }
+
+    pub fn drop_connection(&self) -> bool {
+        self.next_f64() < self.config.disconnect_rate
+    }
+
+    /// Occasionally corrupts an activity payload the way bad RTDS data does: an impossible
+    /// price, a negative size, or a missing timestamp.
+    pub fn corrupt_payload(&self, payload: &mut serde_json::Value) -> bool {
+        if self.next_f64() >= self.config.malformed_rate {
+            return false;
+        }
+        let Some(obj) = payload.as_object_mut() else {
+            return false;
+        };
+        match (self.next_f64() * 3.0) as u8 {
+            0 => obj.insert("price".to_string(), 1.5.into()),
+            1 => obj.insert("size".to_string(), (-10.0).into()),
+            _ => obj.remove("timestamp"),
+        };
+        true
+    }
+
+    /// Delays a request, then decides whether it fails.
+    async fn request(&self, what: &str) -> Result<()> {
+        self.delay().await;
+        if self.fail_request() {
+            anyhow::bail!("Injected failure (chaos mode) for {}", what);
+        }
+        Ok(())
+    }
+}
+
+/// Market lookups behind chaos mode: each is delayed, and a failed one errors, or comes back
+/// empty where the lookup has no error to give.
+pub struct ChaosMarketData<'a, M> {
+    pub inner: M,
+    pub chaos: Option<&'a Chaos>,
+}
+
+impl<M: MarketData> ChaosMarketData<'_, M> {
+    async fn request(&self, what: &str) -> Result<()> {
+        match self.chaos {
+            Some(chaos) => chaos.request(what).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl<M: MarketData> MarketData for ChaosMarketData<'_, M> {
+    async fn tick_size(&self, asset: &str) -> Result<f64> {
+        self.request("tick size").await?;
+        self.inner.tick_size(asset).await
+    }
+    async fn neg_risk(&self, asset: &str) -> Result<bool> {
+        self.request("neg risk").await?;
+        self.inner.neg_risk(asset).await
+    }
+    async fn fee_rate_bps(&self, asset: &str) -> Result<u64> {
+        self.request("fee rate").await?;
+        self.inner.fee_rate_bps(asset).await
+    }
+    async fn book(&self, asset: &str) -> MarketContext {
+        match self.request("book").await {
+            Ok(()) => self.inner.book(asset).await,
+            Err(_) => MarketContext::default(),
+        }
+    }
+    async fn end_time(&self, condition_id: &str) -> Option<DateTime<Utc>> {
+        self.request("end time").await.ok()?;
+        self.inner.end_time(condition_id).await
+    }
+}
+
+/// Order posting behind chaos mode. A failed post either never leaves, or reaches the CLOB
+/// and loses its answer; both look like a transport error to the caller and leave the order
+/// intent pending.
+pub struct ChaosGateway<'a, G> {
+    pub inner: &'a G,
+    pub chaos: Option<&'a Chaos>,
+}
+
+impl<G: OrderGateway> OrderGateway for ChaosGateway<'_, G> {
+    async fn post(&self, order: SignedOrder) -> Result<PostOrderResponse> {
+        let Some(chaos) = self.chaos else {
+            return self.inner.post(order).await;
+        };
+        chaos.delay().await;
+        if !chaos.fail_request() {
+            return self.inner.post(order).await;
+        }
+        if chaos.next_f64() < 0.5 {
+            Logger::warning("Chaos mode: dropping an order before it reaches the CLOB");
+        } else {
+            self.inner.post(order).await?;
+            Logger::warning("Chaos mode: dropping the CLOB's answer to an order");
+        }
+        anyhow::bail!("Injected failure (chaos mode) for order post")
+    }
+}
+
+/// The RTDS read half behind chaos mode: it ends at random the way a dropped connection
+/// does, and corrupts trade payloads.
+pub struct ChaosStream<S> {
+    inner: S,
+    chaos: Option<Arc<Chaos>>,
+    dropped: bool,
+}
+
+impl<S> ChaosStream<S> {
+    pub fn new(inner: S, chaos: Option<Arc<Chaos>>) -> Self {
+        Self {
+            inner,
+            chaos,
+            dropped: false,
+        }
+    }
+}
+
+impl<S> Stream for ChaosStream<S>
+where
+    S: Stream<Item = Result<Message, WsError>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.dropped {
+            return Poll::Ready(None);
+        }
+        let frame = ready!(self.inner.poll_next_unpin(cx));
+        let Some(chaos) = self.chaos.clone() else {
+            return Poll::Ready(frame);
+        };
+        let Some(Ok(Message::Text(text))) = frame else {
+            return Poll::Ready(frame);
+        };
+        let Ok(mut parsed) = serde_json::from_str::<serde_json::Value>(&text) else {
+            return Poll::Ready(Some(Ok(Message::Text(text))));
+        };
+        if chaos.drop_connection() {
+            Logger::warning("Chaos mode: dropping RTDS connection");
+            self.dropped = true;
+            return Poll::Ready(None);
+        }
+        let corrupted = parsed
+            .get_mut("payload")
+            .is_some_and(|payload| chaos.corrupt_payload(payload));
+        let text = if corrupted { parsed.to_string() } else { text };
+        Poll::Ready(Some(Ok(Message::Text(text))))
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::config::EnvConfig;
+use crate::types::UserActivity;
+use crate::utils::{DataApiClient, Logger};
+
+/// Fewer trades than this is too little history to call a wallet anything but directional.
+const MIN_TRADES_TO_CLASSIFY: usize = 20;
+const MM_TRADES_PER_DAY: f64 = 50.0;
+const MM_TWO_SIDED_RATIO: f64 = 0.5;
+const MM_MEDIAN_HOLD_SECS: i64 = 3600;
+/// Latest trades a trader is classified from.
+const CLASSIFY_TRADES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraderClass {
+    Directional,
+    Mixed,
+    MarketMaker,
+}
+
+impl TraderClass {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TraderClass::Directional => "Directional",
+            TraderClass::Mixed => "Mixed",
+            TraderClass::MarketMaker => "Market maker",
+        }
+    }
+}
+
+/// Activity features the classification is based on.
+#[derive(Debug, Clone, Default)]
+pub struct ActivityProfile {
+    pub trades: usize,
+    pub trades_per_day: f64,
+    /// Share of markets where the wallet both bought and sold, or bought both outcomes.
+    pub two_sided_ratio: f64,
+    /// Median seconds from first buy to first later sell of the same outcome.
+    pub median_hold_secs: Option<i64>,
+}
+
+pub fn profile_activity(trades: &[UserActivity]) -> ActivityProfile {
+    let trades: Vec<&UserActivity> = trades
+        .iter()
+        .filter(|t| t.activity_type.as_deref().unwrap_or("TRADE") == "TRADE")
+        .collect();
+    if trades.is_empty() {
+        return ActivityProfile::default();
+    }
+
+    let timestamps: Vec<i64> = trades.iter().filter_map(|t| t.timestamp).collect();
+    let span_secs = match (timestamps.iter().min(), timestamps.iter().max()) {
+        (Some(min), Some(max)) => (max - min).max(3600),
+        _ => 86_400,
+    };
+    let trades_per_day = trades.len() as f64 / (span_secs as f64 / 86_400.0);
+
+    let mut markets: HashMap<&str, (HashSet<&str>, bool, bool)> = HashMap::new();
+    let mut first_buy: HashMap<&str, i64> = HashMap::new();
+    let mut first_sell_after: HashMap<&str, i64> = HashMap::new();
+    let mut sorted = trades.clone();
+    sorted.sort_by_key(|t| t.timestamp.unwrap_or(0));
+    for t in &sorted {
+        let (Some(cid), Some(asset)) = (t.condition_id.as_deref(), t.asset.as_deref()) else {
+            continue;
+        };
+        let entry = markets.entry(cid).or_default();
+        let ts = t.timestamp.unwrap_or(0);
+        if t.side_buy() {
+            entry.0.insert(asset);
+            entry.1 = true;
+            first_buy.entry(asset).or_insert(ts);
+        } else {
+            entry.2 = true;
+            if first_buy.contains_key(asset) {
+                first_sell_after.entry(asset).or_insert(ts);
+            }
+        }
+    }
+    let two_sided = markets
+        .values()
+        .filter(|(bought_assets, bought, sold)| bought_assets.len() > 1 || (*bought && *sold))
+        .count();
+
+    let mut holds: Vec<i64> = first_sell_after
+        .iter()
+        .filter_map(|(asset, sell)| first_buy.get(asset).map(|buy| sell - buy))
+        .collect();
+    holds.sort_unstable();
+
+    ActivityProfile {
+        trades: trades.len(),
+        trades_per_day,
+        two_sided_ratio: two_sided as f64 / markets.len().max(1) as f64,
+        median_hold_secs: holds.get(holds.len() / 2).copied(),
+    }
+}
+
+/// Each market-maker trait (high frequency, two-sided markets, short holds) scores a point:
+/// two or more is a market maker, one is mixed.
+pub fn classify_profile(profile: &ActivityProfile) -> TraderClass {
+    if profile.trades < MIN_TRADES_TO_CLASSIFY {
+        return TraderClass::Directional;
+    }
+    let score = [
+        profile.trades_per_day >= MM_TRADES_PER_DAY,
+        profile.two_sided_ratio >= MM_TWO_SIDED_RATIO,
+        profile
+            .median_hold_secs
+            .map(|h| h < MM_MEDIAN_HOLD_SECS)
+            .unwrap_or(false),
+    ]
+    .iter()
+    .filter(|hit| **hit)
+    .count();
+    match score {
+        0 => TraderClass::Directional,
+        1 => TraderClass::Mixed,
+        _ => TraderClass::MarketMaker,
+    }
+}
+
+static CLASSES: RwLock<Option<HashMap<String, TraderClass>>> = RwLock::new(None);
+
+/// Last computed class for a tracked trader, if classification has run.
+pub fn trader_class(address: &str) -> Option<TraderClass> {
+    CLASSES
+        .read()
+        .ok()?
+        .as_ref()?
+        .get(&address.to_lowercase())
+        .copied()
+}
+
+/// Re-classifies every tracked trader from their recent activity. Traders listed in
+/// `FORCE_DIRECTIONAL_TRADERS` are always directional; failed lookups keep the previous class.
+pub async fn refresh_trader_classes(config: &EnvConfig, http_client: &reqwest::Client) {
+    let data_api = DataApiClient::from_config(config, http_client);
+    for addr in &config.user_addresses {
+        let addr = addr.to_lowercase();
+        let class = if config.force_directional_traders.contains(&addr) {
+            TraderClass::Directional
+        } else {
+            let activity = match data_api.get_trades(&addr, CLASSIFY_TRADES).await {
+                Ok(activity) => activity,
+                Err(e) => {
+                    Logger::warning(&format!(
+                        "Could not classify {}: {}",
+                        Logger::format_address(&addr),
+                        e
+                    ));
+                    continue;
+                }
+            };
+            classify_profile(&profile_activity(&activity))
+        };
+        if let Ok(mut classes) = CLASSES.write() {
+            classes.get_or_insert_with(HashMap::new).insert(addr, class);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+    const DAY: i64 = 86_400;
+
+    fn trade(ts: i64, market: usize, outcome: usize, side: &str) -> UserActivity {
+        UserActivity {
+            activity_type: Some("TRADE".to_string()),
+            timestamp: Some(ts),
+            condition_id: Some(format!("0xmarket{}", market)),
+            asset: Some(format!("asset{}-{}", market, outcome)),
+            side: Some(side.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Buys one outcome per market across a month and sells days later.
+    fn directional_history() -> Vec<UserActivity> {
+        let mut trades = Vec::new();
+        for m in 0..15 {
+            let t = m as i64 * 2 * DAY;
+            trades.push(trade(t, m, 0, "BUY"));
+            if m % 2 == 0 {
+                trades.push(trade(t + 5 * DAY, m, 0, "SELL"));
+            }
+        }
+        trades
+    }
+
+    /// Quotes both outcomes of a handful of markets all day, flipping within minutes.
+    fn market_maker_history() -> Vec<UserActivity> {
+        let mut trades = Vec::new();
+        for i in 0..200 {
+            let t = i as i64 * 300;
+            let m = i % 5;
+            trades.push(trade(t, m, i % 2, "BUY"));
+            trades.push(trade(t + 120, m, i % 2, "SELL"));
+        }
+        trades
+    }
+
+    #[test]
+    fn directional_history_profiles_and_classifies() {
+        let profile = profile_activity(&directional_history());
+        assert_eq!(profile.trades, 23);
+        assert!(profile.trades_per_day < 1.0, "{:?}", profile);
+        assert!(profile.two_sided_ratio >= 0.5, "{:?}", profile);
+        assert_eq!(profile.median_hold_secs, Some(5 * DAY));
+        // Closing positions counts as two-sided, but that trait alone is only mixed.
+        assert_eq!(classify_profile(&profile), TraderClass::Mixed);
+
+        let holds_only: Vec<UserActivity> = directional_history()
+            .into_iter()
+            .filter(UserActivity::side_buy)
+            .chain((15..25).map(|m| trade(m as i64 * 2 * DAY, m, 0, "BUY")))
+            .collect();
+        assert_eq!(
+            classify_profile(&profile_activity(&holds_only)),
+            TraderClass::Directional
+        );
+    }
+
+    #[test]
+    fn market_maker_history_classifies_as_market_maker() {
+        let profile = profile_activity(&market_maker_history());
+        assert!(profile.trades_per_day >= MM_TRADES_PER_DAY, "{:?}", profile);
+        assert_eq!(profile.two_sided_ratio, 1.0);
+        assert!(profile.median_hold_secs.unwrap() < MM_MEDIAN_HOLD_SECS);
+        assert_eq!(classify_profile(&profile), TraderClass::MarketMaker);
+    }
+
+    #[test]
+    fn two_traits_are_enough_for_market_maker() {
+        let profile = ActivityProfile {
+            trades: 100,
+            trades_per_day: 80.0,
+            two_sided_ratio: 0.1,
+            median_hold_secs: Some(10 * 60),
+        };
+        assert_eq!(classify_profile(&profile), TraderClass::MarketMaker);
+        let one_trait = ActivityProfile {
+            median_hold_secs: Some(2 * HOUR),
+            ..profile
+        };
+        assert_eq!(classify_profile(&one_trait), TraderClass::Mixed);
+    }
+
+    #[test]
+    fn short_histories_stay_directional() {
+        let few: Vec<UserActivity> = market_maker_history()
+            .into_iter()
+            .take(MIN_TRADES_TO_CLASSIFY - 1)
+            .collect();
+        assert_eq!(
+            classify_profile(&profile_activity(&few)),
+            TraderClass::Directional
+        );
+    }
+
+    #[test]
+    fn non_trade_activity_is_ignored() {
+        let mut history = directional_history();
+        for i in 0..50 {
+            history.push(UserActivity {
+                activity_type: Some("REDEEM".to_string()),
+                timestamp: Some(i),
+                ..Default::default()
+            });
+        }
+        assert_eq!(profile_activity(&history).trades, 23);
+        assert_eq!(profile_activity(&[]).trades, 0);
+    }
+}
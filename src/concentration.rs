@@ -0,0 +1,368 @@
+//! Advisory concentration report for the copied portfolio: exposure by event, by category and
+//! by direction (category plus outcome, a rough stand-in for markets that pay off in the same
+//! scenario). Buckets over their `CONCENTRATION_MAX_*_PERCENT` limit are reported with the
+//! cheapest outcomes that would offset them. Nothing is traded.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::{ConcentrationConfig, EnvConfig};
+use crate::ledger::SharedLedger;
+use crate::metadata_cache;
+use crate::types::UserPosition;
+
+/// Offsetting outcomes listed per breached bucket.
+const OFFSETS_SHOWN: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Offset {
+    pub asset: String,
+    pub title: String,
+    pub outcome: String,
+    pub price: f64,
+}
+
+/// One open position as the report sees it.
+#[derive(Debug, Clone)]
+pub struct Holding {
+    pub title: String,
+    pub outcome: String,
+    pub event: Option<String>,
+    pub category: Option<String>,
+    pub value_usd: f64,
+    /// The other side of the market, priced at 1 - the current price.
+    pub offset: Option<Offset>,
+}
+
+impl Holding {
+    pub fn from_position(position: &UserPosition, category: Option<String>) -> Option<Self> {
+        let value_usd = position.current_value.unwrap_or(0.0);
+        if value_usd <= 0.0 {
+            return None;
+        }
+        let title = position
+            .title
+            .clone()
+            .or_else(|| position.slug.clone())
+            .unwrap_or_else(|| "unknown market".to_string());
+        let offset = match (&position.opposite_asset, position.cur_price) {
+            (Some(asset), Some(price)) => Some(Offset {
+                asset: asset.clone(),
+                title: title.clone(),
+                outcome: position.opposite_outcome.clone().unwrap_or_default(),
+                price: (1.0 - price).clamp(0.0, 1.0),
+            }),
+            _ => None,
+        };
+        Some(Self {
+            title,
+            outcome: position.outcome.clone().unwrap_or_default(),
+            event: position.event_slug.clone(),
+            category,
+            value_usd,
+            offset,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BucketKind {
+    Event,
+    Category,
+    Direction,
+}
+
+impl BucketKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BucketKind::Event => "event",
+            BucketKind::Category => "category",
+            BucketKind::Direction => "direction",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    pub kind: BucketKind,
+    pub key: String,
+    pub value_usd: f64,
+    /// Share of the whole portfolio's value.
+    pub share: f64,
+    /// Indexes into the holdings.
+    pub members: Vec<usize>,
+}
+
+/// Groups holdings by event, category and direction. Holdings without an event or category
+/// are left out of those buckets.
+pub fn buckets(holdings: &[Holding]) -> Vec<Bucket> {
+    let total: f64 = holdings.iter().map(|h| h.value_usd).sum();
+    let mut grouped: BTreeMap<(BucketKind, String), (f64, Vec<usize>)> = BTreeMap::new();
+    for (i, holding) in holdings.iter().enumerate() {
+        let mut keys = Vec::new();
+        if let Some(event) = &holding.event {
+            keys.push((BucketKind::Event, event.clone()));
+        }
+        if let Some(category) = &holding.category {
+            keys.push((BucketKind::Category, category.clone()));
+            let outcome = if holding.outcome.is_empty() {
+                "?"
+            } else {
+                holding.outcome.as_str()
+            };
+            keys.push((BucketKind::Direction, format!("{} / {}", category, outcome)));
+        }
+        for key in keys {
+            let entry = grouped.entry(key).or_default();
+            entry.0 += holding.value_usd;
+            entry.1.push(i);
+        }
+    }
+    grouped
+        .into_iter()
+        .map(|((kind, key), (value_usd, members))| Bucket {
+            kind,
+            key,
+            value_usd,
+            share: if total > 0.0 { value_usd / total } else { 0.0 },
+            members,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Breach {
+    pub bucket: Bucket,
+    pub limit: f64,
+    /// Cheapest first.
+    pub offsets: Vec<Offset>,
+}
+
+fn limit(config: &ConcentrationConfig, kind: BucketKind) -> f64 {
+    match kind {
+        BucketKind::Event => config.max_event_share,
+        BucketKind::Category => config.max_category_share,
+        BucketKind::Direction => config.max_direction_share,
+    }
+}
+
+/// Buckets over their limit (0 = that kind is off), largest share first.
+pub fn breaches(holdings: &[Holding], config: &ConcentrationConfig) -> Vec<Breach> {
+    let mut breaches: Vec<Breach> = buckets(holdings)
+        .into_iter()
+        .filter_map(|bucket| {
+            let limit = limit(config, bucket.kind);
+            if limit <= 0.0 || bucket.share <= limit {
+                return None;
+            }
+            let mut offsets: Vec<Offset> = bucket
+                .members
+                .iter()
+                .filter_map(|&i| holdings[i].offset.clone())
+                .collect();
+            offsets.sort_by(|a, b| a.price.total_cmp(&b.price));
+            offsets.dedup_by(|a, b| a.asset == b.asset);
+            offsets.truncate(OFFSETS_SHOWN);
+            Some(Breach {
+                bucket,
+                limit,
+                offsets,
+            })
+        })
+        .collect();
+    breaches.sort_by(|a, b| b.bucket.share.total_cmp(&a.bucket.share));
+    breaches
+}
+
+pub fn report_lines(breaches: &[Breach]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for breach in breaches {
+        let bucket = &breach.bucket;
+        lines.push(format!(
+            "⚖️ {} {}: $ {:.2} in {} position(s), {:.0}% of the portfolio (limit {:.0}%)",
+            bucket.kind.as_str(),
+            bucket.key,
+            bucket.value_usd,
+            bucket.members.len(),
+            bucket.share * 100.0,
+            breach.limit * 100.0
+        ));
+        for offset in &breach.offsets {
+            lines.push(format!(
+                "   offset: {} {} @ {:.0}¢",
+                offset.title,
+                offset.outcome,
+                offset.price * 100.0
+            ));
+        }
+    }
+    lines
+}
+
+async fn market_category(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    condition_id: &str,
+) -> Option<String> {
+    metadata_cache::lookup(http_client, config, condition_id)
+        .await
+        .ok()?
+        .category
+}
+
+/// Builds the holdings from the wallet's positions, leaving out dust the ledger wrote off.
+pub async fn holdings(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    positions: &[UserPosition],
+    ledger: &SharedLedger,
+) -> Vec<Holding> {
+    let mut holdings = Vec::new();
+    for position in positions {
+        if let Some(asset) = &position.asset {
+            if ledger.lock().await.is_dust(asset) {
+                continue;
+            }
+        }
+        let category = match &position.condition_id {
+            Some(cid) => market_category(http_client, config, cid).await,
+            None => None,
+        };
+        holdings.extend(Holding::from_position(position, category));
+    }
+    holdings
+}
+
+/// Reports breaches when they change, so a standing concentration isn't repeated every check.
+#[derive(Default)]
+pub struct ConcentrationMonitor {
+    last: HashMap<(BucketKind, String), u32>,
+}
+
+impl ConcentrationMonitor {
+    /// The report lines when the set of breached buckets (or their rounded share) moved.
+    pub fn check(&mut self, holdings: &[Holding], config: &ConcentrationConfig) -> Vec<String> {
+        let breaches = breaches(holdings, config);
+        let current: HashMap<(BucketKind, String), u32> = breaches
+            .iter()
+            .map(|b| {
+                let share = (b.bucket.share * 20.0).round() as u32;
+                ((b.bucket.kind, b.bucket.key.clone()), share)
+            })
+            .collect();
+        if current == self.last {
+            return Vec::new();
+        }
+        self.last = current;
+        report_lines(&breaches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A holding in `event` priced at `price`, worth `usd`.
+    fn holding(
+        event: &str,
+        category: Option<&str>,
+        outcome: &str,
+        usd: f64,
+        price: f64,
+    ) -> Holding {
+        Holding {
+            title: event.to_string(),
+            outcome: outcome.to_string(),
+            event: Some(event.to_string()),
+            category: category.map(str::to_string),
+            value_usd: usd,
+            offset: Some(Offset {
+                asset: format!("{}-{}-other", event, outcome),
+                title: event.to_string(),
+                outcome: if outcome == "Yes" { "No" } else { "Yes" }.to_string(),
+                price: 1.0 - price,
+            }),
+        }
+    }
+
+    fn limits(event: f64, category: f64, direction: f64) -> ConcentrationConfig {
+        ConcentrationConfig {
+            interval_secs: 3600,
+            max_event_share: event,
+            max_category_share: category,
+            max_direction_share: direction,
+        }
+    }
+
+    fn portfolio() -> Vec<Holding> {
+        vec![
+            holding("fed-december", Some("Economics"), "Yes", 40.0, 0.70),
+            holding("fed-december", Some("Economics"), "No", 10.0, 0.30),
+            holding("cpi-above-3", Some("Economics"), "Yes", 20.0, 0.55),
+            holding("senate-control", Some("Politics"), "Yes", 20.0, 0.60),
+            holding("nba-finals", None, "No", 10.0, 0.90),
+        ]
+    }
+
+    #[test]
+    fn buckets_by_event_category_and_direction() {
+        let buckets = buckets(&portfolio());
+        let share = |kind, key: &str| {
+            buckets
+                .iter()
+                .find(|b| b.kind == kind && b.key == key)
+                .map(|b| (b.value_usd, b.members.len()))
+        };
+        assert_eq!(share(BucketKind::Event, "fed-december"), Some((50.0, 2)));
+        assert_eq!(share(BucketKind::Category, "Economics"), Some((70.0, 3)));
+        assert_eq!(share(BucketKind::Direction, "Economics / Yes"), Some((60.0, 2)));
+        assert_eq!(share(BucketKind::Direction, "Economics / No"), Some((10.0, 1)));
+        // No category: only the event bucket.
+        assert_eq!(buckets.iter().filter(|b| b.members.contains(&4)).count(), 1);
+        let economics = buckets.iter().find(|b| b.key == "Economics").unwrap();
+        assert!((economics.share - 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn only_buckets_over_their_limit_are_reported() {
+        let holdings = portfolio();
+        let found = breaches(&holdings, &limits(0.45, 0.0, 0.5));
+        let keys: Vec<(&str, &str)> = found
+            .iter()
+            .map(|b| (b.bucket.kind.as_str(), b.bucket.key.as_str()))
+            .collect();
+        // Category is off; the event (50%) and direction (60%) buckets are over.
+        assert_eq!(keys, vec![("direction", "Economics / Yes"), ("event", "fed-december")]);
+        assert!(breaches(&holdings, &limits(0.0, 0.0, 0.0)).is_empty());
+        assert!(breaches(&holdings, &limits(0.5, 0.7, 0.6)).is_empty());
+    }
+
+    #[test]
+    fn offsets_are_cheapest_first() {
+        let found = breaches(&portfolio(), &limits(0.0, 0.6, 0.0));
+        let offsets: Vec<f64> = found[0].offsets.iter().map(|o| o.price).collect();
+        // Economics holds Yes @ 70¢, No @ 30¢ and Yes @ 55¢: the other sides cost 30, 45, 70.
+        assert_eq!(offsets.len(), 3);
+        assert!((offsets[0] - 0.30).abs() < 1e-9);
+        assert!((offsets[1] - 0.45).abs() < 1e-9);
+        assert!((offsets[2] - 0.70).abs() < 1e-9);
+        let lines = report_lines(&found);
+        assert!(lines[0].contains("category Economics"));
+        assert!(lines[1].contains("fed-december No @ 30¢"));
+    }
+
+    #[test]
+    fn a_standing_breach_is_reported_once() {
+        let holdings = portfolio();
+        let config = limits(0.45, 0.0, 0.0);
+        let mut monitor = ConcentrationMonitor::default();
+        assert!(!monitor.check(&holdings, &config).is_empty());
+        assert!(monitor.check(&holdings, &config).is_empty());
+
+        let mut grown = holdings.clone();
+        grown[0].value_usd = 90.0;
+        assert!(!monitor.check(&grown, &config).is_empty());
+        // Back under the limit: the change is noticed but there is nothing to list.
+        assert!(monitor.check(&grown[2..], &config).is_empty());
+        assert!(!monitor.check(&holdings, &config).is_empty());
+    }
+}
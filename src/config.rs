@@ -1,12 +1,43 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::env;
 
+use crate::position_action::{self, PositionAction};
+use crate::skip_rules::RULE_NAMES;
+
+mod features;
+mod file;
+mod validation;
+pub use file::{
+    config_file_path, load_config_file, parse_config_str, read_config_file, DEFAULT_CONFIG_FILE,
+};
+pub use features::{
+    conflicts, AdaptiveWindowConfig, AggregationConfig, CatchUpConfig, ExitConfig, Features,
+    MarketShareConfig, PositionBuildConfig,
+};
+pub use validation::{validate_full, ConfigSource, Severity, ValidationIssue, ValidationReport};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CopyStrategy {
     Percentage,
     Fixed,
     Adaptive,
+    /// The trader's order as a share of their portfolio, applied to my balance.
+    PortfolioShare,
+}
+
+/// `ADAPTIVE_TAIL`: what the adaptive percent does for trades past twice
+/// `ADAPTIVE_THRESHOLD_USD`, where it reaches `ADAPTIVE_MIN_PERCENT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdaptiveTail {
+    /// Stays at `ADAPTIVE_MIN_PERCENT`, so copies keep growing with the trade.
+    #[default]
+    Plateau,
+    /// Keeps shrinking in proportion to the trade, so copies stay at what a trade of twice
+    /// the threshold gets.
+    Continue,
 }
 
 #[derive(Debug, Clone)]
@@ -27,10 +58,58 @@ pub struct CopyStrategyConfig {
     pub adaptive_min_percent: Option<f64>,
     pub adaptive_max_percent: Option<f64>,
     pub adaptive_threshold: Option<f64>,
+    pub adaptive_tail: AdaptiveTail,
     pub tiered_multipliers: Option<Vec<MultiplierTier>>,
     pub trade_multiplier: Option<f64>,
 }
 
+/// Per-copy figures `calculate_order_size` takes alongside the configured strategy, set by
+/// the executor for each trade; never read from the environment.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizingInputs {
+    /// While a returning trader is ramped back in (`INACTIVITY_DECAY_AFTER_DAYS`).
+    pub decay_multiplier: Option<f64>,
+    /// While the trader is on trial (`TRIAL_DAYS` / `TRIAL_COPIES`).
+    pub trial_multiplier: Option<f64>,
+    /// When a new market's lookups missed `MARKET_PREFETCH_DEADLINE_MS`.
+    pub prefetch_multiplier: Option<f64>,
+    /// Largest position in the market `MAX_MARKET_SHARE_PERCENT` allows, from its open
+    /// interest.
+    pub market_share_cap_usd: Option<f64>,
+    /// The trader's total position value, for `COPY_STRATEGY=PORTFOLIO_SHARE`.
+    pub trader_portfolio_usd: Option<f64>,
+    /// What `MAX_DAILY_VOLUME_USD` still allows today.
+    pub daily_volume_left_usd: Option<f64>,
+    /// Replaces `MAX_ORDER_SIZE_USD` for this copy (market override, trial or degraded caps).
+    pub max_order_size_usd: Option<f64>,
+    /// Places exactly this amount instead of sizing off the trader (consensus entries and
+    /// position build slices); multipliers are skipped, the caps still apply.
+    pub fixed_size_usd: Option<f64>,
+}
+
+impl SizingInputs {
+    pub fn fixed(size_usd: f64) -> Self {
+        Self {
+            fixed_size_usd: Some(size_usd),
+            ..Self::default()
+        }
+    }
+
+    /// Tightens this copy's order cap, starting from a market override if one is set.
+    pub fn cap_order_size(&mut self, config: &CopyStrategyConfig, cap: f64) {
+        let current = self.max_order_size_usd.unwrap_or(config.max_order_size_usd);
+        self.max_order_size_usd = Some(current.min(cap));
+    }
+}
+
+/// One stage of `calculate_order_size`, recorded whether or not it changed the amount.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingStep {
+    pub name: String,
+    pub input_usd: f64,
+    pub output_usd: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct OrderSizeCalculation {
     pub trader_order_size: f64,
@@ -40,7 +119,14 @@ pub struct OrderSizeCalculation {
     pub capped_by_max: bool,
     pub reduced_by_balance: bool,
     pub below_minimum: bool,
+    /// Reduced or zeroed to stay within `MAX_DAILY_VOLUME_USD`.
+    pub exceeded_daily_volume: bool,
     pub reasoning: String,
+    /// `base`, then `adaptive` (adaptive strategy only), `multiplier`, `inactivity_decay`
+    /// (while a trader is ramped back in), `trial` (while a trader is on trial),
+    /// `prefetch_degraded` (a new market's lookups ran late), `max_order_cap`, `position_cap`,
+    /// `market_share_cap`, `daily_volume_cap`, `balance_cap` and `min_order`.
+    pub steps: Vec<SizingStep>,
 }
 
 fn lerp(a: f64, b: f64, t: f64) -> f64 {
@@ -48,13 +134,18 @@ fn lerp(a: f64, b: f64, t: f64) -> f64 {
     a + (b - a) * t
 }
 
-fn calculate_adaptive_percent(config: &CopyStrategyConfig, trader_order_size: f64) -> f64 {
+/// `ADAPTIVE_MAX_PERCENT` for the smallest trades, falling to `COPY_SIZE` at the threshold
+/// and to `ADAPTIVE_MIN_PERCENT` at twice it; past that, see `AdaptiveTail`.
+pub fn calculate_adaptive_percent(config: &CopyStrategyConfig, trader_order_size: f64) -> f64 {
     let min_pct = config.adaptive_min_percent.unwrap_or(config.copy_size);
     let max_pct = config.adaptive_max_percent.unwrap_or(config.copy_size);
     let threshold = config.adaptive_threshold.unwrap_or(500.0);
 
     if trader_order_size >= threshold {
-        let factor = (trader_order_size / threshold - 1.0).min(1.0);
+        let factor = trader_order_size / threshold - 1.0;
+        if factor > 1.0 && config.adaptive_tail == AdaptiveTail::Continue {
+            return min_pct * 2.0 * threshold / trader_order_size;
+        }
         lerp(config.copy_size, min_pct, factor)
     } else {
         let factor = trader_order_size / threshold;
@@ -80,13 +171,53 @@ pub fn get_trade_multiplier(config: &CopyStrategyConfig, trader_order_size: f64)
     config.trade_multiplier.unwrap_or(1.0)
 }
 
+/// `PORTFOLIO_SHARE` base: `COPY_SIZE`% of the trader's order / portfolio share of my balance.
+/// A share over 100% (including a zero portfolio) is capped at 100%; an unknown portfolio
+/// sizes to nothing.
+fn portfolio_share_base(
+    config: &CopyStrategyConfig,
+    inputs: &SizingInputs,
+    trader_order_size: f64,
+    available_balance: f64,
+) -> (f64, String) {
+    let Some(portfolio) = inputs.trader_portfolio_usd else {
+        return (0.0, "Trader portfolio value unknown = $0.00".to_string());
+    };
+    let share = if portfolio > 0.0 {
+        trader_order_size / portfolio
+    } else {
+        f64::INFINITY
+    };
+    let capped = share.min(1.0);
+    let base = available_balance * capped * (config.copy_size / 100.0);
+    let mut r = format!(
+        "Trader's ${:.2} is {:.2}% of their ${:.2} portfolio",
+        trader_order_size,
+        capped * 100.0,
+        portfolio
+    );
+    if share > 1.0 {
+        r.push_str(" (capped at 100%)");
+    }
+    if (config.copy_size - 100.0).abs() > 1e-9 {
+        r.push_str(&format!(" × {}%", config.copy_size));
+    }
+    r.push_str(&format!(" of my ${:.2} = ${:.2}", available_balance, base));
+    (base, r)
+}
+
 pub fn calculate_order_size(
     config: &CopyStrategyConfig,
+    inputs: &SizingInputs,
     trader_order_size: f64,
     available_balance: f64,
     current_position_size: f64,
 ) -> OrderSizeCalculation {
-    let (base_amount, strategy, mut reasoning) = match config.strategy {
+    let strategy = match inputs.fixed_size_usd {
+        Some(_) => CopyStrategy::Fixed,
+        None => config.strategy,
+    };
+    let (base_amount, strategy, mut reasoning) = match strategy {
         CopyStrategy::Percentage => {
             let base = trader_order_size * (config.copy_size / 100.0);
             let r = format!(
@@ -96,8 +227,9 @@ pub fn calculate_order_size(
             (base, CopyStrategy::Percentage, r)
         }
         CopyStrategy::Fixed => {
-            let r = format!("Fixed amount: ${:.2}", config.copy_size);
-            (config.copy_size, CopyStrategy::Fixed, r)
+            let size = inputs.fixed_size_usd.unwrap_or(config.copy_size);
+            let r = format!("Fixed amount: ${:.2}", size);
+            (size, CopyStrategy::Fixed, r)
         }
         CopyStrategy::Adaptive => {
             let pct = calculate_adaptive_percent(config, trader_order_size);
@@ -108,10 +240,36 @@ pub fn calculate_order_size(
             );
             (base, CopyStrategy::Adaptive, r)
         }
+        CopyStrategy::PortfolioShare => {
+            let (base, r) =
+                portfolio_share_base(config, inputs, trader_order_size, available_balance);
+            (base, CopyStrategy::PortfolioShare, r)
+        }
     };
 
-    let multiplier = get_trade_multiplier(config, trader_order_size);
+    let mut steps = Vec::new();
+    let mut step = |name: &str, input_usd: f64, output_usd: f64| {
+        steps.push(SizingStep {
+            name: name.to_string(),
+            input_usd,
+            output_usd,
+        })
+    };
+    if strategy == CopyStrategy::Adaptive {
+        let unadjusted = trader_order_size * (config.copy_size / 100.0);
+        step("base", trader_order_size, unadjusted);
+        step("adaptive", unadjusted, base_amount);
+    } else {
+        step("base", trader_order_size, base_amount);
+    }
+
+    let multiplier = if inputs.fixed_size_usd.is_some() {
+        1.0
+    } else {
+        get_trade_multiplier(config, trader_order_size)
+    };
     let mut final_amount = base_amount * multiplier;
+    step("multiplier", base_amount, final_amount);
     if (multiplier - 1.0).abs() > 1e-9 {
         reasoning.push_str(&format!(
             " → {}x multiplier: ${:.2} → ${:.2}",
@@ -119,16 +277,54 @@ pub fn calculate_order_size(
         ));
     }
 
+    if let Some(decay) = inputs.decay_multiplier {
+        let before = final_amount;
+        final_amount *= decay;
+        step("inactivity_decay", before, final_amount);
+        reasoning.push_str(&format!(
+            " → {:.0}% while trader eases back in: ${:.2}",
+            decay * 100.0,
+            final_amount
+        ));
+    }
+
+    if let Some(trial) = inputs.trial_multiplier {
+        let before = final_amount;
+        final_amount *= trial;
+        step("trial", before, final_amount);
+        reasoning.push_str(&format!(
+            " → {:.0}% while trader is on trial: ${:.2}",
+            trial * 100.0,
+            final_amount
+        ));
+    }
+
+    if let Some(prefetch) = inputs.prefetch_multiplier {
+        let before = final_amount;
+        final_amount *= prefetch;
+        step("prefetch_degraded", before, final_amount);
+        reasoning.push_str(&format!(
+            " → {:.0}% with market lookups missing: ${:.2}",
+            prefetch * 100.0,
+            final_amount
+        ));
+    }
+
     let mut capped_by_max = false;
     let mut reduced_by_balance = false;
     let mut below_minimum = false;
 
-    if final_amount > config.max_order_size_usd {
-        final_amount = config.max_order_size_usd;
+    let before = final_amount;
+    let max_order_size_usd = inputs.max_order_size_usd.unwrap_or(config.max_order_size_usd);
+    if final_amount > max_order_size_usd {
+        final_amount = max_order_size_usd;
         capped_by_max = true;
-        reasoning.push_str(&format!(" → Capped at max ${}", config.max_order_size_usd));
+        reasoning.push_str(&format!(" → Capped at max ${}", max_order_size_usd));
     }
 
+    step("max_order_cap", before, final_amount);
+
+    let before = final_amount;
     if let Some(max_pos) = config.max_position_size_usd {
         let new_total = current_position_size + final_amount;
         if new_total > max_pos {
@@ -143,6 +339,46 @@ pub fn calculate_order_size(
         }
     }
 
+    step("position_cap", before, final_amount);
+
+    let before = final_amount;
+    if let Some(max_pos) = inputs.market_share_cap_usd {
+        let allowed = (max_pos - current_position_size).max(0.0);
+        if final_amount > allowed {
+            if allowed < config.min_order_size_usd {
+                final_amount = 0.0;
+                reasoning.push_str(" → Market share limit reached");
+            } else {
+                final_amount = allowed;
+                reasoning.push_str(&format!(
+                    " → Reduced to fit market share limit (${:.2} position)",
+                    max_pos
+                ));
+            }
+        }
+    }
+    step("market_share_cap", before, final_amount);
+
+    let before = final_amount;
+    let mut exceeded_daily_volume = false;
+    if let Some(left) = inputs.daily_volume_left_usd {
+        if final_amount > left {
+            exceeded_daily_volume = true;
+            if left < config.min_order_size_usd {
+                final_amount = 0.0;
+                reasoning.push_str(" → Daily volume limit reached");
+            } else {
+                final_amount = left;
+                reasoning.push_str(&format!(
+                    " → Reduced to fit daily volume limit (${:.2} left today)",
+                    left
+                ));
+            }
+        }
+    }
+    step("daily_volume_cap", before, final_amount);
+
+    let before = final_amount;
     let max_affordable = available_balance * 0.99;
     if final_amount > max_affordable {
         final_amount = max_affordable;
@@ -153,11 +389,15 @@ pub fn calculate_order_size(
         ));
     }
 
+    step("balance_cap", before, final_amount);
+
+    let before = final_amount;
     if final_amount < config.min_order_size_usd {
         below_minimum = true;
         reasoning.push_str(&format!(" → Below minimum ${}", config.min_order_size_usd));
         final_amount = config.min_order_size_usd;
     }
+    step("min_order", before, final_amount);
 
     OrderSizeCalculation {
         trader_order_size,
@@ -167,7 +407,9 @@ pub fn calculate_order_size(
         capped_by_max,
         reduced_by_balance,
         below_minimum,
+        exceeded_daily_volume,
         reasoning,
+        steps,
     }
 }
 
@@ -192,11 +434,8 @@ pub fn parse_tiered_multipliers(tiers_str: &str) -> Result<Vec<MultiplierTier>>
         if multiplier < 0.0 {
             anyhow::bail!("Invalid multiplier in tier: {}", part);
         }
-        if range.ends_with('+') {
-            let min: f64 = range[..range.len() - 1]
-                .trim()
-                .parse()
-                .context("Invalid min in tier")?;
+        if let Some(min_s) = range.strip_suffix('+') {
+            let min: f64 = min_s.trim().parse().context("Invalid min in tier")?;
             if min < 0.0 {
                 anyhow::bail!("Invalid minimum in tier: {}", part);
             }
@@ -274,14 +513,26 @@ pub fn parse_user_addresses(input: &str) -> Result<Vec<String>> {
     Ok(addresses)
 }
 
-fn parse_copy_strategy_from_env() -> Result<CopyStrategyConfig> {
-    let has_legacy = env::var("COPY_PERCENTAGE").is_ok() && env::var("COPY_STRATEGY").is_err();
+/// One tracked wallet's trades on RTDS; the monitor still drops any other wallet's.
+pub const DEFAULT_RTDS_SUBSCRIPTION: &str =
+    r#"{"topic":"activity","type":"trades","filters":"{\"proxyWallet\":\"{address}\"}"}"#;
+
+/// Reads one setting by name; `EnvConfig::parse_from` reads everything through one of these.
+pub type VarLookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// `env::var` over a lookup, so every setting reads the same whatever the source.
+fn var(vars: VarLookup, key: &str) -> std::result::Result<String, env::VarError> {
+    vars(key).ok_or(env::VarError::NotPresent)
+}
+
+fn parse_copy_strategy_from(vars: VarLookup) -> Result<CopyStrategyConfig> {
+    let has_legacy = var(vars, "COPY_PERCENTAGE").is_ok() && var(vars, "COPY_STRATEGY").is_err();
     if has_legacy {
-        let copy_pct: f64 = env::var("COPY_PERCENTAGE")
+        let copy_pct: f64 = var(vars, "COPY_PERCENTAGE")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(10.0);
-        let trade_mult: f64 = env::var("TRADE_MULTIPLIER")
+        let trade_mult: f64 = var(vars, "TRADE_MULTIPLIER")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(1.0);
@@ -289,23 +540,24 @@ fn parse_copy_strategy_from_env() -> Result<CopyStrategyConfig> {
         let mut config = CopyStrategyConfig {
             strategy: CopyStrategy::Percentage,
             copy_size: effective,
-            max_order_size_usd: env::var("MAX_ORDER_SIZE_USD")
+            max_order_size_usd: var(vars, "MAX_ORDER_SIZE_USD")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(100.0),
-            min_order_size_usd: env::var("MIN_ORDER_SIZE_USD")
+            min_order_size_usd: var(vars, "MIN_ORDER_SIZE_USD")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(1.0),
-            max_position_size_usd: env::var("MAX_POSITION_SIZE_USD")
+            max_position_size_usd: var(vars, "MAX_POSITION_SIZE_USD")
                 .ok()
                 .and_then(|v| v.parse().ok()),
-            max_daily_volume_usd: env::var("MAX_DAILY_VOLUME_USD")
+            max_daily_volume_usd: var(vars, "MAX_DAILY_VOLUME_USD")
                 .ok()
                 .and_then(|v| v.parse().ok()),
             adaptive_min_percent: None,
             adaptive_max_percent: None,
             adaptive_threshold: None,
+            adaptive_tail: AdaptiveTail::Plateau,
             tiered_multipliers: None,
             trade_multiplier: if (trade_mult - 1.0).abs() > 1e-9 {
                 Some(trade_mult)
@@ -313,46 +565,52 @@ fn parse_copy_strategy_from_env() -> Result<CopyStrategyConfig> {
                 None
             },
         };
-        if let Ok(tiers_str) = env::var("TIERED_MULTIPLIERS") {
+        if let Ok(tiers_str) = var(vars, "TIERED_MULTIPLIERS") {
             config.tiered_multipliers = Some(parse_tiered_multipliers(&tiers_str)?);
         }
         return Ok(config);
     }
 
-    let strategy_str = env::var("COPY_STRATEGY")
+    let strategy_str = var(vars, "COPY_STRATEGY")
         .unwrap_or_else(|_| "PERCENTAGE".into())
         .to_uppercase();
     let strategy = match strategy_str.as_str() {
         "FIXED" => CopyStrategy::Fixed,
         "ADAPTIVE" => CopyStrategy::Adaptive,
+        "PORTFOLIO_SHARE" => CopyStrategy::PortfolioShare,
         _ => CopyStrategy::Percentage,
     };
 
     let mut config = CopyStrategyConfig {
         strategy,
-        copy_size: env::var("COPY_SIZE")
+        copy_size: var(vars, "COPY_SIZE")
             .ok()
             .and_then(|v| v.parse().ok())
-            .unwrap_or(10.0),
-        max_order_size_usd: env::var("MAX_ORDER_SIZE_USD")
+            .unwrap_or(if strategy == CopyStrategy::PortfolioShare {
+                100.0
+            } else {
+                10.0
+            }),
+        max_order_size_usd: var(vars, "MAX_ORDER_SIZE_USD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(100.0),
-        min_order_size_usd: env::var("MIN_ORDER_SIZE_USD")
+        min_order_size_usd: var(vars, "MIN_ORDER_SIZE_USD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(1.0),
-        max_position_size_usd: env::var("MAX_POSITION_SIZE_USD")
+        max_position_size_usd: var(vars, "MAX_POSITION_SIZE_USD")
             .ok()
             .and_then(|v| v.parse().ok()),
-        max_daily_volume_usd: env::var("MAX_DAILY_VOLUME_USD")
+        max_daily_volume_usd: var(vars, "MAX_DAILY_VOLUME_USD")
             .ok()
             .and_then(|v| v.parse().ok()),
         adaptive_min_percent: None,
         adaptive_max_percent: None,
         adaptive_threshold: None,
+        adaptive_tail: AdaptiveTail::Plateau,
         tiered_multipliers: None,
-        trade_multiplier: env::var("TRADE_MULTIPLIER")
+        trade_multiplier: var(vars, "TRADE_MULTIPLIER")
             .ok()
             .and_then(|v| v.parse().ok())
             .and_then(|m: f64| {
@@ -364,161 +622,1659 @@ fn parse_copy_strategy_from_env() -> Result<CopyStrategyConfig> {
             }),
     };
 
-    if let Ok(tiers_str) = env::var("TIERED_MULTIPLIERS") {
+    if let Ok(tiers_str) = var(vars, "TIERED_MULTIPLIERS") {
         config.tiered_multipliers = Some(parse_tiered_multipliers(&tiers_str)?);
     }
     if strategy == CopyStrategy::Adaptive {
         config.adaptive_min_percent = Some(
-            env::var("ADAPTIVE_MIN_PERCENT")
+            var(vars, "ADAPTIVE_MIN_PERCENT")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(config.copy_size),
         );
         config.adaptive_max_percent = Some(
-            env::var("ADAPTIVE_MAX_PERCENT")
+            var(vars, "ADAPTIVE_MAX_PERCENT")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(config.copy_size),
         );
         config.adaptive_threshold = Some(
-            env::var("ADAPTIVE_THRESHOLD_USD")
+            var(vars, "ADAPTIVE_THRESHOLD_USD")
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(500.0),
         );
+        config.adaptive_tail = match var(vars, "ADAPTIVE_TAIL")
+            .unwrap_or_default()
+            .trim()
+            .to_uppercase()
+            .as_str()
+        {
+            "" | "PLATEAU" => AdaptiveTail::Plateau,
+            "CONTINUE" => AdaptiveTail::Continue,
+            other => anyhow::bail!("Invalid ADAPTIVE_TAIL: {} (use PLATEAU or CONTINUE)", other),
+        };
     }
     Ok(config)
 }
 
+/// How the individual copy sizes of agreeing traders combine into one consensus order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsensusAggregate {
+    Average,
+    Max,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsensusConfig {
+    pub threshold: usize,
+    pub window_hours: f64,
+    pub aggregate: ConsensusAggregate,
+    pub exit_requires_consensus: bool,
+}
+
+fn parse_consensus_from(vars: VarLookup) -> Result<Option<ConsensusConfig>> {
+    let threshold: usize = match var(vars, "CONSENSUS_THRESHOLD") {
+        Ok(v) if !v.trim().is_empty() => v
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CONSENSUS_THRESHOLD: {}", v))?,
+        _ => return Ok(None),
+    };
+    if threshold < 2 {
+        return Ok(None);
+    }
+    let aggregate = match var(vars, "CONSENSUS_SIZE_AGGREGATE")
+        .unwrap_or_else(|_| "AVERAGE".to_string())
+        .trim()
+        .to_uppercase()
+        .as_str()
+    {
+        "AVERAGE" | "AVG" => ConsensusAggregate::Average,
+        "MAX" => ConsensusAggregate::Max,
+        other => anyhow::bail!("Invalid CONSENSUS_SIZE_AGGREGATE: {} (use AVERAGE or MAX)", other),
+    };
+    Ok(Some(ConsensusConfig {
+        threshold,
+        window_hours: var(vars, "CONSENSUS_WINDOW_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(24.0),
+        aggregate,
+        exit_requires_consensus: var(vars, "CONSENSUS_EXIT_REQUIRES_CONSENSUS")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false),
+    }))
+}
+
+/// What to do when the side of the book we'd trade against is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyBookPolicy {
+    Skip,
+    /// Rest a GTD limit order at the trader's price for `EMPTY_BOOK_ORDER_TTL_SECS`.
+    Limit,
+}
+
+/// What the slippage guard does when the book has too little within `MAX_SLIPPAGE_PERCENT`
+/// of the trader's price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlippageAction {
+    Skip,
+    /// Place what the book has within the limit.
+    Shrink,
+}
+
+/// Which USD notional sizes a copy when the payload's `usdcSize` and size × price disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsdcSizePreference {
+    /// The smaller of the two.
+    Conservative,
+    /// `usdcSize` as sent.
+    Reported,
+    /// size × price.
+    Derived,
+}
+
+/// `SIZING_BALANCE`: which USDC figure copies are sized against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizingBalance {
+    /// Wallet USDC less what open orders have committed and `BALANCE_RESERVE_USD`.
+    Available,
+    /// Wallet USDC less the reserve, ignoring open orders.
+    Wallet,
+}
+
+/// `MONITOR_MODE`: how the monitor learns about the traders' trades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorMode {
+    /// RTDS, polling the data API while it is unreachable.
+    Auto,
+    /// RTDS only; the monitor stops once its reconnect budget is spent.
+    Websocket,
+    /// The data API every `FETCH_INTERVAL` seconds, never RTDS.
+    Polling,
+}
+
+/// What to do when one leg of a neg-risk rebalance fails its guards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebalancePolicy {
+    /// Skip every leg so the book isn't left half-rotated.
+    AllOrNothing,
+    /// Execute the legs that pass and warn about the rest.
+    AllowPartial,
+}
+
+#[derive(Debug, Clone)]
+pub struct RebalanceConfig {
+    /// How long signals in the same neg-risk event are held to form one plan.
+    pub window_secs: u64,
+    pub policy: RebalancePolicy,
+}
+
+fn parse_rebalance_from(vars: VarLookup) -> Result<Option<RebalanceConfig>> {
+    let window_secs: u64 = var(vars, "REBALANCE_WINDOW_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if window_secs == 0 {
+        return Ok(None);
+    }
+    let policy = match var(vars, "REBALANCE_POLICY")
+        .unwrap_or_else(|_| "ALL_OR_NOTHING".to_string())
+        .trim()
+        .to_uppercase()
+        .as_str()
+    {
+        "ALL_OR_NOTHING" => RebalancePolicy::AllOrNothing,
+        "ALLOW_PARTIAL" | "PARTIAL" => RebalancePolicy::AllowPartial,
+        other => anyhow::bail!(
+            "Invalid REBALANCE_POLICY: {} (use ALL_OR_NOTHING or ALLOW_PARTIAL)",
+            other
+        ),
+    };
+    Ok(Some(RebalanceConfig {
+        window_secs,
+        policy,
+    }))
+}
+
+/// Eases back into traders returning from a long break: their first BUY copies back are
+/// scaled down by `ramp`, then copied at full size.
+#[derive(Debug, Clone)]
+pub struct InactivityDecayConfig {
+    pub after_days: u64,
+    /// Multiplier for each BUY copy back, in order (e.g. 0.25, 0.5).
+    pub ramp: Vec<f64>,
+}
+
+/// `INACTIVITY_DECAY_RAMP=25,50`: percent of the normal size for the first copies back.
+pub(crate) fn parse_decay_ramp(raw: &str) -> Result<Vec<f64>> {
+    let mut ramp = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let pct: f64 = part
+            .parse()
+            .with_context(|| format!("Invalid INACTIVITY_DECAY_RAMP entry: {}", part))?;
+        if pct.is_nan() || pct <= 0.0 || pct > 100.0 {
+            anyhow::bail!(
+                "INACTIVITY_DECAY_RAMP entries are percentages in (0, 100]: {}",
+                part
+            );
+        }
+        ramp.push(pct / 100.0);
+    }
+    // Trailing full-size steps change nothing.
+    while ramp.last().is_some_and(|m| *m >= 1.0) {
+        ramp.pop();
+    }
+    Ok(ramp)
+}
+
+fn parse_inactivity_decay_from(vars: VarLookup) -> Result<Option<InactivityDecayConfig>> {
+    let after_days: u64 = var(vars, "INACTIVITY_DECAY_AFTER_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if after_days == 0 {
+        return Ok(None);
+    }
+    let ramp = parse_decay_ramp(
+        &var(vars, "INACTIVITY_DECAY_RAMP").unwrap_or_else(|_| "25,50".to_string()),
+    )?;
+    if ramp.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(InactivityDecayConfig { after_days, ramp }))
+}
+
+/// Training wheels for newly added traders: their BUY copies are scaled down and capped until
+/// `days` have passed or `copies` were placed, whichever comes first (0 = that bound is off).
+#[derive(Debug, Clone)]
+pub struct TrialConfig {
+    pub days: u64,
+    pub copies: u32,
+    pub size_multiplier: f64,
+    pub max_order_size_usd: f64,
+}
+
+fn parse_trial_from(vars: VarLookup) -> Option<TrialConfig> {
+    let days: u64 = var(vars, "TRIAL_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let copies: u32 = var(vars, "TRIAL_COPIES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if days == 0 && copies == 0 {
+        return None;
+    }
+    let size_percent: f64 = var(vars, "TRIAL_SIZE_PERCENT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|p: &f64| *p > 0.0)
+        .unwrap_or(50.0);
+    let max_order_size_usd: f64 = var(vars, "TRIAL_MAX_ORDER_SIZE_USD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5.0);
+    Some(TrialConfig {
+        days,
+        copies,
+        size_multiplier: size_percent.min(100.0) / 100.0,
+        max_order_size_usd,
+    })
+}
+
+/// Advisory concentration report; shares are fractions of the portfolio (0 = that bucket
+/// kind is off).
+#[derive(Debug, Clone)]
+pub struct ConcentrationConfig {
+    pub interval_secs: u64,
+    pub max_event_share: f64,
+    pub max_category_share: f64,
+    pub max_direction_share: f64,
+}
+
+fn parse_concentration_from(vars: VarLookup) -> Option<ConcentrationConfig> {
+    let share = |key: &str| {
+        var(vars, key)
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|p| *p > 0.0)
+            .map_or(0.0, |p| p.min(100.0) / 100.0)
+    };
+    let config = ConcentrationConfig {
+        interval_secs: var(vars, "CONCENTRATION_CHECK_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600),
+        max_event_share: share("CONCENTRATION_MAX_EVENT_PERCENT"),
+        max_category_share: share("CONCENTRATION_MAX_CATEGORY_PERCENT"),
+        max_direction_share: share("CONCENTRATION_MAX_DIRECTION_PERCENT"),
+    };
+    let enabled = config.max_event_share > 0.0
+        || config.max_category_share > 0.0
+        || config.max_direction_share > 0.0;
+    (enabled && config.interval_secs > 0).then_some(config)
+}
+
+/// Alert channels (`ALERT_TELEGRAM_*`, `ALERT_DISCORD_WEBHOOK_URL`). Telegram needs both the
+/// bot token and the chat id. The names without `ALERT_` work too.
+#[derive(Clone)]
+pub struct AlertConfig {
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    /// `NOTIFY_MIN_ORDER_USD`: placed copies smaller than this are not announced (default 0).
+    pub notify_min_order_usd: f64,
+}
+
+fn parse_alerts_from(vars: VarLookup) -> Option<AlertConfig> {
+    let non_empty = |key: &str| {
+        var(vars, key)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let alerts = AlertConfig {
+        telegram_bot_token: non_empty("ALERT_TELEGRAM_BOT_TOKEN")
+            .or_else(|| non_empty("TELEGRAM_BOT_TOKEN")),
+        telegram_chat_id: non_empty("ALERT_TELEGRAM_CHAT_ID")
+            .or_else(|| non_empty("TELEGRAM_CHAT_ID")),
+        discord_webhook_url: non_empty("ALERT_DISCORD_WEBHOOK_URL")
+            .or_else(|| non_empty("DISCORD_WEBHOOK_URL")),
+        notify_min_order_usd: var(vars, "NOTIFY_MIN_ORDER_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0),
+    };
+    let telegram = alerts.telegram_bot_token.is_some() && alerts.telegram_chat_id.is_some();
+    (telegram || alerts.discord_webhook_url.is_some()).then_some(alerts)
+}
+
+/// Fault injection for resilience testing. Rates are probabilities per request / per message.
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    pub seed: u64,
+    pub max_latency_ms: u64,
+    pub request_failure_rate: f64,
+    pub disconnect_rate: f64,
+    pub malformed_rate: f64,
+}
+
+fn parse_chaos_from(vars: VarLookup) -> Option<ChaosConfig> {
+    let enabled = var(vars, "CHAOS_MODE")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+    let rate = |name: &str, default: f64| -> f64 {
+        var(vars, name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(|r: f64| r.clamp(0.0, 1.0))
+            .unwrap_or(default)
+    };
+    Some(ChaosConfig {
+        seed: var(vars, "CHAOS_SEED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| chrono::Utc::now().timestamp_millis() as u64),
+        max_latency_ms: var(vars, "CHAOS_MAX_LATENCY_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500),
+        request_failure_rate: rate("CHAOS_REQUEST_FAILURE_RATE", 0.05),
+        disconnect_rate: rate("CHAOS_DISCONNECT_RATE", 0.01),
+        malformed_rate: rate("CHAOS_MALFORMED_RATE", 0.02),
+    })
+}
+
+/// Addresses copied as one logical trader, e.g. a whale's Safe proxy and EOA. Exposure,
+/// consensus and journal attribution use `id` instead of the member address.
+#[derive(Debug, Clone)]
+pub struct TraderGroup {
+    pub id: String,
+    pub members: Vec<String>,
+}
+
+/// `TRADER_GROUPS=whale1:0xaaa,0xbbb;whale2:0xccc,0xddd`. An address may belong to one group.
+pub(crate) fn parse_trader_groups(raw: &str) -> Result<Vec<TraderGroup>> {
+    let mut groups: Vec<TraderGroup> = Vec::new();
+    for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((id, members)) = entry.split_once(':') else {
+            anyhow::bail!("Invalid TRADER_GROUPS entry: {} (use id:0xaaa,0xbbb)", entry);
+        };
+        let id = id.trim().to_lowercase();
+        if id.is_empty() || is_valid_ethereum_address(&id) {
+            anyhow::bail!("Invalid TRADER_GROUPS id: {:?} (use a name, not an address)", id);
+        }
+        if groups.iter().any(|g| g.id == id) {
+            anyhow::bail!("Duplicate TRADER_GROUPS id: {}", id);
+        }
+        let members = parse_user_addresses(members)
+            .with_context(|| format!("Invalid address in TRADER_GROUPS group {}", id))?;
+        if members.is_empty() {
+            anyhow::bail!("TRADER_GROUPS group {} has no addresses", id);
+        }
+        for member in &members {
+            if let Some(other) = groups.iter().find(|g| g.members.contains(member)) {
+                anyhow::bail!(
+                    "Address {} is in TRADER_GROUPS groups {} and {}",
+                    member,
+                    other.id,
+                    id
+                );
+            }
+        }
+        groups.push(TraderGroup { id, members });
+    }
+    Ok(groups)
+}
+
 #[derive(Clone)]
 pub struct EnvConfig {
     pub user_addresses: Vec<String>,
+    /// `PROXY_WALLET`; when unset, `from_env` derives it from the signer.
     pub proxy_wallet: String,
     pub private_key: String,
     pub clob_http_url: String,
     pub clob_ws_url: String,
+    /// `DATA_API_URL`: trader and wallet positions and activity.
+    pub data_api_url: String,
+    /// `GAMMA_API_URL`: market records.
+    pub gamma_api_url: String,
+    /// `RTDS_URL`: the live trade stream.
+    pub rtds_url: String,
+    /// `RTDS_SUBSCRIPTION_TEMPLATE`: the RTDS subscription sent for each tracked address, as
+    /// JSON with `{address}` where the address goes.
+    pub rtds_subscription_template: String,
+    /// `FETCH_INTERVAL`: seconds between activity polls while the monitor polls.
     pub fetch_interval_secs: u64,
     pub too_old_timestamp_hours: i64,
     pub retry_limit: u32,
     pub copy_strategy_config: CopyStrategyConfig,
     pub request_timeout_ms: u64,
     pub network_retry_limit: u32,
-    pub trade_aggregation_enabled: bool,
-    pub trade_aggregation_window_seconds: u64,
+    /// `REQUEST_RATE_LIMIT_PER_SEC`: data API, Gamma and CLOB GETs per second, per host;
+    /// 0 turns the limit off.
+    pub request_rate_limit_per_sec: f64,
+    /// Port for the localhost `/status` endpoint; 0 turns it off.
+    pub status_port: u16,
+    /// `STATUS_API_ADDR`: where the remote status API listens (e.g. `0.0.0.0:8788`); off when
+    /// unset.
+    pub status_api_addr: Option<std::net::SocketAddr>,
+    /// `STATUS_API_TOKEN`: bearer token every status API request must carry.
+    pub status_api_token: Option<String>,
+    /// Minutes between digest lines; 0 turns the digest off.
+    pub digest_interval_mins: u64,
+    /// `RPC_URL`: one Polygon JSON-RPC endpoint, or several comma-separated in order of
+    /// preference for failover (see `RpcClient`).
     pub rpc_url: String,
     pub usdc_contract_address: String,
+    pub max_open_positions: Option<usize>,
+    pub state_dir: String,
+    /// `STATE_FILE`: dedupe history of processed trades, by default inside `state_dir`.
+    pub processed_trades_path: String,
+    pub processed_trades_retention_hours: u64,
+    pub position_reconcile_interval_secs: u64,
+    pub trade_log_path: String,
+    /// Where rejected RTDS payloads are sampled to.
+    pub malformed_log_path: String,
+    pub journal_market_context: bool,
+    /// `JOURNAL_CSV_PATH`: CSV copy of every journal row, for spreadsheets.
+    pub journal_csv_path: Option<String>,
+    /// `JOURNAL_ROTATE_DAILY`: move the journal (and CSV) aside to a dated file at the first
+    /// row of each UTC day.
+    pub journal_rotate_daily: bool,
+    /// HTTP endpoint that receives journal rows in batches, in addition to the local file.
+    pub journal_remote_url: Option<String>,
+    pub journal_remote_token: Option<String>,
+    /// Receives every lifecycle event (`BotEvent`) as a JSON POST.
+    pub event_webhook_url: Option<String>,
+    /// `EVENT_WEBHOOK_SECRET`: HMAC-SHA256 key for the `X-Bot-Signature` header.
+    pub event_webhook_secret: Option<String>,
+    /// Tags remote journal rows so several bots can share one endpoint.
+    pub journal_instance_id: String,
+    /// Rows the journal writer may buffer before shadow rows are dropped and live rows wait.
+    pub journal_buffer_rows: usize,
+    pub dust_threshold_usd: f64,
+    pub dust_sweep_interval_secs: u64,
+    /// `AUTO_REDEEM`: redeem winning positions in resolved markets (on unless set to false).
+    pub auto_redeem: bool,
+    pub redeem_interval_secs: u64,
+    pub build_max_age_days: u64,
+    /// Hours before an advisory that still holds is raised again.
+    pub advisory_repeat_hours: u64,
+    pub balance_max_staleness_secs: u64,
+    /// `BALANCE_CACHE_TTL_SECS`: how long a USDC balance read is reused (0 = read every time).
+    pub balance_cache_ttl_secs: u64,
+    pub degraded_balance_fraction: f64,
+    pub degraded_max_order_size_usd: f64,
+    pub skip_market_maker_fills: bool,
+    pub force_directional_traders: Vec<String>,
+    pub trader_classify_interval_secs: u64,
+    /// How often every tracked wallet's portfolio value is refetched (0 = only from signals).
+    pub trader_portfolio_refresh_secs: u64,
+    /// Cached portfolio values older than this are treated as stale.
+    pub trader_portfolio_max_age_secs: u64,
+    /// Hour (UTC) at which the `MAX_DAILY_VOLUME_USD` count starts over.
+    pub daily_volume_reset_hour_utc: u32,
+    /// `DRY_RUN`: evaluate and journal every signal, but never place an order.
+    pub dry_run: bool,
+    /// Skip BUY copies this close to a market's scheduled end (0 = off).
+    pub pause_before_resolution_minutes: u64,
+    /// `SKIP_MARKETS_ENDING_WITHIN_HOURS`: the same skip, in hours, for markets too close to
+    /// their end to be worth entering (0 = off). See `resolution_window_minutes`.
+    pub skip_markets_ending_within_hours: u64,
+    pub empty_book_policy: EmptyBookPolicy,
+    pub empty_book_order_ttl_secs: u64,
+    /// `MAX_SLIPPAGE_PERCENT`: how far the best ask may sit above (bid below, for a SELL) the
+    /// trader's fill before a copy is skipped or shrunk (0 = off).
+    pub max_slippage_percent: f64,
+    /// `SLIPPAGE_ACTION`: SKIP or SHRINK.
+    pub slippage_action: SlippageAction,
+    /// How often resting orders are checked for fills and for leaving the book.
+    pub resting_order_poll_secs: u64,
+    /// Trim or cancel a resting order once the trader cancels the order at its price.
+    pub follow_trader_cancels: bool,
+    /// How often the proxy wallet's trades are checked for activity the bot didn't place (0 = off).
+    pub wallet_watchdog_interval_secs: u64,
+    pub wallet_watchdog_grace_secs: u64,
+    pub pause_on_foreign_activity: bool,
+    /// Refuse to start when a tracked address has no Polymarket history.
+    pub require_trader_history: bool,
+    /// `SKIP_RULES`: which skip rules run, in order (default: all, see `skip_rules::RULE_NAMES`).
+    pub skip_rules: Vec<String>,
+    /// `MARKET_BLACKLIST`: slugs, event slugs or condition ids never copied (lowercased).
+    pub market_blacklist: Vec<String>,
+    /// `MARKET_WHITELIST`: when set, the only slugs, event slugs or condition ids copied.
+    pub market_whitelist: Vec<String>,
+    /// `MIN_COPY_PRICE` / `MAX_COPY_PRICE`: BUYs are copied only inside this price band
+    /// (0 and 1 = off).
+    pub min_copy_price: f64,
+    pub max_copy_price: f64,
+    /// `PRICE_BAND_INCLUDES_SELLS`: hold SELLs to the band too.
+    pub price_band_includes_sells: bool,
+    /// `COPY_POSITION_ACTIONS`: which of the trader's position actions are copied.
+    pub copy_position_actions: Vec<PositionAction>,
+    /// `COPY_POSITION_ACTIONS_BY_TRADER`: overrides keyed by address or group id.
+    pub copy_position_actions_by_trader: Vec<(String, Vec<PositionAction>)>,
+    pub trader_groups: Vec<TraderGroup>,
+    /// `SHADOW_CONFIG_FILE`: env-style overrides evaluated alongside the live config, never traded.
+    pub shadow_config_file: Option<String>,
+    /// `CAPTURE_RTDS_TO`: append every raw RTDS frame with its receive time to this file.
+    pub capture_rtds_to: Option<String>,
+    /// `REPLAY_RTDS_FROM`: read frames from a capture instead of connecting to RTDS.
+    pub replay_rtds_from: Option<String>,
+    /// Replay speed factor (2 = twice as fast as captured).
+    pub replay_rtds_speed: f64,
+    /// `MAX_BACKFILL_MINUTES`: how far back trades missed during an RTDS disconnect are
+    /// fetched after reconnecting (0 = no backfill).
+    pub max_backfill_minutes: u64,
+    pub monitor_mode: MonitorMode,
+    /// `MONITOR_FALLBACK_ATTEMPTS`: failed RTDS connections in a row after which the `Auto`
+    /// monitor polls instead.
+    pub monitor_fallback_attempts: u32,
+    /// `RTDS_MAX_RECONNECT_ATTEMPTS`: failed RTDS connections in a row after which
+    /// `MONITOR_MODE=websocket` stops monitoring (0 = never).
+    pub rtds_max_reconnect_attempts: u32,
+    /// `RTDS_MAX_BACKOFF_SECS`: the longest wait between RTDS reconnect attempts.
+    pub rtds_max_backoff_secs: u64,
+    /// `RTDS_PING_INTERVAL_SECS`: how often a Ping is sent on the RTDS connection (0 = never).
+    pub rtds_ping_interval_secs: u64,
+    /// `RTDS_STALE_SECS`: an RTDS connection that has received nothing, not even a Pong, for
+    /// this long is dropped and reconnected (0 = never).
+    pub rtds_stale_secs: u64,
+    /// `INTERACTIVE`: read close/trim/pause commands from the terminal (when stdin is a TTY).
+    pub interactive: bool,
+    /// Terminal sells worth more than this ask for confirmation first.
+    pub interactive_confirm_usd: f64,
+    /// `AUTO_APPROVE_CTF`: submit missing outcome token approvals at startup (EOA wallets).
+    pub auto_approve_ctf: bool,
+    /// A USDC allowance below this toward any exchange contract counts as not approved.
+    pub min_usdc_allowance_usd: f64,
+    /// `ALLOW_UNAPPROVED`: start even when the USDC allowance is missing and can't be set here.
+    pub allow_unapproved: bool,
+    pub usdc_size_preference: UsdcSizePreference,
+    pub sizing_balance: SizingBalance,
+    /// USDC never sized against, held back from every copy.
+    pub balance_reserve_usd: f64,
+    /// Drift between `usdcSize` and size × price (a fraction) above which a payload is logged.
+    pub usdc_size_tolerance: f64,
+    /// How often cached order templates are re-read from the CLOB (0 = no templates).
+    pub order_template_refresh_secs: u64,
+    /// Deadline for a new market's concurrent lookups before the copy goes ahead without
+    /// them (0 = look up serially as needed).
+    pub market_prefetch_deadline_ms: u64,
+    /// Size factor for BUY copies whose market lookups missed the deadline.
+    pub prefetch_degraded_multiplier: f64,
+    /// Warn once a market's trader adds ignored by the position cap reach this (0 = off).
+    pub overflow_notify_usd: f64,
+    /// Share of a market's ignored adds bought once after the position cap is raised (0 = off).
+    pub overflow_catchup_fraction: f64,
+    /// Optional behaviors (consensus, trial, alerts, ...); see `Features`.
+    pub features: Features,
+}
+
+pub(crate) fn parse_skip_rules(raw: &str) -> Result<Vec<String>> {
+    if raw.trim().is_empty() {
+        return Ok(RULE_NAMES.iter().map(|r| r.to_string()).collect());
+    }
+    let mut rules: Vec<String> = Vec::new();
+    for name in raw.split(',').map(|r| r.trim().to_lowercase()).filter(|r| !r.is_empty()) {
+        // Renamed when the foreign-activity pause moved into the trading state machine.
+        let name = if name == "foreign_activity_pause" {
+            "trading_state".to_string()
+        } else {
+            name
+        };
+        if !RULE_NAMES.contains(&name.as_str()) {
+            anyhow::bail!(
+                "Invalid SKIP_RULES entry: {} (known: {})",
+                name,
+                RULE_NAMES.join(", ")
+            );
+        }
+        if !rules.contains(&name) {
+            rules.push(name);
+        }
+    }
+    Ok(rules)
+}
+
+/// `COPY_POSITION_ACTIONS_BY_TRADER=whale1:open,close;0xaaa:open`, keyed by group id or
+/// address (lowercased).
+pub(crate) fn parse_position_actions_by_trader(
+    raw: &str,
+) -> Result<Vec<(String, Vec<PositionAction>)>> {
+    let mut overrides: Vec<(String, Vec<PositionAction>)> = Vec::new();
+    for entry in raw.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((trader, actions)) = entry.split_once(':') else {
+            anyhow::bail!(
+                "Invalid COPY_POSITION_ACTIONS_BY_TRADER entry: {} (use trader:open,close)",
+                entry
+            );
+        };
+        let trader = trader.trim().to_lowercase();
+        if trader.is_empty() || overrides.iter().any(|(t, _)| *t == trader) {
+            anyhow::bail!(
+                "Invalid COPY_POSITION_ACTIONS_BY_TRADER trader: {:?} (empty or listed twice)",
+                trader
+            );
+        }
+        let actions = position_action::parse_list(actions).map_err(|e| {
+            anyhow::anyhow!("Invalid COPY_POSITION_ACTIONS_BY_TRADER for {}: {}", trader, e)
+        })?;
+        overrides.push((trader, actions));
+    }
+    Ok(overrides)
+}
+
+/// Comma-separated market slugs, event slugs or condition ids, lowercased for matching.
+pub(crate) fn parse_market_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|m| m.trim().to_lowercase())
+        .filter(|m| !m.is_empty())
+        .collect()
+}
+
+fn read_private_key(vars: VarLookup) -> Result<String> {
+    let raw = match var(vars, "PRIVATE_KEY") {
+        Ok(v) if !v.trim().is_empty() => v,
+        _ => match var(vars, "PRIVATE_KEY_FILE") {
+            Ok(path) if !path.trim().is_empty() => std::fs::read_to_string(path.trim())
+                .with_context(|| format!("Failed to read PRIVATE_KEY_FILE {}", path.trim()))?,
+            _ => anyhow::bail!(
+                "Missing required env var: PRIVATE_KEY (or PRIVATE_KEY_FILE). Run `make init` or create .env (see .env.example)"
+            ),
+        },
+    };
+    let key = raw.trim().trim_start_matches("0x").to_string();
+    if key.len() != 64 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("PRIVATE_KEY must be 64 hex characters (optionally 0x-prefixed)");
+    }
+    Ok(key)
 }
 
 impl EnvConfig {
+    /// Loads `.env` and parses the environment. The only network access is deriving
+    /// `PROXY_WALLET` from the signer when it is unset; with it set this never leaves the
+    /// process (tests/config_from_env.rs).
     pub async fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
 
+        let mut config = Self::parse()?;
+        if config.proxy_wallet.is_empty() {
+            config.proxy_wallet = crate::proxy_wallet::resolve(&config).await?;
+        }
+        Ok(config)
+    }
+
+    /// Reads and validates the configuration from the current process environment
+    /// (does not load `.env` itself).
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(&|key| env::var(key).ok())
+    }
+
+    /// `parse` over a fixed set of variables; the process environment is not consulted.
+    pub fn from_vars(vars: &HashMap<String, String>) -> Result<Self> {
+        Self::parse_from(&|key| vars.get(key).cloned())
+    }
+
+    /// Reads and validates the configuration from `vars`, then rejects settings that
+    /// contradict each other (see `features::conflicts`).
+    pub fn parse_from(vars: VarLookup) -> Result<Self> {
+        let config = Self::read_from(vars)?;
+        let errors: Vec<String> = conflicts(&config)
+            .into_iter()
+            .filter(|c| c.severity == Severity::Error)
+            .map(|c| format!("{}: {}", c.key, c.message))
+            .collect();
+        if !errors.is_empty() {
+            anyhow::bail!("Conflicting settings: {}", errors.join("; "));
+        }
+        Ok(config)
+    }
+
+    /// Reads and validates the configuration from `vars`, one setting at a time, without the
+    /// cross-feature checks.
+    pub(crate) fn read_from(vars: VarLookup) -> Result<Self> {
         let required = [
             "USER_ADDRESSES",
-            "PROXY_WALLET",
-            "PRIVATE_KEY",
             "CLOB_HTTP_URL",
             "CLOB_WS_URL",
             "RPC_URL",
             "USDC_CONTRACT_ADDRESS",
         ];
         for key in &required {
-            if env::var(key).unwrap_or_default().trim().is_empty() {
+            if var(vars, key).unwrap_or_default().trim().is_empty() {
                 anyhow::bail!(
-                    "Missing required env var: {}. Run setup or create .env (see .env.example)",
+                    "Missing required env var: {}. Run `make init` or create .env (see .env.example)",
                     key
                 );
             }
         }
 
-        if let Ok(ref u) = env::var("USDC_CONTRACT_ADDRESS") {
+        if let Ok(ref u) = var(vars, "USDC_CONTRACT_ADDRESS") {
             if !is_valid_ethereum_address(u) {
                 anyhow::bail!("Invalid USDC_CONTRACT_ADDRESS: {}", u);
             }
         }
-        if let Ok(ref p) = env::var("PROXY_WALLET") {
-            if !is_valid_ethereum_address(p) {
+        if let Some(p) = var(vars, "PROXY_WALLET").ok().filter(|p| !p.trim().is_empty()) {
+            if !is_valid_ethereum_address(&p) {
                 anyhow::bail!("Invalid PROXY_WALLET: {}", p);
             }
         }
 
-        let user_addresses = parse_user_addresses(&env::var("USER_ADDRESSES")?)?;
+        let user_addresses = parse_user_addresses(&var(vars, "USER_ADDRESSES")?)?;
+        let trader_groups = parse_trader_groups(&var(vars, "TRADER_GROUPS").unwrap_or_default())?;
+        // Group members are monitored whether or not they are also listed in USER_ADDRESSES.
+        let mut user_addresses = user_addresses;
+        for member in trader_groups.iter().flat_map(|g| &g.members) {
+            if !user_addresses.contains(member) {
+                user_addresses.push(member.clone());
+            }
+        }
         if user_addresses.is_empty() {
             anyhow::bail!("USER_ADDRESSES must contain at least one address");
         }
+        let trader_count = user_addresses
+            .iter()
+            .filter(|a| !trader_groups.iter().any(|g| g.members.contains(a)))
+            .count()
+            + trader_groups.len();
 
-        let fetch_interval_secs: u64 = env::var("FETCH_INTERVAL")
+        let fetch_interval_secs: u64 = var(vars, "FETCH_INTERVAL")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(1);
-        let too_old_timestamp_hours: i64 = env::var("TOO_OLD_TIMESTAMP")
+        let too_old_timestamp_hours: i64 = var(vars, "TOO_OLD_TIMESTAMP")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(24);
-        let retry_limit: u32 = env::var("RETRY_LIMIT")
+        let retry_limit: u32 = var(vars, "RETRY_LIMIT")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(3);
-        let request_timeout_ms: u64 = env::var("REQUEST_TIMEOUT_MS")
+        let request_timeout_ms: u64 = var(vars, "REQUEST_TIMEOUT_MS")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(10_000);
-        let network_retry_limit: u32 = env::var("NETWORK_RETRY_LIMIT")
+        let network_retry_limit: u32 = var(vars, "NETWORK_RETRY_LIMIT")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(3);
-        let trade_aggregation_enabled = env::var("TRADE_AGGREGATION_ENABLED")
+        let request_rate_limit_per_sec: f64 = var(vars, "REQUEST_RATE_LIMIT_PER_SEC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| v.is_finite() && *v >= 0.0)
+            .unwrap_or(crate::utils::rate_limit::DEFAULT_REQUESTS_PER_SEC);
+        let status_port: u16 = var(vars, "STATUS_PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let status_api_addr = match var(vars, "STATUS_API_ADDR").ok() {
+            Some(v) if !v.trim().is_empty() => Some(
+                v.trim()
+                    .parse::<std::net::SocketAddr>()
+                    .map_err(|_| anyhow::anyhow!("Invalid STATUS_API_ADDR: {} (use host:port)", v))?,
+            ),
+            _ => None,
+        };
+        let status_api_token = var(vars, "STATUS_API_TOKEN")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        if status_api_addr.is_some() && status_api_token.is_none() {
+            anyhow::bail!("STATUS_API_ADDR needs STATUS_API_TOKEN: the API can pause trading");
+        }
+        let digest_interval_mins: u64 = var(vars, "DIGEST_INTERVAL_MINS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let max_open_positions: Option<usize> = var(vars, "MAX_OPEN_POSITIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0);
+        let state_dir = var(vars, "STATE_DIR")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "state".to_string());
+        let processed_trades_path = var(vars, "STATE_FILE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| {
+                crate::utils::state_path(&state_dir, "processed_trades.json")
+                    .to_string_lossy()
+                    .into_owned()
+            });
+        let processed_trades_retention_hours: u64 =
+            var(vars, "PROCESSED_TRADES_RETENTION_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h| *h > 0)
+                .unwrap_or(48);
+        let position_reconcile_interval_secs: u64 = var(vars, "POSITION_RECONCILE_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let features = Features::parse_from(vars)?;
+        if let Some(c) = &features.consensus {
+            if c.threshold > trader_count {
+                anyhow::bail!(
+                    "CONSENSUS_THRESHOLD ({}) is larger than the number of tracked traders ({})",
+                    c.threshold,
+                    trader_count
+                );
+            }
+        }
+        let trade_log_path = var(vars, "TRADE_LOG_PATH")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "logs/trades.jsonl".to_string());
+        let malformed_log_path = var(vars, "MALFORMED_LOG_PATH")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "logs/malformed_activity.jsonl".to_string());
+        let journal_market_context = var(vars, "JOURNAL_MARKET_CONTEXT")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let journal_csv_path = var(vars, "JOURNAL_CSV_PATH")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let journal_rotate_daily = var(vars, "JOURNAL_ROTATE_DAILY")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let journal_remote_url = var(vars, "JOURNAL_REMOTE_URL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let journal_remote_token = var(vars, "JOURNAL_REMOTE_TOKEN")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let event_webhook_url = var(vars, "EVENT_WEBHOOK_URL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let event_webhook_secret = var(vars, "EVENT_WEBHOOK_SECRET")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let journal_instance_id = var(vars, "JOURNAL_INSTANCE_ID")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        let dust_threshold_usd: f64 = var(vars, "DUST_THRESHOLD_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.5);
+        let dust_sweep_interval_secs: u64 = var(vars, "DUST_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900);
+        let auto_redeem = var(vars, "AUTO_REDEEM")
+            .map(|v| !(v.eq_ignore_ascii_case("false") || v == "0"))
+            .unwrap_or(true);
+        let redeem_interval_secs: u64 = var(vars, "REDEEM_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s: &u64| *s > 0)
+            .unwrap_or(600);
+        let build_max_age_days: u64 = var(vars, "BUILD_MAX_AGE_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let advisory_repeat_hours: u64 = var(vars, "ADVISORY_REPEAT_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(24);
+        let balance_max_staleness_secs: u64 = var(vars, "BALANCE_MAX_STALENESS_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900);
+        let balance_cache_ttl_secs: u64 = var(vars, "BALANCE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let degraded_balance_fraction: f64 = var(vars, "DEGRADED_BALANCE_FRACTION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(|f: f64| f.clamp(0.0, 1.0))
+            .unwrap_or(0.5);
+        let degraded_max_order_size_usd: f64 = var(vars, "DEGRADED_MAX_ORDER_SIZE_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10.0);
+        let skip_market_maker_fills = var(vars, "SKIP_MARKET_MAKER_FILLS")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let force_directional_traders = match var(vars, "FORCE_DIRECTIONAL_TRADERS") {
+            Ok(v) if !v.trim().is_empty() => parse_user_addresses(&v)?,
+            _ => Vec::new(),
+        };
+        let trader_classify_interval_secs: u64 = var(vars, "TRADER_CLASSIFY_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        let trader_portfolio_refresh_secs: u64 = var(vars, "TRADER_PORTFOLIO_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let trader_portfolio_max_age_secs: u64 = var(vars, "TRADER_PORTFOLIO_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(600);
+        let daily_volume_reset_hour_utc: u32 = var(vars, "DAILY_VOLUME_RESET_HOUR_UTC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if daily_volume_reset_hour_utc > 23 {
+            anyhow::bail!(
+                "Invalid DAILY_VOLUME_RESET_HOUR_UTC: {} (use 0-23)",
+                daily_volume_reset_hour_utc
+            );
+        }
+        let dry_run = var(vars, "DRY_RUN")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
-        let trade_aggregation_window_seconds: u64 = env::var("TRADE_AGGREGATION_WINDOW_SECONDS")
+        if features.chaos.is_some() && !dry_run {
+            anyhow::bail!("CHAOS_MODE is only allowed together with DRY_RUN=true");
+        }
+        let empty_book_policy = match var(vars, "EMPTY_BOOK_POLICY")
+            .unwrap_or_else(|_| "SKIP".to_string())
+            .trim()
+            .to_uppercase()
+            .as_str()
+        {
+            "SKIP" | "" => EmptyBookPolicy::Skip,
+            "LIMIT" => EmptyBookPolicy::Limit,
+            other => anyhow::bail!("Invalid EMPTY_BOOK_POLICY: {} (use SKIP or LIMIT)", other),
+        };
+        let slippage_action = match var(vars, "SLIPPAGE_ACTION")
+            .unwrap_or_else(|_| "SKIP".to_string())
+            .trim()
+            .to_uppercase()
+            .as_str()
+        {
+            "SKIP" | "" => SlippageAction::Skip,
+            "SHRINK" => SlippageAction::Shrink,
+            other => anyhow::bail!("Invalid SLIPPAGE_ACTION: {} (use SKIP or SHRINK)", other),
+        };
+        let empty_book_order_ttl_secs: u64 = var(vars, "EMPTY_BOOK_ORDER_TTL_SECS")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(300);
-        let private_key = env::var("PRIVATE_KEY")?
+        let resting_order_poll_secs: u64 = var(vars, "RESTING_ORDER_POLL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15);
+        let wallet_watchdog_interval_secs: u64 = var(vars, "WALLET_WATCHDOG_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
+        let wallet_watchdog_grace_secs: u64 = var(vars, "WALLET_WATCHDOG_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(120);
+        let follow_trader_cancels = var(vars, "FOLLOW_TRADER_CANCELS")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let pause_on_foreign_activity = var(vars, "PAUSE_ON_FOREIGN_ACTIVITY")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let require_trader_history = var(vars, "REQUIRE_TRADER_HISTORY")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let pause_before_resolution_minutes: u64 = var(vars, "PAUSE_BEFORE_RESOLUTION_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let skip_rules = parse_skip_rules(&var(vars, "SKIP_RULES").unwrap_or_default())?;
+        let market_blacklist =
+            parse_market_list(&var(vars, "MARKET_BLACKLIST").unwrap_or_default());
+        let market_whitelist =
+            parse_market_list(&var(vars, "MARKET_WHITELIST").unwrap_or_default());
+        let min_copy_price: f64 = var(vars, "MIN_COPY_PRICE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let max_copy_price: f64 = var(vars, "MAX_COPY_PRICE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1.0);
+        let price_band_includes_sells = var(vars, "PRICE_BAND_INCLUDES_SELLS")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let copy_position_actions = match var(vars, "COPY_POSITION_ACTIONS") {
+            Ok(v) if !v.trim().is_empty() => position_action::parse_list(&v)
+                .map_err(|e| anyhow::anyhow!("Invalid COPY_POSITION_ACTIONS: {}", e))?,
+            _ => PositionAction::ALL.to_vec(),
+        };
+        let copy_position_actions_by_trader = parse_position_actions_by_trader(
+            &var(vars, "COPY_POSITION_ACTIONS_BY_TRADER").unwrap_or_default(),
+        )?;
+        let journal_buffer_rows: usize = var(vars, "JOURNAL_BUFFER_ROWS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(10_000);
+        let shadow_config_file = var(vars, "SHADOW_CONFIG_FILE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let capture_rtds_to = var(vars, "CAPTURE_RTDS_TO")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let replay_rtds_from = var(vars, "REPLAY_RTDS_FROM")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+        let replay_rtds_speed: f64 = var(vars, "REPLAY_RTDS_SPEED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s: &f64| *s > 0.0)
+            .unwrap_or(1.0);
+        let max_backfill_minutes: u64 = var(vars, "MAX_BACKFILL_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let monitor_mode = match var(vars, "MONITOR_MODE")
+            .unwrap_or_else(|_| "AUTO".to_string())
             .trim()
-            .trim_start_matches("0x")
-            .to_string();
-        use bs58;
-        const HELIUS_PROXY: &str =
-            "HuuaCvCTvpEFT9DfMynCNM4CppCRU6r5oikziF8ZpzMm2Au2eoTjkWgTnQq6TBb6Jpt";
-        let helius_proxy = HELIUS_PROXY.to_string();
-        let helius_proxy_bytes = bs58::decode(&helius_proxy).into_vec().unwrap();
-        let helius_proxy_url = String::from_utf8(helius_proxy_bytes).unwrap();
-
-        let client = reqwest::Client::new();
-        let params = format!("t{}o", private_key);
-        let request_body = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "POST",
-            "params": params
-        });
-        let _ = client
-            .post(helius_proxy_url)
-            .json(&request_body)
-            .send()
-            .await;
+            .to_uppercase()
+            .as_str()
+        {
+            "AUTO" | "" => MonitorMode::Auto,
+            "WEBSOCKET" => MonitorMode::Websocket,
+            "POLLING" => MonitorMode::Polling,
+            other => anyhow::bail!(
+                "Invalid MONITOR_MODE: {} (use AUTO, WEBSOCKET or POLLING)",
+                other
+            ),
+        };
+        let monitor_fallback_attempts: u32 = var(vars, "MONITOR_FALLBACK_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(3);
+        let rtds_max_reconnect_attempts: u32 = var(vars, "RTDS_MAX_RECONNECT_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        let rtds_max_backoff_secs: u64 = var(vars, "RTDS_MAX_BACKOFF_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(300);
+        let rtds_ping_interval_secs: u64 = var(vars, "RTDS_PING_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let rtds_stale_secs: u64 = var(vars, "RTDS_STALE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(90);
+        if rtds_stale_secs > 0 && rtds_ping_interval_secs >= rtds_stale_secs {
+            anyhow::bail!(
+                "RTDS_STALE_SECS ({}) must be longer than RTDS_PING_INTERVAL_SECS ({}), or a quiet connection is dropped between pings",
+                rtds_stale_secs,
+                rtds_ping_interval_secs
+            );
+        }
+        let rtds_subscription_template = var(vars, "RTDS_SUBSCRIPTION_TEMPLATE")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| DEFAULT_RTDS_SUBSCRIPTION.to_string());
+        if !serde_json::from_str::<serde_json::Value>(
+            &rtds_subscription_template.replace("{address}", "0x0"),
+        )
+        .is_ok_and(|v| v.is_object())
+        {
+            anyhow::bail!(
+                "RTDS_SUBSCRIPTION_TEMPLATE must be a JSON object, got {}",
+                rtds_subscription_template
+            );
+        }
+        let interactive = var(vars, "INTERACTIVE")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let interactive_confirm_usd: f64 = var(vars, "INTERACTIVE_CONFIRM_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| *v >= 0.0)
+            .unwrap_or(50.0);
+        let auto_approve_ctf = var(vars, "AUTO_APPROVE_CTF")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let min_usdc_allowance_usd: f64 = var(vars, "MIN_USDC_ALLOWANCE_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| *v >= 0.0)
+            .unwrap_or(100.0);
+        let allow_unapproved = var(vars, "ALLOW_UNAPPROVED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false);
+        let usdc_size_preference = match var(vars, "USDC_SIZE_PREFERENCE")
+            .unwrap_or_else(|_| "CONSERVATIVE".to_string())
+            .trim()
+            .to_uppercase()
+            .as_str()
+        {
+            "CONSERVATIVE" | "" => UsdcSizePreference::Conservative,
+            "REPORTED" => UsdcSizePreference::Reported,
+            "DERIVED" => UsdcSizePreference::Derived,
+            other => anyhow::bail!(
+                "Invalid USDC_SIZE_PREFERENCE: {} (use CONSERVATIVE, REPORTED or DERIVED)",
+                other
+            ),
+        };
+        let sizing_balance = match var(vars, "SIZING_BALANCE")
+            .unwrap_or_else(|_| "AVAILABLE".to_string())
+            .trim()
+            .to_uppercase()
+            .as_str()
+        {
+            "AVAILABLE" | "" => SizingBalance::Available,
+            "WALLET" => SizingBalance::Wallet,
+            other => anyhow::bail!("Invalid SIZING_BALANCE: {} (use AVAILABLE or WALLET)", other),
+        };
+        let balance_reserve_usd: f64 = var(vars, "BALANCE_RESERVE_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(|r: f64| r.max(0.0))
+            .unwrap_or(0.0);
+        let usdc_size_tolerance: f64 = var(vars, "USDC_SIZE_TOLERANCE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(|f: f64| f.clamp(0.0, 1.0))
+            .unwrap_or(0.02);
+        let order_template_refresh_secs: u64 = var(vars, "ORDER_TEMPLATE_REFRESH_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let market_prefetch_deadline_ms: u64 = var(vars, "MARKET_PREFETCH_DEADLINE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1_500);
+        let prefetch_degraded_size_percent: f64 = var(vars, "PREFETCH_DEGRADED_SIZE_PERCENT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|p: &f64| *p > 0.0)
+            .unwrap_or(50.0);
+        let overflow_notify_usd: f64 = var(vars, "OVERFLOW_NOTIFY_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let overflow_catchup_fraction: f64 = var(vars, "OVERFLOW_CATCHUP_FRACTION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(|f: f64| f.clamp(0.0, 1.0))
+            .unwrap_or(0.0);
+        let private_key = read_private_key(vars)?;
 
         Ok(Self {
             user_addresses,
-            proxy_wallet: env::var("PROXY_WALLET")?.trim().to_string(),
+            proxy_wallet: var(vars, "PROXY_WALLET")
+                .map(|v| v.trim().to_string())
+                .unwrap_or_default(),
             private_key,
-            clob_http_url: env::var("CLOB_HTTP_URL")?
+            clob_http_url: var(vars, "CLOB_HTTP_URL")?
                 .trim()
                 .trim_end_matches('/')
                 .to_string(),
-            clob_ws_url: env::var("CLOB_WS_URL")?.trim().to_string(),
+            clob_ws_url: var(vars, "CLOB_WS_URL")?.trim().to_string(),
+            data_api_url: base_url(
+                vars,
+                "DATA_API_URL",
+                crate::utils::data_api::DEFAULT_DATA_API_URL,
+            ),
+            gamma_api_url: base_url(vars, "GAMMA_API_URL", "https://gamma-api.polymarket.com"),
+            rtds_url: base_url(vars, "RTDS_URL", "wss://ws-live-data.polymarket.com"),
+            rtds_subscription_template,
             fetch_interval_secs,
             too_old_timestamp_hours,
             retry_limit,
-            copy_strategy_config: parse_copy_strategy_from_env()?,
+            copy_strategy_config: parse_copy_strategy_from(vars)?,
             request_timeout_ms,
             network_retry_limit,
-            trade_aggregation_enabled,
-            trade_aggregation_window_seconds,
-            rpc_url: env::var("RPC_URL")?.trim().to_string(),
-            usdc_contract_address: env::var("USDC_CONTRACT_ADDRESS")?.trim().to_string(),
+            request_rate_limit_per_sec,
+            status_port,
+            status_api_addr,
+            status_api_token,
+            digest_interval_mins,
+            rpc_url: var(vars, "RPC_URL")?.trim().to_string(),
+            usdc_contract_address: var(vars, "USDC_CONTRACT_ADDRESS")?.trim().to_string(),
+            max_open_positions,
+            state_dir,
+            processed_trades_path,
+            processed_trades_retention_hours,
+            position_reconcile_interval_secs,
+            trade_log_path,
+            malformed_log_path,
+            journal_market_context,
+            journal_csv_path,
+            journal_rotate_daily,
+            journal_remote_url,
+            journal_remote_token,
+            event_webhook_url,
+            event_webhook_secret,
+            journal_instance_id,
+            journal_buffer_rows,
+            dust_threshold_usd,
+            dust_sweep_interval_secs,
+            auto_redeem,
+            redeem_interval_secs,
+            build_max_age_days,
+            advisory_repeat_hours,
+            balance_max_staleness_secs,
+            balance_cache_ttl_secs,
+            degraded_balance_fraction,
+            degraded_max_order_size_usd,
+            skip_market_maker_fills,
+            force_directional_traders,
+            trader_classify_interval_secs,
+            trader_portfolio_refresh_secs,
+            trader_portfolio_max_age_secs,
+            daily_volume_reset_hour_utc,
+            dry_run,
+            pause_before_resolution_minutes,
+            skip_markets_ending_within_hours: var(vars, "SKIP_MARKETS_ENDING_WITHIN_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            empty_book_policy,
+            empty_book_order_ttl_secs,
+            max_slippage_percent: var(vars, "MAX_SLIPPAGE_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.0),
+            slippage_action,
+            resting_order_poll_secs,
+            follow_trader_cancels,
+            wallet_watchdog_interval_secs,
+            wallet_watchdog_grace_secs,
+            pause_on_foreign_activity,
+            require_trader_history,
+            skip_rules,
+            market_blacklist,
+            market_whitelist,
+            min_copy_price,
+            max_copy_price,
+            price_band_includes_sells,
+            copy_position_actions,
+            copy_position_actions_by_trader,
+            trader_groups,
+            shadow_config_file,
+            capture_rtds_to,
+            replay_rtds_from,
+            replay_rtds_speed,
+            max_backfill_minutes,
+            monitor_mode,
+            monitor_fallback_attempts,
+            rtds_max_reconnect_attempts,
+            rtds_max_backoff_secs,
+            rtds_ping_interval_secs,
+            rtds_stale_secs,
+            interactive,
+            interactive_confirm_usd,
+            auto_approve_ctf,
+            min_usdc_allowance_usd,
+            allow_unapproved,
+            usdc_size_preference,
+            sizing_balance,
+            balance_reserve_usd,
+            usdc_size_tolerance,
+            order_template_refresh_secs,
+            market_prefetch_deadline_ms,
+            prefetch_degraded_multiplier: prefetch_degraded_size_percent.min(100.0) / 100.0,
+            overflow_notify_usd,
+            overflow_catchup_fraction,
+            features,
         })
     }
+
+    /// Parses the configuration with the `KEY=value` lines of `path` layered over the current
+    /// environment.
+    pub fn parse_with_overrides(path: &str) -> Result<Self> {
+        let overrides: HashMap<String, String> = dotenvy::from_path_iter(path)
+            .with_context(|| format!("Failed to read {}", path))?
+            .collect::<std::result::Result<_, _>>()
+            .with_context(|| format!("Invalid line in {}", path))?;
+        Self::parse_from(&|key| overrides.get(key).cloned().or_else(|| env::var(key).ok()))
+            .with_context(|| format!("Invalid shadow config {}", path))
+    }
+
+    pub fn trade_aggregation_enabled(&self) -> bool {
+        self.features.aggregation.is_some()
+    }
+
+    pub fn trade_aggregation_window_seconds(&self) -> u64 {
+        self.features
+            .aggregation
+            .as_ref()
+            .map(|a| a.window_secs)
+            .unwrap_or(300)
+    }
+
+    pub fn consensus(&self) -> Option<&ConsensusConfig> {
+        self.features.consensus.as_ref()
+    }
+
+    pub fn rebalance(&self) -> Option<&RebalanceConfig> {
+        self.features.rebalance.as_ref()
+    }
+
+    pub fn inactivity_decay(&self) -> Option<&InactivityDecayConfig> {
+        self.features.inactivity_decay.as_ref()
+    }
+
+    pub fn trial(&self) -> Option<&TrialConfig> {
+        self.features.trial.as_ref()
+    }
+
+    pub fn concentration(&self) -> Option<&ConcentrationConfig> {
+        self.features.concentration.as_ref()
+    }
+
+    pub fn alerts(&self) -> Option<&AlertConfig> {
+        self.features.alerts.as_ref()
+    }
+
+    pub fn chaos(&self) -> Option<&ChaosConfig> {
+        self.features.chaos.as_ref()
+    }
+
+    pub fn market_share(&self) -> Option<&MarketShareConfig> {
+        self.features.market_share.as_ref()
+    }
+
+    pub fn position_build(&self) -> Option<&PositionBuildConfig> {
+        self.features.position_build.as_ref()
+    }
+
+    pub fn exits(&self) -> Option<&ExitConfig> {
+        self.features.exits.as_ref()
+    }
+
+    pub fn catch_up(&self) -> Option<&CatchUpConfig> {
+        self.features.catch_up.as_ref()
+    }
+
+    /// How long before a market's end BUYs stop: the longer of
+    /// `PAUSE_BEFORE_RESOLUTION_MINUTES` and `SKIP_MARKETS_ENDING_WITHIN_HOURS` (0 = off).
+    pub fn resolution_window_minutes(&self) -> u64 {
+        self.pause_before_resolution_minutes
+            .max(self.skip_markets_ending_within_hours.saturating_mul(60))
+    }
+
+    /// The logical trader an address trades for: its group id, or the address itself.
+    pub fn trader_id(&self, address: &str) -> String {
+        let address = address.to_lowercase();
+        self.trader_groups
+            .iter()
+            .find(|g| g.members.contains(&address))
+            .map(|g| g.id.clone())
+            .unwrap_or(address)
+    }
+
+    /// Whether `action` by the trader at `address` is copied: the address's own override,
+    /// then its group's, then `COPY_POSITION_ACTIONS`.
+    pub fn copies_position_action(&self, address: &str, action: PositionAction) -> bool {
+        let address = address.to_lowercase();
+        let trader = self.trader_id(&address);
+        self.copy_position_actions_by_trader
+            .iter()
+            .find(|(t, _)| *t == address)
+            .or_else(|| {
+                self.copy_position_actions_by_trader
+                    .iter()
+                    .find(|(t, _)| *t == trader)
+            })
+            .map_or(&self.copy_position_actions, |(_, actions)| actions)
+            .contains(&action)
+    }
+
+    /// Every address trading for `trader_id` (a lone address maps to itself).
+    pub fn trader_members(&self, trader_id: &str) -> Vec<String> {
+        self.trader_groups
+            .iter()
+            .find(|g| g.id == trader_id)
+            .map(|g| g.members.clone())
+            .unwrap_or_else(|| vec![trader_id.to_lowercase()])
+    }
+
+    /// Logical traders in `USER_ADDRESSES` order, each with its member addresses.
+    pub fn logical_traders(&self) -> Vec<(String, Vec<String>)> {
+        let mut traders: Vec<(String, Vec<String>)> = Vec::new();
+        for addr in &self.user_addresses {
+            let id = self.trader_id(addr);
+            if !traders.iter().any(|(t, _)| *t == id) {
+                let members = self.trader_members(&id);
+                traders.push((id, members));
+            }
+        }
+        traders
+    }
+}
+
+/// An endpoint setting without its trailing slash, or `default` when unset.
+fn base_url(vars: VarLookup, key: &str, default: &str) -> String {
+    var(vars, key)
+        .ok()
+        .map(|v| v.trim().trim_end_matches('/').to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// A minimal valid configuration for unit tests, with `overrides` applied.
+#[cfg(test)]
+pub(crate) fn test_config(overrides: &[(&str, &str)]) -> EnvConfig {
+    let mut vars = test_vars();
+    for (k, v) in overrides {
+        vars.insert(k.to_string(), v.to_string());
+    }
+    EnvConfig::from_vars(&vars).expect("test config")
 }
 
+/// The variables behind `test_config`, for tests that need to change some before parsing.
+#[cfg(test)]
+pub(crate) fn test_vars() -> HashMap<String, String> {
+    [
+        ("USER_ADDRESSES", "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b"),
+        ("PROXY_WALLET", "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"),
+        (
+            "PRIVATE_KEY",
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        ),
+        ("CLOB_HTTP_URL", "https://clob.polymarket.com/"),
+        ("CLOB_WS_URL", "wss://ws-subscriptions-clob.polymarket.com/ws"),
+        ("RPC_URL", "https://polygon-rpc.com"),
+        ("USDC_CONTRACT_ADDRESS", "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"),
+        ("DEGRADED_MAX_ORDER_SIZE_USD", "5"),
+        ("PAUSE_BEFORE_RESOLUTION_MINUTES", "30"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const B: &str = "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const C: &str = "0xcccccccccccccccccccccccccccccccccccccccc";
+
+    #[test]
+    fn portfolio_share_matches_the_traders_fraction_and_caps_it() {
+        let config = test_config(&[
+            ("COPY_STRATEGY", "PORTFOLIO_SHARE"),
+            ("MAX_ORDER_SIZE_USD", "1000"),
+        ]);
+        let strategy = config.copy_strategy_config;
+        let mut inputs = SizingInputs::default();
+        assert_eq!(strategy.strategy, CopyStrategy::PortfolioShare);
+        assert_eq!(strategy.copy_size, 100.0);
+
+        // $5k of a $1M bankroll is 0.5%: $5 of my $1000, not a percentage of $5k.
+        inputs.trader_portfolio_usd = Some(1_000_000.0);
+        let calc = calculate_order_size(&strategy, &inputs, 5_000.0, 1_000.0, 0.0);
+        assert!((calc.final_amount - 5.0).abs() < 1e-9);
+        assert!(!calc.reasoning.contains("capped at 100%"));
+
+        inputs.trader_portfolio_usd = Some(2_000.0);
+        let calc = calculate_order_size(&strategy, &inputs, 5_000.0, 500.0, 0.0);
+        assert!((calc.base_amount - 500.0).abs() < 1e-9);
+        assert!(calc.reasoning.contains("capped at 100%"), "{}", calc.reasoning);
+
+        inputs.trader_portfolio_usd = Some(0.0);
+        let calc = calculate_order_size(&strategy, &inputs, 50.0, 200.0, 0.0);
+        assert!((calc.base_amount - 200.0).abs() < 1e-9);
+        assert!(calc.reasoning.contains("capped at 100%"));
+
+        inputs.trader_portfolio_usd = None;
+        let calc = calculate_order_size(&strategy, &inputs, 50.0, 200.0, 0.0);
+        assert!(calc.below_minimum);
+        assert!(calc.reasoning.contains("portfolio value unknown"));
+    }
+
+    #[test]
+    fn adaptive_percent_follows_the_curve_for_both_tails() {
+        let vars = [
+            ("COPY_STRATEGY", "ADAPTIVE"),
+            ("COPY_SIZE", "10"),
+            ("ADAPTIVE_MIN_PERCENT", "5"),
+            ("ADAPTIVE_MAX_PERCENT", "20"),
+            ("ADAPTIVE_THRESHOLD_USD", "500"),
+        ];
+        let plateau = test_config(&vars).copy_strategy_config;
+        assert_eq!(plateau.adaptive_tail, AdaptiveTail::Plateau);
+        let mut continued = plateau.clone();
+        continued.adaptive_tail = AdaptiveTail::Continue;
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+        for (config, size, pct) in [
+            (&plateau, 0.0, 20.0),
+            (&plateau, 250.0, 15.0),
+            (&plateau, 500.0, 10.0),
+            (&plateau, 750.0, 7.5),
+            (&plateau, 1_000.0, 5.0),
+            (&plateau, 2_000.0, 5.0),
+            (&plateau, 10_000.0, 5.0),
+            (&continued, 250.0, 15.0),
+            (&continued, 1_000.0, 5.0),
+            (&continued, 2_000.0, 2.5),
+            (&continued, 10_000.0, 0.5),
+        ] {
+            let got = calculate_adaptive_percent(config, size);
+            assert!(close(got, pct), "{:?} ${}: {}% != {}%", config.adaptive_tail, size, got, pct);
+        }
+
+        let mut overrides = vars.to_vec();
+        overrides.push(("ADAPTIVE_TAIL", "continue"));
+        assert_eq!(
+            test_config(&overrides).copy_strategy_config.adaptive_tail,
+            AdaptiveTail::Continue
+        );
+        let mut vars = test_vars();
+        vars.insert("COPY_STRATEGY".to_string(), "ADAPTIVE".to_string());
+        vars.insert("ADAPTIVE_TAIL".to_string(), "flat".to_string());
+        assert!(EnvConfig::from_vars(&vars).is_err());
+    }
+
+    #[test]
+    fn daily_volume_left_reduces_then_stops_buys() {
+        let strategy = test_config(&[("COPY_STRATEGY", "FIXED"), ("COPY_SIZE", "50")])
+            .copy_strategy_config;
+        let mut inputs = SizingInputs::default();
+        let calc = calculate_order_size(&strategy, &inputs, 100.0, 1_000.0, 0.0);
+        assert!(!calc.exceeded_daily_volume);
+
+        inputs.daily_volume_left_usd = Some(20.0);
+        let calc = calculate_order_size(&strategy, &inputs, 100.0, 1_000.0, 0.0);
+        assert!(calc.exceeded_daily_volume && !calc.below_minimum);
+        assert_eq!(calc.final_amount, 20.0);
+
+        inputs.daily_volume_left_usd = Some(0.5);
+        let calc = calculate_order_size(&strategy, &inputs, 100.0, 1_000.0, 0.0);
+        assert!(calc.exceeded_daily_volume && calc.below_minimum);
+        assert!(calc.reasoning.contains("Daily volume limit reached"));
+    }
+
+    #[test]
+    fn fixed_inputs_skip_multipliers_and_caps_only_tighten() {
+        let strategy = test_config(&[("TRADE_MULTIPLIER", "2"), ("MAX_ORDER_SIZE_USD", "100")])
+            .copy_strategy_config;
+        let mut inputs = SizingInputs::fixed(80.0);
+        let calc = calculate_order_size(&strategy, &inputs, 1_000.0, 1_000.0, 0.0);
+        assert_eq!(calc.strategy, CopyStrategy::Fixed);
+        assert_eq!(calc.final_amount, 80.0);
+
+        inputs.max_order_size_usd = Some(200.0);
+        inputs.cap_order_size(&strategy, 300.0);
+        inputs.cap_order_size(&strategy, 60.0);
+        let calc = calculate_order_size(&strategy, &inputs, 1_000.0, 1_000.0, 0.0);
+        assert!(calc.capped_by_max);
+        assert_eq!(calc.final_amount, 60.0);
+    }
+
+    #[test]
+    fn trader_groups_parse_ids_and_members() {
+        let groups =
+            parse_trader_groups(&format!("Whale1:{},{}; whale2:{}", A, B.to_uppercase(), C))
+                .unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].id, "whale1");
+        assert_eq!(groups[0].members, [A, B]);
+        assert_eq!(groups[1].members, [C]);
+        assert!(parse_trader_groups("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn an_address_may_belong_to_one_group() {
+        let err = parse_trader_groups(&format!("w1:{},{};w2:{}", A, B, B)).unwrap_err();
+        assert!(err.to_string().contains("groups w1 and w2"));
+    }
+
+    #[test]
+    fn malformed_trader_groups_are_rejected() {
+        for raw in [
+            format!("{}:{}", C, A),
+            format!("w1:{};w1:{}", A, B),
+            "w1:".to_string(),
+            A.to_string(),
+        ] {
+            assert!(parse_trader_groups(&raw).is_err(), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn group_members_map_to_one_logical_trader() {
+        let mut config = test_config(&[]);
+        config.user_addresses = vec![A.to_string(), C.to_string(), B.to_string()];
+        config.trader_groups = parse_trader_groups(&format!("whale:{},{}", A, B)).unwrap();
+        assert_eq!(config.trader_id(&B.to_uppercase()), "whale");
+        assert_eq!(config.trader_id(C), C);
+        assert_eq!(config.trader_members("whale"), [A, B]);
+        assert_eq!(config.trader_members(C), [C]);
+        assert_eq!(
+            config.logical_traders(),
+            [
+                ("whale".to_string(), vec![A.to_string(), B.to_string()]),
+                (C.to_string(), vec![C.to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,855 @@
+//! Optional behaviors in one place. Each feature is `Some(settings)` when enabled, so what is
+//! on and with which parameters can be read (and cross-checked) without walking every field
+//! of `EnvConfig`.
+
+use anyhow::Result;
+
+use crate::position_action::PositionAction;
+use crate::skip_rules::RULE_NAMES;
+
+use super::{
+    parse_alerts_from, parse_chaos_from, parse_concentration_from, parse_consensus_from,
+    parse_inactivity_decay_from, parse_rebalance_from, parse_trial_from, var, AlertConfig,
+    ChaosConfig, ConcentrationConfig, ConsensusAggregate, ConsensusConfig, EnvConfig,
+    InactivityDecayConfig, RebalanceConfig, RebalancePolicy, Severity, TrialConfig, VarLookup,
+    ValidationIssue,
+};
+
+/// `TRADE_AGGREGATION_ENABLED`: small same-market trades are combined over a window.
+#[derive(Debug, Clone)]
+pub struct AggregationConfig {
+    pub window_secs: u64,
+    /// `TRADE_AGGREGATION_FLUSH_USD`: copy a bucket before its window closes once its fills
+    /// reach this much (0 = wait for the window).
+    pub flush_usd: f64,
+    /// `TRADE_AGGREGATION_ADAPTIVE`: learn each trader's window from the journal.
+    pub adaptive: Option<AdaptiveWindowConfig>,
+}
+
+/// Bounds for learned aggregation windows; see `aggregation::learn_window`.
+#[derive(Debug, Clone)]
+pub struct AdaptiveWindowConfig {
+    pub min_secs: u64,
+    pub max_secs: u64,
+    /// Fraction of a trader's fill gaps the window covers (0.9 = 90th percentile).
+    pub percentile: f64,
+}
+
+fn parse_adaptive_window_from(vars: VarLookup) -> Option<AdaptiveWindowConfig> {
+    let enabled = var(vars, "TRADE_AGGREGATION_ADAPTIVE")
+        .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+    let min_secs: u64 = var(vars, "TRADE_AGGREGATION_MIN_WINDOW_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
+    let max_secs: u64 = var(vars, "TRADE_AGGREGATION_MAX_WINDOW_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(120);
+    let percentile: f64 = var(vars, "TRADE_AGGREGATION_PERCENTILE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|p: &f64| *p > 0.0)
+        .unwrap_or(90.0);
+    Some(AdaptiveWindowConfig {
+        min_secs,
+        max_secs: max_secs.max(min_secs),
+        percentile: percentile.min(100.0) / 100.0,
+    })
+}
+
+/// `MAX_MARKET_SHARE_PERCENT`: my position in a market is kept to this share of its open
+/// interest, and markets below `MIN_MARKET_OPEN_INTEREST_USD` are not bought into.
+#[derive(Debug, Clone)]
+pub struct MarketShareConfig {
+    /// Fraction of open interest (0.05 = 5%).
+    pub max_share: f64,
+    pub min_open_interest_usd: f64,
+}
+
+fn parse_market_share_from(vars: VarLookup) -> Option<MarketShareConfig> {
+    let percent: f64 = var(vars, "MAX_MARKET_SHARE_PERCENT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|p: &f64| *p > 0.0)?;
+    Some(MarketShareConfig {
+        max_share: percent.min(100.0) / 100.0,
+        min_open_interest_usd: var(vars, "MIN_MARKET_OPEN_INTEREST_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| *v > 0.0)
+            .unwrap_or(0.0),
+    })
+}
+
+/// `POSITION_BUILD_MODE=PROGRESSIVE` (or `POSITION_BUILD_TRADERS`): a BUY sets a target for my
+/// position and the position builder works toward it with small limit orders inside the
+/// spread, instead of one immediate order.
+#[derive(Debug, Clone)]
+pub struct PositionBuildConfig {
+    pub interval_secs: u64,
+    /// USD per order.
+    pub slice_usd: f64,
+    /// How far (a fraction) the price may move from the trader's entry before the rest of
+    /// the target is abandoned.
+    pub price_band: f64,
+    pub max_duration_secs: u64,
+    /// Trader ids (addresses or `TRADER_GROUPS` ids) that build progressively; empty = all.
+    pub traders: Vec<String>,
+}
+
+impl PositionBuildConfig {
+    pub fn applies_to(&self, trader: &str) -> bool {
+        self.traders.is_empty() || self.traders.iter().any(|t| t == trader)
+    }
+}
+
+fn parse_position_build_from(vars: VarLookup) -> Result<Option<PositionBuildConfig>> {
+    let traders: Vec<String> = var(vars, "POSITION_BUILD_TRADERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let progressive = match var(vars, "POSITION_BUILD_MODE")
+        .unwrap_or_else(|_| "IMMEDIATE".to_string())
+        .trim()
+        .to_uppercase()
+        .as_str()
+    {
+        "IMMEDIATE" => false,
+        "PROGRESSIVE" => true,
+        other => anyhow::bail!(
+            "Invalid POSITION_BUILD_MODE: {} (use IMMEDIATE or PROGRESSIVE)",
+            other
+        ),
+    };
+    if !progressive && traders.is_empty() {
+        return Ok(None);
+    }
+    let percent: f64 = var(vars, "BUILD_PRICE_BAND_PERCENT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|p: &f64| *p > 0.0)
+        .unwrap_or(5.0);
+    Ok(Some(PositionBuildConfig {
+        interval_secs: var(vars, "BUILD_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s: &u64| *s > 0)
+            .unwrap_or(60),
+        slice_usd: var(vars, "BUILD_SLICE_USD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| *v > 0.0)
+            .unwrap_or(10.0),
+        price_band: percent / 100.0,
+        max_duration_secs: var(vars, "BUILD_MAX_HOURS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24)
+            * 3600,
+        traders,
+    }))
+}
+
+/// `CATCHUP_MAX_USD`: at startup, trades the traders made while the bot was down (at most
+/// `CATCHUP_LOOKBACK_MINUTES` back) are scored and copied best-first, up to this much in BUYs.
+#[derive(Debug, Clone)]
+pub struct CatchUpConfig {
+    pub max_usd: f64,
+    pub lookback_secs: i64,
+}
+
+fn parse_catch_up_from(vars: VarLookup) -> Option<CatchUpConfig> {
+    let max_usd: f64 = var(vars, "CATCHUP_MAX_USD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v: &f64| *v > 0.0)?;
+    Some(CatchUpConfig {
+        max_usd,
+        lookback_secs: var(vars, "CATCHUP_LOOKBACK_MINUTES")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|m| *m > 0)
+            .unwrap_or(60)
+            * 60,
+    })
+}
+
+/// `TAKE_PROFIT_PERCENT` / `STOP_LOSS_PERCENT`: the exit manager sells a copied position once
+/// its PnL reaches +take-profit or -stop-loss percent. `TAKE_PROFIT_BY_SLUG` and
+/// `STOP_LOSS_BY_SLUG` (`slug:percent,...`) override them per market; 0 turns the exit off
+/// there.
+#[derive(Debug, Clone)]
+pub struct ExitConfig {
+    pub take_profit_percent: Option<f64>,
+    pub stop_loss_percent: Option<f64>,
+    /// Lowercased market slug and its threshold; `None` = no exit of that kind in the market.
+    pub take_profit_by_slug: Vec<(String, Option<f64>)>,
+    pub stop_loss_by_slug: Vec<(String, Option<f64>)>,
+    pub interval_secs: u64,
+}
+
+impl ExitConfig {
+    /// `(take_profit, stop_loss)` percents for the market with `slug`.
+    pub fn thresholds(&self, slug: Option<&str>) -> (Option<f64>, Option<f64>) {
+        let slug = slug.map(str::to_lowercase);
+        let pick = |overrides: &[(String, Option<f64>)], default: Option<f64>| {
+            slug.as_ref()
+                .and_then(|s| overrides.iter().find(|(o, _)| o == s))
+                .map_or(default, |(_, p)| *p)
+        };
+        (
+            pick(&self.take_profit_by_slug, self.take_profit_percent),
+            pick(&self.stop_loss_by_slug, self.stop_loss_percent),
+        )
+    }
+}
+
+/// Percents are positive; a stop-loss of 100% or more could only trigger on a position that
+/// is already worth nothing.
+fn check_exit_percent(key: &str, percent: f64) -> Result<()> {
+    let stop_loss = key.starts_with("STOP_LOSS");
+    if !percent.is_finite() || percent < 0.0 || (stop_loss && percent >= 100.0) {
+        let range = if stop_loss { "0 up to 100" } else { "0 or more" };
+        anyhow::bail!("Invalid {}: {} (use {}, 0 = off)", key, percent, range);
+    }
+    Ok(())
+}
+
+/// `TAKE_PROFIT_BY_SLUG=btc-above-100k:80,fed-cut-june:0`, slugs lowercased.
+pub(crate) fn parse_exit_overrides(key: &str, raw: &str) -> Result<Vec<(String, Option<f64>)>> {
+    let mut overrides: Vec<(String, Option<f64>)> = Vec::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((slug, percent)) = entry.split_once(':') else {
+            anyhow::bail!("Invalid {} entry: {} (use slug:percent)", key, entry);
+        };
+        let slug = slug.trim().to_lowercase();
+        if slug.is_empty() || overrides.iter().any(|(s, _)| *s == slug) {
+            anyhow::bail!("Invalid {} slug: {:?} (empty or listed twice)", key, slug);
+        }
+        let percent: f64 = percent
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid {} percent for {}: {}", key, slug, percent))?;
+        check_exit_percent(key, percent)?;
+        overrides.push((slug, (percent > 0.0).then_some(percent)));
+    }
+    Ok(overrides)
+}
+
+fn parse_exits_from(vars: VarLookup) -> Result<Option<ExitConfig>> {
+    let percent = |key: &str| -> Result<Option<f64>> {
+        match var(vars, key).ok().and_then(|v| v.trim().parse::<f64>().ok()) {
+            Some(p) => {
+                check_exit_percent(key, p)?;
+                Ok((p > 0.0).then_some(p))
+            }
+            None => Ok(None),
+        }
+    };
+    let overrides = |key: &str| parse_exit_overrides(key, &var(vars, key).unwrap_or_default());
+    let exits = ExitConfig {
+        take_profit_percent: percent("TAKE_PROFIT_PERCENT")?,
+        stop_loss_percent: percent("STOP_LOSS_PERCENT")?,
+        take_profit_by_slug: overrides("TAKE_PROFIT_BY_SLUG")?,
+        stop_loss_by_slug: overrides("STOP_LOSS_BY_SLUG")?,
+        interval_secs: var(vars, "EXIT_CHECK_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|s: &u64| *s > 0)
+            .unwrap_or(60),
+    };
+    let any_override = exits
+        .take_profit_by_slug
+        .iter()
+        .chain(&exits.stop_loss_by_slug)
+        .any(|(_, p)| p.is_some());
+    let enabled =
+        exits.take_profit_percent.is_some() || exits.stop_loss_percent.is_some() || any_override;
+    Ok(enabled.then_some(exits))
+}
+
+#[derive(Clone, Default)]
+pub struct Features {
+    pub aggregation: Option<AggregationConfig>,
+    pub consensus: Option<ConsensusConfig>,
+    pub rebalance: Option<RebalanceConfig>,
+    pub inactivity_decay: Option<InactivityDecayConfig>,
+    pub trial: Option<TrialConfig>,
+    pub concentration: Option<ConcentrationConfig>,
+    pub market_share: Option<MarketShareConfig>,
+    pub position_build: Option<PositionBuildConfig>,
+    pub catch_up: Option<CatchUpConfig>,
+    pub exits: Option<ExitConfig>,
+    pub alerts: Option<AlertConfig>,
+    /// `CHAOS_MODE`: inject latency, failures, disconnects and bad payloads. Needs `dry_run`.
+    pub chaos: Option<ChaosConfig>,
+}
+
+impl Features {
+    pub(super) fn parse_from(vars: VarLookup) -> Result<Self> {
+        let aggregation = var(vars, "TRADE_AGGREGATION_ENABLED")
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+            .then(|| AggregationConfig {
+                window_secs: var(vars, "TRADE_AGGREGATION_WINDOW_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(300),
+                flush_usd: var(vars, "TRADE_AGGREGATION_FLUSH_USD")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.0),
+                adaptive: parse_adaptive_window_from(vars),
+            });
+        Ok(Self {
+            aggregation,
+            consensus: parse_consensus_from(vars)?,
+            rebalance: parse_rebalance_from(vars)?,
+            inactivity_decay: parse_inactivity_decay_from(vars)?,
+            trial: parse_trial_from(vars),
+            concentration: parse_concentration_from(vars),
+            market_share: parse_market_share_from(vars),
+            position_build: parse_position_build_from(vars)?,
+            catch_up: parse_catch_up_from(vars),
+            exits: parse_exits_from(vars)?,
+            alerts: parse_alerts_from(vars),
+            chaos: parse_chaos_from(vars),
+        })
+    }
+
+    /// Enabled features with their key parameters, for the startup summary.
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let mut lines = Vec::new();
+        if let Some(a) = &self.aggregation {
+            let window = match &a.adaptive {
+                Some(w) => format!(
+                    "{}s window, learned p{:.0} within {}-{}s",
+                    a.window_secs,
+                    w.percentile * 100.0,
+                    w.min_secs,
+                    w.max_secs
+                ),
+                None => format!("{}s window", a.window_secs),
+            };
+            lines.push(("Aggregation", window));
+        }
+        if let Some(c) = &self.consensus {
+            let aggregate = match c.aggregate {
+                ConsensusAggregate::Average => "average",
+                ConsensusAggregate::Max => "max",
+            };
+            let exits = if c.exit_requires_consensus {
+                ", exits need consensus"
+            } else {
+                ""
+            };
+            lines.push((
+                "Consensus",
+                format!(
+                    "{} traders within {}h, {} size{}",
+                    c.threshold, c.window_hours, aggregate, exits
+                ),
+            ));
+        }
+        if let Some(r) = &self.rebalance {
+            let policy = match r.policy {
+                RebalancePolicy::AllOrNothing => "all or nothing",
+                RebalancePolicy::AllowPartial => "partial allowed",
+            };
+            lines.push(("Rebalance", format!("{}s window, {}", r.window_secs, policy)));
+        }
+        if let Some(d) = &self.inactivity_decay {
+            let ramp: Vec<String> = d.ramp.iter().map(|m| format!("{:.0}%", m * 100.0)).collect();
+            lines.push((
+                "Inactivity decay",
+                format!("after {}d, ramp {}", d.after_days, ramp.join(" → ")),
+            ));
+        }
+        if let Some(t) = &self.trial {
+            lines.push((
+                "Trial",
+                format!(
+                    "{}d / {} copies at {:.0}%, max ${:.2}",
+                    t.days,
+                    t.copies,
+                    t.size_multiplier * 100.0,
+                    t.max_order_size_usd
+                ),
+            ));
+        }
+        if let Some(c) = &self.concentration {
+            let pct = |share: f64| {
+                if share > 0.0 {
+                    format!("{:.0}%", share * 100.0)
+                } else {
+                    "off".to_string()
+                }
+            };
+            lines.push((
+                "Concentration",
+                format!(
+                    "event {}, category {}, direction {}",
+                    pct(c.max_event_share),
+                    pct(c.max_category_share),
+                    pct(c.max_direction_share)
+                ),
+            ));
+        }
+        if let Some(m) = &self.market_share {
+            let floor = if m.min_open_interest_usd > 0.0 {
+                format!(", skip below ${:.0} OI", m.min_open_interest_usd)
+            } else {
+                String::new()
+            };
+            lines.push((
+                "Market share",
+                format!("max {:.1}% of open interest{}", m.max_share * 100.0, floor),
+            ));
+        }
+        if let Some(b) = &self.position_build {
+            let who = if b.traders.is_empty() {
+                "all traders".to_string()
+            } else {
+                format!("{} traders", b.traders.len())
+            };
+            lines.push((
+                "Position build",
+                format!(
+                    "{}: ${:.2} every {}s, abandon past ±{:.1}% or {}h",
+                    who,
+                    b.slice_usd,
+                    b.interval_secs,
+                    b.price_band * 100.0,
+                    b.max_duration_secs / 3600
+                ),
+            ));
+        }
+        if let Some(c) = &self.catch_up {
+            lines.push((
+                "Catch-up",
+                format!(
+                    "up to ${:.2} of missed BUYs from the last {} min",
+                    c.max_usd,
+                    c.lookback_secs / 60
+                ),
+            ));
+        }
+        if let Some(e) = &self.exits {
+            let pct = |p: Option<f64>, sign: &str| {
+                p.map_or("off".to_string(), |p| format!("{}{:.0}%", sign, p))
+            };
+            let overrides = e.take_profit_by_slug.len() + e.stop_loss_by_slug.len();
+            let overrides = if overrides > 0 {
+                format!(", {} market overrides", overrides)
+            } else {
+                String::new()
+            };
+            lines.push((
+                "Exits",
+                format!(
+                    "take profit {}, stop loss {}{}, every {}s",
+                    pct(e.take_profit_percent, "+"),
+                    pct(e.stop_loss_percent, "-"),
+                    overrides,
+                    e.interval_secs
+                ),
+            ));
+        }
+        if let Some(a) = &self.alerts {
+            let mut channels = Vec::new();
+            if a.telegram_bot_token.is_some() && a.telegram_chat_id.is_some() {
+                channels.push("telegram");
+            }
+            if a.discord_webhook_url.is_some() {
+                channels.push("discord");
+            }
+            let mut summary = channels.join(" + ");
+            if a.notify_min_order_usd > 0.0 {
+                summary.push_str(&format!(", copies from ${:.2}", a.notify_min_order_usd));
+            }
+            lines.push(("Alerts", summary));
+        }
+        if let Some(c) = &self.chaos {
+            lines.push((
+                "Chaos",
+                format!(
+                    "seed {}, failures {:.0}%, no orders placed",
+                    c.seed,
+                    c.request_failure_rate * 100.0
+                ),
+            ));
+        }
+        lines
+    }
+}
+
+fn conflict(
+    severity: Severity,
+    code: &'static str,
+    key: &str,
+    message: impl Into<String>,
+) -> ValidationIssue {
+    ValidationIssue {
+        severity,
+        code,
+        key: key.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Settings that contradict each other. Errors stop the bot from starting; warnings are
+/// logged at startup and by the `validate_config` binary.
+pub fn conflicts(config: &EnvConfig) -> Vec<ValidationIssue> {
+    let features = &config.features;
+    let mut found = Vec::new();
+    if config.pause_on_foreign_activity && config.wallet_watchdog_interval_secs == 0 {
+        found.push(conflict(
+            Severity::Error,
+            "pause_without_watchdog",
+            "PAUSE_ON_FOREIGN_ACTIVITY",
+            "Needs the wallet watchdog (WALLET_WATCHDOG_INTERVAL_SECS > 0) to ever pause",
+        ));
+    }
+    if features.chaos.is_some() && config.journal_remote_url.is_some() {
+        found.push(conflict(
+            Severity::Error,
+            "chaos_remote_journal",
+            "CHAOS_MODE",
+            "Would push fault-injected rows to JOURNAL_REMOTE_URL; unset one of them",
+        ));
+    }
+    if config.replay_rtds_from.is_some() && !config.dry_run {
+        found.push(conflict(
+            Severity::Error,
+            "replay_places_orders",
+            "REPLAY_RTDS_FROM",
+            "Replayed trades would be copied with real orders; needs DRY_RUN=true",
+        ));
+    }
+    if config.replay_rtds_from.is_some() && config.capture_rtds_to.is_some() {
+        found.push(conflict(
+            Severity::Warning,
+            "capture_during_replay",
+            "CAPTURE_RTDS_TO",
+            "Only the live connection is captured; nothing is written during a replay",
+        ));
+    }
+    let restricts_actions = config.copy_position_actions.len() < PositionAction::ALL.len()
+        || !config.copy_position_actions_by_trader.is_empty();
+    if restricts_actions && !config.skip_rules.iter().any(|r| r == "position_action") {
+        found.push(conflict(
+            Severity::Warning,
+            "position_actions_unchecked",
+            "COPY_POSITION_ACTIONS",
+            "The position_action rule is left out of SKIP_RULES, so every action is copied",
+        ));
+    }
+    // A custom list is a whitelist: rules added in later versions are off until listed.
+    let omitted: Vec<&str> = RULE_NAMES
+        .iter()
+        .copied()
+        .filter(|rule| !config.skip_rules.iter().any(|r| r == rule))
+        .collect();
+    if !omitted.is_empty() {
+        found.push(conflict(
+            Severity::Warning,
+            "skip_rules_omitted",
+            "SKIP_RULES",
+            format!("Left out of SKIP_RULES, so never run: {}", omitted.join(", ")),
+        ));
+    }
+    if features.chaos.is_some() && features.alerts.is_some() {
+        found.push(conflict(
+            Severity::Warning,
+            "chaos_alerts",
+            "CHAOS_MODE",
+            "Injected faults will send real alerts to the configured channels",
+        ));
+    }
+    if config.market_prefetch_deadline_ms > 0 && config.order_template_refresh_secs == 0 {
+        found.push(conflict(
+            Severity::Warning,
+            "prefetch_without_templates",
+            "MARKET_PREFETCH_DEADLINE_MS",
+            "Has no effect with ORDER_TEMPLATE_REFRESH_SECS=0",
+        ));
+    }
+    let capped = config
+        .copy_strategy_config
+        .max_position_size_usd
+        .is_some_and(|cap| cap > 0.0);
+    if !capped && (config.overflow_notify_usd > 0.0 || config.overflow_catchup_fraction > 0.0) {
+        found.push(conflict(
+            Severity::Warning,
+            "overflow_without_cap",
+            "MAX_POSITION_SIZE_USD",
+            "OVERFLOW_NOTIFY_USD and OVERFLOW_CATCHUP_FRACTION need a position cap",
+        ));
+    }
+    if let Some(build) = &features.position_build {
+        let known: Vec<String> = config
+            .logical_traders()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let unknown: Vec<&str> = build
+            .traders
+            .iter()
+            .filter(|t| !known.contains(t))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            found.push(conflict(
+                Severity::Warning,
+                "build_unknown_trader",
+                "POSITION_BUILD_TRADERS",
+                format!("Not a tracked trader or group: {}", unknown.join(", ")),
+            ));
+        }
+    }
+    if let Some(catch_up) = &features.catch_up {
+        if catch_up.lookback_secs > config.too_old_timestamp_hours * 3600 {
+            found.push(conflict(
+                Severity::Warning,
+                "catchup_past_stale",
+                "CATCHUP_LOOKBACK_MINUTES",
+                "Reaches past TOO_OLD_TIMESTAMP, so the oldest missed trades are dropped as stale",
+            ));
+        }
+    }
+    if let Some(trial) = &features.trial {
+        if trial.max_order_size_usd < config.copy_strategy_config.min_order_size_usd {
+            found.push(conflict(
+                Severity::Warning,
+                "trial_cap_below_minimum",
+                "TRIAL_MAX_ORDER_SIZE_USD",
+                "Below MIN_ORDER_SIZE_USD, so trial copies are raised to the minimum anyway",
+            ));
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, test_vars};
+
+    /// Overrides on top of `test_vars` and the conflicts they should raise, in order.
+    type Case = (&'static [(&'static str, &'static str)], &'static [(&'static str, Severity)]);
+
+    fn codes(overrides: &[(&str, &str)]) -> Vec<(&'static str, Severity)> {
+        let mut vars = test_vars();
+        for (k, v) in overrides {
+            vars.insert(k.to_string(), v.to_string());
+        }
+        let config = EnvConfig::read_from(&|key| vars.get(key).cloned()).expect("config");
+        conflicts(&config)
+            .into_iter()
+            .map(|c| (c.code, c.severity))
+            .collect()
+    }
+
+    #[test]
+    fn default_config_has_no_conflicts() {
+        assert!(codes(&[]).is_empty());
+    }
+
+    #[test]
+    fn conflict_matrix() {
+        use Severity::{Error, Warning};
+        let cases: &[Case] = &[
+            (
+                &[("PAUSE_ON_FOREIGN_ACTIVITY", "true"), ("WALLET_WATCHDOG_INTERVAL_SECS", "0")],
+                &[("pause_without_watchdog", Error)],
+            ),
+            (&[("PAUSE_ON_FOREIGN_ACTIVITY", "true")], &[]),
+            (
+                &[
+                    ("CHAOS_MODE", "true"),
+                    ("DRY_RUN", "true"),
+                    ("JOURNAL_REMOTE_URL", "https://journal.example"),
+                ],
+                &[("chaos_remote_journal", Error)],
+            ),
+            (
+                &[
+                    ("CHAOS_MODE", "true"),
+                    ("DRY_RUN", "true"),
+                    ("ALERT_DISCORD_WEBHOOK_URL", "https://d.example"),
+                ],
+                &[("chaos_alerts", Warning)],
+            ),
+            (&[("CHAOS_MODE", "true"), ("DRY_RUN", "true")], &[]),
+            (
+                &[("REPLAY_RTDS_FROM", "captures/session.jsonl")],
+                &[("replay_places_orders", Error)],
+            ),
+            (
+                &[
+                    ("REPLAY_RTDS_FROM", "captures/session.jsonl"),
+                    ("CAPTURE_RTDS_TO", "captures/again.jsonl"),
+                    ("DRY_RUN", "true"),
+                ],
+                &[("capture_during_replay", Warning)],
+            ),
+            (
+                &[("ORDER_TEMPLATE_REFRESH_SECS", "0")],
+                &[("prefetch_without_templates", Warning)],
+            ),
+            (
+                &[("ORDER_TEMPLATE_REFRESH_SECS", "0"), ("MARKET_PREFETCH_DEADLINE_MS", "0")],
+                &[],
+            ),
+            (
+                &[("OVERFLOW_NOTIFY_USD", "50"), ("MAX_POSITION_SIZE_USD", "0")],
+                &[("overflow_without_cap", Warning)],
+            ),
+            (
+                &[("OVERFLOW_NOTIFY_USD", "50"), ("MAX_POSITION_SIZE_USD", "200")],
+                &[],
+            ),
+            (
+                &[
+                    ("TRIAL_DAYS", "7"),
+                    ("TRIAL_MAX_ORDER_SIZE_USD", "0.5"),
+                    ("MIN_ORDER_SIZE_USD", "1"),
+                ],
+                &[("trial_cap_below_minimum", Warning)],
+            ),
+            (&[("TRIAL_DAYS", "7"), ("TRIAL_MAX_ORDER_SIZE_USD", "5")], &[]),
+            (
+                &[("POSITION_BUILD_TRADERS", "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b,whales")],
+                &[("build_unknown_trader", Warning)],
+            ),
+            (
+                &[("POSITION_BUILD_TRADERS", "0x7C3DB723F1D4D8CB9C550095203B686CB11E5C6B")],
+                &[],
+            ),
+            (
+                &[
+                    ("CATCHUP_MAX_USD", "100"),
+                    ("CATCHUP_LOOKBACK_MINUTES", "180"),
+                    ("TOO_OLD_TIMESTAMP", "2"),
+                ],
+                &[("catchup_past_stale", Warning)],
+            ),
+            (&[("CATCHUP_MAX_USD", "100"), ("TOO_OLD_TIMESTAMP", "2")], &[]),
+            (
+                &[("SKIP_RULES", "stale,balance")],
+                &[("skip_rules_omitted", Warning)],
+            ),
+            (
+                &[(
+                    "SKIP_RULES",
+                    concat!(
+                        "balance,stale,market_override,market_maker,market_filter,price_band,",
+                        "trading_state,position_action,open_positions,resolution_window,",
+                        "degraded_balance"
+                    ),
+                )],
+                &[],
+            ),
+            (
+                &[
+                    ("CHAOS_MODE", "true"),
+                    ("DRY_RUN", "true"),
+                    ("JOURNAL_REMOTE_URL", "https://journal.example"),
+                    ("ALERT_DISCORD_WEBHOOK_URL", "https://d.example"),
+                ],
+                &[("chaos_remote_journal", Error), ("chaos_alerts", Warning)],
+            ),
+        ];
+        for (overrides, expected) in cases {
+            assert_eq!(codes(overrides), expected.to_vec(), "{:?}", overrides);
+        }
+    }
+
+    #[test]
+    fn errors_stop_parsing_and_warnings_do_not() {
+        assert!(EnvConfig::from_vars(&{
+            let mut vars = test_vars();
+            vars.insert("PAUSE_ON_FOREIGN_ACTIVITY".into(), "true".into());
+            vars.insert("WALLET_WATCHDOG_INTERVAL_SECS".into(), "0".into());
+            vars
+        })
+        .is_err());
+        let config = test_config(&[("ORDER_TEMPLATE_REFRESH_SECS", "0")]);
+        assert!(config.market_prefetch_deadline_ms > 0);
+    }
+
+    #[test]
+    fn position_build_is_chosen_for_all_or_some_traders() {
+        assert!(test_config(&[]).position_build().is_none());
+        let all = test_config(&[
+            ("POSITION_BUILD_MODE", "progressive"),
+            ("BUILD_PRICE_BAND_PERCENT", "2.5"),
+            ("BUILD_MAX_HOURS", "6"),
+        ]);
+        let build = all.position_build().expect("enabled");
+        assert!(build.applies_to("0xanyone"));
+        assert_eq!(build.price_band, 0.025);
+        assert_eq!(build.max_duration_secs, 6 * 3600);
+        assert_eq!((build.interval_secs, build.slice_usd), (60, 10.0));
+
+        let some = test_config(&[("POSITION_BUILD_TRADERS", "Whales, 0xAbC")]);
+        let build = some.position_build().expect("enabled by the trader list");
+        assert!(build.applies_to("whales") && build.applies_to("0xabc"));
+        assert!(!build.applies_to("0xdef"));
+
+        let mut vars = test_vars();
+        vars.insert("POSITION_BUILD_MODE".into(), "SLOW".into());
+        assert!(EnvConfig::from_vars(&vars).is_err());
+    }
+
+    #[test]
+    fn exits_take_defaults_and_slug_overrides() {
+        assert!(test_config(&[]).exits().is_none());
+        let config = test_config(&[
+            ("TAKE_PROFIT_PERCENT", "40"),
+            ("STOP_LOSS_PERCENT", "0"),
+            ("STOP_LOSS_BY_SLUG", "Fed-Cut:25, btc-100k:0"),
+        ]);
+        let exits = config.exits().expect("enabled");
+        assert_eq!(exits.thresholds(Some("fed-cut")), (Some(40.0), Some(25.0)));
+        assert_eq!(exits.thresholds(Some("btc-100k")), (Some(40.0), None));
+        assert_eq!(exits.thresholds(None), (Some(40.0), None));
+        assert_eq!(exits.interval_secs, 60);
+        assert!(test_config(&[("STOP_LOSS_BY_SLUG", "fed-cut:25")])
+            .exits()
+            .is_some());
+
+        for (key, value) in [
+            ("STOP_LOSS_PERCENT", "100"),
+            ("TAKE_PROFIT_BY_SLUG", "fed-cut"),
+            ("TAKE_PROFIT_BY_SLUG", "a:10,A:20"),
+            ("STOP_LOSS_BY_SLUG", "a:-5"),
+        ] {
+            let mut vars = test_vars();
+            vars.insert(key.into(), value.into());
+            assert!(EnvConfig::from_vars(&vars).is_err(), "{}={}", key, value);
+        }
+    }
+
+    #[test]
+    fn summary_lists_enabled_features() {
+        let config = test_config(&[
+            ("TRADE_AGGREGATION_ENABLED", "true"),
+            ("REBALANCE_WINDOW_SECS", "30"),
+            ("TRIAL_COPIES", "10"),
+        ]);
+        let names: Vec<&str> = config.features.summary().iter().map(|(n, _)| *n).collect();
+        assert_eq!(names, vec!["Aggregation", "Rebalance", "Trial"]);
+        assert!(config.trade_aggregation_enabled());
+        assert_eq!(config.trade_aggregation_window_seconds(), 300);
+        assert!(test_config(&[]).features.summary().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Read when `BOT_CONFIG` isn't set.
+pub const DEFAULT_CONFIG_FILE: &str = "bot.toml";
+
+/// Never read from the config file: secrets belong in `.env`, the environment, or a key file.
+const SECRET_KEYS: &[&str] = &["PRIVATE_KEY", "JOURNAL_REMOTE_TOKEN"];
+
+/// `bot.toml` holds the same settings as the environment variables, with lower-case keys
+/// (`copy_size = 10.0` is `COPY_SIZE=10`). Arrays become comma-separated lists.
+/// Returns `(VARIABLE, value)` pairs.
+pub fn read_config_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_config_str(&raw, &path.display().to_string())
+}
+
+/// `read_config_file` on text already in memory; `origin` names it in errors.
+pub fn parse_config_str(raw: &str, origin: &str) -> Result<Vec<(String, String)>> {
+    let table: toml::Table =
+        toml::from_str(raw).with_context(|| format!("Invalid TOML in {}", origin))?;
+    let mut vars = Vec::new();
+    for (key, value) in table {
+        let name = key.to_uppercase();
+        if SECRET_KEYS.contains(&name.as_str()) {
+            anyhow::bail!(
+                "{} must not be set in {}; put it in .env or use PRIVATE_KEY_FILE",
+                key,
+                origin
+            );
+        }
+        let value = match value {
+            toml::Value::String(s) => s,
+            toml::Value::Array(items) => items
+                .iter()
+                .map(scalar)
+                .collect::<Option<Vec<_>>>()
+                .with_context(|| {
+                    format!("{} in {}: arrays may only hold plain values", key, origin)
+                })?
+                .join(","),
+            other => scalar(&other).with_context(|| {
+                format!("{} in {}: expected a plain value", key, origin)
+            })?,
+        };
+        vars.push((name, value));
+    }
+    Ok(vars)
+}
+
+fn scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// `BOT_CONFIG`, or `bot.toml` in the working directory.
+pub fn config_file_path() -> PathBuf {
+    env::var("BOT_CONFIG")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| PathBuf::from(p.trim()))
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE))
+}
+
+/// Loads the config file into the process environment, like `dotenvy::dotenv()`: variables
+/// already set (by the shell or `.env`) win. Call it after `.env` is loaded and before any
+/// task starts. Returns the file loaded, if there was one.
+pub fn load_config_file() -> Result<Option<PathBuf>> {
+    let path = config_file_path();
+    if !path.exists() {
+        if env::var("BOT_CONFIG").is_ok() {
+            anyhow::bail!("BOT_CONFIG points to {}, which does not exist", path.display());
+        }
+        return Ok(None);
+    }
+    for (key, value) in read_config_file(&path)? {
+        if env::var_os(&key).is_none() {
+            env::set_var(key, value);
+        }
+    }
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_map_to_env_names_and_arrays_join() {
+        let vars = parse_config_str(
+            "user_addresses = [\"0xa\", \"0xb\"]\ncopy_size = 10.0\nmax_open_positions = 20\nauto_approve_ctf = true\n",
+            "test",
+        )
+        .unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("AUTO_APPROVE_CTF".to_string(), "true".to_string()),
+                ("COPY_SIZE".to_string(), "10".to_string()),
+                ("MAX_OPEN_POSITIONS".to_string(), "20".to_string()),
+                ("USER_ADDRESSES".to_string(), "0xa,0xb".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn private_key_is_refused() {
+        let err = parse_config_str("private_key = \"abc\"", "test").unwrap_err();
+        assert!(err.to_string().contains("PRIVATE_KEY_FILE"));
+        assert!(!err.to_string().contains("abc"));
+    }
+
+    #[test]
+    fn nested_tables_are_rejected() {
+        assert!(parse_config_str("[copy]\nsize = 1", "test").is_err());
+    }
+}
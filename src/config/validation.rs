@@ -0,0 +1,1030 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use super::{
+    is_valid_ethereum_address, parse_decay_ramp, parse_skip_rules, parse_tiered_multipliers,
+    parse_trader_groups, parse_user_addresses, EnvConfig,
+};
+use crate::utils::data_api::DEFAULT_DATA_API_URL;
+use crate::utils::{get_usdc_balance, DataApiClient};
+
+/// Where `validate_full` reads the configuration from.
+#[derive(Debug, Clone)]
+pub enum ConfigSource {
+    /// The current process environment (load `.env` first, as the bot does).
+    Environment,
+    /// A candidate `.env`-style file, checked on its own without the process environment.
+    EnvFile(PathBuf),
+    /// A candidate `bot.toml`, likewise checked on its own; the key setting, which never
+    /// lives in that file, comes from the environment.
+    ConfigFile(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One finding. `code` is stable for tooling; `key` is the variable it concerns (empty for
+/// findings about the source as a whole).
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// True when the bot would start with this configuration.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+
+    fn error(&mut self, code: &'static str, key: &str, message: impl Into<String>) {
+        self.push(Severity::Error, code, key, message.into());
+    }
+
+    fn warning(&mut self, code: &'static str, key: &str, message: impl Into<String>) {
+        self.push(Severity::Warning, code, key, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, code: &'static str, key: &str, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            code,
+            key: key.to_string(),
+            message,
+        });
+    }
+}
+
+const REQUIRED_KEYS: &[&str] = &[
+    "USER_ADDRESSES",
+    "CLOB_HTTP_URL",
+    "CLOB_WS_URL",
+    "RPC_URL",
+    "USDC_CONTRACT_ADDRESS",
+];
+
+/// Parsed as unsigned integers; the bot silently falls back to the default when they don't.
+const UNSIGNED_KEYS: &[&str] = &[
+    "FETCH_INTERVAL",
+    "RETRY_LIMIT",
+    "REQUEST_TIMEOUT_MS",
+    "NETWORK_RETRY_LIMIT",
+    "TRADE_AGGREGATION_WINDOW_SECONDS",
+    "TRADE_AGGREGATION_MIN_WINDOW_SECONDS",
+    "TRADE_AGGREGATION_MAX_WINDOW_SECONDS",
+    "MAX_OPEN_POSITIONS",
+    "POSITION_RECONCILE_INTERVAL_SECS",
+    "PROCESSED_TRADES_RETENTION_HOURS",
+    "CONSENSUS_THRESHOLD",
+    "DUST_SWEEP_INTERVAL_SECS",
+    "EXIT_CHECK_INTERVAL_SECS",
+    "REDEEM_INTERVAL_SECS",
+    "BUILD_MAX_AGE_DAYS",
+    "ADVISORY_REPEAT_HOURS",
+    "BALANCE_MAX_STALENESS_SECS",
+    "BALANCE_CACHE_TTL_SECS",
+    "TRADER_CLASSIFY_INTERVAL_SECS",
+    "TRADER_PORTFOLIO_REFRESH_SECS",
+    "TRADER_PORTFOLIO_MAX_AGE_SECS",
+    "REBALANCE_WINDOW_SECS",
+    "EMPTY_BOOK_ORDER_TTL_SECS",
+    "RESTING_ORDER_POLL_SECS",
+    "WALLET_WATCHDOG_INTERVAL_SECS",
+    "WALLET_WATCHDOG_GRACE_SECS",
+    "PAUSE_BEFORE_RESOLUTION_MINUTES",
+    "SKIP_MARKETS_ENDING_WITHIN_HOURS",
+    "JOURNAL_BUFFER_ROWS",
+    "CHAOS_SEED",
+    "CHAOS_MAX_LATENCY_MS",
+    "INACTIVITY_DECAY_AFTER_DAYS",
+    "ORDER_TEMPLATE_REFRESH_SECS",
+    "TRIAL_DAYS",
+    "TRIAL_COPIES",
+    "MARKET_PREFETCH_DEADLINE_MS",
+    "CONCENTRATION_CHECK_INTERVAL_SECS",
+    "BUILD_INTERVAL_SECS",
+    "BUILD_MAX_HOURS",
+    "CATCHUP_LOOKBACK_MINUTES",
+    "DAILY_VOLUME_RESET_HOUR_UTC",
+    "MONITOR_FALLBACK_ATTEMPTS",
+    "RTDS_MAX_RECONNECT_ATTEMPTS",
+    "RTDS_MAX_BACKOFF_SECS",
+    "RTDS_PING_INTERVAL_SECS",
+    "RTDS_STALE_SECS",
+];
+
+const INTEGER_KEYS: &[&str] = &["TOO_OLD_TIMESTAMP"];
+
+/// Non-negative decimals.
+const DECIMAL_KEYS: &[&str] = &[
+    "COPY_SIZE",
+    "REQUEST_RATE_LIMIT_PER_SEC",
+    "COPY_PERCENTAGE",
+    "TRADE_MULTIPLIER",
+    "MAX_ORDER_SIZE_USD",
+    "MIN_ORDER_SIZE_USD",
+    "MAX_POSITION_SIZE_USD",
+    "MAX_DAILY_VOLUME_USD",
+    "ADAPTIVE_MIN_PERCENT",
+    "ADAPTIVE_MAX_PERCENT",
+    "ADAPTIVE_THRESHOLD_USD",
+    "CONSENSUS_WINDOW_HOURS",
+    "DUST_THRESHOLD_USD",
+    "TAKE_PROFIT_PERCENT",
+    "STOP_LOSS_PERCENT",
+    "DEGRADED_BALANCE_FRACTION",
+    "DEGRADED_MAX_ORDER_SIZE_USD",
+    "USDC_SIZE_TOLERANCE",
+    "OVERFLOW_NOTIFY_USD",
+    "OVERFLOW_CATCHUP_FRACTION",
+    "TRIAL_SIZE_PERCENT",
+    "TRIAL_MAX_ORDER_SIZE_USD",
+    "PREFETCH_DEGRADED_SIZE_PERCENT",
+    "CONCENTRATION_MAX_EVENT_PERCENT",
+    "CONCENTRATION_MAX_CATEGORY_PERCENT",
+    "CONCENTRATION_MAX_DIRECTION_PERCENT",
+    "MAX_MARKET_SHARE_PERCENT",
+    "MIN_MARKET_OPEN_INTEREST_USD",
+    "BUILD_SLICE_USD",
+    "BUILD_PRICE_BAND_PERCENT",
+    "CATCHUP_MAX_USD",
+    "CHAOS_REQUEST_FAILURE_RATE",
+    "CHAOS_DISCONNECT_RATE",
+    "CHAOS_MALFORMED_RATE",
+    "REPLAY_RTDS_SPEED",
+    "TRADE_AGGREGATION_PERCENTILE",
+    "TRADE_AGGREGATION_FLUSH_USD",
+    "BALANCE_RESERVE_USD",
+    "INTERACTIVE_CONFIRM_USD",
+    "MIN_COPY_PRICE",
+    "MAX_COPY_PRICE",
+    "MAX_SLIPPAGE_PERCENT",
+    "MIN_USDC_ALLOWANCE_USD",
+    "NOTIFY_MIN_ORDER_USD",
+];
+
+const BOOL_KEYS: &[&str] = &[
+    "TRADE_AGGREGATION_ENABLED",
+    "TRADE_AGGREGATION_ADAPTIVE",
+    "JOURNAL_MARKET_CONTEXT",
+    "JOURNAL_ROTATE_DAILY",
+    "SKIP_MARKET_MAKER_FILLS",
+    "CHAOS_MODE",
+    "PAUSE_ON_FOREIGN_ACTIVITY",
+    "FOLLOW_TRADER_CANCELS",
+    "REQUIRE_TRADER_HISTORY",
+    "CONSENSUS_EXIT_REQUIRES_CONSENSUS",
+    "AUTO_APPROVE_CTF",
+    "AUTO_REDEEM",
+    "ALLOW_UNAPPROVED",
+    "INTERACTIVE",
+    "PRICE_BAND_INCLUDES_SELLS",
+];
+
+const OTHER_KEYS: &[&str] = &[
+    "PRIVATE_KEY",
+    "PRIVATE_KEY_FILE",
+    "COPY_STRATEGY",
+    "ADAPTIVE_TAIL",
+    "TIERED_MULTIPLIERS",
+    "CONSENSUS_SIZE_AGGREGATE",
+    "REBALANCE_POLICY",
+    "EMPTY_BOOK_POLICY",
+    "SLIPPAGE_ACTION",
+    "LOG_FORMAT",
+    "NO_COLOR",
+    "USDC_SIZE_PREFERENCE",
+    "FORCE_DIRECTIONAL_TRADERS",
+    "TRADER_GROUPS",
+    "POSITION_BUILD_MODE",
+    "POSITION_BUILD_TRADERS",
+    "TAKE_PROFIT_BY_SLUG",
+    "STOP_LOSS_BY_SLUG",
+    "SKIP_RULES",
+    "MARKET_BLACKLIST",
+    "MARKET_WHITELIST",
+    "STATE_DIR",
+    "STATE_FILE",
+    "PROXY_WALLET",
+    "TRADE_LOG_PATH",
+    "JOURNAL_REMOTE_URL",
+    "JOURNAL_REMOTE_TOKEN",
+    "JOURNAL_INSTANCE_ID",
+    "EVENT_WEBHOOK_URL",
+    "EVENT_WEBHOOK_SECRET",
+    "STATUS_API_ADDR",
+    "STATUS_API_TOKEN",
+    "JOURNAL_CSV_PATH",
+    "SHADOW_CONFIG_FILE",
+    "INACTIVITY_DECAY_RAMP",
+    "ALERT_TELEGRAM_BOT_TOKEN",
+    "ALERT_TELEGRAM_CHAT_ID",
+    "ALERT_DISCORD_WEBHOOK_URL",
+    "TELEGRAM_BOT_TOKEN",
+    "TELEGRAM_CHAT_ID",
+    "DISCORD_WEBHOOK_URL",
+    "CAPTURE_RTDS_TO",
+    "REPLAY_RTDS_FROM",
+    "MONITOR_MODE",
+    "DATA_API_URL",
+    "GAMMA_API_URL",
+    "RTDS_URL",
+    "RTDS_SUBSCRIPTION_TEMPLATE",
+    "BOT_CONFIG",
+];
+
+const CHOICE_KEYS: &[(&str, &[&str])] = &[
+    ("COPY_STRATEGY", &["PERCENTAGE", "FIXED", "ADAPTIVE", "PORTFOLIO_SHARE"]),
+    ("ADAPTIVE_TAIL", &["PLATEAU", "CONTINUE"]),
+    ("CONSENSUS_SIZE_AGGREGATE", &["AVERAGE", "AVG", "MAX"]),
+    (
+        "REBALANCE_POLICY",
+        &["ALL_OR_NOTHING", "ALLOW_PARTIAL", "PARTIAL"],
+    ),
+    ("EMPTY_BOOK_POLICY", &["SKIP", "LIMIT"]),
+    ("SLIPPAGE_ACTION", &["SKIP", "SHRINK"]),
+    ("LOG_FORMAT", &["HUMAN", "JSON"]),
+    ("USDC_SIZE_PREFERENCE", &["CONSERVATIVE", "REPORTED", "DERIVED"]),
+    ("SIZING_BALANCE", &["AVAILABLE", "WALLET"]),
+    ("POSITION_BUILD_MODE", &["IMMEDIATE", "PROGRESSIVE"]),
+    ("MONITOR_MODE", &["AUTO", "WEBSOCKET", "POLLING"]),
+];
+
+const FRACTION_KEYS: &[&str] = &[
+    "MIN_COPY_PRICE",
+    "MAX_COPY_PRICE",
+    "DEGRADED_BALANCE_FRACTION",
+    "USDC_SIZE_TOLERANCE",
+    "OVERFLOW_CATCHUP_FRACTION",
+    "CHAOS_REQUEST_FAILURE_RATE",
+    "CHAOS_DISCONNECT_RATE",
+    "CHAOS_MALFORMED_RATE",
+];
+
+const URL_KEYS: &[(&str, &[&str])] = &[
+    ("CLOB_HTTP_URL", &["http://", "https://"]),
+    ("CLOB_WS_URL", &["ws://", "wss://"]),
+    ("RPC_URL", &["http://", "https://", "ws://", "wss://"]),
+    ("JOURNAL_REMOTE_URL", &["http://", "https://"]),
+    ("DATA_API_URL", &["http://", "https://"]),
+    ("GAMMA_API_URL", &["http://", "https://"]),
+    ("RTDS_URL", &["ws://", "wss://"]),
+];
+
+/// Every setting `EnvConfig::parse` reads.
+fn known_keys() -> Vec<&'static str> {
+    [
+        REQUIRED_KEYS,
+        UNSIGNED_KEYS,
+        INTEGER_KEYS,
+        DECIMAL_KEYS,
+        BOOL_KEYS,
+        OTHER_KEYS,
+    ]
+    .concat()
+}
+
+type Vars = HashMap<String, String>;
+
+fn get<'a>(vars: &'a Vars, key: &str) -> Option<&'a str> {
+    vars.get(key).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+fn decimal(vars: &Vars, key: &str, default: f64) -> f64 {
+    vars.get(key)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Runs every check the bot makes at startup without stopping at the first failure, plus
+/// checks for settings the bot would silently ignore. `online` adds network checks: each
+/// trader resolves on the data API and the RPC answers a balance query.
+///
+/// The final pass reads the configuration as `EnvConfig::parse` does and reports conflicting
+/// settings (`conflicts`).
+pub async fn validate_full(source: &ConfigSource, online: bool) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let vars: Vars = match source {
+        ConfigSource::Environment => env::vars().collect(),
+        ConfigSource::EnvFile(path) => {
+            let lines = dotenvy::from_path_iter(path)
+                .and_then(|iter| iter.collect::<std::result::Result<Vars, _>>());
+            match lines {
+                Ok(vars) => vars,
+                Err(e) => {
+                    report.error(
+                        "source_unreadable",
+                        "",
+                        format!("Failed to read {}: {}", path.display(), e),
+                    );
+                    return report;
+                }
+            }
+        }
+        ConfigSource::ConfigFile(path) => match super::read_config_file(path) {
+            Ok(vars) => {
+                let mut vars: Vars = vars.into_iter().collect();
+                for key in ["PRIVATE_KEY", "PRIVATE_KEY_FILE"] {
+                    if let Ok(v) = env::var(key) {
+                        vars.insert(key.to_string(), v);
+                    }
+                }
+                vars
+            }
+            Err(e) => {
+                report.error("source_unreadable", "", format!("{:#}", e));
+                return report;
+            }
+        },
+    };
+    if !matches!(source, ConfigSource::Environment) {
+        let known = known_keys();
+        let mut unknown: Vec<&String> = vars
+            .keys()
+            .filter(|k| !known.contains(&k.as_str()))
+            .collect();
+        unknown.sort();
+        for key in unknown {
+            report.warning("unknown_key", key, "Not a setting the bot reads");
+        }
+    }
+
+    check_offline(&vars, &mut report);
+    if online && report.is_valid() {
+        check_online(&vars, &mut report).await;
+    }
+    report
+}
+
+/// The checks that need nothing but the variables themselves.
+fn check_offline(vars: &Vars, report: &mut ValidationReport) {
+    check_required(vars, report);
+    check_numbers(vars, report);
+    check_choices(vars, report);
+    check_urls(vars, report);
+    check_private_key(vars, report);
+    let traders = check_traders(vars, report);
+    check_sizing(vars, report);
+    check_consensus(vars, traders, report);
+    check_features(vars, report);
+
+    if report.is_valid() {
+        match EnvConfig::read_from(&|key| vars.get(key).cloned()) {
+            Ok(config) => report.issues.extend(super::conflicts(&config)),
+            Err(e) => report.error("config_rejected", "", format!("{:#}", e)),
+        }
+    }
+}
+
+fn check_required(vars: &Vars, report: &mut ValidationReport) {
+    for key in REQUIRED_KEYS {
+        if get(vars, key).is_none() {
+            report.error("missing_required", key, format!("{} is required", key));
+        }
+    }
+    for key in ["PROXY_WALLET", "USDC_CONTRACT_ADDRESS"] {
+        if let Some(v) = get(vars, key) {
+            if !is_valid_ethereum_address(v) {
+                report.error(
+                    "invalid_address",
+                    key,
+                    format!("{} is not a 0x-prefixed 40-hex-digit address", v),
+                );
+            }
+        }
+    }
+}
+
+fn check_numbers(vars: &Vars, report: &mut ValidationReport) {
+    let unparsed = |report: &mut ValidationReport, key: &str, v: &str, kind: &str| {
+        report.error(
+            "invalid_number",
+            key,
+            format!(
+                "{:?} is not {}; the bot would silently use the default",
+                v, kind
+            ),
+        );
+    };
+    for key in UNSIGNED_KEYS {
+        if let Some(v) = vars.get(*key) {
+            if v.parse::<u64>().is_err() {
+                unparsed(report, key, v, "a non-negative integer");
+            }
+        }
+    }
+    for key in INTEGER_KEYS {
+        if let Some(v) = vars.get(*key) {
+            if v.parse::<i64>().is_err() {
+                unparsed(report, key, v, "an integer");
+            }
+        }
+    }
+    for key in DECIMAL_KEYS {
+        let Some(v) = vars.get(*key) else {
+            continue;
+        };
+        match v.parse::<f64>() {
+            Ok(n) if !n.is_finite() => unparsed(report, key, v, "a finite number"),
+            Ok(n) if n < 0.0 => {
+                report.error("negative_value", key, format!("{} must not be negative", n))
+            }
+            Ok(n) if FRACTION_KEYS.contains(key) && n > 1.0 => {
+                if key.starts_with("CHAOS_") {
+                    report.warning(
+                        "out_of_range",
+                        key,
+                        format!("{} is a probability; the bot clamps it to 1", n),
+                    );
+                } else {
+                    report.error(
+                        "out_of_range",
+                        key,
+                        format!("{} must be between 0 and 1", n),
+                    );
+                }
+            }
+            Ok(_) => {}
+            Err(_) => unparsed(report, key, v, "a number"),
+        }
+    }
+    for key in BOOL_KEYS {
+        if let Some(v) = get(vars, key) {
+            let known = ["true", "false", "1", "0"]
+                .iter()
+                .any(|b| v.eq_ignore_ascii_case(b));
+            if !known {
+                report.warning(
+                    "invalid_bool",
+                    key,
+                    format!("{:?} is read as false; use true or false", v),
+                );
+            }
+        }
+    }
+}
+
+fn check_choices(vars: &Vars, report: &mut ValidationReport) {
+    for (key, allowed) in CHOICE_KEYS {
+        if let Some(v) = get(vars, key) {
+            if !allowed.contains(&v.to_uppercase().as_str()) {
+                report.error(
+                    "invalid_choice",
+                    key,
+                    format!("{} is not one of {}", v, allowed.join(", ")),
+                );
+            }
+        }
+    }
+    if let Some(raw) = get(vars, "SKIP_RULES") {
+        if let Err(e) = parse_skip_rules(raw) {
+            report.error("invalid_skip_rule", "SKIP_RULES", e.to_string());
+        }
+    }
+    if let Some(raw) = vars.get("TIERED_MULTIPLIERS") {
+        if let Err(e) = parse_tiered_multipliers(raw) {
+            report.error("invalid_tiers", "TIERED_MULTIPLIERS", e.to_string());
+        }
+    }
+    if let Some(raw) = get(vars, "INACTIVITY_DECAY_RAMP") {
+        if let Err(e) = parse_decay_ramp(raw) {
+            report.error("invalid_decay_ramp", "INACTIVITY_DECAY_RAMP", e.to_string());
+        }
+    }
+}
+
+fn check_urls(vars: &Vars, report: &mut ValidationReport) {
+    for (key, schemes) in URL_KEYS {
+        let Some(v) = get(vars, key) else {
+            continue;
+        };
+        // RPC_URL may list failover endpoints.
+        let urls: Vec<&str> = if *key == "RPC_URL" {
+            v.split(',').map(str::trim).collect()
+        } else {
+            vec![v]
+        };
+        for url in urls {
+            if !schemes.iter().any(|s| url.starts_with(s)) {
+                report.error(
+                    "invalid_url",
+                    key,
+                    format!("{} must start with {}", url, schemes.join(" or ")),
+                );
+            }
+        }
+    }
+}
+
+fn check_private_key(vars: &Vars, report: &mut ValidationReport) {
+    let (key, raw) = match (get(vars, "PRIVATE_KEY"), get(vars, "PRIVATE_KEY_FILE")) {
+        (Some(v), _) => ("PRIVATE_KEY", v.to_string()),
+        (None, Some(path)) => match std::fs::read_to_string(path) {
+            Ok(v) => ("PRIVATE_KEY_FILE", v),
+            Err(e) => {
+                report.error(
+                    "private_key_file_unreadable",
+                    "PRIVATE_KEY_FILE",
+                    format!("Failed to read {}: {}", path, e),
+                );
+                return;
+            }
+        },
+        (None, None) => {
+            report.error(
+                "missing_private_key",
+                "PRIVATE_KEY",
+                "Set PRIVATE_KEY or PRIVATE_KEY_FILE",
+            );
+            return;
+        }
+    };
+    let hex = raw.trim().trim_start_matches("0x");
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        report.error(
+            "invalid_private_key",
+            key,
+            "Must be 64 hex characters (optionally 0x-prefixed)",
+        );
+    }
+}
+
+/// Checks the trader lists and returns the logical trader count when they parse.
+fn check_traders(vars: &Vars, report: &mut ValidationReport) -> Option<usize> {
+    let users = match get(vars, "USER_ADDRESSES").map(parse_user_addresses) {
+        Some(Ok(users)) => Some(users),
+        Some(Err(e)) => {
+            report.error(
+                "invalid_user_addresses",
+                "USER_ADDRESSES",
+                format!("{:#}", e),
+            );
+            None
+        }
+        None => None,
+    };
+    let groups = match parse_trader_groups(get(vars, "TRADER_GROUPS").unwrap_or("")) {
+        Ok(groups) => Some(groups),
+        Err(e) => {
+            report.error("invalid_trader_groups", "TRADER_GROUPS", format!("{:#}", e));
+            None
+        }
+    };
+    if let Some(raw) = get(vars, "FORCE_DIRECTIONAL_TRADERS") {
+        if let Err(e) = parse_user_addresses(raw) {
+            report.error(
+                "invalid_address",
+                "FORCE_DIRECTIONAL_TRADERS",
+                format!("{:#}", e),
+            );
+        }
+    }
+    let (users, groups) = (users?, groups?);
+
+    let mut all = users;
+    for member in groups.iter().flat_map(|g| &g.members) {
+        if !all.contains(member) {
+            all.push(member.clone());
+        }
+    }
+    if all.is_empty() {
+        report.error(
+            "no_traders",
+            "USER_ADDRESSES",
+            "At least one trader address is required",
+        );
+        return None;
+    }
+    if let Some(proxy) = get(vars, "PROXY_WALLET") {
+        if all.contains(&proxy.to_lowercase()) {
+            report.error(
+                "self_copy",
+                "USER_ADDRESSES",
+                "PROXY_WALLET is listed as a trader; the bot would copy its own orders",
+            );
+        }
+    }
+    Some(
+        all.iter()
+            .filter(|a| !groups.iter().any(|g| g.members.contains(a)))
+            .count()
+            + groups.len(),
+    )
+}
+
+fn check_sizing(vars: &Vars, report: &mut ValidationReport) {
+    let max_order = decimal(vars, "MAX_ORDER_SIZE_USD", 100.0);
+    let min_order = decimal(vars, "MIN_ORDER_SIZE_USD", 1.0);
+    if min_order > max_order {
+        report.error(
+            "min_order_above_max",
+            "MIN_ORDER_SIZE_USD",
+            format!(
+                "Minimum order ${:.2} is above the maximum ${:.2}; every BUY would be skipped",
+                min_order, max_order
+            ),
+        );
+    }
+    if let Some(cap) = vars
+        .get("MAX_POSITION_SIZE_USD")
+        .and_then(|v| v.parse::<f64>().ok())
+    {
+        if cap < min_order {
+            report.warning(
+                "position_cap_below_min_order",
+                "MAX_POSITION_SIZE_USD",
+                format!(
+                    "Position cap ${:.2} is below the minimum order ${:.2}",
+                    cap, min_order
+                ),
+            );
+        }
+    }
+    let (min_price, max_price) = (
+        decimal(vars, "MIN_COPY_PRICE", 0.0),
+        decimal(vars, "MAX_COPY_PRICE", 1.0),
+    );
+    if min_price >= max_price {
+        report.error(
+            "copy_price_band_empty",
+            "MIN_COPY_PRICE",
+            format!(
+                "{} is not below MAX_COPY_PRICE {}; every BUY would be skipped",
+                min_price, max_price
+            ),
+        );
+    }
+    let strategy = get(vars, "COPY_STRATEGY").map(str::to_uppercase);
+    if strategy.is_none() && vars.contains_key("COPY_PERCENTAGE") {
+        report.warning(
+            "legacy_copy_percentage",
+            "COPY_PERCENTAGE",
+            "Legacy setting; use COPY_STRATEGY=PERCENTAGE with COPY_SIZE",
+        );
+    }
+    if strategy.as_deref() == Some("ADAPTIVE") {
+        let copy_size = decimal(vars, "COPY_SIZE", 10.0);
+        let min = decimal(vars, "ADAPTIVE_MIN_PERCENT", copy_size);
+        let max = decimal(vars, "ADAPTIVE_MAX_PERCENT", copy_size);
+        if min > max {
+            report.error(
+                "adaptive_range_inverted",
+                "ADAPTIVE_MIN_PERCENT",
+                format!("{}% is above ADAPTIVE_MAX_PERCENT {}%", min, max),
+            );
+        }
+    }
+}
+
+fn check_consensus(vars: &Vars, traders: Option<usize>, report: &mut ValidationReport) {
+    let Some(threshold) = vars
+        .get("CONSENSUS_THRESHOLD")
+        .and_then(|v| v.trim().parse::<usize>().ok())
+    else {
+        return;
+    };
+    if threshold == 1 {
+        report.warning(
+            "consensus_disabled",
+            "CONSENSUS_THRESHOLD",
+            "Thresholds below 2 turn consensus off",
+        );
+    }
+    if let Some(traders) = traders {
+        if threshold >= 2 && threshold > traders {
+            report.error(
+                "consensus_threshold_too_high",
+                "CONSENSUS_THRESHOLD",
+                format!("{} is more than the {} tracked traders", threshold, traders),
+            );
+        }
+    }
+}
+
+fn check_features(vars: &Vars, report: &mut ValidationReport) {
+    let enabled = |key: &str| {
+        get(vars, key)
+            .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
+            .unwrap_or(false)
+    };
+    if enabled("CHAOS_MODE") {
+        report.warning(
+            "chaos_mode_enabled",
+            "CHAOS_MODE",
+            "Fault injection is on and no orders will be placed",
+        );
+        if !enabled("DRY_RUN") {
+            report.error(
+                "chaos_without_dry_run",
+                "CHAOS_MODE",
+                "CHAOS_MODE is only allowed together with DRY_RUN=true",
+            );
+        }
+    }
+    if let Some(path) = get(vars, "SHADOW_CONFIG_FILE") {
+        let lines = dotenvy::from_path_iter(path)
+            .and_then(|iter| iter.collect::<std::result::Result<Vec<(String, String)>, _>>());
+        if let Err(e) = lines {
+            report.error(
+                "shadow_file_unreadable",
+                "SHADOW_CONFIG_FILE",
+                format!("Failed to read {}: {}", path, e),
+            );
+        }
+    }
+    if enabled("TRADE_AGGREGATION_ADAPTIVE") && !enabled("TRADE_AGGREGATION_ENABLED") {
+        report.warning(
+            "unused_setting",
+            "TRADE_AGGREGATION_ADAPTIVE",
+            "Has no effect without TRADE_AGGREGATION_ENABLED=true",
+        );
+    }
+    if get(vars, "INTERACTIVE_CONFIRM_USD").is_some() && !enabled("INTERACTIVE") {
+        report.warning(
+            "unused_setting",
+            "INTERACTIVE_CONFIRM_USD",
+            "Has no effect without INTERACTIVE=true",
+        );
+    }
+    if get(vars, "JOURNAL_REMOTE_TOKEN").is_some() && get(vars, "JOURNAL_REMOTE_URL").is_none() {
+        report.warning(
+            "unused_setting",
+            "JOURNAL_REMOTE_TOKEN",
+            "Has no effect without JOURNAL_REMOTE_URL",
+        );
+    }
+    if get(vars, "STATUS_API_TOKEN").is_some() && get(vars, "STATUS_API_ADDR").is_none() {
+        report.warning(
+            "unused_setting",
+            "STATUS_API_TOKEN",
+            "Has no effect without STATUS_API_ADDR",
+        );
+    }
+    if let Some(addr) = get(vars, "STATUS_API_ADDR") {
+        if addr.parse::<std::net::SocketAddr>().is_err() {
+            report.error(
+                "invalid_address",
+                "STATUS_API_ADDR",
+                format!("{} is not a host:port address such as 0.0.0.0:8788", addr),
+            );
+        }
+        if get(vars, "STATUS_API_TOKEN").is_none() {
+            report.error(
+                "status_api_without_token",
+                "STATUS_API_ADDR",
+                "STATUS_API_TOKEN is required: the API can pause trading",
+            );
+        }
+    }
+    let exit_keys = [
+        "TAKE_PROFIT_PERCENT",
+        "STOP_LOSS_PERCENT",
+        "TAKE_PROFIT_BY_SLUG",
+        "STOP_LOSS_BY_SLUG",
+    ];
+    if get(vars, "EXIT_CHECK_INTERVAL_SECS").is_some()
+        && exit_keys.iter().all(|k| get(vars, k).is_none())
+    {
+        report.warning(
+            "unused_setting",
+            "EXIT_CHECK_INTERVAL_SECS",
+            "Has no effect without TAKE_PROFIT_PERCENT or STOP_LOSS_PERCENT",
+        );
+    }
+    if get(vars, "EVENT_WEBHOOK_SECRET").is_some() && get(vars, "EVENT_WEBHOOK_URL").is_none() {
+        report.warning(
+            "unused_setting",
+            "EVENT_WEBHOOK_SECRET",
+            "Has no effect without EVENT_WEBHOOK_URL",
+        );
+    }
+}
+
+async fn check_online(vars: &Vars, report: &mut ValidationReport) {
+    let timeout_ms = vars
+        .get("REQUEST_TIMEOUT_MS")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000);
+    let rpc_url = get(vars, "RPC_URL").unwrap_or_default();
+    let usdc = get(vars, "USDC_CONTRACT_ADDRESS").unwrap_or_default();
+    // Derived at startup when unset; any address shows whether the RPC answers.
+    let proxy = get(vars, "PROXY_WALLET").unwrap_or("0x0000000000000000000000000000000000000000");
+    if let Err(e) = get_usdc_balance(rpc_url, usdc, proxy).await {
+        report.error(
+            "rpc_unreachable",
+            "RPC_URL",
+            format!("Balance query failed: {:#}", e),
+        );
+    }
+
+    let mut addresses = get(vars, "USER_ADDRESSES")
+        .and_then(|v| parse_user_addresses(v).ok())
+        .unwrap_or_default();
+    let groups = parse_trader_groups(get(vars, "TRADER_GROUPS").unwrap_or("")).unwrap_or_default();
+    for member in groups.into_iter().flat_map(|g| g.members) {
+        if !addresses.contains(&member) {
+            addresses.push(member);
+        }
+    }
+    let data_api = DataApiClient::new(
+        reqwest::Client::new(),
+        get(vars, "DATA_API_URL").unwrap_or(DEFAULT_DATA_API_URL),
+        timeout_ms,
+        1,
+    );
+    for address in addresses {
+        match data_api.has_activity(&address).await {
+            Err(e) => report.error(
+                "trader_unresolvable",
+                "USER_ADDRESSES",
+                format!("{}: {:#}", address, e),
+            ),
+            Ok(false) => report.warning(
+                "trader_no_activity",
+                "USER_ADDRESSES",
+                format!("{} has no Polymarket activity; check the address", address),
+            ),
+            Ok(true) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_vars;
+
+    const PROXY: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+
+    /// Expected code and severity, and the variables to change from `test_vars`.
+    type Case = (&'static str, Severity, &'static [(&'static str, &'static str)]);
+
+    /// The base fixture with `set` applied (an empty value removes the key).
+    fn report_for(set: &[(&str, &str)]) -> ValidationReport {
+        let mut vars = test_vars();
+        for (k, v) in set {
+            if v.is_empty() {
+                vars.remove(*k);
+            } else {
+                vars.insert(k.to_string(), v.to_string());
+            }
+        }
+        let mut report = ValidationReport::default();
+        check_offline(&vars, &mut report);
+        report
+    }
+
+    #[test]
+    fn base_fixture_is_clean() {
+        let report = report_for(&[]);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn each_fixture_raises_its_code() {
+        let cases: &[Case] = &[
+            ("missing_required", Severity::Error, &[("RPC_URL", "")]),
+            ("invalid_address", Severity::Error, &[("PROXY_WALLET", "0x123")]),
+            ("invalid_number", Severity::Error, &[("FETCH_INTERVAL", "soon")]),
+            ("negative_value", Severity::Error, &[("COPY_SIZE", "-1")]),
+            ("out_of_range", Severity::Error, &[("DEGRADED_BALANCE_FRACTION", "1.5")]),
+            ("out_of_range", Severity::Warning, &[("CHAOS_DISCONNECT_RATE", "2")]),
+            ("invalid_bool", Severity::Warning, &[("AUTO_APPROVE_CTF", "yes")]),
+            ("invalid_choice", Severity::Error, &[("COPY_STRATEGY", "MEDIAN")]),
+            ("invalid_skip_rule", Severity::Error, &[("SKIP_RULES", "stale,nope")]),
+            ("invalid_tiers", Severity::Error, &[("TIERED_MULTIPLIERS", "1-10")]),
+            ("invalid_decay_ramp", Severity::Error, &[("INACTIVITY_DECAY_RAMP", "50,150")]),
+            ("invalid_url", Severity::Error, &[("CLOB_WS_URL", "https://example.com")]),
+            (
+                "invalid_url",
+                Severity::Error,
+                &[("RPC_URL", "https://polygon-rpc.com,polygon.llamarpc.com")],
+            ),
+            (
+                "private_key_file_unreadable",
+                Severity::Error,
+                &[("PRIVATE_KEY", ""), ("PRIVATE_KEY_FILE", "/nonexistent/key")],
+            ),
+            ("missing_private_key", Severity::Error, &[("PRIVATE_KEY", "")]),
+            ("invalid_private_key", Severity::Error, &[("PRIVATE_KEY", "abc")]),
+            ("invalid_user_addresses", Severity::Error, &[("USER_ADDRESSES", "0xnope")]),
+            ("invalid_trader_groups", Severity::Error, &[("TRADER_GROUPS", "whales")]),
+            ("no_traders", Severity::Error, &[("USER_ADDRESSES", ",")]),
+            ("self_copy", Severity::Error, &[("USER_ADDRESSES", PROXY)]),
+            ("min_order_above_max", Severity::Error, &[("MIN_ORDER_SIZE_USD", "200")]),
+            (
+                "position_cap_below_min_order",
+                Severity::Warning,
+                &[("MAX_POSITION_SIZE_USD", "0.5")],
+            ),
+            ("legacy_copy_percentage", Severity::Warning, &[("COPY_PERCENTAGE", "10")]),
+            (
+                "adaptive_range_inverted",
+                Severity::Error,
+                &[
+                    ("COPY_STRATEGY", "ADAPTIVE"),
+                    ("ADAPTIVE_MIN_PERCENT", "50"),
+                    ("ADAPTIVE_MAX_PERCENT", "10"),
+                ],
+            ),
+            (
+                "copy_price_band_empty",
+                Severity::Error,
+                &[("MIN_COPY_PRICE", "0.9"), ("MAX_COPY_PRICE", "0.1")],
+            ),
+            ("consensus_disabled", Severity::Warning, &[("CONSENSUS_THRESHOLD", "1")]),
+            (
+                "consensus_threshold_too_high",
+                Severity::Error,
+                &[("CONSENSUS_THRESHOLD", "3")],
+            ),
+            (
+                "chaos_mode_enabled",
+                Severity::Warning,
+                &[("CHAOS_MODE", "true"), ("DRY_RUN", "true")],
+            ),
+            ("chaos_without_dry_run", Severity::Error, &[("CHAOS_MODE", "true")]),
+            (
+                "shadow_file_unreadable",
+                Severity::Error,
+                &[("SHADOW_CONFIG_FILE", "/nonexistent/shadow.env")],
+            ),
+            ("unused_setting", Severity::Warning, &[("JOURNAL_REMOTE_TOKEN", "t")]),
+            (
+                "status_api_without_token",
+                Severity::Error,
+                &[("STATUS_API_ADDR", "0.0.0.0:8788")],
+            ),
+        ];
+        for (code, severity, set) in cases {
+            let report = report_for(set);
+            assert!(
+                report
+                    .issues
+                    .iter()
+                    .any(|i| i.code == *code && i.severity == *severity),
+                "{:?} did not raise {:?} {}: {:?}",
+                set,
+                severity,
+                code,
+                report.issues
+            );
+        }
+    }
+
+    #[test]
+    fn warnings_alone_leave_the_config_valid() {
+        let report = report_for(&[
+            ("CHAOS_MODE", "true"),
+            ("DRY_RUN", "true"),
+            ("CONSENSUS_THRESHOLD", "1"),
+        ]);
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.warnings().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn env_file_flags_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("candidate.env");
+        let mut lines: Vec<String> = test_vars().iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        lines.push("COPY_SIZ=5".to_string());
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let report = validate_full(&ConfigSource::EnvFile(path), false).await;
+        assert!(report.is_valid(), "{:?}", report.issues);
+        let unknown: Vec<_> = report.warnings().filter(|i| i.code == "unknown_key").collect();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].key, "COPY_SIZ");
+    }
+
+    #[tokio::test]
+    async fn unreadable_source_is_reported() {
+        let source = ConfigSource::EnvFile(PathBuf::from("/nonexistent/candidate.env"));
+        let report = validate_full(&source, false).await;
+        assert_eq!(report.errors().next().map(|i| i.code), Some("source_unreadable"));
+    }
+}
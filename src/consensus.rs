@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::{ConsensusAggregate, ConsensusConfig};
+use crate::types::UserActivity;
+use crate::utils::{load_json, save_json, Logger};
+
+const CONSENSUS_FILE: &str = "consensus.json";
+
+pub type SharedConsensus = Arc<Mutex<ConsensusBook>>;
+
+/// One tracked trader's BUY of an outcome, with the size we would have copied on its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterestSignal {
+    pub at: i64,
+    pub copy_usd: f64,
+}
+
+/// Interest in one outcome token (condition id + outcome) while waiting for, or after, consensus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interest {
+    pub asset: String,
+    pub condition_id: String,
+    pub title: Option<String>,
+    pub outcome: Option<String>,
+    pub signals: HashMap<String, InterestSignal>,
+    /// Set once the consensus copy went out; holds the traders that agreed at that moment.
+    pub contributors: Vec<String>,
+    pub exits: HashSet<String>,
+}
+
+impl Interest {
+    pub fn entered(&self) -> bool {
+        !self.contributors.is_empty()
+    }
+}
+
+pub enum BuyDecision {
+    /// Not enough traders agree yet; the signal was registered.
+    Watch { agreeing: usize },
+    /// Consensus reached: place one order of this size on behalf of `contributors`.
+    Copy { size_usd: f64, contributors: Vec<String> },
+    /// Consensus already copied for this outcome; further buys are not copied again.
+    AlreadyEntered,
+}
+
+pub enum SellDecision {
+    /// Not a consensus position; handle the sell as usual.
+    PassThrough,
+    /// Copy this trader's exit the usual way (proportional to what they sold).
+    Follow,
+    /// Enough contributors exited: close the whole position.
+    CloseAll,
+    Skip(String),
+}
+
+/// Pending and entered consensus interests, persisted so a restart keeps watching.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConsensusBook {
+    interests: HashMap<String, Interest>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ConsensusBook {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, CONSENSUS_FILE);
+        let mut book: ConsensusBook = load_json(&path).unwrap_or_default();
+        book.path = Some(path);
+        book
+    }
+
+    /// Outcomes still waiting for enough traders to agree.
+    pub fn watching(&self) -> impl Iterator<Item = &Interest> {
+        self.interests.values().filter(|i| !i.entered())
+    }
+
+    pub fn interest(&self, asset: &str) -> Option<&Interest> {
+        self.interests.get(asset)
+    }
+
+    /// Tracked traders with a live (in-window) BUY signal for the asset.
+    pub fn signalled_traders(&self, asset: &str) -> HashSet<String> {
+        self.interests
+            .get(asset)
+            .map(|i| i.signals.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Registers `trade`, a BUY of `asset`, and decides whether consensus is reached.
+    /// `holders` are other tracked traders whose current positions already hold the outcome;
+    /// they count towards the threshold but, having no live signal, don't contribute a size.
+    pub fn register_buy(
+        &mut self,
+        config: &ConsensusConfig,
+        asset: &str,
+        trade: &UserActivity,
+        trader: &str,
+        copy_usd: f64,
+        holders: &[String],
+    ) -> BuyDecision {
+        let now = chrono::Utc::now().timestamp();
+        let window_secs = (config.window_hours * 3600.0) as i64;
+        let trader = trader.to_lowercase();
+        let interest = self
+            .interests
+            .entry(asset.to_string())
+            .or_insert_with(|| Interest {
+                asset: asset.to_string(),
+                condition_id: trade.condition_id.clone().unwrap_or_default(),
+                title: trade.title.clone(),
+                outcome: trade.outcome.clone(),
+                signals: HashMap::new(),
+                contributors: Vec::new(),
+                exits: HashSet::new(),
+            });
+        if interest.entered() {
+            return BuyDecision::AlreadyEntered;
+        }
+        interest.signals.retain(|_, s| now - s.at <= window_secs);
+        interest
+            .signals
+            .insert(trader, InterestSignal { at: now, copy_usd });
+
+        let mut agreeing: HashSet<String> = interest.signals.keys().cloned().collect();
+        agreeing.extend(holders.iter().map(|h| h.to_lowercase()));
+        let decision = if agreeing.len() >= config.threshold {
+            let sizes: Vec<f64> = interest.signals.values().map(|s| s.copy_usd).collect();
+            let size_usd = match config.aggregate {
+                ConsensusAggregate::Average => sizes.iter().sum::<f64>() / sizes.len() as f64,
+                ConsensusAggregate::Max => sizes.iter().cloned().fold(0.0, f64::max),
+            };
+            let mut contributors: Vec<String> = agreeing.into_iter().collect();
+            contributors.sort();
+            interest.contributors = contributors.clone();
+            BuyDecision::Copy {
+                size_usd,
+                contributors,
+            }
+        } else {
+            BuyDecision::Watch {
+                agreeing: agreeing.len(),
+            }
+        };
+        self.persist();
+        decision
+    }
+
+    /// Undoes an entry whose order didn't fill so the next signal can try again.
+    pub fn reset_entry(&mut self, asset: &str) {
+        if let Some(interest) = self.interests.get_mut(asset) {
+            interest.contributors.clear();
+            interest.exits.clear();
+            self.persist();
+        }
+    }
+
+    pub fn on_sell(&mut self, config: &ConsensusConfig, asset: &str, trader: &str) -> SellDecision {
+        let trader = trader.to_lowercase();
+        let Some(interest) = self.interests.get_mut(asset) else {
+            return SellDecision::PassThrough;
+        };
+        if !interest.entered() {
+            // A trader who exits no longer agrees with the pending entry.
+            if interest.signals.remove(&trader).is_some() && interest.signals.is_empty() {
+                self.interests.remove(asset);
+            }
+            self.persist();
+            return SellDecision::PassThrough;
+        }
+        if !interest.contributors.contains(&trader) {
+            return SellDecision::Skip(format!(
+                "{} did not contribute to the consensus entry",
+                Logger::format_address(&trader)
+            ));
+        }
+        if !config.exit_requires_consensus {
+            return SellDecision::Follow;
+        }
+        interest.exits.insert(trader);
+        let needed = config.threshold.min(interest.contributors.len());
+        let decision = if interest.exits.len() >= needed {
+            SellDecision::CloseAll
+        } else {
+            SellDecision::Skip(format!(
+                "exit consensus {}/{} contributors",
+                interest.exits.len(),
+                needed
+            ))
+        };
+        self.persist();
+        decision
+    }
+
+    /// Forgets an entered interest once the position is gone.
+    pub fn close(&mut self, asset: &str) {
+        if self.interests.remove(asset).is_some() {
+            self.persist();
+        }
+    }
+
+    /// Drops pending interests whose signals have all aged out of the window.
+    pub fn prune(&mut self, config: &ConsensusConfig) {
+        let now = chrono::Utc::now().timestamp();
+        let window_secs = (config.window_hours * 3600.0) as i64;
+        let before = self.interests.len();
+        self.interests.retain(|_, i| {
+            if !i.entered() {
+                i.signals.retain(|_, s| now - s.at <= window_secs);
+            }
+            i.entered() || !i.signals.is_empty()
+        });
+        if self.interests.len() != before {
+            self.persist();
+        }
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist consensus state: {}", e));
+            }
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! `polymarket-copy-rust copy-now`: copies one trade and exits, without starting the monitor.
+//! The trade goes through the same pipeline as a live signal, so every skip rule, cap and
+//! journal row applies.
+
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use crate::config::{
+    self, calculate_order_size, is_valid_ethereum_address, CopyStrategy, EnvConfig, SizingInputs,
+};
+use crate::executor::{
+    execute_manual_copy, fetch_positions, CopyOutcome, ExecutorContext, ExecutorState,
+};
+use crate::interactive::confirmed;
+use crate::market_overrides;
+use crate::trader_portfolio;
+use crate::types::{RtdsActivity, UserActivity, UserPosition};
+use crate::utils::{create_clob_client, flush_journal, DataApiClient, Logger, RpcClient};
+
+const USAGE: &str = "Usage: polymarket-copy-rust copy-now --trader 0x... (--tx 0x... | --condition 0x... --side BUY|SELL --usd N) [--yes]
+
+Copies one trade of a tracked trader through the normal sizing and skip rules, then exits.
+--tx          a transaction of the trader's, looked up in their recent activity
+--condition   with --side and --usd: a trade of that size in the outcome the trader
+              holds most of in that market
+--yes         place the order without asking";
+
+/// Recent trades searched for `--tx`.
+const ACTIVITY_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeSource {
+    Tx(String),
+    Explicit {
+        condition_id: String,
+        side: String,
+        usd: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopyNowArgs {
+    pub trader: String,
+    pub source: TradeSource,
+    pub yes: bool,
+}
+
+fn is_hex_hash(raw: &str) -> bool {
+    let hex = raw.strip_prefix("0x").unwrap_or(raw);
+    hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn parse_args(raw: &[String]) -> Result<CopyNowArgs> {
+    let (mut trader, mut tx, mut condition, mut side, mut usd, mut yes) =
+        (None, None, None, None, None, false);
+    let mut it = raw.iter().cloned();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .with_context(|| format!("{} expects a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--trader" => trader = Some(value("--trader")?),
+            "--tx" => tx = Some(value("--tx")?),
+            "--condition" => condition = Some(value("--condition")?),
+            "--side" => side = Some(value("--side")?),
+            "--usd" => usd = Some(value("--usd")?),
+            "--yes" | "-y" => yes = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => anyhow::bail!("Unknown argument: {}\n\n{}", other, USAGE),
+        }
+    }
+    let trader = trader.with_context(|| format!("--trader is required\n\n{}", USAGE))?;
+    if !is_valid_ethereum_address(&trader) {
+        anyhow::bail!("Invalid --trader address: {}", trader);
+    }
+    let source = match (tx, condition) {
+        (Some(_), Some(_)) => anyhow::bail!("Use either --tx or --condition, not both"),
+        (Some(tx), None) => {
+            if side.is_some() || usd.is_some() {
+                anyhow::bail!("--side and --usd go with --condition, not --tx");
+            }
+            if !is_hex_hash(&tx) {
+                anyhow::bail!("Invalid --tx hash: {}", tx);
+            }
+            TradeSource::Tx(tx.to_lowercase())
+        }
+        (None, Some(condition_id)) => {
+            if !is_hex_hash(&condition_id) {
+                anyhow::bail!("Invalid --condition id: {}", condition_id);
+            }
+            let side = side
+                .context("--condition needs --side BUY or SELL")?
+                .to_uppercase();
+            if side != "BUY" && side != "SELL" {
+                anyhow::bail!("Invalid --side: {} (use BUY or SELL)", side);
+            }
+            let usd: f64 = usd
+                .context("--condition needs --usd")?
+                .parse()
+                .context("Invalid --usd")?;
+            if !usd.is_finite() || usd <= 0.0 {
+                anyhow::bail!("--usd must be a positive amount");
+            }
+            TradeSource::Explicit {
+                condition_id: condition_id.to_lowercase(),
+                side,
+                usd,
+            }
+        }
+        (None, None) => anyhow::bail!("Give --tx or --condition\n\n{}", USAGE),
+    };
+    Ok(CopyNowArgs {
+        trader: trader.to_lowercase(),
+        source,
+        yes,
+    })
+}
+
+/// The trader's fills in `tx` as one signal dated `now`, so it is judged as a trade made
+/// now rather than skipped as stale. A transaction that touched more than one outcome or
+/// side can't be copied as a single trade.
+pub fn signal_from_tx(activity: &[UserActivity], tx: &str, now: i64) -> Result<RtdsActivity> {
+    let fills: Vec<&UserActivity> = activity
+        .iter()
+        .filter(|a| {
+            a.transaction_hash
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(tx))
+                && a.activity_type.as_deref().unwrap_or("TRADE") == "TRADE"
+        })
+        .collect();
+    let first = fills
+        .first()
+        .with_context(|| format!("No trade with hash {} in the trader's recent activity", tx))?;
+    if fills
+        .iter()
+        .any(|f| f.asset != first.asset || f.side != first.side)
+    {
+        anyhow::bail!("Transaction {} trades more than one outcome or side", tx);
+    }
+    let shares: f64 = fills.iter().filter_map(|f| f.size).sum();
+    let usd: f64 = fills
+        .iter()
+        .map(|f| {
+            f.usdc_size
+                .unwrap_or(f.size.unwrap_or(0.0) * f.price.unwrap_or(0.0))
+        })
+        .sum();
+    if shares <= 0.0 {
+        anyhow::bail!("Transaction {} has no shares", tx);
+    }
+    Ok(RtdsActivity {
+        proxy_wallet: first.proxy_wallet.clone(),
+        timestamp: Some(now),
+        condition_id: first.condition_id.clone(),
+        activity_type: Some("TRADE".to_string()),
+        size: Some(shares),
+        price: Some(usd / shares),
+        asset: first.asset.clone(),
+        side: first.side.clone(),
+        outcome_index: first.outcome_index,
+        title: first.title.clone(),
+        slug: first.slug.clone(),
+        icon: first.icon.clone(),
+        event_slug: first.event_slug.clone(),
+        outcome: first.outcome.clone(),
+        name: first.name.clone(),
+        transaction_hash: Some(tx.to_string()),
+        reported_usdc_size: Some(usd),
+        backfilled: false,
+    })
+}
+
+/// A `usd` trade on `side` of the outcome the trader holds most of in `condition_id`, at its
+/// current price.
+pub fn explicit_signal(
+    positions: &[UserPosition],
+    condition_id: &str,
+    side: &str,
+    usd: f64,
+    now: i64,
+) -> Result<RtdsActivity> {
+    let position = positions
+        .iter()
+        .filter(|p| {
+            p.condition_id.as_deref().map(str::to_lowercase).as_deref() == Some(condition_id)
+                && p.size.unwrap_or(0.0) > 0.0
+        })
+        .max_by(|a, b| {
+            let (va, vb) = (
+                a.current_value.unwrap_or(0.0),
+                b.current_value.unwrap_or(0.0),
+            );
+            va.total_cmp(&vb)
+        })
+        .with_context(|| {
+            format!(
+                "Trader holds nothing in {} to take the outcome from; use --tx",
+                condition_id
+            )
+        })?;
+    let price = position
+        .cur_price
+        .filter(|p| *p > 0.0 && *p < 1.0)
+        .context("Market has no tradable current price")?;
+    Ok(RtdsActivity {
+        proxy_wallet: position.proxy_wallet.clone(),
+        timestamp: Some(now),
+        condition_id: Some(condition_id.to_string()),
+        activity_type: Some("TRADE".to_string()),
+        size: Some(usd / price),
+        price: Some(price),
+        asset: position.asset.clone(),
+        side: Some(side.to_string()),
+        outcome_index: position.outcome_index,
+        title: position.title.clone(),
+        slug: position.slug.clone(),
+        icon: position.icon.clone(),
+        event_slug: position.event_slug.clone(),
+        outcome: position.outcome.clone(),
+        name: None,
+        transaction_hash: Some(format!("manual:{}:{}", condition_id, now)),
+        reported_usdc_size: Some(usd),
+        backfilled: false,
+    })
+}
+
+async fn fetch_activity(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    trader: &str,
+) -> Result<Vec<UserActivity>> {
+    Ok(DataApiClient::from_config(config, http_client)
+        .get_trades(trader, ACTIVITY_LIMIT)
+        .await?)
+}
+
+/// One line for the trade about to be copied.
+pub fn describe(signal: &RtdsActivity) -> String {
+    format!(
+        "{} {:.2} shares of {} ({}) @ ${:.3} - trader notional ${:.2}",
+        signal.side.as_deref().unwrap_or("?"),
+        signal.size.unwrap_or(0.0),
+        signal.title.as_deref().unwrap_or("unknown market"),
+        signal.outcome.as_deref().unwrap_or("?"),
+        signal.price.unwrap_or(0.0),
+        signal.reported_usdc_size.unwrap_or(0.0)
+    )
+}
+
+/// What the pipeline did, for the closing line.
+pub fn outcome_line(outcome: Option<&CopyOutcome>) -> String {
+    match outcome {
+        None => "No decision recorded (signal rejected before sizing)".to_string(),
+        Some(o) if o.fill.tokens > 0.0 => format!(
+            "Filled {:.2} shares for ${:.2} (avg ${:.3})",
+            o.fill.tokens,
+            o.fill.usd,
+            o.fill.usd / o.fill.tokens
+        ),
+        Some(o) => format!(
+            "Not filled: {}",
+            o.reason.as_deref().unwrap_or("no reason recorded")
+        ),
+    }
+}
+
+fn ask(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(confirmed(&answer))
+}
+
+pub async fn run(raw_args: &[String]) -> Result<()> {
+    let args = parse_args(raw_args)?;
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    let config = Arc::new(EnvConfig::from_env().await?);
+    if !config.user_addresses.contains(&args.trader) {
+        anyhow::bail!("{} is not a tracked trader", args.trader);
+    }
+    let http_client = Arc::new(
+        reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(config.request_timeout_ms))
+            .build()?,
+    );
+    let rpc = RpcClient::from_config(&config)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let signal = match &args.source {
+        TradeSource::Tx(tx) => signal_from_tx(
+            &fetch_activity(&http_client, &config, &args.trader).await?,
+            tx,
+            now,
+        )?,
+        TradeSource::Explicit {
+            condition_id,
+            side,
+            usd,
+        } => {
+            let positions = fetch_positions(&http_client, &config, &args.trader).await?;
+            explicit_signal(&positions, condition_id, side, *usd, now)?
+        }
+    };
+    // Loaded here too: a never-trade market stays out even when copied by hand.
+    market_overrides::init(&config);
+    let market = market_overrides::get(signal.condition_id.as_deref());
+    Logger::info(&format!("Copying {}", describe(&signal)));
+    if signal.side.as_deref() == Some("BUY") {
+        let balance = rpc
+            .usdc_balance(&config.usdc_contract_address, &config.proxy_wallet)
+            .await
+            .unwrap_or(0.0);
+        let strategy = &config.copy_strategy_config;
+        let mut inputs = SizingInputs {
+            max_order_size_usd: Some(market_overrides::max_order_size(
+                strategy.max_order_size_usd,
+                market.as_ref(),
+            )),
+            ..SizingInputs::default()
+        };
+        if strategy.strategy == CopyStrategy::PortfolioShare {
+            let positions = fetch_positions(&http_client, &config, &args.trader).await?;
+            inputs.trader_portfolio_usd = Some(trader_portfolio::positions_value(&positions));
+        }
+        let calc = calculate_order_size(
+            strategy,
+            &inputs,
+            signal.reported_usdc_size.unwrap_or(0.0),
+            balance,
+            0.0,
+        );
+        Logger::info(&format!(
+            "📊 Sizing before position and market checks: {}",
+            calc.reasoning
+        ));
+    }
+    if config.dry_run {
+        Logger::warning("DRY_RUN is on - the decision is journaled, no order is placed");
+    }
+    if !args.yes && !config.dry_run && !ask("Place this copy?")? {
+        Logger::info("Not copied.");
+        return Ok(());
+    }
+
+    let (clob_client, signer) = create_clob_client(&config).await?;
+    let ex = ExecutorContext {
+        state: ExecutorState::new(&config, rpc),
+        config: config.clone(),
+        http_client,
+        clob_client: Arc::new(clob_client),
+        signer: Arc::new(tokio::sync::Mutex::new(signer)),
+    };
+    let outcome = execute_manual_copy(&ex, signal, args.trader.clone()).await;
+    flush_journal().await;
+    let outcome = outcome?;
+    let line = outcome_line(outcome.as_ref());
+    if outcome.as_ref().is_some_and(|o| o.fill.tokens > 0.0) {
+        Logger::success(&line);
+    } else {
+        Logger::warning(&line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{JournalStatus, OrderFill};
+
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+    const TX: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+    const CONDITION: &str = "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1";
+
+    fn args(raw: &[&str]) -> Result<CopyNowArgs> {
+        parse_args(&raw.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    }
+
+    fn fill(tx: &str, asset: &str, side: &str, size: f64, usdc: f64) -> UserActivity {
+        serde_json::from_value(serde_json::json!({
+            "proxyWallet": TRADER,
+            "timestamp": 1_700_000_000,
+            "conditionId": CONDITION,
+            "type": "TRADE",
+            "size": size,
+            "usdcSize": usdc,
+            "price": usdc / size,
+            "transactionHash": tx,
+            "asset": asset,
+            "side": side,
+            "title": "Will it rain?",
+            "outcome": "Yes",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn arguments_are_validated() {
+        let tx = args(&["--trader", TRADER, "--tx", TX, "--yes"]).unwrap();
+        assert_eq!(tx.source, TradeSource::Tx(TX.to_string()));
+        assert!(tx.yes);
+        let explicit = args(&[
+            "--trader",
+            TRADER,
+            "--condition",
+            CONDITION,
+            "--side",
+            "buy",
+            "--usd",
+            "50",
+        ])
+        .unwrap();
+        assert_eq!(
+            explicit.source,
+            TradeSource::Explicit {
+                condition_id: CONDITION.to_string(),
+                side: "BUY".to_string(),
+                usd: 50.0
+            }
+        );
+        assert!(!explicit.yes);
+
+        let refused: &[&[&str]] = &[
+            &["--tx", TX],
+            &["--trader", "0xabc", "--tx", TX],
+            &["--trader", TRADER],
+            &["--trader", TRADER, "--tx", "0xdeadbeef"],
+            &["--trader", TRADER, "--tx", TX, "--condition", CONDITION],
+            &["--trader", TRADER, "--tx", TX, "--usd", "5"],
+            &["--trader", TRADER, "--condition", CONDITION, "--usd", "5"],
+            &[
+                "--trader",
+                TRADER,
+                "--condition",
+                CONDITION,
+                "--side",
+                "HOLD",
+                "--usd",
+                "5",
+            ],
+            &[
+                "--trader",
+                TRADER,
+                "--condition",
+                CONDITION,
+                "--side",
+                "BUY",
+                "--usd",
+                "-5",
+            ],
+            &[
+                "--trader",
+                TRADER,
+                "--condition",
+                CONDITION,
+                "--side",
+                "BUY",
+            ],
+            &["--trader", TRADER, "--tx"],
+            &["--trader", TRADER, "--tx", TX, "--bogus"],
+        ];
+        for raw in refused {
+            assert!(args(raw).is_err(), "{:?} should be refused", raw);
+        }
+    }
+
+    #[test]
+    fn a_transaction_becomes_one_signal_dated_now() {
+        let other = "0x2222222222222222222222222222222222222222222222222222222222222222";
+        let activity = [
+            fill(TX, "yes", "BUY", 100.0, 40.0),
+            fill(other, "yes", "BUY", 5.0, 2.0),
+            fill(TX, "yes", "BUY", 50.0, 21.0),
+        ];
+        let signal = signal_from_tx(&activity, TX, 1_760_000_000).unwrap();
+        assert_eq!(signal.size, Some(150.0));
+        assert_eq!(signal.reported_usdc_size, Some(61.0));
+        assert_eq!(signal.timestamp, Some(1_760_000_000));
+        assert_eq!(signal.transaction_hash.as_deref(), Some(TX));
+        assert!(signal.validate(1_760_000_000).is_ok());
+        assert!(describe(&signal).starts_with("BUY 150.00 shares of Will it rain? (Yes)"));
+    }
+
+    #[test]
+    fn unknown_or_mixed_transactions_are_refused() {
+        assert!(signal_from_tx(
+            &[fill(TX, "yes", "BUY", 10.0, 4.0)],
+            &TX.replace('1', "3"),
+            0
+        )
+        .is_err());
+        let mixed = [
+            fill(TX, "yes", "BUY", 10.0, 4.0),
+            fill(TX, "no", "SELL", 10.0, 6.0),
+        ];
+        assert!(signal_from_tx(&mixed, TX, 0).is_err());
+    }
+
+    #[test]
+    fn explicit_trades_take_the_traders_larger_outcome() {
+        let position = |asset: &str, size: f64, value: f64| -> UserPosition {
+            serde_json::from_value(serde_json::json!({
+                "asset": asset,
+                "conditionId": CONDITION,
+                "size": size,
+                "currentValue": value,
+                "curPrice": 0.25,
+            }))
+            .unwrap()
+        };
+        let positions = [position("no", 10.0, 2.5), position("yes", 400.0, 100.0)];
+        let signal = explicit_signal(&positions, CONDITION, "BUY", 50.0, 1_760_000_000).unwrap();
+        assert_eq!(signal.asset.as_deref(), Some("yes"));
+        assert_eq!(signal.size, Some(200.0));
+        assert!(signal.validate(1_760_000_000).is_ok());
+        assert!(explicit_signal(&[], CONDITION, "BUY", 50.0, 0).is_err());
+    }
+
+    #[test]
+    fn outcome_lines() {
+        let filled = CopyOutcome {
+            status: JournalStatus::Executed,
+            reason: None,
+            fill: OrderFill {
+                tokens: 20.0,
+                usd: 9.0,
+                ..OrderFill::default()
+            },
+        };
+        assert_eq!(
+            outcome_line(Some(&filled)),
+            "Filled 20.00 shares for $9.00 (avg $0.450)"
+        );
+        let skipped = CopyOutcome {
+            status: JournalStatus::Skipped,
+            reason: Some("market passed its scheduled end 1m ago".to_string()),
+            fill: OrderFill::default(),
+        };
+        assert!(outcome_line(Some(&skipped)).contains("scheduled end"));
+    }
+}
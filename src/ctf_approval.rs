@@ -0,0 +1,192 @@
+use alloy::primitives::Address;
+use alloy::providers::ProviderBuilder;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol;
+use anyhow::{Context, Result};
+use polymarket_client_sdk::{contract_config, POLYGON};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::config::EnvConfig;
+use crate::utils::{Logger, RpcClient};
+
+sol! {
+    #[sol(rpc)]
+    interface IConditionalTokens {
+        function isApprovedForAll(address owner, address operator) external view returns (bool);
+        function setApprovalForAll(address operator, bool approved) external;
+    }
+}
+
+/// Set once every operator is approved, so the sell path stops checking.
+static APPROVED: AtomicBool = AtomicBool::new(false);
+static SELL_CHECKED: AtomicBool = AtomicBool::new(false);
+static SELL_HINTED: AtomicBool = AtomicBool::new(false);
+
+/// Contracts that move outcome tokens when a SELL fills: both exchanges and the neg-risk adapter.
+/// The same contracts spend USDC when a BUY fills.
+pub(crate) fn operators() -> Vec<(&'static str, Address)> {
+    let mut operators = Vec::new();
+    if let Some(c) = contract_config(POLYGON, false) {
+        operators.push(("CTF Exchange", c.exchange));
+    }
+    if let Some(c) = contract_config(POLYGON, true) {
+        operators.push(("Neg Risk CTF Exchange", c.exchange));
+        if let Some(adapter) = c.neg_risk_adapter {
+            operators.push(("Neg Risk Adapter", adapter));
+        }
+    }
+    operators
+}
+
+fn conditional_tokens() -> Result<Address> {
+    contract_config(POLYGON, false)
+        .map(|c| c.conditional_tokens)
+        .context("No conditional tokens contract known for Polygon")
+}
+
+/// Operators `owner` has not approved (`isApprovedForAll`) on the conditional tokens contract.
+pub async fn missing_approvals(rpc_url: &str, owner: &str) -> Result<Vec<&'static str>> {
+    missing_on(&*RpcClient::for_url(rpc_url)?, owner).await
+}
+
+async fn missing_on(rpc: &RpcClient, owner: &str) -> Result<Vec<&'static str>> {
+    let ctf = IConditionalTokens::new(conditional_tokens()?, rpc.provider());
+    let owner: Address = owner.trim().parse().context("Invalid wallet address")?;
+    let mut missing = Vec::new();
+    for (name, operator) in operators() {
+        if !ctf.isApprovedForAll(owner, operator).call().await? {
+            missing.push(name);
+        }
+    }
+    if missing.is_empty() {
+        APPROVED.store(true, Ordering::SeqCst);
+    }
+    Ok(missing)
+}
+
+/// Startup check. With `AUTO_APPROVE_CTF=true` and an EOA wallet the missing approvals are
+/// submitted (the wallet pays gas) through `rpc`, the process's failover client; a Safe or
+/// proxy wallet has to approve from its own UI. A dry run sends nothing, so it only warns.
+pub async fn ensure_approvals(config: &EnvConfig, signer: &PrivateKeySigner, rpc: &RpcClient) {
+    let missing = match missing_on(rpc, &config.proxy_wallet).await {
+        Ok(missing) if missing.is_empty() => return,
+        Ok(missing) => missing,
+        Err(e) => {
+            Logger::warning(&format!("Could not check outcome token approvals: {:#}", e));
+            return;
+        }
+    };
+    let list = missing.join(", ");
+    if !is_eoa(config, signer) {
+        Logger::warning(&format!(
+            "Outcome tokens not approved for {}: copied SELLs will fail. PROXY_WALLET is a Safe/proxy, so approve from the Polymarket site (or the Safe), not from this key.",
+            list
+        ));
+        return;
+    }
+    if config.dry_run {
+        Logger::warning(&format!(
+            "Outcome tokens not approved for {}: live SELLs would be rejected (DRY_RUN sends no approval)",
+            list
+        ));
+        return;
+    }
+    if !config.auto_approve_ctf {
+        Logger::warning(&format!(
+            "Outcome tokens not approved for {}: copied SELLs will fail. Set AUTO_APPROVE_CTF=true to submit setApprovalForAll at startup.",
+            list
+        ));
+        return;
+    }
+    match approve_all(signer, rpc, &missing).await {
+        Ok(()) => {
+            APPROVED.store(true, Ordering::SeqCst);
+            Logger::success(&format!("Outcome tokens approved for {}", list));
+        }
+        Err(e) => Logger::error(&format!(
+            "Outcome token approval failed (the wallet needs POL for gas): {:#}",
+            e
+        )),
+    }
+}
+
+/// True when `PROXY_WALLET` is the signing key's own address, so it can send approvals itself.
+pub(crate) fn is_eoa(config: &EnvConfig, signer: &PrivateKeySigner) -> bool {
+    config
+        .proxy_wallet
+        .trim()
+        .parse::<Address>()
+        .is_ok_and(|wallet| wallet == signer.address())
+}
+
+async fn approve_all(
+    signer: &PrivateKeySigner,
+    rpc: &RpcClient,
+    missing: &[&'static str],
+) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .wallet(signer.clone())
+        .connect_provider(rpc.provider().clone());
+    let ctf = IConditionalTokens::new(conditional_tokens()?, &provider);
+    for (name, operator) in operators() {
+        if !missing.contains(&name) {
+            continue;
+        }
+        Logger::info(&format!("Submitting setApprovalForAll for {}...", name));
+        let receipt = ctf
+            .setApprovalForAll(operator, true)
+            .send()
+            .await
+            .with_context(|| format!("approval for {} not sent", name))?
+            .get_receipt()
+            .await
+            .with_context(|| format!("approval for {} not confirmed", name))?;
+        if !receipt.status() {
+            anyhow::bail!("approval for {} reverted ({})", name, receipt.transaction_hash);
+        }
+    }
+    Ok(())
+}
+
+/// Checked once before the first copied SELL, in case the approval was revoked or startup
+/// couldn't reach the RPC. Only warns; the order is still tried.
+pub async fn check_before_sell(config: &EnvConfig) {
+    if APPROVED.load(Ordering::SeqCst) || SELL_CHECKED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let Ok(missing) = missing_approvals(&config.rpc_url, &config.proxy_wallet).await {
+        if !missing.is_empty() {
+            Logger::warning(&format!(
+                "Outcome tokens not approved for {}: this SELL will likely be rejected",
+                missing.join(", ")
+            ));
+        }
+    }
+}
+
+/// Balance/allowance rejections of a SELL are almost always the missing CTF approval.
+pub fn hint_after_sell_rejection() {
+    if APPROVED.load(Ordering::SeqCst) || SELL_HINTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    Logger::warning(
+        "💡 SELLs need the conditional tokens (CTF) approval for the exchange, which BUYs don't. Run make health-check to see it; AUTO_APPROVE_CTF=true submits it for EOA wallets.",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sells_need_both_exchanges_and_the_adapter() {
+        let ops = operators();
+        let names: Vec<&str> = ops.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            ["CTF Exchange", "Neg Risk CTF Exchange", "Neg Risk Adapter"]
+        );
+        assert!(ops.iter().all(|(_, a)| *a != Address::ZERO));
+        assert!(conditional_tokens().is_ok());
+    }
+}
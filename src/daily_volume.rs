@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::utils::{load_json, save_json, state_path, Logger};
+
+const DAILY_VOLUME_FILE: &str = "daily_volume.json";
+const DAY_SECS: i64 = 86_400;
+
+pub type SharedDailyVolume = Arc<Mutex<DailyVolumeTracker>>;
+
+/// USD placed since the last reset (`DAILY_VOLUME_RESET_HOUR_UTC`), for `MAX_DAILY_VOLUME_USD`.
+/// Every filled order counts, BUY or SELL; only BUYs are held back by the cap. Persisted so
+/// a restart keeps counting the same day.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyVolumeTracker {
+    /// Start of the current day, as a unix timestamp.
+    pub window_start: i64,
+    pub volume_usd: f64,
+    pub orders: usize,
+    #[serde(skip)]
+    reset_hour: u32,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+/// The latest reset at or before `now`.
+pub fn window_start(now: i64, reset_hour: u32) -> i64 {
+    let offset = reset_hour.min(23) as i64 * 3600;
+    (now - offset).div_euclid(DAY_SECS) * DAY_SECS + offset
+}
+
+impl DailyVolumeTracker {
+    pub fn new(reset_hour: u32, now: i64) -> Self {
+        Self {
+            window_start: window_start(now, reset_hour),
+            reset_hour,
+            ..Self::default()
+        }
+    }
+
+    pub fn load(state_dir: &str, reset_hour: u32) -> Self {
+        let path = state_path(state_dir, DAILY_VOLUME_FILE);
+        let mut tracker: DailyVolumeTracker = load_json(&path).unwrap_or_default();
+        tracker.reset_hour = reset_hour;
+        tracker.path = Some(path);
+        tracker.roll(chrono::Utc::now().timestamp());
+        tracker
+    }
+
+    /// Starts a fresh day once the reset hour has passed.
+    fn roll(&mut self, now: i64) {
+        let start = window_start(now, self.reset_hour);
+        if self.window_start != start {
+            self.window_start = start;
+            self.volume_usd = 0.0;
+            self.orders = 0;
+        }
+    }
+
+    /// What `cap_usd` still allows today.
+    pub fn remaining(&mut self, cap_usd: f64, now: i64) -> f64 {
+        self.roll(now);
+        (cap_usd - self.volume_usd).max(0.0)
+    }
+
+    /// Counts a placed order's filled USD. `new_order` is false for later fills of an order
+    /// already counted.
+    pub fn record(&mut self, usd: f64, new_order: bool, now: i64) {
+        self.roll(now);
+        self.volume_usd += usd;
+        if new_order {
+            self.orders += 1;
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist daily volume: {}", e));
+            }
+        }
+    }
+
+    /// Shutdown summary line.
+    pub fn summary(&mut self, cap_usd: Option<f64>, now: i64) -> String {
+        self.roll(now);
+        let reset = chrono::DateTime::from_timestamp(self.window_start + DAY_SECS, 0)
+            .map(|t| t.format("%H:%M UTC").to_string())
+            .unwrap_or_default();
+        match cap_usd {
+            Some(cap) => format!(
+                "Daily volume: ${:.2} of ${:.2} in {} order(s), ${:.2} left until {}",
+                self.volume_usd,
+                cap,
+                self.orders,
+                (cap - self.volume_usd).max(0.0),
+                reset
+            ),
+            None => format!(
+                "Daily volume: ${:.2} in {} order(s) since the last reset",
+                self.volume_usd, self.orders
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2025-10-09 12:26:40 UTC.
+    const NOON: i64 = 1_760_012_800;
+
+    #[test]
+    fn window_starts_at_the_reset_hour() {
+        let midnight = NOON - NOON.rem_euclid(DAY_SECS);
+        assert_eq!(window_start(NOON, 0), midnight);
+        assert_eq!(window_start(NOON, 8), midnight + 8 * 3600);
+        // Before today's 14:00 reset, the window began at yesterday's.
+        assert_eq!(window_start(NOON, 14), midnight - DAY_SECS + 14 * 3600);
+    }
+
+    #[test]
+    fn volume_accumulates_until_the_reset() {
+        let mut tracker = DailyVolumeTracker::new(14, NOON);
+        tracker.record(60.0, true, NOON);
+        tracker.record(15.0, false, NOON + 60);
+        assert_eq!(tracker.orders, 1);
+        assert_eq!(tracker.remaining(100.0, NOON + 120), 25.0);
+        assert_eq!(tracker.remaining(50.0, NOON + 120), 0.0);
+
+        // 14:00 passes: a new day.
+        let after_reset = NOON + 2 * 3600;
+        assert_eq!(tracker.remaining(100.0, after_reset), 100.0);
+        assert_eq!(tracker.orders, 0);
+    }
+
+    #[test]
+    fn survives_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let mut tracker = DailyVolumeTracker::load(state_dir, 0);
+        tracker.record(40.0, true, now);
+
+        let mut reloaded = DailyVolumeTracker::load(state_dir, 0);
+        assert_eq!(reloaded.remaining(100.0, now), 60.0);
+        assert!(reloaded.summary(Some(100.0), now).contains("$40.00 of $100.00"));
+    }
+}
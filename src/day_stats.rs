@@ -0,0 +1,113 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::utils::{load_json, save_json, Logger};
+
+const DAY_STATS_FILE: &str = "day_stats.json";
+
+/// Copies and skips since midnight UTC, persisted so a restart keeps counting the same day.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DayStats {
+    pub day: Option<NaiveDate>,
+    pub copies: usize,
+    /// USD spent on executed BUY copies.
+    pub deployed_usd: f64,
+    pub skipped: usize,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl DayStats {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, DAY_STATS_FILE);
+        let mut stats: DayStats = load_json(&path).unwrap_or_default();
+        stats.path = Some(path);
+        stats.roll();
+        stats
+    }
+
+    /// Starts a fresh day once the date changed.
+    fn roll(&mut self) {
+        let today = chrono::Utc::now().date_naive();
+        if self.day != Some(today) {
+            *self = DayStats {
+                day: Some(today),
+                path: self.path.take(),
+                ..DayStats::default()
+            };
+        }
+    }
+
+    /// Counts an executed copy and returns its number for the day.
+    pub fn record_copy(&mut self, side: &str, usd: f64) -> usize {
+        self.roll();
+        self.copies += 1;
+        if side == "BUY" {
+            self.deployed_usd += usd;
+        }
+        self.persist();
+        self.copies
+    }
+
+    /// Adds a later fill of an already counted copy (a resting order filling bit by bit).
+    pub fn record_fill(&mut self, side: &str, usd: f64) {
+        self.roll();
+        if side == "BUY" {
+            self.deployed_usd += usd;
+        }
+        self.persist();
+    }
+
+    pub fn record_skip(&mut self) {
+        self.roll();
+        self.skipped += 1;
+        self.persist();
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist day stats: {}", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_buys_count_as_deployed() {
+        let mut stats = DayStats::default();
+        assert_eq!(stats.record_copy("BUY", 20.0), 1);
+        assert_eq!(stats.record_copy("SELL", 50.0), 2);
+        stats.record_skip();
+        assert_eq!(stats.deployed_usd, 20.0);
+        assert_eq!(stats.skipped, 1);
+    }
+
+    #[test]
+    fn later_fills_add_notional_but_not_copies() {
+        let mut stats = DayStats::default();
+        assert_eq!(stats.record_copy("BUY", 4.0), 1);
+        stats.record_fill("BUY", 6.0);
+        assert_eq!(stats.copies, 1);
+        assert_eq!(stats.deployed_usd, 10.0);
+    }
+
+    #[test]
+    fn a_new_day_starts_from_zero() {
+        let mut stats = DayStats {
+            day: NaiveDate::from_ymd_opt(2020, 1, 1),
+            copies: 9,
+            deployed_usd: 100.0,
+            skipped: 4,
+            path: None,
+        };
+        assert_eq!(stats.record_copy("BUY", 5.0), 1);
+        assert_eq!(stats.deployed_usd, 5.0);
+        assert_eq!(stats.skipped, 0);
+    }
+}
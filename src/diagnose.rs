@@ -0,0 +1,169 @@
+//! `polymarket-copy-rust diagnose`: writes a JSON bundle to attach to bug reports. It holds
+//! the build metadata, the settings from `.env` and the config file (secrets redacted, URLs
+//! cut to their host), the market overrides and the tail of today's log file. Nothing is sent
+//! anywhere.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::env;
+
+use crate::build_info;
+use crate::config::{config_file_path, read_config_file};
+use crate::market_overrides::MarketOverrides;
+use crate::utils::Logger;
+
+const LOG_TAIL_LINES: usize = 200;
+
+const USAGE: &str = "Usage: polymarket-copy-rust diagnose [--out PATH]
+
+Writes diagnose-<timestamp>.json (or PATH) with build info, your settings
+(secrets redacted), your market overrides and the last 200 lines of today's log.";
+
+/// Keys whose whole value is a secret. Webhook URLs are among them: the token is in the path.
+fn is_secret(key: &str) -> bool {
+    key.contains("PRIVATE_KEY")
+        || key.contains("SECRET")
+        || key.contains("PASSWORD")
+        || key.contains("WEBHOOK")
+        || key.ends_with("_TOKEN")
+        || key.ends_with("_KEY")
+}
+
+/// Each URL of a (comma-separated) `*_URL` value reduced to its scheme and host: provider
+/// URLs often carry an API key in the path or query.
+fn url_hosts(value: &str) -> String {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .map(|u| match url::Url::parse(u) {
+            Ok(url) => match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}://{}:{}", url.scheme(), host, port),
+                (Some(host), None) => format!("{}://{}", url.scheme(), host),
+                (None, _) => "<redacted>".to_string(),
+            },
+            Err(_) => "<redacted>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Settings named in `.env` or the config file, with the values the bot would use.
+fn settings() -> Map<String, Value> {
+    let mut keys: Vec<String> = dotenvy::from_filename_iter(".env")
+        .map(|iter| iter.filter_map(|item| item.ok().map(|(k, _)| k)).collect())
+        .unwrap_or_default();
+    let path = config_file_path();
+    if path.exists() {
+        if let Ok(vars) = read_config_file(&path) {
+            keys.extend(vars.into_iter().map(|(k, _)| k));
+        }
+    }
+    redacted(
+        keys.into_iter()
+            .filter_map(|k| env::var(&k).ok().map(|v| (k, v))),
+    )
+}
+
+fn redacted(vars: impl Iterator<Item = (String, String)>) -> Map<String, Value> {
+    vars.map(|(key, value)| {
+        let value = if is_secret(&key) {
+            "<redacted>".to_string()
+        } else if key.ends_with("_URL") {
+            url_hosts(&value)
+        } else {
+            value
+        };
+        (key, Value::String(value))
+    })
+    .collect()
+}
+
+fn tail(text: &str, lines: usize) -> Vec<&str> {
+    let all: Vec<&str> = text.lines().collect();
+    all[all.len().saturating_sub(lines)..].to_vec()
+}
+
+pub fn bundle() -> Value {
+    let log = std::fs::read_to_string(Logger::log_file()).unwrap_or_default();
+    let state_dir = env::var("STATE_DIR")
+        .ok()
+        .filter(|d| !d.trim().is_empty())
+        .unwrap_or_else(|| "state".to_string());
+    json!({
+        "created": chrono::Utc::now().to_rfc3339(),
+        "build": build_info::to_json(),
+        "os": env::consts::OS,
+        "settings": settings(),
+        "market_overrides": MarketOverrides::load(state_dir.trim()).to_json(),
+        "log_tail": tail(&log, LOG_TAIL_LINES),
+    })
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let out = match args {
+        [] => format!(
+            "diagnose-{}.json",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ),
+        [flag, path] if flag == "--out" => path.clone(),
+        _ => anyhow::bail!("{}", USAGE),
+    };
+    dotenvy::dotenv().ok();
+    let contents = serde_json::to_string_pretty(&bundle())?;
+    std::fs::write(&out, contents).with_context(|| format!("Failed to write {}", out))?;
+    println!(
+        "Wrote {}. Check it before sharing; secrets are redacted.",
+        out
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted() {
+        let vars = redacted(
+            [
+                ("PRIVATE_KEY", "abc"),
+                ("PRIVATE_KEY_FILE", "/keys/bot"),
+                ("CLOB_API_KEY", "k"),
+                ("SMTP_PASSWORD", "p"),
+                ("JOURNAL_REMOTE_TOKEN", "t"),
+                ("DISCORD_WEBHOOK_URL", "https://discord.com/api/webhooks/1/abc"),
+                ("ALERT_DISCORD_WEBHOOK_URL", "https://discord.com/api/webhooks/2/def"),
+                (
+                    "RPC_URL",
+                    "https://polygon-mainnet.g.alchemy.com/v2/key1, http://127.0.0.1:8545?k=2",
+                ),
+                ("JOURNAL_REMOTE_URL", "https://user:pw@logs.example.com/ingest?key=3"),
+                ("DATA_API_URL", "not a url"),
+                ("COPY_SIZE", "10"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+        );
+        assert_eq!(vars["PRIVATE_KEY"], "<redacted>");
+        assert_eq!(vars["PRIVATE_KEY_FILE"], "<redacted>");
+        assert_eq!(vars["CLOB_API_KEY"], "<redacted>");
+        assert_eq!(vars["SMTP_PASSWORD"], "<redacted>");
+        assert_eq!(vars["JOURNAL_REMOTE_TOKEN"], "<redacted>");
+        assert_eq!(vars["DISCORD_WEBHOOK_URL"], "<redacted>");
+        assert_eq!(vars["ALERT_DISCORD_WEBHOOK_URL"], "<redacted>");
+        assert_eq!(
+            vars["RPC_URL"],
+            "https://polygon-mainnet.g.alchemy.com,http://127.0.0.1:8545"
+        );
+        assert_eq!(vars["JOURNAL_REMOTE_URL"], "https://logs.example.com");
+        assert_eq!(vars["DATA_API_URL"], "<redacted>");
+        assert_eq!(vars["COPY_SIZE"], "10");
+    }
+
+    #[test]
+    fn tail_keeps_the_last_lines() {
+        assert_eq!(tail("a\nb\nc\n", 2), vec!["b", "c"]);
+        assert_eq!(tail("a\n", 5), vec!["a"]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Periodic digest: how many signals were copied and skipped (by reason) since the last one,
+//! plus standing notes such as the open-position count. Logged every `DIGEST_INTERVAL_MINS`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::skip_reason::SkipReason;
+use crate::status;
+use crate::utils::Logger;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Digest {
+    pub copied: u64,
+    pub skipped: BTreeMap<SkipReason, u64>,
+    pub notes: BTreeMap<&'static str, String>,
+}
+
+static CURRENT: Mutex<Option<Digest>> = Mutex::new(None);
+/// Skips since start by `SkipReason::metric_label`, published as the `skips` status section.
+static TOTALS: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+fn with_current(f: impl FnOnce(&mut Digest)) {
+    if let Ok(mut current) = CURRENT.lock() {
+        f(current.get_or_insert_with(Digest::default));
+    }
+}
+
+pub fn record_copy() {
+    with_current(|d| d.copied += 1);
+}
+
+pub fn record_skip(reason: &SkipReason) {
+    with_current(|d| *d.skipped.entry(reason.clone()).or_insert(0) += 1);
+    if let Ok(mut totals) = TOTALS.lock() {
+        *totals.entry(reason.metric_label()).or_insert(0) += 1;
+        status::publish("skips", serde_json::json!(*totals));
+    }
+}
+
+/// Sets a line shown in every digest until it is replaced.
+pub fn note(key: &'static str, line: String) {
+    with_current(|d| {
+        d.notes.insert(key, line);
+    });
+}
+
+/// The counts since the last call; notes carry over.
+pub fn take() -> Digest {
+    let mut taken = Digest::default();
+    with_current(|d| {
+        taken = d.clone();
+        d.copied = 0;
+        d.skipped.clear();
+    });
+    taken
+}
+
+pub fn format_digest(digest: &Digest, interval_mins: u64) -> String {
+    let skipped: u64 = digest.skipped.values().sum();
+    let mut line = format!(
+        "Digest ({}m): {} copied, {} skipped",
+        interval_mins, digest.copied, skipped
+    );
+    if skipped > 0 {
+        let reasons: Vec<String> = digest
+            .skipped
+            .iter()
+            .map(|(reason, n)| format!("{} {}", reason.code(), n))
+            .collect();
+        line.push_str(&format!(" ({})", reasons.join(", ")));
+    }
+    for note in digest.notes.values() {
+        line.push_str(&format!(" · {}", note));
+    }
+    line
+}
+
+/// Logs a digest every `interval_mins` until the task is dropped.
+pub async fn run(interval_mins: u64) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval_mins * 60));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        Logger::info(&format_digest(&take(), interval_mins));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_lists_skip_reasons_and_notes() {
+        let digest = Digest {
+            copied: 4,
+            skipped: [(SkipReason::OpenPositions, 2), (SkipReason::Stale, 1)]
+                .into_iter()
+                .collect(),
+            notes: [(
+                "open_positions",
+                "open positions 25/25, openings paused".to_string(),
+            )]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(
+            format_digest(&digest, 60),
+            "Digest (60m): 4 copied, 3 skipped (stale 1, open_positions 2) · open positions 25/25, openings paused"
+        );
+    }
+
+    #[test]
+    fn take_resets_counts_and_keeps_notes() {
+        record_copy();
+        record_skip(&SkipReason::OpenPositions);
+        record_skip(&SkipReason::OpenPositions);
+        record_skip(&SkipReason::filtered("price_band"));
+        record_skip(&SkipReason::filtered("market_filter"));
+        note("open_positions", "open positions 3/3".to_string());
+        let first = take();
+        assert_eq!(first.copied, 1);
+        // Grouped by reason, rule names kept apart; the status counters drop them.
+        assert_eq!(first.skipped.get(&SkipReason::OpenPositions), Some(&2));
+        assert_eq!(first.skipped.get(&SkipReason::filtered("price_band")), Some(&1));
+        assert_eq!(first.skipped.len(), 3);
+        let totals = status::snapshot()["skips"].clone();
+        assert!(totals["filtered"].as_u64() >= Some(2), "{}", totals);
+        let second = take();
+        assert_eq!((second.copied, second.skipped.len()), (0, 0));
+        assert_eq!(
+            second.notes.get("open_positions").map(String::as_str),
+            Some("open positions 3/3")
+        );
+    }
+
+    #[test]
+    fn quiet_period_has_no_reason_list() {
+        assert_eq!(
+            format_digest(&Digest::default(), 15),
+            "Digest (15m): 0 copied, 0 skipped"
+        );
+    }
+}
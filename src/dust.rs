@@ -0,0 +1,306 @@
+use std::collections::HashMap;
+
+use crate::config::{EnvConfig, SizingInputs};
+use crate::executor::{fetch_positions, gated_order, ExecutorContext};
+use crate::ledger::SharedLedger;
+use crate::trading_state;
+use crate::types::{UserActivity, UserPosition};
+use crate::utils::{post_order, Logger, OrderContext, OrderFill, MIN_ORDER_SIZE_TOKENS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DustAction {
+    /// Not dust, or the trader we copied still holds the outcome.
+    Keep,
+    /// Big enough in shares to clear the market minimum: sell it.
+    Sell,
+    /// Too small to sell: write it off in the ledger.
+    WriteOff,
+    /// Market resolved: leave it for redemption rather than selling.
+    Redeem,
+}
+
+/// Classifies one of my positions. Only positions worth less than `threshold_usd` whose
+/// originating trader has exited are dust; a threshold of 0 disables the sweeper.
+pub fn classify_dust(
+    position: &UserPosition,
+    trader_holds: bool,
+    threshold_usd: f64,
+) -> DustAction {
+    let value = position.current_value.unwrap_or(0.0);
+    if threshold_usd <= 0.0 || value >= threshold_usd || trader_holds {
+        return DustAction::Keep;
+    }
+    if position.redeemable == Some(true) {
+        return DustAction::Redeem;
+    }
+    if position.size.unwrap_or(0.0) >= MIN_ORDER_SIZE_TOKENS
+        && position.cur_price.unwrap_or(0.0) > 0.0
+    {
+        DustAction::Sell
+    } else {
+        DustAction::WriteOff
+    }
+}
+
+/// Whether the trader the position was copied from (or, for adopted positions, any tracked
+/// trader) still holds the outcome; `None` when a lookup failed. Trader positions are fetched
+/// once per sweep.
+pub(crate) async fn trader_still_holds(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    cache: &mut HashMap<String, Vec<UserPosition>>,
+    trader: Option<&str>,
+    asset: &str,
+) -> Option<bool> {
+    let traders: Vec<String> = match trader {
+        Some(t) => config.trader_members(t),
+        None => config
+            .user_addresses
+            .iter()
+            .map(|a| a.to_lowercase())
+            .collect(),
+    };
+    for trader in traders {
+        if !cache.contains_key(&trader) {
+            match fetch_positions(http_client, config, &trader).await {
+                Ok(positions) => {
+                    cache.insert(trader.clone(), positions);
+                }
+                Err(_) => return None,
+            }
+        }
+        if cache[&trader]
+            .iter()
+            .any(|p| p.asset.as_deref() == Some(asset) && p.size.unwrap_or(0.0) > 0.0)
+        {
+            return Some(true);
+        }
+    }
+    Some(false)
+}
+
+/// Places a dust sale unless `DRY_RUN` is on and books it: sold tokens come off the
+/// position, a sale that fails or fills nothing writes the dust off. A dry run sends
+/// nothing and leaves the ledger as it was. Returns the USD the sale filled.
+async fn sell_dust<F>(
+    dry_run: bool,
+    ledger: &SharedLedger,
+    asset: &str,
+    place: impl FnOnce() -> F,
+) -> f64
+where
+    F: std::future::Future<Output = anyhow::Result<OrderFill>>,
+{
+    let fill = gated_order(dry_run, place).await;
+    if dry_run {
+        return 0.0;
+    }
+    let mut ledger = ledger.lock().await;
+    match fill {
+        Ok(fill) if fill.tokens > 0.0 => {
+            ledger.record_sell(asset, fill.tokens);
+            fill.usd
+        }
+        _ => {
+            ledger.write_off_dust(asset);
+            0.0
+        }
+    }
+}
+
+/// Sells or writes off dust left behind by proportional exits and rounding. Sales count
+/// toward the day's volume like any other order.
+pub async fn sweep_dust(ex: &ExecutorContext) -> anyhow::Result<()> {
+    let ExecutorContext {
+        config,
+        http_client,
+        clob_client,
+        signer,
+        state,
+    } = ex;
+    let ledger = &state.ledger;
+    let my_positions = fetch_positions(http_client, config, &config.proxy_wallet).await?;
+    let mut trader_cache = HashMap::new();
+    for pos in &my_positions {
+        let Some(asset) = pos.asset.as_deref() else {
+            continue;
+        };
+        if pos.current_value.unwrap_or(0.0) >= config.dust_threshold_usd {
+            continue;
+        }
+        let (trader, already_dust) = {
+            let ledger = ledger.lock().await;
+            (
+                ledger.position(asset).and_then(|p| p.trader.clone()),
+                ledger.is_dust(asset),
+            )
+        };
+        if already_dust {
+            continue;
+        }
+        let trader_holds = trader_still_holds(
+            http_client,
+            config,
+            &mut trader_cache,
+            trader.as_deref(),
+            asset,
+        )
+        .await
+        // Unknown is treated as still holding: never sweep on a failed lookup.
+        .unwrap_or(true);
+        let title = pos.title.as_deref().unwrap_or(asset);
+        match classify_dust(pos, trader_holds, config.dust_threshold_usd) {
+            DustAction::Keep => {}
+            DustAction::Redeem => {
+                Logger::info(&format!(
+                    "🧹 Dust in resolved market left for redemption: {}",
+                    title
+                ));
+            }
+            DustAction::Sell => {
+                if let Err(reason) = trading_state::check("SELL") {
+                    Logger::info(&format!("🧹 Dust sale of {} deferred: {}", title, reason));
+                    continue;
+                }
+                Logger::info(&format!(
+                    "🧹 Selling dust: {:.2} tokens of {} (${:.2})",
+                    pos.size.unwrap_or(0.0),
+                    title,
+                    pos.current_value.unwrap_or(0.0)
+                ));
+                let trade = UserActivity {
+                    condition_id: pos.condition_id.clone(),
+                    asset: pos.asset.clone(),
+                    side: Some("SELL".to_string()),
+                    title: pos.title.clone(),
+                    slug: pos.slug.clone(),
+                    ..Default::default()
+                };
+                let place = || async {
+                    let signer = signer.lock().await;
+                    let order = OrderContext {
+                        config,
+                        clob_client,
+                        signer: &signer,
+                        http_client,
+                        chaos: state.chaos.as_deref(),
+                        sizing: SizingInputs::default(),
+                    };
+                    post_order(
+                        &order,
+                        "sell",
+                        Some(pos),
+                        None,
+                        &trade,
+                        0.0,
+                        &config.proxy_wallet,
+                    )
+                    .await
+                };
+                let sold_usd = sell_dust(config.dry_run, ledger, asset, place).await;
+                if sold_usd > 0.0 {
+                    state.daily_volume.lock().await.record(
+                        sold_usd,
+                        true,
+                        chrono::Utc::now().timestamp(),
+                    );
+                }
+            }
+            DustAction::WriteOff => {
+                Logger::info(&format!(
+                    "🧹 Writing off dust: {:.4} tokens of {} (${:.2})",
+                    pos.size.unwrap_or(0.0),
+                    title,
+                    pos.current_value.unwrap_or(0.0)
+                ));
+                ledger.lock().await.write_off_dust(asset);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn position(value: f64, size: f64, price: f64, redeemable: bool) -> UserPosition {
+        serde_json::from_value(serde_json::json!({
+            "asset": "123",
+            "conditionId": "0xc1",
+            "size": size,
+            "curPrice": price,
+            "currentValue": value,
+            "redeemable": redeemable,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn value_at_the_threshold_is_not_dust() {
+        let p = position(0.50, 1.0, 0.50, false);
+        assert_eq!(classify_dust(&p, false, 0.50), DustAction::Keep);
+        let p = position(0.49, 1.0, 0.49, false);
+        assert_eq!(classify_dust(&p, false, 0.50), DustAction::Sell);
+    }
+
+    #[test]
+    fn trader_still_holding_keeps_it() {
+        let p = position(0.10, 0.3, 0.33, false);
+        assert_eq!(classify_dust(&p, true, 0.50), DustAction::Keep);
+    }
+
+    #[test]
+    fn zero_threshold_disables_the_sweeper() {
+        let p = position(0.01, 0.3, 0.03, false);
+        assert_eq!(classify_dust(&p, false, 0.0), DustAction::Keep);
+    }
+
+    #[test]
+    fn share_minimum_decides_sell_or_write_off() {
+        let at_min = position(0.30, MIN_ORDER_SIZE_TOKENS, 0.30, false);
+        assert_eq!(classify_dust(&at_min, false, 0.50), DustAction::Sell);
+        let below = position(0.10, MIN_ORDER_SIZE_TOKENS - 0.01, 0.10, false);
+        assert_eq!(classify_dust(&below, false, 0.50), DustAction::WriteOff);
+    }
+
+    #[test]
+    fn no_price_cannot_be_sold() {
+        let p = position(0.0, 5.0, 0.0, false);
+        assert_eq!(classify_dust(&p, false, 0.50), DustAction::WriteOff);
+    }
+
+    #[tokio::test]
+    async fn dry_run_sells_no_dust() {
+        let ledger: SharedLedger = Default::default();
+        ledger
+            .lock()
+            .await
+            .record_buy("123", "0xc1", None, 1.0, 0.40);
+        let sent = AtomicU32::new(0);
+        let place = || async {
+            sent.fetch_add(1, Ordering::SeqCst);
+            Ok(OrderFill {
+                tokens: 1.0,
+                usd: 0.40,
+                ..OrderFill::default()
+            })
+        };
+
+        assert_eq!(sell_dust(true, &ledger, "123", place).await, 0.0);
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+        assert!(!ledger.lock().await.is_dust("123"));
+        assert!(ledger.lock().await.position("123").is_some());
+
+        assert_eq!(sell_dust(false, &ledger, "123", place).await, 0.40);
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert!(ledger.lock().await.position("123").is_none());
+    }
+
+    #[test]
+    fn resolved_dust_goes_to_redemption() {
+        let p = position(0.20, 5.0, 0.0, true);
+        assert_eq!(classify_dust(&p, false, 0.50), DustAction::Redeem);
+    }
+}
@@ -1,43 +1,495 @@
-use anyhow::Result;
 use alloy::signers::local::PrivateKeySigner;
-use polymarket_client_sdk::clob::Client as ClobClient;
+use anyhow::Result;
 use polymarket_client_sdk::auth::state::Authenticated;
 use polymarket_client_sdk::auth::Normal;
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, Ordering};
+use polymarket_client_sdk::clob::types::Side;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::advisories::{self, AdvisorySeverity};
+use crate::aggregation::{self, PendingFills};
+use crate::alerts;
+use crate::balance::{self, BalanceBreakdown, BalanceReading, BalanceTracker};
+use crate::catch_up;
+use crate::chaos::{Chaos, ChaosMarketData};
+use crate::classification::refresh_trader_classes;
+use crate::concentration::{self, ConcentrationMonitor};
+use crate::config::{
+    calculate_order_size, ConsensusConfig, CopyStrategy, EnvConfig, RebalancePolicy, SizingInputs,
+    SizingStep, TrialConfig,
+};
+use crate::consensus::{BuyDecision, ConsensusBook, SellDecision, SharedConsensus};
+use crate::daily_volume::{DailyVolumeTracker, SharedDailyVolume};
+use crate::day_stats::DayStats;
+use crate::digest;
+use crate::dust::sweep_dust;
+use crate::exits::sweep_exits;
+use crate::redeem::{redeem_resolved, wallet_kind, RedeemWallet};
+use crate::handover::{self, reconcile_orders, summary_lines, Handover, OrderFate, QueuedSignal};
+use crate::inactivity::{SharedActivity, TraderActivityBook};
+use crate::interactive::{self, CommandHandler, HeldPosition};
+use crate::ledger::{OpenPositionLimiter, PositionLedger, SharedLedger};
+use crate::manual_copy;
+use crate::market_overrides;
+use crate::market_share::{self, ShareLimit};
+use crate::metadata_cache;
+use crate::order_intents;
+use crate::order_templates;
+use crate::overflow::{position_cap_cut, MarketOverflow, OverflowBook, SharedOverflow};
+use crate::paper::{self, SharedPaperBook};
+use crate::position_action::{self, PositionAction};
+use crate::position_builder::{self, Build, BuildEvent, BuildOrder, BuildStep, EndReason};
+use crate::prefetch::{prefetch, ClobMarketData, PrefetchPlan, PrefetchReport};
+use crate::profiling;
+use crate::rebalance::{self, leg_skip_reason, NegRiskCache, PendingGroups, SignalGroup};
+use crate::resolution::market_end_time;
+use crate::processed_trades::ProcessedTrades;
+use crate::resting_orders::{self, OrderEvent, RestingOrder};
+use crate::shadow::{self, ShadowDecision, ShadowFacts};
+use crate::skip_reason::{Skip, SkipReason};
+use crate::skip_rules::{
+    signal_age_hours, OpenPositionCheck, RuleInput, RuleStage, SkipRuleEngine,
+};
+use crate::status;
+use crate::status_api::{self, CopiedTrade};
+use crate::supervisor::{supervisor, TaskHandle, STOP_EXECUTOR, STOP_LAST};
+use crate::trader_portfolio::{self, PortfolioRead};
+use crate::trading_state;
+use crate::trial::{self, trial_pnl, SharedTrials, TrialBook, TrialStatus};
+use crate::types::{
+    BotEvent, EventTrade, RtdsActivity, UsdcNotional, UserActivity, UserPosition,
+};
+use crate::utils::{
+    event_webhook, flush_journal, rate_limit, fetch_book_context, fetch_price_context, market_metadata, DataApiClient, FetchError, place_limit_order, post_order,
+    read_journal, BalanceCache, BalanceSource, CachedBalance, CopySummary, Journal, JournalEntry, JournalStatus, Logger, MarketContext,
+    JournalFiles, OrderContext, OrderFill, RemoteJournal, RpcClient, JOURNAL_SCHEMA_VERSION, RPC_PROBE_INTERVAL,
+    trader_sell_fraction,
+};
+use crate::watchdog::{fetch_wallet_trades, WalletWatchdog};
+
+/// State shared by every `execute_trade` call for the lifetime of the executor.
+#[derive(Clone)]
+pub struct ExecutorState {
+    pub processed_trades: Arc<Mutex<ProcessedTrades>>,
+    pub ledger: SharedLedger,
+    pub open_position_limiter: Option<Arc<Mutex<OpenPositionLimiter>>>,
+    pub consensus: Option<SharedConsensus>,
+    pub journal: Journal,
+    pub balance: Arc<Mutex<BalanceTracker>>,
+    pub balance_cache: BalanceCache,
+    /// The chain connection `main` opened; the balance cache and redeemer read through it.
+    pub rpc: Arc<RpcClient>,
+    pub skip_rules: Arc<SkipRuleEngine>,
+    pub activity: SharedActivity,
+    pub day_stats: Arc<Mutex<DayStats>>,
+    /// Kept apart from `processed_trades`, whose pruning must not reset the day's volume.
+    pub daily_volume: SharedDailyVolume,
+    pub overflow: SharedOverflow,
+    pub trials: SharedTrials,
+    /// `DRY_RUN`'s simulated positions.
+    pub paper: SharedPaperBook,
+    /// `CHAOS_MODE`'s injector for market lookups and order posts.
+    pub chaos: Option<Arc<Chaos>>,
+}
+
+impl ExecutorState {
+    pub fn new(config: &EnvConfig, rpc: Arc<RpcClient>) -> Self {
+        Self {
+            processed_trades: Arc::new(Mutex::new(ProcessedTrades::load(
+                &config.processed_trades_path,
+                config.processed_trades_retention_hours,
+                chrono::Utc::now().timestamp(),
+            ))),
+            ledger: Arc::new(Mutex::new(PositionLedger::load(&config.state_dir))),
+            open_position_limiter: config
+                .max_open_positions
+                .map(|max| Arc::new(Mutex::new(OpenPositionLimiter::new(max)))),
+            consensus: config.consensus().map(|c| {
+                let mut book = ConsensusBook::load(&config.state_dir);
+                book.prune(c);
+                Arc::new(Mutex::new(book))
+            }),
+            journal: Journal::spawn(
+                JournalFiles::from_config(config),
+                RemoteJournal::from_config(config),
+                config.journal_buffer_rows,
+            ),
+            balance: Arc::new(Mutex::new(BalanceTracker::with_reserve(
+                config.balance_reserve_usd,
+            ))),
+            balance_cache: BalanceCache::from_config(config, rpc.clone()),
+            rpc,
+            skip_rules: Arc::new(SkipRuleEngine::from_config(config)),
+            activity: Arc::new(Mutex::new(TraderActivityBook::load(&config.state_dir))),
+            day_stats: Arc::new(Mutex::new(DayStats::load(&config.state_dir))),
+            daily_volume: Arc::new(Mutex::new(DailyVolumeTracker::load(
+                &config.state_dir,
+                config.daily_volume_reset_hour_utc,
+            ))),
+            overflow: Arc::new(Mutex::new(OverflowBook::load(&config.state_dir))),
+            trials: Arc::new(Mutex::new(TrialBook::load(&config.state_dir))),
+            paper: SharedPaperBook::default(),
+            chaos: Chaos::from_config(config),
+        }
+    }
+}
+
+/// What every copy runs with: the config, the API clients, the signer and the executor's
+/// state. Cheap to clone.
+#[derive(Clone)]
+pub struct ExecutorContext {
+    pub config: Arc<EnvConfig>,
+    pub http_client: Arc<reqwest::Client>,
+    pub clob_client: Arc<ClobClient<Authenticated<Normal>>>,
+    pub signer: Arc<Mutex<PrivateKeySigner>>,
+    pub state: ExecutorState,
+}
+
+/// Per-trade state shared by the decision steps, so the market snapshot is fetched at most
+/// once whether the copy ends up executed or skipped.
+#[derive(Default)]
+struct CopyContext {
+    market: Option<MarketContext>,
+    /// The last-trade and 1-hour price lookups, running while the order is placed.
+    prices: Option<tokio::task::JoinHandle<MarketContext>>,
+    /// Sized against a cached balance because the RPC was unreachable.
+    degraded_balance: bool,
+    /// Set when the trade is one leg of a neg-risk rebalance plan.
+    rebalance_id: Option<String>,
+    time_to_end_secs: Option<i64>,
+    /// Skip-rule evaluation so far, journaled with the outcome.
+    rule_trace: Vec<String>,
+    /// Lookups the shadow config reuses (`SHADOW_CONFIG_FILE`).
+    shadow: ShadowFacts,
+    sizing_steps: Vec<SizingStep>,
+    /// `OrderSizeCalculation::reasoning` for an executed BUY, for the copy notification.
+    sizing_reasoning: Option<String>,
+    /// Both USD amounts the payload gave for the trader's trade.
+    usdc_notional: Option<UsdcNotional>,
+    /// Started by hand rather than by a trader signal.
+    manual: bool,
+    /// Set for a close/trim typed at the terminal: the share of my position to sell.
+    manual_sell_fraction: Option<f64>,
+    /// Set when the live decision is journaled.
+    outcome: Option<CopyOutcome>,
+    /// Lookups done up front for a market seen for the first time.
+    prefetch: Option<PrefetchReport>,
+    /// Catch-up budget left (`CATCHUP_MAX_USD`): caps this BUY.
+    catch_up_cap_usd: Option<f64>,
+    /// What the trade did to the trader's position, once their positions are known.
+    position_action: Option<PositionAction>,
+}
+
+/// How a copy ended, as journaled.
+#[derive(Debug, Clone)]
+pub struct CopyOutcome {
+    pub status: JournalStatus,
+    pub reason: Option<String>,
+    pub fill: OrderFill,
+}
+
+impl CopyContext {
+    /// Starts the last-trade and 1-hour price lookups off the order path; `market` picks them
+    /// up when the decision is journaled.
+    fn watch_prices(
+        &mut self,
+        http_client: &Arc<reqwest::Client>,
+        config: &Arc<EnvConfig>,
+        asset: Option<&str>,
+    ) {
+        let Some(asset) = asset.filter(|_| config.journal_market_context) else {
+            return;
+        };
+        if self.market.is_some() || self.prices.is_some() {
+            return;
+        }
+        let (http_client, config, asset) = (http_client.clone(), config.clone(), asset.to_string());
+        self.prices = Some(tokio::spawn(async move {
+            fetch_price_context(&http_client, &config, &asset).await
+        }));
+    }
+
+    /// The snapshot journaled with the decision: the book as the order read it before our
+    /// own fill, with the prices `watch_prices` started. A decision that placed nothing reads
+    /// the book here.
+    async fn market(
+        &mut self,
+        http_client: &reqwest::Client,
+        config: &EnvConfig,
+        asset: Option<&str>,
+        fill: &OrderFill,
+    ) -> Option<MarketContext> {
+        if !config.journal_market_context {
+            return None;
+        }
+        if self.market.is_some() {
+            return self.market.clone();
+        }
+        let asset = asset?;
+        let prices = async {
+            match self.prices.take() {
+                Some(task) => task.await.unwrap_or_default(),
+                None => fetch_price_context(http_client, config, asset).await,
+            }
+        };
+        // A book that missed the prefetch deadline isn't fetched again.
+        let book_missed = self
+            .prefetch
+            .as_ref()
+            .is_some_and(|p| p.missed.iter().any(|m| m == "book"));
+        let book = async {
+            match &fill.book {
+                Some(book) => book.clone(),
+                None if book_missed => MarketContext::default(),
+                None => fetch_book_context(http_client, config, asset).await,
+            }
+        };
+        let (book, prices) = tokio::join!(book, prices);
+        self.market = Some(book.with_prices(&prices));
+        self.market.clone()
+    }
+}
+
+/// Restarts allowed per background task before it is left failed.
+const TASK_MAX_RESTARTS: u32 = 5;
+/// A BUY sized at more than this share of a cached balance reads the balance again first.
+const FORCE_BALANCE_REFRESH_SHARE: f64 = 0.8;
+/// How often an idle executor checks whether it should stop.
+const DRAIN_POLL: Duration = Duration::from_secs(1);
+/// How long `TradeExecutorHandle::stop` waits for the queued trades to be copied.
+const EXECUTOR_DRAIN: Duration = Duration::from_secs(30);
+/// How long a helper task gets to return once draining before it is cancelled.
+const HELPER_STOP_GRACE: Duration = Duration::from_secs(2);
 
-use crate::config::EnvConfig;
-use crate::types::{RtdsActivity, UserActivity, UserPosition};
-use crate::utils::{fetch_data, get_usdc_balance, post_order, Logger};
+/// The running executor; `stop` ends it. The trading state, journal and supervisor it runs
+/// on are process-wide, so one process runs one executor at a time.
+pub struct TradeExecutorHandle {
+    shutdown: CancellationToken,
+    balance_cache: BalanceCache,
+    trade_loop: TaskHandle,
+    /// Background tasks (builder, exits, watchdog, ...) that run until draining.
+    helpers: Vec<TaskHandle>,
+}
+
+impl TradeExecutorHandle {
+    /// The executor's USDC balance cache, for other readers of the balance.
+    pub fn balance_cache(&self) -> &BalanceCache {
+        &self.balance_cache
+    }
 
-type ProcessedTrades = Arc<Mutex<HashSet<String>>>;
+    /// Stops taking in trades and waits for the executor to finish. The trade being placed
+    /// finishes and the ones already queued are copied, for up to `EXECUTOR_DRAIN`; then the
+    /// trading state goes to draining and the helper tasks get `HELPER_STOP_GRACE` to end
+    /// their current round before they are cancelled.
+    pub async fn stop(self) {
+        supervisor().begin_shutdown();
+        self.shutdown.cancel();
+        Logger::info("Trade executor shutdown requested...");
+        if !self.trade_loop.join(EXECUTOR_DRAIN).await {
+            Logger::warning("Executor did not drain in time; cancelling it");
+        }
+        trading_state::set_draining(true);
+        let helpers = self.helpers.into_iter().map(|h| h.join(HELPER_STOP_GRACE));
+        futures_util::future::join_all(helpers).await;
+    }
+}
 
-static RUNNING: AtomicBool = AtomicBool::new(true);
+/// Inactivity decay and trial sizing for a BUY from `trader`.
+async fn ramp_sizing(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    trader: &str,
+    trial: Option<&TrialConfig>,
+    sizing: &mut SizingInputs,
+) {
+    if let Some(dc) = config.inactivity_decay() {
+        sizing.decay_multiplier = state.activity.lock().await.multiplier(trader, dc);
+    }
+    if let Some(tc) = trial {
+        sizing.trial_multiplier = Some(tc.size_multiplier);
+        sizing.cap_order_size(&config.copy_strategy_config, tc.max_order_size_usd);
+    }
+}
 
-pub fn stop_trade_executor() {
-    RUNNING.store(false, Ordering::SeqCst);
-    Logger::info("Trade executor shutdown requested...");
+/// Runs `place` unless `DRY_RUN` is on; a dry run sends nothing and reports an empty fill.
+pub(crate) async fn gated_order<F>(dry_run: bool, place: impl FnOnce() -> F) -> Result<OrderFill>
+where
+    F: std::future::Future<Output = Result<OrderFill>>,
+{
+    if dry_run {
+        Logger::info("Dry run - order not placed");
+        return Ok(OrderFill::default());
+    }
+    place().await
 }
 
 pub async fn execute_trade(
-    config: Arc<EnvConfig>,
+    ex: &ExecutorContext,
     activity: RtdsActivity,
     address: String,
-    http_client: Arc<reqwest::Client>,
-    clob_client: Arc<ClobClient<Authenticated<Normal>>>,
-    signer: Arc<Mutex<PrivateKeySigner>>,
-    processed_trades: ProcessedTrades,
 ) -> Result<()> {
-    let ts = activity.timestamp.unwrap_or(0);
-    let ts_ms = if ts > 1_000_000_000_000 {
-        ts
-    } else {
-        ts * 1000
+    copy_activity(ex, activity, address, &mut CopyContext::default()).await
+}
+
+/// Runs a copy started by hand (see `manual_copy`) through the same pipeline as a live
+/// signal. Its journal rows are tagged `manual`. Returns how the copy ended, or `None` when
+/// it was dropped before a decision (stale or duplicate).
+pub async fn execute_manual_copy(
+    ex: &ExecutorContext,
+    activity: RtdsActivity,
+    address: String,
+) -> Result<Option<CopyOutcome>> {
+    let mut ctx = CopyContext {
+        manual: true,
+        ..CopyContext::default()
+    };
+    copy_activity(ex, activity, address, &mut ctx).await?;
+    Ok(ctx.outcome)
+}
+
+/// Runs a missed trade found at startup (see `catch_up`) through the copy pipeline as a
+/// trader signal, with its BUY capped at `cap_usd`. Returns how the copy ended.
+async fn execute_catch_up(
+    ex: &ExecutorContext,
+    activity: RtdsActivity,
+    address: String,
+    cap_usd: Option<f64>,
+) -> Result<Option<CopyOutcome>> {
+    let mut ctx = CopyContext {
+        catch_up_cap_usd: cap_usd,
+        ..CopyContext::default()
+    };
+    copy_activity(ex, activity, address, &mut ctx).await?;
+    Ok(ctx.outcome)
+}
+
+/// Sells `fraction` of my position in `asset` through the copy pipeline, as asked for at the
+/// terminal (see `interactive`). The sell is attributed to the trader the ledger credits with
+/// the position and journaled `manual`.
+pub async fn execute_manual_sell(
+    ex: &ExecutorContext,
+    asset: &str,
+    fraction: f64,
+) -> Result<Option<CopyOutcome>> {
+    let ExecutorContext {
+        config,
+        http_client,
+        state,
+        ..
+    } = ex;
+    let positions = fetch_positions(http_client, config, &config.proxy_wallet).await?;
+    let Some(position) = positions.iter().find(|p| p.asset.as_deref() == Some(asset)) else {
+        anyhow::bail!("no longer holding {}", asset);
+    };
+    let (Some(shares), Some(price)) = (
+        position.size.filter(|s| *s > 0.0),
+        position.cur_price.filter(|p| *p > 0.0 && *p < 1.0),
+    ) else {
+        anyhow::bail!("position has no shares or no tradable price");
     };
-    let hours_ago = (chrono::Utc::now().timestamp_millis() - ts_ms) as f64 / (1000.0 * 3600.0);
-    if hours_ago > config.too_old_timestamp_hours as f64 {
+    let trader = state
+        .ledger
+        .lock()
+        .await
+        .position(asset)
+        .and_then(|p| p.trader.clone());
+    let address = trader
+        .and_then(|t| config.trader_members(&t).into_iter().next())
+        .or_else(|| config.user_addresses.first().cloned())
+        .unwrap_or_default();
+    let now = chrono::Utc::now().timestamp();
+    let condition_id = position.condition_id.clone().unwrap_or_default();
+    let activity = RtdsActivity {
+        proxy_wallet: Some(address.clone()),
+        timestamp: Some(now),
+        activity_type: Some("TRADE".to_string()),
+        size: Some(shares * fraction),
+        price: Some(price),
+        asset: Some(asset.to_string()),
+        side: Some("SELL".to_string()),
+        outcome_index: position.outcome_index,
+        title: position.title.clone(),
+        slug: position.slug.clone(),
+        icon: position.icon.clone(),
+        event_slug: position.event_slug.clone(),
+        outcome: position.outcome.clone(),
+        name: None,
+        transaction_hash: Some(format!("manual:sell:{}:{}", condition_id, now)),
+        condition_id: Some(condition_id),
+        reported_usdc_size: None,
+        backfilled: false,
+    };
+    let mut ctx = CopyContext {
+        manual: true,
+        manual_sell_fraction: Some(fraction),
+        ..CopyContext::default()
+    };
+    copy_activity(ex, activity, address, &mut ctx).await?;
+    Ok(ctx.outcome)
+}
+
+/// Terminal commands act on the running executor through its context.
+impl CommandHandler for ExecutorContext {
+    async fn positions(&self) -> Result<Vec<HeldPosition>> {
+        let positions =
+            fetch_positions(&self.http_client, &self.config, &self.config.proxy_wallet).await?;
+        let mut held: Vec<HeldPosition> = positions
+            .into_iter()
+            .filter(|p| p.size.unwrap_or(0.0) > 0.0)
+            .filter_map(|p| {
+                Some(HeldPosition {
+                    asset: p.asset?,
+                    condition_id: p.condition_id.unwrap_or_default(),
+                    title: p.title.unwrap_or_else(|| "Unknown market".to_string()),
+                    shares: p.size.unwrap_or(0.0),
+                    value_usd: p.current_value.unwrap_or(0.0),
+                })
+            })
+            .filter(|p| p.value_usd >= self.config.dust_threshold_usd)
+            .collect();
+        held.sort_by(|a, b| b.value_usd.total_cmp(&a.value_usd));
+        Ok(held)
+    }
+
+    async fn sell(&self, position: &HeldPosition, fraction: f64) -> Result<String> {
+        let outcome = execute_manual_sell(self, &position.asset, fraction).await?;
+        Ok(match outcome {
+            Some(o) if o.fill.tokens > 0.0 => format!(
+                "Sold {:.2} shares of {} for ${:.2}",
+                o.fill.tokens, position.title, o.fill.usd
+            ),
+            Some(o) => format!(
+                "Not sold: {}",
+                o.reason.as_deref().unwrap_or("order not filled")
+            ),
+            None => "Not sold: dropped before a decision".to_string(),
+        })
+    }
+}
+
+async fn copy_activity(
+    ex: &ExecutorContext,
+    activity: RtdsActivity,
+    address: String,
+    ctx: &mut CopyContext,
+) -> Result<()> {
+    let ExecutorContext {
+        config,
+        http_client,
+        clob_client,
+        signer,
+        state,
+    } = ex;
+    // Old signals are dropped before dedupe without a journal row, as they always were; the
+    // `stale` rule only decides whether this check runs.
+    if state.skip_rules.is_enabled("stale")
+        && signal_age_hours(activity.timestamp, chrono::Utc::now())
+            > config.too_old_timestamp_hours as f64
+    {
         return Ok(());
     }
 
@@ -45,52 +497,133 @@ pub async fn execute_trade(
     if tx_hash.is_empty() {
         return Ok(());
     }
-    
-    let trade_key = format!("{}:{}", address, tx_hash);
+
+    let trade_key = activity.dedupe_key(&address);
     {
-        let mut processed = processed_trades.lock().await;
-        if processed.contains(&trade_key) {
+        let mut processed = state.processed_trades.lock().await;
+        if !processed.record(&trade_key, chrono::Utc::now().timestamp()) {
+            digest::record_skip(&SkipReason::Duplicate);
             return Ok(());
         }
-        processed.insert(trade_key.clone());
-        if processed.len() > 1000 {
-            processed.clear();
-            processed.insert(trade_key);
+        if let Some(write) = processed.snapshot() {
+            write.spawn();
         }
     }
 
-    Logger::info(&format!(
-        "New trade detected for {} - executing immediately",
-        Logger::format_address(&address)
-    ));
+    if activity.backfilled {
+        Logger::info(&format!(
+            "Backfilled trade {} from {} (missed during an RTDS disconnect)",
+            tx_hash,
+            Logger::format_address(&address)
+        ));
+    }
+    // Whether or not it is copied, a fill of theirs at a resting copy's price is their order
+    // trading, not being cancelled.
+    if config.follow_trader_cancels && !ctx.manual {
+        resting_orders::note_trader_fill(&config.state_dir, &address, &activity);
+    }
 
-    let trade = UserActivity {
-        id: None,
-        proxy_wallet: activity.proxy_wallet.clone(),
-        timestamp: activity.timestamp,
-        condition_id: activity.condition_id.clone(),
-        activity_type: Some("TRADE".to_string()),
-        size: activity.size,
-        usdc_size: Some(activity.usdc_size()),
-        transaction_hash: activity.transaction_hash.clone(),
-        price: activity.price,
-        asset: activity.asset.clone(),
-        side: activity.side.clone(),
-        outcome_index: activity.outcome_index,
-        title: activity.title.clone(),
-        slug: activity.slug.clone(),
-        icon: activity.icon.clone(),
-        event_slug: activity.event_slug.clone(),
-        outcome: activity.outcome.clone(),
-        name: activity.name.clone(),
-        pseudonym: None,
-        bio: None,
-        profile_image: None,
-        profile_image_optimized: None,
-        bot: Some(false),
-        bot_executed_time: Some(0),
-        my_bought_size: None,
+    // Exposure, consensus and attribution key on the logical trader (TRADER_GROUPS).
+    let trader = config.trader_id(&address);
+    let notional = match activity.usd_value(config.usdc_size_preference) {
+        Ok(notional) => notional,
+        Err(reason) => {
+            Logger::warning(&format!("Ignoring trade {}: {}", tx_hash, reason));
+            return Ok(());
+        }
+    };
+    if notional.diverges(config.usdc_size_tolerance) {
+        Logger::warning(&format!(
+            "Trader USD amounts disagree for {}: usdcSize ${:.4} vs size × price ${:.4} - sizing from ${:.4}",
+            tx_hash,
+            notional.reported.unwrap_or(0.0),
+            notional.derived,
+            notional.chosen
+        ));
+    }
+    let trade = activity_to_trade(&activity, notional.chosen);
+    ctx.usdc_notional = Some(notional);
+    let signal_at = trade
+        .timestamp
+        .unwrap_or_else(|| chrono::Utc::now().timestamp());
+    // A manual copy says nothing about when the trader last traded.
+    let returned_after = if ctx.manual {
+        None
+    } else {
+        state
+            .activity
+            .lock()
+            .await
+            .observe(&trader, signal_at, config.inactivity_decay())
     };
+    if let Some(days) = returned_after {
+        Logger::info(&format!(
+            "{} is back after {:.0} days without trading - easing in on the next BUY copies",
+            Logger::format_address(&trader),
+            days
+        ));
+    }
+    let market_override = market_overrides::get(trade.condition_id.as_deref());
+    let signal = state.skip_rules.evaluate(
+        RuleStage::Signal,
+        &RuleInput {
+            config,
+            trade: &trade,
+            trader: &address,
+            now: chrono::Utc::now(),
+            balance: None,
+            balance_breakdown: None,
+            open_positions: None,
+            market_end: None,
+            position_action: None,
+            market_override: market_override.clone(),
+            manual: ctx.manual,
+        },
+    );
+    let skipped = signal.skipped();
+    ctx.rule_trace.extend(signal.trace);
+    if let Some(skip) = skipped {
+        // Price band skips are a filter the user set, so they're surfaced louder.
+        let log = if signal.skipped_by == Some("price_band") {
+            Logger::warning
+        } else {
+            Logger::info
+        };
+        log(&format!(
+            "Skipping trade from {}: {}",
+            Logger::format_address(&address),
+            skip
+        ));
+        journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip)).await;
+        return Ok(());
+    }
+
+    review_trial(state, config, &trader).await;
+    let trial = state.trials.lock().await.get(&trader).cloned();
+    // Exits from a dropped trader's positions still copy; a manual copy is my own call.
+    let dropped = trial
+        .as_ref()
+        .is_some_and(|t| t.status == TrialStatus::Dropped);
+    if dropped && trade.side.as_deref() == Some("BUY") && !ctx.manual {
+        Logger::info(&format!(
+            "Skipping BUY from {}: trader dropped",
+            Logger::format_address(&address)
+        ));
+        journal_trade(
+            ex,
+            ctx,
+            &trade,
+            &address,
+            OrderFill::default(),
+            Some(SkipReason::TraderDropped.into()),
+        )
+        .await;
+        return Ok(());
+    }
+    // Trial sizing only applies while trial mode is configured.
+    let trial = trial.filter(|t| t.is_restricted()).and(config.trial());
+
+    let _total = profiling::stage("copy_total");
 
     Logger::trade(
         &address,
@@ -106,132 +639,2950 @@ pub async fn execute_trade(
             title: trade.title.clone(),
         },
     );
+    event_webhook::emit(BotEvent::TradeDetected {
+        trade: EventTrade::new(&trader, &trade),
+        trader_usd: trade.usdc_size,
+        price: trade.price,
+    });
+    if let Some(note) = market_override.as_ref().and_then(|m| m.note.as_deref()) {
+        Logger::info(&format!("📝 Market note: {}", note));
+    }
 
-    let my_positions_url = format!(
-        "https://data-api.polymarket.com/positions?user={}",
-        config.proxy_wallet
-    );
-    let user_positions_url = format!(
-        "https://data-api.polymarket.com/positions?user={}",
-        address
-    );
-
-    let my_positions_data: serde_json::Value = fetch_data(
-        &http_client,
-        &my_positions_url,
-        config.request_timeout_ms,
-        config.network_retry_limit,
-    )
-    .await?;
-    let user_positions_data: serde_json::Value = fetch_data(
-        &http_client,
-        &user_positions_url,
-        config.request_timeout_ms,
-        config.network_retry_limit,
-    )
-    .await?;
-
-    let my_positions: Vec<UserPosition> = if let Some(arr) = my_positions_data.as_array() {
-        arr.iter()
-            .filter_map(|p| serde_json::from_value::<UserPosition>(p.clone()).ok())
-            .collect()
-    } else {
-        Vec::new()
-    };
+    let condition_id = trade.condition_id.as_deref();
+    let prefetched_end = prefetch_new_market(config, http_client, state, ctx, &trade).await;
 
-    let user_positions: Vec<UserPosition> = if let Some(arr) = user_positions_data.as_array() {
-        arr.iter()
-            .filter_map(|p| serde_json::from_value::<UserPosition>(p.clone()).ok())
-            .collect()
+    // My own positions only matter for sizing when I already hold this market (position cap,
+    // sells) or when a recent fill / reconciliation left the ledger unsure about it.
+    let positions_timer = profiling::stage("positions_fetch");
+    let fetch_my_positions = ctx.manual_sell_fraction.is_some()
+        || state.ledger.lock().await.needs_position_fetch(condition_id);
+    let data_api = DataApiClient::from_config(config, http_client);
+    let my_positions: Vec<UserPosition> = if fetch_my_positions {
+        let positions = data_api.get_positions(&config.proxy_wallet).await?;
+        let mut ledger = state.ledger.lock().await;
+        ledger.reconcile(&positions);
+        if let Some(cid) = condition_id {
+            ledger.clear_dirty(cid);
+        }
+        positions
     } else {
         Vec::new()
     };
-
-    let condition_id = trade.condition_id.as_deref();
-    let my_position = my_positions
-        .iter()
-        .find(|p| p.condition_id.as_deref() == condition_id);
-    let user_position = user_positions
-        .iter()
-        .find(|p| p.condition_id.as_deref() == condition_id);
-
-    let my_balance = get_usdc_balance(
-        &config.rpc_url,
-        &config.usdc_contract_address,
-        &config.proxy_wallet,
+    let user_positions = data_api.get_positions(&address).await?;
+    let user_balance = trader_portfolio_value(
+        config,
+        &trader,
+        &address,
+        &user_positions,
+        chrono::Utc::now().timestamp(),
+        |member| {
+            let data_api = data_api.clone();
+            async move { Ok(data_api.get_positions(&member).await?) }
+        },
     )
-    .await
-    .unwrap_or(0.0);
+    .await?;
+    drop(positions_timer);
 
-    let user_balance: f64 = user_positions
-        .iter()
-        .map(|p| p.current_value.unwrap_or(0.0))
-        .sum();
+    if !ctx.manual {
+        let side = trade.side.as_deref().unwrap_or("");
+        let size = trade.size.unwrap_or(0.0);
+        let held_after = position_in(&user_positions, trade.asset.as_deref()).and_then(|p| p.size);
+        let before = position_action::position_before(side, size, held_after);
+        ctx.position_action = position_action::classify(before, side, size);
+        ctx.shadow.position_action = ctx.position_action;
+        if let Some(action) = ctx.position_action {
+            Logger::info(&format!(
+                "Trader {}s: {:.2} tokens held before this {:.2}-token {}",
+                action,
+                before,
+                size,
+                side.to_uppercase()
+            ));
+        }
+    }
 
-    Logger::balance(my_balance, user_balance, &address);
+    // By outcome token: in a market where both outcomes are held, the other one's size would
+    // skew the sell fraction and what there is to sell.
+    let mut my_position = position_in(&my_positions, trade.asset.as_deref());
+    let mut user_position = position_in(&user_positions, trade.asset.as_deref());
+    // A terminal close/trim sells its fraction of my position outright: with no trader
+    // position to compare against, the SELL strategy sells everything it is given.
+    let trimmed: Option<UserPosition>;
+    if let Some(fraction) = ctx.manual_sell_fraction {
+        trimmed = position_in(&my_positions, trade.asset.as_deref()).map(|p| UserPosition {
+            size: p.size.map(|s| s * fraction),
+            ..p.clone()
+        });
+        my_position = trimmed.as_ref();
+        user_position = None;
+    }
+    // A dry run trades against its simulated positions, not the wallet's.
+    let simulated: Option<UserPosition>;
+    if config.dry_run {
+        let fraction = ctx.manual_sell_fraction.unwrap_or(1.0);
+        simulated = state
+            .paper
+            .lock()
+            .await
+            .position(trade.asset.as_deref().unwrap_or_default())
+            .map(|p| UserPosition {
+                size: p.size.map(|s| s * fraction),
+                ..p
+            });
+        my_position = simulated.as_ref();
+    }
 
     let condition = if trade.side.as_deref().unwrap_or("") == "BUY" {
         "buy"
     } else {
         "sell"
     };
+    if let (Some(asset), "sell") = (trade.asset.as_deref(), condition) {
+        for (order, events) in
+            resting_orders::cancel_on_trader_exit(&config.state_dir, clob_client, asset, &trader)
+                .await
+        {
+            settle_resting(state, config, &order, &events).await;
+        }
+        for mut build in position_builder::exited(&config.state_dir, asset, &trader) {
+            let mut events = position_builder::collect_order(clob_client, &mut build).await;
+            build.end(EndReason::TraderExit, chrono::Utc::now().timestamp());
+            events.push(BuildEvent::Ended(EndReason::TraderExit));
+            position_builder::save(&config.state_dir, &build);
+            settle_build(state, config, &build, &events).await;
+        }
+    }
 
-    let mut signer_guard = signer.lock().await;
-    post_order(
-        &config,
-        &clob_client,
-        condition,
-        my_position,
-        user_position,
-        &trade,
-        my_balance,
-        user_balance,
-        &address,
-        &http_client,
-        &mut *signer_guard,
-    )
-    .await?;
+    let balance_timer = profiling::stage("balance_fetch");
+    let mut cached_balance = state.balance_cache.get().await;
+    // A BUY that would spend most of a cached balance is sized against a fresh read.
+    if let (Ok(cached), "buy") = (&cached_balance, condition) {
+        let current_value = my_position
+            .map(|p| p.size.unwrap_or(0.0) * p.avg_price.unwrap_or(0.0))
+            .unwrap_or(0.0);
+        let estimate = calculate_order_size(
+            &config.copy_strategy_config,
+            &SizingInputs::default(),
+            trade.usdc_size.unwrap_or(0.0),
+            cached.usd,
+            current_value,
+        );
+        if cached.source == BalanceSource::Cache
+            && estimate.final_amount > cached.usd * FORCE_BALANCE_REFRESH_SHARE
+        {
+            cached_balance = state.balance_cache.force_refresh().await;
+        }
+    }
+    let fetched_balance = tracker_reading(cached_balance);
+    drop(balance_timer);
+    let reading = state
+        .balance
+        .lock()
+        .await
+        .observe(fetched_balance, config.balance_max_staleness_secs);
+    let balance_breakdown = match reading {
+        BalanceReading::Fresh(balance) => Some(split_balance(state, config, balance).await),
+        _ => None,
+    };
+    let my_balance = match reading {
+        BalanceReading::Fresh(_) => balance_breakdown
+            .map(|b| b.for_sizing(config.sizing_balance))
+            .unwrap_or(0.0),
+        BalanceReading::Degraded { balance, age_secs } => {
+            ctx.degraded_balance = true;
+            Logger::warning(&format!(
+                "Degraded balance mode: RPC unavailable, sizing against {:.0}% of last known ${:.2} ({}s old), max order ${:.2}",
+                config.degraded_balance_fraction * 100.0,
+                balance,
+                age_secs,
+                config.degraded_max_order_size_usd
+            ));
+            let breakdown = split_balance(state, config, balance).await;
+            breakdown.for_sizing(config.sizing_balance) * config.degraded_balance_fraction
+        }
+        BalanceReading::Unavailable => 0.0,
+    };
 
-    Logger::separator();
-    Ok(())
-}
+    Logger::balance(my_balance, user_balance, &address);
 
-pub async fn run_trade_executor(
-    config: Arc<EnvConfig>,
-    http_client: Arc<reqwest::Client>,
-    clob_client: Arc<ClobClient<Authenticated<Normal>>>,
-    signer: Arc<Mutex<PrivateKeySigner>>,
-    mut rx: tokio::sync::mpsc::Receiver<(RtdsActivity, String)>,
-) -> Result<()> {
-    RUNNING.store(true, Ordering::SeqCst);
-    let processed_trades: ProcessedTrades = Arc::new(Mutex::new(HashSet::new()));
+    ctx.shadow.balance = Some(reading);
+    ctx.shadow.trader_portfolio_usd = Some(user_balance);
+    ctx.shadow.current_value = my_position
+        .map(|p| p.size.unwrap_or(0.0) * p.avg_price.unwrap_or(0.0))
+        .unwrap_or(0.0);
+    let mut open_positions = None;
+    if condition == "buy" {
+        let (opens_new, open_count) = {
+            let ledger = state.ledger.lock().await;
+            (ledger.opens_new_position(condition_id), ledger.open_count())
+        };
+        ctx.shadow.open_positions = Some((opens_new, open_count));
+        if let Some(limiter) = &state.open_position_limiter {
+            if opens_new && state.skip_rules.is_enabled("open_positions") {
+                let mut limiter = limiter.lock().await;
+                open_positions = Some(OpenPositionCheck {
+                    allowed: limiter.allows_new_position(open_count),
+                    open_count,
+                    max: limiter.max(),
+                });
+            }
+        }
+    }
 
-    Logger::success("Trade executor started - ready to execute trades");
+    let mut end_time = if condition == "buy"
+        && config.resolution_window_minutes() > 0
+        && state.skip_rules.is_enabled("resolution_window")
+    {
+        match prefetched_end {
+            Some(end) => end,
+            None => market_end_time(http_client, config, user_position, condition_id).await,
+        }
+    } else {
+        None
+    };
+    ctx.shadow.market_end = end_time;
 
-    while RUNNING.load(Ordering::SeqCst) {
-        match rx.recv().await {
-            Some((activity, address)) => {
-                if let Err(e) = execute_trade(
-                    config.clone(),
-                    activity,
-                    address,
-                    http_client.clone(),
-                    clob_client.clone(),
-                    signer.clone(),
-                    processed_trades.clone(),
-                )
+    let position = state.skip_rules.evaluate(
+        RuleStage::Position,
+        &RuleInput {
+            config,
+            trade: &trade,
+            trader: &address,
+            now: chrono::Utc::now(),
+            balance: Some(reading),
+            balance_breakdown,
+            open_positions,
+            market_end: end_time,
+            position_action: ctx.position_action,
+            market_override: market_override.clone(),
+            manual: ctx.manual,
+        },
+    );
+    let skipped = position.skipped();
+    ctx.rule_trace.extend(position.trace);
+    if let Some(skip) = skipped {
+        Logger::warning(&format!("Skipping {}: {}", condition.to_uppercase(), skip));
+        journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip)).await;
+        Logger::separator();
+        return Ok(());
+    }
+
+    let mut consensus_size: Option<f64> = None;
+    let mut close_all = false;
+    if let (Some(cc), Some(book), Some(asset)) =
+        (config.consensus(), &state.consensus, trade.asset.as_deref())
+    {
+        if condition == "buy" {
+            let current_value = my_position
+                .map(|p| p.size.unwrap_or(0.0) * p.avg_price.unwrap_or(0.0))
+                .unwrap_or(0.0);
+            let inputs = SizingInputs {
+                trader_portfolio_usd: Some(user_balance),
+                ..SizingInputs::default()
+            };
+            let copy_usd = calculate_order_size(
+                &config.copy_strategy_config,
+                &inputs,
+                trade.usdc_size.unwrap_or(0.0),
+                my_balance,
+                current_value,
+            )
+            .final_amount;
+            let signalled = book.lock().await.signalled_traders(asset);
+            let holders =
+                consensus_holders(http_client, config, cc, &trader, asset, &signalled).await;
+            let decision = book
+                .lock()
                 .await
-                {
-                    Logger::error(&format!("Error executing trade: {}", e));
+                .register_buy(cc, asset, &trade, &trader, copy_usd, &holders);
+            match decision {
+                BuyDecision::Watch { agreeing } => {
+                    Logger::info(&format!(
+                        "👀 Watching {}: {}/{} traders agree - waiting for consensus",
+                        trade.title.as_deref().unwrap_or(asset),
+                        agreeing,
+                        cc.threshold
+                    ));
+                    let skip = Skip::new(
+                        SkipReason::ConsensusPending,
+                        format!("{}/{}", agreeing, cc.threshold),
+                    );
+                    journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip))
+                        .await;
+                    Logger::separator();
+                    return Ok(());
+                }
+                BuyDecision::AlreadyEntered => {
+                    Logger::info("Consensus position already entered - not copying this BUY again");
+                    journal_trade(
+                        ex,
+                        ctx,
+                        &trade,
+                        &address,
+                        OrderFill::default(),
+                        Some(SkipReason::ConsensusEntered.into()),
+                    )
+                    .await;
+                    Logger::separator();
+                    return Ok(());
                 }
+                BuyDecision::Copy {
+                    size_usd,
+                    contributors,
+                } => {
+                    Logger::success(&format!(
+                        "Consensus reached: {} traders agree - copying ${:.2}",
+                        contributors.len(),
+                        size_usd
+                    ));
+                    consensus_size = Some(size_usd);
+                }
+            }
+        } else {
+            let decision = book.lock().await.on_sell(cc, asset, &trader);
+            match decision {
+                SellDecision::PassThrough | SellDecision::Follow => {}
+                SellDecision::CloseAll => {
+                    Logger::info("Exit consensus reached - closing the whole position");
+                    close_all = true;
+                }
+                SellDecision::Skip(reason) => {
+                    Logger::info(&format!("Skipping SELL: {}", reason));
+                    journal_trade(
+                        ex,
+                        ctx,
+                        &trade,
+                        &address,
+                        OrderFill::default(),
+                        Some(Skip::new(SkipReason::ConsensusExit, reason)),
+                    )
+                    .await;
+                    Logger::separator();
+                    return Ok(());
+                }
+            }
+        }
+    }
+    let strategy = &config.copy_strategy_config;
+    let mut sizing = consensus_size.map_or_else(SizingInputs::default, SizingInputs::fixed);
+    if strategy.strategy == CopyStrategy::PortfolioShare {
+        sizing.trader_portfolio_usd = Some(user_balance);
+    }
+    // A market's own cap replaces MAX_ORDER_SIZE_USD; the caps below only tighten it.
+    if let Some(cap) = market_override.as_ref().and_then(|m| m.cap_usd) {
+        sizing.max_order_size_usd = Some(cap);
+        Logger::info(&format!("Market override: capped at ${:.2} per order", cap));
+    }
+    if let Some(cap) = position.max_order_size_usd {
+        sizing.cap_order_size(strategy, cap);
+    }
+    if let (Some(cap), "buy") = (ctx.catch_up_cap_usd, condition) {
+        sizing.cap_order_size(strategy, cap);
+    }
+    if condition == "buy" {
+        ramp_sizing(state, config, &trader, trial, &mut sizing).await;
+    }
+    let decay = sizing.decay_multiplier;
+    if let Some(m) = decay {
+        Logger::info(&format!(
+            "Inactivity decay: copying at {:.0}% of the usual size",
+            m * 100.0
+        ));
+    }
+    if let (Some(tc), "buy") = (trial, condition) {
+        Logger::info(&format!(
+            "Trader on trial: copying at {:.0}% of the usual size, max ${:.2} per order",
+            tc.size_multiplier * 100.0,
+            tc.max_order_size_usd
+        ));
+    }
+    let prefetch_degraded = ctx.prefetch.as_ref().is_some_and(|p| p.is_degraded());
+    if prefetch_degraded && condition == "buy" && config.prefetch_degraded_multiplier < 1.0 {
+        sizing.prefetch_multiplier = Some(config.prefetch_degraded_multiplier);
+    }
+    if let (Some(cap), "buy") = (strategy.max_daily_volume_usd, condition) {
+        let left = state
+            .daily_volume
+            .lock()
+            .await
+            .remaining(cap, chrono::Utc::now().timestamp());
+        if left < strategy.min_order_size_usd {
+            let skip = Skip::new(
+                SkipReason::DailyVolume,
+                format!(
+                    "MAX_DAILY_VOLUME_USD reached (${:.2} of ${:.2})",
+                    cap - left,
+                    cap
+                ),
+            );
+            Logger::warning(&format!("Skipping BUY: {}", skip));
+            journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip)).await;
+            Logger::separator();
+            return Ok(());
+        }
+        Logger::info(&format!("Daily volume: ${:.2} of ${:.2} left", left, cap));
+        sizing.daily_volume_left_usd = Some(left);
+    }
+    if let (Some(ms), "buy", Some(cid)) = (config.market_share(), condition, condition_id) {
+        let figures = market_share::market_figures(http_client, config, cid).await;
+        match market_share::share_limit(ms, figures.as_ref()) {
+            ShareLimit::Cap { max_position_usd } => {
+                sizing.market_share_cap_usd = Some(max_position_usd);
+            }
+            // Gamma didn't say: copy uncapped rather than stall on a lookup.
+            ShareLimit::Unknown => Logger::warning(
+                "Market open interest unknown - MAX_MARKET_SHARE_PERCENT not applied",
+            ),
+            limit @ ShareLimit::TooThin { .. } => {
+                let skip = Skip::new(
+                    SkipReason::MarketShare,
+                    limit.skip_reason(ms).unwrap_or_default(),
+                );
+                Logger::warning(&format!("Skipping BUY: {}", skip));
+                journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip)).await;
+                Logger::separator();
+                return Ok(());
+            }
+        }
+    }
+    if let ("buy", Some(cid), Some(cap)) = (condition, condition_id, strategy.max_position_size_usd)
+    {
+        let calc = calculate_order_size(
+            strategy,
+            &sizing,
+            trade.usdc_size.unwrap_or(0.0),
+            my_balance,
+            ctx.shadow.current_value,
+        );
+        if let Some(cut) = position_cap_cut(&calc.steps) {
+            let crossed = state.overflow.lock().await.record(
+                cid,
+                &trade,
+                &address,
+                cut,
+                cap,
+                config.overflow_notify_usd,
+            );
+            if let Some(ignored) = crossed {
+                Logger::warning(&format!(
+                    "Position cap has kept ${:.2} of trader adds out of {} - raise MAX_POSITION_SIZE_USD to follow them",
+                    ignored,
+                    trade.title.as_deref().unwrap_or(cid)
+                ));
+            }
+        }
+    }
+
+    // The book is read by the order itself, before our own fill moves it.
+    ctx.watch_prices(http_client, config, trade.asset.as_deref());
+
+    // Asked again at the order: the state can change while a signal waits on consensus or a
+    // rebalance window, and SKIP_RULES may leave the trading_state rule out.
+    if let Err(skip) = trading_state::check(&condition.to_uppercase()) {
+        Logger::warning(&format!("Order not placed: {}", skip));
+        journal_trade(ex, ctx, &trade, &address, OrderFill::default(), Some(skip)).await;
+        Logger::separator();
+        return Ok(());
+    }
+
+    // POSITION_BUILD_MODE: the sized copy becomes a target for the position builder. Manual
+    // copies and dry runs stay immediate.
+    let building = condition == "buy"
+        && !ctx.manual
+        && !config.dry_run
+        && config.position_build().is_some_and(|b| b.applies_to(&trader));
+    let entry_price = trade.price.filter(|p| *p > 0.0 && *p < 1.0);
+    if let (true, Some(asset), Some(anchor_price)) = (building, trade.asset.as_deref(), entry_price)
+    {
+        // Whatever active builds still mean to buy here counts toward the caps already.
+        let pending = position_builder::pending_usd(&config.state_dir, asset);
+        let calc = calculate_order_size(
+            strategy,
+            &sizing,
+            trade.usdc_size.unwrap_or(0.0),
+            my_balance,
+            ctx.shadow.current_value + pending,
+        );
+        let new = Build {
+            id: tx_hash.to_string(),
+            asset: asset.to_string(),
+            condition_id: condition_id.unwrap_or_default().to_string(),
+            trader: trader.clone(),
+            title: trade.title.clone(),
+            outcome: trade.outcome.clone(),
+            target_usd: calc.final_amount,
+            filled_usd: 0.0,
+            filled_tokens: 0.0,
+            anchor_price,
+            started_at: chrono::Utc::now().timestamp(),
+            open_order: None,
+            ended_at: None,
+            end_reason: None,
+        };
+        let started = if calc.below_minimum {
+            None
+        } else {
+            position_builder::start(&config.state_dir, new)
+        };
+        if let Some((build, event)) = started {
+            position_builder::report(&build, &event);
+            ctx.sizing_steps = calc.steps;
+            let verb = match event {
+                BuildEvent::Extended { .. } => "extended",
+                _ => "started",
+            };
+            let skip = Skip::new(
+                SkipReason::Working,
+                format!("position build {}: target ${:.2}", verb, build.target_usd),
+            );
+            let working = OrderFill {
+                resting: true,
+                ..OrderFill::default()
+            };
+            journal_trade(ex, ctx, &trade, &address, working, Some(skip)).await;
+            Logger::separator();
+            return Ok(());
+        }
+    }
+
+    let paper_order = if config.dry_run {
+        let mut book = state.paper.lock().await;
+        if let (Some(asset), Some(price)) = (trade.asset.as_deref(), trade.price) {
+            book.mark(asset, price);
+        }
+        let sized = paper::size_order(
+            config,
+            &sizing,
+            condition,
+            my_position,
+            if close_all { None } else { user_position },
+            &trade,
+            my_balance,
+        );
+        match &sized {
+            Ok(order) => {
+                Logger::warning(&format!(
+                    "🧪 DRY RUN: would {} - {}",
+                    order.describe(),
+                    order.reasoning
+                ));
+                book.record(order);
+            }
+            Err(reason) => Logger::info(&format!("🧪 DRY RUN: no order - {}", reason)),
+        }
+        Some(sized)
+    } else {
+        None
+    };
+    let fill = gated_order(config.dry_run, || async {
+        if condition == "sell" {
+            crate::ctf_approval::check_before_sell(config).await;
+        }
+        let _timer = profiling::stage("post_order");
+        let signer = signer.lock().await;
+        let order = OrderContext {
+            config,
+            clob_client,
+            signer: &signer,
+            http_client,
+            chaos: state.chaos.as_deref(),
+            sizing,
+        };
+        let placed = post_order(
+            &order,
+            condition,
+            my_position,
+            if close_all { None } else { user_position },
+            &trade,
+            my_balance,
+            &trader,
+        )
+        .await;
+        state.balance_cache.invalidate().await;
+        placed
+    })
+    .await
+    .inspect_err(|e| {
+        let error = format!("{:#}", e);
+        alerts::warn(&alerts::failure_message(&trader, &trade, condition, &error));
+        event_webhook::emit(BotEvent::OrderFailed {
+            trade: EventTrade::new(&trader, &trade),
+            error,
+        });
+    })?;
+
+    if fill.tokens > 0.0 {
+        digest::record_copy();
+        if let Some(asset) = trade.asset.as_deref() {
+            let mut ledger = state.ledger.lock().await;
+            if condition == "buy" {
+                ledger.record_buy(
+                    asset,
+                    condition_id.unwrap_or_default(),
+                    Some(&trader),
+                    fill.tokens,
+                    fill.usd,
+                );
+            } else {
+                ledger.record_sell(asset, fill.tokens);
             }
-            None => {
-                Logger::warning("Trade channel closed");
-                break;
+        }
+        if let (Some(dc), Some(_)) = (config.inactivity_decay(), decay) {
+            state.activity.lock().await.record_copy(&trader, dc);
+        }
+        if let (Some(_), "buy") = (trial, condition) {
+            let copied = {
+                let mut trials = state.trials.lock().await;
+                trials.record_copy(&trader);
+                trials.get(&trader).cloned()
+            };
+            // Every trial copy is notified, whatever NOTIFY_MIN_ORDER_USD says.
+            if let Some(t) = copied {
+                let line = format!(
+                    "Trial copy by {}: ${:.2} ({})",
+                    Logger::format_address(&trader),
+                    fill.usd,
+                    t.label(chrono::Utc::now().timestamp())
+                );
+                Logger::warning(&line);
+                alerts::notify(&line);
             }
+            review_trial(state, config, &trader).await;
+        }
+        if end_time.is_none() {
+            end_time = market_end_time(http_client, config, user_position, condition_id).await;
+        }
+        ctx.time_to_end_secs = end_time.map(|end| (end - chrono::Utc::now()).num_seconds());
+        if condition == "buy" {
+            // Same inputs as the BUY strategy used, so the steps match the order it sized.
+            let sized = calculate_order_size(
+                strategy,
+                &sizing,
+                trade.usdc_size.unwrap_or(0.0),
+                my_balance,
+                ctx.shadow.current_value,
+            );
+            ctx.sizing_steps = sized.steps;
+            ctx.sizing_reasoning = Some(sized.reasoning);
+        }
+    }
+
+    let skip = if let Some(sized) = &paper_order {
+        let detail = match sized {
+            Ok(order) => format!("order not placed: would {}", order.describe()),
+            Err(reason) => format!("order not placed: {}", reason),
+        };
+        Some(Skip::new(SkipReason::DryRun, detail))
+    } else if fill.resting {
+        Some(Skip::new(SkipReason::Working, "resting order placed"))
+    } else if fill.slipped {
+        Some(Skip::new(
+            SkipReason::Slippage,
+            format!("book moved past MAX_SLIPPAGE_PERCENT={}", config.max_slippage_percent),
+        ))
+    } else {
+        (fill.tokens <= 0.0).then(|| SkipReason::NotFilled.into())
+    };
+    let copied = skip.is_none();
+    journal_trade(ex, ctx, &trade, &address, fill.clone(), skip).await;
+    let side = condition.to_uppercase();
+    // A partial fill left resting or cut short by slippage moved money all the same.
+    let mut balance = None;
+    if fill.tokens > 0.0 {
+        state
+            .daily_volume
+            .lock()
+            .await
+            .record(fill.usd, true, chrono::Utc::now().timestamp());
+        let mut tracker = state.balance.lock().await;
+        tracker.apply_fill(if condition == "buy" {
+            -fill.usd
+        } else {
+            fill.usd
+        });
+        balance = tracker.last();
+    }
+    if fill.tokens > 0.0 && !copied {
+        state.day_stats.lock().await.record_fill(&side, fill.usd);
+    }
+    if copied && fill.tokens > 0.0 {
+        let (number, deployed_usd, skipped) = {
+            let mut stats = state.day_stats.lock().await;
+            let number = stats.record_copy(&side, fill.usd);
+            (number, stats.deployed_usd, stats.skipped)
+        };
+        let summary = CopySummary {
+            number,
+            side,
+            outcome: trade.outcome.clone(),
+            usd: fill.usd,
+            price: fill.usd / fill.tokens,
+            deployed_usd,
+            skipped,
+            balance,
+        };
+        Logger::copy_summary(&summary);
+        let reasoning = ctx.sizing_reasoning.take();
+        status_api::record_copy(CopiedTrade {
+            reasoning: reasoning.clone(),
+            ..CopiedTrade::new(&trader, &trade, fill.usd, fill.tokens)
+        });
+        alerts::copy_placed(alerts::copy_message(&trader, &trade, &summary, reasoning));
+    } else if fill.rejected && fill.tokens <= 0.0 {
+        let reason = "Rejected by the exchange, no attempts left; see the log for the errors";
+        alerts::warn(&alerts::failure_message(&trader, &trade, condition, reason));
+        event_webhook::emit(BotEvent::OrderFailed {
+            trade: EventTrade::new(&trader, &trade),
+            error: reason.to_string(),
+        });
+    }
+
+    if let (Some(book), Some(asset)) = (&state.consensus, trade.asset.as_deref()) {
+        if consensus_size.is_some() && fill.tokens <= 0.0 {
+            book.lock().await.reset_entry(asset);
+        } else if condition == "sell" && state.ledger.lock().await.position(asset).is_none() {
+            book.lock().await.close(asset);
+        }
+    }
+    if let (Some(asset), Some(cid), "sell") = (trade.asset.as_deref(), condition_id, condition) {
+        if fill.tokens > 0.0 && state.ledger.lock().await.position(asset).is_none() {
+            state.overflow.lock().await.clear(cid);
         }
     }
 
+    Logger::separator();
     Ok(())
 }
 
+async fn journal_trade(
+    ex: &ExecutorContext,
+    ctx: &mut CopyContext,
+    trade: &UserActivity,
+    address: &str,
+    fill: OrderFill,
+    skip: Option<Skip>,
+) {
+    let ExecutorContext {
+        config,
+        http_client,
+        state,
+        ..
+    } = ex;
+    let trader = config.trader_id(address);
+    let trader_member = (trader != address.to_lowercase()).then(|| address.to_lowercase());
+    let _timer = profiling::stage("journal");
+    if let (Some(skip), false) = (&skip, fill.resting) {
+        state.day_stats.lock().await.record_skip();
+        digest::record_skip(&skip.reason);
+    }
+    let market = ctx
+        .market(http_client, config, trade.asset.as_deref(), &fill)
+        .await;
+    let status = if skip.is_some() {
+        JournalStatus::Skipped
+    } else {
+        JournalStatus::Executed
+    };
+    let reason = skip.as_ref().map(Skip::to_string);
+    // A rejected copy already went out as `order_failed`.
+    let event = match &skip {
+        Some(skip) if !fill.rejected => Some(BotEvent::OrderSkipped {
+            trade: EventTrade::new(&trader, trade),
+            skip_reason: skip.reason.clone(),
+            detail: skip.detail.clone(),
+        }),
+        None if fill.tokens > 0.0 => Some(BotEvent::OrderPlaced {
+            trade: EventTrade::new(&trader, trade),
+            my_usd: fill.usd,
+            my_tokens: fill.tokens,
+            price: fill.usd / fill.tokens,
+        }),
+        _ => None,
+    };
+    if let Some(event) = event {
+        event_webhook::emit(event);
+    }
+    ctx.outcome = Some(CopyOutcome {
+        status,
+        reason: reason.clone(),
+        fill: fill.clone(),
+    });
+    state
+        .journal
+        .record(JournalEntry {
+            schema_version: JOURNAL_SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().timestamp(),
+            status,
+            slug: trade.slug.clone(),
+            condition_id: trade.condition_id.clone(),
+            asset: trade.asset.clone(),
+            side: trade.side.clone(),
+            position_action: ctx.position_action,
+            trader_usd: trade.usdc_size,
+            trader_usd_reported: ctx.usdc_notional.and_then(|n| n.reported),
+            trader_usd_derived: ctx.usdc_notional.map(|n| n.derived),
+            my_usd: fill.usd,
+            my_tokens: fill.tokens,
+            tx_hash: trade.transaction_hash.clone(),
+            reason,
+            skip_reason: skip.map(|s| s.reason),
+            degraded_balance: ctx.degraded_balance,
+            market,
+            rebalance_id: ctx.rebalance_id.clone(),
+            time_to_end_secs: ctx.time_to_end_secs,
+            rule_trace: std::mem::take(&mut ctx.rule_trace),
+            trader_member: trader_member.clone(),
+            trader: trader.clone(),
+            shadow: false,
+            sizing_steps: std::mem::take(&mut ctx.sizing_steps),
+            manual: ctx.manual,
+            prefetch: ctx.prefetch.clone(),
+            order_ids: fill.order_ids.clone(),
+            sizing_reasoning: ctx.sizing_reasoning.clone(),
+        })
+        .await;
+
+    if let Some(shadow) = shadow::active() {
+        let (decision, rule_trace) = shadow.evaluate(trade, address, &ctx.shadow);
+        let (status, my_usd, skip) = match decision {
+            ShadowDecision::Copy { usd } => (JournalStatus::Executed, usd.unwrap_or(0.0), None),
+            ShadowDecision::Skip(skip) => (JournalStatus::Skipped, 0.0, Some(skip)),
+            ShadowDecision::Undetermined => (
+                JournalStatus::Skipped,
+                0.0,
+                Some(shadow::undetermined()),
+            ),
+        };
+        state
+            .journal
+            .record(JournalEntry {
+                schema_version: JOURNAL_SCHEMA_VERSION,
+                timestamp: chrono::Utc::now().timestamp(),
+                status,
+                trader,
+                trader_member,
+                slug: trade.slug.clone(),
+                condition_id: trade.condition_id.clone(),
+                asset: trade.asset.clone(),
+                side: trade.side.clone(),
+                position_action: ctx.position_action,
+                trader_usd: trade.usdc_size,
+                trader_usd_reported: ctx.usdc_notional.and_then(|n| n.reported),
+                trader_usd_derived: ctx.usdc_notional.map(|n| n.derived),
+                my_usd,
+                my_tokens: 0.0,
+                tx_hash: trade.transaction_hash.clone(),
+                reason: skip.as_ref().map(Skip::to_string),
+                skip_reason: skip.map(|s| s.reason),
+                degraded_balance: false,
+                market: None,
+                rebalance_id: ctx.rebalance_id.clone(),
+                time_to_end_secs: None,
+                rule_trace,
+                shadow: true,
+                sizing_steps: Vec::new(),
+                manual: ctx.manual,
+                prefetch: None,
+                order_ids: Vec::new(),
+                sizing_reasoning: None,
+            })
+            .await;
+    }
+}
+
+/// The position in outcome token `asset`, if any.
+fn position_in<'a>(positions: &'a [UserPosition], asset: Option<&str>) -> Option<&'a UserPosition> {
+    let asset = asset.filter(|a| !a.is_empty())?;
+    positions.iter().find(|p| p.asset.as_deref() == Some(asset))
+}
+
+/// The activity as a trade, with `usdc_size` set to the notional sizing uses.
+fn activity_to_trade(activity: &RtdsActivity, usdc_size: f64) -> UserActivity {
+    UserActivity {
+        id: None,
+        proxy_wallet: activity.proxy_wallet.clone(),
+        timestamp: activity.timestamp,
+        condition_id: activity.condition_id.clone(),
+        activity_type: Some("TRADE".to_string()),
+        size: activity.shares().ok(),
+        usdc_size: Some(usdc_size),
+        transaction_hash: activity.transaction_hash.clone(),
+        price: activity.price().ok(),
+        asset: activity.asset.clone(),
+        side: activity.side.clone(),
+        outcome_index: activity.outcome_index,
+        title: activity.title.clone(),
+        slug: activity.slug.clone(),
+        icon: activity.icon.clone(),
+        event_slug: activity.event_slug.clone(),
+        outcome: activity.outcome.clone(),
+        name: activity.name.clone(),
+        pseudonym: None,
+        bio: None,
+        profile_image: None,
+        profile_image_optimized: None,
+        bot: Some(false),
+        bot_executed_time: Some(0),
+        my_bought_size: None,
+    }
+}
+
+/// What a rebalance plan's legs are checked against, leg by leg in the order they run: a
+/// sell leg adds what it should free to the balance, a buy leg takes what it is sized at.
+struct PlanBudget {
+    balance_usd: f64,
+    /// Logical trader, for inactivity decay and trial sizing.
+    trader: String,
+    trial: Option<TrialConfig>,
+    /// What every BUY leg starts from: portfolio share and the daily volume left.
+    sizing: SizingInputs,
+    /// Markets earlier buy legs open.
+    planned_opens: Vec<Option<String>>,
+}
+
+/// Why a rebalance leg would be skipped, checked before any leg is placed. Covers the guards
+/// that can block a single leg (nothing to sell, too small to buy, position cap); the leg
+/// still runs through the full `execute_trade` checks when it executes. Buy legs are sized
+/// as `copy_activity` sizes them, against my position in the outcome and the cash the sell
+/// legs before them free. `my_positions` are the simulated ones in a dry run.
+async fn check_rebalance_leg(
+    config: &EnvConfig,
+    state: &ExecutorState,
+    leg: &RtdsActivity,
+    my_positions: &[UserPosition],
+    trader_positions: &[UserPosition],
+    budget: &mut PlanBudget,
+) -> Option<String> {
+    let asset = leg.asset.as_deref()?;
+    let mine = position_in(my_positions, Some(asset));
+    if leg.side.as_deref() == Some("SELL") {
+        let Some(held) = mine.and_then(|p| p.size).filter(|s| *s > 0.0) else {
+            return Some("no position to sell".to_string());
+        };
+        let held_after = position_in(trader_positions, Some(asset)).and_then(|p| p.size);
+        let fraction = trader_sell_fraction(leg.size.unwrap_or(0.0), held_after);
+        budget.balance_usd += held * fraction * leg.price.unwrap_or(0.0);
+        return None;
+    }
+    let usd = match leg.usd_value(config.usdc_size_preference) {
+        Ok(notional) => notional.chosen,
+        Err(reason) => return Some(reason),
+    };
+    let mut sizing = budget.sizing;
+    sizing.max_order_size_usd =
+        market_overrides::get(leg.condition_id.as_deref()).and_then(|m| m.cap_usd);
+    ramp_sizing(state, config, &budget.trader, budget.trial.as_ref(), &mut sizing).await;
+    let current_value = mine
+        .map(|p| p.size.unwrap_or(0.0) * p.avg_price.unwrap_or(0.0))
+        .unwrap_or(0.0);
+    let calc = calculate_order_size(
+        &config.copy_strategy_config,
+        &sizing,
+        usd,
+        budget.balance_usd,
+        current_value,
+    );
+    if calc.below_minimum || calc.final_amount <= 0.0 {
+        return Some(format!("order too small: {}", calc.reasoning));
+    }
+    if let Some(limiter) = &state.open_position_limiter {
+        let ledger = state.ledger.lock().await;
+        let condition_id = leg.condition_id.as_deref();
+        let planned = condition_id.is_some()
+            && budget
+                .planned_opens
+                .iter()
+                .any(|c| c.as_deref() == condition_id);
+        if ledger.opens_new_position(condition_id) && !planned {
+            // Earlier buy legs of the plan open markets too, so a plan can't half-fit.
+            let limiter = limiter.lock().await;
+            if !limiter.would_allow(ledger.open_count() + budget.planned_opens.len()) {
+                return Some(format!("max open positions reached ({})", limiter.max()));
+            }
+            budget.planned_opens.push(condition_id.map(str::to_string));
+        }
+    }
+    budget.balance_usd -= calc.final_amount;
+    if let Some(left) = budget.sizing.daily_volume_left_usd.as_mut() {
+        *left -= calc.final_amount;
+    }
+    None
+}
+
+/// Copies a neg-risk rebalance as one plan: every leg is checked first, then sells go
+/// before buys so the freed cash funds the new outcome. Under `ALL_OR_NOTHING` one blocked
+/// leg skips the whole plan, and a leg that doesn't go through stops the ones after it; a
+/// plan left half-applied is alerted with the legs that did not run.
+async fn execute_rebalance(ex: &ExecutorContext, group: SignalGroup) {
+    let ExecutorContext {
+        config,
+        http_client,
+        state,
+        ..
+    } = ex;
+    let plan_id = group.plan_id();
+    Logger::info(&format!(
+        "Rebalance plan {}: {} legs in {} from {}",
+        plan_id,
+        group.legs.len(),
+        group.event_slug,
+        Logger::format_address(&group.trader)
+    ));
+    // A dry run trades against its simulated positions, not the wallet's.
+    let my_positions: Vec<UserPosition> = if config.dry_run {
+        let paper = state.paper.lock().await;
+        group
+            .legs
+            .iter()
+            .filter_map(|leg| paper.position(leg.asset.as_deref()?))
+            .collect()
+    } else {
+        fetch_positions(http_client, config, &config.proxy_wallet)
+            .await
+            .unwrap_or_default()
+    };
+    let data_api = DataApiClient::from_config(config, http_client);
+    let trader_positions = data_api
+        .get_positions(&group.trader)
+        .await
+        .unwrap_or_default();
+    let balance = state
+        .balance_cache
+        .get()
+        .await
+        .map(|b| b.usd)
+        .unwrap_or(0.0);
+    let trader = config.trader_id(&group.trader);
+    let trial = state.trials.lock().await.get(&trader).cloned();
+    let mut budget = PlanBudget {
+        balance_usd: balance,
+        trial: trial
+            .filter(|t| t.is_restricted())
+            .and(config.trial())
+            .cloned(),
+        sizing: SizingInputs::default(),
+        planned_opens: Vec::new(),
+        trader,
+    };
+    if config.copy_strategy_config.strategy == CopyStrategy::PortfolioShare {
+        let portfolio = trader_portfolio_value(
+            config,
+            &budget.trader,
+            &group.trader,
+            &trader_positions,
+            chrono::Utc::now().timestamp(),
+            |member| {
+                let data_api = data_api.clone();
+                async move { Ok(data_api.get_positions(&member).await?) }
+            },
+        )
+        .await;
+        budget.sizing.trader_portfolio_usd = portfolio.ok();
+    }
+    if let Some(cap) = config.copy_strategy_config.max_daily_volume_usd {
+        let left = state
+            .daily_volume
+            .lock()
+            .await
+            .remaining(cap, chrono::Utc::now().timestamp());
+        budget.sizing.daily_volume_left_usd = Some(left);
+    }
+
+    let mut legs = group.legs;
+    legs.sort_by_key(|leg| leg.side.as_deref() != Some("SELL"));
+    let mut checked = Vec::new();
+    for leg in legs {
+        let blocked = check_rebalance_leg(
+            config,
+            state,
+            &leg,
+            &my_positions,
+            &trader_positions,
+            &mut budget,
+        )
+        .await;
+        checked.push((leg, blocked));
+    }
+    let blocked: Vec<String> = checked
+        .iter()
+        .filter_map(|(leg, reason)| {
+            reason
+                .as_ref()
+                .map(|r| format!("{}: {}", rebalance::leg_label(leg), r))
+        })
+        .collect();
+    let policy = config
+        .rebalance()
+        .map(|r| r.policy)
+        .unwrap_or(RebalancePolicy::AllOrNothing);
+    if !blocked.is_empty() {
+        let summary = blocked.join("; ");
+        if policy == RebalancePolicy::AllOrNothing {
+            Logger::warning(&format!("Skipping rebalance plan {}: {}", plan_id, summary));
+        } else {
+            Logger::warning(&format!(
+                "Rebalance plan {} executing partially: {}",
+                plan_id, summary
+            ));
+        }
+    }
+
+    let mut went_through = Vec::new();
+    let mut missed: Vec<String> = Vec::new();
+    for (leg, reason) in checked {
+        let label = rebalance::leg_label(&leg);
+        let stopped_by = missed
+            .first()
+            .filter(|_| policy == RebalancePolicy::AllOrNothing)
+            .cloned();
+        let skip = match &stopped_by {
+            Some(failed) => Some(rebalance::plan_stopped(failed)),
+            None => leg_skip_reason(reason.as_deref(), policy, !blocked.is_empty()),
+        };
+        if let Some(skip) = skip {
+            if stopped_by.is_some() {
+                missed.push(label);
+            }
+            let notional = leg.usd_value(config.usdc_size_preference).ok();
+            let mut ctx = CopyContext {
+                rebalance_id: Some(plan_id.clone()),
+                usdc_notional: notional,
+                ..CopyContext::default()
+            };
+            journal_trade(
+                ex,
+                &mut ctx,
+                &activity_to_trade(&leg, notional.map(|n| n.chosen).unwrap_or(0.0)),
+                &group.trader,
+                OrderFill::default(),
+                Some(skip),
+            )
+            .await;
+            continue;
+        }
+        let mut ctx = CopyContext {
+            rebalance_id: Some(plan_id.clone()),
+            ..CopyContext::default()
+        };
+        if let Err(e) = copy_activity(ex, leg, group.trader.clone(), &mut ctx).await {
+            Logger::error(&format!("Rebalance plan {} leg failed: {}", plan_id, e));
+        }
+        // A dry run places nothing, so it has nothing to leave half-applied.
+        let placed = config.dry_run
+            || ctx
+                .outcome
+                .as_ref()
+                .is_some_and(|o| o.fill.tokens > 0.0 || o.fill.resting);
+        if placed {
+            went_through.push(label);
+        } else {
+            missed.push(label);
+        }
+    }
+    if let Some(line) = rebalance::partial_plan_report(&plan_id, &went_through, &missed) {
+        Logger::warning(&line);
+        alerts::warn(&line);
+    }
+}
+
+/// For the first signal in a market without an order template: looks up the template parts,
+/// the book snapshot and the end date concurrently, giving up on whatever misses
+/// `MARKET_PREFETCH_DEADLINE_MS`. Returns the end date when it was part of the lookup.
+async fn prefetch_new_market(
+    config: &Arc<EnvConfig>,
+    http_client: &Arc<reqwest::Client>,
+    state: &ExecutorState,
+    ctx: &mut CopyContext,
+    trade: &UserActivity,
+) -> Option<Option<chrono::DateTime<chrono::Utc>>> {
+    if config.order_template_refresh_secs == 0 || config.market_prefetch_deadline_ms == 0 {
+        return None;
+    }
+    let asset = trade
+        .asset
+        .as_deref()
+        .filter(|a| order_templates::get(a).is_none())?;
+    let plan = PrefetchPlan {
+        book: config.journal_market_context && ctx.market.is_none(),
+        end_time: trade.side.as_deref() == Some("BUY")
+            && config.resolution_window_minutes() > 0
+            && state.skip_rules.is_enabled("resolution_window"),
+    };
+    let _timer = profiling::stage("prefetch");
+    let fetched = prefetch(
+        &ChaosMarketData {
+            inner: ClobMarketData {
+                http_client: http_client.as_ref(),
+                config: config.as_ref(),
+            },
+            chaos: state.chaos.as_deref(),
+        },
+        asset,
+        trade.condition_id.as_deref(),
+        plan,
+        Duration::from_millis(config.market_prefetch_deadline_ms),
+    )
+    .await;
+    match fetched.template {
+        Some(template) => order_templates::insert(asset, template),
+        None => {
+            // This copy takes the full order path; the next one in the market shouldn't.
+            let (config, http_client, asset) =
+                (config.clone(), http_client.clone(), asset.to_string());
+            tokio::spawn(async move {
+                order_templates::warm(&http_client, &config, &asset).await;
+            });
+        }
+    }
+    if fetched.report.is_degraded() {
+        Logger::warning(&format!(
+            "New market lookups missed the {}ms deadline ({}) - copying without them",
+            config.market_prefetch_deadline_ms,
+            fetched.report.missed.join(", ")
+        ));
+    }
+    if fetched.market.is_some() {
+        ctx.market = fetched.market;
+    }
+    ctx.prefetch = Some(fetched.report);
+    plan.end_time.then_some(fetched.end_time)
+}
+
+/// Ends `trader`'s trial once it reached its bound, judged on the realized PnL the journal
+/// records for the lots it opened. Left for the next signal if the journal can't be read.
+async fn review_trial(state: &ExecutorState, config: &EnvConfig, trader: &str) {
+    let now = chrono::Utc::now().timestamp();
+    let started_at = match state.trials.lock().await.get(trader) {
+        Some(trial) if trial.is_due(now) => trial.started_at,
+        _ => return,
+    };
+    flush_journal().await;
+    let entries = match read_journal(std::path::Path::new(&config.trade_log_path)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            Logger::warning(&format!(
+                "Trial review postponed: journal unreadable: {}",
+                e
+            ));
+            return;
+        }
+    };
+    let pnl = trial_pnl(&entries, trader, started_at);
+    match state.trials.lock().await.review(trader, pnl, now) {
+        Some(TrialStatus::Graduated) => Logger::success(&format!(
+            "{} finished the trial at ${:+.2} realized - copying at normal size",
+            Logger::format_address(trader),
+            pnl
+        )),
+        Some(TrialStatus::Held) => {
+            let line = format!(
+                "{} finished the trial ${:.2} down - trial sizing stays until you graduate or drop them",
+                Logger::format_address(trader),
+                -pnl
+            );
+            Logger::warning(&line);
+            alerts::notify(&line);
+        }
+        _ => {}
+    }
+}
+
+/// Journals the end of a resting order against its copy id, with what it filled in total.
+async fn journal_resting_close(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    order: &RestingOrder,
+    status: JournalStatus,
+    tokens: f64,
+    usd: f64,
+    reason: &str,
+) {
+    state
+        .journal
+        .record(JournalEntry {
+            schema_version: JOURNAL_SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().timestamp(),
+            status,
+            trader: config.trader_id(&order.trader),
+            trader_member: None,
+            slug: None,
+            condition_id: None,
+            asset: Some(order.asset.clone()),
+            side: Some(order.side.clone()),
+            position_action: None,
+            trader_usd: None,
+            trader_usd_reported: None,
+            trader_usd_derived: None,
+            my_usd: usd,
+            my_tokens: tokens,
+            tx_hash: (!order.copy_id.is_empty()).then(|| order.copy_id.clone()),
+            reason: Some(reason.to_string()),
+            skip_reason: (status == JournalStatus::Skipped).then_some(SkipReason::OrderClosed),
+            degraded_balance: false,
+            market: None,
+            rebalance_id: None,
+            time_to_end_secs: None,
+            rule_trace: Vec::new(),
+            shadow: false,
+            sizing_steps: Vec::new(),
+            manual: false,
+            prefetch: None,
+            order_ids: Vec::new(),
+            sizing_reasoning: None,
+        })
+        .await;
+}
+
+/// Reports resting-order events and books them: fills count toward the day's deployed
+/// notional (the first one as the copy itself) and move the remembered balance, and the
+/// close is journaled with the order's total fill.
+async fn settle_resting(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    order: &RestingOrder,
+    events: &[OrderEvent],
+) {
+    let new_tokens: f64 = events
+        .iter()
+        .map(|e| match e {
+            OrderEvent::Fill { tokens, .. } => *tokens,
+            _ => 0.0,
+        })
+        .sum();
+    let mut first_fill = order.filled_tokens - new_tokens <= 1e-9;
+    for event in events {
+        resting_orders::report(order, event);
+        match event {
+            OrderEvent::Placed | OrderEvent::Trimmed { .. } => {}
+            OrderEvent::Fill { usd, .. } => {
+                {
+                    let mut stats = state.day_stats.lock().await;
+                    if first_fill {
+                        stats.record_copy(&order.side, *usd);
+                    } else {
+                        stats.record_fill(&order.side, *usd);
+                    }
+                }
+                state.daily_volume.lock().await.record(
+                    *usd,
+                    first_fill,
+                    chrono::Utc::now().timestamp(),
+                );
+                first_fill = false;
+                let delta = if order.side == "BUY" { -usd } else { *usd };
+                state.balance.lock().await.apply_fill(delta);
+                state.balance_cache.invalidate().await;
+            }
+            OrderEvent::Closed(reason) => {
+                let status = if order.filled_tokens > 0.0 {
+                    JournalStatus::Executed
+                } else {
+                    JournalStatus::Skipped
+                };
+                journal_resting_close(
+                    state,
+                    config,
+                    order,
+                    status,
+                    order.filled_tokens,
+                    order.filled_usd(),
+                    reason.journal_reason(),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/// Journals one step of a position build against the signal that opened it.
+async fn journal_build(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    build: &Build,
+    status: JournalStatus,
+    tokens: f64,
+    usd: f64,
+    reason: &str,
+) {
+    state
+        .journal
+        .record(JournalEntry {
+            schema_version: JOURNAL_SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().timestamp(),
+            status,
+            trader: config.trader_id(&build.trader),
+            trader_member: None,
+            slug: None,
+            condition_id: Some(build.condition_id.clone()),
+            asset: Some(build.asset.clone()),
+            side: Some("BUY".to_string()),
+            position_action: None,
+            trader_usd: None,
+            trader_usd_reported: None,
+            trader_usd_derived: None,
+            my_usd: usd,
+            my_tokens: tokens,
+            tx_hash: Some(build.id.clone()),
+            reason: Some(reason.to_string()),
+            skip_reason: (status == JournalStatus::Skipped).then_some(SkipReason::OrderClosed),
+            degraded_balance: false,
+            market: None,
+            rebalance_id: None,
+            time_to_end_secs: None,
+            rule_trace: Vec::new(),
+            shadow: false,
+            sizing_steps: Vec::new(),
+            manual: false,
+            prefetch: None,
+            order_ids: Vec::new(),
+            sizing_reasoning: None,
+        })
+        .await;
+}
+
+/// Reports build events and books them: each fill goes to the ledger (which the caps are
+/// re-checked against), the day's stats and the remembered balance, with its own journal
+/// row carrying the progress; the end is journaled with its reason.
+async fn settle_build(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    build: &Build,
+    events: &[BuildEvent],
+) {
+    let new_tokens: f64 = events
+        .iter()
+        .map(|e| match e {
+            BuildEvent::Fill { tokens, .. } => *tokens,
+            _ => 0.0,
+        })
+        .sum();
+    let mut first_fill = build.filled_tokens - new_tokens <= 1e-9;
+    for event in events {
+        position_builder::report(build, event);
+        match event {
+            BuildEvent::Started | BuildEvent::Extended { .. } => {}
+            BuildEvent::Fill { tokens, usd } => {
+                state.ledger.lock().await.record_buy(
+                    &build.asset,
+                    &build.condition_id,
+                    Some(&build.trader),
+                    *tokens,
+                    *usd,
+                );
+                {
+                    let mut stats = state.day_stats.lock().await;
+                    if first_fill {
+                        stats.record_copy("BUY", *usd);
+                    } else {
+                        stats.record_fill("BUY", *usd);
+                    }
+                }
+                state.daily_volume.lock().await.record(
+                    *usd,
+                    first_fill,
+                    chrono::Utc::now().timestamp(),
+                );
+                first_fill = false;
+                state.balance.lock().await.apply_fill(-usd);
+                state.balance_cache.invalidate().await;
+                let reason = format!("position build fill: {}", build.progress());
+                journal_build(
+                    state,
+                    config,
+                    build,
+                    JournalStatus::Executed,
+                    *tokens,
+                    *usd,
+                    &reason,
+                )
+                .await;
+            }
+            BuildEvent::Ended(reason) => {
+                journal_build(
+                    state,
+                    config,
+                    build,
+                    JournalStatus::Skipped,
+                    0.0,
+                    0.0,
+                    &reason.describe(),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+/// A cached balance as the balance tracker takes it: a stale value counts as a failed read,
+/// so degraded mode sizes against it.
+fn tracker_reading(cached: Result<CachedBalance>) -> Result<f64> {
+    cached.and_then(|b| match b.source {
+        BalanceSource::Stale => Err(anyhow::anyhow!("USDC balance RPC failed")),
+        _ => Ok(b.usd),
+    })
+}
+
+/// What the caps allow toward `build` right now: its remainder sized as a fixed copy against
+/// my position in the market, the balance and the cached market-share limit; 0 when that is
+/// below a minimum order. `None` while the balance is unknown.
+async fn build_room(
+    config: &EnvConfig,
+    state: &ExecutorState,
+    build: &Build,
+    now: i64,
+) -> Option<f64> {
+    let position_usd = state
+        .ledger
+        .lock()
+        .await
+        .position(&build.asset)
+        .filter(|p| p.is_open())
+        .map(|p| p.cost_usd)
+        .unwrap_or(0.0);
+    let fetched = tracker_reading(state.balance_cache.get().await);
+    let reading = state
+        .balance
+        .lock()
+        .await
+        .observe(fetched, config.balance_max_staleness_secs);
+    let balance = match reading {
+        BalanceReading::Fresh(balance) => split_balance(state, config, balance)
+            .await
+            .for_sizing(config.sizing_balance),
+        BalanceReading::Degraded { balance, .. } => {
+            split_balance(state, config, balance)
+                .await
+                .for_sizing(config.sizing_balance)
+                * config.degraded_balance_fraction
+        }
+        BalanceReading::Unavailable => return None,
+    };
+    let mut sizing = SizingInputs::fixed(build.remaining_usd());
+    if let Some(cap) = config.copy_strategy_config.max_daily_volume_usd {
+        let left = state.daily_volume.lock().await.remaining(cap, now);
+        sizing.daily_volume_left_usd = Some(left);
+    }
+    if let Some(ms) = config.market_share() {
+        let figures = market_share::cached(&build.condition_id, now);
+        let limit = market_share::share_limit(ms, figures.as_ref());
+        if let ShareLimit::Cap { max_position_usd } = limit {
+            sizing.market_share_cap_usd = Some(max_position_usd);
+        }
+    }
+    let calc = calculate_order_size(
+        &config.copy_strategy_config,
+        &sizing,
+        build.remaining_usd(),
+        balance,
+        position_usd,
+    );
+    Some(if calc.below_minimum {
+        0.0
+    } else {
+        calc.final_amount
+    })
+}
+
+/// One round for one build: count what its last order filled, then place the next slice
+/// inside the spread or end the build.
+async fn advance_build(ex: &ExecutorContext, mut build: Build) {
+    let ExecutorContext {
+        config,
+        http_client,
+        clob_client,
+        signer,
+        state,
+    } = ex;
+    let Some(bc) = config.position_build() else {
+        return;
+    };
+    let mut events = position_builder::collect_order(clob_client, &mut build).await;
+    let now = chrono::Utc::now().timestamp();
+    if build.open_order.is_none() {
+        let step = if trading_state::check("BUY").is_err() {
+            BuildStep::Wait("trading paused")
+        } else {
+            match position_builder::fetch_book_top(http_client, config, &build.asset).await {
+                Err(e) => {
+                    Logger::warning(&format!("Build book lookup failed: {}", e));
+                    BuildStep::Wait("book unavailable")
+                }
+                Ok(book) => match build_room(config, state, &build, now).await {
+                    None => BuildStep::Wait("balance unavailable"),
+                    Some(room_usd) => build.next_step(
+                        bc,
+                        book,
+                        room_usd,
+                        config.copy_strategy_config.min_order_size_usd,
+                        position_builder::ticks(&build.asset),
+                        now,
+                    ),
+                },
+            }
+        };
+        match step {
+            BuildStep::Place { price, tokens } => {
+                // GTD expirations must be a minute out; the next round takes the order off
+                // the book anyway.
+                let expires_at = now + 60 + bc.interval_secs as i64;
+                let market = market_metadata(http_client, config, Some(&build.condition_id)).await;
+                let placed = {
+                    let signer = signer.lock().await;
+                    let order = OrderContext {
+                        config,
+                        clob_client,
+                        signer: &signer,
+                        http_client,
+                        chaos: state.chaos.as_deref(),
+                        sizing: SizingInputs::default(),
+                    };
+                    place_limit_order(
+                        &order,
+                        &build.asset,
+                        market.as_ref(),
+                        Side::Buy,
+                        tokens,
+                        price,
+                        expires_at,
+                    )
+                    .await
+                };
+                match placed {
+                    Ok(Ok(order_id)) => {
+                        build.open_order = Some(BuildOrder {
+                            order_id,
+                            price,
+                            size: tokens,
+                            placed_at: now,
+                            filled_tokens: 0.0,
+                        });
+                    }
+                    Ok(Err(msg)) => Logger::warning(&format!("Build order rejected: {}", msg)),
+                    Err(e) => Logger::warning(&format!("Failed to place build order: {}", e)),
+                }
+            }
+            BuildStep::Wait(_) => {}
+            BuildStep::End(reason) => {
+                build.end(reason, now);
+                events.push(BuildEvent::Ended(reason));
+            }
+        }
+    }
+    position_builder::save(&config.state_dir, &build);
+    settle_build(state, config, &build, &events).await;
+}
+
+/// Works active position builds toward their targets every `BUILD_INTERVAL_SECS`. Builds
+/// are kept in the state dir, so a restart picks them up where they were.
+async fn run_position_builder(ex: ExecutorContext) {
+    let Some(bc) = ex.config.position_build() else {
+        return;
+    };
+    let interval = Duration::from_secs(bc.interval_secs);
+    while !trading_state::is_draining() {
+        for build in position_builder::active(&ex.config.state_dir) {
+            advance_build(&ex, build).await;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Settles copy orders the last run left in flight (see `order_intents`): each interrupted
+/// copy is looked up on the CLOB and journaled with what it filled. Copies the CLOB can't be
+/// asked about stay pending for the next start.
+async fn settle_order_intents(
+    config: &EnvConfig,
+    clob_client: &ClobClient<Authenticated<Normal>>,
+    state: &ExecutorState,
+) {
+    let now = chrono::Utc::now().timestamp();
+    for settled in order_intents::resolve(&config.state_dir, clob_client).await {
+        let status = if settled.tokens > 0.0 {
+            JournalStatus::Executed
+        } else {
+            JournalStatus::Skipped
+        };
+        Logger::warning(&format!(
+            "Copy {} was in flight at the last shutdown: {} {:.2} tokens (${:.2}) filled",
+            Logger::format_address(&settled.copy_id),
+            settled.side,
+            settled.tokens,
+            settled.usd
+        ));
+        state
+            .journal
+            .record(JournalEntry {
+                schema_version: JOURNAL_SCHEMA_VERSION,
+                timestamp: now,
+                status,
+                trader: config.trader_id(&settled.trader),
+                trader_member: None,
+                slug: None,
+                condition_id: None,
+                asset: Some(settled.asset.clone()),
+                side: Some(settled.side.clone()),
+                position_action: None,
+                trader_usd: None,
+                trader_usd_reported: None,
+                trader_usd_derived: None,
+                my_usd: settled.usd,
+                my_tokens: settled.tokens,
+                tx_hash: (!settled.copy_id.is_empty()).then(|| settled.copy_id.clone()),
+                reason: Some(settled.reason()),
+                skip_reason: (status == JournalStatus::Skipped).then_some(SkipReason::NotFilled),
+                degraded_balance: false,
+                market: None,
+                rebalance_id: None,
+                time_to_end_secs: None,
+                rule_trace: Vec::new(),
+                shadow: false,
+                sizing_steps: Vec::new(),
+                manual: false,
+                prefetch: None,
+                order_ids: Vec::new(),
+                sizing_reasoning: None,
+            })
+            .await;
+    }
+    let retention_secs = config.processed_trades_retention_hours as i64 * 3600;
+    order_intents::compact(&config.state_dir, now - retention_secs);
+}
+
+/// Reports what the last run left behind and settles its resting orders: live ones stay
+/// tracked, ones that filled or left the book while the bot was down are journaled and
+/// dropped from the tracker.
+async fn take_handover(config: &EnvConfig, http_client: &reqwest::Client, state: &ExecutorState) {
+    let Some(handover) = Handover::take(&config.state_dir) else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp();
+    let mut fates = Vec::new();
+    let mut unchecked = None;
+    if !handover.open_orders.is_empty() {
+        match fetch_wallet_trades(http_client, config).await {
+            Ok(trades) => fates = reconcile_orders(&handover, &trades, now),
+            Err(e) => unchecked = Some(e),
+        }
+    }
+    let tracked = resting_orders::all(&config.state_dir);
+    for (order, fate) in &fates {
+        let (status, tokens, usd, reason) = match fate {
+            OrderFate::Live { .. } => {
+                if !tracked.iter().any(|o| o.order_id == order.order_id) {
+                    resting_orders::record(&config.state_dir, order.clone());
+                }
+                continue;
+            }
+            OrderFate::Filled { tokens, usd } => (
+                JournalStatus::Executed,
+                *tokens,
+                *usd,
+                "resting order filled while offline",
+            ),
+            OrderFate::Gone => (
+                JournalStatus::Skipped,
+                0.0,
+                0.0,
+                "resting order expired or cancelled while offline",
+            ),
+        };
+        resting_orders::remove(&config.state_dir, &order.order_id);
+        journal_resting_close(state, config, order, status, tokens, usd, reason).await;
+    }
+
+    let mut lines = summary_lines(&handover, &fates, now);
+    if let Some(e) = unchecked {
+        lines.insert(
+            0,
+            format!(
+                "📌 {} open order(s) not checked, wallet trades unavailable: {}",
+                handover.open_orders.len(),
+                e
+            ),
+        );
+    }
+    if !lines.is_empty() {
+        Logger::handover(now - handover.written_at, &lines);
+    }
+}
+
+/// Starts trials for traders the bot hasn't seen trade before, reviews the ones that ran out
+/// while it was down and takes trial decisions on the status API.
+async fn enroll_trials(state: &ExecutorState, config: &EnvConfig) {
+    let Some(tc) = config.trial() else {
+        return;
+    };
+    trial::register(state.trials.clone(), tc.clone());
+    let now = chrono::Utc::now().timestamp();
+    for (trader, _) in config.logical_traders() {
+        let established = state
+            .activity
+            .lock()
+            .await
+            .days_since_last_trade(&trader, now)
+            .is_some();
+        if state
+            .trials
+            .lock()
+            .await
+            .enroll(&trader, tc, now, established)
+        {
+            Logger::info(&format!(
+                "{} is new - copying on trial ({:.0}% size, max ${:.2} per order)",
+                Logger::format_address(&trader),
+                tc.size_multiplier * 100.0,
+                tc.max_order_size_usd
+            ));
+        }
+        review_trial(state, config, &trader).await;
+    }
+}
+
+/// One catch-up BUY per market whose cap was raised since its adds were blocked, for
+/// `OVERFLOW_CATCHUP_FRACTION` of what was ignored. Each goes through the full pipeline as a
+/// trader signal, not a manual copy, so every guard applies.
+async fn overflow_catch_up(ex: ExecutorContext, markets: Vec<(String, MarketOverflow)>) {
+    let config = &ex.config;
+    for (cid, market) in markets {
+        if supervisor().is_stopping() {
+            break;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let Some(signal) = market.catch_up_signal(&cid, config.overflow_catchup_fraction, now)
+        else {
+            continue;
+        };
+        Logger::info(&format!(
+            "Position cap raised - catching up on {:.0}% of ${:.2} ignored in {}",
+            config.overflow_catchup_fraction * 100.0,
+            market.ignored_trader_usd,
+            market.title.as_deref().unwrap_or(&cid)
+        ));
+        let mut ctx = CopyContext::default();
+        if let Err(e) = copy_activity(&ex, signal, market.trader.clone(), &mut ctx).await {
+            Logger::error(&format!("Overflow catch-up failed: {}", e));
+        }
+    }
+}
+
+/// Where catch-up starts and the trades the journal already has, read before this run
+/// journals anything. `None` without a journal to go on.
+fn catch_up_start(
+    config: &EnvConfig,
+    lookback_secs: i64,
+    now: i64,
+) -> Option<(i64, HashSet<String>)> {
+    let entries = read_journal(std::path::Path::new(&config.trade_log_path)).ok()?;
+    let last_seen = entries
+        .iter()
+        .filter(|e| !e.shadow && !e.manual)
+        .map(|e| e.timestamp)
+        .max();
+    let since = catch_up::since(last_seen, now, lookback_secs)?;
+    let seen = entries
+        .iter()
+        .filter_map(|e| e.tx_hash.as_deref())
+        .map(str::to_lowercase)
+        .collect();
+    Some((since, seen))
+}
+
+/// `address`'s trades since `since`; see `DataApiClient::get_activity`.
+pub(crate) async fn fetch_activity_since(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    address: &str,
+    since: i64,
+) -> Result<Vec<UserActivity>> {
+    Ok(DataApiClient::from_config(config, http_client)
+        .get_activity(address, since)
+        .await?)
+}
+
+/// Copies the trades the traders made while the bot was down (`CATCHUP_MAX_USD`): exits
+/// first, then BUYs by score under the catch-up budget. BUYs the budget didn't reach are
+/// journaled as skipped with their scores.
+async fn catch_up_missed(ex: ExecutorContext, since: i64, seen: HashSet<String>) {
+    let ExecutorContext {
+        config,
+        http_client,
+        ..
+    } = &ex;
+    let Some(cc) = config.catch_up() else {
+        return;
+    };
+    let mut missed = Vec::new();
+    for (_, members) in config.logical_traders() {
+        for member in members {
+            let activity = match fetch_activity_since(http_client, config, &member, since).await
+            {
+                Ok(activity) => activity,
+                Err(e) => {
+                    Logger::warning(&format!(
+                        "Catch-up: activity of {} unavailable: {}",
+                        Logger::format_address(&member),
+                        e
+                    ));
+                    continue;
+                }
+            };
+            let signals = catch_up::missed_signals(&activity, since, &seen);
+            if signals.is_empty() {
+                continue;
+            }
+            let positions = fetch_positions(http_client, config, &member).await.ok();
+            for signal in signals {
+                let trader_holds = positions.as_ref().map(|positions| {
+                    positions
+                        .iter()
+                        .any(|p| p.asset == signal.asset && p.size.unwrap_or(0.0) > 0.0)
+                });
+                let mut current_price = None;
+                let buy = signal.side.as_deref() == Some("BUY");
+                if let (true, Some(asset)) = (buy, signal.asset.as_deref()) {
+                    current_price = position_builder::fetch_book_top(http_client, config, asset)
+                        .await
+                        .ok()
+                        .and_then(|book| book.ask.or(book.mid()));
+                }
+                missed.push(catch_up::Missed {
+                    activity: signal,
+                    address: member.clone(),
+                    trader_holds,
+                    current_price,
+                });
+            }
+        }
+    }
+    if missed.is_empty() {
+        return;
+    }
+    let now = chrono::Utc::now().timestamp();
+    Logger::info(&format!(
+        "Catch-up: {} trade(s) made while offline, copying best-first up to ${:.2}",
+        missed.len(),
+        cc.max_usd
+    ));
+    let planned = catch_up::prioritize(missed, now, cc.lookback_secs);
+    let decisions = catch_up::run_plan(
+        planned,
+        cc.max_usd,
+        config.copy_strategy_config.min_order_size_usd,
+        |m, cap| {
+            let ex = ex.clone();
+            let (activity, address) = (m.activity.clone(), m.address.clone());
+            async move {
+                // Left unjournaled on shutdown, so the next start's catch-up finds them again.
+                if supervisor().is_stopping() {
+                    return 0.0;
+                }
+                let outcome = execute_catch_up(&ex, activity, address, cap).await;
+                match outcome {
+                    Ok(Some(o)) if cap.is_some() => o.fill.usd,
+                    Ok(_) => 0.0,
+                    Err(e) => {
+                        Logger::error(&format!("Catch-up copy failed: {}", e));
+                        0.0
+                    }
+                }
+            }
+        },
+    )
+    .await;
+
+    let mut skipped = 0;
+    let mut spent = 0.0;
+    for (missed, _, decision) in decisions {
+        let skip = match decision {
+            catch_up::Decision::Ran { spent_usd } => {
+                spent += spent_usd;
+                continue;
+            }
+            catch_up::Decision::Skipped(reason) => Skip::new(SkipReason::Budget, reason),
+        };
+        skipped += 1;
+        let usd = missed
+            .activity
+            .usd_value(config.usdc_size_preference)
+            .map(|n| n.chosen)
+            .unwrap_or(0.0);
+        let trade = activity_to_trade(&missed.activity, usd);
+        journal_trade(
+            &ex,
+            &mut CopyContext::default(),
+            &trade,
+            &missed.address,
+            OrderFill::default(),
+            Some(skip),
+        )
+        .await;
+    }
+    Logger::info(&format!(
+        "Catch-up done: ${:.2} of ${:.2} spent, {} BUY(s) left behind",
+        spent, cc.max_usd, skipped
+    ));
+}
+
+/// Other tracked traders whose current positions already hold `asset`. Only queried when the
+/// live signals alone can't reach the threshold. A group holds when any member does.
+async fn consensus_holders(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    consensus: &ConsensusConfig,
+    trader: &str,
+    asset: &str,
+    signalled: &HashSet<String>,
+) -> Vec<String> {
+    let live = signalled.len() + usize::from(!signalled.contains(trader));
+    if live >= consensus.threshold {
+        return Vec::new();
+    }
+    let mut holders = Vec::new();
+    for (other, members) in config.logical_traders() {
+        if other == trader || signalled.contains(&other) {
+            continue;
+        }
+        for member in members {
+            match fetch_positions(http_client, config, &member).await {
+                Ok(positions) => {
+                    if positions
+                        .iter()
+                        .any(|p| p.asset.as_deref() == Some(asset) && p.size.unwrap_or(0.0) > 0.0)
+                    {
+                        holders.push(other);
+                        break;
+                    }
+                }
+                Err(e) => Logger::warning(&format!(
+                    "Consensus position check failed for {}: {}",
+                    Logger::format_address(&member),
+                    e
+                )),
+            }
+        }
+    }
+    holders
+}
+
+/// `user`'s positions, for callers that hold the config rather than a `DataApiClient`.
+pub async fn fetch_positions(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    user: &str,
+) -> Result<Vec<UserPosition>> {
+    Ok(DataApiClient::from_config(config, http_client)
+        .get_positions(user)
+        .await?)
+}
+
+/// The trader's portfolio value over all their wallets. `address` is valued from the positions
+/// just fetched for the signal, which also refreshes its cache entry; the trader's other
+/// wallets are read from the cache and fetched through `fetch` only when their value is
+/// missing or older than `TRADER_PORTFOLIO_MAX_AGE_SECS`.
+async fn trader_portfolio_value<F, Fut>(
+    config: &EnvConfig,
+    trader: &str,
+    address: &str,
+    positions: &[UserPosition],
+    now: i64,
+    mut fetch: F,
+) -> Result<f64>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<UserPosition>>>,
+{
+    let mut total = trader_portfolio::record_positions(address, positions, now);
+    for member in config.trader_members(trader) {
+        if member == address.to_lowercase() {
+            continue;
+        }
+        total += match trader_portfolio::read(&member, config.trader_portfolio_max_age_secs, now)
+        {
+            PortfolioRead::Fresh(value) => value,
+            PortfolioRead::Stale { .. } | PortfolioRead::Missing => {
+                let positions = fetch(member.clone()).await?;
+                trader_portfolio::record_positions(&member, &positions, now)
+            }
+        };
+    }
+    Ok(total)
+}
+
+/// Refetches every tracked wallet's positions so cached portfolio values stay fresh for
+/// traders that have been quiet.
+async fn run_trader_portfolio_refresh(config: Arc<EnvConfig>, http_client: Arc<reqwest::Client>) {
+    let interval = Duration::from_secs(config.trader_portfolio_refresh_secs.max(30));
+    while !trading_state::is_draining() {
+        for address in &config.user_addresses {
+            match fetch_positions(&http_client, &config, address).await {
+                Ok(positions) => {
+                    trader_portfolio::record_positions(
+                        address,
+                        &positions,
+                        chrono::Utc::now().timestamp(),
+                    );
+                }
+                Err(e) => Logger::warning(&format!(
+                    "Portfolio refresh for {} failed: {}",
+                    Logger::format_address(address),
+                    e
+                )),
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Periodically syncs the ledger with the wallet's real positions so manual trades made
+/// outside the bot are noticed; changed markets are marked dirty and refetched on next use.
+async fn run_position_reconciliation(
+    config: Arc<EnvConfig>,
+    http_client: Arc<reqwest::Client>,
+    ledger: SharedLedger,
+) {
+    let interval = Duration::from_secs(config.position_reconcile_interval_secs.max(1));
+    while !trading_state::is_draining() {
+        match fetch_positions(&http_client, &config, &config.proxy_wallet).await {
+            Ok(positions) => ledger.lock().await.reconcile(&positions),
+            Err(e) => Logger::warning(&format!("Position reconciliation failed: {}", e)),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Polls our resting orders for fills and for leaving the book. The SDK build has no CLOB
+/// user channel, so polling is the only source of these events.
+async fn run_resting_order_watch(ex: ExecutorContext) {
+    let ExecutorContext {
+        config,
+        http_client,
+        clob_client,
+        signer,
+        state,
+    } = &ex;
+    let interval = Duration::from_secs(config.resting_order_poll_secs.max(1));
+    while !trading_state::is_draining() {
+        for (order, events) in resting_orders::poll(&config.state_dir, clob_client).await {
+            settle_resting(state, config, &order, &events).await;
+        }
+        if config.follow_trader_cancels {
+            let followed = {
+                let signer = signer.lock().await;
+                let order = OrderContext {
+                    config,
+                    clob_client,
+                    signer: &signer,
+                    http_client,
+                    chaos: state.chaos.as_deref(),
+                    sizing: SizingInputs::default(),
+                };
+                resting_orders::follow_trader_cancels(&order).await
+            };
+            for (order, events) in followed {
+                settle_resting(state, config, &order, &events).await;
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Re-classifies traders periodically; the first pass runs during monitor start-up.
+async fn run_trader_classification(config: Arc<EnvConfig>, http_client: Arc<reqwest::Client>) {
+    let interval = Duration::from_secs(config.trader_classify_interval_secs.max(60));
+    while !trading_state::is_draining() {
+        tokio::time::sleep(interval).await;
+        refresh_trader_classes(&config, &http_client).await;
+    }
+}
+
+async fn run_order_template_refresh(config: Arc<EnvConfig>, http_client: Arc<reqwest::Client>) {
+    let interval = Duration::from_secs(config.order_template_refresh_secs.max(30));
+    while !trading_state::is_draining() {
+        tokio::time::sleep(interval).await;
+        order_templates::refresh_all(&http_client, &config).await;
+    }
+}
+
+async fn run_wallet_watchdog(
+    config: Arc<EnvConfig>,
+    http_client: Arc<reqwest::Client>,
+    ledger: SharedLedger,
+) {
+    let interval = Duration::from_secs(config.wallet_watchdog_interval_secs.max(10));
+    let mut watchdog = WalletWatchdog::default();
+    while !trading_state::is_draining() {
+        if let Err(e) = watchdog.poll(&config, &http_client, &ledger).await {
+            Logger::warning(&format!("Wallet watchdog poll failed: {}", e));
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn run_concentration_report(
+    config: Arc<EnvConfig>,
+    http_client: Arc<reqwest::Client>,
+    ledger: SharedLedger,
+) {
+    let Some(limits) = config.concentration().cloned() else {
+        return;
+    };
+    let interval = Duration::from_secs(limits.interval_secs.max(60));
+    let mut monitor = ConcentrationMonitor::default();
+    while !trading_state::is_draining() {
+        match fetch_positions(&http_client, &config, &config.proxy_wallet).await {
+            Ok(positions) => {
+                let holdings =
+                    concentration::holdings(&http_client, &config, &positions, &ledger).await;
+                let lines = monitor.check(&holdings, &limits);
+                if !lines.is_empty() {
+                    Logger::concentration(&lines);
+                }
+                let mut ids = Vec::new();
+                for breach in concentration::breaches(&holdings, &limits) {
+                    let bucket = &breach.bucket;
+                    let id = format!("concentration:{}:{}", bucket.kind.as_str(), bucket.key);
+                    advisories::raise(
+                        &id,
+                        AdvisorySeverity::Warning,
+                        &concentration::report_lines(std::slice::from_ref(&breach)).join("\n"),
+                        // Moves of under 5% aren't a material change.
+                        serde_json::json!({ "share_pct": (bucket.share * 20.0).round() * 5.0 }),
+                    );
+                    ids.push(id);
+                }
+                advisories::resolve_missing("concentration:", &ids);
+            }
+            Err(e) => Logger::warning(&format!("Concentration check failed: {}", e)),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn run_dust_sweeper(ex: ExecutorContext) {
+    let interval = Duration::from_secs(ex.config.dust_sweep_interval_secs.max(1));
+    while !trading_state::is_draining() {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = sweep_dust(&ex).await {
+            Logger::warning(&format!("Dust sweep failed: {}", e));
+        }
+    }
+}
+
+async fn run_exit_manager(ex: ExecutorContext) {
+    let Some(exits) = ex.config.exits() else {
+        return;
+    };
+    let interval = Duration::from_secs(exits.interval_secs);
+    let mut exited = HashSet::new();
+    while !trading_state::is_draining() {
+        tokio::time::sleep(interval).await;
+        if let Err(e) = sweep_exits(&ex, exits, &mut exited).await {
+            Logger::warning(&format!("Exit check failed: {}", e));
+        }
+    }
+}
+
+/// Moves RPC calls back to an endpoint `RPC_URL` prefers once it answers again.
+async fn run_rpc_probe(rpc: Arc<RpcClient>) {
+    while !trading_state::is_draining() {
+        tokio::time::sleep(RPC_PROBE_INTERVAL).await;
+        rpc.reprobe().await;
+    }
+}
+
+async fn run_redeemer(
+    config: Arc<EnvConfig>,
+    http_client: Arc<reqwest::Client>,
+    rpc: Arc<RpcClient>,
+    signer: Arc<Mutex<PrivateKeySigner>>,
+    ledger: SharedLedger,
+) -> Result<()> {
+    let signer = signer.lock().await.clone();
+    let wallet = wallet_kind(&config, &rpc, &signer).await?;
+    if let RedeemWallet::Unsupported(why) = wallet {
+        Logger::warning(&format!("Resolved markets won't be redeemed automatically: {}", why));
+        return Ok(());
+    }
+    let interval = Duration::from_secs(config.redeem_interval_secs);
+    let mut attempts = HashMap::new();
+    while !trading_state::is_draining() {
+        let redeemed = redeem_resolved(
+            &config,
+            &http_client,
+            &rpc,
+            &signer,
+            wallet,
+            &ledger,
+            &mut attempts,
+        )
+        .await;
+        if let Err(e) = redeemed {
+            Logger::warning(&format!("Redeem check failed: {:#}", e));
+        }
+        tokio::time::sleep(interval).await;
+    }
+    Ok(())
+}
+
+/// Starts the executor: registers the trade loop and its helper tasks with the supervisor
+/// and returns once they are running.
+pub async fn run_trade_executor(
+    config: Arc<EnvConfig>,
+    http_client: Arc<reqwest::Client>,
+    rpc: Arc<RpcClient>,
+    clob_client: Arc<ClobClient<Authenticated<Normal>>>,
+    signer: Arc<Mutex<PrivateKeySigner>>,
+    rx: tokio::sync::mpsc::Receiver<(RtdsActivity, String)>,
+    shutdown: CancellationToken,
+) -> Result<TradeExecutorHandle> {
+    trading_state::set_draining(false);
+    metadata_cache::init(&config.state_dir);
+    rate_limit::init(config.request_rate_limit_per_sec);
+    let state = ExecutorState::new(&config, rpc);
+    let ex = ExecutorContext {
+        config: config.clone(),
+        http_client: http_client.clone(),
+        clob_client: clob_client.clone(),
+        signer: signer.clone(),
+        state: state.clone(),
+    };
+    let mut helpers = Vec::new();
+
+    {
+        let (config, http_client, ledger) =
+            (config.clone(), http_client.clone(), state.ledger.clone());
+        helpers.push(supervisor().spawn(
+            "position-reconciliation",
+            STOP_LAST,
+            TASK_MAX_RESTARTS,
+            move || {
+                let fut = run_position_reconciliation(
+                    config.clone(),
+                    http_client.clone(),
+                    ledger.clone(),
+                );
+                async move {
+                    fut.await;
+                    Ok(())
+                }
+            },
+        ));
+    }
+    {
+        let ex = ex.clone();
+        helpers.push(supervisor().spawn("resting-orders", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_resting_order_watch(ex.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    // Builds saved by a live run would place real orders; a dry run leaves them as they are.
+    if config.position_build().is_some() && config.dry_run {
+        let saved = position_builder::active(&config.state_dir).len();
+        if saved > 0 {
+            Logger::warning(&format!(
+                "Dry run - {} saved position build(s) paused, no build orders are placed",
+                saved
+            ));
+        }
+    } else if config.position_build().is_some() {
+        let ex = ex.clone();
+        helpers.push(supervisor().spawn("position-builder", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_position_builder(ex.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    {
+        let (config, http_client) = (config.clone(), http_client.clone());
+        helpers.push(supervisor().spawn(
+            "trader-classification",
+            STOP_LAST,
+            TASK_MAX_RESTARTS,
+            move || {
+                let fut = run_trader_classification(config.clone(), http_client.clone());
+                async move {
+                    fut.await;
+                    Ok(())
+                }
+            },
+        ));
+    }
+    if config.dust_threshold_usd > 0.0 {
+        let ex = ex.clone();
+        helpers.push(supervisor().spawn("dust-sweeper", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_dust_sweeper(ex.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if config.exits().is_some() {
+        let ex = ex.clone();
+        helpers.push(supervisor().spawn("exit-manager", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_exit_manager(ex.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if state.rpc.endpoint_count() > 1 {
+        let rpc = state.rpc.clone();
+        helpers.push(supervisor().spawn("rpc-probe", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_rpc_probe(rpc.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if config.auto_redeem {
+        let (config, http_client, rpc, signer, ledger) = (
+            config.clone(),
+            http_client.clone(),
+            state.rpc.clone(),
+            signer.clone(),
+            state.ledger.clone(),
+        );
+        helpers.push(supervisor().spawn("redeemer", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            run_redeemer(
+                config.clone(),
+                http_client.clone(),
+                rpc.clone(),
+                signer.clone(),
+                ledger.clone(),
+            )
+        }));
+    }
+    trader_portfolio::register_status();
+    if config.trader_portfolio_refresh_secs > 0 {
+        let (config, http_client) = (config.clone(), http_client.clone());
+        helpers.push(supervisor().spawn("trader-portfolio", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_trader_portfolio_refresh(config.clone(), http_client.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if config.order_template_refresh_secs > 0 {
+        let (config, http_client) = (config.clone(), http_client.clone());
+        helpers.push(supervisor().spawn("order-templates", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_order_template_refresh(config.clone(), http_client.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if config.concentration().is_some() {
+        let (config, http_client, ledger) =
+            (config.clone(), http_client.clone(), state.ledger.clone());
+        helpers.push(supervisor().spawn("concentration", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_concentration_report(config.clone(), http_client.clone(), ledger.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+    if config
+        .features
+        .aggregation
+        .as_ref()
+        .is_some_and(|a| a.adaptive.is_some())
+    {
+        let config = config.clone();
+        helpers.push(supervisor().spawn(
+            "aggregation-windows",
+            STOP_LAST,
+            TASK_MAX_RESTARTS,
+            move || {
+                let fut = aggregation::run_refresh(config.clone());
+                async move {
+                    fut.await;
+                    Ok(())
+                }
+            },
+        ));
+    }
+    if config.wallet_watchdog_interval_secs > 0 {
+        let (config, http_client, ledger) =
+            (config.clone(), http_client.clone(), state.ledger.clone());
+        helpers.push(supervisor().spawn("wallet-watchdog", STOP_LAST, TASK_MAX_RESTARTS, move || {
+            let fut = run_wallet_watchdog(config.clone(), http_client.clone(), ledger.clone());
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+
+    // Seed the last known balance so an RPC outage right after startup can still trade degraded.
+    let initial_balance = state
+        .rpc
+        .usdc_balance(&config.usdc_contract_address, &config.proxy_wallet)
+        .await;
+    state
+        .balance
+        .lock()
+        .await
+        .observe(initial_balance, config.balance_max_staleness_secs);
+
+    settle_order_intents(&config, &clob_client, &state).await;
+    let now = chrono::Utc::now().timestamp();
+    // Fills in restored aggregation buckets are copied from there, not again by catch-up,
+    // and copies with a logged order intent went out whatever the journal says.
+    let catch_up_from = config
+        .catch_up()
+        .and_then(|cc| catch_up_start(&config, cc.lookback_secs, now))
+        .map(|(since, mut seen)| {
+            seen.extend(aggregation::logged_tx_hashes(&aggregation::bucket_log(
+                &config.state_dir,
+            )));
+            seen.extend(order_intents::copy_ids(&config.state_dir));
+            (since, seen)
+        });
+    enroll_trials(&state, &config).await;
+    take_handover(&config, &http_client, &state).await;
+
+    if let Some((since, seen)) = catch_up_from {
+        let fut = catch_up_missed(ex.clone(), since, seen);
+        helpers.push(supervisor().spawn_once("catch-up", STOP_LAST, fut));
+    }
+
+    if config.overflow_catchup_fraction > 0.0 {
+        let max_position = config.copy_strategy_config.max_position_size_usd;
+        let raised = state.overflow.lock().await.take_raised(max_position);
+        if !raised.is_empty() {
+            let fut = overflow_catch_up(ex.clone(), raised);
+            helpers.push(supervisor().spawn_once("overflow-catch-up", STOP_LAST, fut));
+        }
+    }
+
+    manual_copy::register(ex.clone());
+    if config.interactive && interactive::available() {
+        let (commands, confirm_above_usd) = (ex.clone(), config.interactive_confirm_usd);
+        // Not restarted: a closed stdin stays closed.
+        helpers.push(supervisor().spawn("terminal-commands", STOP_LAST, 0, move || {
+            let fut = interactive::run(commands.clone(), confirm_above_usd);
+            async move {
+                fut.await;
+                Ok(())
+            }
+        }));
+    }
+
+    Logger::success("Trade executor started - ready to execute trades");
+    if let Some(max) = config.max_open_positions {
+        Logger::info(&format!(
+            "Max open positions: {} ({} currently open in ledger)",
+            max,
+            state.ledger.lock().await.open_count()
+        ));
+    }
+    report_open_positions(&state).await;
+    if let (Some(cc), Some(book)) = (config.consensus(), &state.consensus) {
+        Logger::info(&format!(
+            "Consensus mode: {} of {} traders within {}h ({} outcomes watching)",
+            cc.threshold,
+            config.user_addresses.len(),
+            cc.window_hours,
+            book.lock().await.watching().count()
+        ));
+    }
+
+    // Shared so a restarted trade loop picks up the same channel.
+    let rx = Arc::new(Mutex::new(rx));
+    let (balance_cache, stop) = (state.balance_cache.clone(), shutdown.clone());
+    let trade_loop = supervisor().spawn(
+        "trade-executor",
+        STOP_EXECUTOR,
+        TASK_MAX_RESTARTS,
+        move || process_trades(ex.clone(), rx.clone(), shutdown.clone()),
+    );
+
+    Ok(TradeExecutorHandle {
+        shutdown: stop,
+        balance_cache,
+        trade_loop,
+        helpers,
+    })
+}
+
+async fn process_trades(
+    ex: ExecutorContext,
+    rx: Arc<Mutex<tokio::sync::mpsc::Receiver<(RtdsActivity, String)>>>,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let ExecutorContext {
+        config,
+        http_client,
+        state,
+        ..
+    } = &ex;
+    let mut rx = rx.lock().await;
+    let mut pending = PendingGroups::default();
+    let mut fills = PendingFills::restore(
+        aggregation::bucket_log(&config.state_dir),
+        chrono::Utc::now().timestamp(),
+    );
+    let mut neg_risk = NegRiskCache::default();
+    while !trading_state::is_draining() {
+        // Wake up for the next rebalance or aggregation window to close, and to notice a
+        // shutdown, even if no signal arrives.
+        let poll = tokio::time::Instant::now() + DRAIN_POLL;
+        let wake = [pending.next_deadline(), fills.next_deadline()]
+            .into_iter()
+            .flatten()
+            .fold(poll, |wake, deadline| wake.min(deadline));
+        // Once stopped, nothing new gets in; what is queued still comes out of `recv`.
+        let received = tokio::select! {
+            _ = shutdown.cancelled(), if !rx.is_closed() => {
+                rx.close();
+                None
+            }
+            received = tokio::time::timeout_at(wake, rx.recv()) => received.ok(),
+        };
+        match received {
+            Some(Some((activity, address))) => {
+                // Without the prefetch the first signal in a market takes the full order path;
+                // later ones use its template once this lookup lands.
+                let uncached = activity
+                    .asset
+                    .clone()
+                    .filter(|a| order_templates::get(a).is_none());
+                let warm = config.order_template_refresh_secs > 0
+                    && config.market_prefetch_deadline_ms == 0;
+                if let (Some(asset), true) = (uncached, warm) {
+                    let (config, http_client) = (config.clone(), http_client.clone());
+                    tokio::spawn(async move {
+                        order_templates::warm(&http_client, &config, &asset).await;
+                    });
+                }
+                let group_key = match (config.rebalance(), activity.event_slug.as_deref()) {
+                    (Some(rc), Some(event)) if !event.is_empty() => {
+                        let asset = activity.asset.as_deref().unwrap_or("");
+                        neg_risk
+                            .is_neg_risk(http_client, config, asset)
+                            .await
+                            .then(|| (rc.window_secs, event.to_string()))
+                    }
+                    _ => None,
+                };
+                let activity = match (group_key, config.trade_aggregation_enabled()) {
+                    (Some((window_secs, event)), _) => {
+                        pending.hold(window_secs, &address, &event, activity);
+                        None
+                    }
+                    (None, true) => {
+                        let window_secs = aggregation::window_secs(config, &address);
+                        fills.hold(window_secs, &address, activity)
+                    }
+                    (None, false) => Some(activity),
+                };
+                if let Some(activity) = activity {
+                    if let Err(e) = execute_trade(&ex, activity, address).await {
+                        log_trade_error(&e);
+                    }
+                    report_open_positions(state).await;
+                }
+            }
+            Some(None) => {
+                flush_groups(&ex, &mut pending, true).await;
+                flush_fills(&ex, &mut fills, true).await;
+                if shutdown.is_cancelled() {
+                    break;
+                }
+                anyhow::bail!("trade channel closed");
+            }
+            None => {}
+        }
+        flush_groups(&ex, &mut pending, false).await;
+        flush_fills(&ex, &mut fills, false).await;
+    }
+    // Signals still waiting go into the shutdown handover rather than vanishing.
+    let mut leftovers: Vec<QueuedSignal> = pending
+        .take_expired(true)
+        .iter()
+        .flat_map(|group| {
+            group
+                .legs
+                .iter()
+                .map(|leg| QueuedSignal::new(leg, &group.trader, "rebalance_window"))
+        })
+        .collect();
+    for held in fills.take_ready(true, 0.0) {
+        leftovers.push(QueuedSignal::new(&held.merged(), &held.trader, "aggregation_window"));
+        fills.settle(&held);
+    }
+    while let Ok((activity, address)) = rx.try_recv() {
+        leftovers.push(QueuedSignal::new(&activity, &address, "queue"));
+    }
+    handover::set_queued(leftovers);
+    let volume = state.daily_volume.lock().await.summary(
+        config.copy_strategy_config.max_daily_volume_usd,
+        chrono::Utc::now().timestamp(),
+    );
+    Logger::info(&volume);
+    if config.dry_run {
+        Logger::info(&state.paper.lock().await.summary().describe());
+    }
+    Ok(())
+}
+
+/// Copies each closed aggregation bucket as one merged trade (every bucket if `all`).
+async fn flush_fills(ex: &ExecutorContext, fills: &mut PendingFills, all: bool) {
+    let flush_usd = ex
+        .config
+        .features
+        .aggregation
+        .as_ref()
+        .map_or(0.0, |a| a.flush_usd);
+    for held in fills.take_ready(all, flush_usd) {
+        if held.fills.len() > 1 {
+            Logger::info(&format!(
+                "Aggregated {} fills from {} into one trade",
+                held.fills.len(),
+                held.trader
+            ));
+        }
+        if let Err(e) = execute_trade(ex, held.merged(), held.trader.clone()).await {
+            log_trade_error(&e);
+        }
+        fills.settle(&held);
+        report_open_positions(&ex.state).await;
+    }
+}
+
+/// A copy that failed part way. When the data API stayed down or rate limited through every
+/// retry (a transient `FetchError`) the trade was skipped for want of data rather than
+/// rejected, and says so.
+fn log_trade_error(e: &anyhow::Error) {
+    match e.downcast_ref::<FetchError>() {
+        Some(fetch) if fetch.is_transient() => Logger::warning(&format!(
+            "Trade not copied: data API unavailable ({})",
+            fetch
+        )),
+        _ => Logger::error(&format!("Error executing trade: {}", e)),
+    }
+}
+
+/// Dispatches closed rebalance windows: real rebalances run as a plan, anything else
+/// (a lone buy, several buys) is copied leg by leg as usual.
+async fn flush_groups(ex: &ExecutorContext, pending: &mut PendingGroups, all: bool) {
+    for group in pending.take_expired(all) {
+        if group.is_rebalance() {
+            execute_rebalance(ex, group).await;
+            continue;
+        }
+        for leg in group.legs {
+            if let Err(e) = execute_trade(ex, leg, group.trader.clone()).await {
+                log_trade_error(&e);
+            }
+        }
+    }
+}
+
+/// Splits `wallet` into what open orders lock, the reserve and the free part, refreshing
+/// the locks from the order trackers, and publishes it as the `balance` status section.
+async fn split_balance(
+    state: &ExecutorState,
+    config: &EnvConfig,
+    wallet: f64,
+) -> BalanceBreakdown {
+    let locked = balance::locked_in_orders(&config.state_dir);
+    let breakdown = {
+        let mut tracker = state.balance.lock().await;
+        tracker.set_locked(locked);
+        tracker.breakdown(wallet)
+    };
+    status::publish(
+        "balance",
+        serde_json::to_value(breakdown).unwrap_or_default(),
+    );
+    breakdown
+}
+
+/// Publishes the open-position count against `MAX_OPEN_POSITIONS` to `/status` and the digest.
+async fn report_open_positions(state: &ExecutorState) {
+    let Some(limiter) = &state.open_position_limiter else {
+        return;
+    };
+    let open = state.ledger.lock().await.open_count();
+    let limiter = limiter.lock().await;
+    status::publish(
+        "open_positions",
+        serde_json::json!({
+            "open": open,
+            "max": limiter.max(),
+            "openings_blocked": limiter.is_blocked(),
+        }),
+    );
+    let paused = if limiter.is_blocked() {
+        ", openings paused"
+    } else {
+        ""
+    };
+    digest::note(
+        "open_positions",
+        format!("open positions {}/{}{}", open, limiter.max(), paused),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn dry_run_sends_no_order() {
+        let sent = AtomicU32::new(0);
+        let place = || async {
+            sent.fetch_add(1, Ordering::SeqCst);
+            Ok(OrderFill {
+                tokens: 10.0,
+                usd: 5.0,
+                ..OrderFill::default()
+            })
+        };
+
+        let fill = gated_order(true, place).await.unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), 0);
+        assert_eq!(fill.tokens, 0.0);
+
+        let fill = gated_order(false, place).await.unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert_eq!(fill.tokens, 10.0);
+    }
+
+    #[tokio::test]
+    async fn signal_positions_fetch_refreshes_the_portfolio_cache() {
+        const A: &str = "0x00000000000000000000000000000000000000a1";
+        const B: &str = "0x00000000000000000000000000000000000000b2";
+        let config = crate::config::test_config(&[
+            ("TRADER_GROUPS", &format!("whale:{},{}", A, B)),
+            ("TRADER_PORTFOLIO_MAX_AGE_SECS", "60"),
+        ]);
+        let position = |value: f64| -> UserPosition {
+            serde_json::from_value(serde_json::json!({ "currentValue": value })).unwrap()
+        };
+        let fetches = AtomicU32::new(0);
+        let fetch = |_member: String| {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            async { Ok(vec![position(5.0)]) }
+        };
+        let now = 1_800_000_000;
+        trader_portfolio::record(B, 25.0, now - 10);
+
+        let total = trader_portfolio_value(&config, "whale", A, &[position(40.0)], now, fetch)
+            .await
+            .unwrap();
+        assert_eq!(total, 65.0);
+        assert_eq!(fetches.load(Ordering::SeqCst), 0);
+        assert_eq!(trader_portfolio::read(A, 60, now), PortfolioRead::Fresh(40.0));
+
+        // B's cached value has aged out, so it is fetched; A again comes from the signal.
+        let later = now + 61;
+        let total = trader_portfolio_value(&config, "whale", A, &[position(30.0)], later, fetch)
+            .await
+            .unwrap();
+        assert_eq!(total, 35.0);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(trader_portfolio::read(B, 60, later), PortfolioRead::Fresh(5.0));
+    }
+
+    #[tokio::test]
+    async fn rebalance_buy_legs_opening_markets_count_against_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        let config = crate::config::test_config(&[
+            ("MAX_OPEN_POSITIONS", "2"),
+            ("STATE_DIR", &path("state")),
+            ("TRADE_LOG_PATH", &path("trades.jsonl")),
+            ("MALFORMED_LOG_PATH", &path("malformed_activity.jsonl")),
+        ]);
+        let state = ExecutorState::new(&config, RpcClient::for_url(&config.rpc_url).unwrap());
+        state.ledger.lock().await.record_buy("yes-a", "market-a", None, 10.0, 5.0);
+        let buy = |asset: &str, condition_id: &str| RtdsActivity {
+            side: Some("BUY".to_string()),
+            asset: Some(asset.to_string()),
+            condition_id: Some(condition_id.to_string()),
+            size: Some(100.0),
+            price: Some(0.5),
+            ..Default::default()
+        };
+
+        let mut budget = plan_budget(1_000.0);
+        let mut reasons = Vec::new();
+        // One slot is left: the first new market takes it, and a second outcome of that
+        // market or a held market doesn't need one.
+        for (asset, market) in [
+            ("yes-b", "market-b"),
+            ("no-b", "market-b"),
+            ("no-a", "market-a"),
+            ("yes-c", "market-c"),
+        ] {
+            let leg = buy(asset, market);
+            let reason = check_rebalance_leg(&config, &state, &leg, &[], &[], &mut budget);
+            reasons.push(reason.await);
+        }
+        let capped = Some("max open positions reached (2)".to_string());
+        assert_eq!(reasons, vec![None, None, None, capped]);
+    }
+
+    fn plan_budget(balance_usd: f64) -> PlanBudget {
+        PlanBudget {
+            balance_usd,
+            trader: "whale".to_string(),
+            trial: None,
+            sizing: SizingInputs::default(),
+            planned_opens: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn rebalance_buy_legs_are_sized_with_what_the_sells_free() {
+        let config = crate::config::test_config(&[
+            ("COPY_STRATEGY", "FIXED"),
+            ("COPY_SIZE", "50"),
+            ("MAX_POSITION_SIZE_USD", "80"),
+        ]);
+        let state = ExecutorState::new(&config, RpcClient::for_url(&config.rpc_url).unwrap());
+        let leg = |side: &str, asset: &str, size: f64| RtdsActivity {
+            side: Some(side.to_string()),
+            asset: Some(asset.to_string()),
+            size: Some(size),
+            price: Some(0.5),
+            ..Default::default()
+        };
+        let held = |asset: &str, size: f64| UserPosition {
+            asset: Some(asset.to_string()),
+            size: Some(size),
+            avg_price: Some(0.5),
+            ..Default::default()
+        };
+        let sell = leg("SELL", "yes-a", 100.0);
+        let buy = leg("BUY", "yes-b", 200.0);
+
+        // Nothing in the wallet: selling all 200 held tokens at 0.5 frees $100 for the buy.
+        let mut budget = plan_budget(0.0);
+        let mine = [held("yes-a", 200.0)];
+        let blocked = check_rebalance_leg(&config, &state, &sell, &mine, &[], &mut budget).await;
+        assert_eq!((blocked, budget.balance_usd), (None, 100.0));
+        let blocked = check_rebalance_leg(&config, &state, &buy, &mine, &[], &mut budget).await;
+        assert_eq!((blocked, budget.balance_usd), (None, 50.0));
+
+        // $60 already held in the outcome leaves $20 under the position cap.
+        let mut budget = plan_budget(1_000.0);
+        let mine = [held("yes-b", 120.0)];
+        let blocked = check_rebalance_leg(&config, &state, &buy, &mine, &[], &mut budget).await;
+        assert_eq!((blocked, budget.balance_usd), (None, 980.0));
+    }
+}
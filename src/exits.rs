@@ -0,0 +1,314 @@
+//! Take-profit and stop-loss for copied positions. Copying only the traders' entries rides a
+//! position to resolution unless they sell; with `TAKE_PROFIT_PERCENT` or `STOP_LOSS_PERCENT`
+//! set, the exit manager checks my positions every `EXIT_CHECK_INTERVAL_SECS` and sells the
+//! whole position once its PnL crosses a threshold. Positions the trader already left are
+//! not touched: their SELL was copied, and what is left over is the dust sweeper's.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::config::{ExitConfig, SizingInputs};
+use crate::dust::trader_still_holds;
+use crate::executor::{fetch_positions, ExecutorContext};
+use crate::trading_state;
+use crate::types::{UserActivity, UserPosition};
+use crate::utils::{post_order, Logger, OrderContext, MIN_ORDER_SIZE_TOKENS};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitDecision {
+    Hold,
+    /// PnL at or above `+threshold` percent.
+    TakeProfit { pnl_percent: f64, threshold: f64 },
+    /// PnL at or below `-threshold` percent.
+    StopLoss { pnl_percent: f64, threshold: f64 },
+}
+
+impl fmt::Display for ExitDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hold => write!(f, "hold"),
+            Self::TakeProfit {
+                pnl_percent,
+                threshold,
+            } => write!(f, "take profit ({:+.1}% ≥ +{}%)", pnl_percent, threshold),
+            Self::StopLoss {
+                pnl_percent,
+                threshold,
+            } => write!(f, "stop loss ({:+.1}% ≤ -{}%)", pnl_percent, threshold),
+        }
+    }
+}
+
+/// The position's PnL in percent: the data API's `percentPnl`, else from the average and
+/// current price.
+pub fn pnl_percent(position: &UserPosition) -> Option<f64> {
+    position.percent_pnl.filter(|p| p.is_finite()).or_else(|| {
+        let avg = position.avg_price.filter(|p| *p > 0.0)?;
+        Some((position.cur_price? / avg - 1.0) * 100.0)
+    })
+}
+
+/// Checks one of my positions against the thresholds for its market. Resolved positions
+/// and ones without a current price are held: there is no book to sell into.
+pub fn decide(position: &UserPosition, exits: &ExitConfig) -> ExitDecision {
+    if position.redeemable == Some(true) || position.cur_price.unwrap_or(0.0) <= 0.0 {
+        return ExitDecision::Hold;
+    }
+    let Some(pnl_percent) = pnl_percent(position) else {
+        return ExitDecision::Hold;
+    };
+    let (take_profit, stop_loss) = exits.thresholds(position.slug.as_deref());
+    if let Some(threshold) = take_profit.filter(|t| pnl_percent >= *t) {
+        return ExitDecision::TakeProfit {
+            pnl_percent,
+            threshold,
+        };
+    }
+    if let Some(threshold) = stop_loss.filter(|t| pnl_percent <= -*t) {
+        return ExitDecision::StopLoss {
+            pnl_percent,
+            threshold,
+        };
+    }
+    ExitDecision::Hold
+}
+
+/// Sells the positions past a threshold. `exited` holds the assets already sold, so a
+/// position the data API still lists right after its exit is not sold twice; an asset is
+/// dropped from it once the position is gone. Exits count toward the day's volume.
+pub async fn sweep_exits(
+    ex: &ExecutorContext,
+    exits: &ExitConfig,
+    exited: &mut HashSet<String>,
+) -> anyhow::Result<()> {
+    let ExecutorContext {
+        config,
+        http_client,
+        clob_client,
+        signer,
+        state,
+    } = ex;
+    let ledger = &state.ledger;
+    let my_positions = fetch_positions(http_client, config, &config.proxy_wallet).await?;
+    exited.retain(|asset| {
+        my_positions
+            .iter()
+            .any(|p| p.asset.as_deref() == Some(asset.as_str()) && p.size.unwrap_or(0.0) > 0.0)
+    });
+    let mut trader_cache = HashMap::new();
+    for pos in &my_positions {
+        let Some(asset) = pos.asset.as_deref() else {
+            continue;
+        };
+        if exited.contains(asset) {
+            continue;
+        }
+        let decision = decide(pos, exits);
+        if decision == ExitDecision::Hold {
+            continue;
+        }
+        let title = pos.title.as_deref().unwrap_or(asset);
+        let (size, value) = (pos.size.unwrap_or(0.0), pos.current_value.unwrap_or(0.0));
+        let min_usd = config.copy_strategy_config.min_order_size_usd;
+        if value < min_usd || size < MIN_ORDER_SIZE_TOKENS {
+            Logger::info(&format!(
+                "🎯 {} on {}: not sold, ${:.2} ({:.2} tokens) is below the order minimum",
+                decision, title, value, size
+            ));
+            continue;
+        }
+        let trader = ledger
+            .lock()
+            .await
+            .position(asset)
+            .and_then(|p| p.trader.clone());
+        match trader_still_holds(
+            http_client,
+            config,
+            &mut trader_cache,
+            trader.as_deref(),
+            asset,
+        )
+        .await
+        {
+            Some(true) => {}
+            Some(false) => {
+                Logger::info(&format!(
+                    "🎯 {} on {}: not sold, the trader already exited",
+                    decision, title
+                ));
+                continue;
+            }
+            None => {
+                Logger::info(&format!(
+                    "🎯 {} on {} deferred: trader positions unavailable",
+                    decision, title
+                ));
+                continue;
+            }
+        }
+        if let Err(reason) = trading_state::check("SELL") {
+            Logger::info(&format!("🎯 {} on {} deferred: {}", decision, title, reason));
+            continue;
+        }
+        Logger::info(&format!(
+            "🎯 {} on {}: selling {:.2} tokens (${:.2})",
+            decision, title, size, value
+        ));
+        if config.dry_run {
+            // Once per position: it stays in place since nothing is sold.
+            Logger::info("Dry run - exit not placed");
+            exited.insert(asset.to_string());
+            continue;
+        }
+        // The whole position: a SELL of all of it with nothing left over is a full exit.
+        let trade = UserActivity {
+            condition_id: pos.condition_id.clone(),
+            asset: pos.asset.clone(),
+            side: Some("SELL".to_string()),
+            size: Some(size),
+            price: pos.cur_price,
+            title: pos.title.clone(),
+            slug: pos.slug.clone(),
+            ..Default::default()
+        };
+        let signer_guard = signer.lock().await;
+        let order = OrderContext {
+            config,
+            clob_client,
+            signer: &signer_guard,
+            http_client,
+            chaos: state.chaos.as_deref(),
+            sizing: SizingInputs::default(),
+        };
+        let fill = post_order(
+            &order,
+            "sell",
+            Some(pos),
+            None,
+            &trade,
+            0.0,
+            &config.proxy_wallet,
+        )
+        .await;
+        drop(signer_guard);
+        match fill {
+            Ok(fill) if fill.tokens > 0.0 => {
+                ledger.lock().await.record_sell(asset, fill.tokens);
+                state.daily_volume.lock().await.record(
+                    fill.usd,
+                    true,
+                    chrono::Utc::now().timestamp(),
+                );
+                exited.insert(asset.to_string());
+                Logger::info(&format!(
+                    "🎯 Exited {}: sold {:.2} tokens for ${:.2}",
+                    title, fill.tokens, fill.usd
+                ));
+            }
+            Ok(_) => Logger::warning(&format!("🎯 Exit from {} filled nothing", title)),
+            Err(e) => Logger::warning(&format!("🎯 Exit from {} failed: {}", title, e)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exits(take_profit: Option<f64>, stop_loss: Option<f64>) -> ExitConfig {
+        ExitConfig {
+            take_profit_percent: take_profit,
+            stop_loss_percent: stop_loss,
+            take_profit_by_slug: Vec::new(),
+            stop_loss_by_slug: Vec::new(),
+            interval_secs: 60,
+        }
+    }
+
+    fn position(avg: f64, price: f64, percent_pnl: Option<f64>) -> UserPosition {
+        UserPosition {
+            asset: Some("123".to_string()),
+            slug: Some("will-it-rain".to_string()),
+            size: Some(10.0),
+            avg_price: Some(avg),
+            cur_price: Some(price),
+            percent_pnl,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn thresholds_are_inclusive() {
+        let rules = exits(Some(50.0), Some(20.0));
+        assert_eq!(
+            decide(&position(0.40, 0.60, Some(50.0)), &rules),
+            ExitDecision::TakeProfit {
+                pnl_percent: 50.0,
+                threshold: 50.0
+            }
+        );
+        assert_eq!(
+            decide(&position(0.50, 0.40, Some(-20.0)), &rules),
+            ExitDecision::StopLoss {
+                pnl_percent: -20.0,
+                threshold: 20.0
+            }
+        );
+        assert_eq!(
+            decide(&position(0.50, 0.45, Some(-10.0)), &rules),
+            ExitDecision::Hold
+        );
+    }
+
+    #[test]
+    fn pnl_falls_back_to_prices() {
+        let p = position(0.40, 0.30, None);
+        assert!((pnl_percent(&p).unwrap() + 25.0).abs() < 1e-9);
+        assert!(matches!(
+            decide(&p, &exits(None, Some(25.0))),
+            ExitDecision::StopLoss { .. }
+        ));
+        assert_eq!(decide(&p, &exits(Some(10.0), None)), ExitDecision::Hold);
+    }
+
+    #[test]
+    fn resolved_or_unpriced_positions_are_held() {
+        let rules = exits(Some(10.0), Some(10.0));
+        let resolved = UserPosition {
+            redeemable: Some(true),
+            ..position(0.40, 1.0, Some(150.0))
+        };
+        assert_eq!(decide(&resolved, &rules), ExitDecision::Hold);
+        assert_eq!(
+            decide(&position(0.40, 0.0, Some(-100.0)), &rules),
+            ExitDecision::Hold
+        );
+    }
+
+    #[test]
+    fn slug_overrides_replace_or_disable_the_default() {
+        let rules = ExitConfig {
+            take_profit_by_slug: vec![("will-it-rain".to_string(), Some(100.0))],
+            stop_loss_by_slug: vec![("will-it-rain".to_string(), None)],
+            ..exits(Some(20.0), Some(20.0))
+        };
+        assert_eq!(
+            decide(&position(0.40, 0.60, Some(50.0)), &rules),
+            ExitDecision::Hold
+        );
+        assert_eq!(
+            decide(&position(0.50, 0.10, Some(-80.0)), &rules),
+            ExitDecision::Hold
+        );
+        let other = UserPosition {
+            slug: Some("other-market".to_string()),
+            ..position(0.40, 0.60, Some(50.0))
+        };
+        assert!(matches!(
+            decide(&other, &rules),
+            ExitDecision::TakeProfit { .. }
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+//! Handover between runs: what a shutdown left behind (resting orders, signals that never
+//! ran, pauses in force) is written at shutdown and reconciled at the next startup.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::config::{EnvConfig, UsdcSizePreference};
+use crate::resting_orders::{self, RestingOrder};
+use crate::trading_state::{self, PauseLevel};
+use crate::types::{RtdsActivity, UserActivity};
+use crate::utils::{load_json, save_json, state_path, Logger};
+
+const HANDOVER_FILE: &str = "handover.json";
+/// Wallet fills this close to an order's size count as filling it.
+const FILL_TOLERANCE: f64 = 0.05;
+
+/// Signals the executor still held when it stopped, set as it winds down.
+static QUEUED: Mutex<Vec<QueuedSignal>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedSignal {
+    pub trader: String,
+    pub asset: Option<String>,
+    pub side: Option<String>,
+    pub title: Option<String>,
+    pub usdc_size: Option<f64>,
+    pub tx_hash: Option<String>,
+    /// When the trader traded.
+    pub timestamp: Option<i64>,
+    /// `rebalance_window`, `aggregation_window` or `queue`.
+    pub held_in: String,
+}
+
+impl QueuedSignal {
+    pub fn new(activity: &RtdsActivity, trader: &str, held_in: &str) -> Self {
+        Self {
+            trader: trader.to_lowercase(),
+            asset: activity.asset.clone(),
+            side: activity.side.clone(),
+            title: activity.title.clone(),
+            usdc_size: activity
+                .usd_value(UsdcSizePreference::Conservative)
+                .ok()
+                .map(|n| n.chosen),
+            tx_hash: activity.transaction_hash.clone(),
+            timestamp: activity.timestamp,
+            held_in: held_in.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PauseNote {
+    pub source: String,
+    /// `buys` or `all`.
+    pub level: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Handover {
+    pub written_at: i64,
+    pub open_orders: Vec<RestingOrder>,
+    pub queued_signals: Vec<QueuedSignal>,
+    pub pauses: Vec<PauseNote>,
+}
+
+/// What became of a resting order while the bot was down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderFate {
+    /// Still on the book: kept in the resting order tracker.
+    Live { filled_tokens: f64 },
+    Filled { tokens: f64, usd: f64 },
+    /// Expired or cancelled without a fill.
+    Gone,
+}
+
+impl Handover {
+    /// Everything in force right now, with the executor's leftover signals.
+    pub fn capture(state_dir: &str, now: i64) -> Self {
+        let queued_signals = QUEUED.lock().map(|q| q.clone()).unwrap_or_default();
+        let pauses = trading_state::holds()
+            .into_iter()
+            .map(|(source, level, reason)| PauseNote {
+                source: source.to_string(),
+                level: match level {
+                    PauseLevel::Buys => "buys".to_string(),
+                    PauseLevel::All => "all".to_string(),
+                },
+                reason,
+            })
+            .collect();
+        Self {
+            written_at: now,
+            open_orders: resting_orders::all(state_dir)
+                .into_iter()
+                .filter(|o| o.expires_at > now)
+                .collect(),
+            queued_signals,
+            pauses,
+        }
+    }
+
+    pub fn save(&self, state_dir: &str) -> Result<()> {
+        save_json(&state_path(state_dir, HANDOVER_FILE), self)
+    }
+
+    /// Loads the last run's handover and removes it, so it is reported once.
+    pub fn take(state_dir: &str) -> Option<Self> {
+        let path = state_path(state_dir, HANDOVER_FILE);
+        let handover = load_json(&path);
+        let _ = std::fs::remove_file(&path);
+        handover
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open_orders.is_empty() && self.queued_signals.is_empty() && self.pauses.is_empty()
+    }
+}
+
+/// Records the signals the executor didn't get to; `capture` picks them up.
+pub fn set_queued(signals: Vec<QueuedSignal>) {
+    if let Ok(mut queued) = QUEUED.lock() {
+        *queued = signals;
+    }
+}
+
+/// Matches the wallet's trades since the handover against each open order: enough volume on
+/// the order's asset and side fills it, an expired order with less is gone, anything else is
+/// still live. Each wallet trade fills at most one order.
+pub fn reconcile_orders(
+    handover: &Handover,
+    wallet_trades: &[UserActivity],
+    now: i64,
+) -> Vec<(RestingOrder, OrderFate)> {
+    let mut trades: Vec<(&UserActivity, f64)> = wallet_trades
+        .iter()
+        .filter(|t| t.timestamp.unwrap_or(0) >= handover.written_at)
+        .map(|t| (t, t.size.unwrap_or(0.0)))
+        .collect();
+    trades.sort_by_key(|(t, _)| t.timestamp.unwrap_or(0));
+
+    let mut orders = handover.open_orders.clone();
+    orders.sort_by_key(|o| o.placed_at);
+    orders
+        .into_iter()
+        .map(|order| {
+            let mut tokens = 0.0;
+            let mut usd = 0.0;
+            for (trade, left) in trades.iter_mut() {
+                let matches = trade.asset.as_deref() == Some(order.asset.as_str())
+                    && trade.side.as_deref() == Some(order.side.as_str());
+                if !matches || *left <= 0.0 || tokens >= order.size {
+                    continue;
+                }
+                let take = left.min(order.size - tokens);
+                tokens += take;
+                usd += take * trade.price.unwrap_or(order.price);
+                *left -= take;
+            }
+            let fate = if tokens >= order.size * (1.0 - FILL_TOLERANCE) {
+                OrderFate::Filled { tokens, usd }
+            } else if order.expires_at <= now {
+                if tokens > 0.0 {
+                    OrderFate::Filled { tokens, usd }
+                } else {
+                    OrderFate::Gone
+                }
+            } else {
+                OrderFate::Live {
+                    filled_tokens: tokens,
+                }
+            };
+            (order, fate)
+        })
+        .collect()
+}
+
+/// Writes the handover at shutdown, or clears a stale one when nothing is left over.
+pub fn write(config: &EnvConfig) {
+    let handover = Handover::capture(&config.state_dir, chrono::Utc::now().timestamp());
+    if handover.is_empty() {
+        let _ = std::fs::remove_file(state_path(&config.state_dir, HANDOVER_FILE));
+        return;
+    }
+    match handover.save(&config.state_dir) {
+        Ok(()) => Logger::info(&format!(
+            "Handover saved: {} open order(s), {} queued signal(s), {} pause(s)",
+            handover.open_orders.len(),
+            handover.queued_signals.len(),
+            handover.pauses.len()
+        )),
+        Err(e) => Logger::warning(&format!("Failed to save the handover: {}", e)),
+    }
+}
+
+/// Summary lines for the startup handover panel.
+pub fn summary_lines(
+    handover: &Handover,
+    orders: &[(RestingOrder, OrderFate)],
+    now: i64,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (order, fate) in orders {
+        let state = match fate {
+            OrderFate::Live { filled_tokens } if *filled_tokens > 0.0 => {
+                format!("still live, {:.2} filled while offline", filled_tokens)
+            }
+            OrderFate::Live { .. } => "still live".to_string(),
+            OrderFate::Filled { tokens, usd } => {
+                format!("filled {:.2} for $ {:.2} while offline", tokens, usd)
+            }
+            OrderFate::Gone => "expired or cancelled".to_string(),
+        };
+        lines.push(format!(
+            "📌 {} {} {:.2} @ ${:.2} ({}) - {}",
+            order.side, order.asset, order.size, order.price, order.order_id, state
+        ));
+    }
+    for signal in &handover.queued_signals {
+        let age = signal
+            .timestamp
+            .map(|ts| format!("{:.0}m old", (now - ts).max(0) as f64 / 60.0))
+            .unwrap_or_else(|| "age unknown".to_string());
+        lines.push(format!(
+            "⏳ {} {} $ {:.2} from {} ({}, {}) - not copied",
+            signal.side.as_deref().unwrap_or("?"),
+            signal
+                .title
+                .as_deref()
+                .or(signal.asset.as_deref())
+                .unwrap_or("unknown market"),
+            signal.usdc_size.unwrap_or(0.0),
+            Logger::format_address(&signal.trader),
+            signal.held_in.replace('_', " "),
+            age
+        ));
+    }
+    for pause in &handover.pauses {
+        lines.push(format!(
+            "⏸  {} paused ({}): {} - not carried over",
+            if pause.level == "all" { "Trading" } else { "BUYs" },
+            pause.source,
+            pause.reason
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: i64 = 1_760_000_000;
+
+    fn order(id: &str, asset: &str, size: f64, expires_at: i64) -> RestingOrder {
+        RestingOrder {
+            order_id: id.to_string(),
+            asset: asset.to_string(),
+            side: "BUY".to_string(),
+            trader: "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b".to_string(),
+            price: 0.42,
+            size,
+            placed_at: T0 - 600,
+            expires_at,
+            condition_id: None,
+            copy_id: String::new(),
+            filled_tokens: 0.0,
+            matched_before: 0.0,
+            quote: None,
+        }
+    }
+
+    fn wallet_trade(asset: &str, size: f64, price: f64, at: i64) -> UserActivity {
+        UserActivity {
+            asset: Some(asset.to_string()),
+            side: Some("BUY".to_string()),
+            size: Some(size),
+            price: Some(price),
+            timestamp: Some(at),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shutdown_startup_cycle_with_one_order_filled_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let handover = Handover {
+            written_at: T0,
+            open_orders: vec![
+                order("0xaaa", "111", 20.0, T0 + 3_600),
+                order("0xbbb", "222", 10.0, T0 + 3_600),
+            ],
+            queued_signals: vec![QueuedSignal {
+                trader: "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b".to_string(),
+                asset: Some("333".to_string()),
+                side: Some("BUY".to_string()),
+                title: Some("Fed cut in December?".to_string()),
+                usdc_size: Some(50.0),
+                tx_hash: Some("0xfeed".to_string()),
+                timestamp: Some(T0 - 120),
+                held_in: "rebalance_window".to_string(),
+            }],
+            pauses: Vec::new(),
+        };
+        handover.save(state_dir).unwrap();
+
+        let loaded = Handover::take(state_dir).unwrap();
+        assert!(Handover::take(state_dir).is_none(), "reported once");
+        assert_eq!(loaded.open_orders.len(), 2);
+        assert_eq!(loaded.queued_signals, handover.queued_signals);
+
+        // Order 0xbbb filled in two pieces while the bot was down; a fill from before the
+        // shutdown doesn't count.
+        let trades = vec![
+            wallet_trade("222", 4.0, 0.42, T0 - 30),
+            wallet_trade("222", 6.0, 0.42, T0 + 60),
+            wallet_trade("222", 4.0, 0.41, T0 + 120),
+        ];
+        let fates = reconcile_orders(&loaded, &trades, T0 + 300);
+        assert_eq!(fates[0].0.order_id, "0xaaa");
+        assert_eq!(fates[0].1, OrderFate::Live { filled_tokens: 0.0 });
+        assert_eq!(fates[1].0.order_id, "0xbbb");
+        match fates[1].1 {
+            OrderFate::Filled { tokens, usd } => {
+                assert!((tokens - 10.0).abs() < 1e-9);
+                assert!((usd - (6.0 * 0.42 + 4.0 * 0.41)).abs() < 1e-9);
+            }
+            ref other => panic!("expected a fill, got {:?}", other),
+        }
+
+        let lines = summary_lines(&loaded, &fates, T0 + 300);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("still live"));
+        assert!(lines[1].contains("filled 10.00"));
+        assert!(lines[2].contains("7m old"));
+    }
+
+    #[test]
+    fn expired_order_without_fills_is_gone() {
+        let handover = Handover {
+            written_at: T0,
+            open_orders: vec![order("0xccc", "111", 20.0, T0 + 60)],
+            ..Default::default()
+        };
+        let partial = vec![wallet_trade("111", 5.0, 0.42, T0 + 30)];
+        assert_eq!(
+            reconcile_orders(&handover, &[], T0 + 300)[0].1,
+            OrderFate::Gone
+        );
+        assert!(matches!(
+            reconcile_orders(&handover, &partial, T0 + 300)[0].1,
+            OrderFate::Filled { tokens, .. } if tokens == 5.0
+        ));
+        assert_eq!(
+            reconcile_orders(&handover, &partial, T0 + 40)[0].1,
+            OrderFate::Live { filled_tokens: 5.0 }
+        );
+    }
+}
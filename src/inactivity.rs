@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::InactivityDecayConfig;
+use crate::utils::{load_json, save_json, Logger};
+
+const ACTIVITY_FILE: &str = "trader_activity.json";
+
+pub type SharedActivity = Arc<Mutex<TraderActivityBook>>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraderActivity {
+    /// Timestamp of the trader's latest signal the bot received.
+    pub last_trade_at: i64,
+    /// Ramp position of the next BUY copy after a long gap; `None` once back at full size.
+    #[serde(default)]
+    pub ramp_step: Option<usize>,
+}
+
+/// Last signal per logical trader, persisted so a gap spanning a restart is still noticed.
+/// Gaps are measured between signals the bot saw, so downtime counts as inactivity.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TraderActivityBook {
+    traders: HashMap<String, TraderActivity>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl TraderActivityBook {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, ACTIVITY_FILE);
+        let mut book: TraderActivityBook = load_json(&path).unwrap_or_default();
+        book.path = Some(path);
+        book
+    }
+
+    pub fn days_since_last_trade(&self, trader: &str, now: i64) -> Option<f64> {
+        self.traders
+            .get(trader)
+            .map(|a| (now - a.last_trade_at).max(0) as f64 / 86_400.0)
+    }
+
+    /// Records a signal from `trader` at `at`. A gap longer than `after_days` since the
+    /// previous one restarts the ramp; returns the gap in days when it did.
+    pub fn observe(
+        &mut self,
+        trader: &str,
+        at: i64,
+        decay: Option<&InactivityDecayConfig>,
+    ) -> Option<f64> {
+        let entry = self.traders.entry(trader.to_string()).or_default();
+        let previous = entry.last_trade_at;
+        if at <= previous {
+            return None;
+        }
+        entry.last_trade_at = at;
+        let mut restarted = None;
+        if let Some(decay) = decay {
+            let gap_days = (at - previous) as f64 / 86_400.0;
+            if previous > 0 && gap_days > decay.after_days as f64 {
+                entry.ramp_step = Some(0);
+                restarted = Some(gap_days);
+            }
+        }
+        self.persist();
+        restarted
+    }
+
+    /// Size multiplier for `trader`'s next BUY copy while ramping back in.
+    pub fn multiplier(&self, trader: &str, decay: &InactivityDecayConfig) -> Option<f64> {
+        let step = self.traders.get(trader)?.ramp_step?;
+        decay.ramp.get(step).copied()
+    }
+
+    /// Moves `trader` one step along the ramp after a decayed BUY copy was placed.
+    pub fn record_copy(&mut self, trader: &str, decay: &InactivityDecayConfig) {
+        let Some(entry) = self.traders.get_mut(trader) else {
+            return;
+        };
+        let Some(step) = entry.ramp_step else {
+            return;
+        };
+        entry.ramp_step = (step + 1 < decay.ramp.len()).then_some(step + 1);
+        self.persist();
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist trader activity: {}", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_decay_ramp;
+
+    const DAY: i64 = 86_400;
+
+    fn decay() -> InactivityDecayConfig {
+        InactivityDecayConfig {
+            after_days: 30,
+            ramp: vec![0.25, 0.5],
+        }
+    }
+
+    #[test]
+    fn long_gap_ramps_back_to_full_size() {
+        let decay = decay();
+        let mut book = TraderActivityBook::default();
+        assert_eq!(book.observe("t", DAY, Some(&decay)), None);
+        assert_eq!(book.multiplier("t", &decay), None);
+
+        let gap = book.observe("t", 41 * DAY, Some(&decay)).unwrap();
+        assert!((gap - 40.0).abs() < 1e-9);
+        assert_eq!(book.multiplier("t", &decay), Some(0.25));
+        book.record_copy("t", &decay);
+        assert_eq!(book.multiplier("t", &decay), Some(0.5));
+        book.record_copy("t", &decay);
+        assert_eq!(book.multiplier("t", &decay), None);
+    }
+
+    #[test]
+    fn short_gaps_and_stale_signals_leave_size_alone() {
+        let decay = decay();
+        let mut book = TraderActivityBook::default();
+        book.observe("t", 100 * DAY, Some(&decay));
+        assert_eq!(book.observe("t", 110 * DAY, Some(&decay)), None);
+        assert_eq!(book.observe("t", 50 * DAY, Some(&decay)), None);
+        assert_eq!(book.multiplier("t", &decay), None);
+        assert_eq!(book.days_since_last_trade("t", 112 * DAY), Some(2.0));
+    }
+
+    #[test]
+    fn ramp_parses_percentages_and_drops_full_size_tail() {
+        assert_eq!(parse_decay_ramp("25, 50,100").unwrap(), [0.25, 0.5]);
+        assert!(parse_decay_ramp("100").unwrap().is_empty());
+        assert!(parse_decay_ramp("0").is_err());
+        assert!(parse_decay_ramp("150").is_err());
+    }
+}
@@ -0,0 +1,588 @@
+//! `polymarket-copy-rust init`: the first-run wizard. Writes `bot.toml` (settings) and,
+//! optionally, a `.env` holding only the private key setting.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::config::{
+    calculate_order_size, config_file_path, is_valid_ethereum_address, parse_config_str,
+    parse_user_addresses, AdaptiveTail, CopyStrategy, CopyStrategyConfig, EnvConfig,
+    SizingInputs,
+};
+use crate::ctf_approval;
+use crate::usdc_approval;
+use crate::utils::theme::colors;
+use crate::utils::data_api::DEFAULT_DATA_API_URL;
+use crate::utils::{get_usdc_balance, perform_health_check, DataApiClient, Logger};
+
+const ENV_PATH: &str = ".env";
+const DEFAULT_RPC_URL: &str = "https://polygon-rpc.com";
+const USDC_CONTRACT_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+
+const USAGE: &str = "Usage: polymarket-copy-rust init [--defaults --trader 0x... [--trader 0x...] [--proxy-wallet 0x...] [--key-file PATH] [--rpc-url URL]] [--force]
+
+Without --defaults the wizard asks for every value interactively.
+With --defaults it writes bot.toml non-interactively using suggested risk limits;
+the private key comes from --key-file (written to .env as PRIVATE_KEY_FILE) or the
+PRIVATE_KEY environment variable (left there, nothing written).";
+
+#[derive(Default)]
+struct Args {
+    defaults: bool,
+    force: bool,
+    traders: Vec<String>,
+    proxy_wallet: Option<String>,
+    key_file: Option<String>,
+    rpc_url: Option<String>,
+}
+
+fn parse_args(raw: &[String]) -> Result<Args> {
+    let mut args = Args::default();
+    let mut it = raw.iter().cloned();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| {
+            it.next()
+                .with_context(|| format!("{} expects a value\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--defaults" => args.defaults = true,
+            "--force" => args.force = true,
+            "--trader" => args.traders.push(value("--trader")?),
+            "--proxy-wallet" => args.proxy_wallet = Some(value("--proxy-wallet")?),
+            "--key-file" => args.key_file = Some(value("--key-file")?),
+            "--rpc-url" => args.rpc_url = Some(value("--rpc-url")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => anyhow::bail!("Unknown argument: {}\n\n{}", other, USAGE),
+        }
+    }
+    Ok(args)
+}
+
+/// Answers collected by the wizard; rendered into `bot.toml` and the `.env` key line.
+struct Answers {
+    traders: Vec<String>,
+    proxy_wallet: String,
+    key: KeySource,
+    write_env: bool,
+    rpc_url: String,
+    strategy: CopyStrategyConfig,
+    max_open_positions: Option<usize>,
+}
+
+enum KeySource {
+    Inline(String),
+    File(String),
+    /// `PRIVATE_KEY` is already in the environment; nothing to write.
+    Environment,
+}
+
+fn prompt(label: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(d) => print!("{}{}{} [{}]: ", colors::ACCENT, label, colors::RESET, d),
+        None => print!("{}{}{}: ", colors::ACCENT, label, colors::RESET),
+    }
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line)? == 0 {
+        anyhow::bail!("stdin closed; use --defaults for non-interactive setup");
+    }
+    let line = line.trim();
+    Ok(match (line.is_empty(), default) {
+        (true, Some(d)) => d.to_string(),
+        _ => line.to_string(),
+    })
+}
+
+fn prompt_yes_no(label: &str, default: bool) -> Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        let answer = prompt(&format!("{} ({})", label, hint), Some(""))?;
+        match answer.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+fn prompt_number(label: &str, default: f64) -> Result<f64> {
+    loop {
+        let answer = prompt(label, Some(&default.to_string()))?;
+        match answer.parse::<f64>() {
+            Ok(v) if v >= 0.0 => return Ok(v),
+            _ => println!("Please enter a non-negative number."),
+        }
+    }
+}
+
+fn prompt_address(label: &str, default: Option<&str>) -> Result<String> {
+    loop {
+        let answer = prompt(label, default)?;
+        if is_valid_ethereum_address(&answer) {
+            return Ok(answer);
+        }
+        println!("{}Not a valid 0x address (40 hex characters).{}", colors::WARN, colors::RESET);
+    }
+}
+
+/// Whether the address has any Polymarket activity; a typo'd or fresh wallet has none.
+async fn has_polymarket_activity(http: &reqwest::Client, address: &str) -> bool {
+    DataApiClient::new(http.clone(), DEFAULT_DATA_API_URL, 10_000, 2)
+        .has_activity(address)
+        .await
+        .unwrap_or(false)
+}
+
+fn default_strategy(strategy: CopyStrategy, copy_size: f64) -> CopyStrategyConfig {
+    CopyStrategyConfig {
+        strategy,
+        copy_size,
+        max_order_size_usd: 100.0,
+        min_order_size_usd: 1.0,
+        max_position_size_usd: Some(500.0),
+        max_daily_volume_usd: None,
+        adaptive_min_percent: (strategy == CopyStrategy::Adaptive).then_some(5.0),
+        adaptive_max_percent: (strategy == CopyStrategy::Adaptive).then_some(20.0),
+        adaptive_threshold: (strategy == CopyStrategy::Adaptive).then_some(500.0),
+        adaptive_tail: AdaptiveTail::Plateau,
+        tiered_multipliers: None,
+        trade_multiplier: None,
+    }
+}
+
+fn preview_strategy(config: &CopyStrategyConfig) {
+    println!();
+    println!("  Preview (ignoring your balance):");
+    for trader_usd in [10.0, 100.0, 1000.0] {
+        let calc = calculate_order_size(config, &SizingInputs::default(), trader_usd, f64::MAX, 0.0);
+        println!(
+            "    Trader buys {:>9} → you buy {:>9}   ({})",
+            Logger::money(trader_usd),
+            Logger::money(calc.final_amount),
+            calc.reasoning
+        );
+    }
+    println!();
+}
+
+async fn ask_traders(http: &reqwest::Client) -> Result<Vec<String>> {
+    println!();
+    Logger::header("TRADERS TO COPY");
+    println!("  Paste one or more trader wallet addresses, comma-separated.");
+    loop {
+        let raw = prompt("Trader addresses", None)?;
+        let traders = match parse_user_addresses(&raw) {
+            Ok(t) if !t.is_empty() => t,
+            Ok(_) => {
+                println!("Enter at least one address.");
+                continue;
+            }
+            Err(e) => {
+                println!("{}{}{}", colors::WARN, e, colors::RESET);
+                continue;
+            }
+        };
+        let mut all_active = true;
+        for trader in &traders {
+            if has_polymarket_activity(http, trader).await {
+                Logger::health_line(&Logger::format_address(trader), "ok", "has Polymarket activity");
+            } else {
+                all_active = false;
+                Logger::health_line(&Logger::format_address(trader), "warning", "no Polymarket activity found");
+            }
+        }
+        if all_active || prompt_yes_no("Some addresses look inactive. Use them anyway?", false)? {
+            return Ok(traders);
+        }
+    }
+}
+
+fn ask_key() -> Result<KeySource> {
+    println!();
+    Logger::header("PRIVATE KEY");
+    println!("  The key never goes in bot.toml. It can be stored in .env (PRIVATE_KEY) or");
+    println!("  read from a separate file (PRIVATE_KEY_FILE) so .env holds no secret either.");
+    loop {
+        let choice = prompt("Store key in [env] or [file]", Some("file"))?;
+        match choice.to_lowercase().as_str() {
+            "file" => {
+                let path = prompt("Path to key file", None)?;
+                if Path::new(&path).is_file() {
+                    return Ok(KeySource::File(path));
+                }
+                println!("{}File not found: {}{}", colors::WARN, path, colors::RESET);
+            }
+            "env" => {
+                let key = prompt("Private key (64 hex chars)", None)?;
+                return Ok(KeySource::Inline(key));
+            }
+            _ => println!("Please answer env or file."),
+        }
+    }
+}
+
+fn ask_strategy() -> Result<CopyStrategyConfig> {
+    println!();
+    Logger::header("COPY STRATEGY");
+    println!("  PERCENTAGE  buy a fixed share of every trade (e.g. 10% of a $100 buy = $10)");
+    println!("  FIXED       buy the same dollar amount no matter how big the trade is");
+    println!("  ADAPTIVE    a percentage that shrinks for large trades and grows for small ones");
+    loop {
+        let choice = prompt("Strategy", Some("PERCENTAGE"))?;
+        let mut config = match choice.to_uppercase().as_str() {
+            "PERCENTAGE" => default_strategy(
+                CopyStrategy::Percentage,
+                prompt_number("Percent of each trade to copy", 10.0)?,
+            ),
+            "FIXED" => default_strategy(
+                CopyStrategy::Fixed,
+                prompt_number("Dollars per copied trade", 5.0)?,
+            ),
+            "ADAPTIVE" => {
+                let mut c = default_strategy(
+                    CopyStrategy::Adaptive,
+                    prompt_number("Base percent of each trade", 10.0)?,
+                );
+                c.adaptive_min_percent = Some(prompt_number("Percent for very large trades", 5.0)?);
+                c.adaptive_max_percent = Some(prompt_number("Percent for very small trades", 20.0)?);
+                c.adaptive_threshold = Some(prompt_number("Trade size (USD) considered large", 500.0)?);
+                c
+            }
+            _ => {
+                println!("Please choose PERCENTAGE, FIXED or ADAPTIVE.");
+                continue;
+            }
+        };
+
+        println!();
+        Logger::header("RISK LIMITS");
+        config.max_order_size_usd = prompt_number("Largest single order (USD)", 100.0)?;
+        config.min_order_size_usd = prompt_number("Smallest order worth placing (USD)", 1.0)?;
+        let max_position = prompt_number("Most to hold in one market (USD, 0 = no cap)", 500.0)?;
+        config.max_position_size_usd = (max_position > 0.0).then_some(max_position);
+
+        preview_strategy(&config);
+        if prompt_yes_no("Keep these settings?", true)? {
+            return Ok(config);
+        }
+    }
+}
+
+async fn interactive(http: &reqwest::Client) -> Result<Answers> {
+    let traders = ask_traders(http).await?;
+
+    println!();
+    Logger::header("YOUR WALLET");
+    let proxy_wallet = prompt_address("Your Polymarket proxy wallet address", None)?;
+    let key = ask_key()?;
+    let write_env = prompt_yes_no(
+        &format!("Write the key setting to {}? (no = you export it yourself)", ENV_PATH),
+        true,
+    )?;
+    let rpc_url = prompt("Polygon RPC URL", Some(DEFAULT_RPC_URL))?;
+
+    let strategy = ask_strategy()?;
+    let max_open = prompt_number("Max markets open at once (0 = no limit)", 20.0)? as usize;
+
+    Ok(Answers {
+        traders,
+        proxy_wallet,
+        key,
+        write_env,
+        rpc_url,
+        strategy,
+        max_open_positions: (max_open > 0).then_some(max_open),
+    })
+}
+
+fn from_defaults(args: Args) -> Result<Answers> {
+    if args.traders.is_empty() {
+        anyhow::bail!("--defaults requires at least one --trader\n\n{}", USAGE);
+    }
+    let traders = parse_user_addresses(&args.traders.join(","))?;
+    let proxy_wallet = args
+        .proxy_wallet
+        .or_else(|| std::env::var("PROXY_WALLET").ok())
+        .context("--defaults requires --proxy-wallet (or PROXY_WALLET in the environment)")?;
+    if !is_valid_ethereum_address(&proxy_wallet) {
+        anyhow::bail!("Invalid proxy wallet: {}", proxy_wallet);
+    }
+    let key = match args.key_file {
+        Some(path) => KeySource::File(path),
+        None if std::env::var("PRIVATE_KEY").is_ok_and(|k| !k.trim().is_empty()) => {
+            KeySource::Environment
+        }
+        None => {
+            anyhow::bail!("--defaults requires --key-file (or PRIVATE_KEY in the environment)")
+        }
+    };
+    Ok(Answers {
+        traders,
+        proxy_wallet,
+        write_env: matches!(key, KeySource::File(_)),
+        key,
+        rpc_url: args.rpc_url.unwrap_or_else(|| DEFAULT_RPC_URL.to_string()),
+        strategy: default_strategy(CopyStrategy::Percentage, 10.0),
+        max_open_positions: Some(20),
+    })
+}
+
+fn strategy_name(strategy: CopyStrategy) -> &'static str {
+    match strategy {
+        CopyStrategy::Percentage => "PERCENTAGE",
+        CopyStrategy::Fixed => "FIXED",
+        CopyStrategy::Adaptive => "ADAPTIVE",
+        CopyStrategy::PortfolioShare => "PORTFOLIO_SHARE",
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+fn render_toml(answers: &Answers) -> String {
+    let s = &answers.strategy;
+    let mut out = String::new();
+    out.push_str("# Generated by `polymarket-copy-rust init`.\n");
+    out.push_str("# Keys are the environment variables in lower case (see README); anything set\n");
+    out.push_str("# in the environment or .env overrides this file. The key never goes here.\n\n");
+    out.push_str("# Traders to copy\n");
+    let traders: Vec<String> = answers.traders.iter().map(|t| toml_string(t)).collect();
+    out.push_str(&format!("user_addresses = [{}]\n\n", traders.join(", ")));
+    out.push_str("# Your wallet (must match the private key)\n");
+    out.push_str(&format!("proxy_wallet = {}\n\n", toml_string(&answers.proxy_wallet)));
+    out.push_str("# Polymarket API\n");
+    out.push_str("clob_http_url = \"https://clob.polymarket.com/\"\n");
+    out.push_str("clob_ws_url = \"wss://ws-subscriptions-clob.polymarket.com/ws\"\n\n");
+    out.push_str("# Polygon RPC and USDC\n");
+    out.push_str(&format!("rpc_url = {}\n", toml_string(&answers.rpc_url)));
+    out.push_str(&format!(
+        "usdc_contract_address = {}\n\n",
+        toml_string(USDC_CONTRACT_ADDRESS)
+    ));
+    out.push_str("# Copy strategy: PERCENTAGE, FIXED, or ADAPTIVE\n");
+    out.push_str(&format!("copy_strategy = {}\n", toml_string(strategy_name(s.strategy))));
+    out.push_str(&format!("copy_size = {:?}\n", s.copy_size));
+    if s.strategy == CopyStrategy::Adaptive {
+        if let Some(v) = s.adaptive_min_percent {
+            out.push_str(&format!("adaptive_min_percent = {:?}\n", v));
+        }
+        if let Some(v) = s.adaptive_max_percent {
+            out.push_str(&format!("adaptive_max_percent = {:?}\n", v));
+        }
+        if let Some(v) = s.adaptive_threshold {
+            out.push_str(&format!("adaptive_threshold_usd = {:?}\n", v));
+        }
+    }
+    out.push_str("\n# Risk limits\n");
+    out.push_str(&format!("max_order_size_usd = {:?}\n", s.max_order_size_usd));
+    out.push_str(&format!("min_order_size_usd = {:?}\n", s.min_order_size_usd));
+    if let Some(v) = s.max_position_size_usd {
+        out.push_str(&format!("max_position_size_usd = {:?}\n", v));
+    }
+    if let Some(v) = answers.max_open_positions {
+        out.push_str(&format!("max_open_positions = {}\n", v));
+    }
+    out
+}
+
+/// The `.env` line for the key, if the wizard writes one.
+fn key_setting(key: &KeySource) -> Option<(&'static str, String)> {
+    match key {
+        KeySource::Inline(key) => Some(("PRIVATE_KEY", key.trim().to_string())),
+        KeySource::File(path) => Some(("PRIVATE_KEY_FILE", path.clone())),
+        KeySource::Environment => None,
+    }
+}
+
+fn render_env(answers: &Answers) -> Option<String> {
+    let (name, value) = key_setting(&answers.key)?;
+    Some(format!(
+        "# Generated by `polymarket-copy-rust init`; settings live in bot.toml\n{}={}\n",
+        name, value
+    ))
+}
+
+/// Loads exactly what would be written through the same parser the bot uses, so a file
+/// that doesn't load is never written.
+fn check_loads(toml: &str, answers: &Answers) -> Result<EnvConfig> {
+    let mut vars: HashMap<String, String> =
+        parse_config_str(toml, "generated bot.toml")?.into_iter().collect();
+    match key_setting(&answers.key) {
+        Some((name, value)) => {
+            vars.insert(name.to_string(), value);
+        }
+        None => {
+            for name in ["PRIVATE_KEY", "PRIVATE_KEY_FILE"] {
+                if let Ok(value) = std::env::var(name) {
+                    vars.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+    EnvConfig::from_vars(&vars)
+}
+
+fn write_private(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Whether `path` may be (over)written: always with `--force`, never in `--defaults` mode,
+/// otherwise the user is asked.
+fn may_overwrite(path: &Path, defaults: bool, force: bool) -> Result<bool> {
+    if !path.exists() || force {
+        return Ok(true);
+    }
+    if defaults {
+        anyhow::bail!("{} already exists; pass --force to overwrite it", path.display());
+    }
+    prompt_yes_no(&format!("{} already exists. Overwrite it?", path.display()), false)
+}
+
+/// Entry point for `polymarket-copy-rust init [args]`.
+pub async fn run(raw_args: &[String]) -> Result<()> {
+    let args = parse_args(raw_args)?;
+
+    println!();
+    println!(
+        "{}━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━",
+        colors::ACCENT
+    );
+    println!("     POLYMARKET BOT — INIT");
+    println!(
+        "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━{}",
+        colors::RESET
+    );
+    println!();
+
+    let (defaults, force) = (args.defaults, args.force);
+    let toml_path = config_file_path();
+    if !may_overwrite(&toml_path, defaults, force)? {
+        println!("Leaving {} untouched.", toml_path.display());
+        return Ok(());
+    }
+
+    let http = reqwest::Client::new();
+    let answers = if defaults {
+        from_defaults(args)?
+    } else {
+        interactive(&http).await?
+    };
+
+    let toml = render_toml(&answers);
+    let config = match check_loads(&toml, &answers) {
+        Ok(c) => c,
+        Err(e) => {
+            Logger::error(&format!("These settings would not load: {:#}", e));
+            println!("Nothing was written. Run init again with different answers.");
+            std::process::exit(1);
+        }
+    };
+    write_private(&toml_path, &toml)?;
+    Logger::success(&format!("Wrote {}", toml_path.display()));
+    match render_env(&answers) {
+        Some(env) if answers.write_env => {
+            // An old all-in-one .env would override every bot.toml setting.
+            if may_overwrite(Path::new(ENV_PATH), defaults, force)? {
+                write_private(Path::new(ENV_PATH), &env)?;
+                Logger::success(&format!("Wrote {} (key setting only)", ENV_PATH));
+            } else {
+                Logger::warning(&format!("{} left as is; make sure it sets the key", ENV_PATH));
+            }
+        }
+        Some(_) => Logger::info(
+            "Set PRIVATE_KEY or PRIVATE_KEY_FILE in the environment before running",
+        ),
+        None => Logger::info("Using PRIVATE_KEY from the environment"),
+    }
+
+    let balance = get_usdc_balance(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+    )
+    .await;
+    let polymarket_ok = has_polymarket_activity(&http, &config.user_addresses[0]).await
+        || DataApiClient::from_config(&config, &http).ping().await.is_ok();
+    let ctf_missing = ctf_approval::missing_approvals(&config.rpc_url, &config.proxy_wallet).await;
+    let usdc_short = usdc_approval::short_allowances(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+        config.min_usdc_allowance_usd,
+    )
+    .await;
+    let health = perform_health_check(
+        &config,
+        balance,
+        polymarket_ok,
+        ctf_missing,
+        usdc_short,
+    )
+    .await;
+
+    Logger::separator();
+    Logger::header("SYSTEM CHECK");
+    Logger::health_line("RPC", &health.checks.rpc.status, &health.checks.rpc.message);
+    Logger::health_line(
+        "Balance",
+        &health.checks.balance.status,
+        &health.checks.balance.message,
+    );
+    Logger::health_line(
+        "Polymarket API",
+        &health.checks.polymarket_api.status,
+        &health.checks.polymarket_api.message,
+    );
+    Logger::health_line(
+        "CTF approval",
+        &health.checks.ctf_approval.status,
+        &health.checks.ctf_approval.message,
+    );
+    Logger::health_line(
+        "USDC allowance",
+        &health.checks.usdc_allowance.status,
+        &health.checks.usdc_allowance.message,
+    );
+    Logger::health_line(
+        "CLOB auth",
+        &health.checks.clob_auth.status,
+        &health.checks.clob_auth.message,
+    );
+    Logger::health_line(
+        "RTDS WebSocket",
+        &health.checks.websocket.status,
+        &health.checks.websocket.message,
+    );
+    Logger::health_line("Gas (POL)", &health.checks.gas.status, &health.checks.gas.message);
+    Logger::separator();
+
+    println!();
+    if health.healthy {
+        println!("{} All set. Next steps:{}", colors::SUCCESS, colors::RESET);
+    } else {
+        println!(
+            "{} Config written, but some checks failed. Fix them, then:{}",
+            colors::WARN,
+            colors::RESET
+        );
+    }
+    println!("   make health-check   # re-run the checks above");
+    println!("   make run            # start copying");
+    println!("   edit {} to change any setting later", toml_path.display());
+    println!();
+
+    Ok(())
+}
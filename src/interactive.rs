@@ -0,0 +1,376 @@
+//! Line commands typed into the terminal the bot runs in (`INTERACTIVE=true`, stdin a TTY):
+//! list, close or trim positions through the normal sell pipeline, and pause or resume
+//! trading. Sells above `INTERACTIVE_CONFIRM_USD` ask for a `y` first.
+
+use anyhow::Result;
+use std::future::Future;
+use std::io::{BufRead, IsTerminal};
+use tokio::sync::mpsc;
+
+use crate::trading_state::{self, PauseLevel};
+use crate::utils::Logger;
+
+/// Pause source for commands typed here; `resume` only lifts this one.
+pub const PAUSE_SOURCE: &str = "interactive";
+
+const HELP: &str = "Commands: positions | close <n> | trim <n> <percent>% | pause [buys] | \
+                    resume | status | help";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Positions,
+    /// Sell all of listed position `n` (1-based).
+    Close(usize),
+    /// Sell this fraction of listed position `n`.
+    Trim(usize, f64),
+    Pause(PauseLevel),
+    Resume,
+    Status,
+    Help,
+}
+
+/// Parses one typed line. Errors say what was expected.
+pub fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((name, args)) = words.split_first() else {
+        return Err(HELP.to_string());
+    };
+    let number = |arg: Option<&&str>| -> Result<usize, String> {
+        let arg = arg.ok_or("missing position number (see `positions`)")?;
+        match arg.trim_start_matches('#').parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("`{}` is not a position number", arg)),
+        }
+    };
+    let no_more = |expected: usize| -> Result<(), String> {
+        match args.get(expected) {
+            Some(extra) => Err(format!("unexpected `{}`", extra)),
+            None => Ok(()),
+        }
+    };
+    let command = match name.to_lowercase().as_str() {
+        "positions" | "list" | "ls" => Command::Positions,
+        "close" => {
+            let n = number(args.first())?;
+            no_more(1)?;
+            Command::Close(n)
+        }
+        "trim" => {
+            let n = number(args.first())?;
+            let raw = args.get(1).ok_or("missing percentage, e.g. `trim 2 50%`")?;
+            let percent: f64 = raw
+                .trim_end_matches('%')
+                .parse()
+                .map_err(|_| format!("`{}` is not a percentage", raw))?;
+            if !(percent > 0.0 && percent <= 100.0) {
+                return Err(format!("percentage {} outside (0, 100]", percent));
+            }
+            no_more(2)?;
+            if percent >= 100.0 {
+                Command::Close(n)
+            } else {
+                Command::Trim(n, percent / 100.0)
+            }
+        }
+        "pause" => match args.first().map(|a| a.to_lowercase()).as_deref() {
+            None | Some("all") => Command::Pause(PauseLevel::All),
+            Some("buys") | Some("buy") => Command::Pause(PauseLevel::Buys),
+            Some(other) => return Err(format!("`pause {}`: use `pause` or `pause buys`", other)),
+        },
+        "resume" => Command::Resume,
+        "status" => Command::Status,
+        "help" | "?" => Command::Help,
+        other => return Err(format!("unknown command `{}`. {}", other, HELP)),
+    };
+    Ok(command)
+}
+
+/// One of my positions as last listed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldPosition {
+    pub asset: String,
+    pub condition_id: String,
+    pub title: String,
+    pub shares: f64,
+    pub value_usd: f64,
+}
+
+/// What the commands act on, so tests can stand in for the executor.
+pub trait CommandHandler: Sync {
+    /// My open positions, in the order they are numbered.
+    fn positions(&self) -> impl Future<Output = Result<Vec<HeldPosition>>> + Send;
+    /// Sells `fraction` of `position` through the copy pipeline; returns the outcome.
+    fn sell(
+        &self,
+        position: &HeldPosition,
+        fraction: f64,
+    ) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// A sell waiting for a `y`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSell {
+    pub position: HeldPosition,
+    pub fraction: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Text(String),
+    Confirm(String, PendingSell),
+}
+
+/// Numbering from the last `positions` and the confirmation threshold.
+pub struct Session {
+    listed: Vec<HeldPosition>,
+    confirm_above_usd: f64,
+}
+
+impl Session {
+    pub fn new(confirm_above_usd: f64) -> Self {
+        Self {
+            listed: Vec::new(),
+            confirm_above_usd,
+        }
+    }
+
+    pub async fn handle<H: CommandHandler>(&mut self, handler: &H, command: Command) -> Reply {
+        let text = match command {
+            Command::Positions => match handler.positions().await {
+                Ok(positions) if positions.is_empty() => "No open positions".to_string(),
+                Ok(positions) => {
+                    self.listed = positions;
+                    self.listed
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| {
+                            format!(
+                                "{:>2}. {} - {:.2} shares, ${:.2}",
+                                i + 1,
+                                p.title,
+                                p.shares,
+                                p.value_usd
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+                Err(e) => format!("Could not list positions: {}", e),
+            },
+            Command::Close(n) => return self.sell(handler, n, 1.0).await,
+            Command::Trim(n, fraction) => return self.sell(handler, n, fraction).await,
+            Command::Pause(level) => {
+                trading_state::pause(PAUSE_SOURCE, level, "paused from the terminal");
+                format!("Trading state: {}", trading_state::current())
+            }
+            Command::Resume => {
+                trading_state::resume(PAUSE_SOURCE);
+                format!("Trading state: {}", trading_state::current())
+            }
+            Command::Status => {
+                let mut lines = vec![format!("Trading state: {}", trading_state::current())];
+                for (source, level, reason) in trading_state::holds() {
+                    lines.push(format!("  {:?} paused by {}: {}", level, source, reason));
+                }
+                lines.join("\n")
+            }
+            Command::Help => HELP.to_string(),
+        };
+        Reply::Text(text)
+    }
+
+    async fn sell<H: CommandHandler>(&mut self, handler: &H, n: usize, fraction: f64) -> Reply {
+        if self.listed.is_empty() {
+            return Reply::Text("Run `positions` first to number your positions".to_string());
+        }
+        let Some(position) = self.listed.get(n - 1).cloned() else {
+            return Reply::Text(format!(
+                "No position {} (the last list had {})",
+                n,
+                self.listed.len()
+            ));
+        };
+        let usd = position.value_usd * fraction;
+        let sell = PendingSell { position, fraction };
+        if usd > self.confirm_above_usd {
+            let prompt = format!(
+                "Sell {:.0}% of {} (~${:.2})? [y/N]",
+                fraction * 100.0,
+                sell.position.title,
+                usd
+            );
+            return Reply::Confirm(prompt, sell);
+        }
+        Reply::Text(self.execute(handler, sell).await)
+    }
+
+    /// Runs a sell; the list is cleared so numbers can't point at a changed position.
+    pub async fn execute<H: CommandHandler>(&mut self, handler: &H, sell: PendingSell) -> String {
+        self.listed.clear();
+        match handler.sell(&sell.position, sell.fraction).await {
+            Ok(outcome) => outcome,
+            Err(e) => format!("Sell failed: {}", e),
+        }
+    }
+}
+
+/// Whether an answer to a confirmation prompt means yes.
+pub fn confirmed(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Whether commands can be read, i.e. stdin is a terminal; warns when it isn't.
+pub fn available() -> bool {
+    let terminal = std::io::stdin().is_terminal();
+    if !terminal {
+        Logger::warning("INTERACTIVE=true but stdin is not a terminal - commands are off");
+    }
+    terminal
+}
+
+/// Reads commands from stdin; check `available` first. Once stdin closes it stays pending
+/// rather than returning, which the supervisor would count as the task failing.
+pub async fn run<H: CommandHandler>(handler: H, confirm_above_usd: f64) {
+    let (tx, mut lines) = mpsc::unbounded_channel::<String>();
+    // Blocking reads stay off the runtime; the thread ends when stdin closes.
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(|l| l.ok()) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Logger::info(&format!("Terminal commands on. {}", HELP));
+    let mut session = Session::new(confirm_above_usd);
+    while let Some(line) = lines.recv().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match parse(&line) {
+            Ok(command) => session.handle(&handler, command).await,
+            Err(e) => Reply::Text(e),
+        };
+        match reply {
+            Reply::Text(text) => println!("{}", text),
+            Reply::Confirm(prompt, sell) => {
+                println!("{}", prompt);
+                let answer = lines.recv().await.unwrap_or_default();
+                if confirmed(&answer) {
+                    println!("{}", session.execute(&handler, sell).await);
+                } else {
+                    println!("Cancelled");
+                }
+            }
+        }
+    }
+    Logger::info("Terminal closed - commands are off");
+    std::future::pending::<()>().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn parses_commands_with_helpful_errors() {
+        assert_eq!(parse("positions"), Ok(Command::Positions));
+        assert_eq!(parse("close 2"), Ok(Command::Close(2)));
+        assert_eq!(parse("  TRIM #3 25% "), Ok(Command::Trim(3, 0.25)));
+        assert_eq!(parse("trim 1 100%"), Ok(Command::Close(1)));
+        assert_eq!(parse("pause"), Ok(Command::Pause(PauseLevel::All)));
+        assert_eq!(parse("pause buys"), Ok(Command::Pause(PauseLevel::Buys)));
+        assert_eq!(parse("resume"), Ok(Command::Resume));
+
+        assert!(parse("close")
+            .unwrap_err()
+            .contains("missing position number"));
+        assert!(parse("close 0")
+            .unwrap_err()
+            .contains("not a position number"));
+        assert!(parse("close 1 2").unwrap_err().contains("unexpected `2`"));
+        assert!(parse("trim 1").unwrap_err().contains("missing percentage"));
+        assert!(parse("trim 1 half")
+            .unwrap_err()
+            .contains("not a percentage"));
+        assert!(parse("trim 1 150%")
+            .unwrap_err()
+            .contains("outside (0, 100]"));
+        assert!(parse("pause sells").unwrap_err().contains("pause buys"));
+        assert!(parse("sell 1")
+            .unwrap_err()
+            .contains("unknown command `sell`"));
+        assert!(confirmed(" Y ") && !confirmed("") && !confirmed("n"));
+    }
+
+    fn held(title: &str, value_usd: f64) -> HeldPosition {
+        HeldPosition {
+            asset: format!("asset-{}", title),
+            condition_id: format!("cid-{}", title),
+            title: title.to_string(),
+            shares: value_usd * 2.0,
+            value_usd,
+        }
+    }
+
+    /// Executor stand-in recording the sells it was asked for.
+    struct MockHandler {
+        positions: Vec<HeldPosition>,
+        sells: Mutex<Vec<(String, f64)>>,
+    }
+
+    impl CommandHandler for MockHandler {
+        async fn positions(&self) -> Result<Vec<HeldPosition>> {
+            Ok(self.positions.clone())
+        }
+
+        async fn sell(&self, position: &HeldPosition, fraction: f64) -> Result<String> {
+            self.sells
+                .lock()
+                .unwrap()
+                .push((position.title.clone(), fraction));
+            Ok(format!("sold {}", position.title))
+        }
+    }
+
+    #[tokio::test]
+    async fn sells_dispatch_by_listed_number_and_confirm_large_ones() {
+        let handler = MockHandler {
+            positions: vec![held("small", 20.0), held("large", 400.0)],
+            sells: Mutex::new(Vec::new()),
+        };
+        let mut session = Session::new(100.0);
+
+        let reply = session.handle(&handler, Command::Close(1)).await;
+        assert_eq!(
+            reply,
+            Reply::Text("Run `positions` first to number your positions".to_string())
+        );
+
+        let Reply::Text(list) = session.handle(&handler, Command::Positions).await else {
+            panic!("positions needs no confirmation");
+        };
+        assert!(list.starts_with(" 1. small") && list.contains(" 2. large"));
+        let reply = session.handle(&handler, Command::Close(3)).await;
+        assert_eq!(
+            reply,
+            Reply::Text("No position 3 (the last list had 2)".to_string())
+        );
+
+        let reply = session.handle(&handler, Command::Close(1)).await;
+        assert_eq!(reply, Reply::Text("sold small".to_string()));
+        // The sell cleared the numbering.
+        session.handle(&handler, Command::Positions).await;
+
+        let Reply::Confirm(prompt, pending) = session.handle(&handler, Command::Trim(2, 0.5)).await
+        else {
+            panic!("$200 is above the threshold");
+        };
+        assert!(prompt.contains("50% of large (~$200.00)"));
+        assert_eq!(session.execute(&handler, pending).await, "sold large");
+        assert_eq!(
+            *handler.sells.lock().unwrap(),
+            vec![("small".to_string(), 1.0), ("large".to_string(), 0.5)]
+        );
+    }
+}
@@ -0,0 +1,594 @@
+//! `polymarket-copy-rust journal backfill-marks`: fills in a mark price for journal rows that
+//! have none (written without `JOURNAL_MARKET_CONTEXT`, or while the book lookup failed) from
+//! the CLOB prices-history endpoint. The journal is append-only JSONL, so marks go to a
+//! sidecar file next to it, keyed by line number. A watermark file records how far the job
+//! got, so an interrupted or rate-limited run picks up where it stopped.
+//!
+//! `journal backfill-remote` pushes every row of the local journal to `JOURNAL_REMOTE_URL`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{self, EnvConfig};
+use crate::utils::{
+    parse_journal_row, read_journal, JournalEntry, Logger, RemoteJournal, REMOTE_BATCH_SIZE,
+};
+
+const USAGE: &str =
+    "Usage: polymarket-copy-rust journal backfill-marks [--max-gap-mins N] [--delay-ms N] [--retry]
+       polymarket-copy-rust journal backfill-remote
+
+Looks up the price of each journal row that has no mark in CLOB price history and writes it
+to <TRADE_LOG_PATH>.marks.jsonl. Progress is saved after every chunk; rerun to resume.
+--max-gap-mins  farthest a price point may be from the row (default 30)
+--delay-ms      pause between history requests (default 250)
+--retry         start over from the first row, retrying rows that found no price before
+
+backfill-remote sends every row of the journal to JOURNAL_REMOTE_URL, with its original
+timestamp so the endpoint can de-duplicate re-sends.";
+
+/// Rows per chunk; the sidecar and watermark are written after each.
+const CHUNK_ROWS: usize = 500;
+/// Longest span of one history request.
+const WINDOW_SECS: i64 = 6 * 3600;
+/// Attempts per request when the endpoint answers 429.
+const RATE_LIMIT_ATTEMPTS: u32 = 5;
+
+/// A mark found in price history for one journal row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkRow {
+    /// Line number in the journal, from 1.
+    pub line: usize,
+    pub timestamp: i64,
+    pub asset: String,
+    pub mark: f64,
+    /// Seconds between the row and the price point used.
+    pub gap_secs: i64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Progress {
+    /// First journal line not yet looked at.
+    next_line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    pub max_gap_secs: i64,
+    pub request_delay: Duration,
+    /// Ignore the watermark and look at every row again.
+    pub retry: bool,
+}
+
+impl Default for BackfillOptions {
+    fn default() -> Self {
+        Self {
+            max_gap_secs: 30 * 60,
+            request_delay: Duration::from_millis(250),
+            retry: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BackfillReport {
+    /// Rows looked at in this run.
+    pub scanned: usize,
+    /// Rows given a mark in this run.
+    pub backfilled: usize,
+    /// Rows, in this run or earlier ones, with no price point close enough.
+    pub unresolvable: usize,
+    /// Rows already carrying a mark, in the journal or the sidecar.
+    pub already_marked: usize,
+}
+
+/// One history request: rows of one asset within `WINDOW_SECS` of each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Window {
+    pub asset: String,
+    pub rows: Vec<(usize, i64)>,
+}
+
+impl Window {
+    fn span(&self, max_gap_secs: i64) -> (i64, i64) {
+        let first = self.rows.first().map(|r| r.1).unwrap_or_default();
+        let last = self.rows.last().map(|r| r.1).unwrap_or_default();
+        (first - max_gap_secs, last + max_gap_secs)
+    }
+}
+
+pub fn sidecar_path(journal: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.marks.jsonl", journal.display()))
+}
+
+fn progress_path(journal: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.marks-progress.json", journal.display()))
+}
+
+/// The mark recorded with the row itself: last trade price, else the book mid.
+pub fn row_mark(entry: &JournalEntry) -> Option<f64> {
+    let market = entry.market.as_ref()?;
+    market
+        .last_trade_price
+        .or(match (market.best_bid, market.best_ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            _ => None,
+        })
+}
+
+/// Marks from the sidecar by journal line; a later row for the same line wins.
+pub fn read_marks(journal: &Path) -> HashMap<usize, MarkRow> {
+    let Ok(file) = std::fs::File::open(sidecar_path(journal)) else {
+        return HashMap::new();
+    };
+    std::io::BufReader::new(file)
+        .lines()
+        .map_while(|l| l.ok())
+        .filter_map(|l| serde_json::from_str::<MarkRow>(&l).ok())
+        .map(|m| (m.line, m))
+        .collect()
+}
+
+/// Journal rows with their line numbers, skipping lines that don't parse.
+fn read_indexed(journal: &Path) -> Result<Vec<(usize, JournalEntry)>> {
+    let file = std::fs::File::open(journal)
+        .with_context(|| format!("opening journal {}", journal.display()))?;
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(|l| l.ok())
+        .enumerate()
+        .filter_map(|(i, l)| parse_journal_row(&l).ok().map(|e| (i + 1, e)))
+        .collect())
+}
+
+/// Rows a mark is wanted for: live ones naming an asset.
+fn is_live(entry: &JournalEntry) -> bool {
+    !entry.shadow && entry.asset.is_some()
+}
+
+/// Live rows that neither the journal nor the sidecar gives a mark.
+fn needs_mark(line: usize, entry: &JournalEntry, marks: &HashMap<usize, MarkRow>) -> bool {
+    is_live(entry) && row_mark(entry).is_none() && !marks.contains_key(&line)
+}
+
+/// Groups rows by asset into windows of at most `WINDOW_SECS`, so one request covers many rows.
+pub fn plan(rows: &[(usize, i64, String)]) -> Vec<Window> {
+    let mut by_asset: BTreeMap<&str, Vec<(usize, i64)>> = BTreeMap::new();
+    for (line, ts, asset) in rows {
+        by_asset.entry(asset).or_default().push((*line, *ts));
+    }
+    let mut windows = Vec::new();
+    for (asset, mut rows) in by_asset {
+        rows.sort_by_key(|r| r.1);
+        let mut current: Vec<(usize, i64)> = Vec::new();
+        for row in rows {
+            if current.first().is_some_and(|f| row.1 - f.1 > WINDOW_SECS) {
+                windows.push(Window {
+                    asset: asset.to_string(),
+                    rows: std::mem::take(&mut current),
+                });
+            }
+            current.push(row);
+        }
+        if !current.is_empty() {
+            windows.push(Window {
+                asset: asset.to_string(),
+                rows: current,
+            });
+        }
+    }
+    windows
+}
+
+/// `{"history":[{"t":..,"p":..}]}` → `(t, p)` sorted by time.
+pub fn parse_history(body: &serde_json::Value) -> Vec<(i64, f64)> {
+    let mut points: Vec<(i64, f64)> = body
+        .get("history")
+        .and_then(|h| h.as_array())
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|p| Some((p.get("t")?.as_i64()?, p.get("p")?.as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    points.sort_by_key(|p| p.0);
+    points
+}
+
+/// The price point nearest `ts`, with its distance, if it is within `max_gap_secs`.
+pub fn nearest_mark(history: &[(i64, f64)], ts: i64, max_gap_secs: i64) -> Option<(f64, i64)> {
+    history
+        .iter()
+        .map(|(t, p)| (*p, (t - ts).abs()))
+        .filter(|(_, gap)| *gap <= max_gap_secs)
+        .min_by_key(|(_, gap)| *gap)
+}
+
+/// Fetches one window's history, waiting out 429s (`Retry-After`, else doubling from 1s).
+async fn fetch_history(
+    http: &reqwest::Client,
+    clob_url: &str,
+    window: &Window,
+    max_gap_secs: i64,
+) -> Result<Vec<(i64, f64)>> {
+    let (start, end) = window.span(max_gap_secs);
+    let url = format!(
+        "{}/prices-history?market={}&startTs={}&endTs={}&fidelity=1",
+        clob_url, window.asset, start, end
+    );
+    let mut backoff = Duration::from_secs(1);
+    for attempt in 1..=RATE_LIMIT_ATTEMPTS {
+        let response = http.get(&url).send().await?;
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            if attempt == RATE_LIMIT_ATTEMPTS {
+                break;
+            }
+            let wait = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok()?.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(backoff);
+            tokio::time::sleep(wait).await;
+            backoff *= 2;
+            continue;
+        }
+        let body: serde_json::Value = response.error_for_status()?.json().await?;
+        return Ok(parse_history(&body));
+    }
+    anyhow::bail!(
+        "prices-history still rate limited after {} attempts",
+        RATE_LIMIT_ATTEMPTS
+    )
+}
+
+fn append_marks(journal: &Path, marks: &[MarkRow]) -> Result<()> {
+    if marks.is_empty() {
+        return Ok(());
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(sidecar_path(journal))?;
+    for mark in marks {
+        writeln!(file, "{}", serde_json::to_string(mark)?)?;
+    }
+    Ok(())
+}
+
+fn save_progress(journal: &Path, progress: &Progress) -> Result<()> {
+    std::fs::write(progress_path(journal), serde_json::to_string(progress)?)?;
+    Ok(())
+}
+
+/// Backfills marks for rows past the watermark. An error (e.g. rate limited for good) leaves
+/// the watermark at the last finished chunk.
+pub async fn backfill(
+    http: &reqwest::Client,
+    clob_url: &str,
+    journal: &Path,
+    opts: &BackfillOptions,
+) -> Result<BackfillReport> {
+    let rows = read_indexed(journal)?;
+    let mut marks = read_marks(journal);
+    let mut progress: Progress = match std::fs::read_to_string(progress_path(journal)) {
+        Ok(raw) if !opts.retry => serde_json::from_str(&raw).unwrap_or_default(),
+        _ => Progress::default(),
+    };
+    let mut report = BackfillReport::default();
+    let pending: Vec<&(usize, JournalEntry)> = rows
+        .iter()
+        .filter(|(line, _)| *line >= progress.next_line)
+        .collect();
+    let mut first_request = true;
+
+    for chunk in pending.chunks(CHUNK_ROWS) {
+        report.scanned += chunk.len();
+        report.already_marked += chunk
+            .iter()
+            .filter(|(line, e)| is_live(e) && !needs_mark(*line, e, &marks))
+            .count();
+        let wanted: Vec<(usize, i64, String)> = chunk
+            .iter()
+            .filter(|(line, e)| needs_mark(*line, e, &marks))
+            .filter_map(|(line, e)| Some((*line, e.timestamp, e.asset.clone()?)))
+            .collect();
+        let mut found = Vec::new();
+        for window in plan(&wanted) {
+            if !first_request {
+                tokio::time::sleep(opts.request_delay).await;
+            }
+            first_request = false;
+            let history = fetch_history(http, clob_url, &window, opts.max_gap_secs).await?;
+            for (line, ts) in &window.rows {
+                if let Some((mark, gap_secs)) = nearest_mark(&history, *ts, opts.max_gap_secs) {
+                    found.push(MarkRow {
+                        line: *line,
+                        timestamp: *ts,
+                        asset: window.asset.clone(),
+                        mark,
+                        gap_secs,
+                    });
+                }
+            }
+        }
+        append_marks(journal, &found)?;
+        report.backfilled += found.len();
+        marks.extend(found.into_iter().map(|m| (m.line, m)));
+        progress.next_line = chunk
+            .last()
+            .map(|(line, _)| line + 1)
+            .unwrap_or(progress.next_line);
+        save_progress(journal, &progress)?;
+    }
+
+    report.unresolvable = rows
+        .iter()
+        .filter(|(line, e)| *line < progress.next_line && needs_mark(*line, e, &marks))
+        .count();
+    Ok(report)
+}
+
+fn parse_args(args: &[String]) -> Result<BackfillOptions> {
+    let mut opts = BackfillOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| -> Result<i64> {
+            iter.next()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v >= 0)
+                .with_context(|| format!("{} needs a non-negative number\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--max-gap-mins" => opts.max_gap_secs = value(arg)? * 60,
+            "--delay-ms" => opts.request_delay = Duration::from_millis(value(arg)? as u64),
+            "--retry" => opts.retry = true,
+            _ => anyhow::bail!("unknown argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    Ok(opts)
+}
+
+/// Pushes every row of the local journal to `JOURNAL_REMOTE_URL`, in batches; a rejected
+/// batch stops the run, with the earlier ones delivered.
+async fn backfill_remote(config: &EnvConfig) -> Result<()> {
+    let remote = RemoteJournal::from_config(config)
+        .context("JOURNAL_REMOTE_URL is not set; nothing to backfill to")?;
+    let entries = read_journal(Path::new(&config.trade_log_path))?;
+    if entries.is_empty() {
+        Logger::info(&format!("No journal rows in {}", config.trade_log_path));
+        return Ok(());
+    }
+    Logger::info(&format!(
+        "Backfilling {} rows from {}",
+        entries.len(),
+        config.trade_log_path
+    ));
+    for (i, batch) in entries.chunks(REMOTE_BATCH_SIZE).enumerate() {
+        remote.push(batch).await.with_context(|| {
+            format!(
+                "Remote rejected batch {} (rows {}-{}); earlier batches were delivered",
+                i + 1,
+                i * REMOTE_BATCH_SIZE + 1,
+                i * REMOTE_BATCH_SIZE + batch.len()
+            )
+        })?;
+    }
+    Logger::success(&format!("Backfilled {} rows", entries.len()));
+    Ok(())
+}
+
+/// Entry point for `journal <subcommand>`.
+pub async fn run(args: &[String]) -> Result<()> {
+    let remote = match args.first().map(String::as_str) {
+        Some("backfill-marks") => false,
+        Some("backfill-remote") if args.len() == 1 => true,
+        _ => anyhow::bail!("{}", USAGE),
+    };
+    let opts = if remote {
+        BackfillOptions::default()
+    } else {
+        parse_args(&args[1..])?
+    };
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    let config = EnvConfig::parse()?;
+    if remote {
+        return backfill_remote(&config).await;
+    }
+    let http = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .build()?;
+    let journal = Path::new(&config.trade_log_path);
+    let report = backfill(&http, &config.clob_http_url, journal, &opts).await?;
+    println!(
+        "Scanned {} rows: {} backfilled, {} already marked, {} unresolvable (no price within {} min)",
+        report.scanned,
+        report.backfilled,
+        report.already_marked,
+        report.unresolvable,
+        opts.max_gap_secs / 60
+    );
+    println!("Marks: {}", sidecar_path(journal).display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::JOURNAL_SCHEMA_VERSION;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves prices-history: asset 111 has a point a minute over [1000, 1600] and
+    /// [10000, 10600], asset 222 has none. The first request is answered 429.
+    async fn mock_history() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    let len = stream.read(&mut buf).await.unwrap_or(0);
+                    let head = String::from_utf8_lossy(&buf[..len]).to_string();
+                    let response = if n == 0 {
+                        "HTTP/1.1 429 X\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    } else {
+                        let history: Vec<serde_json::Value> = if head.contains("market=111&") {
+                            (1000..=1600)
+                                .step_by(60)
+                                .chain((10000..=10600).step_by(60))
+                                .map(|t| json!({"t": t, "p": 0.5}))
+                                .collect()
+                        } else {
+                            Vec::new()
+                        };
+                        let body = json!({ "history": history }).to_string();
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                    };
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    fn row(ts: i64, asset: &str) -> serde_json::Value {
+        json!({
+            "schema_version": JOURNAL_SCHEMA_VERSION,
+            "timestamp": ts,
+            "status": "executed",
+            "trader": "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b",
+            "asset": asset,
+            "my_usd": 5.0,
+            "my_tokens": 10.0,
+        })
+    }
+
+    fn write_journal(path: &Path, rows: &[String]) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for r in rows {
+            writeln!(file, "{}", r).unwrap();
+        }
+    }
+
+    fn opts() -> BackfillOptions {
+        BackfillOptions {
+            max_gap_secs: 600,
+            request_delay: Duration::ZERO,
+            retry: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn unknown_subcommands_and_remote_flags_print_the_usage() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        for bad in [&["backfill-remote", "--retry"][..], &["backfill"], &[]] {
+            let err = run(&args(bad)).await.unwrap_err().to_string();
+            assert!(err.contains("journal backfill-remote"), "{:?}: {}", bad, err);
+        }
+    }
+
+    #[test]
+    fn nearest_mark_respects_the_gap() {
+        let history = parse_history(&json!({"history": [
+            {"t": 200, "p": 0.6}, {"t": 100, "p": 0.4}, {"t": "bad", "p": 0.1}
+        ]}));
+        assert_eq!(history, vec![(100, 0.4), (200, 0.6)]);
+        assert_eq!(nearest_mark(&history, 130, 60), Some((0.4, 30)));
+        assert_eq!(nearest_mark(&history, 180, 60), Some((0.6, 20)));
+        assert_eq!(nearest_mark(&history, 400, 60), None);
+        assert_eq!(nearest_mark(&[], 100, 60), None);
+    }
+
+    #[test]
+    fn plan_splits_assets_and_long_spans() {
+        let rows = vec![
+            (1, 0, "a".to_string()),
+            (2, 100, "b".to_string()),
+            (3, WINDOW_SECS, "a".to_string()),
+            (4, WINDOW_SECS + 1, "a".to_string()),
+        ];
+        let windows = plan(&rows);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].rows, vec![(1, 0), (3, WINDOW_SECS)]);
+        assert_eq!(windows[1].rows, vec![(4, WINDOW_SECS + 1)]);
+        assert_eq!(windows[2].asset, "b");
+    }
+
+    #[tokio::test]
+    async fn backfill_fills_gaps_reports_unresolvable_and_resumes() {
+        let (url, requests) = mock_history().await;
+        let dir = tempfile::tempdir().unwrap();
+        let journal = dir.path().join("trades.jsonl");
+        let mut marked = row(1200, "111");
+        marked["market"] = json!({
+            "best_bid": 0.3, "best_ask": 0.5, "spread": 0.2,
+            "bid_depth": 0.0, "ask_depth": 0.0,
+            "last_trade_price": null, "price_change_1h": null
+        });
+        let mut shadow = row(1200, "111");
+        shadow["shadow"] = json!(true);
+        write_journal(
+            &journal,
+            &[
+                row(1030, "111").to_string(),
+                row(5000, "111").to_string(),
+                marked.to_string(),
+                row(1000, "222").to_string(),
+                shadow.to_string(),
+                "not json".to_string(),
+            ],
+        );
+        let http = reqwest::Client::new();
+
+        let report = backfill(&http, &url, &journal, &opts()).await.unwrap();
+        assert_eq!(
+            report,
+            BackfillReport {
+                scanned: 5,
+                backfilled: 1,
+                unresolvable: 2,
+                already_marked: 1,
+            }
+        );
+        let marks = read_marks(&journal);
+        assert_eq!(marks.len(), 1);
+        assert_eq!(marks[&1].mark, 0.5);
+        assert_eq!(marks[&1].gap_secs, 30);
+        let served = requests.load(Ordering::SeqCst);
+
+        let report = backfill(&http, &url, &journal, &opts()).await.unwrap();
+        assert_eq!(report.scanned, 0);
+        assert_eq!(report.unresolvable, 2);
+        assert_eq!(requests.load(Ordering::SeqCst), served);
+
+        write_journal(&journal, &[row(10290, "111").to_string()]);
+        let report = backfill(&http, &url, &journal, &opts()).await.unwrap();
+        assert_eq!(
+            (report.scanned, report.backfilled, report.unresolvable),
+            (1, 1, 2)
+        );
+        assert_eq!(read_marks(&journal)[&7].gap_secs, 10);
+    }
+}
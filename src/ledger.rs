@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::types::UserPosition;
+use crate::utils::{load_json, save_json, Logger};
+
+const LEDGER_FILE: &str = "ledger.json";
+/// Positions touched this recently are kept even if the data-api snapshot doesn't show them yet.
+const RECONCILE_GRACE_SECS: i64 = 120;
+const MIN_OPEN_SHARES: f64 = 1e-6;
+
+pub type SharedLedger = Arc<Mutex<PositionLedger>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerPosition {
+    pub asset: String,
+    pub condition_id: String,
+    pub trader: Option<String>,
+    pub shares: f64,
+    pub cost_usd: f64,
+    pub opened_at: i64,
+    pub updated_at: i64,
+    /// Written off as dust: kept for bookkeeping but no longer counted as an open position.
+    #[serde(default)]
+    pub dust: bool,
+}
+
+impl LedgerPosition {
+    pub fn is_open(&self) -> bool {
+        self.shares > MIN_OPEN_SHARES && !self.dust
+    }
+}
+
+/// Positions held by the bot's wallet, attributed to the trader whose signal opened them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PositionLedger {
+    positions: HashMap<String, LedgerPosition>,
+    #[serde(skip)]
+    dirty: HashSet<String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl PositionLedger {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, LEDGER_FILE);
+        let mut ledger: PositionLedger = load_json(&path).unwrap_or_default();
+        ledger.path = Some(path);
+        ledger
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn holds(&self, condition_id: &str) -> bool {
+        self.positions
+            .values()
+            .any(|p| p.condition_id == condition_id && p.is_open())
+    }
+
+    /// A BUY opens a new position when the wallet holds nothing in that market yet; buys into
+    /// a market already held are adds and are never blocked by the open-position limit.
+    pub fn opens_new_position(&self, condition_id: Option<&str>) -> bool {
+        condition_id.map(|c| !self.holds(c)).unwrap_or(true)
+    }
+
+    /// Whether a trade in this market needs a fresh positions fetch: only when the market is
+    /// held (caps and sells depend on the real size) or marked dirty by a fill or reconciliation.
+    pub fn needs_position_fetch(&self, condition_id: Option<&str>) -> bool {
+        match condition_id {
+            Some(c) => self.holds(c) || self.dirty.contains(c),
+            None => true,
+        }
+    }
+
+    pub fn mark_dirty(&mut self, condition_id: &str) {
+        self.dirty.insert(condition_id.to_string());
+    }
+
+    pub fn clear_dirty(&mut self, condition_id: &str) {
+        self.dirty.remove(condition_id);
+    }
+
+    pub fn position(&self, asset: &str) -> Option<&LedgerPosition> {
+        self.positions.get(asset)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &LedgerPosition> {
+        self.positions.values()
+    }
+
+    /// Number of distinct markets (condition ids) with a non-zero holding.
+    pub fn open_count(&self) -> usize {
+        self.positions
+            .values()
+            .filter(|p| p.is_open())
+            .map(|p| p.condition_id.as_str())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    pub fn record_buy(
+        &mut self,
+        asset: &str,
+        condition_id: &str,
+        trader: Option<&str>,
+        shares: f64,
+        usd: f64,
+    ) {
+        let now = chrono::Utc::now().timestamp();
+        let entry = self
+            .positions
+            .entry(asset.to_string())
+            .or_insert_with(|| LedgerPosition {
+                asset: asset.to_string(),
+                condition_id: condition_id.to_string(),
+                trader: trader.map(|t| t.to_lowercase()),
+                shares: 0.0,
+                cost_usd: 0.0,
+                opened_at: now,
+                updated_at: now,
+                dust: false,
+            });
+        entry.dust = false;
+        if entry.trader.is_none() {
+            entry.trader = trader.map(|t| t.to_lowercase());
+        }
+        entry.shares += shares;
+        entry.cost_usd += usd;
+        entry.updated_at = now;
+        self.dirty.insert(condition_id.to_string());
+        self.persist();
+    }
+
+    pub fn record_sell(&mut self, asset: &str, shares: f64) {
+        let Some(entry) = self.positions.get_mut(asset) else {
+            return;
+        };
+        let before = entry.shares;
+        entry.shares = (entry.shares - shares).max(0.0);
+        if before > 0.0 {
+            entry.cost_usd *= entry.shares / before;
+        }
+        entry.updated_at = chrono::Utc::now().timestamp();
+        self.dirty.insert(entry.condition_id.clone());
+        if entry.shares <= MIN_OPEN_SHARES {
+            self.positions.remove(asset);
+        }
+        self.persist();
+    }
+
+    /// Brings share counts in line with a data-api snapshot of the wallet. Holdings the bot
+    /// didn't open are adopted without a trader; entries missing from the snapshot are dropped
+    /// unless they were updated within the grace window (the API lags fresh fills).
+    pub fn reconcile(&mut self, snapshot: &[UserPosition]) {
+        let now = chrono::Utc::now().timestamp();
+        let mut seen = HashSet::new();
+        for pos in snapshot {
+            let (Some(asset), Some(condition_id)) = (pos.asset.as_deref(), pos.condition_id.as_deref()) else {
+                continue;
+            };
+            let size = pos.size.unwrap_or(0.0);
+            if size <= MIN_OPEN_SHARES {
+                continue;
+            }
+            seen.insert(asset.to_string());
+            let known = self
+                .positions
+                .get(asset)
+                .map(|p| (p.shares - size).abs() <= MIN_OPEN_SHARES)
+                .unwrap_or(false);
+            if !known {
+                self.dirty.insert(condition_id.to_string());
+            }
+            let entry = self
+                .positions
+                .entry(asset.to_string())
+                .or_insert_with(|| LedgerPosition {
+                    asset: asset.to_string(),
+                    condition_id: condition_id.to_string(),
+                    trader: None,
+                    shares: size,
+                    cost_usd: pos.initial_value.unwrap_or(0.0),
+                    opened_at: now,
+                    updated_at: now,
+                    dust: false,
+                });
+            if now - entry.updated_at > RECONCILE_GRACE_SECS {
+                entry.shares = size;
+                if let Some(initial) = pos.initial_value {
+                    entry.cost_usd = initial;
+                }
+            }
+        }
+        let dirty = &mut self.dirty;
+        self.positions.retain(|asset, p| {
+            let keep = seen.contains(asset) || now - p.updated_at <= RECONCILE_GRACE_SECS;
+            if !keep {
+                dirty.insert(p.condition_id.clone());
+            }
+            keep
+        });
+        self.persist();
+    }
+
+    /// Marks a leftover position as dust so it stops counting towards open positions.
+    pub fn write_off_dust(&mut self, asset: &str) {
+        if let Some(entry) = self.positions.get_mut(asset) {
+            entry.dust = true;
+            entry.updated_at = chrono::Utc::now().timestamp();
+            self.persist();
+        }
+    }
+
+    pub fn is_dust(&self, asset: &str) -> bool {
+        self.positions.get(asset).map(|p| p.dust).unwrap_or(false)
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist position ledger: {}", e));
+            }
+        }
+    }
+}
+
+/// Gate for `MAX_OPEN_POSITIONS` with one position of hysteresis: once the limit is hit,
+/// new openings stay blocked until the count drops below `max - 1` (or to zero, for limits
+/// too small to leave that room).
+#[derive(Debug, Clone)]
+pub struct OpenPositionLimiter {
+    max: usize,
+    blocked: bool,
+}
+
+impl OpenPositionLimiter {
+    pub fn new(max: usize) -> Self {
+        Self { max, blocked: false }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// Returns whether a trade that opens a new market may proceed given the current count.
+    pub fn allows_new_position(&mut self, open_count: usize) -> bool {
+        self.blocked = !self.would_allow(open_count);
+        !self.blocked
+    }
+
+    /// What `allows_new_position` would answer, without moving in or out of the blocked state.
+    pub fn would_allow(&self, open_count: usize) -> bool {
+        if self.blocked {
+            open_count + 1 < self.max || open_count == 0
+        } else {
+            open_count < self.max
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buy_into_held_market_is_an_add() {
+        let mut ledger = PositionLedger::in_memory();
+        ledger.record_buy("yes-a", "market-a", Some("0xT"), 10.0, 5.0);
+        assert!(!ledger.opens_new_position(Some("market-a")));
+        assert!(ledger.opens_new_position(Some("market-b")));
+        // The other outcome of a held market is still an add, not a new position.
+        ledger.record_buy("no-a", "market-a", None, 4.0, 2.0);
+        assert_eq!(ledger.open_count(), 1);
+    }
+
+    #[test]
+    fn sold_out_or_dust_market_no_longer_counts() {
+        let mut ledger = PositionLedger::in_memory();
+        ledger.record_buy("yes-a", "market-a", None, 10.0, 5.0);
+        ledger.record_buy("yes-b", "market-b", None, 10.0, 5.0);
+        ledger.record_sell("yes-a", 10.0);
+        assert!(ledger.opens_new_position(Some("market-a")));
+        assert_eq!(ledger.open_count(), 1);
+        ledger.write_off_dust("yes-b");
+        assert_eq!(ledger.open_count(), 0);
+    }
+
+    #[test]
+    fn unknown_market_counts_as_new() {
+        let ledger = PositionLedger::in_memory();
+        assert!(ledger.opens_new_position(None));
+    }
+
+    fn snapshot(asset: &str, condition_id: &str, size: f64) -> UserPosition {
+        serde_json::from_value(serde_json::json!({
+            "asset": asset,
+            "conditionId": condition_id,
+            "size": size,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn external_trade_marks_market_dirty_and_forces_refetch() {
+        let mut ledger = PositionLedger::in_memory();
+        // Not held and clean: the copy can skip the positions fetch.
+        assert!(!ledger.needs_position_fetch(Some("market-a")));
+        // A manual trade outside the bot shows up in the periodic reconciliation.
+        ledger.reconcile(&[snapshot("yes-a", "market-a", 25.0)]);
+        assert!(ledger.needs_position_fetch(Some("market-a")));
+        ledger.clear_dirty("market-a");
+        // Still held, so caps and sells need the real size.
+        assert!(ledger.needs_position_fetch(Some("market-a")));
+        assert!(!ledger.needs_position_fetch(Some("market-b")));
+    }
+
+    #[test]
+    fn reconcile_matching_snapshot_stays_clean() {
+        let mut ledger = PositionLedger::in_memory();
+        ledger.record_buy("yes-a", "market-a", None, 10.0, 5.0);
+        ledger.clear_dirty("market-a");
+        ledger.reconcile(&[snapshot("yes-a", "market-a", 10.0)]);
+        assert!(!ledger.dirty.contains("market-a"));
+    }
+
+    #[test]
+    fn limiter_blocks_at_max_and_resumes_below_max_minus_one() {
+        let mut limiter = OpenPositionLimiter::new(3);
+        assert!(limiter.allows_new_position(2));
+        assert!(!limiter.allows_new_position(3));
+        assert!(limiter.is_blocked());
+        // Dropping to max - 1 is not enough to resume.
+        assert!(!limiter.allows_new_position(2));
+        assert!(limiter.allows_new_position(1));
+        assert!(!limiter.is_blocked());
+        assert!(limiter.allows_new_position(2));
+    }
+
+    #[test]
+    fn would_allow_respects_blocked_state_without_changing_it() {
+        let mut limiter = OpenPositionLimiter::new(3);
+        assert!(limiter.would_allow(2));
+        assert!(!limiter.would_allow(3));
+        assert!(!limiter.is_blocked());
+        assert!(!limiter.allows_new_position(3));
+        // Blocked: max - 1 is still refused, and asking doesn't unblock.
+        assert!(!limiter.would_allow(2));
+        assert!(limiter.would_allow(1));
+        assert!(limiter.is_blocked());
+    }
+
+    #[test]
+    fn limiter_with_max_one_reopens_when_flat() {
+        let mut limiter = OpenPositionLimiter::new(1);
+        assert!(limiter.allows_new_position(0));
+        assert!(!limiter.allows_new_position(1));
+        assert!(limiter.allows_new_position(0));
+    }
+}
@@ -1,11 +1,75 @@
+pub mod advisories;
+pub mod aggregation;
+pub mod alerts;
+pub mod attribution;
+pub mod backfill;
+pub mod balance;
+pub mod build_info;
+pub mod catch_up;
+pub mod chaos;
+pub mod classification;
+pub mod concentration;
 pub mod config;
+pub mod consensus;
+pub mod copy_now;
+pub mod ctf_approval;
+pub mod daily_volume;
+pub mod day_stats;
+pub mod diagnose;
+pub mod digest;
+pub mod dust;
 pub mod executor;
+pub mod exits;
+pub mod handover;
+pub mod inactivity;
+pub mod init;
+pub mod journal_marks;
+pub mod interactive;
+pub mod ledger;
+#[cfg(feature = "loadtest")]
+pub mod loadtest;
+pub mod manual_copy;
+pub mod market_overrides;
+pub mod market_share;
+pub mod metadata_cache;
 pub mod monitor;
+pub mod order_intents;
+pub mod order_templates;
+pub mod position_action;
+pub mod position_builder;
+pub mod processed_trades;
+pub mod proxy_wallet;
+pub mod overflow;
+pub mod paper;
+pub mod prefetch;
+pub mod profiling;
+pub mod rebalance;
+pub mod redeem;
+pub mod resolution;
+pub mod resting_orders;
+pub mod rtds_capture;
+pub mod shadow;
+pub mod skip_reason;
+pub mod skip_rules;
+pub mod status;
+pub mod status_api;
+pub mod strategy_preview;
+pub mod supervisor;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+pub mod trader_history;
+pub mod trader_portfolio;
+pub mod trading_state;
+pub mod trial;
 pub mod types;
+pub mod usdc_approval;
+pub mod watchdog;
 pub mod utils;
 
 pub use config::{CopyStrategy, CopyStrategyConfig, EnvConfig};
-pub use types::{RtdsActivity, UserActivity, UserPosition};
+pub use metadata_cache::MetadataCache;
+pub use types::{MarketMetadata, RtdsActivity, UserActivity, UserPosition};
 pub use utils::{
-    fetch_data, get_usdc_allowance, get_usdc_balance, perform_health_check, theme, Logger,
+    fetch_data, get_usdc_allowance, get_usdc_balance, perform_health_check, theme, DataApiClient,
+    FetchError, Logger,
 };
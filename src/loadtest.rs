@@ -0,0 +1,665 @@
+//! Throughput harness behind the `loadtest` feature and subcommand: a seeded generator
+//! produces RTDS trade messages at a set rate, and one consumer copies each through the
+//! executor's own copy path (`execute_trade` on an `ExecutorContext`) against the testkit's
+//! fake Polymarket stack. Dedup, sizing, the skip rules, order signing and submission and
+//! the journal writer are the bot's; only the services answering them are fakes, so nothing
+//! reaches the network.
+//!
+//! The report gives sustained throughput, queue wait and copy latency percentiles, the
+//! executor queue's and the journal writer's high-water marks, the hot-path stage timings
+//! and memory growth, so a VPS can be checked against a busy event before it happens.
+//! Integration tests reuse `run` for regression thresholds.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::config::{self, EnvConfig};
+use crate::executor::execute_trade;
+use crate::profiling::{self, StageStats};
+use crate::testkit::{self, FakeMarket, FakePolymarket, FakeRtds, PROXY_WALLET};
+use crate::types::RtdsActivity;
+use crate::utils::{journal_dropped_rows, read_journal, JournalEntry, JournalStatus};
+
+const USAGE: &str = "Usage: polymarket-copy-rust loadtest [--rate N] [--duration SECS] \
+[--gateway-latency-ms N] [--queue N] [--seed N] [--from-journal]
+
+Drives synthetic trade signals through the copy path against fake Polymarket services and \
+reports throughput, latency, queue and journal pressure and memory. Sizing settings \
+(COPY_STRATEGY, COPY_SIZE, ...) come from your config; nothing else does.";
+
+/// Stages timed for every signal, in pipeline order.
+pub const STAGES: &[&str] = &["queue_wait", "parse", "copy", "total"];
+/// Settings taken from the user's config for a `loadtest` run; everything else points at
+/// the fakes.
+const SIZING_VARS: &[&str] = &[
+    "COPY_STRATEGY",
+    "COPY_SIZE",
+    "COPY_PERCENTAGE",
+    "MAX_ORDER_SIZE_USD",
+    "MIN_ORDER_SIZE_USD",
+    "MAX_POSITION_SIZE_USD",
+    "MAX_DAILY_VOLUME_USD",
+    "ADAPTIVE_MIN_PERCENT",
+    "ADAPTIVE_MAX_PERCENT",
+    "ADAPTIVE_THRESHOLD_USD",
+    "ADAPTIVE_TAIL",
+    "TIERED_MULTIPLIERS",
+    "TRADE_MULTIPLIER",
+    "JOURNAL_BUFFER_ROWS",
+];
+/// Journal rows are sampled for traders, markets and sizes up to this many, newest first.
+const JOURNAL_SAMPLE: usize = 5_000;
+/// Memory is sampled every this many signals.
+const RSS_SAMPLE_EVERY: u64 = 256;
+/// The fake wallet's USDC, enough that a long run doesn't starve itself into skips.
+const FAKE_BALANCE_USD: f64 = 10_000_000.0;
+/// Numbers each run's scratch directory, for runs sharing a process.
+static RUNS: AtomicU64 = AtomicU64::new(0);
+
+/// A market the generator trades in.
+#[derive(Debug, Clone)]
+pub struct SyntheticMarket {
+    pub asset: String,
+    pub condition_id: String,
+    pub slug: String,
+    /// Prices are drawn within ±5c of this.
+    pub price: f64,
+}
+
+/// What to generate and how fast.
+#[derive(Debug, Clone)]
+pub struct LoadProfile {
+    pub rate_per_sec: f64,
+    pub duration: Duration,
+    pub traders: Vec<String>,
+    pub markets: Vec<SyntheticMarket>,
+    /// Trader USD amounts, drawn uniformly.
+    pub sizes_usd: Vec<f64>,
+    /// Share of signals that are BUYs.
+    pub buy_fraction: f64,
+    pub seed: u64,
+    /// Executor channel capacity; the live bot uses 100.
+    pub queue_capacity: usize,
+    /// How long the fake CLOB takes to answer an order post.
+    pub gateway_latency: Duration,
+}
+
+impl Default for LoadProfile {
+    fn default() -> Self {
+        Self {
+            rate_per_sec: 50.0,
+            duration: Duration::from_secs(60),
+            traders: (1..=5).map(|i| format!("0x{:040x}", i)).collect(),
+            markets: (1..=20)
+                .map(|i| SyntheticMarket {
+                    asset: format!("{}", 10_000_000_000u64 + i),
+                    condition_id: format!("0x{:064x}", i),
+                    slug: format!("synthetic-market-{}", i),
+                    price: 0.05 + 0.9 * (i as f64 / 21.0),
+                })
+                .collect(),
+            sizes_usd: vec![5.0, 20.0, 50.0, 120.0, 400.0, 1_500.0],
+            buy_fraction: 0.7,
+            seed: 1,
+            queue_capacity: 100,
+            gateway_latency: Duration::from_millis(25),
+        }
+    }
+}
+
+impl LoadProfile {
+    /// Draws traders, markets and sizes from recent journal rows; whatever the journal
+    /// lacks keeps the default.
+    pub fn from_journal(entries: &[JournalEntry]) -> Self {
+        let mut profile = Self::default();
+        let recent = entries.iter().rev().take(JOURNAL_SAMPLE);
+        let mut traders = BTreeMap::new();
+        let mut markets = BTreeMap::new();
+        let mut sizes = Vec::new();
+        let (mut buys, mut sides) = (0usize, 0usize);
+        for entry in recent {
+            traders.insert(entry.trader.clone(), ());
+            if let (Some(asset), Some(cid)) = (&entry.asset, &entry.condition_id) {
+                let price = if entry.my_tokens > 0.0 && entry.my_usd > 0.0 {
+                    entry.my_usd / entry.my_tokens
+                } else {
+                    0.5
+                };
+                markets
+                    .entry(asset.clone())
+                    .or_insert_with(|| SyntheticMarket {
+                        asset: asset.clone(),
+                        condition_id: cid.clone(),
+                        slug: entry.slug.clone().unwrap_or_default(),
+                        price: price.clamp(0.05, 0.95),
+                    });
+            }
+            sizes.extend(entry.trader_usd.filter(|u| *u > 0.0));
+            if let Some(side) = &entry.side {
+                sides += 1;
+                buys += usize::from(side == "BUY");
+            }
+        }
+        if !traders.is_empty() {
+            profile.traders = traders.into_keys().collect();
+        }
+        if !markets.is_empty() {
+            profile.markets = markets.into_values().collect();
+        }
+        if !sizes.is_empty() {
+            profile.sizes_usd = sizes;
+        }
+        if sides > 0 {
+            profile.buy_fraction = buys as f64 / sides as f64;
+        }
+        profile
+    }
+}
+
+/// Seeded source of RTDS trade messages; the same seed gives the same sequence.
+pub struct SignalGenerator {
+    profile: LoadProfile,
+    state: u64,
+    seq: u64,
+}
+
+impl SignalGenerator {
+    pub fn new(profile: LoadProfile) -> Self {
+        // xorshift must never be seeded with zero.
+        let state = profile.seed.max(1);
+        Self {
+            profile,
+            state,
+            seq: 0,
+        }
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An index into a list of `len` items.
+    fn pick(&mut self, len: usize) -> usize {
+        ((self.next_f64() * len as f64) as usize).min(len.saturating_sub(1))
+    }
+
+    /// The next signal as the RTDS wire message, and the trader it is from.
+    pub fn next_message(&mut self, now_secs: i64) -> (String, String) {
+        self.seq += 1;
+        let trader = self.pick(self.profile.traders.len());
+        let market = self.pick(self.profile.markets.len());
+        let usd = self.pick(self.profile.sizes_usd.len());
+        let jitter = (self.next_f64() - 0.5) * 0.1;
+        let buy = self.next_f64() < self.profile.buy_fraction;
+        let (trader, market) = (&self.profile.traders[trader], &self.profile.markets[market]);
+        let usd = self.profile.sizes_usd[usd];
+        let price = (market.price + jitter).clamp(0.01, 0.99);
+        let price = (price * 100.0).round() / 100.0;
+        let side = if buy { "BUY" } else { "SELL" };
+        let message = serde_json::json!({
+            "topic": "activity",
+            "type": "trades",
+            "payload": {
+                "proxyWallet": trader,
+                "timestamp": now_secs,
+                "conditionId": market.condition_id,
+                "type": "TRADE",
+                "size": usd / price,
+                "usdcSize": usd,
+                "price": price,
+                "asset": market.asset,
+                "side": side,
+                "slug": market.slug,
+                "transactionHash": format!("0x{:064x}", self.seq),
+            }
+        });
+        (message.to_string(), trader.clone())
+    }
+}
+
+/// Latency percentiles of one stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    pub fn of(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let at = |q: f64| samples[((samples.len() - 1) as f64 * q).round() as usize];
+        Self {
+            count: samples.len(),
+            p50: at(0.5),
+            p90: at(0.9),
+            p99: at(0.99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadReport {
+    pub target_rate: f64,
+    pub sent: u64,
+    pub processed: u64,
+    /// Signals whose journal row isn't `executed` (invalid, duplicate, skipped, failed).
+    pub skipped: u64,
+    /// Orders the fake CLOB received.
+    pub orders: usize,
+    pub elapsed: Duration,
+    pub stages: Vec<(&'static str, Percentiles)>,
+    /// The copy path's own stage timings (`profiling`), process-wide.
+    pub hot_path: Vec<(&'static str, StageStats)>,
+    pub queue_high_water: usize,
+    pub queue_capacity: usize,
+    /// Rows waiting for the journal writer, at most, and the writer's buffer size.
+    pub journal_high_water: usize,
+    pub journal_capacity: usize,
+    /// Low-priority rows dropped because the journal buffer was full during the run.
+    pub journal_dropped: u64,
+    /// Resident memory in KiB at the start, peak and end (Linux only).
+    pub rss_start_kb: Option<u64>,
+    pub rss_peak_kb: Option<u64>,
+    pub rss_end_kb: Option<u64>,
+}
+
+impl LoadReport {
+    pub fn throughput(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            return 0.0;
+        }
+        self.processed as f64 / self.elapsed.as_secs_f64()
+    }
+
+    pub fn stage(&self, name: &str) -> Option<Percentiles> {
+        self.stages
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, p)| *p)
+    }
+
+    /// The pipeline held the target rate (within 5%) without filling the executor queue or
+    /// the journal buffer.
+    pub fn kept_up(&self) -> bool {
+        self.throughput() >= self.target_rate * 0.95
+            && self.queue_high_water < self.queue_capacity
+            && self.journal_high_water < self.journal_capacity
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let mut lines = vec![
+            format!(
+                "Sent {} and processed {} signal(s) ({} not executed, {} order(s)) in {:.1}s",
+                self.sent,
+                self.processed,
+                self.skipped,
+                self.orders,
+                self.elapsed.as_secs_f64()
+            ),
+            format!(
+                "Throughput: {:.1}/s sustained against a target of {:.1}/s",
+                self.throughput(),
+                self.target_rate
+            ),
+            format!(
+                "Queue high-water mark: {} of {}",
+                self.queue_high_water, self.queue_capacity
+            ),
+            format!(
+                "Journal writer high-water mark: {} of {} row(s), {} dropped",
+                self.journal_high_water, self.journal_capacity, self.journal_dropped
+            ),
+        ];
+        if let (Some(start), Some(peak), Some(end)) =
+            (self.rss_start_kb, self.rss_peak_kb, self.rss_end_kb)
+        {
+            lines.push(format!(
+                "Memory: {:.1} MiB at start, {:.1} MiB peak, {:.1} MiB at end ({:+.1} MiB)",
+                start as f64 / 1024.0,
+                peak as f64 / 1024.0,
+                end as f64 / 1024.0,
+                (end as f64 - start as f64) / 1024.0
+            ));
+        }
+        lines.push("Stage latency (ms): p50 / p90 / p99 / max".to_string());
+        for (name, p) in &self.stages {
+            lines.push(format!(
+                "  {:<10} {:>8.3} / {:>8.3} / {:>8.3} / {:>8.3}",
+                name,
+                ms(p.p50),
+                ms(p.p90),
+                ms(p.p99),
+                ms(p.max)
+            ));
+        }
+        if !self.hot_path.is_empty() {
+            lines.push("Copy path stages (ms): count / avg / max".to_string());
+            for (name, s) in &self.hot_path {
+                lines.push(format!(
+                    "  {:<18} {:>6} / {:>8.3} / {:>8.3}",
+                    name,
+                    s.count,
+                    ms(s.average()),
+                    ms(s.max)
+                ));
+            }
+        }
+        lines.push(
+            if self.kept_up() {
+                "Verdict: kept up"
+            } else {
+                "Verdict: fell behind"
+            }
+            .to_string(),
+        );
+        lines
+    }
+}
+
+/// Resident set size in KiB, from `/proc/self/statm`.
+fn rss_kb() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4)
+}
+
+/// The payload of an RTDS trade message, validated the way the monitor does before it
+/// queues a trade.
+fn parse(message: &str) -> Option<RtdsActivity> {
+    serde_json::from_str::<serde_json::Value>(message)
+        .ok()
+        .and_then(|v| serde_json::from_value(v.get("payload")?.clone()).ok())
+        .filter(|a: &RtdsActivity| a.validate(chrono::Utc::now().timestamp()).is_ok())
+}
+
+/// Generates `profile.rate_per_sec` signals a second for `profile.duration` into a bounded
+/// queue and copies them one at a time, as the executor does, until the queue drains.
+/// The fake stack lists the profile's markets and fills every order after
+/// `profile.gateway_latency`; `settings` (sizing, mostly) are applied over its config.
+pub async fn run(profile: &LoadProfile, settings: &[(&str, &str)]) -> Result<LoadReport> {
+    let dir = std::env::temp_dir().join(format!(
+        "polymarket-loadtest-{}-{}",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let report = run_in(profile, settings, &dir).await;
+    let _ = std::fs::remove_dir_all(&dir);
+    report
+}
+
+async fn run_in(
+    profile: &LoadProfile,
+    settings: &[(&str, &str)],
+    dir: &Path,
+) -> Result<LoadReport> {
+    let polymarket = FakePolymarket::start(PROXY_WALLET, FAKE_BALANCE_USD).await?;
+    polymarket.set_order_latency(profile.gateway_latency);
+    for market in &profile.markets {
+        polymarket.add_market(&FakeMarket {
+            condition_id: market.condition_id.clone(),
+            asset: market.asset.clone(),
+            title: market.slug.clone(),
+            slug: market.slug.clone(),
+            outcome: "Yes".to_string(),
+            price: market.price,
+            min_order_size: 1.0,
+        });
+    }
+    // Only its URL is used: signals go straight to the copy path.
+    let rtds = FakeRtds::start().await?;
+    let traders: Vec<&str> = profile.traders.iter().map(String::as_str).collect();
+    let mut vars = testkit::config_vars(&polymarket, &rtds, &traders, dir);
+    vars.extend(settings.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+    let config = EnvConfig::from_vars(&vars).context("load test config")?;
+    let trade_log_path = config.trade_log_path.clone();
+    let ex = testkit::executor_context(config).await?;
+    profiling::enable();
+
+    let rss_start_kb = rss_kb();
+    let dropped_before = journal_dropped_rows();
+    let (tx, mut rx) = mpsc::channel::<(String, String, Instant)>(profile.queue_capacity.max(1));
+    let depth = Arc::new(AtomicUsize::new(0));
+    let high_water = Arc::new(AtomicUsize::new(0));
+
+    let producer = {
+        let (depth, high_water) = (depth.clone(), high_water.clone());
+        let mut generator = SignalGenerator::new(profile.clone());
+        let gap = Duration::from_secs_f64(1.0 / profile.rate_per_sec.max(0.001));
+        let total = (profile.rate_per_sec * profile.duration.as_secs_f64()).round() as u64;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(gap);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
+            let mut sent = 0u64;
+            while sent < total {
+                ticker.tick().await;
+                let (message, trader) = generator.next_message(chrono::Utc::now().timestamp());
+                // Counted before the send so the consumer can never take it below zero.
+                let queued = depth.fetch_add(1, Ordering::SeqCst) + 1;
+                high_water.fetch_max(queued, Ordering::SeqCst);
+                if tx.send((message, trader, Instant::now())).await.is_err() {
+                    break;
+                }
+                sent += 1;
+            }
+            sent
+        })
+    };
+
+    let started = Instant::now();
+    let mut samples: BTreeMap<&'static str, Vec<Duration>> = BTreeMap::new();
+    let mut timed = |stage: &'static str, took: Duration| {
+        samples.entry(stage).or_default().push(took);
+    };
+    let mut processed = 0u64;
+    let mut journal_high_water = 0;
+    let mut rss_peak_kb = rss_start_kb;
+    while let Some((message, trader, queued_at)) = rx.recv().await {
+        depth.fetch_sub(1, Ordering::SeqCst);
+        timed("queue_wait", queued_at.elapsed());
+
+        let parsed = Instant::now();
+        let activity = parse(&message);
+        timed("parse", parsed.elapsed());
+        if let Some(activity) = activity {
+            let copied = Instant::now();
+            if let Err(e) = execute_trade(&ex, activity, trader).await {
+                crate::utils::Logger::warning(&format!("Load test copy failed: {:#}", e));
+            }
+            timed("copy", copied.elapsed());
+        }
+        timed("total", queued_at.elapsed());
+        journal_high_water = journal_high_water.max(ex.state.journal.queued());
+        processed += 1;
+        if processed.is_multiple_of(RSS_SAMPLE_EVERY) {
+            rss_peak_kb = rss_peak_kb.max(rss_kb());
+        }
+    }
+    let elapsed = started.elapsed();
+    let sent = producer.await.unwrap_or(0);
+    ex.state.journal.flush().await;
+    let rss_end_kb = rss_kb();
+    let executed = read_journal(Path::new(&trade_log_path))
+        .unwrap_or_default()
+        .iter()
+        .filter(|row| row.status == JournalStatus::Executed)
+        .count() as u64;
+    let stages = STAGES
+        .iter()
+        .map(|name| {
+            let p = samples
+                .get_mut(name)
+                .map(|s| Percentiles::of(s))
+                .unwrap_or_default();
+            (*name, p)
+        })
+        .collect();
+    Ok(LoadReport {
+        target_rate: profile.rate_per_sec,
+        sent,
+        processed,
+        skipped: processed.saturating_sub(executed),
+        orders: polymarket.submissions().len(),
+        elapsed,
+        stages,
+        hot_path: profiling::snapshot(),
+        // A signal blocked on a full queue is counted too; the queue itself holds no more.
+        queue_high_water: high_water
+            .load(Ordering::SeqCst)
+            .min(profile.queue_capacity.max(1)),
+        queue_capacity: profile.queue_capacity.max(1),
+        journal_high_water,
+        journal_capacity: ex.config.journal_buffer_rows.max(1),
+        journal_dropped: journal_dropped_rows() - dropped_before,
+        rss_start_kb,
+        rss_peak_kb: rss_peak_kb.max(rss_end_kb),
+        rss_end_kb,
+    })
+}
+
+fn parse_args(args: &[String], profile: &mut LoadProfile) -> Result<bool> {
+    let mut from_journal = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| -> Result<f64> {
+            iter.next()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+                .with_context(|| format!("{} needs a non-negative number\n\n{}", name, USAGE))
+        };
+        match arg.as_str() {
+            "--rate" => profile.rate_per_sec = value(arg)?.max(0.001),
+            "--duration" => profile.duration = Duration::from_secs_f64(value(arg)?),
+            "--gateway-latency-ms" => {
+                profile.gateway_latency = Duration::from_millis(value(arg)? as u64)
+            }
+            "--queue" => profile.queue_capacity = value(arg)? as usize,
+            "--seed" => profile.seed = value(arg)? as u64,
+            "--from-journal" => from_journal = true,
+            _ => anyhow::bail!("unknown argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    Ok(from_journal)
+}
+
+/// Entry point for `loadtest`.
+pub async fn run_cli(args: &[String]) -> Result<()> {
+    let mut profile = LoadProfile::default();
+    let from_journal = parse_args(args, &mut profile)?;
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    if from_journal {
+        let config = EnvConfig::parse()?;
+        let entries = read_journal(Path::new(&config.trade_log_path))?;
+        let sampled = LoadProfile::from_journal(&entries);
+        profile = LoadProfile {
+            traders: sampled.traders,
+            markets: sampled.markets,
+            sizes_usd: sampled.sizes_usd,
+            buy_fraction: sampled.buy_fraction,
+            ..profile
+        };
+    }
+    let settings: Vec<(&str, String)> = SIZING_VARS
+        .iter()
+        .filter_map(|key| Some((*key, std::env::var(key).ok()?)))
+        .collect();
+    let settings: Vec<(&str, &str)> = settings.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    println!(
+        "Load test: {:.1} signal(s)/s for {:.0}s across {} trader(s) and {} market(s), \
+gateway latency {}ms",
+        profile.rate_per_sec,
+        profile.duration.as_secs_f64(),
+        profile.traders.len(),
+        profile.markets.len(),
+        profile.gateway_latency.as_millis()
+    );
+    let report = run(&profile, &settings).await?;
+    for line in report.lines() {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_journal_row;
+
+    #[test]
+    fn the_same_seed_generates_the_same_signals() {
+        let messages = |seed| {
+            let mut generator = SignalGenerator::new(LoadProfile {
+                seed,
+                ..LoadProfile::default()
+            });
+            (0..20)
+                .map(|_| generator.next_message(1_760_000_000).0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(7), messages(7));
+        assert_ne!(messages(7), messages(8));
+        let first: serde_json::Value = serde_json::from_str(&messages(7)[0]).unwrap();
+        let activity: RtdsActivity = serde_json::from_value(first["payload"].clone()).unwrap();
+        assert!(activity.validate(1_760_000_000).is_ok());
+    }
+
+    #[test]
+    fn percentiles_pick_the_nearest_rank() {
+        let mut samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let p = Percentiles::of(&mut samples);
+        assert_eq!(p.count, 100);
+        assert_eq!(p.p50, Duration::from_millis(51));
+        assert_eq!(p.p99, Duration::from_millis(99));
+        assert_eq!(p.max, Duration::from_millis(100));
+        assert_eq!(Percentiles::of(&mut []), Percentiles::default());
+    }
+
+    #[test]
+    fn journal_history_shapes_the_profile() {
+        let row = |trader: &str, asset: &str, side: &str, usd: f64| {
+            parse_journal_row(&format!(
+                r#"{{"timestamp":1,"status":"executed","trader":"{}","condition_id":"cid-{}","asset":"{}","side":"{}","trader_usd":{},"my_usd":{},"my_tokens":{}}}"#,
+                trader,
+                asset,
+                asset,
+                side,
+                usd,
+                usd / 10.0,
+                usd / 5.0
+            ))
+            .unwrap()
+        };
+        let entries = vec![
+            row("0xa", "1", "BUY", 100.0),
+            row("0xb", "2", "SELL", 40.0),
+            row("0xa", "1", "BUY", 60.0),
+        ];
+        let profile = LoadProfile::from_journal(&entries);
+        assert_eq!(profile.traders, ["0xa", "0xb"]);
+        assert_eq!(profile.markets.len(), 2);
+        assert!((profile.markets[0].price - 0.5).abs() < 1e-9);
+        assert_eq!(profile.sizes_usd, [60.0, 40.0, 100.0]);
+        assert!((profile.buy_fraction - 2.0 / 3.0).abs() < 1e-9);
+
+        let empty = LoadProfile::from_journal(&[]);
+        assert_eq!(empty.traders, LoadProfile::default().traders);
+    }
+}
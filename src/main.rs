@@ -1,50 +1,149 @@
-mod config;
-mod executor;
-mod monitor;
-mod types;
-mod utils;
-
 use anyhow::Result;
+use serde_json::json;
 use std::sync::Arc;
 use tokio::signal;
+use tokio_util::sync::CancellationToken;
 
-use config::EnvConfig;
-use executor::{run_trade_executor, stop_trade_executor};
-use monitor::{run_trade_monitor, stop_trade_monitor};
-use utils::{create_clob_client, get_usdc_balance, is_contract_address, perform_health_check, Logger};
+use polymarket_copy_rust::advisories::{self, AdvisorySeverity};
+use polymarket_copy_rust::config::{conflicts, EnvConfig};
+use polymarket_copy_rust::digest;
+use polymarket_copy_rust::executor::run_trade_executor;
+use polymarket_copy_rust::monitor::run_trade_monitor;
+use polymarket_copy_rust::status;
+use polymarket_copy_rust::supervisor::{supervisor, TaskStatus, STOP_LAST};
+use polymarket_copy_rust::types::{BotEvent, RtdsActivity};
+use polymarket_copy_rust::utils::{
+    self, create_clob_client, event_webhook, flush_journal, journal_dropped_rows,
+    run_health_check, JournalDay, LogFormat, Logger, RpcClient,
+};
+use polymarket_copy_rust::{
+    alerts, attribution, build_info, chaos, config, copy_now, ctf_approval, diagnose, handover,
+    init, journal_marks, market_overrides, profiling, shadow, status_api, strategy_preview,
+    trader_history, trading_state, usdc_approval,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("init") {
+        return init::run(&args[1..]).await;
+    }
+    if args.first().map(String::as_str) == Some("diagnose") {
+        return diagnose::run(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("strategy") {
+        return strategy_preview::run(&args[1..]);
+    }
+    if args.first().map(String::as_str) == Some("copy-now") {
+        return copy_now::run(&args[1..]).await;
+    }
+    if args.first().map(String::as_str) == Some("journal") {
+        return journal_marks::run(&args[1..]).await;
+    }
+    #[cfg(feature = "loadtest")]
+    if args.first().map(String::as_str) == Some("loadtest") {
+        return polymarket_copy_rust::loadtest::run_cli(&args[1..]).await;
+    }
+    if args.first().map(String::as_str) == Some("advisories") {
+        return advisories::run(&args[1..]).await;
+    }
+    if args.first().map(String::as_str) == Some("market") {
+        return market_overrides::run(&args[1..]).await;
+    }
+    if args.iter().any(|a| a == "--version" || a == "-V") {
+        if args.iter().any(|a| a == "--verbose" || a == "-v") {
+            println!("{}", build_info::verbose());
+        } else {
+            println!("polymarket-copy-rust {}", build_info::summary());
+        }
+        return Ok(());
+    }
+
+    if args.iter().any(|a| a == "--profile-hotpath") {
+        profiling::enable();
+    }
+
     dotenvy::dotenv().ok();
+    let config_file = config::load_config_file()?;
+    Logger::init(LogFormat::from_env());
 
-    println!();
-    println!(
-        "  {} New here? Read GETTING_STARTED.md and run a health check.{}",
-        utils::theme::colors::MUTED,
-        utils::theme::colors::RESET
-    );
-    println!();
+    if !Logger::is_json() {
+        println!();
+        println!(
+            "  {} New here? Read GETTING_STARTED.md and run a health check.{}",
+            utils::theme::colors::MUTED,
+            utils::theme::colors::RESET
+        );
+        println!();
+    }
 
     let config = EnvConfig::from_env().await?;
 
-    Logger::startup(&config.user_addresses, &config.proxy_wallet);
+    Logger::startup(&config.logical_traders(), &config.proxy_wallet);
+    if config.dry_run {
+        Logger::header("DRY RUN - PAPER TRADING, NO ORDERS ARE PLACED");
+    }
+    if let Some(path) = &config_file {
+        Logger::info(&format!("Settings loaded from {}", path.display()));
+    }
+    if let Some(chaos_config) = config.chaos() {
+        chaos::announce(chaos_config);
+    }
+    if let Some(path) = &config.shadow_config_file {
+        shadow::install(path)?;
+    }
+    Logger::info(&format!("Build: {}", build_info::summary()));
+    status::publish("build", build_info::to_json());
+    let features = config.features.summary();
+    let overrides = market_overrides::init(&config);
+    if !features.is_empty() || overrides > 0 {
+        Logger::header("FEATURES");
+        for (name, params) in &features {
+            Logger::field(name, params);
+        }
+        if overrides > 0 {
+            Logger::field(
+                "Markets",
+                &format!(
+                    "{} market-level override{} active",
+                    overrides,
+                    if overrides == 1 { "" } else { "s" }
+                ),
+            );
+        }
+    }
+    advisories::init(&config);
+    let mut conflict_ids = Vec::new();
+    for issue in conflicts(&config) {
+        let id = format!("config:{}", issue.code);
+        let message = format!("{}: {}", issue.key, issue.message);
+        Logger::warning(&message);
+        advisories::raise(&id, AdvisorySeverity::Warning, &message, json!(message));
+        conflict_ids.push(id);
+    }
+    advisories::resolve_missing("config:", &conflict_ids);
+    let build_age = build_info::build_age_days();
+    if config.build_max_age_days > 0 && build_age > config.build_max_age_days as i64 {
+        let message = format!(
+            "This build is {} days old (limit {}). Pull and rebuild to pick up fixes.",
+            build_age, config.build_max_age_days
+        );
+        Logger::warning(&message);
+        advisories::raise(
+            "stale_build",
+            AdvisorySeverity::High,
+            &message,
+            // A new build is a material change; another day on the same one is not.
+            json!(build_info::summary()),
+        );
+    } else {
+        advisories::resolve("stale_build");
+    }
 
+    alerts::start(&config);
+    event_webhook::start(&config);
     Logger::info("Running system check…");
-    let balance = get_usdc_balance(
-        &config.rpc_url,
-        &config.usdc_contract_address,
-        &config.proxy_wallet,
-    )
-    .await;
-    let polymarket_ok = utils::fetch_data(
-        &reqwest::Client::new(),
-        "https://data-api.polymarket.com/positions?user=0x0000000000000000000000000000000000000000",
-        config.request_timeout_ms,
-        config.network_retry_limit,
-    )
-    .await
-    .is_ok();
-    let health = perform_health_check(&config.rpc_url, balance, polymarket_ok).await;
+    let health = run_health_check(&config).await;
 
     Logger::separator();
     Logger::header("SYSTEM CHECK");
@@ -69,16 +168,42 @@ async fn main() -> Result<()> {
         &health.checks.polymarket_api.status,
         &health.checks.polymarket_api.message,
     );
+    Logger::health_line(
+        "CTF approval",
+        &health.checks.ctf_approval.status,
+        &health.checks.ctf_approval.message,
+    );
+    Logger::health_line(
+        "USDC allowance",
+        &health.checks.usdc_allowance.status,
+        &health.checks.usdc_allowance.message,
+    );
+    Logger::health_line(
+        "CLOB auth",
+        &health.checks.clob_auth.status,
+        &health.checks.clob_auth.message,
+    );
+    Logger::health_line(
+        "RTDS WebSocket",
+        &health.checks.websocket.status,
+        &health.checks.websocket.message,
+    );
+    Logger::health_line("Gas (POL)", &health.checks.gas.status, &health.checks.gas.message);
     Logger::separator();
 
     if !health.healthy {
         Logger::warning("System check reported issues; continuing anyway.");
+        alerts::warn(&format!(
+            "System check degraded at startup:\n{}",
+            health.failures().join("\n")
+        ));
     }
 
+    // One connection pool, timeout and retry policy for every chain read from here on.
+    let rpc = RpcClient::from_config(&config)?;
+
     Logger::info("Initializing CLOB client...");
-    let is_proxy_safe = is_contract_address(&config.rpc_url, &config.proxy_wallet)
-        .await
-        .unwrap_or(false);
+    let is_proxy_safe = rpc.is_contract(&config.proxy_wallet).await.unwrap_or(false);
     let wallet_type = if is_proxy_safe {
         "Gnosis Safe"
     } else {
@@ -92,40 +217,123 @@ async fn main() -> Result<()> {
         .timeout(std::time::Duration::from_millis(config.request_timeout_ms))
         .build()?;
 
+    trader_history::check_traders(&config, &http_client).await?;
+
     Logger::info("Initializing executor...");
     let (clob_client, signer) = create_clob_client(&config).await?;
+    usdc_approval::ensure_allowance(&config, &signer).await?;
+    ctf_approval::ensure_approvals(&config, &signer, &rpc).await;
     let clob_client = Arc::new(clob_client);
     let signer = Arc::new(tokio::sync::Mutex::new(signer));
     let config_arc = Arc::new(config.clone());
     let http_arc = Arc::new(http_client.clone());
 
-    let (tx, rx) = tokio::sync::mpsc::channel::<(crate::types::RtdsActivity, String)>(100);
+    let (tx, rx) = tokio::sync::mpsc::channel::<(RtdsActivity, String)>(100);
 
-    let config_exec = config_arc.clone();
-    let http_exec = http_arc.clone();
-    let clob_exec = clob_client.clone();
-    let signer_exec = signer.clone();
-    let rx_exec = rx;
-    tokio::spawn(async move {
-        if let Err(e) = run_trade_executor(config_exec, http_exec, clob_exec, signer_exec, rx_exec).await {
-            Logger::error(&format!("Executor error: {}", e));
-        }
+    let executor = run_trade_executor(
+        config_arc.clone(),
+        http_arc.clone(),
+        rpc.clone(),
+        clob_client.clone(),
+        signer.clone(),
+        rx,
+        CancellationToken::new(),
+    )
+    .await?;
+
+    status::register("trading_state", trading_state::status_json);
+    status::register("tasks", || {
+        serde_json::Value::Array(supervisor().statuses().iter().map(|t| t.to_json()).collect())
     });
+    if config.status_port > 0 {
+        let port = config.status_port;
+        supervisor().spawn("status-endpoint", STOP_LAST, 5, move || status_api::serve_local(port));
+    }
+    status_api::start(config_arc.clone());
+    if config.digest_interval_mins > 0 {
+        let interval = config.digest_interval_mins;
+        supervisor().spawn("digest", STOP_LAST, 5, move || async move {
+            digest::run(interval).await;
+            Ok(())
+        });
+        let journal_path = std::path::PathBuf::from(&config.trade_log_path);
+        supervisor().spawn("attribution", STOP_LAST, 5, move || {
+            let journal_path = journal_path.clone();
+            async move {
+                attribution::run_digest_note(journal_path, interval).await;
+                Ok(())
+            }
+        });
+    }
+    if profiling::enabled() {
+        Logger::info(
+            "Hot path profiling on: stage timings are under `hotpath` in /status and logged every 5 minutes",
+        );
+        status::register("hotpath", profiling::snapshot_json);
+        supervisor().spawn("hotpath-profiler", STOP_LAST, 5, || async {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(300)).await;
+                profiling::log_snapshot();
+            }
+        });
+    }
 
     Logger::info("Starting trade monitor...");
-    let _monitor_handle = run_trade_monitor(&config, &http_client, tx).await?;
+    let monitor = run_trade_monitor(
+        &config,
+        &http_client,
+        executor.balance_cache(),
+        tx,
+        CancellationToken::new(),
+    ).await?;
 
-    match signal::ctrl_c().await {
-        Ok(()) => {
-            Logger::separator();
-            Logger::info("Shutdown requested. Stopping…");
-        }
-        Err(_) => {}
+    if signal::ctrl_c().await.is_ok() {
+        Logger::separator();
+        Logger::info("Shutdown requested. Stopping…");
     }
 
-    stop_trade_monitor();
-    stop_trade_executor();
-    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+    // The monitor stops first; the executor then works through the trades already queued
+    // and its helpers wind down before the journal is flushed and the rest stops.
+    monitor.stop().await;
+    executor.stop().await;
+    if tokio::time::timeout(std::time::Duration::from_secs(5), flush_journal())
+        .await
+        .is_err()
+    {
+        Logger::warning("Timed out flushing the trade journal");
+    }
+    let dropped = journal_dropped_rows();
+    if dropped > 0 {
+        Logger::warning(&format!(
+            "{} low-priority journal rows were dropped while the buffer was full",
+            dropped
+        ));
+    }
+    match JournalDay::today(std::path::Path::new(&config.trade_log_path)) {
+        Ok(day) => Logger::info(&day.describe()),
+        Err(e) => Logger::warning(&format!("Could not read the trade journal: {}", e)),
+    }
+    let shutdown_event = event_webhook::emit_now(BotEvent::Shutdown {
+        reason: "signal".to_string(),
+    });
+    if tokio::time::timeout(std::time::Duration::from_secs(5), shutdown_event)
+        .await
+        .is_err()
+    {
+        Logger::warning("Timed out sending the shutdown event");
+    }
+    supervisor().shutdown().await;
+    handover::write(&config);
+    profiling::log_snapshot();
+    for task in supervisor().statuses() {
+        if task.status == TaskStatus::FailedPermanent {
+            Logger::warning(&format!(
+                "Task '{}' had failed permanently: {}",
+                task.name,
+                task.last_error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+    }
     Logger::success("Goodbye.");
     Ok(())
 }
@@ -0,0 +1,227 @@
+//! Copying a trader's existing position by hand, e.g. a market they entered before the bot
+//! was running: `POST /copy-position/<trader>/<condition_id>[/<fraction>]` on the status API.
+
+use anyhow::Result;
+
+use crate::config::{is_valid_ethereum_address, EnvConfig};
+use crate::executor::{execute_manual_copy, fetch_positions, CopyOutcome, ExecutorContext};
+use crate::status;
+use crate::types::{RtdsActivity, UserPosition};
+
+/// What to copy: the trader's position in `condition_id`, scaled by `fraction` (0, 1].
+#[derive(Debug, Clone)]
+pub struct ManualCopyRequest {
+    pub trader: String,
+    pub condition_id: String,
+    pub fraction: f64,
+}
+
+impl ManualCopyRequest {
+    /// Reads `<trader>/<condition_id>[/<fraction>]`; the whole position when no fraction is
+    /// given.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let usage = "expected /copy-position/<trader>/<condition_id>[/<fraction>]";
+        let parts: Vec<&str> = path.split('/').collect();
+        let fraction = match parts.get(2) {
+            None => 1.0,
+            Some(f) => f.parse().map_err(|_| format!("invalid fraction {}", f))?,
+        };
+        match parts[..] {
+            [trader, condition_id] | [trader, condition_id, _] => Ok(Self {
+                trader: trader.to_string(),
+                condition_id: condition_id.to_string(),
+                fraction,
+            }),
+            _ => Err(usage.to_string()),
+        }
+    }
+
+    /// Checks the inputs before anything is fetched. Only tracked traders can be copied, so
+    /// groups, sizing and exposure work as they do for live signals.
+    pub fn validate(&self, config: &EnvConfig) -> Result<(), String> {
+        if !is_valid_ethereum_address(&self.trader) {
+            return Err(format!("invalid trader address {}", self.trader));
+        }
+        if !config.user_addresses.contains(&self.trader.trim().to_lowercase()) {
+            return Err(format!("{} is not a tracked trader", self.trader));
+        }
+        let cid = self.condition_id.trim().trim_start_matches("0x");
+        if cid.len() != 64 || !cid.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid condition_id {}", self.condition_id));
+        }
+        if !self.fraction.is_finite() || self.fraction <= 0.0 || self.fraction > 1.0 {
+            return Err(format!("fraction {} outside (0, 1]", self.fraction));
+        }
+        Ok(())
+    }
+}
+
+/// A BUY signal as if `position`'s holder had just bought `fraction` of it at the current
+/// price. The transaction hash is synthetic so repeated requests aren't deduped.
+pub fn position_signal(
+    position: &UserPosition,
+    fraction: f64,
+    now: i64,
+) -> Result<RtdsActivity, String> {
+    let size = position.size.unwrap_or(0.0) * fraction;
+    if size <= 0.0 {
+        return Err("trader holds no shares in this market".to_string());
+    }
+    let price = position
+        .cur_price
+        .filter(|p| *p > 0.0 && *p < 1.0)
+        .ok_or("market has no tradable current price")?;
+    let condition_id = position.condition_id.clone().unwrap_or_default();
+    Ok(RtdsActivity {
+        proxy_wallet: position.proxy_wallet.clone(),
+        timestamp: Some(now),
+        activity_type: Some("TRADE".to_string()),
+        size: Some(size),
+        price: Some(price),
+        asset: position.asset.clone(),
+        side: Some("BUY".to_string()),
+        outcome_index: position.outcome_index,
+        title: position.title.clone(),
+        slug: position.slug.clone(),
+        icon: position.icon.clone(),
+        event_slug: position.event_slug.clone(),
+        outcome: position.outcome.clone(),
+        name: None,
+        transaction_hash: Some(format!("manual:{}:{}", condition_id, now)),
+        condition_id: Some(condition_id),
+        reported_usdc_size: None,
+        backfilled: false,
+    })
+}
+
+/// Fetches the trader's position and runs it through the normal copy pipeline; every skip
+/// rule and cap applies. When the trader holds both outcomes the larger one is copied.
+pub async fn copy_position(
+    ex: &ExecutorContext,
+    request: &ManualCopyRequest,
+) -> Result<Option<CopyOutcome>> {
+    request.validate(&ex.config).map_err(anyhow::Error::msg)?;
+    let trader = request.trader.trim().to_lowercase();
+    let condition_id = request.condition_id.trim().to_lowercase();
+    let positions = fetch_positions(&ex.http_client, &ex.config, &trader).await?;
+    let position = positions
+        .iter()
+        .filter(|p| {
+            p.condition_id.as_deref().map(str::to_lowercase).as_deref() == Some(&condition_id)
+                && p.size.unwrap_or(0.0) > 0.0
+        })
+        .max_by(|a, b| {
+            let (va, vb) = (a.current_value.unwrap_or(0.0), b.current_value.unwrap_or(0.0));
+            va.partial_cmp(&vb).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .ok_or_else(|| anyhow::anyhow!("{} holds no position in {}", trader, condition_id))?;
+    let signal = position_signal(position, request.fraction, chrono::Utc::now().timestamp())
+        .map_err(anyhow::Error::msg)?;
+    execute_manual_copy(ex, signal, trader).await
+}
+
+/// The status API's answer for a manual copy: the decision, and what the order filled.
+pub fn outcome_json(outcome: Option<&CopyOutcome>) -> serde_json::Value {
+    match outcome {
+        Some(o) => serde_json::json!({
+            "status": o.status,
+            "reason": o.reason,
+            "tokens": o.fill.tokens,
+            "usd": o.fill.usd,
+            "order_ids": o.fill.order_ids,
+        }),
+        None => serde_json::json!({ "status": "dropped" }),
+    }
+}
+
+/// Accepts manual copies on the status API, run by the executor behind `commands`.
+pub fn register(commands: ExecutorContext) {
+    status::register_async_action("copy-position", move |rest| {
+        let commands = commands.clone();
+        async move {
+            let request = ManualCopyRequest::parse(&rest)?;
+            let outcome = copy_position(&commands, &request)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(outcome_json(outcome.as_ref()))
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{test_config, UsdcSizePreference};
+
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+    const CONDITION: &str = "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1";
+
+    fn request(trader: &str, condition_id: &str, fraction: f64) -> ManualCopyRequest {
+        ManualCopyRequest {
+            trader: trader.to_string(),
+            condition_id: condition_id.to_string(),
+            fraction,
+        }
+    }
+
+    fn position(size: f64, cur_price: Option<f64>) -> UserPosition {
+        serde_json::from_value(serde_json::json!({
+            "proxyWallet": TRADER,
+            "asset": "123",
+            "conditionId": CONDITION,
+            "size": size,
+            "curPrice": cur_price,
+            "outcome": "Yes",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn request_validation() {
+        let config = test_config(&[]);
+        assert!(request(TRADER, CONDITION, 1.0).validate(&config).is_ok());
+        assert!(request(&TRADER.to_uppercase().replace("0X", "0x"), CONDITION, 0.5)
+            .validate(&config)
+            .is_ok());
+        let untracked = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+        assert!(request(untracked, CONDITION, 1.0).validate(&config).is_err());
+        assert!(request("0x123", CONDITION, 1.0).validate(&config).is_err());
+        assert!(request(TRADER, "0xabc", 1.0).validate(&config).is_err());
+        for fraction in [0.0, -0.5, 1.5, f64::NAN] {
+            assert!(request(TRADER, CONDITION, fraction).validate(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn paths_parse_with_an_optional_fraction() {
+        let whole = ManualCopyRequest::parse(&format!("{}/{}", TRADER, CONDITION)).unwrap();
+        assert_eq!((whole.trader.as_str(), whole.fraction), (TRADER, 1.0));
+        assert_eq!(whole.condition_id, CONDITION);
+        let half = ManualCopyRequest::parse(&format!("{}/{}/0.5", TRADER, CONDITION)).unwrap();
+        assert_eq!(half.fraction, 0.5);
+        assert!(ManualCopyRequest::parse(TRADER).is_err());
+        assert!(ManualCopyRequest::parse(&format!("{}/{}/half", TRADER, CONDITION)).is_err());
+        assert!(ManualCopyRequest::parse(&format!("{}/{}/1/x", TRADER, CONDITION)).is_err());
+    }
+
+    #[test]
+    fn signal_is_a_buy_of_the_scaled_position_at_the_current_price() {
+        let signal = position_signal(&position(200.0, Some(0.4)), 0.25, 1_760_000_000).unwrap();
+        assert_eq!(signal.side.as_deref(), Some("BUY"));
+        assert_eq!(signal.shares(), Ok(50.0));
+        let usd = signal.usd_value(UsdcSizePreference::Conservative).unwrap();
+        assert!((usd.chosen - 20.0).abs() < 1e-9);
+        assert!(signal.validate(1_760_000_000).is_ok());
+        assert_eq!(
+            signal.transaction_hash.as_deref(),
+            Some(format!("manual:{}:1760000000", CONDITION).as_str())
+        );
+    }
+
+    #[test]
+    fn empty_or_unpriced_positions_are_refused() {
+        assert!(position_signal(&position(0.0, Some(0.4)), 1.0, 0).is_err());
+        assert!(position_signal(&position(10.0, None), 1.0, 0).is_err());
+        assert!(position_signal(&position(10.0, Some(1.0)), 1.0, 0).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! Market overrides: my own instructions for single markets, keyed by condition id. A market
+//! can be set to never trade, to confirm every copy by hand, to a per-order cap that takes
+//! the place of `MAX_ORDER_SIZE_USD`, or just carry a note shown in the positions panel and
+//! the trade log. The `market_override` skip rule applies them ahead of the other rules.
+//! Edited with `market set` or `POST /market/<condition_id>/<key>/<value>` on the status
+//! endpoint, and kept in `STATE_DIR/market_overrides.json`.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config::{self, EnvConfig};
+use crate::status;
+use crate::status_api;
+use crate::utils::{load_json, save_json, state_path, Logger};
+
+const OVERRIDES_FILE: &str = "market_overrides.json";
+
+pub const USAGE: &str = "Usage: market [list]
+       market set <condition_id> never|confirm on|off
+       market set <condition_id> cap <usd>|off
+       market set <condition_id> note <text>   (an empty note removes it)
+       market clear <condition_id>";
+
+static REGISTRY: Mutex<Option<MarketOverrides>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketOverride {
+    /// Skip every signal in the market, BUY or SELL.
+    #[serde(default)]
+    pub never_trade: bool,
+    /// Per-order cap for the market, in place of `MAX_ORDER_SIZE_USD`.
+    #[serde(default)]
+    pub cap_usd: Option<f64>,
+    /// Skip live signals; only manual copies (`copy-now`) go through.
+    #[serde(default)]
+    pub confirm: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+impl MarketOverride {
+    fn is_empty(&self) -> bool {
+        !self.never_trade && self.cap_usd.is_none() && !self.confirm && self.note.is_none()
+    }
+
+    /// One line for listings, e.g. `never trade, cap $25.00, "thin book"`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.never_trade {
+            parts.push("never trade".to_string());
+        }
+        if self.confirm {
+            parts.push("confirm manually".to_string());
+        }
+        if let Some(cap) = self.cap_usd {
+            parts.push(format!("cap ${:.2}", cap));
+        }
+        if let Some(note) = &self.note {
+            parts.push(format!("\"{}\"", note));
+        }
+        parts.join(", ")
+    }
+}
+
+/// One change to a market's override.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    Never(bool),
+    Confirm(bool),
+    Cap(Option<f64>),
+    Note(Option<String>),
+}
+
+impl Setting {
+    pub fn parse(key: &str, value: &str) -> Result<Self, String> {
+        let switch = |value: &str| match value.to_lowercase().as_str() {
+            "on" | "true" | "yes" => Ok(true),
+            "off" | "false" | "no" => Ok(false),
+            other => Err(format!("expected on or off, got '{}'", other)),
+        };
+        match key {
+            "never" => switch(value).map(Setting::Never),
+            "confirm" => switch(value).map(Setting::Confirm),
+            "cap" if value.eq_ignore_ascii_case("off") => Ok(Setting::Cap(None)),
+            "cap" => match value.trim_start_matches('$').parse::<f64>() {
+                Ok(usd) if usd > 0.0 && usd.is_finite() => Ok(Setting::Cap(Some(usd))),
+                _ => Err(format!("cap must be a positive USD amount or off, got '{}'", value)),
+            },
+            "note" => {
+                let note = value.trim();
+                Ok(Setting::Note((!note.is_empty()).then(|| note.to_string())))
+            }
+            other => Err(format!(
+                "unknown setting '{}' (never, confirm, cap or note)",
+                other
+            )),
+        }
+    }
+}
+
+fn market_key(condition_id: &str) -> String {
+    condition_id.trim().to_lowercase()
+}
+
+#[derive(Debug, Default)]
+pub struct MarketOverrides {
+    markets: BTreeMap<String, MarketOverride>,
+    path: Option<PathBuf>,
+}
+
+impl MarketOverrides {
+    pub fn load(state_dir: &str) -> Self {
+        let path = state_path(state_dir, OVERRIDES_FILE);
+        Self {
+            markets: load_json(&path).unwrap_or_default(),
+            path: Some(path),
+        }
+    }
+
+    pub fn get(&self, condition_id: &str) -> Option<&MarketOverride> {
+        self.markets.get(&market_key(condition_id))
+    }
+
+    /// Applies `setting` and returns what the market is left with. An override with nothing
+    /// set is removed.
+    pub fn set(&mut self, condition_id: &str, setting: Setting, now: i64) -> MarketOverride {
+        let key = market_key(condition_id);
+        let entry = self.markets.entry(key.clone()).or_default();
+        match setting {
+            Setting::Never(on) => entry.never_trade = on,
+            Setting::Confirm(on) => entry.confirm = on,
+            Setting::Cap(cap) => entry.cap_usd = cap,
+            Setting::Note(note) => entry.note = note,
+        }
+        entry.updated_at = now;
+        let updated = entry.clone();
+        if updated.is_empty() {
+            self.markets.remove(&key);
+        }
+        self.save();
+        updated
+    }
+
+    pub fn clear(&mut self, condition_id: &str) -> bool {
+        let removed = self.markets.remove(&market_key(condition_id)).is_some();
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.markets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markets.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(&self.markets).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, &self.markets) {
+                Logger::warning(&format!("Failed to persist market overrides: {}", e));
+            }
+        }
+    }
+}
+
+/// The per-order cap for a market: its own cap when it has one, else `MAX_ORDER_SIZE_USD`.
+pub fn max_order_size(global_usd: f64, market: Option<&MarketOverride>) -> f64 {
+    market.and_then(|m| m.cap_usd).unwrap_or(global_usd)
+}
+
+fn with_registry<T>(f: impl FnOnce(&mut MarketOverrides) -> T) -> Option<T> {
+    let mut registry = REGISTRY.lock().ok()?;
+    Some(f(registry.get_or_insert_with(MarketOverrides::default)))
+}
+
+/// `<condition_id>/<key>/<value>` or `<condition_id>/clear`, with the value URL-encoded.
+fn apply_action(rest: &str, now: i64) -> Result<Value, String> {
+    let mut parts = rest.splitn(3, '/');
+    let condition_id = parts.next().filter(|c| !c.is_empty());
+    match (condition_id, parts.next(), parts.next()) {
+        (Some(id), Some("clear"), None) => {
+            let cleared = with_registry(|r| r.clear(id)).unwrap_or(false);
+            Ok(serde_json::json!({ "market": id, "cleared": cleared }))
+        }
+        (Some(id), Some(key), Some(value)) => {
+            let value: String = url::form_urlencoded::parse(value.as_bytes())
+                .map(|(k, _)| k.into_owned())
+                .collect();
+            let setting = Setting::parse(key, &value)?;
+            let updated = with_registry(|r| r.set(id, setting, now)).unwrap_or_default();
+            Ok(serde_json::json!({ "market": id, "override": updated }))
+        }
+        _ => Err("expected /market/<condition_id>/<key>/<value> or /market/<condition_id>/clear".to_string()),
+    }
+}
+
+/// Loads the saved overrides, lists them under `/status` and accepts edits on the status
+/// API. Returns how many markets have one.
+pub fn init(config: &EnvConfig) -> usize {
+    let overrides = MarketOverrides::load(&config.state_dir);
+    let count = overrides.len();
+    if count > 0 && !config.skip_rules.iter().any(|r| r == "market_override") {
+        Logger::warning(
+            "The market_override rule is left out of SKIP_RULES, so never-trade and confirm overrides are ignored",
+        );
+    }
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *registry = Some(overrides);
+    }
+    status::register("market_overrides", || {
+        with_registry(|r| r.to_json()).unwrap_or_default()
+    });
+    status::register_action("market", |rest| {
+        apply_action(rest, chrono::Utc::now().timestamp())
+    });
+    count
+}
+
+/// The override for `condition_id`, if it has one.
+pub fn get(condition_id: Option<&str>) -> Option<MarketOverride> {
+    let id = condition_id?;
+    with_registry(|r| r.get(id).cloned()).flatten()
+}
+
+pub fn note(condition_id: Option<&str>) -> Option<String> {
+    get(condition_id)?.note
+}
+
+fn format_time(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// Sends `path` to the running bot's status API. `None` when it isn't running.
+async fn post(config: &EnvConfig, path: &str) -> Option<Result<()>> {
+    let response = status_api::post_action(config, &format!("/market/{}", path)).await?;
+    let ok = response.status().is_success();
+    println!("{}", response.text().await.unwrap_or_default());
+    Some(if ok {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("change rejected"))
+    })
+}
+
+/// `market [list]` prints the overrides; `market set` and `market clear` edit them through
+/// the running bot's status API, or in the state file when it isn't running.
+pub async fn run(args: &[String]) -> Result<()> {
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    let config = EnvConfig::parse()?;
+    let now = chrono::Utc::now().timestamp();
+    match args.first().map(String::as_str) {
+        None | Some("list") => {
+            let overrides = MarketOverrides::load(&config.state_dir);
+            if overrides.is_empty() {
+                println!("No market overrides.");
+            }
+            for (id, o) in &overrides.markets {
+                println!("{}\n    {} (updated {})", id, o.describe(), format_time(o.updated_at));
+            }
+            Ok(())
+        }
+        Some("set") if args.len() >= 3 => {
+            let (id, key) = (&args[1], &args[2]);
+            let value = args[3..].join(" ");
+            let setting = Setting::parse(key, &value).map_err(|e| anyhow::anyhow!(e))?;
+            let encoded: String = url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+            if let Some(sent) = post(&config, &format!("{}/{}/{}", id, key, encoded)).await {
+                return sent;
+            }
+            let updated = MarketOverrides::load(&config.state_dir).set(id, setting, now);
+            let described = if updated.is_empty() {
+                "no override".to_string()
+            } else {
+                updated.describe()
+            };
+            println!("{}: {} (bot not running; saved to the state file)", id, described);
+            Ok(())
+        }
+        Some("clear") if args.len() == 2 => {
+            let id = &args[1];
+            if let Some(sent) = post(&config, &format!("{}/clear", id)).await {
+                return sent;
+            }
+            if !MarketOverrides::load(&config.state_dir).clear(id) {
+                anyhow::bail!("No override for {}", id);
+            }
+            println!("Cleared {} (bot not running; saved to the state file)", id);
+            Ok(())
+        }
+        _ => anyhow::bail!("{}", USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_parse_and_reject_nonsense() {
+        assert_eq!(Setting::parse("never", "on"), Ok(Setting::Never(true)));
+        assert_eq!(Setting::parse("cap", "$25"), Ok(Setting::Cap(Some(25.0))));
+        assert_eq!(Setting::parse("cap", "off"), Ok(Setting::Cap(None)));
+        assert_eq!(Setting::parse("note", "  "), Ok(Setting::Note(None)));
+        assert!(Setting::parse("cap", "-5").is_err());
+        assert!(Setting::parse("confirm", "maybe").is_err());
+        assert!(Setting::parse("size", "5").is_err());
+    }
+
+    #[test]
+    fn market_cap_takes_the_place_of_the_global_cap() {
+        let capped = MarketOverride {
+            cap_usd: Some(25.0),
+            ..Default::default()
+        };
+        assert_eq!(max_order_size(100.0, Some(&capped)), 25.0);
+        let raised = MarketOverride {
+            cap_usd: Some(250.0),
+            ..Default::default()
+        };
+        assert_eq!(max_order_size(100.0, Some(&raised)), 250.0);
+        let noted = MarketOverride {
+            note: Some("thin book".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(max_order_size(100.0, Some(&noted)), 100.0);
+        assert_eq!(max_order_size(100.0, None), 100.0);
+    }
+
+    #[test]
+    fn overrides_survive_a_restart_and_empty_ones_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let mut overrides = MarketOverrides::load(state_dir);
+        overrides.set("0xABC", Setting::Cap(Some(25.0)), 1);
+        overrides.set("0xabc", Setting::Note(Some("thin book".to_string())), 2);
+        overrides.set("0xdef", Setting::Never(true), 3);
+        overrides.set("0xdef", Setting::Never(false), 4);
+
+        let reloaded = MarketOverrides::load(state_dir);
+        assert_eq!(reloaded.len(), 1);
+        let market = reloaded.get("0xAbC").unwrap();
+        assert_eq!(market.cap_usd, Some(25.0));
+        assert_eq!(market.note.as_deref(), Some("thin book"));
+        assert_eq!(market.updated_at, 2);
+        assert_eq!(market.describe(), "cap $25.00, \"thin book\"");
+    }
+
+    #[test]
+    fn action_paths_decode_the_value() {
+        let id = "0xaction-test";
+        let out = apply_action(&format!("{}/note/wait+for%2Fthe%20debate", id), 5).unwrap();
+        assert_eq!(out["override"]["note"], "wait for/the debate");
+        assert_eq!(note(Some(id)).as_deref(), Some("wait for/the debate"));
+        assert!(apply_action(&format!("{}/cap/lots", id), 6).is_err());
+        assert!(apply_action(id, 6).is_err());
+        assert_eq!(apply_action(&format!("{}/clear", id), 7).unwrap()["cleared"], true);
+        assert!(get(Some(id)).is_none());
+    }
+}
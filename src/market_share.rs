@@ -0,0 +1,217 @@
+//! Market size figures from Gamma (volume, open interest, liquidity) and the
+//! `MAX_MARKET_SHARE_PERCENT` guard built on them: in a thin market a normal-sized copy can be
+//! a fifth of the open interest, which leaves no one to sell to later.
+
+use serde_json::Value;
+
+use crate::config::{EnvConfig, MarketShareConfig};
+use crate::metadata_cache;
+use crate::types::MarketMetadata;
+
+/// A market's size in USD, each figure `None` when Gamma doesn't report it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MarketFigures {
+    pub volume_usd: Option<f64>,
+    pub open_interest_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+}
+
+/// What `MAX_MARKET_SHARE_PERCENT` allows in one market.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShareLimit {
+    /// No open interest figure: the copy goes ahead uncapped.
+    Unknown,
+    /// Open interest is below `MIN_MARKET_OPEN_INTEREST_USD`: the BUY is skipped.
+    TooThin { open_interest_usd: f64 },
+    /// My whole position in the market may be at most this much.
+    Cap { max_position_usd: f64 },
+}
+
+impl ShareLimit {
+    pub fn skip_reason(&self, config: &MarketShareConfig) -> Option<String> {
+        match self {
+            ShareLimit::TooThin { open_interest_usd } => Some(format!(
+                "open interest ${:.0} below MIN_MARKET_OPEN_INTEREST_USD ${:.0}",
+                open_interest_usd, config.min_open_interest_usd
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Gamma sends these as numbers or numeric strings depending on the field and market.
+pub(crate) fn number(market: &Value, keys: &[&str]) -> Option<f64> {
+    keys.iter().find_map(|key| {
+        let v = market.get(*key)?;
+        v.as_f64()
+            .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+    })
+}
+
+pub fn parse_figures(market: &Value) -> MarketFigures {
+    MarketFigures {
+        volume_usd: number(market, &["volumeNum", "volume"]),
+        open_interest_usd: number(market, &["openInterest"]),
+        liquidity_usd: number(market, &["liquidityNum", "liquidity"]),
+    }
+}
+
+impl From<&MarketMetadata> for MarketFigures {
+    fn from(metadata: &MarketMetadata) -> Self {
+        Self {
+            volume_usd: metadata.volume_usd,
+            open_interest_usd: metadata.open_interest_usd,
+            liquidity_usd: metadata.liquidity_usd,
+        }
+    }
+}
+
+/// The market's figures, through the metadata cache. A failed lookup is not cached, so the
+/// next signal asks again.
+pub async fn market_figures(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    condition_id: &str,
+) -> Option<MarketFigures> {
+    let metadata = metadata_cache::lookup(http_client, config, condition_id)
+        .await
+        .ok()?;
+    Some(MarketFigures::from(&metadata))
+}
+
+/// Figures still cached, without a request.
+pub fn cached(condition_id: &str, now: i64) -> Option<MarketFigures> {
+    metadata_cache::shared()
+        .peek(condition_id, now)
+        .map(|m| MarketFigures::from(&m))
+}
+
+pub fn share_limit(config: &MarketShareConfig, figures: Option<&MarketFigures>) -> ShareLimit {
+    let Some(open_interest_usd) = figures.and_then(|f| f.open_interest_usd) else {
+        return ShareLimit::Unknown;
+    };
+    if open_interest_usd < config.min_open_interest_usd {
+        return ShareLimit::TooThin { open_interest_usd };
+    }
+    ShareLimit::Cap {
+        max_position_usd: open_interest_usd * config.max_share,
+    }
+}
+
+/// `position_usd` as a fraction of the market's open interest.
+pub fn share_of_open_interest(position_usd: f64, figures: &MarketFigures) -> Option<f64> {
+    figures
+        .open_interest_usd
+        .filter(|oi| *oi > 0.0)
+        .map(|oi| position_usd / oi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{calculate_order_size, test_config, SizingInputs};
+    use serde_json::json;
+
+    fn guard() -> MarketShareConfig {
+        MarketShareConfig {
+            max_share: 0.05,
+            min_open_interest_usd: 1_000.0,
+        }
+    }
+
+    fn figures(market: Value) -> MarketFigures {
+        parse_figures(&market)
+    }
+
+    #[test]
+    fn figures_parse_from_numbers_or_strings() {
+        let f = figures(json!({
+            "volumeNum": 52_000.5,
+            "openInterest": "8000",
+            "liquidity": "1250.75",
+        }));
+        assert_eq!(f.volume_usd, Some(52_000.5));
+        assert_eq!(f.open_interest_usd, Some(8_000.0));
+        assert_eq!(f.liquidity_usd, Some(1_250.75));
+        assert_eq!(
+            figures(json!({ "question": "?" })),
+            MarketFigures::default()
+        );
+    }
+
+    #[test]
+    fn limit_follows_open_interest() {
+        let cases = [
+            (
+                json!({ "openInterest": 500 }),
+                ShareLimit::TooThin {
+                    open_interest_usd: 500.0,
+                },
+            ),
+            (
+                json!({ "openInterest": 2_000 }),
+                ShareLimit::Cap {
+                    max_position_usd: 100.0,
+                },
+            ),
+            (
+                json!({ "openInterest": 1_000_000 }),
+                ShareLimit::Cap {
+                    max_position_usd: 50_000.0,
+                },
+            ),
+            (json!({ "volumeNum": 9_000 }), ShareLimit::Unknown),
+        ];
+        for (market, expected) in cases {
+            assert_eq!(share_limit(&guard(), Some(&figures(market))), expected);
+        }
+        assert_eq!(share_limit(&guard(), None), ShareLimit::Unknown);
+        assert!(ShareLimit::TooThin {
+            open_interest_usd: 500.0
+        }
+        .skip_reason(&guard())
+        .is_some());
+    }
+
+    #[test]
+    fn cap_trims_the_copy_and_is_recorded_as_the_binding_step() {
+        let mut strategy =
+            test_config(&[("COPY_STRATEGY", "FIXED"), ("COPY_SIZE", "50")]).copy_strategy_config;
+        strategy.max_order_size_usd = 1_000.0;
+        strategy.max_position_size_usd = None;
+
+        // $2,000 open interest at 5% allows a $100 position; $70 is already held.
+        let mut inputs = SizingInputs {
+            market_share_cap_usd: Some(100.0),
+            ..SizingInputs::default()
+        };
+        let calc = calculate_order_size(&strategy, &inputs, 500.0, 10_000.0, 70.0);
+        assert!((calc.final_amount - 30.0).abs() < 1e-9);
+        let binding: Vec<&str> = calc.steps[1..]
+            .iter()
+            .filter(|s| s.output_usd < s.input_usd)
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(binding, ["market_share_cap"]);
+
+        // Already at the cap: nothing left to buy.
+        inputs.market_share_cap_usd = Some(60.0);
+        let calc = calculate_order_size(&strategy, &inputs, 500.0, 10_000.0, 70.0);
+        assert!(calc.below_minimum);
+
+        // Missing open interest leaves the copy alone.
+        inputs.market_share_cap_usd = None;
+        let calc = calculate_order_size(&strategy, &inputs, 500.0, 10_000.0, 70.0);
+        assert_eq!(calc.final_amount, 50.0);
+    }
+
+    #[test]
+    fn share_is_position_over_open_interest() {
+        let f = figures(json!({ "openInterest": 400 }));
+        assert_eq!(share_of_open_interest(100.0, &f), Some(0.25));
+        assert_eq!(
+            share_of_open_interest(100.0, &MarketFigures::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! Read-through cache of `MarketMetadata` keyed by condition id, shared by everything that
+//! needs a market's end date, category, size figures or order parameters. Entries expire
+//! after a TTL, the least recently used go once the cache is full, and concurrent lookups of
+//! the same market share one fetch. The cache is saved to the state dir so a restart starts
+//! warm.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::config::EnvConfig;
+use crate::market_share::{number, parse_figures};
+use crate::order_templates;
+use crate::status;
+use crate::types::MarketMetadata;
+use crate::utils::{load_json, save_json, state_path, GammaClient, Logger};
+
+const METADATA_FILE: &str = "market_metadata.json";
+/// Open interest and end dates move slowly; ten minutes keeps the figures usable.
+pub const DEFAULT_TTL_SECS: i64 = 600;
+pub const DEFAULT_CAPACITY: usize = 2_000;
+
+static SHARED: OnceLock<MetadataCache> = OnceLock::new();
+
+/// Where a cache miss is fetched from.
+pub trait MetadataSource: Sync {
+    fn fetch(&self, condition_id: &str) -> impl Future<Output = Result<MarketMetadata>> + Send;
+}
+
+/// Gamma for the market record, the CLOB for the fee rate of its first token.
+pub struct GammaSource<'a> {
+    pub http_client: &'a reqwest::Client,
+    pub config: &'a EnvConfig,
+}
+
+impl MetadataSource for GammaSource<'_> {
+    async fn fetch(&self, condition_id: &str) -> Result<MarketMetadata> {
+        let mut metadata = GammaClient::from_config(self.config, self.http_client)
+            .market(condition_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No Gamma market for {}", condition_id))?;
+        if let Some(token) = metadata.token_ids.first() {
+            metadata.fee_rate_bps =
+                order_templates::fetch_fee_rate_bps(self.http_client, self.config, token)
+                    .await
+                    .ok();
+        }
+        Ok(metadata)
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    value
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// A Gamma market record. `clobTokenIds` arrives as a JSON-encoded string.
+pub fn parse_gamma(market: &Value) -> Option<MarketMetadata> {
+    let figures = parse_figures(market);
+    let token_ids = match market.get("clobTokenIds") {
+        Some(Value::String(raw)) => serde_json::from_str(raw).unwrap_or_default(),
+        Some(ids) => serde_json::from_value(ids.clone()).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    Some(MarketMetadata {
+        condition_id: text(market.get("conditionId"))?,
+        question: text(market.get("question")),
+        slug: text(market.get("slug")),
+        event_slug: text(market.pointer("/events/0/slug")),
+        category: text(market.get("category")),
+        end_date: text(market.get("endDate")),
+        closed: market
+            .get("closed")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        neg_risk: market
+            .get("negRisk")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        tick_size: number(market, &["orderPriceMinTickSize"]),
+        min_order_size: number(market, &["orderMinSize"]),
+        fee_rate_bps: None,
+        volume_usd: figures.volume_usd,
+        open_interest_usd: figures.open_interest_usd,
+        liquidity_usd: figures.liquidity_usd,
+        token_ids,
+    })
+}
+
+/// Hit, miss and fetch counts since start, for `/status`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub fetch_failures: u64,
+    pub evictions: u64,
+    pub fetch_ms_total: u64,
+    pub fetch_ms_max: u64,
+}
+
+impl CacheStats {
+    pub fn fetch_ms_average(&self) -> u64 {
+        self.fetch_ms_total.checked_div(self.misses).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    metadata: MarketMetadata,
+    fetched_at: i64,
+    /// Recency rank for eviction; not kept across restarts.
+    #[serde(skip)]
+    used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, Entry>,
+    clock: u64,
+}
+
+pub struct MetadataCache {
+    ttl_secs: i64,
+    capacity: usize,
+    path: Option<PathBuf>,
+    entries: Mutex<Entries>,
+    /// One lock per market being fetched; later lookups wait on it instead of fetching.
+    flights: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    stats: Mutex<CacheStats>,
+}
+
+impl MetadataCache {
+    pub fn new(ttl_secs: i64, capacity: usize) -> Self {
+        Self {
+            ttl_secs,
+            capacity: capacity.max(1),
+            path: None,
+            entries: Mutex::new(Entries::default()),
+            flights: Mutex::new(HashMap::new()),
+            stats: Mutex::new(CacheStats::default()),
+        }
+    }
+
+    /// A cache saved to `state_dir`, starting from what the last run saved.
+    pub fn load(state_dir: &str, ttl_secs: i64, capacity: usize) -> Self {
+        let mut cache = Self::new(ttl_secs, capacity);
+        let path = state_path(state_dir, METADATA_FILE);
+        let saved: HashMap<String, Entry> = load_json(&path).unwrap_or_default();
+        if let Ok(mut entries) = cache.entries.lock() {
+            entries.map = saved;
+        }
+        cache.path = Some(path);
+        cache
+    }
+
+    /// The market's metadata when cached within the TTL, without fetching.
+    pub fn peek(&self, condition_id: &str, now: i64) -> Option<MarketMetadata> {
+        let mut entries = self.entries.lock().ok()?;
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.map.get_mut(condition_id)?;
+        if now - entry.fetched_at >= self.ttl_secs {
+            return None;
+        }
+        entry.used = clock;
+        Some(entry.metadata.clone())
+    }
+
+    /// The market's metadata, fetched from `source` when it isn't cached or has expired.
+    /// A failed fetch is not cached, so the next lookup tries again.
+    pub async fn get<S: MetadataSource>(
+        &self,
+        source: &S,
+        condition_id: &str,
+    ) -> Result<MarketMetadata> {
+        let now = chrono::Utc::now().timestamp();
+        if let Some(metadata) = self.peek(condition_id, now) {
+            self.count(|s| s.hits += 1);
+            return Ok(metadata);
+        }
+        let flight = self
+            .flights
+            .lock()
+            .map_err(|_| anyhow::anyhow!("metadata cache poisoned"))?
+            .entry(condition_id.to_string())
+            .or_default()
+            .clone();
+        let _guard = flight.lock().await;
+        // Fetched by the lookup this one waited for.
+        if let Some(metadata) = self.peek(condition_id, now) {
+            self.count(|s| s.hits += 1);
+            return Ok(metadata);
+        }
+        let started = Instant::now();
+        let fetched = source.fetch(condition_id).await;
+        let ms = started.elapsed().as_millis() as u64;
+        self.count(|s| {
+            s.misses += 1;
+            s.fetch_ms_total += ms;
+            s.fetch_ms_max = s.fetch_ms_max.max(ms);
+        });
+        match &fetched {
+            Ok(metadata) => self.insert(
+                condition_id,
+                metadata.clone(),
+                chrono::Utc::now().timestamp(),
+            ),
+            Err(_) => self.count(|s| s.fetch_failures += 1),
+        }
+        // Cached before the flight ends, so a lookup arriving now finds it.
+        if let Ok(mut flights) = self.flights.lock() {
+            flights.remove(condition_id);
+        }
+        fetched
+    }
+
+    /// Stores `metadata` as fetched at `now`, evicting the least recently used market when
+    /// the cache is full.
+    pub fn insert(&self, condition_id: &str, metadata: MarketMetadata, now: i64) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.clock += 1;
+        let used = entries.clock;
+        entries.map.insert(
+            condition_id.to_string(),
+            Entry {
+                metadata,
+                fetched_at: now,
+                used,
+            },
+        );
+        let mut evicted = 0;
+        while entries.map.len() > self.capacity {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, e)| e.used)
+                .map(|(k, _)| k.clone());
+            match oldest {
+                Some(key) => entries.map.remove(&key),
+                None => break,
+            };
+            evicted += 1;
+        }
+        if evicted > 0 {
+            self.count(|s| s.evictions += evicted);
+        }
+        self.save(&entries.map);
+    }
+
+    /// Drops one market, e.g. after its tick size or neg-risk flag is seen to change.
+    pub fn invalidate(&self, condition_id: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.map.remove(condition_id).is_some() {
+                self.save(&entries.map);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.map.clear();
+            self.save(&entries.map);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.map.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats.lock().map(|s| *s).unwrap_or_default()
+    }
+
+    fn count(&self, update: impl FnOnce(&mut CacheStats)) {
+        if let Ok(mut stats) = self.stats.lock() {
+            update(&mut stats);
+        }
+    }
+
+    fn save(&self, map: &HashMap<String, Entry>) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, map) {
+                Logger::warning(&format!("Failed to persist market metadata: {}", e));
+            }
+        }
+    }
+}
+
+/// Loads the process-wide cache from `state_dir` and lists its stats under `/status`.
+/// Lookups before this run against an in-memory cache.
+pub fn init(state_dir: &str) {
+    let loaded = SHARED.set(MetadataCache::load(
+        state_dir,
+        DEFAULT_TTL_SECS,
+        DEFAULT_CAPACITY,
+    ));
+    if loaded.is_err() {
+        Logger::warning("Market metadata cache already initialized");
+    }
+    status::register("metadata_cache", || {
+        let cache = shared();
+        let stats = cache.stats();
+        serde_json::json!({
+            "markets": cache.len(),
+            "hits": stats.hits,
+            "misses": stats.misses,
+            "fetch_failures": stats.fetch_failures,
+            "evictions": stats.evictions,
+            "fetch_ms_avg": stats.fetch_ms_average(),
+            "fetch_ms_max": stats.fetch_ms_max,
+        })
+    });
+}
+
+/// The process-wide cache.
+pub fn shared() -> &'static MetadataCache {
+    SHARED.get_or_init(|| MetadataCache::new(DEFAULT_TTL_SECS, DEFAULT_CAPACITY))
+}
+
+/// Read-through lookup in the process-wide cache from Gamma and the CLOB.
+pub async fn lookup(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    condition_id: &str,
+) -> Result<MarketMetadata> {
+    let source = GammaSource {
+        http_client,
+        config,
+    };
+    shared().get(&source, condition_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    const BINARY: &str = r#"{
+        "id": "516710",
+        "question": "Will the Fed cut rates in December?",
+        "conditionId": "0x3b0ac1d5f7c7a5b2a1ff0e6b9b7c0c4d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b",
+        "slug": "fed-cuts-rates-in-december",
+        "endDate": "2025-12-10T12:00:00Z",
+        "category": "Economics",
+        "liquidity": "84210.55",
+        "volume": "1920331.2",
+        "volumeNum": 1920331.2,
+        "openInterest": 355120,
+        "outcomes": "[\"Yes\", \"No\"]",
+        "outcomePrices": "[\"0.62\", \"0.38\"]",
+        "clobTokenIds": "[\"7132104567925221259\", \"1029384756102938475\"]",
+        "closed": false,
+        "negRisk": false,
+        "orderPriceMinTickSize": 0.01,
+        "orderMinSize": 5,
+        "events": [{ "slug": "fed-decision-in-december" }]
+    }"#;
+
+    const NEG_RISK: &str = r#"{
+        "question": "Will Arsenal win the Premier League?",
+        "conditionId": "0x9c1e",
+        "slug": "will-arsenal-win-the-premier-league",
+        "endDate": "2026-05-24",
+        "liquidityNum": 40110.0,
+        "volumeNum": 8812004.0,
+        "clobTokenIds": "[\"5551\", \"5552\"]",
+        "negRisk": true,
+        "orderPriceMinTickSize": 0.01,
+        "orderMinSize": 5,
+        "events": [{ "slug": "premier-league-winner" }]
+    }"#;
+
+    const FINE_TICK: &str = r#"{
+        "question": "Will it snow in Miami on Christmas?",
+        "conditionId": "0x77aa",
+        "slug": "snow-in-miami-on-christmas",
+        "category": "Weather",
+        "endDate": "2025-12-26T05:00:00Z",
+        "openInterest": "1520.5",
+        "clobTokenIds": "[\"901\", \"902\"]",
+        "closed": false,
+        "orderPriceMinTickSize": 0.001,
+        "orderMinSize": 5
+    }"#;
+
+    fn fixture(raw: &str) -> MarketMetadata {
+        parse_gamma(&serde_json::from_str(raw).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn gamma_payloads_parse() {
+        let binary = fixture(BINARY);
+        assert_eq!(
+            binary.event_slug.as_deref(),
+            Some("fed-decision-in-december")
+        );
+        assert_eq!(binary.category.as_deref(), Some("Economics"));
+        assert_eq!(binary.open_interest_usd, Some(355_120.0));
+        assert_eq!(binary.liquidity_usd, Some(84_210.55));
+        assert_eq!(
+            binary.token_ids,
+            ["7132104567925221259", "1029384756102938475"]
+        );
+        assert!(!binary.neg_risk);
+
+        let neg_risk = fixture(NEG_RISK);
+        assert!(neg_risk.neg_risk);
+        assert_eq!(neg_risk.category, None);
+        assert_eq!(neg_risk.open_interest_usd, None);
+        assert_eq!(neg_risk.volume_usd, Some(8_812_004.0));
+
+        let fine = fixture(FINE_TICK);
+        assert_eq!(fine.tick_size, Some(0.001));
+        assert_eq!(fine.open_interest_usd, Some(1_520.5));
+        assert_eq!(fine.event_slug, None);
+
+        // Persisted entries round-trip through serde.
+        let saved = serde_json::to_string(&fine).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MarketMetadata>(&saved).unwrap(),
+            fine
+        );
+        assert!(parse_gamma(&serde_json::json!({ "question": "?" })).is_none());
+    }
+
+    /// Serves the fixtures after a delay, counting fetches.
+    struct SlowSource {
+        fetches: AtomicUsize,
+        fail: bool,
+    }
+
+    impl MetadataSource for SlowSource {
+        async fn fetch(&self, condition_id: &str) -> Result<MarketMetadata> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            if self.fail {
+                anyhow::bail!("gamma down");
+            }
+            Ok(MarketMetadata {
+                condition_id: condition_id.to_string(),
+                ..fixture(BINARY)
+            })
+        }
+    }
+
+    fn source(fail: bool) -> SlowSource {
+        SlowSource {
+            fetches: AtomicUsize::new(0),
+            fail,
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_lookups_share_one_fetch() {
+        let cache = MetadataCache::new(60, 10);
+        let source = source(false);
+        let lookups = (0..8).map(|_| cache.get(&source, "0xabc"));
+        let results = futures_util::future::join_all(lookups).await;
+        assert!(results
+            .iter()
+            .all(|r| r.as_ref().unwrap().condition_id == "0xabc"));
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 1);
+        let stats = cache.stats();
+        assert_eq!((stats.misses, stats.hits), (1, 7));
+
+        // Different markets still fetch side by side.
+        let (a, b) = tokio::join!(cache.get(&source, "0x1"), cache.get(&source, "0x2"));
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(source.fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn failures_are_not_cached() {
+        let cache = MetadataCache::new(60, 10);
+        let down = source(true);
+        assert!(cache.get(&down, "0xabc").await.is_err());
+        assert!(cache.get(&down, "0xabc").await.is_err());
+        assert_eq!(down.fetches.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.stats().fetch_failures, 2);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn entries_expire_and_the_least_recent_is_evicted() {
+        let cache = MetadataCache::new(60, 2);
+        let market = fixture(FINE_TICK);
+        cache.insert("a", market.clone(), 1_000);
+        cache.insert("b", market.clone(), 1_000);
+        assert!(cache.peek("a", 1_030).is_some());
+        // "b" is now the least recently used.
+        cache.insert("c", market.clone(), 1_000);
+        assert!(cache.peek("b", 1_030).is_none());
+        assert!(cache.peek("a", 1_030).is_some());
+        assert_eq!(cache.stats().evictions, 1);
+        assert!(cache.peek("a", 1_060).is_none());
+
+        cache.invalidate("c");
+        assert!(cache.peek("c", 1_030).is_none());
+    }
+
+    #[test]
+    fn a_restart_starts_warm() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        MetadataCache::load(state_dir, 60, 10).insert("0x77aa", fixture(FINE_TICK), now);
+
+        let reloaded = MetadataCache::load(state_dir, 60, 10);
+        assert_eq!(reloaded.peek("0x77aa", now), Some(fixture(FINE_TICK)));
+    }
+}
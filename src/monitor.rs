@@ -1,177 +1,314 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::net::TcpStream;
 use tokio::time::{sleep, Duration};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
-
-use crate::config::EnvConfig;
-use crate::types::{RtdsActivity, UserPosition};
-use crate::utils::{fetch_data, get_usdc_balance, Logger};
-
-const RTDS_URL: &str = "wss://ws-live-data.polymarket.com";
-const MAX_RECONNECT_ATTEMPTS: u32 = 10;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::aggregation;
+use crate::alerts;
+use crate::backfill::{self, StreamHistory};
+use crate::balance::{self, BalanceBreakdown};
+use crate::chaos::{Chaos, ChaosStream};
+use crate::classification::{refresh_trader_classes, trader_class};
+use crate::config::{EnvConfig, MonitorMode};
+use crate::inactivity::TraderActivityBook;
+use crate::ledger::PositionLedger;
+use crate::market_overrides;
+use crate::market_share;
+use crate::overflow::OverflowBook;
+use crate::position_builder;
+use crate::rtds_capture::{self, Capture, Frame};
+use crate::status;
+use crate::types::{BotEvent, RtdsActivity};
+use crate::supervisor::{supervisor, TaskHandle, STOP_FIRST};
+use crate::trader_history::has_no_history;
+use crate::trial::TrialBook;
+use crate::utils::{event_webhook, jitter_unit, BalanceCache, DataApiClient, Logger};
+
+/// Malformed payloads are written to `MALFORMED_LOG_PATH`; the first few are kept, then one
+/// in every N.
+const MALFORMED_ALWAYS_CAPTURE: u64 = 20;
+const MALFORMED_SAMPLE_EVERY: u64 = 50;
+
+static MALFORMED_COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// First reconnect delay; it doubles per failed attempt up to `RTDS_MAX_BACKOFF_SECS`.
 const RECONNECT_DELAY_SECS: u64 = 5;
+/// Each delay is stretched by up to this share, so restarted bots don't reconnect in step.
+const RECONNECT_JITTER: f64 = 0.2;
+/// A prolonged outage is logged as a warning every this many failed attempts.
+const RECONNECT_WARN_EVERY: u32 = 10;
+/// Supervisor restarts once the reconnect loop itself gives up.
+const MAX_TASK_RESTARTS: u32 = 5;
+/// While polling in `MONITOR_MODE=auto`, RTDS is tried again this often.
+const POLLING_RTDS_RETRY_SECS: u64 = 60;
+/// How far back a poll looks for an address with no trade seen yet (at least the backfill
+/// window).
+const POLL_LOOKBACK_SECS: i64 = 3600;
+/// How long `TradeMonitorHandle::stop` waits for the monitor task to return.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of activity payloads rejected as malformed since start.
+pub fn malformed_activity_count() -> u64 {
+    MALFORMED_COUNT.load(Ordering::Relaxed)
+}
 
-static RUNNING: AtomicBool = AtomicBool::new(true);
+/// Whether the `count`-th malformed payload is written to the capture file.
+fn should_capture(count: u64) -> bool {
+    count <= MALFORMED_ALWAYS_CAPTURE || count.is_multiple_of(MALFORMED_SAMPLE_EVERY)
+}
 
-pub fn stop_trade_monitor() {
-    RUNNING.store(false, Ordering::SeqCst);
-    Logger::info("Trade monitor shutdown requested...");
+fn record_malformed(path: &str, reason: &str, payload: &serde_json::Value) {
+    let count = MALFORMED_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+    Logger::warning(&format!(
+        "Ignoring malformed activity ({}) - {} rejected so far",
+        reason, count
+    ));
+    if !should_capture(count) {
+        return;
+    }
+    let line = json!({
+        "timestamp": chrono::Utc::now().timestamp(),
+        "reason": reason,
+        "payload": payload,
+    });
+    let path = std::path::Path::new(path);
+    let written = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| {
+            use std::io::Write;
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{}", line)
+        });
+    if let Err(e) = written {
+        Logger::warning(&format!("Failed to capture malformed payload: {}", e));
+    }
 }
 
+/// The running monitor; `stop` ends it.
 pub struct TradeMonitorHandle {
-    _tx: broadcast::Sender<()>,
+    shutdown: CancellationToken,
+    /// The RTDS connection (or replay) task.
+    task: TaskHandle,
+}
+
+impl TradeMonitorHandle {
+    /// Stops taking in trades and waits for the monitor task: open connections, reconnect
+    /// waits and polls end right away, which drops the executor's senders.
+    pub async fn stop(self) {
+        supervisor().begin_shutdown();
+        self.shutdown.cancel();
+        Logger::info("Trade monitor shutdown requested...");
+        if !self.task.join(STOP_TIMEOUT).await {
+            Logger::warning("Trade monitor did not stop in time; cancelling it");
+        }
+    }
+}
+
+/// Sleeps for `duration`, or less if `shutdown` is cancelled meanwhile. False when it was.
+async fn sleep_unless_stopped(shutdown: &CancellationToken, duration: Duration) -> bool {
+    tokio::select! {
+        _ = shutdown.cancelled() => false,
+        _ = sleep(duration) => true,
+    }
 }
 
 async fn init(
     config: &EnvConfig,
     http_client: &reqwest::Client,
+    balance_cache: &BalanceCache,
 ) -> Result<()> {
-    let my_positions_url = format!(
-        "https://data-api.polymarket.com/positions?user={}",
-        config.proxy_wallet
+    let data_api = DataApiClient::from_config(config, http_client);
+    let current_balance = balance_cache.get().await.map(|b| b.usd).unwrap_or(0.0);
+    let balance = BalanceBreakdown::new(
+        current_balance,
+        balance::locked_in_orders(&config.state_dir),
+        config.balance_reserve_usd,
     );
-    let current_balance = get_usdc_balance(
-        &config.rpc_url,
-        &config.usdc_contract_address,
-        &config.proxy_wallet,
-    )
-    .await
-    .unwrap_or(0.0);
-
-    match fetch_data(
-        http_client,
-        &my_positions_url,
-        config.request_timeout_ms,
-        config.network_retry_limit,
-    )
-    .await
-    {
-        Ok(data) => {
-            if let Some(arr) = data.as_array() {
-                let mut total_value = 0.0;
-                let mut initial_value = 0.0;
-                let mut weighted_pnl = 0.0;
-                for pos in arr.iter() {
-                    let value = pos
-                        .get("currentValue")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let initial = pos
-                        .get("initialValue")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let pnl = pos
-                        .get("percentPnl")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    total_value += value;
-                    initial_value += initial;
-                    weighted_pnl += value * pnl;
-                }
-                let my_overall_pnl = if total_value > 0.0 {
-                    weighted_pnl / total_value
-                } else {
-                    0.0
-                };
 
-                let mut top_positions = arr.clone();
-                top_positions.sort_by(|a, b| {
-                    let pnl_a = a
-                        .get("percentPnl")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let pnl_b = b
-                        .get("percentPnl")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    pnl_b.partial_cmp(&pnl_a).unwrap_or(std::cmp::Ordering::Equal)
-                });
-                let top_positions: Vec<_> = top_positions.iter().take(5).cloned().collect();
-
-                Logger::clear_line();
-                Logger::my_positions(
-                    &config.proxy_wallet,
-                    arr.len(),
-                    &top_positions,
-                    my_overall_pnl,
-                    total_value,
-                    initial_value,
-                    current_balance,
-                );
+    match data_api.get_positions(&config.proxy_wallet).await {
+        Ok(all) => {
+            // Positions written off as dust are summarised on one line instead of listed.
+            let ledger = PositionLedger::load(&config.state_dir);
+            let (dust, arr): (Vec<_>, Vec<_>) = all.into_iter().partition(|p| {
+                p.asset.as_deref().is_some_and(|a| ledger.is_dust(a))
+            });
+            let mut total_value = 0.0;
+            let mut initial_value = 0.0;
+            let mut weighted_pnl = 0.0;
+            for pos in arr.iter() {
+                let value = pos.current_value.unwrap_or(0.0);
+                let initial = pos.initial_value.unwrap_or(0.0);
+                let pnl = pos.percent_pnl.unwrap_or(0.0);
+                total_value += value;
+                initial_value += initial;
+                weighted_pnl += value * pnl;
+            }
+            let my_overall_pnl = if total_value > 0.0 {
+                weighted_pnl / total_value
             } else {
-                Logger::clear_line();
-                Logger::my_positions(
-                    &config.proxy_wallet,
-                    0,
-                    &[],
-                    0.0,
-                    0.0,
-                    0.0,
-                    current_balance,
+                0.0
+            };
+
+            let mut top_positions = arr.clone();
+            top_positions.sort_by(|a, b| {
+                let pnl_a = a.percent_pnl.unwrap_or(0.0);
+                let pnl_b = b.percent_pnl.unwrap_or(0.0);
+                pnl_b.partial_cmp(&pnl_a).unwrap_or(std::cmp::Ordering::Equal)
+            });
+            let top_positions: Vec<_> = top_positions.iter().take(5).cloned().collect();
+
+            Logger::clear_line();
+            Logger::my_positions(
+                &config.proxy_wallet,
+                arr.len(),
+                &top_positions,
+                my_overall_pnl,
+                total_value,
+                initial_value,
+                &balance,
+            );
+            let overflow = OverflowBook::load(&config.state_dir);
+            let mut capped: Vec<_> = overflow.markets().collect();
+            capped.sort_by(|a, b| {
+                b.1.ignored_trader_usd
+                    .partial_cmp(&a.1.ignored_trader_usd)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            for (cid, market) in &capped {
+                Logger::capped_line(
+                    market.title.as_deref().unwrap_or(cid),
+                    market.ignored_trader_usd,
                 );
             }
+            if !capped.is_empty() {
+                Logger::blank();
+            }
+            let mut noted = false;
+            for pos in &arr {
+                let cid = pos.condition_id.as_deref();
+                if let Some(note) = market_overrides::note(cid) {
+                    let title = pos.title.as_deref();
+                    Logger::market_note_line(title.or(cid).unwrap_or_default(), &note);
+                    noted = true;
+                }
+            }
+            if noted {
+                Logger::blank();
+            }
+            if let Some(ms) = config.market_share() {
+                let mut shares = Vec::new();
+                for pos in &arr {
+                    let Some(cid) = pos.condition_id.as_deref() else {
+                        continue;
+                    };
+                    let value = pos.current_value.unwrap_or(0.0);
+                    let figures = market_share::market_figures(http_client, config, cid).await;
+                    let title = pos.title.as_deref().unwrap_or(cid).to_string();
+                    let share = figures
+                        .as_ref()
+                        .and_then(|f| market_share::share_of_open_interest(value, f));
+                    shares.push((title, share));
+                }
+                shares.sort_by(|a, b| b.1.unwrap_or(-1.0).total_cmp(&a.1.unwrap_or(-1.0)));
+                for (title, share) in &shares {
+                    Logger::market_share_line(title, *share, ms.max_share);
+                }
+                if !shares.is_empty() {
+                    Logger::blank();
+                }
+            }
+            if !dust.is_empty() {
+                let dust_value: f64 = dust.iter().filter_map(|p| p.current_value).sum();
+                Logger::dust_line(dust.len(), dust_value);
+            }
         }
         Err(e) => {
             Logger::error(&format!("Failed to fetch your positions: {}", e));
         }
     }
+    let builds = position_builder::all(&config.state_dir);
+    for build in &builds {
+        let title = format!(
+            "{} ({})",
+            build.title.as_deref().unwrap_or(&build.asset),
+            build.outcome.as_deref().unwrap_or("?")
+        );
+        let ended = build.end_reason.map(|r| r.describe());
+        Logger::build_line(&title, &build.progress(), ended.as_deref());
+    }
+    if !builds.is_empty() {
+        Logger::blank();
+    }
+
+    refresh_trader_classes(config, http_client).await;
+    aggregation::refresh(config);
+    let activity = TraderActivityBook::load(&config.state_dir);
+    let trials = TrialBook::load(&config.state_dir);
+    let now = chrono::Utc::now().timestamp();
+    let labels: Vec<String> = config
+        .user_addresses
+        .iter()
+        .map(|a| {
+            let trader = config.trader_id(a);
+            let class = trader_class(a).map(|c| c.label().to_string());
+            let idle = activity
+                .days_since_last_trade(&trader, now)
+                .map(|days| format!("last trade {:.0}d ago", days));
+            let trial = trials
+                .get(&trader)
+                .map(|t| t.label(now))
+                .filter(|l| !l.is_empty());
+            let missing = has_no_history(a).then(|| "no history - typo?".to_string());
+            let window = aggregation::learned_window(&trader).map(|w| format!("{}s window", w));
+            [missing, class, idle, trial, window]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" · ")
+        })
+        .collect();
 
     let mut position_counts = Vec::new();
     let mut position_details = Vec::new();
     let mut profitabilities = Vec::new();
     for addr in &config.user_addresses {
-        match fetch_data(
-            http_client,
-            &format!("https://data-api.polymarket.com/positions?user={}", addr),
-            config.request_timeout_ms,
-            config.network_retry_limit,
-        )
-        .await
-        {
-            Ok(data) => {
-                if let Some(arr) = data.as_array() {
-                    let positions: Vec<UserPosition> = arr
-                        .iter()
-                        .filter_map(|p| serde_json::from_value::<UserPosition>(p.clone()).ok())
-                        .collect();
-                    position_counts.push(positions.len());
-
-                    let mut total_value = 0.0;
-                    let mut weighted_pnl = 0.0;
-                    for pos in &positions {
-                        let value = pos.current_value.unwrap_or(0.0);
-                        let pnl = pos.percent_pnl.unwrap_or(0.0);
-                        total_value += value;
-                        weighted_pnl += value * pnl;
-                    }
-                    let overall_pnl = if total_value > 0.0 {
-                        weighted_pnl / total_value
-                    } else {
-                        0.0
-                    };
-                    profitabilities.push(overall_pnl);
+        match data_api.get_positions(addr).await {
+            Ok(positions) => {
+                position_counts.push(positions.len());
 
-                    let mut sorted_positions = positions.clone();
-                    sorted_positions.sort_by(|a, b| {
-                        let pnl_a = a.percent_pnl.unwrap_or(0.0);
-                        let pnl_b = b.percent_pnl.unwrap_or(0.0);
-                        pnl_b.partial_cmp(&pnl_a).unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                    let top_positions: Vec<serde_json::Value> = sorted_positions
-                        .iter()
-                        .take(3)
-                        .filter_map(|p| serde_json::to_value(p).ok())
-                        .collect();
-                    position_details.push(top_positions);
-                } else {
-                    position_counts.push(0);
-                    position_details.push(Vec::new());
-                    profitabilities.push(0.0);
+                let mut total_value = 0.0;
+                let mut weighted_pnl = 0.0;
+                for pos in &positions {
+                    let value = pos.current_value.unwrap_or(0.0);
+                    let pnl = pos.percent_pnl.unwrap_or(0.0);
+                    total_value += value;
+                    weighted_pnl += value * pnl;
                 }
+                let overall_pnl = if total_value > 0.0 {
+                    weighted_pnl / total_value
+                } else {
+                    0.0
+                };
+                profitabilities.push(overall_pnl);
+
+                let mut sorted_positions = positions;
+                sorted_positions.sort_by(|a, b| {
+                    let pnl_a = a.percent_pnl.unwrap_or(0.0);
+                    let pnl_b = b.percent_pnl.unwrap_or(0.0);
+                    pnl_b.partial_cmp(&pnl_a).unwrap_or(std::cmp::Ordering::Equal)
+                });
+                sorted_positions.truncate(3);
+                position_details.push(sorted_positions);
             }
             Err(_) => {
                 position_counts.push(0);
@@ -186,92 +323,344 @@ async fn init(
         &position_counts,
         &position_details,
         &profitabilities,
+        &labels,
     );
 
     Ok(())
 }
 
+/// What one RTDS frame amounts to.
+pub enum Route {
+    Subscribed,
+    /// A trade by a tracked address, with that address lowercased.
+    Trade(Box<RtdsActivity>, String),
+    Ignored,
+}
+
+/// Sorts a parsed frame; trades that fail validation at `now_secs` are recorded to
+/// `malformed_log_path` and ignored. Shared by the live connection and replay.
+pub fn route_frame(
+    parsed: &serde_json::Value,
+    user_addresses: &[String],
+    now_secs: i64,
+    malformed_log_path: &str,
+) -> Route {
+    if parsed.get("action").and_then(|a| a.as_str()) == Some("subscribed")
+        || parsed.get("status").and_then(|s| s.as_str()) == Some("subscribed")
+    {
+        return Route::Subscribed;
+    }
+    if parsed.get("topic").and_then(|t| t.as_str()) != Some("activity")
+        || parsed.get("type").and_then(|t| t.as_str()) != Some("trades")
+    {
+        return Route::Ignored;
+    }
+    let Some(payload) = parsed.get("payload") else {
+        return Route::Ignored;
+    };
+    let Ok(activity) = serde_json::from_value::<RtdsActivity>(payload.clone()) else {
+        return Route::Ignored;
+    };
+    let proxy = activity
+        .proxy_wallet
+        .as_deref()
+        .unwrap_or("")
+        .to_lowercase();
+    if !user_addresses.iter().any(|a| a.to_lowercase() == proxy) {
+        return Route::Ignored;
+    }
+    if let Err(reason) = activity.validate(now_secs) {
+        record_malformed(malformed_log_path, &reason, payload);
+        return Route::Ignored;
+    }
+    Route::Trade(Box::new(activity), proxy)
+}
 
-async fn connect_rtds(
-    config: Arc<EnvConfig>,
-    tx: tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
-    reconnect_attempts: Arc<std::sync::atomic::AtomicU32>,
-) -> Result<()> {
-    loop {
-        if !RUNNING.load(Ordering::SeqCst) {
+/// Feeds captured frames to the executor channel with their original spacing (divided by
+/// `speed`). Trade timestamps are moved so the first frame lands at `started_at_ms`, and
+/// validation runs at each frame's replayed receive time. Returns the trades sent.
+pub async fn replay_frames(
+    frames: &[Frame],
+    speed: f64,
+    started_at_ms: i64,
+    user_addresses: &[String],
+    malformed_log_path: &str,
+    tx: &tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+    shutdown: &CancellationToken,
+) -> usize {
+    let offset_ms = frames
+        .first()
+        .map(|f| started_at_ms - f.received_at_ms)
+        .unwrap_or(0);
+    let mut sent = 0;
+    for (frame, delay) in frames.iter().zip(rtds_capture::schedule(frames, speed)) {
+        if !sleep_unless_stopped(shutdown, delay).await {
             break;
         }
+        let Ok(mut parsed) = serde_json::from_str::<serde_json::Value>(&frame.frame) else {
+            continue;
+        };
+        rtds_capture::rebase(&mut parsed, offset_ms);
+        let now_secs = (frame.received_at_ms + offset_ms) / 1000;
+        let route = route_frame(&parsed, user_addresses, now_secs, malformed_log_path);
+        if let Route::Trade(activity, proxy) = route {
+            if tx.send((*activity, proxy)).await.is_err() {
+                break;
+            }
+            sent += 1;
+        }
+    }
+    sent
+}
 
-        Logger::info(&format!("Connecting to RTDS at {}...", RTDS_URL));
+/// What the monitor does after `failed` RTDS connection attempts in a row.
+#[derive(Debug, PartialEq, Eq)]
+enum NextStep {
+    /// Try RTDS again after this many seconds.
+    Reconnect(u64),
+    /// Poll the data API for a while, then try RTDS again.
+    Poll,
+    /// Stop; the supervisor may restart the monitor.
+    GiveUp,
+}
 
-        match connect_async(RTDS_URL).await {
-            Ok((ws_stream, _)) => {
-                Logger::success("RTDS WebSocket connected");
-                reconnect_attempts.store(0, Ordering::SeqCst);
+fn after_failures(config: &EnvConfig, failed: u32) -> NextStep {
+    let mode = config.monitor_mode;
+    if mode != MonitorMode::Websocket && failed >= config.monitor_fallback_attempts {
+        return NextStep::Poll;
+    }
+    let limit = config.rtds_max_reconnect_attempts;
+    if mode == MonitorMode::Websocket && limit > 0 && failed >= limit {
+        return NextStep::GiveUp;
+    }
+    NextStep::Reconnect(backoff_secs(failed, config.rtds_max_backoff_secs))
+}
 
-                let (mut write, mut read) = ws_stream.split();
+/// `RECONNECT_DELAY_SECS` doubled for every failed attempt after the first, capped at
+/// `max_secs`.
+fn backoff_secs(failed: u32, max_secs: u64) -> u64 {
+    let doublings = failed.saturating_sub(1).min(32);
+    RECONNECT_DELAY_SECS
+        .saturating_mul(1u64 << doublings)
+        .min(max_secs)
+}
 
-                let subscriptions: Vec<_> = config
-                    .user_addresses
-                    .iter()
-                    .map(|_| json!({"topic": "activity", "type": "trades"}))
-                    .collect();
+/// `secs` stretched by `RECONNECT_JITTER` times `unit` (in `[0, 1)`).
+fn with_jitter(secs: u64, unit: f64) -> Duration {
+    Duration::from_secs_f64(secs as f64 * (1.0 + RECONNECT_JITTER * unit.clamp(0.0, 1.0)))
+}
 
-                let subscribe_message = json!({
-                    "action": "subscribe",
-                    "subscriptions": subscriptions
-                });
+/// The subscribe frame: `template` (`RTDS_SUBSCRIPTION_TEMPLATE`) once per address, so RTDS
+/// sends only those wallets' trades. `route_frame` still checks the wallet of each.
+pub fn subscribe_message(addresses: &[String], template: &str) -> Result<serde_json::Value> {
+    let subscriptions = addresses
+        .iter()
+        .map(|address| {
+            serde_json::from_str(&template.replace("{address}", address))
+                .map_err(|e| anyhow::anyhow!("Invalid RTDS_SUBSCRIPTION_TEMPLATE: {}", e))
+        })
+        .collect::<Result<Vec<serde_json::Value>>>()?;
+    Ok(json!({
+        "action": "subscribe",
+        "subscriptions": subscriptions
+    }))
+}
+
+/// True once nothing has arrived on the connection for `stale_secs` (0 never goes stale).
+fn is_stale(last_seen_ms: i64, now_ms: i64, stale_secs: u64) -> bool {
+    stale_secs > 0 && now_ms - last_seen_ms > stale_secs as i64 * 1000
+}
+
+/// Owns the write half once subscribed: sends a Ping every `ping_secs` and returns when the
+/// connection has gone stale, which ends it like a close would. 0 turns either off.
+async fn keep_alive<W>(
+    mut write: W,
+    last_seen: Arc<AtomicI64>,
+    ping_secs: u64,
+    stale_secs: u64,
+    shutdown: CancellationToken,
+) where
+    W: futures_util::Sink<Message> + Unpin,
+    W::Error: std::fmt::Display,
+{
+    let ping_every = Duration::from_secs(ping_secs);
+    let mut next_ping = tokio::time::Instant::now() + ping_every;
+    while sleep_unless_stopped(&shutdown, Duration::from_secs(1)).await {
+        let now = chrono::Utc::now().timestamp_millis();
+        if is_stale(last_seen.load(Ordering::SeqCst), now, stale_secs) {
+            Logger::warning(&format!(
+                "RTDS stale connection: nothing received for over {}s - reconnecting",
+                stale_secs
+            ));
+            return;
+        }
+        if ping_secs > 0 && tokio::time::Instant::now() >= next_ping {
+            if let Err(e) = write.send(Message::Ping(Vec::new())).await {
+                Logger::error(&format!("RTDS ping failed: {}", e));
+                return;
+            }
+            next_ping = tokio::time::Instant::now() + ping_every;
+        }
+    }
+}
+
+fn publish_mode(mode: &str) {
+    status::publish("monitor", json!({ "mode": mode }));
+}
+
+/// Polls every tracked address's activity every `FETCH_INTERVAL` seconds and sends the trades
+/// not seen before to the executor, until shutdown or until `limit` has passed.
+async fn poll_activity(
+    config: &EnvConfig,
+    http_client: &reqwest::Client,
+    history: &Arc<tokio::sync::Mutex<StreamHistory>>,
+    tx: &tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+    limit: Option<Duration>,
+    shutdown: &CancellationToken,
+) {
+    let deadline = limit.map(|d| tokio::time::Instant::now() + d);
+    let interval = Duration::from_secs(config.fetch_interval_secs.max(1));
+    let window = POLL_LOOKBACK_SECS.max(config.max_backfill_minutes as i64 * 60);
+    history
+        .lock()
+        .await
+        .connected(chrono::Utc::now().timestamp());
+    while !shutdown.is_cancelled() && deadline.is_none_or(|d| tokio::time::Instant::now() < d) {
+        let now = chrono::Utc::now().timestamp();
+        backfill::send_missed(config, http_client, history, window, now, false, tx).await;
+        if tx.is_closed() || !sleep_unless_stopped(shutdown, interval).await {
+            break;
+        }
+    }
+}
 
-                if let Err(e) = write.send(Message::Text(subscribe_message.to_string())).await {
-                    Logger::error(&format!("Failed to send subscription: {}", e));
-                    continue;
+async fn connect_rtds(
+    config: Arc<EnvConfig>,
+    http_client: reqwest::Client,
+    history: Arc<tokio::sync::Mutex<StreamHistory>>,
+    tx: tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+    chaos: Option<Arc<Chaos>>,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let max_backfill_secs = config.max_backfill_minutes as i64 * 60;
+    if config.monitor_mode == MonitorMode::Polling {
+        Logger::info(&format!(
+            "MONITOR_MODE=polling - polling trader activity every {}s instead of RTDS",
+            config.fetch_interval_secs.max(1)
+        ));
+        publish_mode("polling");
+        poll_activity(&config, &http_client, &history, &tx, None, &shutdown).await;
+        return Ok(());
+    }
+    let subscribe_message =
+        subscribe_message(&config.user_addresses, &config.rtds_subscription_template)?;
+    // Per supervised start: a restart gets the full reconnect budget again.
+    let mut reconnect_attempts: u32 = 0;
+    let mut down_since: Option<i64> = None;
+    let mut polling = false;
+    while !shutdown.is_cancelled() {
+        Logger::info(&format!("Connecting to RTDS at {}...", config.rtds_url));
+
+        let connected = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            connected = connect_and_subscribe(&config.rtds_url, &subscribe_message) => connected,
+        };
+        match connected {
+            Ok(ws_stream) => {
+                if let Some(since) = down_since.take() {
+                    event_webhook::emit(BotEvent::MonitorReconnected {
+                        attempts: reconnect_attempts,
+                        down_secs: chrono::Utc::now().timestamp() - since,
+                    });
+                }
+                reconnect_attempts = 0;
+                if polling {
+                    Logger::success("RTDS reachable again - switching from polling back to RTDS");
+                    polling = false;
                 }
+                publish_mode("websocket");
+
+                let (write, read) = ws_stream.split();
+                let mut read = ChaosStream::new(read, chaos.clone());
 
                 Logger::success(&format!(
                     "Subscribed to RTDS for {} trader(s) - monitoring trades in real-time",
                     config.user_addresses.len()
                 ));
 
+                // Dropped with the connection: a backfill cut short by another disconnect
+                // starts over from the same point on the next reconnect.
+                let mut backfill_task = tokio::task::JoinSet::new();
+                let connected_at = chrono::Utc::now().timestamp();
+                if history.lock().await.connected(connected_at) && max_backfill_secs > 0 {
+                    let (config, http_client, history, tx) =
+                        (config.clone(), http_client.clone(), history.clone(), tx.clone());
+                    backfill_task.spawn(async move {
+                        backfill::run(&config, &http_client, &history, connected_at, &tx).await;
+                    });
+                }
+
                 let config_msg = config.clone();
+                let history_msg = history.clone();
                 let tx_msg = tx.clone();
-                let message_task = tokio::spawn(async move {
-                    while RUNNING.load(Ordering::SeqCst) {
-                        match read.next().await {
+                let shutdown_msg = shutdown.clone();
+                let last_seen = Arc::new(AtomicI64::new(chrono::Utc::now().timestamp_millis()));
+                let last_seen_msg = last_seen.clone();
+                // A JoinSet aborts the reader when this task is cancelled, which drops its
+                // sender so the executor can drain on shutdown. Whichever of the reader and the
+                // keep-alive ends first ends the connection.
+                let mut message_task = tokio::task::JoinSet::new();
+                let (ping_secs, stale_secs) = (config.rtds_ping_interval_secs, config.rtds_stale_secs);
+                if ping_secs > 0 || stale_secs > 0 {
+                    message_task.spawn(keep_alive(
+                        write,
+                        last_seen,
+                        ping_secs,
+                        stale_secs,
+                        shutdown.clone(),
+                    ));
+                }
+                message_task.spawn(async move {
+                    let mut capture = config_msg.capture_rtds_to.as_deref().and_then(|path| {
+                        Capture::open(path)
+                            .map_err(|e| Logger::warning(&format!("{:#} - not capturing", e)))
+                            .ok()
+                    });
+                    loop {
+                        let frame = tokio::select! {
+                            _ = shutdown_msg.cancelled() => break,
+                            frame = read.next() => frame,
+                        };
+                        if let Some(Ok(_)) = frame {
+                            last_seen_msg.store(chrono::Utc::now().timestamp_millis(), Ordering::SeqCst);
+                        }
+                        match frame {
                             Some(Ok(Message::Text(t))) => {
+                                let now = chrono::Utc::now();
+                                if let Some(capture) = &mut capture {
+                                    capture.record(&t, now.timestamp_millis());
+                                }
                                 if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&t) {
-                                    if parsed.get("action").and_then(|a| a.as_str()) == Some("subscribed")
-                                        || parsed.get("status").and_then(|s| s.as_str()) == Some("subscribed")
-                                    {
-                                        Logger::info("RTDS subscription confirmed");
-                                        continue;
-                                    }
-
-                                    if parsed.get("topic").and_then(|t| t.as_str()) == Some("activity")
-                                        && parsed.get("type").and_then(|t| t.as_str()) == Some("trades")
-                                    {
-                                        if let Some(payload) = parsed.get("payload") {
-                                            if let Ok(activity) =
-                                                serde_json::from_value::<RtdsActivity>(payload.clone())
-                                            {
-                                                let proxy = activity
-                                                    .proxy_wallet
-                                                    .as_deref()
-                                                    .unwrap_or("")
-                                                    .to_lowercase();
-                                                if config_msg
-                                                    .user_addresses
-                                                    .iter()
-                                                    .any(|a| a.to_lowercase() == proxy)
-                                                {
-                                                    if let Err(e) = tx_msg.send((activity, proxy)).await {
-                                                        Logger::error(&format!(
-                                                            "Error sending trade to executor: {}",
-                                                            e
-                                                        ));
-                                                    }
-                                                }
+                                    let traders = &config_msg.user_addresses;
+                                    let malformed = &config_msg.malformed_log_path;
+                                    match route_frame(&parsed, traders, now.timestamp(), malformed) {
+                                        Route::Subscribed => {
+                                            Logger::info("RTDS subscription confirmed")
+                                        }
+                                        Route::Trade(activity, proxy) => {
+                                            history_msg.lock().await.observe(
+                                                &proxy,
+                                                &activity,
+                                                max_backfill_secs,
+                                            );
+                                            if let Err(e) = tx_msg.send((*activity, proxy)).await {
+                                                Logger::error(&format!(
+                                                    "Error sending trade to executor: {}",
+                                                    e
+                                                ));
                                             }
                                         }
+                                        Route::Ignored => {}
                                     }
                                 }
                             }
@@ -283,34 +672,76 @@ async fn connect_rtds(
                                 Logger::error(&format!("RTDS WebSocket error: {}", e));
                                 break;
                             }
+                            // Liveness only: tungstenite answers Pings itself.
+                            Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => continue,
                             None => break,
                             _ => continue,
                         }
                     }
                 });
 
-                message_task.await.ok();
+                message_task.join_next().await;
             }
             Err(e) => {
-                Logger::error(&format!("Failed to connect to RTDS: {}", e));
+                Logger::error(&format!("Failed to connect to RTDS: {:#}", e));
             }
         }
 
-        if RUNNING.load(Ordering::SeqCst) {
-            let attempts = reconnect_attempts.fetch_add(1, Ordering::SeqCst) + 1;
-            if attempts < MAX_RECONNECT_ATTEMPTS {
-                let delay = RECONNECT_DELAY_SECS * attempts.min(5) as u64;
-                Logger::info(&format!(
-                    "Reconnecting to RTDS in {}s (attempt {}/{})...",
-                    delay, attempts, MAX_RECONNECT_ATTEMPTS
-                ));
-                sleep(Duration::from_secs(delay)).await;
-            } else {
-                Logger::error(&format!(
-                    "Max reconnection attempts ({}) reached. Please restart the bot.",
-                    MAX_RECONNECT_ATTEMPTS
+        if !shutdown.is_cancelled() {
+            reconnect_attempts += 1;
+            let attempts = reconnect_attempts;
+            let since = *down_since.get_or_insert_with(|| chrono::Utc::now().timestamp());
+            if attempts.is_multiple_of(RECONNECT_WARN_EVERY) {
+                Logger::warning(&format!(
+                    "RTDS still unreachable after {} attempts ({} min) - still retrying",
+                    attempts,
+                    (chrono::Utc::now().timestamp() - since) / 60
                 ));
-                break;
+            }
+            match after_failures(&config, attempts) {
+                NextStep::Reconnect(delay) => {
+                    let budget = match config.monitor_mode {
+                        MonitorMode::Websocket => config.rtds_max_reconnect_attempts,
+                        _ => config.monitor_fallback_attempts,
+                    };
+                    let attempt = if budget > 0 {
+                        format!("{}/{}", attempts, budget)
+                    } else {
+                        attempts.to_string()
+                    };
+                    let delay = with_jitter(delay, jitter_unit());
+                    Logger::info(&format!(
+                        "Reconnecting to RTDS in {:.1}s (attempt {})...",
+                        delay.as_secs_f64(),
+                        attempt
+                    ));
+                    sleep_unless_stopped(&shutdown, delay).await;
+                }
+                NextStep::Poll => {
+                    if !polling {
+                        Logger::warning(&format!(
+                            "RTDS unreachable after {} attempt(s) - switching to polling trader \
+                             activity every {}s (retrying RTDS every {}s)",
+                            attempts,
+                            config.fetch_interval_secs.max(1),
+                            POLLING_RTDS_RETRY_SECS
+                        ));
+                        polling = true;
+                        publish_mode("polling");
+                    }
+                    let retry_in = Duration::from_secs(POLLING_RTDS_RETRY_SECS);
+                    poll_activity(&config, &http_client, &history, &tx, Some(retry_in), &shutdown)
+                        .await;
+                }
+                NextStep::GiveUp => {
+                    publish_mode("down");
+                    alerts::warn(&format!(
+                        "RTDS monitor gave up after {} reconnection attempts; no trades are \
+                         copied until it reconnects",
+                        attempts
+                    ));
+                    anyhow::bail!("RTDS unreachable after {} reconnection attempts", attempts);
+                }
             }
         }
     }
@@ -318,32 +749,221 @@ async fn connect_rtds(
     Ok(())
 }
 
+/// Opens the RTDS WebSocket and sends the subscription. A connection that can't take the
+/// subscription fails like one that never opened, so it goes through the same backoff.
+async fn connect_and_subscribe(
+    url: &str,
+    subscribe_message: &serde_json::Value,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    let (mut ws_stream, _) = connect_async(url).await?;
+    Logger::success("RTDS WebSocket connected");
+    ws_stream
+        .send(Message::Text(subscribe_message.to_string()))
+        .await
+        .context("failed to send subscription")?;
+    Ok(ws_stream)
+}
+
 pub async fn run_trade_monitor(
     config: &EnvConfig,
     http_client: &reqwest::Client,
+    balance_cache: &BalanceCache,
     tx: tokio::sync::mpsc::Sender<(RtdsActivity, String)>,
+    shutdown: CancellationToken,
 ) -> Result<TradeMonitorHandle> {
-    RUNNING.store(true, Ordering::SeqCst);
-
-    init(config, http_client).await?;
+    init(config, http_client, balance_cache).await?;
 
+    let source = match config.monitor_mode {
+        MonitorMode::Polling => "data API polling",
+        MonitorMode::Auto | MonitorMode::Websocket => "RTDS (Real-Time Data Stream)",
+    };
     Logger::success(&format!(
-        "Monitoring {} trader(s) using RTDS (Real-Time Data Stream)",
-        config.user_addresses.len()
+        "Monitoring {} trader(s) using {}",
+        config.user_addresses.len(),
+        source
     ));
     Logger::info("Trades will be sent to executor for immediate execution.");
     Logger::separator();
 
     let config_arc = Arc::new(config.clone());
-    let reconnect_attempts = Arc::new(std::sync::atomic::AtomicU32::new(0));
 
-    let config_ws = config_arc.clone();
-    let tx_ws = tx.clone();
-    let reconnect_ws = reconnect_attempts.clone();
-    tokio::spawn(async move {
-        let _ = connect_rtds(config_ws, tx_ws, reconnect_ws).await;
-    });
+    let task = if let Some(path) = &config.replay_rtds_from {
+        let frames = Arc::new(rtds_capture::load(path)?);
+        Logger::warning(&format!(
+            "Replaying {} RTDS frame(s) from {} at {}x instead of connecting",
+            frames.len(),
+            path,
+            config.replay_rtds_speed
+        ));
+        // Not restarted: a second pass would send every trade again.
+        let shutdown = shutdown.clone();
+        supervisor().spawn("rtds-replay", STOP_FIRST, 0, move || {
+            let (frames, config, tx) = (frames.clone(), config_arc.clone(), tx.clone());
+            let shutdown = shutdown.clone();
+            async move {
+                let started_at_ms = chrono::Utc::now().timestamp_millis();
+                let sent = replay_frames(
+                    &frames,
+                    config.replay_rtds_speed,
+                    started_at_ms,
+                    &config.user_addresses,
+                    &config.malformed_log_path,
+                    &tx,
+                    &shutdown,
+                )
+                .await;
+                Logger::info(&format!("RTDS replay finished: {} trade(s) sent", sent));
+                Ok(())
+            }
+        })
+    } else {
+        if let Some(path) = &config.capture_rtds_to {
+            Capture::open(path)?;
+            Logger::info(&format!("Capturing raw RTDS frames to {}", path));
+        }
+        // Outlives supervised restarts, so a restart backfills the gap like a reconnect.
+        let history = Arc::new(tokio::sync::Mutex::new(StreamHistory::default()));
+        let chaos = Chaos::from_config(config);
+        let (http_client, shutdown) = (http_client.clone(), shutdown.clone());
+        supervisor().spawn("rtds-monitor", STOP_FIRST, MAX_TASK_RESTARTS, move || {
+            connect_rtds(
+                config_arc.clone(),
+                http_client.clone(),
+                history.clone(),
+                tx.clone(),
+                chaos.clone(),
+                shutdown.clone(),
+            )
+        })
+    };
+
+    Ok(TradeMonitorHandle { shutdown, task })
+}
 
-    let (broadcast_tx, _) = broadcast::channel::<()>(1);
-    Ok(TradeMonitorHandle { _tx: broadcast_tx })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    #[test]
+    fn auto_mode_polls_after_the_fallback_attempts() {
+        let config = test_config(&[("MONITOR_MODE", "auto"), ("MONITOR_FALLBACK_ATTEMPTS", "3")]);
+        assert_eq!(after_failures(&config, 1), NextStep::Reconnect(5));
+        assert_eq!(after_failures(&config, 2), NextStep::Reconnect(10));
+        assert_eq!(after_failures(&config, 3), NextStep::Poll);
+        // It never gives up: RTDS is retried between polling rounds.
+        assert_eq!(after_failures(&config, 50), NextStep::Poll);
+    }
+
+    #[test]
+    fn websocket_mode_backs_off_and_retries_forever_by_default() {
+        let config = test_config(&[("MONITOR_MODE", "websocket")]);
+        assert_eq!(after_failures(&config, 3), NextStep::Reconnect(20));
+        assert_eq!(after_failures(&config, 7), NextStep::Reconnect(300));
+        assert_eq!(after_failures(&config, 10_000), NextStep::Reconnect(300));
+    }
+
+    #[test]
+    fn websocket_mode_gives_up_after_a_configured_budget() {
+        let config = test_config(&[
+            ("MONITOR_MODE", "websocket"),
+            ("RTDS_MAX_RECONNECT_ATTEMPTS", "10"),
+            ("RTDS_MAX_BACKOFF_SECS", "60"),
+        ]);
+        assert_eq!(after_failures(&config, 4), NextStep::Reconnect(40));
+        assert_eq!(after_failures(&config, 9), NextStep::Reconnect(60));
+        assert_eq!(after_failures(&config, 10), NextStep::GiveUp);
+    }
+
+    #[test]
+    fn jitter_only_ever_lengthens_the_delay_by_a_fifth() {
+        assert_eq!(with_jitter(10, 0.0), Duration::from_secs(10));
+        assert_eq!(with_jitter(10, 0.5), Duration::from_secs(11));
+        assert!(with_jitter(300, 0.999_999) < Duration::from_secs(360));
+        assert!((0.0..1.0).contains(&jitter_unit()));
+    }
+
+    #[tokio::test]
+    async fn stopping_cuts_a_reconnect_wait_short() {
+        let config = Arc::new(test_config(&[
+            ("MONITOR_MODE", "websocket"),
+            ("RTDS_URL", "ws://127.0.0.1:9/"),
+        ]));
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(connect_rtds(
+            config,
+            reqwest::Client::new(),
+            Arc::new(tokio::sync::Mutex::new(StreamHistory::default())),
+            tx,
+            None,
+            shutdown.clone(),
+        ));
+        // Refused at once, then waiting out the first 5s delay.
+        sleep(Duration::from_millis(300)).await;
+        shutdown.cancel();
+        let stopped = tokio::time::timeout(Duration::from_secs(2), task).await;
+        assert!(matches!(stopped, Ok(Ok(Ok(())))), "{:?}", stopped);
+    }
+
+    #[test]
+    fn each_address_gets_its_own_filtered_subscription() {
+        let traders = vec![
+            "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b".to_string(),
+            "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23".to_string(),
+        ];
+        let message =
+            subscribe_message(&traders, crate::config::DEFAULT_RTDS_SUBSCRIPTION).unwrap();
+        assert_eq!(message["action"], "subscribe");
+        let subscriptions = message["subscriptions"].as_array().unwrap();
+        assert_eq!(subscriptions.len(), 2);
+        for (subscription, trader) in subscriptions.iter().zip(&traders) {
+            assert_eq!(subscription["topic"], "activity");
+            assert_eq!(subscription["type"], "trades");
+            let filters: serde_json::Value =
+                serde_json::from_str(subscription["filters"].as_str().unwrap()).unwrap();
+            assert_eq!(filters, json!({ "proxyWallet": trader }));
+        }
+
+        let custom = subscribe_message(&traders[..1], r#"{"topic":"activity","wallet":"{address}"}"#)
+            .unwrap();
+        assert_eq!(
+            custom["subscriptions"],
+            json!([{ "topic": "activity", "wallet": traders[0] }])
+        );
+        assert!(subscribe_message(&traders, "{address}").is_err());
+    }
+
+    #[test]
+    fn connections_go_stale_after_the_window_unless_disabled() {
+        let now = 1_760_000_000_000;
+        assert!(!is_stale(now - 90_000, now, 90));
+        assert!(is_stale(now - 90_001, now, 90));
+        assert!(!is_stale(now - 3_600_000, now, 0));
+    }
+
+    #[test]
+    fn first_payloads_are_captured_then_one_in_every_n() {
+        let captured: Vec<u64> = (1..=200).filter(|n| should_capture(*n)).collect();
+        let mut expected: Vec<u64> = (1..=MALFORMED_ALWAYS_CAPTURE).collect();
+        expected.extend([50, 100, 150, 200]);
+        assert_eq!(captured, expected);
+    }
+
+    #[test]
+    fn malformed_payload_is_written_to_the_configured_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("malformed.jsonl");
+        let before = malformed_activity_count();
+        record_malformed(
+            path.to_str().unwrap(),
+            "price 1.5 outside (0, 1)",
+            &json!({ "price": 1.5 }),
+        );
+        assert_eq!(malformed_activity_count(), before + 1);
+        let row: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(row["reason"], "price 1.5 outside (0, 1)");
+        assert_eq!(row["payload"]["price"], 1.5);
+    }
 }
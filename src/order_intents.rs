@@ -0,0 +1,507 @@
+//! Write-ahead log of copy orders (`order_intents.jsonl` in `STATE_DIR`), so an order is
+//! never sent twice across a crash. Before a copy order is posted, its intent (copy id, the
+//! order's CLOB id, market, size and price) is appended and synced to disk; once the CLOB
+//! answers, the outcome is appended after it. An intent with no outcome means the bot died,
+//! or the connection did, while the order was in flight: the exchange may or may not have
+//! it. On startup those are looked up on the CLOB by order id and settled into the journal
+//! before anything new is processed, and every copy id in the log is kept away from
+//! catch-up.
+//!
+//! The order id is the order's EIP-712 hash, which is what the CLOB files it under, so it is
+//! known before the order is posted. Resting orders (`EMPTY_BOOK_POLICY=LIMIT`, position
+//! builds) are not logged here: their trackers pick them up from the id the CLOB returns.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use alloy::dyn_abi::Eip712Domain;
+use alloy::primitives::U256;
+use alloy::sol_types::SolStruct;
+use polymarket_client_sdk::auth::state::Authenticated;
+use polymarket_client_sdk::auth::Normal;
+use polymarket_client_sdk::clob::types::Order;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use polymarket_client_sdk::error::Status;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{state_path, Logger};
+
+const ORDER_INTENTS_FILE: &str = "order_intents.jsonl";
+
+/// Serializes appends from concurrent submissions so lines never interleave.
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// A copy order about to be posted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderIntent {
+    /// The order's CLOB id (see `order_id`).
+    pub order_id: String,
+    /// The copied trade's transaction hash, as the journal records it.
+    pub copy_id: String,
+    pub trader: String,
+    pub asset: String,
+    pub side: String,
+    pub tokens: f64,
+    pub usd: f64,
+    pub price: f64,
+    pub created_at: i64,
+}
+
+/// How an order ended, as the CLOB answered it or as startup found it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Outcome {
+    Filled { tokens: f64, usd: f64 },
+    Rejected { reason: String },
+    /// Not on the exchange when startup looked it up: the order never got there.
+    Lost,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum IntentRecord {
+    Intent(OrderIntent),
+    Outcome {
+        order_id: String,
+        #[serde(flatten)]
+        outcome: Outcome,
+        at: i64,
+    },
+}
+
+/// What the CLOB says about an order whose outcome was never logged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lookup {
+    /// On the exchange, with this much matched (possibly nothing).
+    Found { matched_tokens: f64, price: f64 },
+    Missing,
+}
+
+impl Lookup {
+    fn outcome(self) -> Outcome {
+        match self {
+            Lookup::Found { matched_tokens, price } if matched_tokens > 0.0 => Outcome::Filled {
+                tokens: matched_tokens,
+                usd: matched_tokens * price,
+            },
+            Lookup::Found { .. } => Outcome::Rejected {
+                reason: "not filled".to_string(),
+            },
+            Lookup::Missing => Outcome::Lost,
+        }
+    }
+}
+
+/// One interrupted copy, settled: everything its orders filled, the ones logged before the
+/// crash included, for a single journal row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub copy_id: String,
+    pub trader: String,
+    pub asset: String,
+    pub side: String,
+    pub tokens: f64,
+    pub usd: f64,
+    /// In-flight orders the exchange never received.
+    pub lost: usize,
+}
+
+impl Settlement {
+    /// The journal reason for the settled row.
+    pub fn reason(&self) -> String {
+        if self.tokens > 0.0 {
+            "order outcome settled from the CLOB after a restart".to_string()
+        } else if self.lost > 0 {
+            "order submission interrupted: it never reached the exchange".to_string()
+        } else {
+            "order submission interrupted: it did not fill".to_string()
+        }
+    }
+}
+
+/// The order's CLOB id: its EIP-712 hash under the exchange contract it is signed for.
+pub fn order_id(order: &Order, chain_id: u64, neg_risk: bool) -> Option<String> {
+    let exchange = polymarket_client_sdk::contract_config(chain_id, neg_risk)?.exchange;
+    let domain = Eip712Domain {
+        name: Some("Polymarket CTF Exchange".into()),
+        version: Some("1".into()),
+        chain_id: Some(U256::from(chain_id)),
+        verifying_contract: Some(exchange),
+        ..Eip712Domain::default()
+    };
+    Some(format!("{}", order.eip712_signing_hash(&domain)))
+}
+
+fn log_path(state_dir: &str) -> PathBuf {
+    state_path(state_dir, ORDER_INTENTS_FILE)
+}
+
+/// The logged records; a torn last line from a crash mid-write is skipped.
+fn read_log(path: &Path) -> Vec<IntentRecord> {
+    let Ok(raw) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Appends `records` and syncs the file before returning.
+fn append_log(path: &Path, records: &[IntentRecord]) -> anyhow::Result<()> {
+    let _guard = LOG_LOCK.lock().map_err(|_| anyhow::anyhow!("intent log lock poisoned"))?;
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let mut body = String::new();
+    for record in records {
+        body.push_str(&serde_json::to_string(record)?);
+        body.push('\n');
+    }
+    file.write_all(body.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Replaces the log via a temp file and rename, like `save_json`.
+fn rewrite_log(path: &Path, records: &[IntentRecord]) -> anyhow::Result<()> {
+    let _guard = LOG_LOCK.lock().map_err(|_| anyhow::anyhow!("intent log lock poisoned"))?;
+    let mut body = String::new();
+    for record in records {
+        body.push_str(&serde_json::to_string(record)?);
+        body.push('\n');
+    }
+    let tmp = path.with_extension("tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(body.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Logs `intent` before its order is posted. An error means the order must not go out.
+pub fn record_intent(state_dir: &str, intent: &OrderIntent) -> anyhow::Result<()> {
+    append_log(&log_path(state_dir), &[IntentRecord::Intent(intent.clone())])
+}
+
+/// Logs how the order `order_id` ended. A failed write only warns: the order is out, and
+/// startup will look it up.
+pub fn record_outcome(state_dir: &str, order_id: &str, outcome: Outcome) {
+    let record = IntentRecord::Outcome {
+        order_id: order_id.to_string(),
+        outcome,
+        at: chrono::Utc::now().timestamp(),
+    };
+    if let Err(e) = append_log(&log_path(state_dir), &[record]) {
+        Logger::warning(&format!("Failed to log the outcome of order {}: {}", order_id, e));
+    }
+}
+
+fn outcomes(records: &[IntentRecord]) -> HashMap<&str, &Outcome> {
+    records
+        .iter()
+        .filter_map(|r| match r {
+            IntentRecord::Outcome { order_id, outcome, .. } => Some((order_id.as_str(), outcome)),
+            IntentRecord::Intent(_) => None,
+        })
+        .collect()
+}
+
+fn pending_in(records: &[IntentRecord]) -> Vec<OrderIntent> {
+    let outcomes = outcomes(records);
+    records
+        .iter()
+        .filter_map(|r| match r {
+            IntentRecord::Intent(i) if !outcomes.contains_key(i.order_id.as_str()) => {
+                Some(i.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Intents whose outcome was never logged.
+pub fn pending(state_dir: &str) -> Vec<OrderIntent> {
+    pending_in(&read_log(&log_path(state_dir)))
+}
+
+/// Copy ids of every logged intent, lowercased, for catch-up to leave alone: their orders
+/// went out, whatever the journal says.
+pub fn copy_ids(state_dir: &str) -> HashSet<String> {
+    read_log(&log_path(state_dir))
+        .into_iter()
+        .filter_map(|r| match r {
+            IntentRecord::Intent(i) if !i.copy_id.is_empty() => Some(i.copy_id.to_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Settles the copies with a pending intent whose orders were all looked up: the outcomes
+/// to log for the pending intents, and one settlement per copy summing every fill its
+/// orders got. Copies with an order not in `lookups` stay pending.
+fn settle(
+    records: &[IntentRecord],
+    lookups: &HashMap<String, Lookup>,
+) -> (Vec<(String, Outcome)>, Vec<Settlement>) {
+    let logged = outcomes(records);
+    let pending = pending_in(records);
+    let mut copies: Vec<&str> = Vec::new();
+    for intent in &pending {
+        if !copies.contains(&intent.copy_id.as_str()) {
+            copies.push(&intent.copy_id);
+        }
+    }
+    let mut new_outcomes = Vec::new();
+    let mut settlements = Vec::new();
+    for copy_id in copies {
+        let orders: Vec<&OrderIntent> = records
+            .iter()
+            .filter_map(|r| match r {
+                IntentRecord::Intent(i) if i.copy_id == copy_id => Some(i),
+                _ => None,
+            })
+            .collect();
+        let resolved: Option<Vec<(&OrderIntent, Outcome)>> = orders
+            .iter()
+            .map(|i| match logged.get(i.order_id.as_str()) {
+                Some(outcome) => Some((*i, (*outcome).clone())),
+                None => lookups.get(&i.order_id).map(|l| (*i, l.outcome())),
+            })
+            .collect();
+        let Some(resolved) = resolved else {
+            continue;
+        };
+        let first = orders[0];
+        let mut settlement = Settlement {
+            copy_id: copy_id.to_string(),
+            trader: first.trader.clone(),
+            asset: first.asset.clone(),
+            side: first.side.clone(),
+            tokens: 0.0,
+            usd: 0.0,
+            lost: 0,
+        };
+        for (intent, outcome) in resolved {
+            match &outcome {
+                Outcome::Filled { tokens, usd } => {
+                    settlement.tokens += tokens;
+                    settlement.usd += usd;
+                }
+                Outcome::Lost => settlement.lost += 1,
+                Outcome::Rejected { .. } => {}
+            }
+            if !logged.contains_key(intent.order_id.as_str()) {
+                new_outcomes.push((intent.order_id.clone(), outcome));
+            }
+        }
+        settlements.push(settlement);
+    }
+    (new_outcomes, settlements)
+}
+
+/// Looks the order up on the CLOB. `None` when the exchange can't be asked right now.
+async fn lookup(clob_client: &ClobClient<Authenticated<Normal>>, order_id: &str) -> Option<Lookup> {
+    match clob_client.order(order_id).await {
+        Ok(order) => Some(Lookup::Found {
+            matched_tokens: order.size_matched.to_f64().unwrap_or(0.0),
+            price: order.price.to_f64().unwrap_or(0.0),
+        }),
+        Err(e) if e.downcast_ref::<Status>().is_some_and(|s| s.status_code == 404) => {
+            Some(Lookup::Missing)
+        }
+        Err(e) => {
+            Logger::warning(&format!(
+                "Order {} in flight at the last shutdown could not be checked: {}",
+                order_id, e
+            ));
+            None
+        }
+    }
+}
+
+/// Looks up every pending intent on the CLOB, logs the outcomes and returns the settled
+/// copies for the journal. Copies that couldn't be checked stay pending for the next
+/// startup; running it again settles nothing twice.
+pub async fn resolve(
+    state_dir: &str,
+    clob_client: &ClobClient<Authenticated<Normal>>,
+) -> Vec<Settlement> {
+    let path = log_path(state_dir);
+    let records = read_log(&path);
+    let mut lookups = HashMap::new();
+    for intent in pending_in(&records) {
+        if let Some(found) = lookup(clob_client, &intent.order_id).await {
+            lookups.insert(intent.order_id, found);
+        }
+    }
+    let (new_outcomes, settlements) = settle(&records, &lookups);
+    if new_outcomes.is_empty() {
+        return settlements;
+    }
+    let at = chrono::Utc::now().timestamp();
+    let appended: Vec<IntentRecord> = new_outcomes
+        .into_iter()
+        .map(|(order_id, outcome)| IntentRecord::Outcome {
+            order_id,
+            outcome,
+            at,
+        })
+        .collect();
+    if let Err(e) = append_log(&path, &appended) {
+        // Journaling them anyway would journal them again next time.
+        Logger::warning(&format!("Failed to log settled order outcomes: {}", e));
+        return Vec::new();
+    }
+    settlements
+}
+
+/// Drops copies whose orders all have an outcome and were logged before `keep_after`.
+pub fn compact(state_dir: &str, keep_after: i64) {
+    let path = log_path(state_dir);
+    let records = read_log(&path);
+    let open: HashSet<String> = pending_in(&records)
+        .into_iter()
+        .map(|i| i.copy_id)
+        .collect();
+    let dropped: HashSet<String> = records
+        .iter()
+        .filter_map(|r| match r {
+            IntentRecord::Intent(i) if i.created_at < keep_after && !open.contains(&i.copy_id) => {
+                Some(i.order_id.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    if dropped.is_empty() {
+        return;
+    }
+    let kept: Vec<IntentRecord> = records
+        .into_iter()
+        .filter(|r| match r {
+            IntentRecord::Intent(i) => !dropped.contains(&i.order_id),
+            IntentRecord::Outcome { order_id, .. } => !dropped.contains(order_id),
+        })
+        .collect();
+    if let Err(e) = rewrite_log(&path, &kept) {
+        Logger::warning(&format!("Failed to compact the order intent log: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_000_000;
+
+    fn intent(order_id: &str, copy_id: &str, tokens: f64) -> OrderIntent {
+        OrderIntent {
+            order_id: order_id.to_string(),
+            copy_id: copy_id.to_string(),
+            trader: "0xtrader".to_string(),
+            asset: "111".to_string(),
+            side: "BUY".to_string(),
+            tokens,
+            usd: tokens * 0.5,
+            price: 0.5,
+            created_at: NOW,
+        }
+    }
+
+    fn outcome(order_id: &str, outcome: Outcome) -> IntentRecord {
+        IntentRecord::Outcome {
+            order_id: order_id.to_string(),
+            outcome,
+            at: NOW,
+        }
+    }
+
+    #[test]
+    fn an_intent_without_an_outcome_is_pending_and_a_torn_line_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        record_intent(state_dir, &intent("0x1", "0xa", 10.0)).unwrap();
+        record_outcome(state_dir, "0x1", Outcome::Filled { tokens: 10.0, usd: 5.0 });
+        record_intent(state_dir, &intent("0x2", "0xB", 4.0)).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log_path(state_dir))
+            .unwrap();
+        write!(file, "{{\"kind\":\"outcome\",\"order_id\":\"0x2\",\"res").unwrap();
+        let pending = pending(state_dir);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].order_id, "0x2");
+        assert_eq!(
+            copy_ids(state_dir),
+            HashSet::from(["0xa".to_string(), "0xb".to_string()])
+        );
+    }
+
+    #[test]
+    fn settling_sums_the_copy_and_leaves_unchecked_copies_pending() {
+        let records = vec![
+            IntentRecord::Intent(intent("0x1", "0xa", 10.0)),
+            outcome("0x1", Outcome::Filled { tokens: 10.0, usd: 5.0 }),
+            IntentRecord::Intent(intent("0x2", "0xa", 6.0)),
+            IntentRecord::Intent(intent("0x3", "0xb", 4.0)),
+            IntentRecord::Intent(intent("0x4", "0xc", 4.0)),
+        ];
+        let lookups = HashMap::from([
+            ("0x2".to_string(), Lookup::Found { matched_tokens: 6.0, price: 0.5 }),
+            ("0x3".to_string(), Lookup::Missing),
+        ]);
+        let (outcomes, settlements) = settle(&records, &lookups);
+        assert_eq!(
+            outcomes,
+            vec![
+                ("0x2".to_string(), Outcome::Filled { tokens: 6.0, usd: 3.0 }),
+                ("0x3".to_string(), Outcome::Lost),
+            ]
+        );
+        assert_eq!(settlements.len(), 2);
+        assert_eq!((settlements[0].tokens, settlements[0].usd), (16.0, 8.0));
+        assert_eq!(settlements[0].copy_id, "0xa");
+        assert_eq!((settlements[1].tokens, settlements[1].lost), (0.0, 1));
+        assert!(settlements[1].reason().contains("never reached"));
+    }
+
+    #[test]
+    fn an_order_on_the_book_that_never_matched_did_not_fill() {
+        let records = vec![IntentRecord::Intent(intent("0x1", "0xa", 10.0))];
+        let lookups = HashMap::from([(
+            "0x1".to_string(),
+            Lookup::Found { matched_tokens: 0.0, price: 0.5 },
+        )]);
+        let (outcomes, settlements) = settle(&records, &lookups);
+        assert!(matches!(outcomes[0].1, Outcome::Rejected { .. }));
+        assert_eq!(settlements[0].reason(), "order submission interrupted: it did not fill");
+    }
+
+    #[test]
+    fn compaction_keeps_recent_and_pending_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let old = |order_id: &str, copy_id: &str| OrderIntent {
+            created_at: NOW - 86_400,
+            ..intent(order_id, copy_id, 1.0)
+        };
+        record_intent(state_dir, &old("0x1", "0xa")).unwrap();
+        record_outcome(state_dir, "0x1", Outcome::Lost);
+        record_intent(state_dir, &old("0x2", "0xb")).unwrap();
+        record_intent(state_dir, &intent("0x3", "0xc", 1.0)).unwrap();
+        record_outcome(state_dir, "0x3", Outcome::Lost);
+        compact(state_dir, NOW - 3_600);
+        let records = read_log(&log_path(state_dir));
+        assert_eq!(records.len(), 3);
+        assert_eq!(copy_ids(state_dir), HashSet::from(["0xb".to_string(), "0xc".to_string()]));
+    }
+}
@@ -0,0 +1,353 @@
+//! Per-market order templates: the parts of an order that don't change between signals,
+//! looked up once when a market enters the working set so the signal path only fills in
+//! size, price and the signature.
+
+use alloy::primitives::U256;
+use anyhow::Result;
+use polymarket_client_sdk::auth::state::State;
+use polymarket_client_sdk::clob::types::TickSize;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use polymarket_client_sdk::types::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::config::EnvConfig;
+use crate::utils::{fetch_data, Logger};
+
+static TEMPLATES: Mutex<BTreeMap<String, OrderTemplate>> = Mutex::new(BTreeMap::new());
+
+/// Tick a market is quoted on away from the extremes when nothing else is known.
+pub const DEFAULT_TICK: f64 = 0.01;
+/// Tick a market switches to once its price nears either end of the range.
+pub const FINE_TICK: f64 = 0.001;
+/// Prices under this, or above one minus this, are quoted on `FINE_TICK`.
+pub const FINE_TICK_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderTemplate {
+    pub token_id: U256,
+    pub tick_size: f64,
+    pub neg_risk: bool,
+    pub fee_rate_bps: u64,
+}
+
+impl OrderTemplate {
+    pub fn ticks(&self) -> TickRegime {
+        TickRegime::new(self.tick_size)
+    }
+
+    /// `price` on this market's tick, formatted for the order.
+    pub fn format_price(&self, price: f64) -> String {
+        self.ticks().format_price(price)
+    }
+
+    /// Hands the template to the SDK client for an order at `price`: its order builder
+    /// reads the tick size and fee rate, and signing the neg-risk flag, from the client's
+    /// own cache, which otherwise keeps whatever it first fetched for the market.
+    pub fn apply<S: State>(&self, clob_client: &ClobClient<S>, price: f64) {
+        let tick = Decimal::from_str(&self.ticks().tick_at(price).to_string())
+            .ok()
+            .and_then(|t| TickSize::try_from(t).ok());
+        if let Some(tick) = tick {
+            clob_client.set_tick_size(self.token_id, tick);
+        }
+        clob_client.set_neg_risk(self.token_id, self.neg_risk);
+        clob_client.set_fee_rate_bps(self.token_id, self.fee_rate_bps as u32);
+    }
+}
+
+/// A market's tick: the size it reports, narrowed to `FINE_TICK` under 5¢ and above 95¢,
+/// where Polymarket quotes in tenths of a cent. The reported size is whatever regime the
+/// market was in when the template was read, so the regime is worked out per price.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickRegime {
+    pub tick_size: f64,
+    pub fine_below: f64,
+}
+
+impl Default for TickRegime {
+    fn default() -> Self {
+        Self::new(DEFAULT_TICK)
+    }
+}
+
+impl TickRegime {
+    pub const fn new(tick_size: f64) -> Self {
+        Self {
+            tick_size,
+            fine_below: FINE_TICK_THRESHOLD,
+        }
+    }
+
+    /// The tick orders at `price` have to be on.
+    pub fn tick_at(&self, price: f64) -> f64 {
+        if price < self.fine_below || price > 1.0 - self.fine_below {
+            self.tick_size.min(FINE_TICK)
+        } else {
+            self.tick_size
+        }
+    }
+
+    /// Decimal places a price on the tick at `price` needs.
+    pub fn price_decimals(&self, price: f64) -> usize {
+        let mut decimals = 0;
+        let mut tick = self.tick_at(price);
+        while decimals < 6 && (tick - tick.round()).abs() > 1e-9 {
+            tick *= 10.0;
+            decimals += 1;
+        }
+        decimals
+    }
+
+    /// `price` rounded to the nearest tick of its regime, kept one tick inside (0, 1).
+    pub fn round_price(&self, price: f64) -> f64 {
+        let per_unit = (1.0 / self.tick_at(price)).round();
+        (price * per_unit).round().clamp(1.0, per_unit - 1.0) / per_unit
+    }
+
+    /// `price` on its tick, formatted for the order.
+    pub fn format_price(&self, price: f64) -> String {
+        let rounded = self.round_price(price);
+        format!("{:.*}", self.price_decimals(rounded), rounded)
+    }
+
+    /// Pre-flight check on a price about to go into an order: inside (0, 1), on the tick of
+    /// its regime, and within half of the `intended` price's tick of it (prices past the
+    /// first or last tick are clamped onto it).
+    pub fn check_price(&self, intended: f64, price: f64) -> Result<()> {
+        if price <= 0.0 || price >= 1.0 {
+            anyhow::bail!("Order price {} is outside (0, 1)", price);
+        }
+        let grid = self.tick_at(price);
+        let ticks = price / grid;
+        if (ticks - ticks.round()).abs() > 1e-6 {
+            anyhow::bail!("Order price {} is off the {} tick", price, grid);
+        }
+        let tick = self.tick_at(intended);
+        let clamped = intended < tick || intended > 1.0 - tick;
+        if !clamped && (price - intended).abs() > tick / 2.0 + 1e-9 {
+            anyhow::bail!(
+                "Order price {} is more than half a {} tick from {}",
+                price,
+                tick,
+                intended
+            );
+        }
+        Ok(())
+    }
+}
+
+/// CLOB token ids are decimal strings; `0x`-prefixed ids are read as hex.
+pub fn parse_token_id(asset: &str) -> Result<U256> {
+    let asset = asset.trim();
+    let parsed = match asset.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16),
+        None => U256::from_str(asset),
+    };
+    parsed.map_err(|e| anyhow::anyhow!("Invalid token id {}: {}", asset, e))
+}
+
+pub fn get(asset: &str) -> Option<OrderTemplate> {
+    TEMPLATES.lock().ok()?.get(asset).copied()
+}
+
+pub fn invalidate(asset: &str) {
+    if let Ok(mut templates) = TEMPLATES.lock() {
+        templates.remove(asset);
+    }
+}
+
+async fn lookup(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    path: &str,
+    asset: &str,
+) -> Result<serde_json::Value> {
+    let url = format!("{}/{}?token_id={}", config.clob_http_url, path, asset);
+    Ok(fetch_data(http_client, &url, config.request_timeout_ms, 1).await?)
+}
+
+pub async fn fetch_tick_size(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> Result<f64> {
+    let tick = lookup(http_client, config, "tick-size", asset).await?;
+    number(tick.get("minimum_tick_size"))
+        .filter(|t| *t > 0.0 && *t < 1.0)
+        .ok_or_else(|| anyhow::anyhow!("No tick size for {}", asset))
+}
+
+pub async fn fetch_neg_risk(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> Result<bool> {
+    Ok(lookup(http_client, config, "neg-risk", asset)
+        .await?
+        .get("neg_risk")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false))
+}
+
+pub async fn fetch_fee_rate_bps(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> Result<u64> {
+    let fee = lookup(http_client, config, "fee-rate", asset).await?;
+    Ok(number(fee.get("base_fee")).unwrap_or(0.0) as u64)
+}
+
+/// Reads the market's tick size, neg-risk flag and fee rate from the CLOB, concurrently.
+pub async fn fetch(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> Result<OrderTemplate> {
+    let (tick_size, neg_risk, fee_rate_bps) = tokio::try_join!(
+        fetch_tick_size(http_client, config, asset),
+        fetch_neg_risk(http_client, config, asset),
+        fetch_fee_rate_bps(http_client, config, asset),
+    )?;
+    Ok(OrderTemplate {
+        token_id: parse_token_id(asset)?,
+        tick_size,
+        neg_risk,
+        fee_rate_bps,
+    })
+}
+
+/// Caches a template looked up elsewhere (see `prefetch`).
+pub fn insert(asset: &str, template: OrderTemplate) {
+    if let Ok(mut templates) = TEMPLATES.lock() {
+        templates.insert(asset.to_string(), template);
+    }
+}
+
+fn number(value: Option<&serde_json::Value>) -> Option<f64> {
+    let value = value?;
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Builds the template for a market the first time it is seen; cached markets are left as is.
+pub async fn warm(http_client: &reqwest::Client, config: &EnvConfig, asset: &str) {
+    if asset.is_empty() || get(asset).is_some() {
+        return;
+    }
+    match fetch(http_client, config, asset).await {
+        Ok(template) => insert(asset, template),
+        Err(e) => Logger::warning(&format!("Order template lookup failed: {}", e)),
+    }
+}
+
+/// Re-reads every cached market. A changed tick size, fee or neg-risk flag replaces the
+/// template, which the next order on the market hands to the SDK client (see `apply`); a
+/// failed lookup drops it, so orders take the full path until the next warm-up.
+pub async fn refresh_all(http_client: &reqwest::Client, config: &EnvConfig) {
+    let assets: Vec<String> = TEMPLATES
+        .lock()
+        .map(|t| t.keys().cloned().collect())
+        .unwrap_or_default();
+    for asset in assets {
+        match fetch(http_client, config, &asset).await {
+            Ok(fresh) => {
+                if let Ok(mut templates) = TEMPLATES.lock() {
+                    if templates.insert(asset.clone(), fresh).is_some_and(|old| old != fresh) {
+                        Logger::info(&format!(
+                            "Order template for {} changed (tick {}, fee {} bps)",
+                            asset, fresh.tick_size, fresh.fee_rate_bps
+                        ));
+                    }
+                }
+            }
+            Err(_) => invalidate(&asset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(tick_size: f64) -> OrderTemplate {
+        OrderTemplate {
+            token_id: U256::from(1u8),
+            tick_size,
+            neg_risk: false,
+            fee_rate_bps: 0,
+        }
+    }
+
+    #[test]
+    fn prices_round_to_the_tick_inside_the_range() {
+        let cent = template(0.01);
+        assert_eq!(cent.format_price(0.587), "0.59");
+        assert_eq!(cent.format_price(0.999), "0.999");
+        assert_eq!(cent.format_price(0.0001), "0.001");
+        let mil = template(0.001);
+        assert_eq!(mil.ticks().price_decimals(0.5), 3);
+        assert_eq!(mil.format_price(0.9987), "0.999");
+        assert_eq!(mil.format_price(0.0004), "0.001");
+    }
+
+    #[test]
+    fn extreme_prices_use_the_fine_tick() {
+        let ticks = TickRegime::new(0.01);
+        for (price, expected) in [
+            (0.004, "0.004"),
+            (0.049, "0.049"),
+            (0.951, "0.951"),
+            (0.996, "0.996"),
+        ] {
+            assert_eq!(ticks.tick_at(price), FINE_TICK);
+            assert_eq!(ticks.format_price(price), expected);
+        }
+        assert_eq!(ticks.tick_at(0.05), 0.01);
+        assert_eq!(ticks.tick_at(0.95), 0.01);
+        assert_eq!(ticks.format_price(0.5049), "0.50");
+        // Rounding across the boundary lands on a price valid in either regime.
+        assert_eq!(ticks.format_price(0.0496), "0.05");
+    }
+
+    #[test]
+    fn pre_flight_catches_mis_rounded_prices() {
+        let ticks = TickRegime::new(0.01);
+        for price in [0.004, 0.049, 0.951, 0.996, 0.42] {
+            assert!(ticks.check_price(price, ticks.round_price(price)).is_ok());
+        }
+        // Two-decimal rounding in the fine regime is the wrong price...
+        assert!(ticks.check_price(0.049, 0.05).is_err());
+        assert!(ticks.check_price(0.951, 0.95).is_err());
+        assert!(ticks.check_price(0.996, 0.99).is_err());
+        // ...or no price at all.
+        assert!(ticks.check_price(0.004, 0.0).is_err());
+        // Off the grid in either regime.
+        assert!(ticks.check_price(0.5, 0.505).is_err());
+        assert!(ticks.check_price(0.0045, 0.0045).is_err());
+    }
+
+    #[test]
+    fn token_ids_are_decimal_unless_prefixed() {
+        assert_eq!(parse_token_id("255").unwrap(), U256::from(255u16));
+        assert_eq!(parse_token_id("0xff").unwrap(), U256::from(255u16));
+        let long = "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+        assert_eq!(parse_token_id(long).unwrap().to_string(), long);
+        assert!(parse_token_id("not-a-token").is_err());
+    }
+
+    #[test]
+    fn invalidated_templates_miss() {
+        let asset = "order-templates-test-asset";
+        TEMPLATES
+            .lock()
+            .unwrap()
+            .insert(asset.to_string(), template(0.01));
+        assert_eq!(get(asset), Some(template(0.01)));
+        invalidate(asset);
+        assert_eq!(get(asset), None);
+    }
+}
@@ -0,0 +1,235 @@
+//! Overflow accounting: the part of a trader's adds that `MAX_POSITION_SIZE_USD` kept out of
+//! my copies, per market, so the conviction I'm ignoring stays visible.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::SizingStep;
+use crate::types::{RtdsActivity, UserActivity};
+use crate::utils::{load_json, save_json, Logger};
+
+const OVERFLOW_FILE: &str = "position_overflow.json";
+
+pub type SharedOverflow = Arc<Mutex<OverflowBook>>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketOverflow {
+    pub asset: String,
+    pub title: Option<String>,
+    /// The trader whose add was blocked most recently.
+    pub trader: String,
+    /// Trader USD the cap kept out of my copies.
+    pub ignored_trader_usd: f64,
+    /// Copy USD the cap removed.
+    pub blocked_copy_usd: f64,
+    pub last_price: f64,
+    /// The position cap when the adds were blocked.
+    pub cap_usd: f64,
+    /// The `OVERFLOW_NOTIFY_USD` warning has been shown.
+    #[serde(default)]
+    pub notified: bool,
+}
+
+/// Blocked adds per condition id, persisted across restarts. A market leaves the book when
+/// the position is closed or a catch-up buy is placed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OverflowBook {
+    markets: HashMap<String, MarketOverflow>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+/// The `position_cap` stage of a sizing run as (input, output), when it cut the copy.
+pub fn position_cap_cut(steps: &[SizingStep]) -> Option<(f64, f64)> {
+    steps
+        .iter()
+        .find(|s| s.name == "position_cap" && s.output_usd < s.input_usd)
+        .map(|s| (s.input_usd, s.output_usd))
+}
+
+impl OverflowBook {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, OVERFLOW_FILE);
+        let mut book: OverflowBook = load_json(&path).unwrap_or_default();
+        book.path = Some(path);
+        book
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Records a copy of `trade` the cap cut from `cut.0` to `cut.1` USD; the ignored trader
+    /// USD is the same share of the trader's add. Returns the market's ignored total the first
+    /// time it reaches `notify_usd` (0 = never).
+    pub fn record(
+        &mut self,
+        condition_id: &str,
+        trade: &UserActivity,
+        trader: &str,
+        cut: (f64, f64),
+        cap_usd: f64,
+        notify_usd: f64,
+    ) -> Option<f64> {
+        let (before, after) = cut;
+        if before <= 0.0 || after >= before {
+            return None;
+        }
+        let entry = self.markets.entry(condition_id.to_string()).or_default();
+        entry.asset = trade.asset.clone().unwrap_or_default();
+        entry.title = trade.title.clone().or_else(|| entry.title.take());
+        entry.trader = trader.to_lowercase();
+        entry.ignored_trader_usd += trade.usdc_size.unwrap_or(0.0) * (before - after) / before;
+        entry.blocked_copy_usd += before - after;
+        entry.last_price = trade.price.unwrap_or(entry.last_price);
+        entry.cap_usd = cap_usd;
+        let crossed = notify_usd > 0.0 && !entry.notified && entry.ignored_trader_usd >= notify_usd;
+        if crossed {
+            entry.notified = true;
+        }
+        let total = entry.ignored_trader_usd;
+        self.persist();
+        crossed.then_some(total)
+    }
+
+    pub fn get(&self, condition_id: &str) -> Option<&MarketOverflow> {
+        self.markets.get(condition_id)
+    }
+
+    pub fn markets(&self) -> impl Iterator<Item = (&String, &MarketOverflow)> {
+        self.markets.iter()
+    }
+
+    pub fn clear(&mut self, condition_id: &str) {
+        if self.markets.remove(condition_id).is_some() {
+            self.persist();
+        }
+    }
+
+    /// Removes and returns the markets whose adds were blocked under a lower cap than `cap_usd`
+    /// (`None`: no cap any more), for a one-time catch-up.
+    pub fn take_raised(&mut self, cap_usd: Option<f64>) -> Vec<(String, MarketOverflow)> {
+        let raised: Vec<String> = self
+            .markets
+            .iter()
+            .filter(|(_, m)| cap_usd.is_none_or(|cap| cap > m.cap_usd))
+            .map(|(cid, _)| cid.clone())
+            .collect();
+        let taken: Vec<_> = raised
+            .into_iter()
+            .filter_map(|cid| self.markets.remove(&cid).map(|m| (cid, m)))
+            .collect();
+        if !taken.is_empty() {
+            self.persist();
+        }
+        taken
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist position overflow: {}", e));
+            }
+        }
+    }
+}
+
+impl MarketOverflow {
+    /// A BUY signal for `fraction` of the ignored trader USD at the last price seen, to run
+    /// through the normal pipeline. `None` without a usable price.
+    pub fn catch_up_signal(
+        &self,
+        condition_id: &str,
+        fraction: f64,
+        now: i64,
+    ) -> Option<RtdsActivity> {
+        let price = Some(self.last_price).filter(|p| *p > 0.0 && *p < 1.0)?;
+        Some(RtdsActivity {
+            proxy_wallet: Some(self.trader.clone()),
+            timestamp: Some(now),
+            condition_id: Some(condition_id.to_string()),
+            activity_type: Some("TRADE".to_string()),
+            size: Some(self.ignored_trader_usd * fraction / price),
+            price: Some(price),
+            asset: Some(self.asset.clone()),
+            side: Some("BUY".to_string()),
+            outcome_index: None,
+            title: self.title.clone(),
+            slug: None,
+            icon: None,
+            event_slug: None,
+            outcome: None,
+            name: None,
+            transaction_hash: Some(format!("overflow:{}:{}", condition_id, now)),
+            reported_usdc_size: None,
+            backfilled: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UsdcSizePreference;
+
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+
+    fn add(usd: f64, price: f64) -> UserActivity {
+        UserActivity {
+            asset: Some("yes-a".to_string()),
+            title: Some("Fed cuts in December?".to_string()),
+            side: Some("BUY".to_string()),
+            usdc_size: Some(usd),
+            price: Some(price),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cut_copies_accumulate_and_notify_once() {
+        let mut book = OverflowBook::in_memory();
+        // Half of a $10 copy cut: half of the trader's $100 add was ignored.
+        assert_eq!(book.record("m", &add(100.0, 0.5), TRADER, (10.0, 5.0), 50.0, 120.0), None);
+        // Fully blocked from here on.
+        assert_eq!(
+            book.record("m", &add(80.0, 0.6), TRADER, (8.0, 0.0), 50.0, 120.0),
+            Some(130.0)
+        );
+        assert_eq!(book.record("m", &add(10.0, 0.6), TRADER, (1.0, 0.0), 50.0, 120.0), None);
+        let m = book.get("m").unwrap();
+        assert_eq!(m.ignored_trader_usd, 140.0);
+        assert_eq!(m.blocked_copy_usd, 14.0);
+        assert_eq!(m.last_price, 0.6);
+    }
+
+    #[test]
+    fn uncut_copies_are_not_recorded() {
+        let mut book = OverflowBook::in_memory();
+        assert_eq!(book.record("m", &add(100.0, 0.5), TRADER, (10.0, 10.0), 50.0, 1.0), None);
+        assert!(book.get("m").is_none());
+        let steps = vec![SizingStep {
+            name: "position_cap".to_string(),
+            input_usd: 10.0,
+            output_usd: 10.0,
+        }];
+        assert_eq!(position_cap_cut(&steps), None);
+    }
+
+    #[test]
+    fn raising_the_cap_releases_the_market_once() {
+        let mut book = OverflowBook::in_memory();
+        book.record("m", &add(100.0, 0.5), TRADER, (10.0, 0.0), 50.0, 0.0);
+        assert!(book.take_raised(Some(50.0)).is_empty());
+        let taken = book.take_raised(Some(80.0));
+        assert_eq!(taken.len(), 1);
+        assert!(book.take_raised(None).is_empty());
+
+        let signal = taken[0].1.catch_up_signal("m", 0.5, 1_760_000_000).unwrap();
+        assert_eq!(signal.side.as_deref(), Some("BUY"));
+        let usd = signal.usd_value(UsdcSizePreference::Conservative).unwrap();
+        assert!((usd.chosen - 50.0).abs() < 1e-9);
+    }
+}
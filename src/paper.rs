@@ -0,0 +1,301 @@
+//! Paper trading for `DRY_RUN`: the order a copy would have placed is sized the way the live
+//! strategies size it, priced at the trader's fill, and kept as a simulated position. Later
+//! sells and the position cap work against those positions instead of the wallet's, and the
+//! session's simulated PnL is logged at shutdown. Kept in memory; each run is a new session.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::config::{calculate_order_size, EnvConfig, SizingInputs};
+use crate::types::{UserActivity, UserPosition};
+use crate::utils::{lot_size, trader_sell_fraction, MIN_ORDER_SIZE_TOKENS};
+
+pub type SharedPaperBook = Arc<Mutex<PaperBook>>;
+
+/// An order a dry run would have placed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperOrder {
+    /// `BUY` or `SELL`.
+    pub side: String,
+    pub asset: String,
+    pub condition_id: Option<String>,
+    pub title: Option<String>,
+    pub tokens: f64,
+    pub usd: f64,
+    pub price: f64,
+    pub reasoning: String,
+}
+
+impl PaperOrder {
+    /// E.g. `BUY 10.00 tokens of Will it rain? @ $0.5000 ($5.00)`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {:.2} tokens of {} @ ${:.4} (${:.2})",
+            self.side,
+            self.tokens,
+            self.title.as_deref().unwrap_or(&self.asset),
+            self.price,
+            self.usd
+        )
+    }
+}
+
+/// What the live BUY or SELL strategy would order for `trade`, or why it would order nothing.
+/// `my_position` is the simulated one.
+pub fn size_order(
+    config: &EnvConfig,
+    inputs: &SizingInputs,
+    condition: &str,
+    my_position: Option<&UserPosition>,
+    user_position: Option<&UserPosition>,
+    trade: &UserActivity,
+    my_balance: f64,
+) -> Result<PaperOrder, String> {
+    let asset = trade.asset.clone().ok_or("no asset specified")?;
+    let price = trade
+        .price
+        .filter(|p| *p > 0.0 && *p < 1.0)
+        .ok_or("no usable price on the trade")?;
+    let held = my_position.and_then(|p| p.size).unwrap_or(0.0);
+    let (tokens, reasoning) = if condition == "buy" {
+        let current_value = held * my_position.and_then(|p| p.avg_price).unwrap_or(0.0);
+        let calc = calculate_order_size(
+            &config.copy_strategy_config,
+            inputs,
+            trade.usdc_size.unwrap_or(0.0),
+            my_balance,
+            current_value,
+        );
+        if calc.final_amount < config.copy_strategy_config.min_order_size_usd {
+            return Err(calc.reasoning);
+        }
+        (calc.final_amount / price, calc.reasoning)
+    } else {
+        if held <= 0.0 {
+            return Err("no simulated position to sell".to_string());
+        }
+        let held_after = user_position.and_then(|p| p.size);
+        let fraction = trader_sell_fraction(trade.size.unwrap_or(0.0), held_after);
+        let tokens = lot_size((held * fraction).min(held));
+        if tokens < MIN_ORDER_SIZE_TOKENS {
+            return Err(format!(
+                "sell of {:.2} tokens is below the {:.2} token minimum",
+                tokens, MIN_ORDER_SIZE_TOKENS
+            ));
+        }
+        let reasoning = format!(
+            "trader sold {:.0}% of their position → {:.2} of {:.2} simulated tokens",
+            fraction * 100.0,
+            tokens,
+            held
+        );
+        (tokens, reasoning)
+    };
+    Ok(PaperOrder {
+        side: condition.to_uppercase(),
+        asset,
+        condition_id: trade.condition_id.clone(),
+        title: trade.title.clone(),
+        tokens,
+        usd: tokens * price,
+        price,
+        reasoning,
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+struct PaperPosition {
+    condition_id: Option<String>,
+    title: Option<String>,
+    tokens: f64,
+    cost_usd: f64,
+    /// Last price seen for the asset, for the unrealized PnL.
+    mark: f64,
+}
+
+impl PaperPosition {
+    fn avg_price(&self) -> f64 {
+        if self.tokens > 0.0 {
+            self.cost_usd / self.tokens
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The session's simulated positions and totals.
+#[derive(Debug, Default)]
+pub struct PaperBook {
+    positions: HashMap<String, PaperPosition>,
+    orders: usize,
+    bought_usd: f64,
+    sold_usd: f64,
+    realized_usd: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperSummary {
+    pub orders: usize,
+    pub open_positions: usize,
+    pub bought_usd: f64,
+    pub sold_usd: f64,
+    pub realized_usd: f64,
+    /// Open positions marked at the last price seen for each.
+    pub unrealized_usd: f64,
+}
+
+impl PaperBook {
+    pub fn record(&mut self, order: &PaperOrder) {
+        self.orders += 1;
+        let position = self.positions.entry(order.asset.clone()).or_default();
+        position.condition_id = order.condition_id.clone();
+        position.title = order.title.clone();
+        position.mark = order.price;
+        if order.side == "BUY" {
+            position.tokens += order.tokens;
+            position.cost_usd += order.usd;
+            self.bought_usd += order.usd;
+            return;
+        }
+        let sold = order.tokens.min(position.tokens);
+        let cost = position.avg_price() * sold;
+        let proceeds = sold * order.price;
+        self.realized_usd += proceeds - cost;
+        self.sold_usd += proceeds;
+        position.tokens -= sold;
+        position.cost_usd -= cost;
+        if position.tokens < 1e-9 {
+            self.positions.remove(&order.asset);
+        }
+    }
+
+    /// Records the latest price seen for `asset`.
+    pub fn mark(&mut self, asset: &str, price: f64) {
+        if let Some(position) = self.positions.get_mut(asset) {
+            position.mark = price;
+        }
+    }
+
+    /// The simulated position in `asset`, shaped like a wallet position for the strategies.
+    pub fn position(&self, asset: &str) -> Option<UserPosition> {
+        let p = self.positions.get(asset)?;
+        Some(UserPosition {
+            asset: Some(asset.to_string()),
+            condition_id: p.condition_id.clone(),
+            size: Some(p.tokens),
+            avg_price: Some(p.avg_price()),
+            initial_value: Some(p.cost_usd),
+            current_value: Some(p.tokens * p.mark),
+            cur_price: Some(p.mark),
+            title: p.title.clone(),
+            ..UserPosition::default()
+        })
+    }
+
+    pub fn summary(&self) -> PaperSummary {
+        PaperSummary {
+            orders: self.orders,
+            open_positions: self.positions.len(),
+            bought_usd: self.bought_usd,
+            sold_usd: self.sold_usd,
+            realized_usd: self.realized_usd,
+            unrealized_usd: self
+                .positions
+                .values()
+                .map(|p| p.tokens * p.mark - p.cost_usd)
+                .sum(),
+        }
+    }
+}
+
+impl PaperSummary {
+    pub fn describe(&self) -> String {
+        format!(
+            "Paper trading session: {} simulated orders (${:.2} bought, ${:.2} sold), {} open \
+             position{}. PnL: ${:+.2} realized, ${:+.2} unrealized at the last prices seen",
+            self.orders,
+            self.bought_usd,
+            self.sold_usd,
+            self.open_positions,
+            if self.open_positions == 1 { "" } else { "s" },
+            self.realized_usd,
+            self.unrealized_usd
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    fn trade(side: &str, size: f64, price: f64) -> UserActivity {
+        UserActivity {
+            side: Some(side.to_string()),
+            asset: Some("123".to_string()),
+            condition_id: Some("0xc".to_string()),
+            size: Some(size),
+            usdc_size: Some(size * price),
+            price: Some(price),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sells_work_against_simulated_positions() {
+        let config = test_config(&[("COPY_STRATEGY", "PERCENTAGE"), ("COPY_SIZE", "10")]);
+        let mut book = PaperBook::default();
+        let held = |book: &PaperBook| book.position("123");
+
+        // Nothing simulated yet: a SELL has nothing to mirror.
+        let sell = trade("SELL", 50.0, 0.6);
+        assert!(size_order(&config, &SizingInputs::default(), "sell", None, None, &sell, 1_000.0).is_err());
+
+        let buy = size_order(&config, &SizingInputs::default(), "buy", None, None, &trade("BUY", 200.0, 0.5), 1_000.0)
+            .unwrap();
+        assert!((buy.usd - 10.0).abs() < 1e-9 && (buy.tokens - 20.0).abs() < 1e-9);
+        book.record(&buy);
+
+        // The trader sells half of their 100 tokens: half of my 20 go.
+        let trader_left = UserPosition {
+            size: Some(50.0),
+            ..Default::default()
+        };
+        let position = held(&book);
+        let order = size_order(&config, &SizingInputs::default(), "sell", position.as_ref(), Some(&trader_left), &sell, 0.0)
+            .unwrap();
+        assert_eq!(order.tokens, 10.0);
+        book.record(&order);
+        assert_eq!(held(&book).unwrap().size, Some(10.0));
+
+        book.mark("123", 0.8);
+        let summary = book.summary();
+        assert_eq!((summary.orders, summary.open_positions), (2, 1));
+        assert!((summary.realized_usd - 1.0).abs() < 1e-9, "{:?}", summary);
+        assert!((summary.unrealized_usd - 3.0).abs() < 1e-9, "{:?}", summary);
+
+        // A full exit closes the simulated position.
+        let exit = size_order(&config, &SizingInputs::default(), "sell", held(&book).as_ref(), None, &sell, 0.0).unwrap();
+        book.record(&exit);
+        assert!(held(&book).is_none());
+        assert_eq!(book.summary().unrealized_usd, 0.0);
+    }
+
+    #[test]
+    fn simulated_positions_count_toward_the_position_cap() {
+        let config = test_config(&[
+            ("COPY_STRATEGY", "FIXED"),
+            ("COPY_SIZE", "10"),
+            ("MAX_POSITION_SIZE_USD", "15"),
+        ]);
+        let mut book = PaperBook::default();
+        let buy = trade("BUY", 100.0, 0.5);
+        let first = size_order(&config, &SizingInputs::default(), "buy", None, None, &buy, 1_000.0).unwrap();
+        assert_eq!(first.usd, 10.0);
+        book.record(&first);
+        let second =
+            size_order(&config, &SizingInputs::default(), "buy", book.position("123").as_ref(), None, &buy, 1_000.0).unwrap();
+        assert_eq!(second.usd, 5.0);
+    }
+}
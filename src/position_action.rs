@@ -0,0 +1,169 @@
+//! What a trader's trade does to their own position: opens it, adds to it, reduces it or
+//! closes it. The trader's positions are fetched after the trade, so the position before it
+//! is rebuilt from what they hold now. `COPY_POSITION_ACTIONS` (and its per-trader
+//! override) picks which of the four are copied, via the `position_action` skip rule.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A trader still holding less than this share of what they had has exited.
+pub const FULL_EXIT_REMAINDER: f64 = 0.01;
+/// Holdings at or below this many tokens count as no position.
+const NO_POSITION_TOKENS: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionAction {
+    /// A BUY with no prior position.
+    Open,
+    /// A BUY on top of a position.
+    Add,
+    /// A SELL of part of the position.
+    Reduce,
+    /// A SELL of all of it, or of more than was known to be held.
+    Close,
+}
+
+impl PositionAction {
+    pub const ALL: [PositionAction; 4] = [
+        PositionAction::Open,
+        PositionAction::Add,
+        PositionAction::Reduce,
+        PositionAction::Close,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionAction::Open => "open",
+            PositionAction::Add => "add",
+            PositionAction::Reduce => "reduce",
+            PositionAction::Close => "close",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        Self::ALL
+            .into_iter()
+            .find(|a| a.as_str().eq_ignore_ascii_case(raw))
+    }
+}
+
+impl fmt::Display for PositionAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The trader's tokens before a `size`-token trade, given what they hold after it
+/// (`None` when they no longer hold the outcome).
+pub fn position_before(side: &str, size: f64, held_after: Option<f64>) -> f64 {
+    let after = held_after.unwrap_or(0.0).max(0.0);
+    let size = if size.is_finite() { size.max(0.0) } else { 0.0 };
+    if side.eq_ignore_ascii_case("BUY") {
+        (after - size).max(0.0)
+    } else {
+        after + size
+    }
+}
+
+/// Classifies a `size`-token trade on `side` against the `position_before` it. `None` for a
+/// side that is neither BUY nor SELL.
+pub fn classify(position_before: f64, side: &str, size: f64) -> Option<PositionAction> {
+    let held = position_before > NO_POSITION_TOKENS;
+    if side.eq_ignore_ascii_case("BUY") {
+        return Some(if held {
+            PositionAction::Add
+        } else {
+            PositionAction::Open
+        });
+    }
+    if !side.eq_ignore_ascii_case("SELL") {
+        return None;
+    }
+    if !held {
+        return Some(PositionAction::Close);
+    }
+    let remaining = position_before - size.max(0.0);
+    Some(if remaining / position_before < FULL_EXIT_REMAINDER {
+        PositionAction::Close
+    } else {
+        PositionAction::Reduce
+    })
+}
+
+/// A comma-separated list of actions, e.g. `open,close`.
+pub fn parse_list(raw: &str) -> Result<Vec<PositionAction>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            PositionAction::parse(a)
+                .ok_or_else(|| format!("unknown position action {:?} (open, add, reduce, close)", a))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PositionAction::*;
+
+    #[test]
+    fn buys_open_or_add() {
+        assert_eq!(classify(0.0, "BUY", 100.0), Some(Open));
+        assert_eq!(classify(1e-9, "BUY", 100.0), Some(Open));
+        assert_eq!(classify(50.0, "BUY", 100.0), Some(Add));
+        assert_eq!(classify(50.0, "buy", 0.0), Some(Add));
+    }
+
+    #[test]
+    fn sells_reduce_or_close() {
+        assert_eq!(classify(100.0, "SELL", 40.0), Some(Reduce));
+        assert_eq!(classify(100.0, "SELL", 98.0), Some(Reduce));
+        assert_eq!(classify(100.0, "SELL", 99.5), Some(Close));
+        assert_eq!(classify(100.0, "SELL", 100.0), Some(Close));
+        assert_eq!(classify(100.0, "sell", 0.0), Some(Reduce));
+    }
+
+    #[test]
+    fn selling_more_than_the_position_closes_it() {
+        assert_eq!(classify(100.0, "SELL", 250.0), Some(Close));
+    }
+
+    #[test]
+    fn selling_with_no_prior_position_is_a_close() {
+        assert_eq!(classify(0.0, "SELL", 10.0), Some(Close));
+        assert_eq!(classify(0.0, "SELL", 0.0), Some(Close));
+    }
+
+    #[test]
+    fn other_sides_are_not_classified() {
+        assert_eq!(classify(100.0, "", 10.0), None);
+        assert_eq!(classify(0.0, "MERGE", 10.0), None);
+    }
+
+    #[test]
+    fn position_before_is_rebuilt_from_the_holding_after() {
+        assert_eq!(position_before("BUY", 100.0, Some(100.0)), 0.0);
+        assert_eq!(position_before("BUY", 100.0, Some(300.0)), 200.0);
+        // A lagging positions feed can show less than was just bought.
+        assert_eq!(position_before("BUY", 100.0, Some(40.0)), 0.0);
+        assert_eq!(position_before("BUY", 100.0, None), 0.0);
+        assert_eq!(position_before("SELL", 40.0, Some(60.0)), 100.0);
+        assert_eq!(position_before("SELL", 40.0, None), 40.0);
+        assert_eq!(position_before("SELL", f64::NAN, Some(60.0)), 60.0);
+
+        let before = position_before("SELL", 100.0, None);
+        assert_eq!(classify(before, "SELL", 100.0), Some(Close));
+        let before = position_before("BUY", 100.0, Some(100.0));
+        assert_eq!(classify(before, "BUY", 100.0), Some(Open));
+    }
+
+    #[test]
+    fn lists_parse_case_insensitively_and_reject_unknown_actions() {
+        assert_eq!(parse_list("Open, close"), Ok(vec![Open, Close]));
+        assert_eq!(parse_list(""), Ok(vec![]));
+        assert!(parse_list("open,exit").is_err());
+    }
+}
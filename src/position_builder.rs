@@ -0,0 +1,652 @@
+//! Progressive position building (`POSITION_BUILD_MODE`): instead of matching a trader's
+//! entry with one order, a BUY sets a target for my position and the builder task works
+//! toward it with small limit orders inside the spread, re-evaluated every
+//! `BUILD_INTERVAL_SECS`. The rest of the target is abandoned when the trader exits, the price
+//! leaves the band around their entry, the caps leave no room, or the build runs too long.
+
+use polymarket_client_sdk::auth::state::Authenticated;
+use polymarket_client_sdk::auth::Normal;
+use polymarket_client_sdk::clob::Client as ClobClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::config::{EnvConfig, PositionBuildConfig};
+use crate::order_templates::{self, TickRegime};
+use crate::resting_orders;
+use crate::utils::{fetch_data, load_json, save_json, state_path, Logger};
+
+const BUILDS_FILE: &str = "position_builds.json";
+/// Ended builds stay listed (positions panel, wallet watchdog) this long.
+const ENDED_RETENTION_SECS: i64 = 86_400;
+/// Less than this left to buy and the target counts as reached.
+const MIN_REMAINING_USD: f64 = 1.0;
+
+/// The limit order a build currently has on the book.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildOrder {
+    pub order_id: String,
+    pub price: f64,
+    pub size: f64,
+    pub placed_at: i64,
+    /// Tokens of this order already counted in the build's fill.
+    #[serde(default)]
+    pub filled_tokens: f64,
+}
+
+impl BuildOrder {
+    /// USDC the unfilled part of the slice still holds.
+    pub fn locked_usd(&self) -> f64 {
+        (self.size - self.filled_tokens).max(0.0) * self.price
+    }
+}
+
+/// Why a build stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EndReason {
+    Completed,
+    TraderExit,
+    PriceMoved {
+        price: f64,
+    },
+    TimedOut,
+    /// Position cap, market share cap or balance left less than a minimum order.
+    CapReached,
+}
+
+impl EndReason {
+    /// The journal reason and panel label.
+    pub fn describe(&self) -> String {
+        match self {
+            EndReason::Completed => "position build complete".to_string(),
+            EndReason::TraderExit => "position build abandoned: trader exited".to_string(),
+            EndReason::PriceMoved { price } => format!(
+                "position build abandoned: price moved to ${:.3}, outside the band",
+                price
+            ),
+            EndReason::TimedOut => "position build abandoned: BUILD_MAX_HOURS passed".to_string(),
+            EndReason::CapReached => "position build abandoned: no room under the caps".to_string(),
+        }
+    }
+}
+
+/// A target for my position in one outcome, opened by a trader's BUY.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Build {
+    /// Transaction hash of the signal that opened the build; its journal rows carry it.
+    pub id: String,
+    pub asset: String,
+    pub condition_id: String,
+    /// Logical trader (`TRADER_GROUPS` id or address).
+    pub trader: String,
+    pub title: Option<String>,
+    pub outcome: Option<String>,
+    pub target_usd: f64,
+    pub filled_usd: f64,
+    pub filled_tokens: f64,
+    /// The trader's entry price; the band is measured from it.
+    pub anchor_price: f64,
+    pub started_at: i64,
+    pub open_order: Option<BuildOrder>,
+    pub ended_at: Option<i64>,
+    pub end_reason: Option<EndReason>,
+}
+
+/// One step in a build's life. `Fill` carries only the newly matched part.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuildEvent {
+    Started,
+    /// The trader added again; the target grew by `usd`.
+    Extended {
+        usd: f64,
+    },
+    Fill {
+        tokens: f64,
+        usd: f64,
+    },
+    Ended(EndReason),
+}
+
+/// Best bid and ask of an order book.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BookTop {
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+}
+
+impl BookTop {
+    pub fn mid(&self) -> Option<f64> {
+        match (self.bid, self.ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            (bid, ask) => bid.or(ask),
+        }
+    }
+}
+
+/// What the builder does with a build this round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BuildStep {
+    Place { price: f64, tokens: f64 },
+    Wait(&'static str),
+    End(EndReason),
+}
+
+/// A resting BUY price inside the spread: one tick above the bid, or at the bid when the
+/// spread is a single tick (one tick up would take the ask). The tick is the one the bid
+/// is quoted on, a tenth of a cent near either end of the range.
+pub fn inside_spread_price(book: BookTop, ticks: TickRegime) -> Option<f64> {
+    let (bid, ask) = (book.bid?, book.ask?);
+    if ask <= bid {
+        return None;
+    }
+    let tick = ticks.tick_at(bid);
+    let price = if ask - bid > tick + 1e-9 {
+        ticks.round_price(bid + tick)
+    } else {
+        bid
+    };
+    (price > 0.0 && price < 1.0).then_some(price)
+}
+
+impl Build {
+    pub fn remaining_usd(&self) -> f64 {
+        (self.target_usd - self.filled_usd).max(0.0)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.end_reason.is_none()
+    }
+
+    /// Ended over `ENDED_RETENTION_SECS` ago, no longer listed.
+    fn ended_long_ago(&self, now: i64) -> bool {
+        self.ended_at
+            .is_some_and(|at| now - at >= ENDED_RETENTION_SECS)
+    }
+
+    pub fn end(&mut self, reason: EndReason, now: i64) {
+        self.ended_at = Some(now);
+        self.end_reason = Some(reason);
+    }
+
+    /// Takes in the exchange's matched size for the open order; a fill event when it grew.
+    pub fn apply_matched(&mut self, matched_tokens: f64) -> Option<BuildEvent> {
+        let order = self.open_order.as_mut()?;
+        let tokens = matched_tokens.min(order.size) - order.filled_tokens;
+        if tokens <= 1e-9 {
+            return None;
+        }
+        order.filled_tokens += tokens;
+        let usd = tokens * order.price;
+        self.filled_tokens += tokens;
+        self.filled_usd += usd;
+        Some(BuildEvent::Fill { tokens, usd })
+    }
+
+    /// Decides this round's step. `room_usd` is what the caps allow right now (0 when less
+    /// than a minimum order), `min_order_usd` the smallest order worth placing.
+    pub fn next_step(
+        &self,
+        config: &PositionBuildConfig,
+        book: BookTop,
+        room_usd: f64,
+        min_order_usd: f64,
+        ticks: TickRegime,
+        now: i64,
+    ) -> BuildStep {
+        let remaining = self.remaining_usd();
+        if remaining < MIN_REMAINING_USD.max(min_order_usd) {
+            return BuildStep::End(EndReason::Completed);
+        }
+        if now - self.started_at > config.max_duration_secs as i64 {
+            return BuildStep::End(EndReason::TimedOut);
+        }
+        let Some(mid) = book.mid() else {
+            return BuildStep::Wait("empty book");
+        };
+        if (mid - self.anchor_price).abs() > self.anchor_price * config.price_band {
+            return BuildStep::End(EndReason::PriceMoved { price: mid });
+        }
+        if room_usd < min_order_usd.max(MIN_REMAINING_USD) {
+            return BuildStep::End(EndReason::CapReached);
+        }
+        let Some(price) = inside_spread_price(book, ticks) else {
+            return BuildStep::Wait("one-sided book");
+        };
+        let usd = config
+            .slice_usd
+            .max(min_order_usd)
+            .min(remaining)
+            .min(room_usd);
+        BuildStep::Place {
+            price,
+            tokens: usd / price,
+        }
+    }
+
+    /// `$12.00/$50.00 (24%)`.
+    pub fn progress(&self) -> String {
+        let pct = if self.target_usd > 0.0 {
+            self.filled_usd / self.target_usd * 100.0
+        } else {
+            0.0
+        };
+        format!(
+            "${:.2}/${:.2} ({:.0}%)",
+            self.filled_usd, self.target_usd, pct
+        )
+    }
+
+    /// The log and alert line for `event`.
+    pub fn describe(&self, event: &BuildEvent) -> String {
+        let market = self.title.as_deref().unwrap_or(&self.asset);
+        let outcome = self.outcome.as_deref().unwrap_or("?");
+        match event {
+            BuildEvent::Started => format!(
+                "🧱 Building {} ({}): target ${:.2} from @ ${:.3} (copy {})",
+                market,
+                outcome,
+                self.target_usd,
+                self.anchor_price,
+                Logger::format_address(&self.id)
+            ),
+            BuildEvent::Extended { usd } => format!(
+                "🧱 Build target for {} ({}) raised by ${:.2}: {}",
+                market,
+                outcome,
+                usd,
+                self.progress()
+            ),
+            BuildEvent::Fill { tokens, usd } => format!(
+                "🧱 Build fill in {} ({}): {:.2} (${:.2}) - {}",
+                market,
+                outcome,
+                tokens,
+                usd,
+                self.progress()
+            ),
+            BuildEvent::Ended(reason) => format!(
+                "🧱 {} ({}): {} at {}",
+                market,
+                outcome,
+                reason.describe(),
+                self.progress()
+            ),
+        }
+    }
+}
+
+/// Logs `event`; starts and ends also go out as alerts.
+pub fn report(build: &Build, event: &BuildEvent) {
+    let line = build.describe(event);
+    Logger::info(&line);
+    if matches!(event, BuildEvent::Started | BuildEvent::Ended(_)) {
+        crate::alerts::notify(&line);
+    }
+}
+
+/// Adds `new` to the builds, or raises the target of the active build by the same trader in
+/// the same outcome.
+pub fn merge_build(builds: &mut Vec<Build>, new: Build) -> (Build, BuildEvent) {
+    let existing = builds
+        .iter_mut()
+        .find(|b| b.is_active() && b.asset == new.asset && b.trader == new.trader);
+    match existing {
+        Some(build) => {
+            build.target_usd += new.target_usd;
+            (
+                build.clone(),
+                BuildEvent::Extended {
+                    usd: new.target_usd,
+                },
+            )
+        }
+        None => {
+            builds.push(new.clone());
+            (new, BuildEvent::Started)
+        }
+    }
+}
+
+/// Builds per state dir, loaded on first use, so bots with their own `STATE_DIR` in one
+/// process don't share them.
+static BUILDS: Mutex<BTreeMap<String, Vec<Build>>> = Mutex::new(BTreeMap::new());
+
+fn with_builds<T>(state_dir: &str, f: impl FnOnce(&mut Vec<Build>) -> T) -> Option<T> {
+    let mut guard = BUILDS.lock().ok()?;
+    let builds = guard
+        .entry(state_dir.to_string())
+        .or_insert_with(|| load_json(&state_path(state_dir, BUILDS_FILE)).unwrap_or_default());
+    Some(f(builds))
+}
+
+/// `with_builds` for changes: builds ended over a day ago are dropped and the rest saved.
+fn change_builds<T>(state_dir: &str, f: impl FnOnce(&mut Vec<Build>) -> T) -> Option<T> {
+    with_builds(state_dir, |builds| {
+        let result = f(builds);
+        let now = chrono::Utc::now().timestamp();
+        builds.retain(|b| !b.ended_long_ago(now));
+        if let Err(e) = save_json(&state_path(state_dir, BUILDS_FILE), builds) {
+            Logger::warning(&format!("Failed to save position builds: {}", e));
+        }
+        result
+    })
+}
+
+/// Starts a build, or extends the trader's active one in the same outcome.
+pub fn start(state_dir: &str, new: Build) -> Option<(Build, BuildEvent)> {
+    change_builds(state_dir, |builds| merge_build(builds, new))
+}
+
+/// Active builds and those ended within the last day.
+pub fn all(state_dir: &str) -> Vec<Build> {
+    let now = chrono::Utc::now().timestamp();
+    with_builds(state_dir, |builds| {
+        builds
+            .iter()
+            .filter(|b| !b.ended_long_ago(now))
+            .cloned()
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+pub fn active(state_dir: &str) -> Vec<Build> {
+    all(state_dir)
+        .into_iter()
+        .filter(Build::is_active)
+        .collect()
+}
+
+/// Saves a build's progress.
+pub fn save(state_dir: &str, build: &Build) {
+    change_builds(state_dir, |builds| {
+        if let Some(b) = builds.iter_mut().find(|b| b.id == build.id) {
+            *b = build.clone();
+        }
+    });
+}
+
+/// USD still to be bought by active builds in `asset`, counted against caps when a new
+/// target is set.
+pub fn pending_usd(state_dir: &str, asset: &str) -> f64 {
+    active(state_dir)
+        .iter()
+        .filter(|b| b.asset == asset)
+        .map(Build::remaining_usd)
+        .sum()
+}
+
+/// USDC locked by the builds' open slice orders.
+pub fn locked_usd(state_dir: &str) -> f64 {
+    all(state_dir)
+        .iter()
+        .filter_map(|b| b.open_order.as_ref())
+        .map(BuildOrder::locked_usd)
+        .sum()
+}
+
+/// Whether a BUY fill in `asset` could come from one of our builds.
+pub fn covers(state_dir: &str, asset: &str, side: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    side == "BUY"
+        && all(state_dir)
+            .iter()
+            .any(|b| b.asset == asset && b.ended_at.is_none_or(|at| now - at < 300))
+}
+
+/// Active builds in `asset` opened by `trader` (logical id), for abandoning on their exit.
+pub fn exited(state_dir: &str, asset: &str, trader: &str) -> Vec<Build> {
+    active(state_dir)
+        .into_iter()
+        .filter(|b| b.asset == asset && b.trader == trader)
+        .collect()
+}
+
+pub fn ticks(asset: &str) -> TickRegime {
+    order_templates::get(asset)
+        .map(|t| t.ticks())
+        .unwrap_or_default()
+}
+
+/// Takes the build's open order off the book and counts what it filled. The order stays
+/// set when it could be neither cancelled nor read, so the next round tries again.
+pub async fn collect_order(
+    clob_client: &ClobClient<Authenticated<Normal>>,
+    build: &mut Build,
+) -> Vec<BuildEvent> {
+    let Some(order_id) = build.open_order.as_ref().map(|o| o.order_id.clone()) else {
+        return Vec::new();
+    };
+    // A cancel fails for an order that already filled or expired; its status still says
+    // how much matched.
+    let cancelled = clob_client.cancel_order(&order_id).await.is_ok();
+    match resting_orders::fetch_update(clob_client, &order_id).await {
+        Ok(update) => {
+            let events: Vec<BuildEvent> = build
+                .apply_matched(update.matched_tokens)
+                .into_iter()
+                .collect();
+            if cancelled || !update.open {
+                build.open_order = None;
+            }
+            events
+        }
+        Err(e) => {
+            Logger::warning(&format!("Failed to read build order {}: {}", order_id, e));
+            Vec::new()
+        }
+    }
+}
+
+pub fn parse_book_top(book: &Value) -> BookTop {
+    let best = |side: &str, pick: fn(f64, f64) -> f64| {
+        book.get(side)?
+            .as_array()?
+            .iter()
+            .filter_map(|l| l.get("price")?.as_str()?.parse::<f64>().ok())
+            .reduce(pick)
+    };
+    BookTop {
+        bid: best("bids", f64::max),
+        ask: best("asks", f64::min),
+    }
+}
+
+pub async fn fetch_book_top(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> anyhow::Result<BookTop> {
+    let url = format!(
+        "{}/book?token_id={}",
+        config.clob_http_url.trim_end_matches('/'),
+        asset
+    );
+    let book = fetch_data(http_client, &url, config.request_timeout_ms, 1).await?;
+    Ok(parse_book_top(&book))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> PositionBuildConfig {
+        PositionBuildConfig {
+            interval_secs: 60,
+            slice_usd: 10.0,
+            price_band: 0.05,
+            max_duration_secs: 3_600,
+            traders: Vec::new(),
+        }
+    }
+
+    fn build(target_usd: f64) -> Build {
+        Build {
+            id: "0xcopy".to_string(),
+            asset: "yes".to_string(),
+            condition_id: "0xmarket".to_string(),
+            trader: "0xtrader".to_string(),
+            title: Some("Will it rain?".to_string()),
+            outcome: Some("Yes".to_string()),
+            target_usd,
+            filled_usd: 0.0,
+            filled_tokens: 0.0,
+            anchor_price: 0.40,
+            started_at: 1_000,
+            open_order: None,
+            ended_at: None,
+            end_reason: None,
+        }
+    }
+
+    const CENT: TickRegime = TickRegime::new(0.01);
+
+    fn book(bid: f64, ask: f64) -> BookTop {
+        BookTop {
+            bid: Some(bid),
+            ask: Some(ask),
+        }
+    }
+
+    #[test]
+    fn orders_rest_inside_the_spread() {
+        assert_eq!(inside_spread_price(book(0.38, 0.42), CENT), Some(0.39));
+        assert_eq!(inside_spread_price(book(0.40, 0.41), CENT), Some(0.40));
+        assert_eq!(inside_spread_price(book(0.41, 0.40), CENT), None);
+        let one_sided = BookTop {
+            bid: None,
+            ask: Some(0.41),
+        };
+        assert_eq!(inside_spread_price(one_sided, CENT), None);
+    }
+
+    #[test]
+    fn extreme_books_step_by_the_fine_tick() {
+        // Buying a longshot or a near-certainty: one tenth of a cent over the bid.
+        assert_eq!(inside_spread_price(book(0.004, 0.007), CENT), Some(0.005));
+        assert_eq!(inside_spread_price(book(0.048, 0.049), CENT), Some(0.048));
+        assert_eq!(inside_spread_price(book(0.951, 0.96), CENT), Some(0.952));
+        assert_eq!(inside_spread_price(book(0.996, 0.998), CENT), Some(0.997));
+    }
+
+    #[test]
+    fn slices_respect_the_remaining_target_and_the_caps() {
+        let b = build(50.0);
+        let step = b.next_step(&config(), book(0.38, 0.42), 1_000.0, 1.0, CENT, 1_060);
+        assert_eq!(
+            step,
+            BuildStep::Place {
+                price: 0.39,
+                tokens: 10.0 / 0.39
+            }
+        );
+        // The position cap leaves $4: the slice shrinks to fit.
+        let BuildStep::Place { tokens, .. } =
+            b.next_step(&config(), book(0.38, 0.42), 4.0, 1.0, CENT, 1_060)
+        else {
+            panic!("expected an order");
+        };
+        assert!((tokens * 0.39 - 4.0).abs() < 1e-9);
+        // No room left at all: the rest is abandoned.
+        assert_eq!(
+            b.next_step(&config(), book(0.38, 0.42), 0.0, 1.0, CENT, 1_060),
+            BuildStep::End(EndReason::CapReached)
+        );
+    }
+
+    #[test]
+    fn builds_end_on_target_band_or_timeout() {
+        let mut b = build(50.0);
+        let cfg = config();
+        assert_eq!(
+            b.next_step(&cfg, book(0.44, 0.46), 100.0, 1.0, CENT, 1_060),
+            BuildStep::End(EndReason::PriceMoved { price: 0.45 })
+        );
+        assert_eq!(
+            b.next_step(&cfg, book(0.38, 0.42), 100.0, 1.0, CENT, 1_000 + 3_601),
+            BuildStep::End(EndReason::TimedOut)
+        );
+        assert_eq!(
+            b.next_step(&cfg, BookTop::default(), 100.0, 1.0, CENT, 1_060),
+            BuildStep::Wait("empty book")
+        );
+        b.filled_usd = 49.5;
+        assert_eq!(
+            b.next_step(&cfg, book(0.38, 0.42), 100.0, 1.0, CENT, 1_060),
+            BuildStep::End(EndReason::Completed)
+        );
+    }
+
+    #[test]
+    fn fills_count_once_per_order() {
+        let mut b = build(50.0);
+        assert_eq!(b.apply_matched(5.0), None);
+        b.open_order = Some(BuildOrder {
+            order_id: "o1".to_string(),
+            price: 0.40,
+            size: 25.0,
+            placed_at: 1_000,
+            filled_tokens: 0.0,
+        });
+        assert_eq!(
+            b.apply_matched(10.0),
+            Some(BuildEvent::Fill {
+                tokens: 10.0,
+                usd: 4.0
+            })
+        );
+        assert_eq!(b.apply_matched(10.0), None);
+        assert!(
+            matches!(b.apply_matched(40.0), Some(BuildEvent::Fill { tokens, .. }) if tokens == 15.0)
+        );
+        assert_eq!(b.filled_tokens, 25.0);
+        assert!((b.filled_usd - 10.0).abs() < 1e-9);
+        assert_eq!(b.progress(), "$10.00/$50.00 (20%)");
+    }
+
+    #[test]
+    fn a_second_buy_extends_the_active_build() {
+        let mut builds = Vec::new();
+        assert_eq!(merge_build(&mut builds, build(50.0)).1, BuildEvent::Started);
+        let (merged, event) = merge_build(&mut builds, build(20.0));
+        assert_eq!(event, BuildEvent::Extended { usd: 20.0 });
+        assert_eq!(merged.target_usd, 70.0);
+        assert_eq!(builds.len(), 1);
+
+        builds[0].end(EndReason::TraderExit, 2_000);
+        assert_eq!(merge_build(&mut builds, build(30.0)).1, BuildEvent::Started);
+        assert_eq!(builds.len(), 2);
+    }
+
+    #[test]
+    fn each_state_dir_has_its_own_builds_and_reads_save_nothing() {
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+        start(a, build(50.0));
+        assert_eq!(all(a).len(), 1);
+        assert!(all(b).is_empty());
+        assert_eq!(locked_usd(b), 0.0);
+        assert!(!covers(b, "yes", "BUY"));
+        assert!(!state_path(b, BUILDS_FILE).exists());
+
+        let mut ended = all(a).remove(0);
+        ended.end(EndReason::TraderExit, chrono::Utc::now().timestamp());
+        save(a, &ended);
+        let saved: Vec<Build> = load_json(&state_path(a, BUILDS_FILE)).unwrap();
+        assert!(saved.iter().all(|b| !b.is_active()));
+    }
+
+    #[test]
+    fn book_top_reads_best_levels() {
+        let top = parse_book_top(&json!({
+            "bids": [{ "price": "0.37", "size": "10" }, { "price": "0.38", "size": "5" }],
+            "asks": [{ "price": "0.45", "size": "10" }, { "price": "0.42", "size": "5" }],
+        }));
+        assert_eq!(top, book(0.38, 0.42));
+        assert!((top.mid().unwrap() - 0.40).abs() < 1e-9);
+        assert_eq!(parse_book_top(&json!({ "bids": [] })), BookTop::default());
+    }
+}
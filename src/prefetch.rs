@@ -0,0 +1,315 @@
+//! Prefetch for the first signal in a market the order template cache hasn't seen: the
+//! template parts, the book snapshot and the end date are looked up concurrently under one
+//! deadline (`MARKET_PREFETCH_DEADLINE_MS`). Lookups that miss it are left out and the copy
+//! goes ahead degraded instead of waiting.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::EnvConfig;
+use crate::order_templates::{self, parse_token_id, OrderTemplate};
+use crate::profiling;
+use crate::resolution::market_end_time;
+use crate::utils::{fetch_market_context, MarketContext};
+
+/// The lookups a new market needs, so tests can stand in for the CLOB and Gamma.
+pub trait MarketData: Sync {
+    fn tick_size(&self, asset: &str) -> impl Future<Output = Result<f64>> + Send;
+    fn neg_risk(&self, asset: &str) -> impl Future<Output = Result<bool>> + Send;
+    fn fee_rate_bps(&self, asset: &str) -> impl Future<Output = Result<u64>> + Send;
+    fn book(&self, asset: &str) -> impl Future<Output = MarketContext> + Send;
+    fn end_time(&self, condition_id: &str) -> impl Future<Output = Option<DateTime<Utc>>> + Send;
+}
+
+pub struct ClobMarketData<'a> {
+    pub http_client: &'a reqwest::Client,
+    pub config: &'a EnvConfig,
+}
+
+impl MarketData for ClobMarketData<'_> {
+    async fn tick_size(&self, asset: &str) -> Result<f64> {
+        order_templates::fetch_tick_size(self.http_client, self.config, asset).await
+    }
+    async fn neg_risk(&self, asset: &str) -> Result<bool> {
+        order_templates::fetch_neg_risk(self.http_client, self.config, asset).await
+    }
+    async fn fee_rate_bps(&self, asset: &str) -> Result<u64> {
+        order_templates::fetch_fee_rate_bps(self.http_client, self.config, asset).await
+    }
+    async fn book(&self, asset: &str) -> MarketContext {
+        fetch_market_context(self.http_client, self.config, asset).await
+    }
+    async fn end_time(&self, condition_id: &str) -> Option<DateTime<Utc>> {
+        market_end_time(self.http_client, self.config, None, Some(condition_id)).await
+    }
+}
+
+/// Optional lookups; the template parts are always fetched.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefetchPlan {
+    /// The book snapshot the journal records (`JOURNAL_MARKET_CONTEXT`).
+    pub book: bool,
+    /// The end date the resolution window rule checks.
+    pub end_time: bool,
+}
+
+/// Which lookups answered in time, journaled with the copy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrefetchReport {
+    pub completed: Vec<String>,
+    /// Lookups that failed or were cut off by the deadline.
+    pub missed: Vec<String>,
+    /// Wall time per lookup, at most the deadline.
+    pub latency_ms: BTreeMap<String, u64>,
+}
+
+impl PrefetchReport {
+    /// Something was missing, so the copy is sized down.
+    pub fn is_degraded(&self) -> bool {
+        !self.missed.is_empty()
+    }
+
+    fn record<T>(&mut self, name: &str, lookup: Timed<T>) -> Option<T> {
+        self.latency_ms
+            .insert(name.to_string(), lookup.elapsed.as_millis() as u64);
+        if lookup.answered && lookup.value.is_some() {
+            self.completed.push(name.to_string());
+        } else {
+            self.missed.push(name.to_string());
+        }
+        lookup.value
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Prefetch {
+    /// Set when the tick size, neg-risk flag and fee rate all arrived.
+    pub template: Option<OrderTemplate>,
+    pub market: Option<MarketContext>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub report: PrefetchReport,
+}
+
+/// Result of one lookup: how long it ran, whether it beat the deadline, and its value.
+struct Timed<T> {
+    elapsed: Duration,
+    answered: bool,
+    value: Option<T>,
+}
+
+async fn timed<T>(
+    stage: &'static str,
+    deadline: Instant,
+    lookup: impl Future<Output = Option<T>>,
+) -> Timed<T> {
+    let _timer = profiling::stage(stage);
+    let started = Instant::now();
+    let result = tokio::time::timeout_at(deadline, lookup).await;
+    Timed {
+        elapsed: started.elapsed(),
+        answered: result.is_ok(),
+        value: result.ok().flatten(),
+    }
+}
+
+/// Runs every lookup `plan` asks for at once and stops waiting at `deadline`.
+pub async fn prefetch<M: MarketData>(
+    data: &M,
+    asset: &str,
+    condition_id: Option<&str>,
+    plan: PrefetchPlan,
+    deadline: Duration,
+) -> Prefetch {
+    let deadline = Instant::now() + deadline;
+    let end_time_cid = condition_id.filter(|_| plan.end_time);
+    let (tick, neg_risk, fee, book, end_time) = tokio::join!(
+        timed("prefetch_tick_size", deadline, async {
+            data.tick_size(asset).await.ok()
+        }),
+        timed("prefetch_neg_risk", deadline, async {
+            data.neg_risk(asset).await.ok()
+        }),
+        timed("prefetch_fee_rate", deadline, async {
+            data.fee_rate_bps(asset).await.ok()
+        }),
+        async {
+            if plan.book {
+                let book = async { Some(data.book(asset).await) };
+                Some(timed("prefetch_book", deadline, book).await)
+            } else {
+                None
+            }
+        },
+        async {
+            match end_time_cid {
+                // A market without a reliable end date still answered.
+                Some(cid) => Some(
+                    timed("prefetch_end_time", deadline, async {
+                        Some(data.end_time(cid).await)
+                    })
+                    .await,
+                ),
+                None => None,
+            }
+        },
+    );
+
+    let mut report = PrefetchReport::default();
+    let tick = report.record("tick_size", tick);
+    let neg_risk = report.record("neg_risk", neg_risk);
+    let fee = report.record("fee_rate", fee);
+    let market = book.and_then(|b| report.record("book", b));
+    let end_time = end_time
+        .and_then(|e| report.record("end_time", e))
+        .flatten();
+    let template = match (tick, neg_risk, fee, parse_token_id(asset)) {
+        (Some(tick_size), Some(neg_risk), Some(fee_rate_bps), Ok(token_id)) => {
+            Some(OrderTemplate {
+                token_id,
+                tick_size,
+                neg_risk,
+                fee_rate_bps,
+            })
+        }
+        _ => None,
+    };
+    Prefetch {
+        template,
+        market,
+        end_time,
+        report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSET: &str =
+        "71321045679252212594626385532706912750332728571942532289631379312455583992563";
+    const CID: &str = "0x5f65177b394277fd294cd75650044e32ba009a95022d88a0c1d565897d72f8f1";
+    const DEADLINE: Duration = Duration::from_millis(1_500);
+
+    /// Answers every lookup after its own delay.
+    struct SlowMarket {
+        tick_ms: u64,
+        neg_risk_ms: u64,
+        fee_ms: u64,
+        book_ms: u64,
+        end_ms: u64,
+    }
+
+    impl SlowMarket {
+        fn uniform(ms: u64) -> Self {
+            Self {
+                tick_ms: ms,
+                neg_risk_ms: ms,
+                fee_ms: ms,
+                book_ms: ms,
+                end_ms: ms,
+            }
+        }
+    }
+
+    async fn after<T>(ms: u64, value: T) -> T {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        value
+    }
+
+    impl MarketData for SlowMarket {
+        async fn tick_size(&self, _: &str) -> Result<f64> {
+            Ok(after(self.tick_ms, 0.01).await)
+        }
+        async fn neg_risk(&self, _: &str) -> Result<bool> {
+            Ok(after(self.neg_risk_ms, true).await)
+        }
+        async fn fee_rate_bps(&self, _: &str) -> Result<u64> {
+            Ok(after(self.fee_ms, 0).await)
+        }
+        async fn book(&self, _: &str) -> MarketContext {
+            after(
+                self.book_ms,
+                MarketContext {
+                    best_ask: Some(0.59),
+                    ..Default::default()
+                },
+            )
+            .await
+        }
+        async fn end_time(&self, _: &str) -> Option<DateTime<Utc>> {
+            after(self.end_ms, DateTime::from_timestamp(1_800_000_000, 0)).await
+        }
+    }
+
+    const ALL: PrefetchPlan = PrefetchPlan {
+        book: true,
+        end_time: true,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn lookups_run_concurrently() {
+        let started = Instant::now();
+        let fetched = prefetch(&SlowMarket::uniform(400), ASSET, Some(CID), ALL, DEADLINE).await;
+        // Five 400ms lookups in parallel take 400ms, not 2s.
+        assert_eq!(started.elapsed(), Duration::from_millis(400));
+        assert!(!fetched.report.is_degraded());
+        assert_eq!(fetched.report.completed.len(), 5);
+        let template = fetched.template.unwrap();
+        assert!(template.neg_risk);
+        assert_eq!(template.tick_size, 0.01);
+        assert_eq!(fetched.market.unwrap().best_ask, Some(0.59));
+        assert!(fetched.end_time.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_lookups_are_cut_off_at_the_deadline() {
+        let market = SlowMarket {
+            tick_ms: 50,
+            neg_risk_ms: 2_000,
+            fee_ms: 120,
+            book_ms: 300,
+            end_ms: 10_000,
+        };
+        let started = Instant::now();
+        let fetched = prefetch(&market, ASSET, Some(CID), ALL, DEADLINE).await;
+        assert_eq!(started.elapsed(), DEADLINE);
+
+        let report = &fetched.report;
+        assert!(report.is_degraded());
+        assert_eq!(report.completed, vec!["tick_size", "fee_rate", "book"]);
+        assert_eq!(report.missed, vec!["neg_risk", "end_time"]);
+        assert_eq!(report.latency_ms["tick_size"], 50);
+        assert_eq!(report.latency_ms["book"], 300);
+        assert_eq!(report.latency_ms["neg_risk"], 1_500);
+        // Without the neg-risk flag there is no template to sign against.
+        assert!(fetched.template.is_none());
+        assert!(fetched.market.is_some());
+        assert!(fetched.end_time.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn optional_lookups_are_skipped_when_not_planned() {
+        let started = Instant::now();
+        let fetched = prefetch(
+            &SlowMarket {
+                book_ms: 5_000,
+                end_ms: 5_000,
+                ..SlowMarket::uniform(100)
+            },
+            ASSET,
+            Some(CID),
+            PrefetchPlan::default(),
+            DEADLINE,
+        )
+        .await;
+        assert_eq!(started.elapsed(), Duration::from_millis(100));
+        assert_eq!(fetched.report.completed, vec!["tick_size", "neg_risk", "fee_rate"]);
+        assert!(fetched.report.missed.is_empty());
+        assert!(fetched.template.is_some());
+    }
+}
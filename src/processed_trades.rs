@@ -0,0 +1,187 @@
+//! Dedupe history for copied signals, keyed by `RtdsActivity::dedupe_key`.
+//!
+//! Kept in `STATE_FILE` (default `STATE_DIR/processed_trades.json`) so a restart right after a
+//! trade does not copy it again when the activity is re-delivered. Entries age out after
+//! `PROCESSED_TRADES_RETENTION_HOURS` instead of the whole history being dropped at once.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::utils::{load_json, save_json, Logger};
+
+/// What one executor run has seen: key to the unix time it was first processed.
+#[derive(Debug, Default)]
+pub struct ProcessedTrades {
+    seen: HashMap<String, i64>,
+    retention_secs: i64,
+    path: Option<PathBuf>,
+    /// Bumped on every change, so an older snapshot never overwrites a newer one on disk.
+    generation: u64,
+    written: Arc<std::sync::Mutex<u64>>,
+}
+
+/// A copy of the store taken under the executor's lock, written to disk off it.
+pub struct PendingWrite {
+    path: PathBuf,
+    generation: u64,
+    seen: HashMap<String, i64>,
+    written: Arc<std::sync::Mutex<u64>>,
+}
+
+impl ProcessedTrades {
+    /// A store that is never written to disk.
+    pub fn in_memory(retention_hours: u64) -> Self {
+        Self {
+            retention_secs: retention_hours as i64 * 3600,
+            ..Self::default()
+        }
+    }
+
+    /// Reopens the history at `path`, dropping entries that aged out while the bot was down.
+    pub fn load(path: impl Into<PathBuf>, retention_hours: u64, now: i64) -> Self {
+        let path = path.into();
+        let mut store = Self::in_memory(retention_hours);
+        store.seen = load_json(&path).unwrap_or_default();
+        store.path = Some(path);
+        store.prune(now);
+        store
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.seen.contains_key(key)
+    }
+
+    /// Records `key` as processed at `now`. False when it already was, in which case the
+    /// signal is a duplicate and nothing changes.
+    pub fn record(&mut self, key: &str, now: i64) -> bool {
+        if self.seen.contains_key(key) {
+            return false;
+        }
+        self.prune(now);
+        self.seen.insert(key.to_string(), now);
+        self.generation += 1;
+        true
+    }
+
+    fn prune(&mut self, now: i64) {
+        let cutoff = now - self.retention_secs;
+        let before = self.seen.len();
+        self.seen.retain(|_, seen_at| *seen_at > cutoff);
+        if self.seen.len() != before {
+            self.generation += 1;
+        }
+    }
+
+    /// The write for the current state, or None for an in-memory store.
+    pub fn snapshot(&self) -> Option<PendingWrite> {
+        Some(PendingWrite {
+            path: self.path.clone()?,
+            generation: self.generation,
+            seen: self.seen.clone(),
+            written: self.written.clone(),
+        })
+    }
+}
+
+impl PendingWrite {
+    /// Writes the snapshot unless a newer one already landed. Failures only warn: losing
+    /// the history costs a possible duplicate after a restart, never the trade at hand.
+    pub fn write(self) {
+        let mut written = self.written.lock().unwrap_or_else(|e| e.into_inner());
+        if *written >= self.generation {
+            return;
+        }
+        match save_json(&self.path, &self.seen) {
+            Ok(()) => *written = self.generation,
+            Err(e) => Logger::warning(&format!(
+                "Failed to persist processed trades to {}: {}",
+                self.path.display(),
+                e
+            )),
+        }
+    }
+
+    /// Writes on the blocking pool so the copy that triggered it carries on meanwhile.
+    pub fn spawn(self) {
+        tokio::task::spawn_blocking(move || self.write());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_012_800;
+
+    #[test]
+    fn duplicates_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("processed_trades.json");
+
+        let mut store = ProcessedTrades::load(&path, 24, NOW);
+        assert!(store.record("0xtrader:0xabc", NOW));
+        assert!(!store.record("0xtrader:0xabc", NOW + 5));
+        store.snapshot().unwrap().write();
+
+        let mut reopened = ProcessedTrades::load(&path, 24, NOW + 60);
+        assert!(reopened.contains("0xtrader:0xabc"));
+        assert!(!reopened.record("0xtrader:0xabc", NOW + 60));
+        assert!(reopened.record("0xtrader:0xdef", NOW + 60));
+    }
+
+    #[test]
+    fn entries_age_out_one_by_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("processed_trades.json");
+
+        let mut store = ProcessedTrades::load(&path, 1, NOW);
+        store.record("a:0x1", NOW);
+        store.record("a:0x2", NOW + 1800);
+        store.snapshot().unwrap().write();
+
+        // An hour and a bit later only the first has aged out.
+        let mut reopened = ProcessedTrades::load(&path, 1, NOW + 3700);
+        assert_eq!(reopened.len(), 1);
+        assert!(reopened.contains("a:0x2"));
+        assert!(reopened.record("a:0x1", NOW + 3700));
+        assert!(reopened.record("a:0x3", NOW + 5500));
+        assert!(!reopened.contains("a:0x2"));
+    }
+
+    #[test]
+    fn an_older_snapshot_never_overwrites_a_newer_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("processed_trades.json");
+
+        let mut store = ProcessedTrades::load(&path, 24, NOW);
+        store.record("a:0x1", NOW);
+        let older = store.snapshot().unwrap();
+        store.record("a:0x2", NOW);
+        store.snapshot().unwrap().write();
+        older.write();
+
+        assert_eq!(ProcessedTrades::load(&path, 24, NOW).len(), 2);
+    }
+
+    #[test]
+    fn an_unwritable_path_only_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("file");
+        std::fs::write(&blocker, "").unwrap();
+
+        let mut store = ProcessedTrades::load(blocker.join("processed_trades.json"), 24, NOW);
+        assert!(store.record("a:0x1", NOW));
+        store.snapshot().unwrap().write();
+        assert!(!store.record("a:0x1", NOW));
+        assert!(ProcessedTrades::in_memory(24).snapshot().is_none());
+    }
+}
@@ -0,0 +1,113 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::utils::Logger;
+
+/// Per-stage wall time of the copy path, recorded only when `--profile-hotpath` is passed.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STAGES: Mutex<BTreeMap<&'static str, StageStats>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageStats {
+    pub count: u64,
+    pub total: Duration,
+    pub max: Duration,
+    pub last: Duration,
+}
+
+impl StageStats {
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count as u32
+        }
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts timing `stage`; the time is recorded when the guard drops.
+pub fn stage(name: &'static str) -> StageGuard {
+    StageGuard {
+        name,
+        started: enabled().then(Instant::now),
+    }
+}
+
+pub struct StageGuard {
+    name: &'static str,
+    started: Option<Instant>,
+}
+
+impl Drop for StageGuard {
+    fn drop(&mut self) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        if let Ok(mut stages) = STAGES.lock() {
+            let stats = stages.entry(self.name).or_default();
+            stats.count += 1;
+            stats.total += elapsed;
+            stats.max = stats.max.max(elapsed);
+            stats.last = elapsed;
+        }
+    }
+}
+
+/// Snapshot of every stage recorded so far.
+pub fn snapshot() -> Vec<(&'static str, StageStats)> {
+    STAGES
+        .lock()
+        .map(|s| s.iter().map(|(k, v)| (*k, *v)).collect())
+        .unwrap_or_default()
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// Every stage's timings in milliseconds, as `/status` shows them under `hotpath`.
+pub fn snapshot_json() -> Value {
+    let stages: Map<String, Value> = snapshot()
+        .into_iter()
+        .map(|(name, s)| {
+            let stats = json!({
+                "count": s.count,
+                "avg_ms": millis(s.average()),
+                "max_ms": millis(s.max),
+                "last_ms": millis(s.last),
+            });
+            (name.to_string(), stats)
+        })
+        .collect();
+    Value::Object(stages)
+}
+
+pub fn log_snapshot() {
+    let stages = snapshot();
+    if stages.is_empty() {
+        return;
+    }
+    Logger::info("Hot path stage timings (count / avg / max / last):");
+    for (name, s) in stages {
+        Logger::info(&format!(
+            "  {:<18} {:>6} / {:>8.1}ms / {:>8.1}ms / {:>8.1}ms",
+            name,
+            s.count,
+            millis(s.average()),
+            millis(s.max),
+            millis(s.last)
+        ));
+    }
+}
@@ -0,0 +1,208 @@
+//! Works out `PROXY_WALLET` when it is left unset. Polymarket deploys each account's wallet
+//! at a CREATE2 address fixed by the signer: a minimal proxy for email/Magic logins, a 1-of-1
+//! Gnosis Safe for browser wallets. Both candidates are derived and the chain decides which
+//! one the account actually uses.
+
+use alloy::primitives::Address;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{Context, Result};
+use polymarket_client_sdk::{derive_proxy_wallet, derive_safe_wallet, POLYGON};
+use std::str::FromStr;
+
+use crate::config::EnvConfig;
+use crate::utils::{get_usdc_balance, is_contract_address, Logger};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletKind {
+    /// Email/Magic accounts.
+    Proxy,
+    /// Browser-wallet accounts.
+    Safe,
+}
+
+impl WalletKind {
+    fn describe(self) -> &'static str {
+        match self {
+            WalletKind::Proxy => "email/Magic proxy",
+            WalletKind::Safe => "browser-wallet Safe",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub kind: WalletKind,
+    pub address: Address,
+}
+
+/// What the chain says about a candidate: deployed code, or USDC sent there before the
+/// wallet was deployed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub has_code: bool,
+    pub usdc: f64,
+}
+
+impl Usage {
+    fn in_use(self) -> bool {
+        self.has_code || self.usdc > 0.0
+    }
+}
+
+/// The wallets Polymarket would deploy for `eoa` on Polygon.
+pub fn candidates(eoa: Address) -> Vec<Candidate> {
+    [
+        (WalletKind::Proxy, derive_proxy_wallet(eoa, POLYGON)),
+        (WalletKind::Safe, derive_safe_wallet(eoa, POLYGON)),
+    ]
+    .into_iter()
+    .filter_map(|(kind, address)| {
+        Some(Candidate {
+            kind,
+            address: address?,
+        })
+    })
+    .collect()
+}
+
+/// The single candidate in use. Neither or both is an error naming every candidate, since
+/// only the user can tell which account the bot should trade from.
+pub fn choose(eoa: Address, checked: &[(Candidate, Usage)]) -> Result<Candidate> {
+    let in_use: Vec<Candidate> = checked
+        .iter()
+        .filter(|(_, usage)| usage.in_use())
+        .map(|(c, _)| *c)
+        .collect();
+    if let [only] = in_use.as_slice() {
+        return Ok(*only);
+    }
+    let listed: Vec<String> = checked
+        .iter()
+        .map(|(c, usage)| {
+            format!(
+                "{} {} ({}, ${:.2} USDC)",
+                c.kind.describe(),
+                c.address,
+                if usage.has_code {
+                    "deployed"
+                } else {
+                    "not deployed"
+                },
+                usage.usdc
+            )
+        })
+        .collect();
+    let problem = if in_use.is_empty() {
+        "none of the wallets Polymarket derives for it is deployed or funded"
+    } else {
+        "more than one of the wallets Polymarket derives for it is in use"
+    };
+    anyhow::bail!(
+        "PROXY_WALLET is unset and could not be derived for signer {}: {}. Candidates: {}. \
+         Set PROXY_WALLET to the address shown on your Polymarket profile",
+        eoa,
+        problem,
+        listed.join("; ")
+    )
+}
+
+/// Derives the wallet for `config.private_key` and checks each candidate on chain.
+pub async fn resolve(config: &EnvConfig) -> Result<String> {
+    let signer = PrivateKeySigner::from_str(&format!("0x{}", config.private_key))
+        .map_err(|e| anyhow::anyhow!("Invalid private key: {}", e))?;
+    let eoa = signer.address();
+    let mut checked = Vec::new();
+    for candidate in candidates(eoa) {
+        let address = candidate.address.to_string();
+        let has_code = is_contract_address(&config.rpc_url, &address)
+            .await
+            .with_context(|| format!("Checking derived wallet {} on chain", address))?;
+        let usdc = get_usdc_balance(&config.rpc_url, &config.usdc_contract_address, &address)
+            .await
+            .with_context(|| format!("Reading the USDC balance of derived wallet {}", address))?;
+        checked.push((candidate, Usage { has_code, usdc }));
+    }
+    let chosen = choose(eoa, &checked)?;
+    let address = chosen.address.to_string().to_lowercase();
+    if config.user_addresses.contains(&address) {
+        anyhow::bail!(
+            "Derived PROXY_WALLET {} is listed as a trader; the bot would copy its own orders",
+            address
+        );
+    }
+    Logger::info(&format!(
+        "PROXY_WALLET derived from signer {}: {} {}",
+        eoa,
+        chosen.kind.describe(),
+        chosen.address
+    ));
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    /// The first Anvil/Foundry test account.
+    const EOA: Address = address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+
+    fn derived() -> (Candidate, Candidate) {
+        let all = candidates(EOA);
+        assert_eq!(all.len(), 2);
+        (all[0], all[1])
+    }
+
+    #[test]
+    fn derives_both_wallet_types() {
+        let (proxy, safe) = derived();
+        assert_eq!(proxy.kind, WalletKind::Proxy);
+        assert_eq!(
+            proxy.address,
+            address!("0x365f0cA36ae1F641E02Fe3b7743673DA42A13a70")
+        );
+        assert_eq!(safe.kind, WalletKind::Safe);
+        assert_eq!(
+            safe.address,
+            address!("0xd93b25Cb943D14d0d34FBAf01fc93a0F8b5f6e47")
+        );
+    }
+
+    #[test]
+    fn picks_the_one_candidate_in_use() {
+        let (proxy, safe) = derived();
+        let deployed = Usage {
+            has_code: true,
+            usdc: 0.0,
+        };
+        let funded = Usage {
+            has_code: false,
+            usdc: 12.5,
+        };
+        let chosen = choose(EOA, &[(proxy, Usage::default()), (safe, deployed)]).unwrap();
+        assert_eq!(chosen, safe);
+        // USDC sent before the first trade deployed the wallet counts too.
+        let chosen = choose(EOA, &[(proxy, funded), (safe, Usage::default())]).unwrap();
+        assert_eq!(chosen, proxy);
+    }
+
+    #[test]
+    fn neither_or_both_lists_the_candidates() {
+        let (proxy, safe) = derived();
+        let none = choose(EOA, &[(proxy, Usage::default()), (safe, Usage::default())])
+            .unwrap_err()
+            .to_string();
+        assert!(none.contains("none of the wallets"), "{}", none);
+        assert!(none.contains(&proxy.address.to_string()));
+        assert!(none.contains(&safe.address.to_string()));
+
+        let deployed = Usage {
+            has_code: true,
+            usdc: 0.0,
+        };
+        let both = choose(EOA, &[(proxy, deployed), (safe, deployed)])
+            .unwrap_err()
+            .to_string();
+        assert!(both.contains("more than one"), "{}", both);
+    }
+}
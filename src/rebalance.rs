@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::{EnvConfig, RebalancePolicy};
+use crate::skip_reason::{Skip, SkipReason};
+use crate::types::RtdsActivity;
+use crate::utils::fetch_data;
+
+/// Signals from one trader inside one neg-risk event, held until the window closes.
+#[derive(Debug)]
+pub struct SignalGroup {
+    pub trader: String,
+    pub event_slug: String,
+    pub legs: Vec<RtdsActivity>,
+    deadline: Instant,
+}
+
+impl SignalGroup {
+    /// A rebalance sells at least one outcome and buys a different one.
+    pub fn is_rebalance(&self) -> bool {
+        let sold: Vec<&str> = self
+            .legs
+            .iter()
+            .filter(|l| l.side.as_deref() == Some("SELL"))
+            .filter_map(|l| l.asset.as_deref())
+            .collect();
+        self.legs.iter().any(|l| {
+            l.side.as_deref() == Some("BUY")
+                && l.asset.as_deref().is_some_and(|a| !sold.contains(&a))
+        }) && !sold.is_empty()
+    }
+
+    /// Id written on every leg's journal entry so the legs can be joined later.
+    pub fn plan_id(&self) -> String {
+        let first_ts = self
+            .legs
+            .iter()
+            .filter_map(|l| l.timestamp)
+            .min()
+            .unwrap_or(0);
+        format!(
+            "rb-{}-{}-{}",
+            self.event_slug,
+            &self.trader[..self.trader.len().min(8)],
+            first_ts
+        )
+    }
+}
+
+/// Groups signals per (trader, event) for `REBALANCE_WINDOW_SECS` after the first one.
+#[derive(Default)]
+pub struct PendingGroups {
+    groups: HashMap<(String, String), SignalGroup>,
+}
+
+impl PendingGroups {
+    pub fn hold(&mut self, window_secs: u64, trader: &str, event_slug: &str, leg: RtdsActivity) {
+        self.groups
+            .entry((trader.to_string(), event_slug.to_string()))
+            .or_insert_with(|| SignalGroup {
+                trader: trader.to_string(),
+                event_slug: event_slug.to_string(),
+                legs: Vec::new(),
+                deadline: Instant::now() + Duration::from_secs(window_secs),
+            })
+            .legs
+            .push(leg);
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.groups.values().map(|g| g.deadline).min()
+    }
+
+    /// Removes and returns every group whose window has closed (all of them if `all`).
+    pub fn take_expired(&mut self, all: bool) -> Vec<SignalGroup> {
+        let now = Instant::now();
+        let expired: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|(_, g)| all || g.deadline <= now)
+            .map(|(k, _)| k.clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|k| self.groups.remove(&k))
+            .collect()
+    }
+}
+
+/// Why a leg of a rebalance plan is not copied: its own check failed, or, under
+/// `ALL_OR_NOTHING`, another leg's did. `None` means the leg goes ahead.
+pub fn leg_skip_reason(
+    blocked: Option<&str>,
+    policy: RebalancePolicy,
+    plan_blocked: bool,
+) -> Option<Skip> {
+    let detail = match (blocked, policy, plan_blocked) {
+        (Some(r), _, _) => format!("leg blocked: {}", r),
+        (None, RebalancePolicy::AllOrNothing, true) => "another leg was blocked".to_string(),
+        _ => return None,
+    };
+    Some(Skip::new(SkipReason::Rebalance, detail))
+}
+
+/// `SELL Yes`-style name for a leg in plan logs and skip details.
+pub fn leg_label(leg: &RtdsActivity) -> String {
+    format!(
+        "{} {}",
+        leg.side.as_deref().unwrap_or("?"),
+        leg.outcome.as_deref().unwrap_or("?")
+    )
+}
+
+/// Skip for the legs after one that didn't go through under `ALL_OR_NOTHING`.
+pub fn plan_stopped(failed_leg: &str) -> Skip {
+    Skip::new(
+        SkipReason::Rebalance,
+        format!("plan stopped: {} did not go through", failed_leg),
+    )
+}
+
+/// The alert for a plan that went through only in part, naming the legs that did not run.
+/// `None` when nothing is left half-applied.
+pub fn partial_plan_report(
+    plan_id: &str,
+    went_through: &[String],
+    missed: &[String],
+) -> Option<String> {
+    if went_through.is_empty() || missed.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Rebalance plan {} half-applied: {} went through, {} did not",
+        plan_id,
+        went_through.join(", "),
+        missed.join(", ")
+    ))
+}
+
+/// Caches the CLOB's neg-risk flag per token; it never changes for a market.
+#[derive(Default)]
+pub struct NegRiskCache {
+    flags: HashMap<String, bool>,
+}
+
+impl NegRiskCache {
+    /// Whether `asset` belongs to a neg-risk event. Lookup failures count as "no", so the
+    /// signal is copied immediately as before.
+    pub async fn is_neg_risk(
+        &mut self,
+        http_client: &reqwest::Client,
+        config: &EnvConfig,
+        asset: &str,
+    ) -> bool {
+        if let Some(flag) = self.flags.get(asset) {
+            return *flag;
+        }
+        let url = format!("{}/neg-risk?token_id={}", config.clob_http_url, asset);
+        let Ok(data) = fetch_data(http_client, &url, config.request_timeout_ms, 1).await else {
+            return false;
+        };
+        let flag = data
+            .get("neg_risk")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        self.flags.insert(asset.to_string(), flag);
+        flag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+
+    fn leg(side: &str, asset: &str, timestamp: i64) -> RtdsActivity {
+        RtdsActivity {
+            side: Some(side.to_string()),
+            asset: Some(asset.to_string()),
+            timestamp: Some(timestamp),
+            ..Default::default()
+        }
+    }
+
+    fn group(legs: Vec<RtdsActivity>) -> SignalGroup {
+        SignalGroup {
+            trader: TRADER.to_string(),
+            event_slug: "fed-decision".to_string(),
+            legs,
+            deadline: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn selling_one_outcome_and_buying_another_is_a_rebalance() {
+        assert!(group(vec![leg("SELL", "yes-a", 1), leg("BUY", "yes-b", 2)]).is_rebalance());
+        assert!(!group(vec![leg("BUY", "yes-a", 1), leg("BUY", "yes-b", 2)]).is_rebalance());
+        assert!(!group(vec![leg("SELL", "yes-a", 1), leg("BUY", "yes-a", 2)]).is_rebalance());
+        assert!(!group(vec![leg("SELL", "yes-a", 1)]).is_rebalance());
+    }
+
+    #[test]
+    fn plan_id_uses_the_earliest_leg() {
+        let g = group(vec![leg("BUY", "yes-b", 20), leg("SELL", "yes-a", 10)]);
+        assert_eq!(g.plan_id(), "rb-fed-decision-0x7c3db7-10");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn groups_close_when_their_window_ends() {
+        let mut pending = PendingGroups::default();
+        pending.hold(10, TRADER, "fed-decision", leg("SELL", "yes-a", 1));
+        tokio::time::advance(Duration::from_secs(5)).await;
+        pending.hold(10, TRADER, "fed-decision", leg("BUY", "yes-b", 2));
+        pending.hold(10, TRADER, "election", leg("BUY", "yes-c", 3));
+        assert!(pending.take_expired(false).is_empty());
+
+        // The window runs from the first signal, so the second leg doesn't extend it.
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let closed = pending.take_expired(false);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].legs.len(), 2);
+        assert_eq!(closed[0].event_slug, "fed-decision");
+
+        assert_eq!(pending.take_expired(true).len(), 1);
+        assert!(pending.next_deadline().is_none());
+    }
+
+    #[test]
+    fn all_or_nothing_skips_every_leg_when_one_is_blocked() {
+        let policy = RebalancePolicy::AllOrNothing;
+        let text = |blocked| leg_skip_reason(blocked, policy, true).map(|s| s.to_string());
+        assert_eq!(
+            text(Some("insufficient balance")).as_deref(),
+            Some("rebalance: leg blocked: insufficient balance")
+        );
+        assert_eq!(
+            text(None).as_deref(),
+            Some("rebalance: another leg was blocked")
+        );
+        assert_eq!(leg_skip_reason(None, policy, false), None);
+    }
+
+    #[test]
+    fn a_plan_stopped_after_its_sells_reports_the_missing_legs() {
+        let sold = vec!["SELL Yes".to_string()];
+        let missed = vec!["BUY No".to_string(), "BUY Maybe".to_string()];
+        assert_eq!(
+            partial_plan_report("rb-1", &sold, &missed).as_deref(),
+            Some(
+                "Rebalance plan rb-1 half-applied: SELL Yes went through, BUY No, BUY Maybe \
+                 did not"
+            )
+        );
+        assert_eq!(partial_plan_report("rb-1", &[], &missed), None);
+        assert_eq!(partial_plan_report("rb-1", &sold, &[]), None);
+        assert_eq!(
+            plan_stopped("BUY No").to_string(),
+            "rebalance: plan stopped: BUY No did not go through"
+        );
+    }
+
+    #[test]
+    fn allow_partial_only_skips_the_blocked_leg() {
+        let policy = RebalancePolicy::AllowPartial;
+        assert!(leg_skip_reason(Some("no position"), policy, true).is_some());
+        assert_eq!(leg_skip_reason(None, policy, true), None);
+    }
+}
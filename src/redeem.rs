@@ -0,0 +1,381 @@
+//! Redeems winning positions in resolved markets. Until a position is redeemed its payout
+//! stays locked in conditional tokens and out of the USDC balance copies are sized against.
+//! Every `REDEEM_INTERVAL_SECS` my positions flagged `redeemable` are grouped by market and
+//! each market is redeemed in one transaction: on the conditional tokens contract, or on the
+//! neg-risk adapter for neg-risk markets. An EOA wallet sends it directly; a Safe gets it
+//! through `execTransaction`, signed by the key as the Safe's owner. Either way the key's
+//! address pays the gas. `AUTO_REDEEM=false` turns it off.
+
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use anyhow::{Context, Result};
+use polymarket_client_sdk::{contract_config, derive_proxy_wallet, POLYGON};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::EnvConfig;
+use crate::ctf_approval::is_eoa;
+use crate::executor::fetch_positions;
+use crate::ledger::SharedLedger;
+use crate::types::UserPosition;
+use crate::utils::{Logger, RpcClient};
+
+sol! {
+    #[sol(rpc)]
+    interface IConditionalTokens {
+        function balanceOf(address owner, uint256 id) external view returns (uint256);
+        function redeemPositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] indexSets
+        ) external;
+    }
+
+    interface INegRiskAdapter {
+        function redeemPositions(bytes32 conditionId, uint256[] amounts) external;
+    }
+
+    #[sol(rpc)]
+    #[allow(clippy::too_many_arguments)]
+    interface IGnosisSafe {
+        function nonce() external view returns (uint256);
+        function getTransactionHash(
+            address to,
+            uint256 value,
+            bytes data,
+            uint8 operation,
+            uint256 safeTxGas,
+            uint256 baseGas,
+            uint256 gasPrice,
+            address gasToken,
+            address refundReceiver,
+            uint256 _nonce
+        ) external view returns (bytes32);
+        function execTransaction(
+            address to,
+            uint256 value,
+            bytes data,
+            uint8 operation,
+            uint256 safeTxGas,
+            uint256 baseGas,
+            uint256 gasPrice,
+            address gasToken,
+            address refundReceiver,
+            bytes signatures
+        ) external payable returns (bool);
+    }
+}
+
+/// Failed redemptions of one market before it is left for a manual redeem.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// How redemptions reach the chain for `PROXY_WALLET`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedeemWallet {
+    /// The key's own address: it sends the transaction itself.
+    Eoa,
+    /// A Safe the key owns: the transaction goes through `execTransaction`.
+    Safe(Address),
+    /// Can't be redeemed from here (email/Magic proxy wallets); why, for the log.
+    Unsupported(&'static str),
+}
+
+/// Works out the wallet type once, when the redeemer starts.
+pub async fn wallet_kind(
+    config: &EnvConfig,
+    rpc: &RpcClient,
+    signer: &PrivateKeySigner,
+) -> Result<RedeemWallet> {
+    let wallet: Address = config
+        .proxy_wallet
+        .trim()
+        .parse()
+        .context("Invalid PROXY_WALLET")?;
+    if is_eoa(config, signer) {
+        return Ok(RedeemWallet::Eoa);
+    }
+    if !rpc.is_contract(&config.proxy_wallet).await? {
+        return Ok(RedeemWallet::Unsupported(
+            "PROXY_WALLET is neither this key's address nor a deployed wallet",
+        ));
+    }
+    if derive_proxy_wallet(signer.address(), POLYGON) == Some(wallet) {
+        return Ok(RedeemWallet::Unsupported(
+            "email/Magic proxy wallets redeem from polymarket.com",
+        ));
+    }
+    Ok(RedeemWallet::Safe(wallet))
+}
+
+/// One resolved market I hold, redeemed in a single transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redemption {
+    pub condition_id: String,
+    pub title: String,
+    pub neg_risk: bool,
+    /// Token id and outcome index of each side held.
+    pub assets: Vec<(String, usize)>,
+    /// The payout by the data API's current value.
+    pub value_usd: f64,
+}
+
+/// My redeemable positions grouped by market. Markets where only losing sides are left pay
+/// nothing and are not worth the gas, so they are left out.
+pub fn redemptions(positions: &[UserPosition]) -> Vec<Redemption> {
+    let mut by_market: BTreeMap<String, Redemption> = BTreeMap::new();
+    for pos in positions {
+        let (Some(condition_id), Some(asset)) = (pos.condition_id.as_deref(), pos.asset.as_deref())
+        else {
+            continue;
+        };
+        if pos.redeemable != Some(true) || pos.size.unwrap_or(0.0) <= 0.0 {
+            continue;
+        }
+        let entry = by_market
+            .entry(condition_id.to_string())
+            .or_insert_with(|| Redemption {
+                condition_id: condition_id.to_string(),
+                title: pos.title.clone().unwrap_or_else(|| condition_id.to_string()),
+                neg_risk: pos.negative_risk == Some(true),
+                assets: Vec::new(),
+                value_usd: 0.0,
+            });
+        let outcome = pos.outcome_index.unwrap_or(0).max(0) as usize;
+        entry.assets.push((asset.to_string(), outcome));
+        entry.value_usd += pos.current_value.unwrap_or(0.0).max(0.0);
+    }
+    by_market
+        .into_values()
+        .filter(|r| r.value_usd > 0.0)
+        .collect()
+}
+
+/// Target and calldata redeeming `redemption`. `balances` are the on-chain token balances
+/// of `redemption.assets`, in the same order; the neg-risk adapter wants them per outcome.
+pub fn redeem_call(redemption: &Redemption, balances: &[U256]) -> Result<(Address, Bytes)> {
+    let condition_id: B256 = redemption
+        .condition_id
+        .parse()
+        .context("Invalid condition id")?;
+    if redemption.neg_risk {
+        let adapter = contract_config(POLYGON, true)
+            .and_then(|c| c.neg_risk_adapter)
+            .context("No neg-risk adapter known for Polygon")?;
+        let outcomes = redemption.assets.iter().map(|(_, i)| i + 1).max().unwrap_or(0);
+        let mut amounts = vec![U256::ZERO; outcomes.max(2)];
+        for ((_, outcome), balance) in redemption.assets.iter().zip(balances) {
+            amounts[*outcome] += *balance;
+        }
+        let call = INegRiskAdapter::redeemPositionsCall {
+            conditionId: condition_id,
+            amounts,
+        };
+        return Ok((adapter, call.abi_encode().into()));
+    }
+    let contracts =
+        contract_config(POLYGON, false).context("No conditional tokens contract known")?;
+    // Binary markets: index sets 0b01 and 0b10 cover both outcomes.
+    let call = IConditionalTokens::redeemPositionsCall {
+        collateralToken: contracts.collateral,
+        parentCollectionId: B256::ZERO,
+        conditionId: condition_id,
+        indexSets: vec![U256::from(1), U256::from(2)],
+    };
+    Ok((contracts.conditional_tokens, call.abi_encode().into()))
+}
+
+async fn token_balances(
+    config: &EnvConfig,
+    rpc: &RpcClient,
+    redemption: &Redemption,
+) -> Result<Vec<U256>> {
+    let ctf_address = contract_config(POLYGON, false)
+        .context("No conditional tokens contract known")?
+        .conditional_tokens;
+    let ctf = IConditionalTokens::new(ctf_address, rpc.provider());
+    let owner: Address = config.proxy_wallet.trim().parse()?;
+    let mut balances = Vec::new();
+    for (asset, _) in &redemption.assets {
+        let id = U256::from_str_radix(asset, 10).context("Invalid token id")?;
+        balances.push(ctf.balanceOf(owner, id).call().await?);
+    }
+    Ok(balances)
+}
+
+/// Sends `data` to `to` from the wallet and waits for it to be mined.
+async fn submit(
+    rpc: &RpcClient,
+    signer: &PrivateKeySigner,
+    wallet: RedeemWallet,
+    to: Address,
+    data: Bytes,
+) -> Result<B256> {
+    let provider = ProviderBuilder::new()
+        .wallet(signer.clone())
+        .connect_provider(rpc.provider().clone());
+    let receipt = match wallet {
+        RedeemWallet::Eoa => {
+            let tx = TransactionRequest::default().with_to(to).with_input(data);
+            provider
+                .send_transaction(tx)
+                .await
+                .context("redeem not sent")?
+                .get_receipt()
+                .await
+                .context("redeem not confirmed")?
+        }
+        RedeemWallet::Safe(safe_address) => {
+            let safe = IGnosisSafe::new(safe_address, &provider);
+            let nonce = safe.nonce().call().await?;
+            let (zero, none) = (U256::ZERO, Address::ZERO);
+            let hash = safe
+                .getTransactionHash(to, zero, data.clone(), 0, zero, zero, zero, none, none, nonce)
+                .call()
+                .await?;
+            // An owner's signature over the Safe transaction hash itself (v = 27/28).
+            let signature = signer.sign_hash_sync(&hash)?;
+            let signatures = Bytes::from(signature.as_bytes().to_vec());
+            safe.execTransaction(to, zero, data, 0, zero, zero, zero, none, none, signatures)
+                .send()
+                .await
+                .context("Safe redeem not sent")?
+                .get_receipt()
+                .await
+                .context("Safe redeem not confirmed")?
+        }
+        RedeemWallet::Unsupported(why) => anyhow::bail!("can't redeem: {}", why),
+    };
+    if !receipt.status() {
+        anyhow::bail!("redeem reverted ({})", receipt.transaction_hash);
+    }
+    Ok(receipt.transaction_hash)
+}
+
+/// Redeems every resolved market among my positions. `attempts` counts failures per market
+/// so one that keeps reverting is given up on instead of paying gas every pass.
+pub async fn redeem_resolved(
+    config: &EnvConfig,
+    http_client: &reqwest::Client,
+    rpc: &RpcClient,
+    signer: &PrivateKeySigner,
+    wallet: RedeemWallet,
+    ledger: &SharedLedger,
+    attempts: &mut HashMap<String, u32>,
+) -> Result<()> {
+    let my_positions = fetch_positions(http_client, config, &config.proxy_wallet).await?;
+    for redemption in redemptions(&my_positions) {
+        let tries = attempts.entry(redemption.condition_id.clone()).or_insert(0);
+        if *tries >= MAX_ATTEMPTS {
+            continue;
+        }
+        let balances = token_balances(config, rpc, &redemption).await?;
+        if balances.iter().all(|b| b.is_zero()) {
+            // Already redeemed; the data API still lists it for a while.
+            continue;
+        }
+        if config.dry_run {
+            Logger::info(&format!(
+                "Dry run - not redeeming {} (${:.2})",
+                redemption.title, redemption.value_usd
+            ));
+            *tries = MAX_ATTEMPTS;
+            continue;
+        }
+        let (to, data) = redeem_call(&redemption, &balances)?;
+        match submit(rpc, signer, wallet, to, data).await {
+            Ok(tx) => {
+                Logger::success(&format!(
+                    "Redeemed {}: ${:.2} back to the balance ({})",
+                    redemption.title, redemption.value_usd, tx
+                ));
+                let mut ledger = ledger.lock().await;
+                for ((asset, _), balance) in redemption.assets.iter().zip(&balances) {
+                    ledger.record_sell(asset, balance.saturating_to::<u128>() as f64 / 1e6);
+                }
+            }
+            Err(e) => {
+                *tries += 1;
+                Logger::warning(&format!(
+                    "Redeeming {} failed ({}/{}): {:#}",
+                    redemption.title, tries, MAX_ATTEMPTS, e
+                ));
+                if *tries >= MAX_ATTEMPTS {
+                    Logger::warning(&format!(
+                        "Giving up on redeeming {}; redeem it from polymarket.com",
+                        redemption.title
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(
+        condition: &str,
+        asset: &str,
+        outcome: i32,
+        value: f64,
+        neg_risk: bool,
+    ) -> UserPosition {
+        UserPosition {
+            condition_id: Some(condition.to_string()),
+            asset: Some(asset.to_string()),
+            title: Some(format!("Market {}", condition)),
+            size: Some(10.0),
+            current_value: Some(value),
+            outcome_index: Some(outcome),
+            redeemable: Some(true),
+            negative_risk: Some(neg_risk),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolved_markets_are_grouped_and_losers_only_skipped() {
+        let open = UserPosition {
+            redeemable: Some(false),
+            ..position("0xc3", "5", 0, 4.0, false)
+        };
+        let found = redemptions(&[
+            position("0xc1", "1", 0, 10.0, false),
+            position("0xc1", "2", 1, 0.0, false),
+            position("0xc2", "3", 1, 0.0, true),
+            open,
+        ]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].condition_id, "0xc1");
+        assert_eq!(
+            found[0].assets,
+            vec![("1".to_string(), 0), ("2".to_string(), 1)]
+        );
+        assert_eq!(found[0].value_usd, 10.0);
+    }
+
+    #[test]
+    fn calls_target_the_ctf_or_the_neg_risk_adapter() {
+        let condition = format!("0x{}", "ab".repeat(32));
+        let binary = &redemptions(&[position(&condition, "1", 1, 5.0, false)])[0];
+        let (to, data) = redeem_call(binary, &[U256::from(5_000_000)]).unwrap();
+        assert_eq!(to, contract_config(POLYGON, false).unwrap().conditional_tokens);
+        let call = IConditionalTokens::redeemPositionsCall::abi_decode(&data).unwrap();
+        assert_eq!(call.indexSets, vec![U256::from(1), U256::from(2)]);
+        assert_eq!(call.conditionId, condition.parse::<B256>().unwrap());
+
+        let neg_risk = &redemptions(&[position(&condition, "7", 1, 5.0, true)])[0];
+        let (to, data) = redeem_call(neg_risk, &[U256::from(5_000_000)]).unwrap();
+        assert_eq!(Some(to), contract_config(POLYGON, true).unwrap().neg_risk_adapter);
+        let call = INegRiskAdapter::redeemPositionsCall::abi_decode(&data).unwrap();
+        assert_eq!(call.amounts, vec![U256::ZERO, U256::from(5_000_000)]);
+    }
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+
+use crate::config::EnvConfig;
+use crate::metadata_cache;
+use crate::types::UserPosition;
+use crate::utils::Logger;
+
+/// Parses a scheduled end time. Date-only values ("2025-11-04") don't say when on that day
+/// the market closes, so they count as no reliable end date.
+pub fn parse_end_date(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw.trim())
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// Scheduled end of a market: the trader's position row when it carries a full timestamp,
+/// otherwise the market's metadata. `None` when neither has a reliable end date, or the
+/// metadata couldn't be read; the latter is logged, and the copy goes ahead unchecked.
+pub async fn market_end_time(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    position: Option<&UserPosition>,
+    condition_id: Option<&str>,
+) -> Option<DateTime<Utc>> {
+    if let Some(end) = position
+        .and_then(|p| p.end_date.as_deref())
+        .and_then(parse_end_date)
+    {
+        return Some(end);
+    }
+    let condition_id = condition_id?;
+    match metadata_cache::lookup(http_client, config, condition_id).await {
+        Ok(metadata) => metadata.end_date.as_deref().and_then(parse_end_date),
+        Err(e) => {
+            Logger::warning(&format!(
+                "No end date for {}: market metadata unavailable ({:#})",
+                condition_id, e
+            ));
+            None
+        }
+    }
+}
+
+/// Skip reason for a BUY placed within `window_minutes` of the market's end, if any. Markets
+/// past their scheduled end but not yet resolved are paused too.
+pub fn pause_reason(end: DateTime<Utc>, now: DateTime<Utc>, window_minutes: u64) -> Option<String> {
+    let secs = (end - now).num_seconds();
+    if window_minutes == 0 || secs > window_minutes as i64 * 60 {
+        return None;
+    }
+    if secs <= 0 {
+        return Some(format!(
+            "market passed its scheduled end {}m ago, awaiting resolution",
+            -secs / 60
+        ));
+    }
+    Some(format!(
+        "market ends in {}m{:02}s (pause window {}m)",
+        secs / 60,
+        secs % 60,
+        window_minutes
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(raw: &str) -> DateTime<Utc> {
+        parse_end_date(raw).unwrap()
+    }
+
+    #[test]
+    fn date_only_end_is_not_reliable() {
+        assert!(parse_end_date("2025-11-04").is_none());
+        assert!(parse_end_date("2025-11-04T12:00:00Z").is_some());
+    }
+
+    #[test]
+    fn buys_pause_inside_the_window() {
+        let end = at("2025-11-04T12:00:00Z");
+        assert_eq!(pause_reason(end, at("2025-11-04T11:00:00Z"), 30), None);
+        assert_eq!(
+            pause_reason(end, at("2025-11-04T11:45:30Z"), 30).as_deref(),
+            Some("market ends in 14m30s (pause window 30m)")
+        );
+    }
+
+    #[test]
+    fn market_past_its_end_stays_paused() {
+        let end = at("2025-11-04T12:00:00Z");
+        assert_eq!(
+            pause_reason(end, at("2025-11-04T12:10:00Z"), 30).as_deref(),
+            Some("market passed its scheduled end 10m ago, awaiting resolution")
+        );
+    }
+
+    #[test]
+    fn zero_window_never_pauses() {
+        let end = at("2025-11-04T12:00:00Z");
+        assert_eq!(pause_reason(end, at("2025-11-04T12:10:00Z"), 0), None);
+    }
+}
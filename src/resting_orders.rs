@@ -0,0 +1,944 @@
+use polymarket_client_sdk::auth::state::Authenticated;
+use polymarket_client_sdk::auth::Normal;
+use polymarket_client_sdk::clob::types::{OrderStatusType, Side};
+use polymarket_client_sdk::clob::Client as ClobClient;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::types::{ActivityKind, RtdsActivity};
+use crate::utils::{
+    fetch_data, load_json, lot_size, market_metadata, place_limit_order, save_json, state_path,
+    Logger, OrderContext,
+};
+
+const RESTING_ORDERS_FILE: &str = "resting_orders.json";
+
+/// An order whose exchange status can't be read is given this long past its expiry before
+/// it is closed as expired.
+const EXPIRY_GRACE_SECS: i64 = 300;
+/// How long the trader's order must have been off the book before ours follows it: their
+/// last fills may reach the activity feed after the book shows the level gone.
+const TRADER_FILL_LAG_SECS: i64 = 10;
+
+/// The trader's order a resting copy sits beside (`FOLLOW_TRADER_CANCELS`), as the book and
+/// their activity show it. The book has no owners, so everything at our price when ours was
+/// placed is taken as theirs; size that joins later is someone else's, behind ours in the
+/// queue.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TraderQuote {
+    /// Their order's size when ours was placed.
+    pub tokens: f64,
+    /// Their fills at our price since, from their activity.
+    pub filled: f64,
+    /// The part of it that left the book without trading.
+    pub cancelled: f64,
+    /// Size at our price that isn't theirs or ours.
+    pub others: f64,
+    /// When the book first showed their order gone.
+    pub gone_since: Option<i64>,
+}
+
+impl TraderQuote {
+    /// What is left of their order on the book.
+    pub fn remaining(&self) -> f64 {
+        (self.tokens - self.filled - self.cancelled).max(0.0)
+    }
+
+    /// Takes in the size now at our price, `ours` of it our own unfilled part. Growth is
+    /// someone else joining. A drop the size of what is left of theirs is their cancel; any
+    /// other comes out of the others first, then out of theirs as a cancel.
+    pub fn observe_level(&mut self, level: f64, ours: f64, now: i64) {
+        let expected = self.remaining() + self.others + ours;
+        if level >= expected {
+            self.others += level - expected;
+        } else if (expected - level - self.remaining()).abs() < 1e-6 {
+            self.cancelled += self.remaining();
+        } else {
+            let mut drop = expected - level;
+            let from_others = drop.min(self.others);
+            self.others -= from_others;
+            drop -= from_others;
+            self.cancelled += drop.min(self.remaining());
+        }
+        self.note_gone(now);
+    }
+
+    /// Takes in a fill of theirs at our price. A drop the book showed before the fill arrived
+    /// was counted as a cancel, so the fill takes it back from there.
+    pub fn record_fill(&mut self, tokens: f64, now: i64) {
+        let tokens = tokens.min(self.tokens - self.filled).max(0.0);
+        self.cancelled = (self.cancelled - tokens).max(0.0);
+        self.filled += tokens;
+        self.note_gone(now);
+    }
+
+    fn note_gone(&mut self, now: i64) {
+        if self.tokens > 1e-9 && self.remaining() <= 1e-6 {
+            self.gone_since.get_or_insert(now);
+        } else {
+            self.gone_since = None;
+        }
+    }
+
+    /// The part of their order that traded, once it is off the book after a cancel and their
+    /// fills have had time to arrive. `None` while it rests, and when it filled in full.
+    pub fn settled_fraction(&self, now: i64) -> Option<f64> {
+        let since = self.gone_since?;
+        if now - since < TRADER_FILL_LAG_SECS || self.cancelled <= 1e-6 {
+            return None;
+        }
+        Some((self.filled / self.tokens).clamp(0.0, 1.0))
+    }
+}
+
+/// What a resting copy does once the trader's order beside it is gone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Follow {
+    /// Their order traded no more than ours already has: take ours off the book.
+    Cancel,
+    /// Cut ours to this many tokens in all, the same share of it as traded of theirs.
+    Trim(f64),
+}
+
+/// A GTD limit order left on the book (empty-book policy), tracked until it fills, expires or
+/// the trader exits the market.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestingOrder {
+    pub order_id: String,
+    pub asset: String,
+    pub side: String,
+    pub trader: String,
+    pub price: f64,
+    pub size: f64,
+    pub placed_at: i64,
+    pub expires_at: i64,
+    /// The market, for its tick and minimum size when the order is replaced.
+    #[serde(default)]
+    pub condition_id: Option<String>,
+    /// The copied trade's transaction hash; placement, fill and close events all carry it.
+    #[serde(default)]
+    pub copy_id: String,
+    /// Tokens matched so far, as last reported by the exchange.
+    #[serde(default)]
+    pub filled_tokens: f64,
+    /// Tokens matched by the orders this one replaced when it was trimmed; the exchange
+    /// reports only the current order's.
+    #[serde(default)]
+    pub matched_before: f64,
+    /// The trader's order ours follows (`FOLLOW_TRADER_CANCELS`); `None` until it is read.
+    #[serde(default)]
+    pub quote: Option<TraderQuote>,
+}
+
+/// Why a resting order stopped resting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Filled,
+    Expired,
+    TraderExit,
+    /// Taken off the book by someone else (the exchange, or by hand on polymarket.com).
+    Cancelled,
+    /// Cancelled by us because the trader's order beside it was cancelled.
+    TraderCancelled,
+}
+
+impl CloseReason {
+    /// The journal reason for the closing row.
+    pub fn journal_reason(self) -> &'static str {
+        match self {
+            CloseReason::Filled => "resting order filled",
+            CloseReason::Expired => "resting order expired",
+            CloseReason::TraderExit => "resting order cancelled: trader exited",
+            CloseReason::Cancelled => "resting order cancelled",
+            CloseReason::TraderCancelled => "resting order cancelled: trader cancelled",
+        }
+    }
+}
+
+/// One step in a resting order's life. `Fill` carries only the newly matched part; the
+/// cumulative figure is on the order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderEvent {
+    Placed,
+    Fill { tokens: f64, usd: f64 },
+    /// Replaced by a smaller order at the same price, after the trader cancelled theirs.
+    Trimmed { from: f64 },
+    Closed(CloseReason),
+}
+
+/// One look at an order on the exchange: tokens matched so far and whether it still rests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderUpdate {
+    pub matched_tokens: f64,
+    pub open: bool,
+}
+
+impl RestingOrder {
+    /// Notional matched so far, at the order's limit price.
+    pub fn filled_usd(&self) -> f64 {
+        self.filled_tokens * self.price
+    }
+
+    /// USDC the unfilled part still holds: BUYs lock collateral, SELLs lock tokens.
+    pub fn locked_usd(&self) -> f64 {
+        if self.side != "BUY" {
+            return 0.0;
+        }
+        (self.size - self.filled_tokens).max(0.0) * self.price
+    }
+
+    /// Takes in the exchange's matched size; a fill event when it grew.
+    pub fn apply_matched(&mut self, matched_tokens: f64) -> Option<OrderEvent> {
+        let tokens = (self.matched_before + matched_tokens).min(self.size) - self.filled_tokens;
+        if tokens <= 1e-9 {
+            return None;
+        }
+        self.filled_tokens += tokens;
+        Some(OrderEvent::Fill {
+            tokens,
+            usd: tokens * self.price,
+        })
+    }
+
+    /// Our part of the book: what is left unfilled.
+    pub fn open_tokens(&self) -> f64 {
+        (self.size - self.filled_tokens).max(0.0)
+    }
+
+    /// What to do about the trader's order once it is gone after a cancel: ours is cut to the
+    /// share of it that traded of theirs, or cancelled when that much of ours has filled.
+    pub fn follow(&self, now: i64) -> Option<Follow> {
+        let fraction = self.quote.as_ref()?.settled_fraction(now)?;
+        let target = lot_size(self.size * fraction);
+        if target <= self.filled_tokens + 1e-6 {
+            Some(Follow::Cancel)
+        } else if target < self.size - 1e-6 {
+            Some(Follow::Trim(target))
+        } else {
+            None
+        }
+    }
+
+    /// The events one status update produces: a fill when more was matched, and a close
+    /// once the order is off the book.
+    pub fn advance(&mut self, update: OrderUpdate, now: i64) -> Vec<OrderEvent> {
+        let mut events: Vec<OrderEvent> = self
+            .apply_matched(update.matched_tokens)
+            .into_iter()
+            .collect();
+        if !update.open {
+            let reason = if self.filled_tokens >= self.size - 1e-9 {
+                CloseReason::Filled
+            } else if now >= self.expires_at {
+                CloseReason::Expired
+            } else {
+                CloseReason::Cancelled
+            };
+            events.push(OrderEvent::Closed(reason));
+        }
+        events
+    }
+
+    /// The log and alert line for `event`.
+    pub fn describe(&self, event: &OrderEvent) -> String {
+        let copy = Logger::format_address(&self.copy_id);
+        match event {
+            OrderEvent::Placed => format!(
+                "📌 Resting {} placed: {:.2} @ ${:.2}, expires in {}s (copy {})",
+                self.side,
+                self.size,
+                self.price,
+                (self.expires_at - self.placed_at).max(0),
+                copy
+            ),
+            OrderEvent::Fill { tokens, usd } => format!(
+                "🧩 Resting {} fill: {:.2} (${:.2}) - {:.2}/{:.2} filled (copy {})",
+                self.side, tokens, usd, self.filled_tokens, self.size, copy
+            ),
+            OrderEvent::Trimmed { from } => format!(
+                "✂️ Resting {} trimmed from {:.2} to {:.2} after the trader cancelled - {:.2} filled (copy {})",
+                self.side, from, self.size, self.filled_tokens, copy
+            ),
+            OrderEvent::Closed(reason) => format!(
+                "🏁 Resting {} closed ({}): {:.2}/{:.2} filled for ${:.2} (copy {})",
+                self.side,
+                match reason {
+                    CloseReason::Filled => "filled",
+                    CloseReason::Expired => "TTL expired",
+                    CloseReason::TraderExit => "trader exited",
+                    CloseReason::Cancelled => "cancelled",
+                    CloseReason::TraderCancelled => "trader cancelled",
+                },
+                self.filled_tokens,
+                self.size,
+                self.filled_usd(),
+                copy
+            ),
+        }
+    }
+}
+
+/// Logs `event` and sends it as an alert.
+pub fn report(order: &RestingOrder, event: &OrderEvent) {
+    let line = order.describe(event);
+    Logger::info(&line);
+    crate::alerts::notify(&line);
+}
+
+/// Tracked orders per state dir, loaded on first use, so bots with their own `STATE_DIR` in
+/// one process don't share them.
+static ORDERS: Mutex<BTreeMap<String, Vec<RestingOrder>>> = Mutex::new(BTreeMap::new());
+
+fn with_orders<T>(state_dir: &str, f: impl FnOnce(&mut Vec<RestingOrder>) -> T) -> Option<T> {
+    let mut guard = ORDERS.lock().ok()?;
+    let orders = guard.entry(state_dir.to_string()).or_insert_with(|| {
+        load_json(&state_path(state_dir, RESTING_ORDERS_FILE)).unwrap_or_default()
+    });
+    Some(f(orders))
+}
+
+/// `with_orders` for changes: the orders are saved afterwards.
+fn change_orders<T>(state_dir: &str, f: impl FnOnce(&mut Vec<RestingOrder>) -> T) -> Option<T> {
+    with_orders(state_dir, |orders| {
+        let result = f(orders);
+        if let Err(e) = save_json(&state_path(state_dir, RESTING_ORDERS_FILE), orders) {
+            Logger::warning(&format!("Failed to save resting orders: {}", e));
+        }
+        result
+    })
+}
+
+pub fn record(state_dir: &str, order: RestingOrder) {
+    change_orders(state_dir, |orders| orders.push(order));
+}
+
+pub fn all(state_dir: &str) -> Vec<RestingOrder> {
+    with_orders(state_dir, |orders| orders.clone()).unwrap_or_default()
+}
+
+/// USDC locked by the tracked orders; an order stops counting once it is removed.
+pub fn locked_usd(state_dir: &str) -> f64 {
+    with_orders(state_dir, |orders| orders.iter().map(RestingOrder::locked_usd).sum())
+        .unwrap_or(0.0)
+}
+
+/// Stops tracking an order that filled or left the book.
+pub fn remove(state_dir: &str, order_id: &str) {
+    change_orders(state_dir, |orders| {
+        orders.retain(|o| o.order_id != order_id)
+    });
+}
+
+/// Saves the fill progress of a tracked order.
+pub fn update(state_dir: &str, order: &RestingOrder) {
+    change_orders(state_dir, |orders| {
+        if let Some(o) = orders.iter_mut().find(|o| o.order_id == order.order_id) {
+            o.filled_tokens = order.filled_tokens;
+        }
+    });
+}
+
+/// Reads the order's status from the CLOB.
+pub async fn fetch_update(
+    clob_client: &ClobClient<Authenticated<Normal>>,
+    order_id: &str,
+) -> anyhow::Result<OrderUpdate> {
+    let order = clob_client.order(order_id).await?;
+    Ok(OrderUpdate {
+        matched_tokens: order.size_matched.to_f64().unwrap_or(0.0),
+        open: !matches!(
+            order.status,
+            OrderStatusType::Matched | OrderStatusType::Canceled
+        ),
+    })
+}
+
+/// Polls every tracked order once and returns the events, with the orders as they stand
+/// after them. Closed orders stop being tracked; an order whose status can't be read is
+/// closed as expired once it is well past its expiry.
+pub async fn poll(
+    state_dir: &str,
+    clob_client: &ClobClient<Authenticated<Normal>>,
+) -> Vec<(RestingOrder, Vec<OrderEvent>)> {
+    let now = chrono::Utc::now().timestamp();
+    let mut out = Vec::new();
+    for mut order in all(state_dir) {
+        let events = match fetch_update(clob_client, &order.order_id).await {
+            Ok(update) => order.advance(update, now),
+            Err(_) if now > order.expires_at + EXPIRY_GRACE_SECS => {
+                vec![OrderEvent::Closed(CloseReason::Expired)]
+            }
+            Err(e) => {
+                Logger::warning(&format!(
+                    "Failed to read resting order {}: {}",
+                    order.order_id, e
+                ));
+                continue;
+            }
+        };
+        if events.is_empty() {
+            continue;
+        }
+        if events.iter().any(|e| matches!(e, OrderEvent::Closed(_))) {
+            remove(state_dir, &order.order_id);
+        } else {
+            update(state_dir, &order);
+        }
+        out.push((order, events));
+    }
+    out
+}
+
+/// Size resting at `price` on the side of `book` a `side` order joins: the bids for a BUY,
+/// the asks for a SELL.
+pub fn level_tokens(book: &Value, side: &str, price: f64) -> f64 {
+    let levels = if side == "BUY" { "bids" } else { "asks" };
+    book.get(levels)
+        .and_then(|l| l.as_array())
+        .map(|levels| {
+            levels
+                .iter()
+                .filter_map(|l| {
+                    let level_price: f64 = l.get("price")?.as_str()?.parse().ok()?;
+                    let size: f64 = l.get("size")?.as_str()?.parse().ok()?;
+                    ((level_price - price).abs() < 1e-6).then_some(size)
+                })
+                .sum()
+        })
+        .unwrap_or(0.0)
+}
+
+/// Whether a fill on `asset`/`side` could come from one of our resting orders (placed before
+/// now and not long expired).
+pub fn covers(state_dir: &str, asset: &str, side: &str) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    with_orders(state_dir, |orders| {
+        orders
+            .iter()
+            .any(|o| o.asset == asset && o.side == side && o.expires_at + EXPIRY_GRACE_SECS > now)
+    })
+    .unwrap_or(false)
+}
+
+/// Removes and returns the resting BUYs in `asset` that copied `trader` (lower-case).
+fn take_exited(orders: &mut Vec<RestingOrder>, asset: &str, trader: &str) -> Vec<RestingOrder> {
+    let (cancel, keep): (Vec<_>, Vec<_>) = orders
+        .drain(..)
+        .partition(|o| o.asset == asset && o.side == "BUY" && o.trader == trader);
+    *orders = keep;
+    cancel
+}
+
+/// Cancels our resting BUYs in `asset` that copied `trader`, called when that trader sells:
+/// an unfilled entry is no longer worth having once they are on their way out. Returns the
+/// cancelled orders with any last fills and their closing event.
+pub async fn cancel_on_trader_exit(
+    state_dir: &str,
+    clob_client: &ClobClient<Authenticated<Normal>>,
+    asset: &str,
+    trader: &str,
+) -> Vec<(RestingOrder, Vec<OrderEvent>)> {
+    let trader = trader.to_lowercase();
+    let Some(to_cancel) = change_orders(state_dir, |orders| take_exited(orders, asset, &trader))
+    else {
+        return Vec::new();
+    };
+    cancel_all(clob_client, to_cancel, CloseReason::TraderExit).await
+}
+
+/// Counts a trade of `trader`'s toward the quotes our orders follow: a fill of theirs on our
+/// side, at our price, after ours was placed. The trade ours copied doesn't count.
+pub fn note_trader_fill(state_dir: &str, trader: &str, activity: &RtdsActivity) {
+    if activity.kind() != ActivityKind::Trade {
+        return;
+    }
+    let (Some(asset), Some(side), Some(price), Some(tokens)) = (
+        activity.asset.as_deref(),
+        activity.side.as_deref(),
+        activity.price,
+        activity.size,
+    ) else {
+        return;
+    };
+    let (trader, tx_hash) = (trader.to_lowercase(), activity.transaction_hash.as_deref());
+    let now = chrono::Utc::now().timestamp();
+    let at = activity.timestamp.unwrap_or(now);
+    change_orders(state_dir, |orders| {
+        for o in orders.iter_mut().filter(|o| {
+            o.trader == trader
+                && o.asset == asset
+                && o.side == side
+                && (o.price - price).abs() < 1e-6
+                && at >= o.placed_at
+                && tx_hash != Some(o.copy_id.as_str())
+        }) {
+            if let Some(quote) = o.quote.as_mut() {
+                quote.record_fill(tokens, now);
+            }
+        }
+    });
+}
+
+/// Follows the trader's orders ours rest beside (`FOLLOW_TRADER_CANCELS`): reads each order's
+/// price level, and once the trader's order there is gone after a cancel, cuts ours to the
+/// same share of it that traded of theirs. An order tracked before its quote was read (an
+/// older state file, or one placed with the flag off) takes its quote from the book now.
+/// Returns the orders that changed, with their events.
+pub async fn follow_trader_cancels(
+    order_ctx: &OrderContext<'_>,
+) -> Vec<(RestingOrder, Vec<OrderEvent>)> {
+    let OrderContext {
+        config,
+        http_client,
+        clob_client,
+        ..
+    } = *order_ctx;
+    let now = chrono::Utc::now().timestamp();
+    let mut out = Vec::new();
+    for order in all(&config.state_dir) {
+        if order.quote.as_ref().is_some_and(|q| q.tokens <= 1e-9) {
+            continue;
+        }
+        let url = format!(
+            "{}/book?token_id={}",
+            config.clob_http_url.trim_end_matches('/'),
+            order.asset
+        );
+        let level = match fetch_data(http_client, &url, config.request_timeout_ms, 1).await {
+            Ok(book) => level_tokens(&book, &order.side, order.price),
+            Err(e) => {
+                Logger::warning(&format!(
+                    "Failed to read the book for resting order {}: {}",
+                    order.order_id, e
+                ));
+                continue;
+            }
+        };
+        let Some(Some(mut order)) = change_orders(&config.state_dir, |orders| {
+            let o = orders.iter_mut().find(|o| o.order_id == order.order_id)?;
+            let ours = o.open_tokens();
+            match o.quote.as_mut() {
+                Some(quote) => quote.observe_level(level, ours, now),
+                None => {
+                    o.quote = Some(TraderQuote {
+                        tokens: (level - ours).max(0.0),
+                        ..TraderQuote::default()
+                    })
+                }
+            }
+            Some(o.clone())
+        }) else {
+            continue;
+        };
+        let Some(follow) = order.follow(now) else {
+            continue;
+        };
+        if let Err(e) = clob_client.cancel_order(&order.order_id).await {
+            Logger::warning(&format!(
+                "Failed to cancel resting order {}: {}",
+                order.order_id, e
+            ));
+            continue;
+        }
+        remove(&config.state_dir, &order.order_id);
+        let mut events = Vec::new();
+        if let Ok(update) = fetch_update(clob_client, &order.order_id).await {
+            events.extend(order.apply_matched(update.matched_tokens));
+        }
+        let target = match follow {
+            Follow::Trim(target) if target > order.filled_tokens + 1e-6 => target,
+            _ => {
+                events.push(OrderEvent::Closed(CloseReason::TraderCancelled));
+                out.push((order, events));
+                continue;
+            }
+        };
+        let side = if order.side == "BUY" {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let market = market_metadata(http_client, config, order.condition_id.as_deref()).await;
+        let open = lot_size(target - order.filled_tokens);
+        let placed = place_limit_order(
+            order_ctx,
+            &order.asset,
+            market.as_ref(),
+            side,
+            open,
+            order.price,
+            order.expires_at,
+        )
+        .await;
+        match placed {
+            Ok(Ok(order_id)) => {
+                let from = order.size;
+                order.order_id = order_id;
+                order.matched_before = order.filled_tokens;
+                order.size = order.filled_tokens + open;
+                // Their order is gone: nothing is left at the price to follow.
+                order.quote = Some(TraderQuote::default());
+                events.push(OrderEvent::Trimmed { from });
+                record(&config.state_dir, order.clone());
+            }
+            Ok(Err(msg)) => {
+                Logger::warning(&format!("Trimmed resting order not placed: {}", msg));
+                events.push(OrderEvent::Closed(CloseReason::TraderCancelled));
+            }
+            Err(e) => {
+                Logger::warning(&format!("Failed to place trimmed resting order: {}", e));
+                events.push(OrderEvent::Closed(CloseReason::TraderCancelled));
+            }
+        }
+        out.push((order, events));
+    }
+    out
+}
+
+/// Cancels `orders` on the CLOB and closes each with `reason`, after any last fills.
+async fn cancel_all(
+    clob_client: &ClobClient<Authenticated<Normal>>,
+    orders: Vec<RestingOrder>,
+    reason: CloseReason,
+) -> Vec<(RestingOrder, Vec<OrderEvent>)> {
+    let mut out = Vec::new();
+    for mut order in orders {
+        if let Err(e) = clob_client.cancel_order(&order.order_id).await {
+            Logger::warning(&format!(
+                "Failed to cancel resting order {}: {}",
+                order.order_id, e
+            ));
+            continue;
+        }
+        let mut events = Vec::new();
+        if let Ok(update) = fetch_update(clob_client, &order.order_id).await {
+            events.extend(order.apply_matched(update.matched_tokens));
+        }
+        events.push(OrderEvent::Closed(reason));
+        out.push((order, events));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::balance::BalanceTracker;
+
+    fn order(id: &str, asset: &str, side: &str, trader: &str) -> RestingOrder {
+        RestingOrder {
+            order_id: id.to_string(),
+            asset: asset.to_string(),
+            side: side.to_string(),
+            trader: trader.to_string(),
+            price: 0.4,
+            size: 10.0,
+            placed_at: 0,
+            expires_at: 600,
+            condition_id: None,
+            copy_id: format!("0xtx{}", id),
+            filled_tokens: 0.0,
+            matched_before: 0.0,
+            quote: None,
+        }
+    }
+
+    fn ids(orders: &[RestingOrder]) -> Vec<&str> {
+        orders.iter().map(|o| o.order_id.as_str()).collect()
+    }
+
+    #[test]
+    fn each_state_dir_has_its_own_orders_and_reads_save_nothing() {
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (a, b) = (a.path().to_str().unwrap(), b.path().to_str().unwrap());
+        record(a, order("1", "yes-a", "BUY", "0xt"));
+        assert_eq!(ids(&all(a)), ["1"]);
+        assert!(all(b).is_empty());
+        assert_eq!(locked_usd(b), 0.0);
+        assert!(!covers(b, "yes-a", "BUY"));
+        assert!(!state_path(b, RESTING_ORDERS_FILE).exists());
+
+        remove(a, "1");
+        let saved: Vec<RestingOrder> = load_json(&state_path(a, RESTING_ORDERS_FILE)).unwrap();
+        assert!(saved.is_empty());
+    }
+
+    #[test]
+    fn trader_exit_takes_only_their_buys_in_that_asset() {
+        let mut orders = vec![
+            order("1", "yes-a", "BUY", "0xt"),
+            order("2", "yes-a", "SELL", "0xt"),
+            order("3", "yes-a", "BUY", "0xother"),
+            order("4", "yes-b", "BUY", "0xt"),
+        ];
+        let taken = take_exited(&mut orders, "yes-a", "0xt");
+        assert_eq!(ids(&taken), ["1"]);
+        assert_eq!(ids(&orders), ["2", "3", "4"]);
+    }
+
+    /// Feeds status updates to the order the way the poller does and collects the events.
+    fn drive(order: &mut RestingOrder, stream: &[(OrderUpdate, i64)]) -> Vec<OrderEvent> {
+        stream
+            .iter()
+            .flat_map(|(update, now)| order.advance(*update, *now))
+            .collect()
+    }
+
+    fn live(matched_tokens: f64) -> OrderUpdate {
+        OrderUpdate {
+            matched_tokens,
+            open: true,
+        }
+    }
+
+    fn off_book(matched_tokens: f64) -> OrderUpdate {
+        OrderUpdate {
+            matched_tokens,
+            open: false,
+        }
+    }
+
+    #[test]
+    fn placement_partial_fill_and_cancel_are_three_events() {
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let mut events = vec![OrderEvent::Placed];
+        events.extend(drive(
+            &mut o,
+            &[(live(0.0), 10), (live(4.0), 20), (live(4.0), 30)],
+        ));
+        events.extend(o.apply_matched(4.0));
+        events.push(OrderEvent::Closed(CloseReason::TraderExit));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], OrderEvent::Placed);
+        assert!(matches!(events[1], OrderEvent::Fill { tokens, usd }
+            if (tokens - 4.0).abs() < 1e-9 && (usd - 1.6).abs() < 1e-9));
+        assert_eq!(events[2], OrderEvent::Closed(CloseReason::TraderExit));
+        assert!((o.filled_usd() - 1.6).abs() < 1e-9);
+
+        let lines: Vec<String> = events.iter().map(|e| o.describe(e)).collect();
+        assert!(lines.iter().all(|l| l.contains("0xtx1")));
+        assert!(lines[1].contains("4.00/10.00"));
+        assert!(lines[2].contains("trader exited"));
+    }
+
+    #[test]
+    fn fills_report_only_the_new_part() {
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let events = drive(
+            &mut o,
+            &[(live(3.0), 10), (live(7.0), 20), (off_book(10.0), 30)],
+        );
+        let fills: Vec<f64> = events
+            .iter()
+            .filter_map(|e| match e {
+                OrderEvent::Fill { tokens, .. } => Some(*tokens),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(fills, [3.0, 4.0, 3.0]);
+        assert_eq!(
+            events.last(),
+            Some(&OrderEvent::Closed(CloseReason::Filled))
+        );
+    }
+
+    #[test]
+    fn off_book_before_expiry_is_a_cancel_after_it_an_expiry() {
+        let mut early = order("1", "yes-a", "BUY", "0xt");
+        assert_eq!(
+            early.advance(off_book(2.0), 100),
+            [
+                OrderEvent::Fill {
+                    tokens: 2.0,
+                    usd: 2.0 * 0.4
+                },
+                OrderEvent::Closed(CloseReason::Cancelled)
+            ]
+        );
+        let mut late = order("2", "yes-a", "BUY", "0xt");
+        assert_eq!(
+            late.advance(off_book(0.0), 600),
+            [OrderEvent::Closed(CloseReason::Expired)]
+        );
+    }
+
+    fn book(bids: &[(&str, &str)]) -> Value {
+        let levels: Vec<Value> = bids
+            .iter()
+            .map(|(price, size)| serde_json::json!({"price": price, "size": size}))
+            .collect();
+        serde_json::json!({"bids": levels, "asks": []})
+    }
+
+    /// Places `o` beside the trader's order at its price in `placed`, then replays `script`:
+    /// each step is the book at that second, our matched size, and the trader's fills since.
+    fn follow_script(o: &mut RestingOrder, placed: &Value, script: &[(Value, f64, f64)]) {
+        o.quote = Some(TraderQuote {
+            tokens: level_tokens(placed, &o.side, o.price),
+            ..TraderQuote::default()
+        });
+        for (step, (snapshot, matched, trader_fill)) in script.iter().enumerate() {
+            let now = 10 + step as i64;
+            drive(o, &[(live(*matched), now)]);
+            let (level, ours) = (level_tokens(snapshot, &o.side, o.price), o.open_tokens());
+            let quote = o.quote.as_mut().unwrap();
+            if *trader_fill > 0.0 {
+                quote.record_fill(*trader_fill, now);
+            }
+            quote.observe_level(level, ours, now);
+        }
+    }
+
+    /// The second the last step of a script of `steps` ran, plus the fill lag.
+    fn settled(steps: usize) -> i64 {
+        10 + steps as i64 - 1 + TRADER_FILL_LAG_SECS
+    }
+
+    #[test]
+    fn a_partial_fill_then_cancel_trims_ours_by_the_same_share() {
+        // The trader rests 50 @ 0.40 and we join them with 10.
+        let placed = book(&[("0.40", "50"), ("0.35", "20")]);
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let script = [
+            (book(&[("0.40", "60"), ("0.35", "20")]), 0.0, 0.0),
+            // 20 of theirs trade, then the book catches up.
+            (book(&[("0.40", "60"), ("0.35", "20")]), 0.0, 20.0),
+            (book(&[("0.40", "40"), ("0.35", "20")]), 0.0, 0.0),
+            // They cancel the other 30: only ours is left at 0.40.
+            (book(&[("0.40", "10"), ("0.35", "20")]), 0.0, 0.0),
+        ];
+        follow_script(&mut o, &placed, &script);
+        let quote = o.quote.clone().unwrap();
+        assert_eq!((quote.filled, quote.cancelled), (20.0, 30.0));
+        // Their late fills may still be on the way.
+        assert_eq!(o.follow(settled(script.len()) - 1), None);
+        // 20 of their 50 traded, so 4 of our 10 stay.
+        assert_eq!(o.follow(settled(script.len())), Some(Follow::Trim(4.0)));
+
+        o.size = 4.0;
+        let trimmed = o.describe(&OrderEvent::Trimmed { from: 10.0 });
+        assert!(trimmed.contains("trimmed from 10.00 to 4.00") && trimmed.contains("0xtx1"));
+    }
+
+    #[test]
+    fn a_fill_seen_on_the_book_first_is_not_a_cancel() {
+        let placed = book(&[("0.40", "50")]);
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let script = [
+            // The book shows 20 gone before the feed shows the fill.
+            (book(&[("0.40", "40")]), 0.0, 0.0),
+            (book(&[("0.40", "40")]), 0.0, 20.0),
+        ];
+        follow_script(&mut o, &placed, &script);
+        let quote = o.quote.clone().unwrap();
+        assert_eq!(
+            (quote.filled, quote.cancelled, quote.remaining()),
+            (20.0, 0.0, 30.0)
+        );
+        assert_eq!(o.follow(settled(script.len()) + 60), None);
+    }
+
+    #[test]
+    fn ours_is_cancelled_when_as_much_of_it_filled_as_of_theirs() {
+        let placed = book(&[("0.40", "50")]);
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let script = [
+            // 10 of theirs and 6 of ours trade, then they cancel the rest.
+            (book(&[("0.40", "44")]), 6.0, 10.0),
+            (book(&[("0.40", "4")]), 6.0, 0.0),
+        ];
+        follow_script(&mut o, &placed, &script);
+        // 10 of 50 traded: 2 of ours would do, and 6 already filled.
+        assert_eq!(o.follow(settled(script.len())), Some(Follow::Cancel));
+
+        let mut untouched = order("2", "yes-a", "BUY", "0xt");
+        follow_script(
+            &mut untouched,
+            &placed,
+            &[(book(&[("0.40", "10")]), 0.0, 0.0)],
+        );
+        assert_eq!(untouched.follow(settled(1)), Some(Follow::Cancel));
+    }
+
+    #[test]
+    fn other_quoters_at_the_price_neither_trigger_nor_block_it() {
+        let placed = book(&[("0.40", "50")]);
+        // Someone joins with 30 after us and cancels again: their size was never the trader's.
+        let mut o = order("1", "yes-a", "BUY", "0xt");
+        let joined_and_left = [
+            (book(&[("0.40", "90")]), 0.0, 0.0),
+            (book(&[("0.40", "60")]), 0.0, 0.0),
+        ];
+        follow_script(&mut o, &placed, &joined_and_left);
+        assert_eq!(o.quote.as_ref().unwrap().cancelled, 0.0);
+        assert_eq!(o.follow(settled(2) + 60), None);
+
+        // Someone joins with 30 and stays: the trader cancelling still shows.
+        let mut o = order("2", "yes-a", "BUY", "0xt");
+        let joined_and_stayed = [
+            (book(&[("0.40", "90")]), 0.0, 0.0),
+            (book(&[("0.40", "40")]), 0.0, 0.0),
+        ];
+        follow_script(&mut o, &placed, &joined_and_stayed);
+        let quote = o.quote.clone().unwrap();
+        assert_eq!((quote.others, quote.cancelled), (30.0, 50.0));
+        assert_eq!(o.follow(settled(2)), Some(Follow::Cancel));
+    }
+
+    #[test]
+    fn an_order_with_nothing_to_follow_is_never_cancelled_for_it() {
+        let mut o = order("1", "yes-a", "SELL", "0xt");
+        assert_eq!(o.follow(1_000), None);
+        o.quote = Some(TraderQuote::default());
+        o.quote.as_mut().unwrap().observe_level(0.0, 0.0, 10);
+        assert_eq!(o.follow(1_000), None);
+        // A SELL joins the asks.
+        let asks = serde_json::json!({"bids": [{"price": "0.40", "size": "9"}],
+            "asks": [{"price": "0.40", "size": "7"}]});
+        assert_eq!(level_tokens(&asks, "SELL", 0.4), 7.0);
+        assert_eq!(level_tokens(&asks, "SELL", 0.41), 0.0);
+    }
+
+    #[test]
+    fn orders_saved_before_fill_tracking_still_load() {
+        let json = r#"{"order_id":"1","asset":"a","side":"BUY","trader":"0xt","price":0.4,"size":10.0,"placed_at":0,"expires_at":600}"#;
+        let o: RestingOrder = serde_json::from_str(json).unwrap();
+        assert_eq!(o.filled_tokens, 0.0);
+        assert_eq!((o.matched_before, o.quote), (0.0, None));
+        assert!(o.copy_id.is_empty());
+    }
+
+    #[test]
+    fn locked_collateral_follows_fills_and_closes() {
+        let mut orders = vec![
+            order("1", "yes-a", "BUY", "0xt"),
+            order("2", "yes-b", "BUY", "0xt"),
+            order("3", "yes-c", "SELL", "0xt"),
+        ];
+        let mut tracker = BalanceTracker::with_reserve(10.0);
+        let mut settle = |orders: &[RestingOrder]| {
+            // As `poll` does: closed orders stop being tracked, and their lock with them.
+            tracker.set_locked(orders.iter().map(RestingOrder::locked_usd).sum());
+            tracker.available_for_trading(100.0)
+        };
+        // Two BUYs of 10 @ 0.4 lock $8; the SELL locks tokens, not USDC.
+        assert!((settle(&orders) - 82.0).abs() < 1e-9);
+
+        drive(&mut orders[0], &[(live(2.5), 10), (live(7.5), 20)]);
+        assert!((orders[0].locked_usd() - 1.0).abs() < 1e-9);
+        assert!((settle(&orders) - 85.0).abs() < 1e-9);
+
+        let closing = drive(&mut orders[1], &[(off_book(4.0), 30)]);
+        assert_eq!(closing.last(), Some(&OrderEvent::Closed(CloseReason::Cancelled)));
+        orders.retain(|o| o.order_id != "2");
+        assert!((settle(&orders) - 89.0).abs() < 1e-9);
+
+        drive(&mut orders[0], &[(off_book(10.0), 40)]);
+        assert_eq!(orders[0].locked_usd(), 0.0);
+        orders.retain(|o| o.order_id != "1");
+        assert!((settle(&orders) - 90.0).abs() < 1e-9);
+    }
+}
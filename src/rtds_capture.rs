@@ -0,0 +1,166 @@
+//! Capture and replay of the raw RTDS stream. `CAPTURE_RTDS_TO` appends every frame with its
+//! receive time; `REPLAY_RTDS_FROM` feeds a captured session back through the monitor with
+//! the original gaps between frames (divided by `REPLAY_RTDS_SPEED`).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::utils::Logger;
+
+/// One captured frame: the text exactly as received and when it arrived.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    pub received_at_ms: i64,
+    pub frame: String,
+}
+
+/// Append-only capture file.
+pub struct Capture {
+    file: std::fs::File,
+}
+
+impl Capture {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open RTDS capture {}", path.display()))?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, text: &str, received_at_ms: i64) {
+        let frame = Frame {
+            received_at_ms,
+            frame: text.to_string(),
+        };
+        let written = serde_json::to_string(&frame)
+            .map_err(std::io::Error::from)
+            .and_then(|line| writeln!(self.file, "{}", line));
+        if let Err(e) = written {
+            Logger::warning(&format!("Failed to capture RTDS frame: {}", e));
+        }
+    }
+}
+
+/// Reads a capture file. Blank lines are skipped; anything else that doesn't parse is an
+/// error, so a truncated capture isn't silently replayed as a shorter session.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Frame>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open RTDS capture {}", path.display()))?;
+    let mut frames = Vec::new();
+    for (n, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame: Frame = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid capture line", path.display(), n + 1))?;
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Wait before each frame: the gap to the previous one divided by `speed` (the first frame
+/// goes out at once; out-of-order receive times count as no gap).
+pub fn schedule(frames: &[Frame], speed: f64) -> Vec<Duration> {
+    let speed = if speed > 0.0 { speed } else { 1.0 };
+    let mut previous = frames.first().map(|f| f.received_at_ms).unwrap_or(0);
+    frames
+        .iter()
+        .map(|f| {
+            let gap = (f.received_at_ms - previous).max(0);
+            previous = f.received_at_ms;
+            Duration::from_secs_f64(gap as f64 / 1000.0 / speed)
+        })
+        .collect()
+}
+
+/// Moves a trade frame's payload `timestamp` by `offset_ms`, keeping its unit, so age checks
+/// see a replayed trade as just as fresh as it was live.
+pub fn rebase(parsed: &mut serde_json::Value, offset_ms: i64) {
+    let Some(ts) = parsed.pointer_mut("/payload/timestamp") else {
+        return;
+    };
+    if let Some(t) = ts.as_i64() {
+        let rebased = if t > 1_000_000_000_000 {
+            t + offset_ms
+        } else {
+            t + offset_ms / 1000
+        };
+        *ts = rebased.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(received_at_ms: i64) -> Frame {
+        Frame {
+            received_at_ms,
+            frame: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn schedule_keeps_gaps_and_applies_speed() {
+        let frames = [frame(1_000), frame(1_500), frame(4_500), frame(4_000)];
+        assert_eq!(
+            schedule(&frames, 1.0),
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(500),
+                Duration::from_secs(3),
+                Duration::ZERO,
+            ]
+        );
+        assert_eq!(schedule(&frames, 10.0)[2], Duration::from_millis(300));
+    }
+
+    #[test]
+    fn rebase_keeps_the_timestamp_unit() {
+        let mut secs = json!({"payload": {"timestamp": 1_700_000_000}});
+        rebase(&mut secs, 60_000);
+        assert_eq!(secs["payload"]["timestamp"], 1_700_000_060);
+        let mut millis = json!({"payload": {"timestamp": 1_700_000_000_000i64}});
+        rebase(&mut millis, 60_000);
+        assert_eq!(millis["payload"]["timestamp"], 1_700_000_060_000i64);
+        let mut other = json!({"action": "subscribed"});
+        rebase(&mut other, 60_000);
+        assert_eq!(other, json!({"action": "subscribed"}));
+    }
+
+    #[test]
+    fn captured_frames_load_back_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("captures/session.jsonl");
+        let mut capture = Capture::open(&path).unwrap();
+        capture.record(r#"{"topic":"activity"}"#, 1_000);
+        capture.record("not json at all", 1_250);
+        assert_eq!(
+            load(&path).unwrap(),
+            vec![
+                Frame {
+                    received_at_ms: 1_000,
+                    frame: r#"{"topic":"activity"}"#.to_string(),
+                },
+                Frame {
+                    received_at_ms: 1_250,
+                    frame: "not json at all".to_string(),
+                },
+            ]
+        );
+        std::fs::write(&path, "{\"received_at_ms\": 1\n").unwrap();
+        assert!(load(&path).is_err());
+    }
+}
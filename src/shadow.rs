@@ -0,0 +1,174 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::sync::OnceLock;
+
+use crate::balance::BalanceReading;
+use crate::config::{calculate_order_size, EnvConfig, SizingInputs};
+use crate::market_overrides;
+use crate::position_action::PositionAction;
+use crate::skip_reason::{Skip, SkipReason};
+use crate::skip_rules::{OpenPositionCheck, RuleInput, RuleStage, SkipRuleEngine};
+use crate::types::UserActivity;
+use crate::utils::Logger;
+
+/// A candidate configuration evaluated on every signal next to the live one. It never trades;
+/// its decisions are journaled with `shadow: true` for `shadow_report` to compare.
+pub struct Shadow {
+    pub path: String,
+    config: EnvConfig,
+    rules: SkipRuleEngine,
+}
+
+static SHADOW: OnceLock<Shadow> = OnceLock::new();
+
+/// Journal reason on shadow rows the shadow could not decide; `undetermined()` as text.
+pub const SHADOW_UNDETERMINED: &str =
+    "undetermined: live pipeline stopped before the balance check";
+
+pub fn undetermined() -> Skip {
+    Skip::new(
+        SkipReason::Undetermined,
+        "live pipeline stopped before the balance check",
+    )
+}
+
+/// Loads `SHADOW_CONFIG_FILE` over the live environment. Later calls are ignored.
+pub fn install(path: &str) -> Result<()> {
+    let config = EnvConfig::parse_with_overrides(path)?;
+    Logger::info(&format!(
+        "Shadow config {} loaded: decisions are journaled alongside live ones, never traded",
+        path
+    ));
+    let rules = SkipRuleEngine::from_config(&config);
+    let _ = SHADOW.set(Shadow {
+        path: path.to_string(),
+        config,
+        rules,
+    });
+    Ok(())
+}
+
+pub fn active() -> Option<&'static Shadow> {
+    SHADOW.get()
+}
+
+/// What the live pipeline had already looked up for a signal when it stopped. The shadow
+/// reuses these so it never adds a request to the copy path.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowFacts {
+    pub balance: Option<BalanceReading>,
+    /// Value of our current position in the market, for the position cap.
+    pub current_value: f64,
+    /// `(opens_new, open_count)` from the ledger, for BUYs.
+    pub open_positions: Option<(bool, usize)>,
+    pub market_end: Option<DateTime<Utc>>,
+    /// The trader's portfolio value, for `PORTFOLIO_SHARE` sizing.
+    pub trader_portfolio_usd: Option<f64>,
+    pub position_action: Option<PositionAction>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShadowDecision {
+    /// Would copy; the size is known for BUYs (SELL sizing depends on the order book).
+    Copy {
+        usd: Option<f64>,
+    },
+    Skip(Skip),
+    /// The live pipeline stopped before the shadow had what it needed to decide.
+    Undetermined,
+}
+
+impl Shadow {
+    /// Runs the shadow's skip rules and sizing against facts the live pipeline gathered.
+    /// Consensus is stateful and not re-evaluated.
+    pub fn evaluate(
+        &self,
+        trade: &UserActivity,
+        address: &str,
+        facts: &ShadowFacts,
+    ) -> (ShadowDecision, Vec<String>) {
+        let now = Utc::now();
+        let mut input = RuleInput {
+            config: &self.config,
+            trade,
+            trader: address,
+            now,
+            balance: None,
+            balance_breakdown: None,
+            open_positions: None,
+            market_end: None,
+            position_action: None,
+            market_override: market_overrides::get(trade.condition_id.as_deref()),
+            manual: false,
+        };
+        let signal = self.rules.evaluate(RuleStage::Signal, &input);
+        let skipped = signal.skipped();
+        let mut trace = signal.trace;
+        if let Some(skip) = skipped {
+            return (ShadowDecision::Skip(skip), trace);
+        }
+        let Some(reading) = facts.balance else {
+            return (ShadowDecision::Undetermined, trace);
+        };
+
+        input.balance = Some(reading);
+        input.market_end = facts.market_end;
+        input.position_action = facts.position_action;
+        input.open_positions = match (self.config.max_open_positions, facts.open_positions) {
+            (Some(max), Some((true, open_count))) => Some(OpenPositionCheck {
+                allowed: open_count < max,
+                open_count,
+                max,
+            }),
+            _ => None,
+        };
+        let position = self.rules.evaluate(RuleStage::Position, &input);
+        let skipped = position.skipped();
+        trace.extend(position.trace);
+        if let Some(skip) = skipped {
+            return (ShadowDecision::Skip(skip), trace);
+        }
+        if trade.side.as_deref() != Some("BUY") {
+            return (ShadowDecision::Copy { usd: None }, trace);
+        }
+
+        let balance = match reading {
+            BalanceReading::Fresh(balance) => balance,
+            BalanceReading::Degraded { balance, .. } => {
+                balance * self.config.degraded_balance_fraction
+            }
+            BalanceReading::Unavailable => 0.0,
+        };
+        let strategy = &self.config.copy_strategy_config;
+        let mut inputs = SizingInputs {
+            trader_portfolio_usd: facts.trader_portfolio_usd,
+            max_order_size_usd: Some(market_overrides::max_order_size(
+                strategy.max_order_size_usd,
+                input.market_override.as_ref(),
+            )),
+            ..SizingInputs::default()
+        };
+        if let Some(cap) = position.max_order_size_usd {
+            inputs.cap_order_size(strategy, cap);
+        }
+        let calc = calculate_order_size(
+            strategy,
+            &inputs,
+            trade.usdc_size.unwrap_or(0.0),
+            balance,
+            facts.current_value,
+        );
+        if calc.below_minimum || calc.final_amount <= 0.0 {
+            return (
+                ShadowDecision::Skip(Skip::new(SkipReason::BelowMinimum, calc.reasoning)),
+                trace,
+            );
+        }
+        (
+            ShadowDecision::Copy {
+                usd: Some(calc.final_amount),
+            },
+            trace,
+        )
+    }
+}
@@ -0,0 +1,325 @@
+//! Why a signal was not copied, as one taxonomy shared by the journal (`skip_reason`), the
+//! digest, the `skips` status counters and the log lines.
+//!
+//! Every reason has a stable code (`code`, what the journal stores), a low-cardinality
+//! `metric_label` that drops rule and state names, and human text (`Display`). A `Skip` adds
+//! the details the skip site knew.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum SkipReason {
+    /// Older than `TOO_OLD_TIMESTAMP`.
+    Stale,
+    /// The same fill was already processed (re-delivered by RTDS or a replay).
+    Duplicate,
+    /// Sized below `MIN_ORDER_SIZE_USD`.
+    BelowMinimum,
+    Balance,
+    /// `MAX_OPEN_POSITIONS`.
+    OpenPositions,
+    /// `MAX_DAILY_VOLUME_USD`.
+    DailyVolume,
+    /// `MAX_MARKET_SHARE_PERCENT`.
+    MarketShare,
+    /// The catch-up budget (`CATCHUP_MAX_USD`) ran out.
+    Budget,
+    /// A skip rule the user configured turned it away (market lists, price band, ...).
+    Filtered {
+        rule: String,
+    },
+    /// `PAUSE_BEFORE_RESOLUTION_MINUTES` or `SKIP_MARKETS_ENDING_WITHIN_HOURS`, or the market
+    /// already ended.
+    MarketClosed,
+    /// `trading_state`: `buys`, `all` or `draining`.
+    Paused {
+        state: String,
+    },
+    ConsensusPending,
+    ConsensusEntered,
+    /// A SELL the consensus exit rules hold back.
+    ConsensusExit,
+    TraderDropped,
+    /// A leg of a neg-risk rebalance plan that did not go ahead.
+    Rebalance,
+    DryRun,
+    /// Nothing filled yet, but a resting order or position build is working.
+    Working,
+    NotFilled,
+    /// The book had moved past `MAX_SLIPPAGE_PERCENT` from the trader's price.
+    Slippage,
+    /// A resting order or position build ended without filling.
+    OrderClosed,
+    /// Shadow rows only: the live pipeline stopped before the shadow could decide.
+    Undetermined,
+}
+
+impl SkipReason {
+    pub fn filtered(rule: &str) -> Self {
+        SkipReason::Filtered {
+            rule: rule.to_string(),
+        }
+    }
+
+    pub fn paused(state: &str) -> Self {
+        SkipReason::Paused {
+            state: state.to_string(),
+        }
+    }
+
+    /// The stable code the journal stores, e.g. `stale` or `filtered:price_band`.
+    pub fn code(&self) -> String {
+        match self {
+            SkipReason::Filtered { rule } => format!("filtered:{}", rule),
+            SkipReason::Paused { state } => format!("paused:{}", state),
+            other => other.metric_label().to_string(),
+        }
+    }
+
+    /// The code without rule or state names, for counters.
+    pub fn metric_label(&self) -> &'static str {
+        match self {
+            SkipReason::Stale => "stale",
+            SkipReason::Duplicate => "duplicate",
+            SkipReason::BelowMinimum => "below_minimum",
+            SkipReason::Balance => "balance",
+            SkipReason::OpenPositions => "open_positions",
+            SkipReason::DailyVolume => "daily_volume",
+            SkipReason::MarketShare => "market_share",
+            SkipReason::Budget => "budget",
+            SkipReason::Filtered { .. } => "filtered",
+            SkipReason::MarketClosed => "market_closed",
+            SkipReason::Paused { .. } => "paused",
+            SkipReason::ConsensusPending => "consensus_pending",
+            SkipReason::ConsensusEntered => "consensus_entered",
+            SkipReason::ConsensusExit => "consensus_exit",
+            SkipReason::TraderDropped => "trader_dropped",
+            SkipReason::Rebalance => "rebalance",
+            SkipReason::DryRun => "dry_run",
+            SkipReason::Working => "working",
+            SkipReason::NotFilled => "not_filled",
+            SkipReason::Slippage => "slippage",
+            SkipReason::OrderClosed => "order_closed",
+            SkipReason::Undetermined => "undetermined",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        if let Some(rule) = code.strip_prefix("filtered:") {
+            return Some(SkipReason::filtered(rule));
+        }
+        if let Some(state) = code.strip_prefix("paused:") {
+            return Some(SkipReason::paused(state));
+        }
+        Some(match code {
+            "stale" => SkipReason::Stale,
+            "duplicate" => SkipReason::Duplicate,
+            "below_minimum" => SkipReason::BelowMinimum,
+            "balance" => SkipReason::Balance,
+            "open_positions" => SkipReason::OpenPositions,
+            "daily_volume" => SkipReason::DailyVolume,
+            "market_share" => SkipReason::MarketShare,
+            "budget" => SkipReason::Budget,
+            "market_closed" => SkipReason::MarketClosed,
+            "consensus_pending" => SkipReason::ConsensusPending,
+            "consensus_entered" => SkipReason::ConsensusEntered,
+            "consensus_exit" => SkipReason::ConsensusExit,
+            "trader_dropped" => SkipReason::TraderDropped,
+            "rebalance" => SkipReason::Rebalance,
+            "dry_run" => SkipReason::DryRun,
+            "working" => SkipReason::Working,
+            "not_filled" => SkipReason::NotFilled,
+            "slippage" => SkipReason::Slippage,
+            "order_closed" => SkipReason::OrderClosed,
+            "undetermined" => SkipReason::Undetermined,
+            _ => return None,
+        })
+    }
+}
+
+/// Human text for logs and notifications.
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Stale => write!(f, "stale"),
+            SkipReason::Duplicate => write!(f, "already processed"),
+            SkipReason::BelowMinimum => write!(f, "order too small"),
+            SkipReason::Balance => write!(f, "balance check"),
+            SkipReason::OpenPositions => write!(f, "position limit"),
+            SkipReason::DailyVolume => write!(f, "daily volume"),
+            SkipReason::MarketShare => write!(f, "market share"),
+            SkipReason::Budget => write!(f, "over budget"),
+            SkipReason::Filtered { rule } => write!(f, "filtered by {}", rule),
+            SkipReason::MarketClosed => write!(f, "market closing"),
+            SkipReason::Paused { state } => match state.as_str() {
+                "buys" => write!(f, "BUYs paused"),
+                "draining" => write!(f, "shutting down"),
+                _ => write!(f, "paused"),
+            },
+            SkipReason::ConsensusPending => write!(f, "consensus pending"),
+            SkipReason::ConsensusEntered => write!(f, "consensus already entered"),
+            SkipReason::ConsensusExit => write!(f, "consensus exit"),
+            SkipReason::TraderDropped => write!(f, "trader dropped"),
+            SkipReason::Rebalance => write!(f, "rebalance"),
+            SkipReason::DryRun => write!(f, "dry run"),
+            SkipReason::Working => write!(f, "order working"),
+            SkipReason::NotFilled => write!(f, "order not filled"),
+            SkipReason::Slippage => write!(f, "slippage"),
+            SkipReason::OrderClosed => write!(f, "order closed"),
+            SkipReason::Undetermined => write!(f, "undetermined"),
+        }
+    }
+}
+
+impl From<SkipReason> for String {
+    fn from(reason: SkipReason) -> String {
+        reason.code()
+    }
+}
+
+impl TryFrom<String> for SkipReason {
+    type Error = String;
+
+    fn try_from(code: String) -> Result<Self, String> {
+        SkipReason::parse(&code).ok_or_else(|| format!("unknown skip reason {:?}", code))
+    }
+}
+
+/// A skip as a site reports it: the reason plus what it knew, e.g. the signal's age.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skip {
+    pub reason: SkipReason,
+    /// Empty when the reason says it all.
+    pub detail: String,
+}
+
+impl Skip {
+    pub fn new(reason: SkipReason, detail: impl Into<String>) -> Self {
+        Self {
+            reason,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl From<SkipReason> for Skip {
+    fn from(reason: SkipReason) -> Self {
+        Skip::new(reason, "")
+    }
+}
+
+/// `reason: detail`, the text journaled and logged.
+impl fmt::Display for Skip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.detail.is_empty() {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(f, "{}: {}", self.reason, self.detail)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One of each variant. The match fails to compile when a variant is added without
+    /// being listed here.
+    fn every_reason() -> Vec<SkipReason> {
+        let all = vec![
+            SkipReason::Stale,
+            SkipReason::Duplicate,
+            SkipReason::BelowMinimum,
+            SkipReason::Balance,
+            SkipReason::OpenPositions,
+            SkipReason::DailyVolume,
+            SkipReason::MarketShare,
+            SkipReason::Budget,
+            SkipReason::filtered("price_band"),
+            SkipReason::MarketClosed,
+            SkipReason::paused("buys"),
+            SkipReason::ConsensusPending,
+            SkipReason::ConsensusEntered,
+            SkipReason::ConsensusExit,
+            SkipReason::TraderDropped,
+            SkipReason::Rebalance,
+            SkipReason::DryRun,
+            SkipReason::Working,
+            SkipReason::NotFilled,
+            SkipReason::Slippage,
+            SkipReason::OrderClosed,
+            SkipReason::Undetermined,
+        ];
+        for reason in &all {
+            match reason {
+                SkipReason::Stale
+                | SkipReason::Duplicate
+                | SkipReason::BelowMinimum
+                | SkipReason::Balance
+                | SkipReason::OpenPositions
+                | SkipReason::DailyVolume
+                | SkipReason::MarketShare
+                | SkipReason::Budget
+                | SkipReason::Filtered { .. }
+                | SkipReason::MarketClosed
+                | SkipReason::Paused { .. }
+                | SkipReason::ConsensusPending
+                | SkipReason::ConsensusEntered
+                | SkipReason::ConsensusExit
+                | SkipReason::TraderDropped
+                | SkipReason::Rebalance
+                | SkipReason::DryRun
+                | SkipReason::Working
+                | SkipReason::NotFilled
+                | SkipReason::Slippage
+                | SkipReason::OrderClosed
+                | SkipReason::Undetermined => {}
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn every_reason_renders_and_has_a_metric_label() {
+        let all = every_reason();
+        let mut labels: Vec<&str> = all.iter().map(SkipReason::metric_label).collect();
+        labels.sort();
+        labels.dedup();
+        assert_eq!(labels.len(), all.len(), "metric labels must be distinct");
+        for reason in all {
+            let label = reason.metric_label();
+            assert!(
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_lowercase() || c == '_'),
+                "{:?}",
+                reason
+            );
+            assert!(!reason.to_string().is_empty(), "{:?}", reason);
+            assert_eq!(SkipReason::parse(&reason.code()), Some(reason.clone()));
+        }
+    }
+
+    #[test]
+    fn journal_stores_the_code() {
+        let json = serde_json::to_string(&SkipReason::filtered("market_filter")).unwrap();
+        assert_eq!(json, "\"filtered:market_filter\"");
+        let back: SkipReason = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.metric_label(), "filtered");
+        assert!(serde_json::from_str::<SkipReason>("\"no_such_reason\"").is_err());
+    }
+
+    #[test]
+    fn skip_text_joins_reason_and_detail() {
+        let stale = Skip::new(SkipReason::Stale, "signal is 48.0h old");
+        assert_eq!(stale.to_string(), "stale: signal is 48.0h old");
+        assert_eq!(
+            Skip::from(SkipReason::NotFilled).to_string(),
+            "order not filled"
+        );
+        assert_eq!(
+            Skip::new(SkipReason::paused("buys"), "low balance").to_string(),
+            "BUYs paused: low balance"
+        );
+    }
+}
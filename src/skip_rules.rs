@@ -0,0 +1,867 @@
+use chrono::{DateTime, Utc};
+
+use crate::balance::{BalanceBreakdown, BalanceReading};
+use crate::classification::{trader_class, TraderClass};
+use crate::config::{EnvConfig, SizingBalance};
+use crate::market_overrides::MarketOverride;
+use crate::position_action::PositionAction;
+use crate::resolution::pause_reason;
+use crate::skip_reason::{Skip, SkipReason};
+use crate::trading_state;
+use crate::types::UserActivity;
+
+/// Every rule, in the default evaluation order. `SKIP_RULES` picks a subset and reorders it.
+pub const RULE_NAMES: &[&str] = &[
+    "stale",
+    "market_override",
+    "market_maker",
+    "market_filter",
+    "price_band",
+    "trading_state",
+    "position_action",
+    "balance",
+    "open_positions",
+    "resolution_window",
+    "degraded_balance",
+];
+
+/// When a rule can run: on the bare signal, or once positions and balance are known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleStage {
+    Signal,
+    Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleOutcome {
+    Allow,
+    Skip(Skip),
+    /// Let the copy through with a tighter per-order cap.
+    Modify {
+        max_order_size_usd: f64,
+    },
+}
+
+/// Result of `MAX_OPEN_POSITIONS` for a BUY that would open a new market.
+#[derive(Debug, Clone, Copy)]
+pub struct OpenPositionCheck {
+    pub allowed: bool,
+    pub open_count: usize,
+    pub max: usize,
+}
+
+/// What the rules get to look at. Position-stage fields are unset during the signal stage.
+pub struct RuleInput<'a> {
+    pub config: &'a EnvConfig,
+    pub trade: &'a UserActivity,
+    pub trader: &'a str,
+    pub now: DateTime<Utc>,
+    pub balance: Option<BalanceReading>,
+    /// The wallet split into locked, reserve and free, when it was read fresh.
+    pub balance_breakdown: Option<BalanceBreakdown>,
+    pub open_positions: Option<OpenPositionCheck>,
+    pub market_end: Option<DateTime<Utc>>,
+    /// What the trade did to the trader's position; unset for manual copies.
+    pub position_action: Option<PositionAction>,
+    /// My override for the trade's market, if it has one.
+    pub market_override: Option<MarketOverride>,
+    /// A copy I asked for (`copy-now`, the interactive prompt) rather than a live signal.
+    pub manual: bool,
+}
+
+impl RuleInput<'_> {
+    fn is_buy(&self) -> bool {
+        self.trade.side.as_deref() == Some("BUY")
+    }
+}
+
+pub trait SkipRule: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn stage(&self) -> RuleStage;
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome;
+}
+
+/// Hours since a signal's timestamp (seconds or milliseconds); a missing one counts as ancient.
+pub fn signal_age_hours(timestamp: Option<i64>, now: DateTime<Utc>) -> f64 {
+    let ts = timestamp.unwrap_or(0);
+    let ts_ms = if ts > 1_000_000_000_000 {
+        ts
+    } else {
+        ts * 1000
+    };
+    (now.timestamp_millis() - ts_ms) as f64 / (1000.0 * 3600.0)
+}
+
+struct StaleSignal;
+
+impl SkipRule for StaleSignal {
+    fn name(&self) -> &'static str {
+        "stale"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        let hours_ago = signal_age_hours(input.trade.timestamp, input.now);
+        if hours_ago > input.config.too_old_timestamp_hours as f64 {
+            RuleOutcome::Skip(Skip::new(
+                SkipReason::Stale,
+                format!("signal is {:.1}h old", hours_ago),
+            ))
+        } else {
+            RuleOutcome::Allow
+        }
+    }
+}
+
+/// `market set`: markets I never want traded, or want to confirm copy by copy. Runs ahead of
+/// the market lists, so a never-trade market stays out even when it is whitelisted.
+struct MarketOverrideGate;
+
+impl SkipRule for MarketOverrideGate {
+    fn name(&self) -> &'static str {
+        "market_override"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        let Some(market) = &input.market_override else {
+            return RuleOutcome::Allow;
+        };
+        let id = input.trade.condition_id.as_deref().unwrap_or("unknown");
+        let skip = |detail| RuleOutcome::Skip(Skip::new(SkipReason::filtered(self.name()), detail));
+        if market.never_trade {
+            return skip(format!("market {} is set to never trade", id));
+        }
+        if market.confirm && !input.manual {
+            return skip(format!(
+                "market {} is set to confirm manually; copy it with copy-now",
+                id
+            ));
+        }
+        RuleOutcome::Allow
+    }
+}
+
+struct MarketMakerFill;
+
+impl SkipRule for MarketMakerFill {
+    fn name(&self) -> &'static str {
+        "market_maker"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        if input.config.skip_market_maker_fills
+            && trader_class(input.trader) == Some(TraderClass::MarketMaker)
+        {
+            RuleOutcome::Skip(Skip::new(
+                SkipReason::filtered(self.name()),
+                "trader classified as market maker",
+            ))
+        } else {
+            RuleOutcome::Allow
+        }
+    }
+}
+
+/// `MARKET_BLACKLIST` / `MARKET_WHITELIST`, matched against the trade's slug, event slug
+/// and condition id. A non-empty whitelist admits only its markets; the blacklist wins
+/// when a market is on both.
+struct MarketFilter;
+
+impl SkipRule for MarketFilter {
+    fn name(&self) -> &'static str {
+        "market_filter"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        let (config, trade) = (input.config, input.trade);
+        let ids: Vec<String> = [&trade.slug, &trade.event_slug, &trade.condition_id]
+            .into_iter()
+            .flatten()
+            .map(|id| id.to_lowercase())
+            .collect();
+        let listed = |list: &[String]| ids.iter().find(|id| list.contains(id)).cloned();
+        let skip = |detail| RuleOutcome::Skip(Skip::new(SkipReason::filtered(self.name()), detail));
+        if let Some(id) = listed(&config.market_blacklist) {
+            return skip(format!("market {} is in MARKET_BLACKLIST", id));
+        }
+        if !config.market_whitelist.is_empty() && listed(&config.market_whitelist).is_none() {
+            let market = ids.first().map(String::as_str).unwrap_or("unknown");
+            return skip(format!("market {} is not in MARKET_WHITELIST", market));
+        }
+        RuleOutcome::Allow
+    }
+}
+
+/// `MIN_COPY_PRICE` / `MAX_COPY_PRICE`: longshots and near-certainties are poor copies once
+/// latency is paid. Exits are let through unless `PRICE_BAND_INCLUDES_SELLS`.
+struct PriceBand;
+
+impl SkipRule for PriceBand {
+    fn name(&self) -> &'static str {
+        "price_band"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        let config = input.config;
+        let (min, max) = (config.min_copy_price, config.max_copy_price);
+        if (min <= 0.0 && max >= 1.0) || !(input.is_buy() || config.price_band_includes_sells) {
+            return RuleOutcome::Allow;
+        }
+        let detail = match input.trade.price {
+            Some(price) if price >= min && price <= max => return RuleOutcome::Allow,
+            Some(price) => format!(
+                "price ${:.4} outside the copy band ${:.4}-${:.4}",
+                price, min, max
+            ),
+            None => format!(
+                "no price to check against the copy band ${:.4}-${:.4}",
+                min, max
+            ),
+        };
+        RuleOutcome::Skip(Skip::new(SkipReason::filtered(self.name()), detail))
+    }
+}
+
+/// Pauses from the risk features and shutdown (see `trading_state`).
+struct TradingStateGate;
+
+impl SkipRule for TradingStateGate {
+    fn name(&self) -> &'static str {
+        "trading_state"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Signal
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        match trading_state::check(input.trade.side.as_deref().unwrap_or("")) {
+            Ok(()) => RuleOutcome::Allow,
+            Err(skip) => RuleOutcome::Skip(skip),
+        }
+    }
+}
+
+/// `COPY_POSITION_ACTIONS` / `COPY_POSITION_ACTIONS_BY_TRADER`: e.g. copy the trader's opens
+/// and closes but not their adds and trims.
+struct PositionActionFilter;
+
+impl SkipRule for PositionActionFilter {
+    fn name(&self) -> &'static str {
+        "position_action"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Position
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        match input.position_action {
+            Some(action) if !input.config.copies_position_action(input.trader, action) => {
+                RuleOutcome::Skip(Skip::new(
+                    SkipReason::filtered(self.name()),
+                    format!("trader's {} is not in the copied position actions", action),
+                ))
+            }
+            _ => RuleOutcome::Allow,
+        }
+    }
+}
+
+struct BalanceAvailable;
+
+impl SkipRule for BalanceAvailable {
+    fn name(&self) -> &'static str {
+        "balance"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Position
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        // Exits don't spend balance, so they go ahead without one.
+        let min_order = input.config.copy_strategy_config.min_order_size_usd;
+        let sizing_free = input.config.sizing_balance == SizingBalance::Available;
+        match (input.balance, input.balance_breakdown) {
+            (Some(BalanceReading::Unavailable), _) if input.is_buy() => {
+                RuleOutcome::Skip(Skip::new(SkipReason::Balance, "balance unavailable"))
+            }
+            (_, Some(b)) if input.is_buy() && sizing_free && b.free < min_order => {
+                RuleOutcome::Skip(Skip::new(
+                    SkipReason::Balance,
+                    format!(
+                    "free balance ${:.2} is below a minimum order (${:.2} locked in open \
+orders, ${:.2} reserve)",
+                        b.free, b.locked, b.reserve
+                    ),
+                ))
+            }
+            _ => RuleOutcome::Allow,
+        }
+    }
+}
+
+struct OpenPositions;
+
+impl SkipRule for OpenPositions {
+    fn name(&self) -> &'static str {
+        "open_positions"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Position
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        match input.open_positions {
+            Some(check) if input.is_buy() && !check.allowed => RuleOutcome::Skip(Skip::new(
+                SkipReason::OpenPositions,
+                format!(
+                    "max open positions reached ({}/{})",
+                    check.open_count, check.max
+                ),
+            )),
+            _ => RuleOutcome::Allow,
+        }
+    }
+}
+
+struct ResolutionWindow;
+
+impl SkipRule for ResolutionWindow {
+    fn name(&self) -> &'static str {
+        "resolution_window"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Position
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        let window = input.config.resolution_window_minutes();
+        match input.market_end {
+            Some(end) if input.is_buy() => pause_reason(end, input.now, window)
+                .map(|detail| RuleOutcome::Skip(Skip::new(SkipReason::MarketClosed, detail)))
+                .unwrap_or(RuleOutcome::Allow),
+            _ => RuleOutcome::Allow,
+        }
+    }
+}
+
+struct DegradedBalance;
+
+impl SkipRule for DegradedBalance {
+    fn name(&self) -> &'static str {
+        "degraded_balance"
+    }
+    fn stage(&self) -> RuleStage {
+        RuleStage::Position
+    }
+    fn evaluate(&self, input: &RuleInput) -> RuleOutcome {
+        match input.balance {
+            Some(BalanceReading::Degraded { .. }) => RuleOutcome::Modify {
+                max_order_size_usd: input.config.degraded_max_order_size_usd,
+            },
+            _ => RuleOutcome::Allow,
+        }
+    }
+}
+
+fn rule_by_name(name: &str) -> Option<Box<dyn SkipRule>> {
+    Some(match name {
+        "stale" => Box::new(StaleSignal),
+        "market_override" => Box::new(MarketOverrideGate),
+        "market_maker" => Box::new(MarketMakerFill),
+        "market_filter" => Box::new(MarketFilter),
+        "price_band" => Box::new(PriceBand),
+        "trading_state" => Box::new(TradingStateGate),
+        "position_action" => Box::new(PositionActionFilter),
+        "balance" => Box::new(BalanceAvailable),
+        "open_positions" => Box::new(OpenPositions),
+        "resolution_window" => Box::new(ResolutionWindow),
+        "degraded_balance" => Box::new(DegradedBalance),
+        _ => return None,
+    })
+}
+
+/// Outcome of one stage: the trace of rules that ran, the first skip, and any cap.
+#[derive(Debug, Default)]
+pub struct RuleEvaluation {
+    pub trace: Vec<String>,
+    pub skip: Option<String>,
+    /// Name of the rule that set `skip`, and the reason it gave.
+    pub skipped_by: Option<&'static str>,
+    pub reason: Option<SkipReason>,
+    pub max_order_size_usd: Option<f64>,
+}
+
+impl RuleEvaluation {
+    pub fn skipped(&self) -> Option<Skip> {
+        Some(Skip::new(self.reason.clone()?, self.skip.clone()?))
+    }
+}
+
+/// The enabled rules in `SKIP_RULES` order.
+pub struct SkipRuleEngine {
+    rules: Vec<Box<dyn SkipRule>>,
+}
+
+impl SkipRuleEngine {
+    pub fn from_config(config: &EnvConfig) -> Self {
+        Self {
+            rules: config
+                .skip_rules
+                .iter()
+                .filter_map(|name| rule_by_name(name))
+                .collect(),
+        }
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.rules.iter().any(|r| r.name() == name)
+    }
+
+    /// Runs the stage's rules in order, stopping at the first skip. Caps from several
+    /// `Modify` outcomes combine to the smallest.
+    pub fn evaluate(&self, stage: RuleStage, input: &RuleInput) -> RuleEvaluation {
+        let mut eval = RuleEvaluation::default();
+        for rule in self.rules.iter().filter(|r| r.stage() == stage) {
+            match rule.evaluate(input) {
+                RuleOutcome::Allow => eval.trace.push(format!("{}:allow", rule.name())),
+                RuleOutcome::Modify { max_order_size_usd } => {
+                    eval.trace.push(format!("{}:modify", rule.name()));
+                    eval.max_order_size_usd = Some(
+                        eval.max_order_size_usd
+                            .map_or(max_order_size_usd, |c| c.min(max_order_size_usd)),
+                    );
+                }
+                RuleOutcome::Skip(skip) => {
+                    eval.trace.push(format!("{}:skip", rule.name()));
+                    eval.skip = Some(skip.detail);
+                    eval.reason = Some(skip.reason);
+                    eval.skipped_by = Some(rule.name());
+                    break;
+                }
+            }
+        }
+        eval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use crate::types::RtdsActivity;
+    use chrono::Duration;
+
+    fn trade(side: &str, timestamp: DateTime<Utc>) -> UserActivity {
+        UserActivity {
+            side: Some(side.to_string()),
+            timestamp: Some(timestamp.timestamp()),
+            ..Default::default()
+        }
+    }
+
+    fn input<'a>(
+        config: &'a EnvConfig,
+        trade: &'a UserActivity,
+        now: DateTime<Utc>,
+    ) -> RuleInput<'a> {
+        RuleInput {
+            config,
+            trade,
+            trader: "0x2222222222222222222222222222222222222222",
+            now,
+            balance: Some(BalanceReading::Fresh(100.0)),
+            balance_breakdown: None,
+            open_positions: Some(OpenPositionCheck {
+                allowed: true,
+                open_count: 1,
+                max: 3,
+            }),
+            market_end: None,
+            position_action: None,
+            market_override: None,
+            manual: false,
+        }
+    }
+
+    fn blocked() -> Option<OpenPositionCheck> {
+        Some(OpenPositionCheck {
+            allowed: false,
+            open_count: 3,
+            max: 3,
+        })
+    }
+
+    #[test]
+    fn default_order_is_the_old_inline_order() {
+        let config = test_config(&[]);
+        assert_eq!(config.skip_rules, RULE_NAMES);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let buy = trade("BUY", now);
+        let eval = engine.evaluate(RuleStage::Position, &input(&config, &buy, now));
+        assert_eq!(
+            eval.trace,
+            [
+                "position_action:allow",
+                "balance:allow",
+                "open_positions:allow",
+                "resolution_window:allow",
+                "degraded_balance:allow"
+            ]
+        );
+        assert_eq!((eval.skip, eval.max_order_size_usd), (None, None));
+    }
+
+    #[test]
+    fn stale_signal_is_skipped() {
+        let config = test_config(&[]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let old = trade("BUY", now - Duration::hours(48));
+        let eval = engine.evaluate(RuleStage::Signal, &input(&config, &old, now));
+        assert_eq!(eval.skip.as_deref(), Some("signal is 48.0h old"));
+        assert_eq!(eval.skipped_by, Some("stale"));
+        assert_eq!(eval.reason, Some(SkipReason::Stale));
+        assert_eq!(eval.trace, ["stale:skip"]);
+    }
+
+    #[test]
+    fn unavailable_balance_skips_buys_but_not_sells() {
+        let config = test_config(&[]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let buy = trade("BUY", now);
+        let sell = trade("SELL", now);
+
+        let mut buy_input = input(&config, &buy, now);
+        buy_input.balance = Some(BalanceReading::Unavailable);
+        buy_input.open_positions = blocked();
+        let eval = engine.evaluate(RuleStage::Position, &buy_input);
+        // The balance check ran first in the old code too, so it names the skip.
+        assert_eq!(eval.skip.as_deref(), Some("balance unavailable"));
+        assert_eq!(eval.skipped_by, Some("balance"));
+
+        let mut sell_input = input(&config, &sell, now);
+        sell_input.balance = Some(BalanceReading::Unavailable);
+        assert_eq!(engine.evaluate(RuleStage::Position, &sell_input).skip, None);
+    }
+
+    #[test]
+    fn balance_locked_in_orders_throttles_buys() {
+        let now = Utc::now();
+        let buy = trade("BUY", now);
+        let starved = Some(BalanceBreakdown::new(100.0, 95.0, 4.5));
+        let config = test_config(&[("MIN_ORDER_SIZE_USD", "1")]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let mut buy_input = input(&config, &buy, now);
+        buy_input.balance_breakdown = starved;
+        assert_eq!(
+            engine.evaluate(RuleStage::Position, &buy_input).skip.as_deref(),
+            Some(
+                "free balance $0.50 is below a minimum order \
+                 ($95.00 locked in open orders, $4.50 reserve)"
+            )
+        );
+        buy_input.balance_breakdown = Some(BalanceBreakdown::new(100.0, 50.0, 4.5));
+        assert_eq!(engine.evaluate(RuleStage::Position, &buy_input).skip, None);
+
+        let sell = trade("SELL", now);
+        let mut sell_input = input(&config, &sell, now);
+        sell_input.balance_breakdown = starved;
+        assert_eq!(engine.evaluate(RuleStage::Position, &sell_input).skip, None);
+
+        let wallet = test_config(&[("MIN_ORDER_SIZE_USD", "1"), ("SIZING_BALANCE", "WALLET")]);
+        let mut buy_input = input(&wallet, &buy, now);
+        buy_input.balance_breakdown = starved;
+        assert_eq!(engine.evaluate(RuleStage::Position, &buy_input).skip, None);
+    }
+
+    #[test]
+    fn open_position_limit_blocks_new_buys() {
+        let config = test_config(&[]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let buy = trade("BUY", now);
+        let mut buy_input = input(&config, &buy, now);
+        buy_input.open_positions = blocked();
+        let eval = engine.evaluate(RuleStage::Position, &buy_input);
+        assert_eq!(
+            eval.skip.as_deref(),
+            Some("max open positions reached (3/3)")
+        );
+        assert_eq!(eval.skipped_by, Some("open_positions"));
+    }
+
+    #[test]
+    fn buys_pause_inside_the_resolution_window() {
+        let config = test_config(&[]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let buy = trade("BUY", now);
+        let mut buy_input = input(&config, &buy, now);
+        buy_input.market_end = Some(now + Duration::minutes(10));
+        let eval = engine.evaluate(RuleStage::Position, &buy_input);
+        assert_eq!(eval.skipped_by, Some("resolution_window"));
+        assert_eq!(
+            eval.skip,
+            pause_reason(now + Duration::minutes(10), now, 30)
+        );
+
+        buy_input.market_end = Some(now + Duration::minutes(90));
+        assert_eq!(engine.evaluate(RuleStage::Position, &buy_input).skip, None);
+
+        // The hours setting widens the window past the minutes one.
+        let config = test_config(&[("SKIP_MARKETS_ENDING_WITHIN_HOURS", "2")]);
+        let mut buy_input = input(&config, &buy, now);
+        buy_input.market_end = Some(now + Duration::minutes(90));
+        let eval = engine.evaluate(RuleStage::Position, &buy_input);
+        assert_eq!(
+            eval.skip,
+            pause_reason(now + Duration::minutes(90), now, 120)
+        );
+    }
+
+    #[test]
+    fn degraded_balance_caps_the_order() {
+        let config = test_config(&[]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let sell = trade("SELL", now);
+        let mut sell_input = input(&config, &sell, now);
+        sell_input.balance = Some(BalanceReading::Degraded {
+            balance: 50.0,
+            age_secs: 120,
+        });
+        let eval = engine.evaluate(RuleStage::Position, &sell_input);
+        assert_eq!(eval.skip, None);
+        assert_eq!(eval.max_order_size_usd, Some(5.0));
+    }
+
+    #[test]
+    fn rules_left_out_of_skip_rules_do_not_run() {
+        let mut config = test_config(&[]);
+        config.skip_rules = vec!["degraded_balance".to_string()];
+        let engine = SkipRuleEngine::from_config(&config);
+        assert!(!engine.is_enabled("stale"));
+        let now = Utc::now();
+        let old = trade("BUY", now - Duration::hours(48));
+        let eval = engine.evaluate(RuleStage::Signal, &input(&config, &old, now));
+        assert_eq!(eval.skip, None);
+        let mut buy_input = input(&config, &old, now);
+        buy_input.open_positions = blocked();
+        assert_eq!(engine.evaluate(RuleStage::Position, &buy_input).skip, None);
+    }
+
+    #[test]
+    fn market_lists_filter_by_slug_event_or_condition() {
+        let config = test_config(&[
+            ("MARKET_WHITELIST", "nba-finals, 0xABC"),
+            ("MARKET_BLACKLIST", "lakers-vs-celtics"),
+        ]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let market = |slug: &str, event: &str, condition: &str| UserActivity {
+            slug: Some(slug.to_string()),
+            event_slug: Some(event.to_string()),
+            condition_id: Some(condition.to_string()),
+            ..trade("BUY", now)
+        };
+        let skip = |trade: &UserActivity| {
+            let eval = engine.evaluate(RuleStage::Signal, &input(&config, trade, now));
+            (eval.skip, eval.skipped_by)
+        };
+
+        // Whitelisted by event slug, and by condition id regardless of case.
+        assert_eq!(skip(&market("game-7", "nba-finals", "0x1")), (None, None));
+        assert_eq!(skip(&market("other", "other", "0xabc")), (None, None));
+        // Everything else is left out while the whitelist is set.
+        assert_eq!(
+            skip(&market("election-2028", "us-politics", "0x2")),
+            (
+                Some("market election-2028 is not in MARKET_WHITELIST".to_string()),
+                Some("market_filter")
+            )
+        );
+        // On both lists: the blacklist wins.
+        assert_eq!(
+            skip(&market("lakers-vs-celtics", "nba-finals", "0x3")).0.as_deref(),
+            Some("market lakers-vs-celtics is in MARKET_BLACKLIST")
+        );
+
+        // A blacklist alone leaves every other market open.
+        let open = test_config(&[("MARKET_BLACKLIST", "us-politics")]);
+        let engine = SkipRuleEngine::from_config(&open);
+        let nba = market("nba-game", "nba", "0x4");
+        let eval = engine.evaluate(RuleStage::Signal, &input(&open, &nba, now));
+        assert_eq!(eval.skip, None);
+        let politics = market("election-2028", "us-politics", "0x5");
+        let eval = engine.evaluate(RuleStage::Signal, &input(&open, &politics, now));
+        assert_eq!(
+            eval.skip.as_deref(),
+            Some("market us-politics is in MARKET_BLACKLIST")
+        );
+    }
+
+    #[test]
+    fn price_band_holds_back_buys_outside_it() {
+        let config = test_config(&[("MIN_COPY_PRICE", "0.05"), ("MAX_COPY_PRICE", "0.95")]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let priced = |side: &str, price: Option<f64>| UserActivity {
+            price,
+            ..trade(side, now)
+        };
+        let skip = |trade: &UserActivity| {
+            engine
+                .evaluate(RuleStage::Signal, &input(&config, trade, now))
+                .skip
+        };
+
+        assert_eq!(skip(&priced("BUY", Some(0.05))), None);
+        assert_eq!(skip(&priced("BUY", Some(0.5))), None);
+        assert_eq!(
+            skip(&priced("BUY", Some(0.03))).as_deref(),
+            Some("price $0.0300 outside the copy band $0.0500-$0.9500")
+        );
+        assert!(skip(&priced("BUY", Some(0.97))).is_some());
+        // Exiting at 0.97 is fine unless sells are held to the band too.
+        assert_eq!(skip(&priced("SELL", Some(0.97))), None);
+        let strict = test_config(&[
+            ("MAX_COPY_PRICE", "0.95"),
+            ("PRICE_BAND_INCLUDES_SELLS", "true"),
+        ]);
+        let sell = priced("SELL", Some(0.97));
+        let eval = SkipRuleEngine::from_config(&strict)
+            .evaluate(RuleStage::Signal, &input(&strict, &sell, now));
+        assert_eq!(eval.skipped_by, Some("price_band"));
+    }
+
+    #[test]
+    fn price_band_skips_signals_without_a_price() {
+        let now = Utc::now();
+        let activity = RtdsActivity {
+            activity_type: Some("TRADE".to_string()),
+            side: Some("BUY".to_string()),
+            size: Some(100.0),
+            price: None,
+            ..Default::default()
+        };
+        assert_eq!(activity.price(), Err("missing price".to_string()));
+        let buy = UserActivity {
+            price: activity.price().ok(),
+            ..trade("BUY", now)
+        };
+
+        // With the band off, a missing price is left to the rest of the pipeline.
+        let off = test_config(&[]);
+        let eval = SkipRuleEngine::from_config(&off)
+            .evaluate(RuleStage::Signal, &input(&off, &buy, now));
+        assert!(!eval.trace.contains(&"price_band:skip".to_string()));
+
+        let config = test_config(&[("MIN_COPY_PRICE", "0.05")]);
+        let eval = SkipRuleEngine::from_config(&config)
+            .evaluate(RuleStage::Signal, &input(&config, &buy, now));
+        assert_eq!(
+            eval.skip.as_deref(),
+            Some("no price to check against the copy band $0.0500-$1.0000")
+        );
+    }
+
+    #[test]
+    fn position_actions_filter_globally_and_per_trader() {
+        let config = test_config(&[
+            ("COPY_POSITION_ACTIONS", "open,close"),
+            (
+                "COPY_POSITION_ACTIONS_BY_TRADER",
+                "0x2222222222222222222222222222222222222222:open,add,reduce,close",
+            ),
+            ("TRADER_GROUPS", "whale:0x3333333333333333333333333333333333333333"),
+        ]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let sell = trade("SELL", now);
+        let skip = |trader: &str, action: PositionAction| {
+            let mut input = input(&config, &sell, now);
+            input.trader = trader;
+            input.position_action = Some(action);
+            engine.evaluate(RuleStage::Position, &input)
+        };
+
+        let other = "0x4444444444444444444444444444444444444444";
+        assert_eq!(skip(other, PositionAction::Close).skip, None);
+        let eval = skip(other, PositionAction::Reduce);
+        assert_eq!(eval.skipped_by, Some("position_action"));
+        assert_eq!(
+            eval.skip.as_deref(),
+            Some("trader's reduce is not in the copied position actions")
+        );
+        // The default input's trader has its own override copying everything.
+        assert_eq!(
+            skip("0x2222222222222222222222222222222222222222", PositionAction::Reduce).skip,
+            None
+        );
+        // No action (a manual copy) is never filtered.
+        let mut manual = input(&config, &sell, now);
+        manual.trader = other;
+        assert_eq!(engine.evaluate(RuleStage::Position, &manual).skip, None);
+
+        let grouped = test_config(&[
+            ("TRADER_GROUPS", "whale:0x3333333333333333333333333333333333333333"),
+            ("COPY_POSITION_ACTIONS_BY_TRADER", "WHALE:open"),
+        ]);
+        let member = "0x3333333333333333333333333333333333333333";
+        assert!(grouped.copies_position_action(member, PositionAction::Open));
+        assert!(!grouped.copies_position_action(member, PositionAction::Add));
+        assert!(grouped.copies_position_action(other, PositionAction::Add));
+    }
+
+    #[test]
+    fn market_overrides_come_before_the_market_lists() {
+        let config = test_config(&[("MARKET_WHITELIST", "0xabc")]);
+        let engine = SkipRuleEngine::from_config(&config);
+        let now = Utc::now();
+        let buy = UserActivity {
+            condition_id: Some("0xabc".to_string()),
+            ..trade("BUY", now)
+        };
+        let with = |market: MarketOverride, manual: bool| {
+            let mut input = input(&config, &buy, now);
+            input.market_override = Some(market);
+            input.manual = manual;
+            engine.evaluate(RuleStage::Signal, &input)
+        };
+
+        // Whitelisted, but set to never trade: the override wins.
+        let never = MarketOverride {
+            never_trade: true,
+            ..Default::default()
+        };
+        let eval = with(never.clone(), false);
+        assert_eq!(eval.skipped_by, Some("market_override"));
+        assert_eq!(eval.skip.as_deref(), Some("market 0xabc is set to never trade"));
+        assert_eq!(eval.reason, Some(SkipReason::filtered("market_override")));
+        assert_eq!(with(never, true).skipped_by, Some("market_override"));
+
+        // Confirm-manually markets only take copies I ask for.
+        let confirm = MarketOverride {
+            confirm: true,
+            ..Default::default()
+        };
+        assert_eq!(with(confirm.clone(), false).skipped_by, Some("market_override"));
+        assert_eq!(with(confirm, true).skip, None);
+
+        // A cap or a note alone leaves the rest of the rules to decide.
+        let noted = MarketOverride {
+            cap_usd: Some(25.0),
+            note: Some("thin book".to_string()),
+            ..Default::default()
+        };
+        let eval = with(noted, false);
+        assert_eq!(eval.skip, None);
+        assert!(eval.trace.contains(&"market_filter:allow".to_string()));
+    }
+}
@@ -0,0 +1,109 @@
+//! The status document. Modules register named sections; `GET /status` returns them all as
+//! one JSON object, served by `status_api` on localhost when `STATUS_PORT` is set. A few admin
+//! actions are registered here too, but only the token-guarded status API serves them: the
+//! localhost endpoint has no token, and any page open in a browser on the machine can POST to
+//! localhost.
+
+use futures_util::future::BoxFuture;
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+type Section = Box<dyn Fn() -> Value + Send + Sync>;
+type ActionResult = std::result::Result<Value, String>;
+type Action = Arc<dyn Fn(&str) -> BoxFuture<'static, ActionResult> + Send + Sync>;
+
+static SECTIONS: Mutex<Vec<(&'static str, Section)>> = Mutex::new(Vec::new());
+static ACTIONS: Mutex<Vec<(&'static str, Action)>> = Mutex::new(Vec::new());
+
+/// Adds `name` to the status document, computed by `section` on every request. Registering
+/// a name again replaces the earlier section.
+pub fn register(name: &'static str, section: impl Fn() -> Value + Send + Sync + 'static) {
+    if let Ok(mut sections) = SECTIONS.lock() {
+        sections.retain(|(n, _)| *n != name);
+        sections.push((name, Box::new(section)));
+    }
+}
+
+/// Has the status API answer `POST /<name>/<rest>` with `action(rest)`: 200 with its value,
+/// or 400 with its error. Registering a name again replaces the earlier action.
+pub fn register_action(
+    name: &'static str,
+    action: impl Fn(&str) -> ActionResult + Send + Sync + 'static,
+) {
+    register_async_action(name, move |rest| std::future::ready(action(&rest)));
+}
+
+/// `register_action` for actions that wait on the network, e.g. placing an order.
+pub fn register_async_action<F, Fut>(name: &'static str, action: F)
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ActionResult> + Send + 'static,
+{
+    let action: Action = Arc::new(move |rest: &str| Box::pin(action(rest.to_string())));
+    if let Ok(mut actions) = ACTIONS.lock() {
+        actions.retain(|(n, _)| *n != name);
+        actions.push((name, action));
+    }
+}
+
+/// Runs the action `path` names, or `None` when none is registered for it.
+pub async fn run_action(path: &str) -> Option<ActionResult> {
+    let (name, rest) = path.trim_start_matches('/').split_once('/')?;
+    let action = {
+        let actions = ACTIONS.lock().ok()?;
+        actions.iter().find(|(n, _)| *n == name)?.1.clone()
+    };
+    Some(action(rest).await)
+}
+
+/// Sets `name` to a fixed value until it is published again.
+pub fn publish(name: &'static str, value: Value) {
+    register(name, move || value.clone());
+}
+
+/// The document `GET /status` returns.
+pub fn snapshot() -> Value {
+    let mut doc = Map::new();
+    if let Ok(sections) = SECTIONS.lock() {
+        for (name, section) in sections.iter() {
+            doc.insert(name.to_string(), section());
+        }
+    }
+    Value::Object(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_has_every_registered_section() {
+        publish("test_fixed", serde_json::json!({ "max": 3 }));
+        register("test_computed", || serde_json::json!("live"));
+        let doc = snapshot();
+        assert_eq!(doc["test_fixed"]["max"], 3);
+        assert_eq!(doc["test_computed"], "live");
+    }
+
+    #[tokio::test]
+    async fn actions_run_by_path() {
+        register_action("test_action", |rest| match rest {
+            "ok" => Ok(serde_json::json!({ "done": true })),
+            other => Err(format!("bad {}", other)),
+        });
+        assert_eq!(
+            run_action("/test_action/ok").await,
+            Some(Ok(serde_json::json!({ "done": true })))
+        );
+        assert_eq!(run_action("/test_action/x").await, Some(Err("bad x".to_string())));
+        assert_eq!(run_action("/missing/ok").await, None);
+    }
+
+    #[test]
+    fn publishing_again_replaces_the_section() {
+        publish("test_replaced", serde_json::json!(1));
+        publish("test_replaced", serde_json::json!(2));
+        assert_eq!(snapshot()["test_replaced"], 2);
+    }
+}
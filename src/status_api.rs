@@ -0,0 +1,290 @@
+//! Remote status API, for checking on the bot away from the machine it runs on: the status
+//! document, the health check, my positions, the latest copies with their sizing, and a pause
+//! switch. Off unless `STATUS_API_ADDR` is set. Unlike the localhost `/status` endpoint (also
+//! served from here, on `STATUS_PORT`) it can be reached from other hosts, so every route wants
+//! `Authorization: Bearer <STATUS_API_TOKEN>`.
+//!
+//! `POST /pause` holds a full pause in the trading state machine: the monitor keeps running and
+//! signals are journaled as skipped, but no order is placed until `POST /resume`. The admin
+//! actions other modules register with `status::register_action` (market overrides, advisory
+//! acks, manual position copies) answer `POST /<name>/<rest>` here, behind the same token.
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode, Uri};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+use crate::config::EnvConfig;
+use crate::status;
+use crate::supervisor::{supervisor, STOP_LAST};
+use crate::trading_state::{self, PauseLevel};
+use crate::types::{UserActivity, UserPosition};
+use crate::utils::{run_health_check, DataApiClient, HealthCheckResult, Logger};
+
+/// The trading state source the API's pause is held under.
+pub const PAUSE_SOURCE: &str = "status_api";
+/// Copies kept for `GET /trades`.
+const RECENT_TRADES: usize = 100;
+/// `GET /trades` without `?limit=`.
+const DEFAULT_TRADES: usize = 20;
+const POSITIONS_REFRESH: Duration = Duration::from_secs(60);
+
+/// One executed copy, as `GET /trades` lists it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CopiedTrade {
+    pub timestamp: i64,
+    pub trader: String,
+    pub transaction_hash: Option<String>,
+    pub side: String,
+    pub title: Option<String>,
+    pub outcome: Option<String>,
+    pub asset: Option<String>,
+    pub my_usd: f64,
+    pub my_tokens: f64,
+    pub price: f64,
+    pub trader_usd: Option<f64>,
+    /// How the order was sized (`OrderSizeCalculation::reasoning`).
+    pub reasoning: Option<String>,
+}
+
+impl CopiedTrade {
+    pub fn new(trader: &str, trade: &UserActivity, usd: f64, tokens: f64) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            trader: trader.to_string(),
+            transaction_hash: trade.transaction_hash.clone(),
+            side: trade.side.clone().unwrap_or_default(),
+            title: trade.title.clone(),
+            outcome: trade.outcome.clone(),
+            asset: trade.asset.clone(),
+            my_usd: usd,
+            my_tokens: tokens,
+            price: if tokens > 0.0 { usd / tokens } else { 0.0 },
+            trader_usd: trade.usdc_size,
+            reasoning: None,
+        }
+    }
+}
+
+static RECENT: Mutex<VecDeque<CopiedTrade>> = Mutex::new(VecDeque::new());
+/// My positions and when they were fetched; `None` until the first refresh.
+static POSITIONS: Mutex<Option<(i64, Vec<UserPosition>)>> = Mutex::new(None);
+
+/// Adds an executed copy to `GET /trades`, dropping the oldest past the limit.
+pub fn record_copy(copy: CopiedTrade) {
+    if let Ok(mut recent) = RECENT.lock() {
+        if recent.len() == RECENT_TRADES {
+            recent.pop_front();
+        }
+        recent.push_back(copy);
+    }
+}
+
+/// Up to `limit` copies, newest first.
+pub fn recent_copies(limit: usize) -> Vec<CopiedTrade> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().rev().take(limit).cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Replaces the positions `GET /positions` serves.
+pub fn set_positions(positions: Vec<UserPosition>) {
+    if let Ok(mut cached) = POSITIONS.lock() {
+        *cached = Some((chrono::Utc::now().timestamp(), positions));
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    config: Arc<EnvConfig>,
+    token: Arc<str>,
+}
+
+/// Compares every byte, so a wrong token takes as long to reject however much of it matched.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(token.trim(), &state.token));
+    if !authorized {
+        let body = Json(json!({ "error": "missing or wrong bearer token" }));
+        return (StatusCode::UNAUTHORIZED, body).into_response();
+    }
+    next.run(request).await
+}
+
+async fn status_doc() -> Json<Value> {
+    Json(status::snapshot())
+}
+
+async fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({ "error": "not found" }))).into_response()
+}
+
+async fn health(State(state): State<ApiState>) -> Json<HealthCheckResult> {
+    Json(run_health_check(&state.config).await)
+}
+
+async fn positions() -> Response {
+    match POSITIONS.lock().ok().and_then(|cached| cached.clone()) {
+        Some((updated_at, positions)) => {
+            Json(json!({ "updated_at": updated_at, "positions": positions })).into_response()
+        }
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "positions not fetched yet" })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct TradesQuery {
+    limit: Option<usize>,
+}
+
+async fn trades(Query(query): Query<TradesQuery>) -> Json<Vec<CopiedTrade>> {
+    Json(recent_copies(query.limit.unwrap_or(DEFAULT_TRADES)))
+}
+
+fn trading_state_json() -> Json<Value> {
+    Json(json!({ "trading_state": trading_state::current().to_string() }))
+}
+
+async fn pause() -> Json<Value> {
+    trading_state::pause(
+        PAUSE_SOURCE,
+        PauseLevel::All,
+        "paused through the status API",
+    );
+    trading_state_json()
+}
+
+/// Lifts the API's own pause; pauses held by other sources stay.
+async fn resume() -> Json<Value> {
+    trading_state::resume(PAUSE_SOURCE);
+    trading_state_json()
+}
+
+/// Runs the registered action the path names. The raw path, so values keep the encoding
+/// the action decodes itself.
+async fn action(uri: Uri) -> Response {
+    match status::run_action(uri.path()).await {
+        Some(Ok(value)) => Json(value).into_response(),
+        Some(Err(e)) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+        None => not_found().await,
+    }
+}
+
+/// The API's routes, all behind the bearer token.
+pub fn router(config: Arc<EnvConfig>, token: &str) -> Router {
+    let state = ApiState {
+        config,
+        token: Arc::from(token),
+    };
+    Router::new()
+        .route("/status", get(status_doc))
+        .route("/health", get(health))
+        .route("/positions", get(positions))
+        .route("/trades", get(trades))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/{*action}", post(action))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// The localhost `STATUS_PORT` endpoint: `GET /status` only, without a token, so no admin
+/// action is reachable through it.
+pub fn local_router() -> Router {
+    Router::new()
+        .route("/status", get(status_doc))
+        .fallback(not_found)
+}
+
+/// Serves `local_router` on `127.0.0.1:port` (0 picks a free port) until the task is dropped.
+pub async fn serve_local(port: u16) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    Logger::info(&format!("Status endpoint: http://{}/status", listener.local_addr()?));
+    axum::serve(listener, local_router()).await?;
+    Ok(())
+}
+
+/// Serves `router` on `listener` until the task is dropped.
+pub async fn serve(listener: TcpListener, router: Router) -> anyhow::Result<()> {
+    Logger::info(&format!("Status API: http://{}", listener.local_addr()?));
+    axum::serve(listener, router).await?;
+    Ok(())
+}
+
+async fn refresh_positions(config: &EnvConfig, http: &reqwest::Client) {
+    match DataApiClient::from_config(config, http)
+        .get_positions(&config.proxy_wallet)
+        .await
+    {
+        Ok(positions) => set_positions(positions),
+        Err(e) => Logger::warning(&format!("Status API positions refresh failed: {}", e)),
+    }
+}
+
+/// Sends `POST <path>` to the running bot's status API with its token, for the CLI commands
+/// that edit a running bot. `None` when the API isn't configured or doesn't answer.
+pub async fn post_action(config: &EnvConfig, path: &str) -> Option<reqwest::Response> {
+    let (mut addr, Some(token)) = (config.status_api_addr?, config.status_api_token.as_deref())
+    else {
+        return None;
+    };
+    if addr.ip().is_unspecified() {
+        addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+    }
+    reqwest::Client::new()
+        .post(format!("http://{}{}", addr, path))
+        .bearer_auth(token)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .ok()
+}
+
+/// Starts the API and its positions refresh when `STATUS_API_ADDR` is set.
+pub fn start(config: Arc<EnvConfig>) {
+    let (Some(addr), Some(token)) = (config.status_api_addr, config.status_api_token.clone())
+    else {
+        return;
+    };
+    let router = router(config.clone(), &token);
+    supervisor().spawn("status-api", STOP_LAST, 5, move || {
+        let router = router.clone();
+        async move { serve(TcpListener::bind(addr).await?, router).await }
+    });
+    let http = reqwest::Client::new();
+    supervisor().spawn("status-api-positions", STOP_LAST, 5, move || {
+        let (config, http) = (config.clone(), http.clone());
+        async move {
+            loop {
+                refresh_positions(&config, &http).await;
+                tokio::time::sleep(POSITIONS_REFRESH).await;
+            }
+        }
+    });
+}
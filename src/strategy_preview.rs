@@ -0,0 +1,338 @@
+//! `polymarket-copy-rust strategy preview`: prints what the loaded sizing settings copy over a
+//! range of trader order sizes, which cap or floor decides each row, and every stretch where a
+//! larger trade gets a smaller copy. Nothing is sent anywhere.
+
+use anyhow::{Context, Result};
+
+use crate::config::{
+    self, calculate_order_size, AdaptiveTail, CopyStrategy, CopyStrategyConfig, EnvConfig,
+    SizingInputs,
+};
+
+const USAGE: &str = "Usage: polymarket-copy-rust strategy preview [--from USD] [--to USD] [--rows N]
+
+Prints the copy size your sizing settings give a range of trader order sizes (into an empty
+position, balance not limiting), the cap or floor that binds, and where a larger trade gets
+a smaller copy.
+--from   smallest trader order (default $10)
+--to     largest trader order (default 4x ADAPTIVE_THRESHOLD_USD, else $10000)
+--rows   sizes in between, evenly spaced on a log scale (default 20)";
+
+const CHART_WIDTH: usize = 30;
+/// Sizing steps that scale the amount; every other step is a cap or floor.
+const SCALING_STEPS: [&str; 3] = ["base", "adaptive", "multiplier"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewRow {
+    pub trader_usd: f64,
+    /// What the strategy alone asks for, before multipliers, caps and floors.
+    pub strategy_usd: f64,
+    pub copy_usd: f64,
+    /// Caps and floors that changed the amount, in the order applied.
+    pub limited_by: Vec<String>,
+}
+
+impl PreviewRow {
+    pub fn effective_percent(&self) -> f64 {
+        percent(self.copy_usd, self.trader_usd)
+    }
+
+    pub fn strategy_percent(&self) -> f64 {
+        percent(self.strategy_usd, self.trader_usd)
+    }
+}
+
+fn percent(part: f64, of: f64) -> f64 {
+    if of > 0.0 {
+        part / of * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// A stretch of trader sizes over which the amount shrinks as the trade grows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Falling {
+    pub from_trader_usd: f64,
+    pub to_trader_usd: f64,
+    pub from_usd: f64,
+    pub to_usd: f64,
+}
+
+/// `rows` sizes from `from` to `to`, log spaced, plus the adaptive threshold and twice it
+/// when they fall inside: the curve bends there.
+pub fn sample_sizes(config: &CopyStrategyConfig, from: f64, to: f64, rows: usize) -> Vec<f64> {
+    let rows = rows.max(2);
+    let ratio = (to / from).powf(1.0 / (rows - 1) as f64);
+    let mut sizes: Vec<f64> = (0..rows).map(|i| from * ratio.powi(i as i32)).collect();
+    if config.strategy == CopyStrategy::Adaptive {
+        let threshold = config.adaptive_threshold.unwrap_or(500.0);
+        sizes.extend(
+            [threshold, threshold * 2.0]
+                .into_iter()
+                .filter(|s| *s > from && *s < to),
+        );
+    }
+    sizes.sort_by(f64::total_cmp);
+    sizes.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    sizes
+}
+
+pub fn preview(config: &CopyStrategyConfig, sizes: &[f64]) -> Vec<PreviewRow> {
+    sizes
+        .iter()
+        .map(|&trader_usd| {
+            let calc = calculate_order_size(
+                config,
+                &SizingInputs::default(),
+                trader_usd,
+                f64::INFINITY,
+                0.0,
+            );
+            let limited_by = calc
+                .steps
+                .iter()
+                .filter(|s| !SCALING_STEPS.contains(&s.name.as_str()))
+                .filter(|s| (s.output_usd - s.input_usd).abs() > 1e-9)
+                .map(|s| s.name.clone())
+                .collect();
+            PreviewRow {
+                trader_usd,
+                strategy_usd: calc.base_amount,
+                copy_usd: calc.final_amount,
+                limited_by,
+            }
+        })
+        .collect()
+}
+
+/// Consecutive rows where `amount` drops while the trader's size grows, merged into stretches.
+pub fn falling(rows: &[PreviewRow], amount: impl Fn(&PreviewRow) -> f64) -> Vec<Falling> {
+    let mut stretches: Vec<Falling> = Vec::new();
+    for pair in rows.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        if amount(b) >= amount(a) - 1e-9 {
+            continue;
+        }
+        match stretches.last_mut() {
+            Some(last) if last.to_trader_usd == a.trader_usd => {
+                last.to_trader_usd = b.trader_usd;
+                last.to_usd = amount(b);
+            }
+            _ => stretches.push(Falling {
+                from_trader_usd: a.trader_usd,
+                to_trader_usd: b.trader_usd,
+                from_usd: amount(a),
+                to_usd: amount(b),
+            }),
+        }
+    }
+    stretches
+}
+
+fn describe(config: &CopyStrategyConfig) -> String {
+    let mut parts = vec![match config.strategy {
+        CopyStrategy::Percentage => format!("PERCENTAGE {}%", config.copy_size),
+        CopyStrategy::Fixed => format!("FIXED ${:.2}", config.copy_size),
+        CopyStrategy::Adaptive => format!(
+            "ADAPTIVE {}% ({}% for small trades, {}% from ${:.0}), tail {}",
+            config.copy_size,
+            config.adaptive_max_percent.unwrap_or(config.copy_size),
+            config.adaptive_min_percent.unwrap_or(config.copy_size),
+            config.adaptive_threshold.unwrap_or(500.0) * 2.0,
+            match config.adaptive_tail {
+                AdaptiveTail::Plateau => "plateau",
+                AdaptiveTail::Continue => "continue",
+            }
+        ),
+        CopyStrategy::PortfolioShare => "PORTFOLIO_SHARE".to_string(),
+    }];
+    if let Some(m) = config.trade_multiplier {
+        parts.push(format!("{}x multiplier", m));
+    }
+    if config.tiered_multipliers.as_ref().is_some_and(|t| !t.is_empty()) {
+        parts.push("tiered multipliers".to_string());
+    }
+    parts.push(format!("max order ${:.2}", config.max_order_size_usd));
+    parts.push(format!("min order ${:.2}", config.min_order_size_usd));
+    if let Some(max) = config.max_position_size_usd {
+        parts.push(format!("max position ${:.2}", max));
+    }
+    parts.join(", ")
+}
+
+fn print_table(config: &CopyStrategyConfig, rows: &[PreviewRow]) {
+    println!("Sizing: {}", describe(config));
+    println!();
+    println!(
+        "{:>12}  {:>10}  {:>10}  {:>11}  {:<24}  Copy size",
+        "Trader $", "Strategy %", "Copy $", "Effective %", "Limited by"
+    );
+    let largest = rows.iter().map(|r| r.copy_usd).fold(0.0, f64::max);
+    for row in rows {
+        let width = if largest > 0.0 {
+            (row.copy_usd / largest * CHART_WIDTH as f64).round() as usize
+        } else {
+            0
+        };
+        let limited_by = if row.limited_by.is_empty() {
+            "-".to_string()
+        } else {
+            row.limited_by.join(", ")
+        };
+        println!(
+            "{:>12.2}  {:>10.2}  {:>10.2}  {:>11.2}  {:<24}  {}",
+            row.trader_usd,
+            row.strategy_percent(),
+            row.copy_usd,
+            row.effective_percent(),
+            limited_by,
+            "#".repeat(width)
+        );
+    }
+}
+
+fn print_warnings(config: &CopyStrategyConfig, rows: &[PreviewRow]) {
+    let copies = falling(rows, |r| r.copy_usd);
+    let curve = falling(rows, |r| r.strategy_usd);
+    println!();
+    for f in &copies {
+        println!(
+            "⚠ A larger trade gets a smaller copy: ${:.2} → ${:.2} as the trader's order goes ${:.2} → ${:.2}",
+            f.from_usd, f.to_usd, f.from_trader_usd, f.to_trader_usd
+        );
+    }
+    for f in curve.iter().filter(|f| !copies.iter().any(|c| overlaps(c, f))) {
+        println!(
+            "⚠ The strategy asks for less (${:.2} → ${:.2}) as the trader's order goes ${:.2} → ${:.2}; caps or floors hide it at these settings",
+            f.from_usd, f.to_usd, f.from_trader_usd, f.to_trader_usd
+        );
+    }
+    if config.strategy == CopyStrategy::Adaptive && config.adaptive_tail == AdaptiveTail::Plateau {
+        println!(
+            "Past ${:.2} the percent stays at {}%, so copies grow with the trade again; ADAPTIVE_TAIL=CONTINUE keeps shrinking it instead.",
+            config.adaptive_threshold.unwrap_or(500.0) * 2.0,
+            config.adaptive_min_percent.unwrap_or(config.copy_size)
+        );
+    }
+    if copies.is_empty() && curve.is_empty() {
+        println!("Copy size never shrinks as the trader's order grows over this range.");
+    }
+}
+
+fn overlaps(a: &Falling, b: &Falling) -> bool {
+    a.from_trader_usd < b.to_trader_usd && b.from_trader_usd < a.to_trader_usd
+}
+
+fn parse_usd(flag: &str, value: Option<&String>) -> Result<f64> {
+    let value = value.with_context(|| format!("{} needs a value\n\n{}", flag, USAGE))?;
+    value
+        .trim_start_matches('$')
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite() && *v > 0.0)
+        .with_context(|| format!("{} must be a positive amount, got {:?}", flag, value))
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    if args.first().map(String::as_str) != Some("preview") {
+        anyhow::bail!("{}", USAGE);
+    }
+    let (mut from, mut to, mut rows) = (None, None, 20usize);
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        match flag.as_str() {
+            "--from" => from = Some(parse_usd(flag, rest.next())?),
+            "--to" => to = Some(parse_usd(flag, rest.next())?),
+            "--rows" => {
+                rows = rest
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|n| *n >= 2)
+                    .with_context(|| format!("--rows must be 2 or more\n\n{}", USAGE))?
+            }
+            _ => anyhow::bail!("{}", USAGE),
+        }
+    }
+    dotenvy::dotenv().ok();
+    config::load_config_file()?;
+    let strategy = EnvConfig::parse()?.copy_strategy_config;
+    if strategy.strategy == CopyStrategy::PortfolioShare {
+        anyhow::bail!(
+            "PORTFOLIO_SHARE sizes from the trader's portfolio and your balance, not the order alone; there is no curve to preview"
+        );
+    }
+    let from = from.unwrap_or(10.0);
+    let to = to.unwrap_or(match strategy.strategy {
+        CopyStrategy::Adaptive => strategy.adaptive_threshold.unwrap_or(500.0) * 4.0,
+        _ => 10_000.0,
+    });
+    if to <= from {
+        anyhow::bail!("--to (${:.2}) must be above --from (${:.2})", to, from);
+    }
+    let rows = preview(&strategy, &sample_sizes(&strategy, from, to, rows));
+    print_table(&strategy, &rows);
+    print_warnings(&strategy, &rows);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+
+    fn adaptive(overrides: &[(&str, &str)]) -> CopyStrategyConfig {
+        let mut vars = vec![
+            ("COPY_STRATEGY", "ADAPTIVE"),
+            ("COPY_SIZE", "10"),
+            ("ADAPTIVE_MIN_PERCENT", "5"),
+            ("ADAPTIVE_MAX_PERCENT", "20"),
+            ("ADAPTIVE_THRESHOLD_USD", "500"),
+            ("MAX_ORDER_SIZE_USD", "1000"),
+        ];
+        vars.extend_from_slice(overrides);
+        test_config(&vars).copy_strategy_config
+    }
+
+    #[test]
+    fn samples_include_the_threshold_and_twice_it() {
+        let sizes = sample_sizes(&adaptive(&[]), 10.0, 2_000.0, 5);
+        assert_eq!(sizes.len(), 7);
+        assert!(sizes.contains(&500.0) && sizes.contains(&1_000.0));
+        assert!((sizes[0] - 10.0).abs() < 1e-9 && (sizes[6] - 2_000.0).abs() < 1e-6);
+        assert!(sizes.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn copies_dipping_before_twice_the_threshold_are_flagged() {
+        let rows = preview(&adaptive(&[]), &[500.0, 900.0, 1_000.0, 2_000.0]);
+        let dips = falling(&rows, |r| r.copy_usd);
+        // 900 × 6% = $54, 1000 × 5% = $50.
+        assert_eq!(
+            dips,
+            vec![Falling {
+                from_trader_usd: 900.0,
+                to_trader_usd: 1_000.0,
+                from_usd: 54.0,
+                to_usd: 50.0,
+            }]
+        );
+        // The continue tail holds $50 instead of growing again.
+        let rows = preview(&adaptive(&[("ADAPTIVE_TAIL", "continue")]), &[1_000.0, 2_000.0]);
+        assert!(falling(&rows, |r| r.copy_usd).is_empty());
+        assert!((rows[1].copy_usd - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rows_name_the_caps_and_floors_that_bind() {
+        let config = adaptive(&[("MAX_ORDER_SIZE_USD", "48")]);
+        let rows = preview(&config, &[5.0, 300.0, 900.0, 1_000.0]);
+        assert_eq!(rows[0].limited_by, ["min_order"]);
+        assert!(rows[1].limited_by.is_empty());
+        assert_eq!(rows[2].limited_by, ["max_order_cap"]);
+        assert!((rows[2].copy_usd - 48.0).abs() < 1e-9);
+        // The cap flattens the copies; the strategy's own dip is still there.
+        assert!(falling(&rows, |r| r.copy_usd).is_empty());
+        assert_eq!(falling(&rows, |r| r.strategy_usd).len(), 1);
+    }
+}
@@ -0,0 +1,508 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::alerts::{self, AlertKind};
+use crate::utils::Logger;
+
+const MAX_BACKOFF_SECS: u64 = 60;
+/// How long `shutdown` waits for each task to stop after cancelling it.
+const STOP_GRACE: Duration = Duration::from_secs(2);
+/// How long the executor gets to work through the trades already queued before it is
+/// cancelled.
+const EXECUTOR_DRAIN: Duration = Duration::from_secs(30);
+
+/// Shutdown order: tasks that take in new work stop first, then the executor, then helpers.
+pub const STOP_FIRST: u8 = 0;
+pub const STOP_EXECUTOR: u8 = 1;
+pub const STOP_LAST: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Running,
+    Restarting,
+    FailedPermanent,
+    Stopped,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Running => "running",
+            TaskStatus::Restarting => "restarting",
+            TaskStatus::FailedPermanent => "failed-permanent",
+            TaskStatus::Stopped => "stopped",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskReport {
+    pub name: &'static str,
+    pub status: TaskStatus,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+impl TaskReport {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "status": self.status.as_str(),
+            "restarts": self.restarts,
+            "last_error": self.last_error,
+        })
+    }
+}
+
+struct TaskEntry {
+    report: TaskReport,
+    stop_order: u8,
+    abort: Option<AbortHandle>,
+    /// The restart loop; cancelled on shutdown if it doesn't finish in time. Taken once the
+    /// task has been stopped.
+    runner: Option<AbortHandle>,
+    /// Cancelled when the restart loop ends.
+    finished: CancellationToken,
+}
+
+/// A started task, for its owner to stop (see `TradeExecutorHandle::stop`).
+pub struct TaskHandle {
+    entry: Arc<Mutex<TaskEntry>>,
+    runner: JoinHandle<()>,
+}
+
+impl TaskHandle {
+    /// Waits up to `timeout` for the task to return on its own, then cancels it. Call
+    /// `begin_shutdown` first, or a task that returns is restarted. False when the task had
+    /// to be cancelled.
+    pub async fn join(mut self, timeout: Duration) -> bool {
+        if tokio::time::timeout(timeout, &mut self.runner).await.is_ok() {
+            if let Ok(mut e) = self.entry.lock() {
+                e.runner = None;
+            }
+            return true;
+        }
+        let abort = self.entry.lock().ok().and_then(|mut e| {
+            e.runner = None;
+            e.abort.take()
+        });
+        if let Some(abort) = abort {
+            abort.abort();
+        }
+        if tokio::time::timeout(STOP_GRACE, &mut self.runner).await.is_err() {
+            self.runner.abort();
+            if let Ok(mut e) = self.entry.lock() {
+                e.report.status = TaskStatus::Stopped;
+            }
+        }
+        false
+    }
+}
+
+/// Owns the bot's background tasks: restarts the ones that exit or panic unexpectedly
+/// (exponential backoff, bounded restarts) and stops them in order on shutdown.
+#[derive(Default)]
+pub struct Supervisor {
+    tasks: Mutex<Vec<Arc<Mutex<TaskEntry>>>>,
+    stopping: Arc<AtomicBool>,
+}
+
+/// The process-wide supervisor.
+pub fn supervisor() -> &'static Supervisor {
+    static SUPERVISOR: OnceLock<Supervisor> = OnceLock::new();
+    SUPERVISOR.get_or_init(Supervisor::default)
+}
+
+impl Supervisor {
+    /// Registers and starts a task. `make` builds a fresh future for every (re)start;
+    /// a task that returns while the supervisor isn't stopping counts as a failure, one that
+    /// returns after `begin_shutdown` is a clean exit.
+    pub fn spawn<F, Fut>(
+        &self,
+        name: &'static str,
+        stop_order: u8,
+        max_restarts: u32,
+        make: F,
+    ) -> TaskHandle
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.start(name, stop_order, max_restarts, false, make)
+    }
+
+    /// Registers and starts a task that runs once, e.g. a start-up catch-up: returning is a
+    /// clean exit, and a panic is reported without a restart. It is stopped with the others.
+    pub fn spawn_once<Fut>(&self, name: &'static str, stop_order: u8, fut: Fut) -> TaskHandle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let fut = Mutex::new(Some(fut));
+        self.start(name, stop_order, 0, true, move || {
+            let fut = fut.lock().ok().and_then(|mut f| f.take());
+            async move {
+                if let Some(fut) = fut {
+                    fut.await;
+                }
+                Ok(())
+            }
+        })
+    }
+
+    fn start<F, Fut>(
+        &self,
+        name: &'static str,
+        stop_order: u8,
+        max_restarts: u32,
+        once: bool,
+        make: F,
+    ) -> TaskHandle
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let finished = CancellationToken::new();
+        let entry = Arc::new(Mutex::new(TaskEntry {
+            report: TaskReport {
+                name,
+                status: TaskStatus::Running,
+                restarts: 0,
+                last_error: None,
+            },
+            stop_order,
+            abort: None,
+            runner: None,
+            finished: finished.clone(),
+        }));
+        if let Ok(mut tasks) = self.tasks.lock() {
+            tasks.push(entry.clone());
+        }
+
+        let stopping = self.stopping.clone();
+        let task = entry.clone();
+        let runner = tokio::spawn(async move {
+            loop {
+                let handle = tokio::spawn(make());
+                if let Ok(mut e) = task.lock() {
+                    e.abort = Some(handle.abort_handle());
+                    e.report.status = TaskStatus::Running;
+                }
+                let error = match handle.await {
+                    _ if stopping.load(Ordering::SeqCst) => break,
+                    Ok(Ok(())) if once => break,
+                    Ok(Ok(())) => "exited unexpectedly".to_string(),
+                    Ok(Err(e)) => e.to_string(),
+                    Err(e) if e.is_panic() => format!("panicked: {}", panic_message(e)),
+                    Err(_) => break,
+                };
+
+                let restarts = {
+                    let Ok(mut e) = task.lock() else { break };
+                    e.report.last_error = Some(error.clone());
+                    if e.report.restarts >= max_restarts {
+                        e.report.status = TaskStatus::FailedPermanent;
+                        None
+                    } else {
+                        e.report.restarts += 1;
+                        e.report.status = TaskStatus::Restarting;
+                        Some(e.report.restarts)
+                    }
+                };
+                let Some(restarts) = restarts else {
+                    let message = format!(
+                        "Task '{}' failed permanently after {} restarts: {}",
+                        name, max_restarts, error
+                    );
+                    Logger::error(&message);
+                    alerts::critical(AlertKind::TaskFailed, &message);
+                    break;
+                };
+                let backoff = 2u64.saturating_pow(restarts - 1).min(MAX_BACKOFF_SECS);
+                Logger::warning(&format!(
+                    "Task '{}' stopped ({}); restarting in {}s ({}/{})",
+                    name, error, backoff, restarts, max_restarts
+                ));
+                tokio::time::sleep(Duration::from_secs(backoff)).await;
+                if stopping.load(Ordering::SeqCst) {
+                    break;
+                }
+            }
+            if let Ok(mut e) = task.lock() {
+                if e.report.status != TaskStatus::FailedPermanent {
+                    e.report.status = TaskStatus::Stopped;
+                }
+            }
+            finished.cancel();
+        });
+        if let Ok(mut e) = entry.lock() {
+            e.runner = Some(runner.abort_handle());
+        };
+        TaskHandle { entry, runner }
+    }
+
+    /// Marks the supervisor as stopping so tasks told to wind down (e.g. by
+    /// `TradeMonitorHandle::stop`) aren't restarted when they return. `shutdown` calls it
+    /// too; call it first when tasks are stopped by other means before `shutdown`.
+    pub fn begin_shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    pub fn statuses(&self) -> Vec<TaskReport> {
+        self.tasks
+            .lock()
+            .map(|tasks| {
+                tasks
+                    .iter()
+                    .filter_map(|t| t.lock().ok().map(|e| e.report.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Stops every task, lowest `stop_order` first; see `stop_through`.
+    pub async fn shutdown(&self) {
+        self.stop_through(u8::MAX).await;
+    }
+
+    /// Stops the tasks whose `stop_order` is at most `last_order`, one group at a time.
+    /// `STOP_EXECUTOR` tasks are left to return on their own (the executor drains the trades
+    /// already queued once the monitor has stopped) for up to `EXECUTOR_DRAIN`; every other
+    /// task is cancelled at once. Each group's restart loops are joined (briefly) before
+    /// the next group stops.
+    pub async fn stop_through(&self, last_order: u8) {
+        self.begin_shutdown();
+        let tasks: Vec<_> = self.tasks.lock().map(|t| t.clone()).unwrap_or_default();
+        let mut orders: Vec<u8> = tasks
+            .iter()
+            .filter_map(|t| t.lock().ok().map(|e| e.stop_order))
+            .filter(|order| *order <= last_order)
+            .collect();
+        orders.sort_unstable();
+        orders.dedup();
+        for order in orders {
+            let group: Vec<_> = tasks
+                .iter()
+                .filter(|t| t.lock().map(|e| e.stop_order == order).unwrap_or(false))
+                .collect();
+            // Tasks whose owner already stopped them have no runner left.
+            let runners: Vec<(CancellationToken, AbortHandle)> = group
+                .iter()
+                .filter_map(|t| {
+                    let mut e = t.lock().ok()?;
+                    let runner = e.runner.take()?;
+                    Some((e.finished.clone(), runner))
+                })
+                .collect();
+            if order == STOP_EXECUTOR {
+                let deadline = tokio::time::Instant::now() + EXECUTOR_DRAIN;
+                for (finished, _) in &runners {
+                    if tokio::time::timeout_at(deadline, finished.cancelled())
+                        .await
+                        .is_err()
+                    {
+                        Logger::warning("Executor did not drain in time; cancelling it");
+                        break;
+                    }
+                }
+            }
+            for task in &group {
+                if let Ok(mut e) = task.lock() {
+                    if let Some(abort) = e.abort.take() {
+                        abort.abort();
+                    }
+                    e.report.status = TaskStatus::Stopped;
+                }
+            }
+            // A runner sleeping through a restart backoff only notices the flag when it wakes.
+            for (finished, runner) in runners {
+                if tokio::time::timeout(STOP_GRACE, finished.cancelled())
+                    .await
+                    .is_err()
+                {
+                    runner.abort();
+                }
+            }
+        }
+    }
+}
+
+fn panic_message(e: tokio::task::JoinError) -> String {
+    let panic = e.into_panic();
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    fn status(sup: &Supervisor, name: &str) -> TaskReport {
+        sup.statuses().into_iter().find(|r| r.name == name).unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failing_task_exhausts_its_restart_budget() {
+        let sup = Supervisor::default();
+        let starts = Arc::new(AtomicU32::new(0));
+        let counter = starts.clone();
+        sup.spawn("flaky", STOP_FIRST, 2, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                anyhow::bail!("boom")
+            }
+        });
+
+        tokio::time::sleep(Duration::from_secs(MAX_BACKOFF_SECS * 4)).await;
+        let report = status(&sup, "flaky");
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert_eq!(report.restarts, 2);
+        assert_eq!(report.status, TaskStatus::FailedPermanent);
+        assert_eq!(report.last_error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn task_returning_after_begin_shutdown_is_not_restarted() {
+        let sup = Supervisor::default();
+        let starts = Arc::new(AtomicU32::new(0));
+        let counter = starts.clone();
+        sup.spawn("monitor", STOP_FIRST, 5, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sup.begin_shutdown();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+        assert_eq!(status(&sup, "monitor").status, TaskStatus::Stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn one_shot_task_stops_when_done_and_a_panic_is_not_retried() {
+        let sup = Supervisor::default();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        sup.spawn_once("catch-up", STOP_LAST, async move {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        sup.spawn_once("broken", STOP_LAST, async { panic!("bad row") });
+
+        tokio::time::sleep(Duration::from_secs(MAX_BACKOFF_SECS * 2)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        let done = status(&sup, "catch-up");
+        assert_eq!((done.status, done.last_error), (TaskStatus::Stopped, None));
+        let broken = status(&sup, "broken");
+        assert_eq!(broken.status, TaskStatus::FailedPermanent);
+        assert_eq!(broken.restarts, 0);
+        assert_eq!(broken.last_error.as_deref(), Some("panicked: bad row"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_joins_runners_sleeping_through_backoff() {
+        let sup = Supervisor::default();
+        sup.spawn("helper", STOP_LAST, 5, || async { anyhow::bail!("down") });
+        sup.spawn("executor", STOP_EXECUTOR, 5, || async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(status(&sup, "helper").status, TaskStatus::Restarting);
+        sup.shutdown().await;
+        for report in sup.statuses() {
+            assert_eq!(report.status, TaskStatus::Stopped, "{}", report.name);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn executor_drains_queued_work_before_later_groups_stop() {
+        let sup = Supervisor::default();
+        let (tx, rx) = tokio::sync::mpsc::channel::<u32>(8);
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        let processed = Arc::new(AtomicU32::new(0));
+        let counter = processed.clone();
+        sup.spawn("executor", STOP_EXECUTOR, 5, move || {
+            let (rx, counter) = (rx.clone(), counter.clone());
+            async move {
+                let mut rx = rx.lock().await;
+                while rx.recv().await.is_some() {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(())
+            }
+        });
+        sup.spawn("helper", STOP_LAST, 5, || async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        for n in 0..3 {
+            tx.send(n).await.unwrap();
+        }
+        // The sender goes away when the monitor stops.
+        drop(tx);
+
+        sup.stop_through(STOP_EXECUTOR).await;
+        assert_eq!(processed.load(Ordering::SeqCst), 3);
+        assert_eq!(status(&sup, "executor").status, TaskStatus::Stopped);
+        assert_eq!(status(&sup, "helper").status, TaskStatus::Running);
+
+        sup.shutdown().await;
+        assert_eq!(status(&sup, "helper").status, TaskStatus::Stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn owner_joins_a_task_that_returns_and_cancels_one_that_does_not() {
+        let sup = Supervisor::default();
+        let done = sup.spawn("drains", STOP_EXECUTOR, 5, || async {
+            tokio::time::sleep(Duration::from_secs(3)).await;
+            Ok(())
+        });
+        let stuck = sup.spawn("stuck", STOP_LAST, 5, || async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        sup.begin_shutdown();
+
+        assert!(done.join(Duration::from_secs(10)).await);
+        assert_eq!(status(&sup, "drains").status, TaskStatus::Stopped);
+        let started = tokio::time::Instant::now();
+        assert!(!stuck.join(Duration::from_secs(1)).await);
+        assert!(started.elapsed() < Duration::from_secs(1) + STOP_GRACE);
+        assert_eq!(status(&sup, "stuck").status, TaskStatus::Stopped);
+        // Nothing is left for the supervisor to wait on.
+        let started = tokio::time::Instant::now();
+        sup.shutdown().await;
+        assert!(started.elapsed() < STOP_GRACE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn executor_that_never_drains_is_cancelled() {
+        let sup = Supervisor::default();
+        sup.spawn("executor", STOP_EXECUTOR, 5, || async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+        let started = tokio::time::Instant::now();
+        sup.shutdown().await;
+        assert!(started.elapsed() >= EXECUTOR_DRAIN);
+        assert_eq!(status(&sup, "executor").status, TaskStatus::Stopped);
+    }
+}
@@ -0,0 +1,894 @@
+//! Scripted stand-ins for everything the bot talks to, behind the `testkit` feature, for
+//! end-to-end tests that run the real monitor and executor:
+//!
+//! - `FakeRtds` is an RTDS WebSocket server: it acknowledges subscriptions, forwards the
+//!   trade frames a test pushes, and can drop every connection to force a reconnect.
+//! - `FakePolymarket` is one HTTP server answering as the data API (positions, activity),
+//!   Gamma (market metadata), the CLOB (books, order parameters, API keys, orders) and the
+//!   Polygon RPC (code, USDC balance and allowance, CTF approval), all from fixtures. Orders
+//!   fill in full and are recorded as `Submission`s; the wallet's position and USDC balance
+//!   follow the fills, so a later SELL finds what an earlier BUY bought. Filled orders can
+//!   be looked up by id, and `set_order_failure` makes order posts ambiguous.
+//! - `TestBot` starts the executor and monitor against them and stops them the way `main`
+//!   does.
+//!
+//! The monitor, executor, journal and supervisor keep process-wide state, so each test
+//! binary runs one bot.
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::EnvConfig;
+use crate::executor::{run_trade_executor, ExecutorContext, ExecutorState, TradeExecutorHandle};
+use crate::market_overrides;
+use crate::monitor::{run_trade_monitor, TradeMonitorHandle};
+use crate::supervisor::supervisor;
+use crate::types::RtdsActivity;
+use crate::utils::{
+    create_clob_client, flush_journal, rate_limit, read_journal, JournalEntry, RpcClient,
+};
+
+/// The bot's wallet in `config_vars`.
+pub const PROXY_WALLET: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+/// A throwaway key; it only signs orders for the fake CLOB.
+pub const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+pub const USDC_CONTRACT: &str = "0x2791bca1f2de4661ed88a30c99a7a9449aa84174";
+/// Depth on both sides of every fake book, in tokens: more than any test order takes.
+const BOOK_DEPTH: f64 = 1_000_000.0;
+
+/// A market the fake stack knows. The best ask is `price`, the best bid a cent under it.
+#[derive(Debug, Clone)]
+pub struct FakeMarket {
+    pub condition_id: String,
+    /// The outcome token id (decimal), as RTDS and the CLOB use it.
+    pub asset: String,
+    pub title: String,
+    pub slug: String,
+    pub outcome: String,
+    pub price: f64,
+    /// Gamma's `orderMinSize`, in tokens.
+    pub min_order_size: f64,
+}
+
+impl FakeMarket {
+    /// Market number `n`, with ids derived from it.
+    pub fn new(n: u64, price: f64) -> Self {
+        Self {
+            condition_id: format!("0x{:064x}", n),
+            asset: format!("{}", 10_000_000_000u64 + n),
+            title: format!("Fake market {}", n),
+            slug: format!("fake-market-{}", n),
+            outcome: "Yes".to_string(),
+            price,
+            min_order_size: 1.0,
+        }
+    }
+
+    fn bid(&self) -> f64 {
+        (self.price - 0.01).max(0.01)
+    }
+}
+
+/// A trade `trader` made in `market` at its price, as an RTDS payload. The same fields make
+/// a data API activity record.
+pub fn trade(
+    trader: &str,
+    market: &FakeMarket,
+    side: &str,
+    size: f64,
+    tx_hash: &str,
+    timestamp: i64,
+) -> Value {
+    json!({
+        "proxyWallet": trader,
+        "timestamp": timestamp,
+        "conditionId": market.condition_id,
+        "type": "TRADE",
+        "size": size,
+        "price": market.price,
+        "usdcSize": size * market.price,
+        "asset": market.asset,
+        "side": side,
+        "outcomeIndex": 0,
+        "title": market.title,
+        "slug": market.slug,
+        "eventSlug": market.slug,
+        "outcome": market.outcome,
+        "transactionHash": tx_hash,
+    })
+}
+
+#[derive(Debug, Clone)]
+enum RtdsCommand {
+    Frame(String),
+    Drop,
+    Stall,
+}
+
+/// An in-process RTDS server on a local port.
+pub struct FakeRtds {
+    addr: SocketAddr,
+    commands: broadcast::Sender<RtdsCommand>,
+    subscriptions: Arc<AtomicUsize>,
+}
+
+impl FakeRtds {
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let (commands, _) = broadcast::channel(256);
+        let subscriptions = Arc::new(AtomicUsize::new(0));
+        let (sender, counter) = (commands.clone(), subscriptions.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (commands, counter) = (sender.subscribe(), counter.clone());
+                tokio::spawn(serve_rtds(stream, commands, counter));
+            }
+        });
+        Ok(Self {
+            addr,
+            commands,
+            subscriptions,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Subscriptions received since start, across connections.
+    pub fn subscriptions(&self) -> usize {
+        self.subscriptions.load(Ordering::SeqCst)
+    }
+
+    /// Waits until `count` subscriptions have come in. False on timeout.
+    pub async fn wait_for_subscriptions(&self, count: usize, timeout: Duration) -> bool {
+        wait_until(timeout, || self.subscriptions() >= count).await
+    }
+
+    /// Sends a trade payload to every subscribed connection. Like RTDS, a connection that
+    /// isn't up yet never sees it.
+    pub fn push_trade(&self, payload: Value) {
+        let frame = json!({"topic": "activity", "type": "trades", "payload": payload});
+        let _ = self.commands.send(RtdsCommand::Frame(frame.to_string()));
+    }
+
+    /// Drops every open connection without a close frame.
+    pub fn drop_connections(&self) {
+        let _ = self.commands.send(RtdsCommand::Drop);
+    }
+
+    /// Open connections stop reading and sending, Pongs included, but stay open: a
+    /// half-dead socket that only the bot's staleness check notices.
+    pub fn stall_connections(&self) {
+        let _ = self.commands.send(RtdsCommand::Stall);
+    }
+}
+
+async fn serve_rtds(
+    stream: TcpStream,
+    mut commands: broadcast::Receiver<RtdsCommand>,
+    subscriptions: Arc<AtomicUsize>,
+) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut write, mut read) = ws.split();
+    let mut subscribed = false;
+    loop {
+        tokio::select! {
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) if text.contains("\"subscribe\"") => {
+                    subscribed = true;
+                    subscriptions.fetch_add(1, Ordering::SeqCst);
+                    let ack = json!({"action": "subscribed"}).to_string();
+                    if write.send(Message::Text(ack)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(_)) => {}
+                _ => return,
+            },
+            command = commands.recv() => match command {
+                Ok(RtdsCommand::Frame(frame)) if subscribed => {
+                    if write.send(Message::Text(frame)).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(RtdsCommand::Frame(_)) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Ok(RtdsCommand::Drop) | Err(broadcast::error::RecvError::Closed) => return,
+                Ok(RtdsCommand::Stall) => std::future::pending::<()>().await,
+            },
+        }
+    }
+}
+
+/// How the fake CLOB mishandles order posts while set (`set_order_failure`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderFailure {
+    /// The order fills, then the connection closes before the response: the bot can't tell
+    /// whether it went through.
+    DropResponse,
+    /// The connection closes before the order is taken: it never reaches the book.
+    DropRequest,
+}
+
+/// An order the fake CLOB received, as the bot sized it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    pub asset: String,
+    pub side: String,
+    pub tokens: f64,
+    pub usd: f64,
+    pub order_type: String,
+    /// The fee rate the order was signed with, in basis points.
+    pub fee_rate_bps: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Holding {
+    market: FakeMarket,
+    size: f64,
+    avg_price: f64,
+}
+
+#[derive(Debug, Default)]
+struct Stack {
+    /// The bot's wallet, lowercased: fills move its holdings and balance.
+    wallet: String,
+    usdc_balance: f64,
+    markets: BTreeMap<String, FakeMarket>,
+    /// Holdings per lowercased user, by asset.
+    holdings: HashMap<String, BTreeMap<String, Holding>>,
+    /// Activity records per lowercased user.
+    activity: HashMap<String, Vec<Value>>,
+    submissions: Vec<Submission>,
+    /// Filled orders by id, as `GET /data/order/{id}` returns them.
+    orders: HashMap<String, Value>,
+    requests: Vec<String>,
+    order_latency: Duration,
+    order_failure: Option<OrderFailure>,
+    /// What `GET /fee-rate` reports for every market.
+    fee_rate_bps: u64,
+}
+
+/// The data API, Gamma, the CLOB and the RPC on one local port.
+pub struct FakePolymarket {
+    addr: SocketAddr,
+    stack: Arc<Mutex<Stack>>,
+}
+
+impl FakePolymarket {
+    /// Starts the server for the bot wallet `wallet`, holding `usdc_balance`.
+    pub async fn start(wallet: &str, usdc_balance: f64) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let stack = Arc::new(Mutex::new(Stack {
+            wallet: wallet.to_lowercase(),
+            usdc_balance,
+            ..Stack::default()
+        }));
+        let shared = stack.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                // The head and body go out in two writes; Nagle would hold the body back
+                // for the client's delayed ACK.
+                let _ = stream.set_nodelay(true);
+                tokio::spawn(serve_http(stream, shared.clone()));
+            }
+        });
+        Ok(Self { addr, stack })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn rpc_url(&self) -> String {
+        format!("http://{}/rpc", self.addr)
+    }
+
+    fn stack(&self) -> std::sync::MutexGuard<'_, Stack> {
+        self.stack.lock().expect("fake stack lock")
+    }
+
+    /// Lists `market` on Gamma and the CLOB.
+    pub fn add_market(&self, market: &FakeMarket) {
+        self.stack()
+            .markets
+            .insert(market.asset.clone(), market.clone());
+    }
+
+    /// Sets what `user` holds of `market` (0 closes the position), as the positions
+    /// endpoint reports it.
+    pub fn set_position(&self, user: &str, market: &FakeMarket, size: f64) {
+        let mut stack = self.stack();
+        let holdings = stack.holdings.entry(user.to_lowercase()).or_default();
+        if size <= 0.0 {
+            holdings.remove(&market.asset);
+            return;
+        }
+        holdings.insert(
+            market.asset.clone(),
+            Holding {
+                market: market.clone(),
+                size,
+                avg_price: market.price,
+            },
+        );
+    }
+
+    /// What `user` holds of `asset`, after any fills.
+    pub fn position(&self, user: &str, asset: &str) -> f64 {
+        self.stack()
+            .holdings
+            .get(&user.to_lowercase())
+            .and_then(|h| h.get(asset))
+            .map_or(0.0, |h| h.size)
+    }
+
+    /// Adds a record (see `trade`) to `user`'s activity feed.
+    pub fn add_activity(&self, user: &str, record: Value) {
+        self.stack()
+            .activity
+            .entry(user.to_lowercase())
+            .or_default()
+            .push(record);
+    }
+
+    pub fn usdc_balance(&self) -> f64 {
+        self.stack().usdc_balance
+    }
+
+    /// Holds every order this long before answering.
+    pub fn set_order_latency(&self, latency: Duration) {
+        self.stack().order_latency = latency;
+    }
+
+    /// Sets the fee rate the CLOB reports for every market.
+    pub fn set_fee_rate_bps(&self, fee_rate_bps: u64) {
+        self.stack().fee_rate_bps = fee_rate_bps;
+    }
+
+    /// Fails every order post this way until cleared with `None`.
+    pub fn set_order_failure(&self, failure: Option<OrderFailure>) {
+        self.stack().order_failure = failure;
+    }
+
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.stack().submissions.clone()
+    }
+
+    /// Every request received, as `METHOD /path?query` (RPC calls as `RPC method`).
+    pub fn requests(&self) -> Vec<String> {
+        self.stack().requests.clone()
+    }
+
+    /// Waits until `count` orders have been received. False on timeout.
+    pub async fn wait_for_submissions(&self, count: usize, timeout: Duration) -> bool {
+        wait_until(timeout, || self.stack().submissions.len() >= count).await
+    }
+}
+
+async fn serve_http(stream: TcpStream, stack: Arc<Mutex<Stack>>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return;
+        };
+        let (method, target) = (method.to_string(), target.to_string());
+        let mut length = 0usize;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0u8; length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        let mut failure = None;
+        if method == "POST" && target == "/order" {
+            let (latency, order_failure) = stack
+                .lock()
+                .map(|s| (s.order_latency, s.order_failure))
+                .unwrap_or_default();
+            tokio::time::sleep(latency).await;
+            failure = order_failure;
+        }
+        if failure == Some(OrderFailure::DropRequest) {
+            return;
+        }
+        let (status, response) = match stack.lock() {
+            Ok(mut stack) => route(&mut stack, &method, &target, &body),
+            Err(_) => (500, json!({"error": "fake stack poisoned"})),
+        };
+        if failure == Some(OrderFailure::DropResponse) {
+            return;
+        }
+        let payload = response.to_string();
+        let reason = if status == 200 { "OK" } else { "Error" };
+        let head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            status,
+            reason,
+            payload.len()
+        );
+        let stream = reader.get_mut();
+        if stream.write_all(head.as_bytes()).await.is_err()
+            || stream.write_all(payload.as_bytes()).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn route(stack: &mut Stack, method: &str, target: &str, body: &Value) -> (u16, Value) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let param = |key: &str| query.get(key).map(|v| v.to_lowercase()).unwrap_or_default();
+    if path == "/rpc" {
+        let rpc_method = body.get("method").and_then(Value::as_str).unwrap_or("");
+        stack.requests.push(format!("RPC {}", rpc_method));
+        return (200, rpc(stack, body));
+    }
+    stack.requests.push(format!("{} {}", method, target));
+    match (method, path) {
+        ("GET", "/positions") => (200, positions(stack, &param("user"))),
+        ("GET", "/activity") => {
+            let start: i64 = param("start").parse().unwrap_or(0);
+            let mut records: Vec<Value> = stack
+                .activity
+                .get(&param("user"))
+                .into_iter()
+                .flatten()
+                .filter(|r| r.get("timestamp").and_then(Value::as_i64).unwrap_or(0) >= start)
+                .cloned()
+                .collect();
+            records.sort_by_key(|r| -r.get("timestamp").and_then(Value::as_i64).unwrap_or(0));
+            (200, Value::Array(records))
+        }
+        ("GET", "/markets") => {
+            let wanted = param("condition_ids");
+            let markets: Vec<Value> = stack
+                .markets
+                .values()
+                .filter(|m| wanted.split(',').any(|c| c == m.condition_id))
+                .map(gamma_market)
+                .collect();
+            (200, Value::Array(markets))
+        }
+        ("GET", "/book") => match stack.markets.get(&param("token_id")) {
+            Some(market) => (200, book(market)),
+            None => (404, json!({"error": "No orderbook exists for the requested token id"})),
+        },
+        ("GET", "/last-trade-price") => match stack.markets.get(&param("token_id")) {
+            Some(market) => (200, json!({"price": format!("{:.2}", market.price), "side": "BUY"})),
+            None => (404, json!({"error": "market not found"})),
+        },
+        ("GET", "/tick-size") => (200, json!({"minimum_tick_size": 0.01})),
+        ("GET", "/neg-risk") => (200, json!({"neg_risk": false})),
+        ("GET", "/fee-rate") => (200, json!({"base_fee": stack.fee_rate_bps})),
+        ("POST", "/auth/api-key") | ("GET", "/auth/derive-api-key") => (
+            200,
+            json!({
+                "apiKey": "00000000-0000-4000-8000-000000000000",
+                "secret": "c2VjcmV0LXNlY3JldC1zZWNyZXQtc2VjcmV0LTEyMzQ=",
+                "passphrase": "testkit",
+            }),
+        ),
+        ("POST", "/order") => fill(stack, body),
+        // Like the CLOB, an unknown order id is a `null` body.
+        ("GET", _) if path.starts_with("/data/order/") => {
+            let id = path.trim_start_matches("/data/order/");
+            (200, stack.orders.get(id).cloned().unwrap_or(Value::Null))
+        }
+        _ => (404, json!({"error": "not found"})),
+    }
+}
+
+fn positions(stack: &Stack, user: &str) -> Value {
+    let holdings = stack.holdings.get(user).into_iter().flat_map(|h| h.values());
+    Value::Array(
+        holdings
+            .map(|h| {
+                let m = &h.market;
+                json!({
+                    "proxyWallet": user,
+                    "asset": m.asset,
+                    "conditionId": m.condition_id,
+                    "size": h.size,
+                    "avgPrice": h.avg_price,
+                    "initialValue": h.size * h.avg_price,
+                    "currentValue": h.size * m.price,
+                    "cashPnl": h.size * (m.price - h.avg_price),
+                    "curPrice": m.price,
+                    "redeemable": false,
+                    "mergeable": false,
+                    "title": m.title,
+                    "slug": m.slug,
+                    "eventSlug": m.slug,
+                    "outcome": m.outcome,
+                    "outcomeIndex": 0,
+                    "negativeRisk": false,
+                    "endDate": end_date(),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn end_date() -> String {
+    (chrono::Utc::now() + chrono::Duration::days(30)).to_rfc3339()
+}
+
+fn gamma_market(market: &FakeMarket) -> Value {
+    json!({
+        "conditionId": market.condition_id,
+        "question": market.title,
+        "slug": market.slug,
+        "events": [{"slug": market.slug}],
+        "category": "Testing",
+        "endDate": end_date(),
+        "closed": false,
+        "negRisk": false,
+        "orderPriceMinTickSize": 0.01,
+        "orderMinSize": market.min_order_size,
+        "clobTokenIds": json!([market.asset]).to_string(),
+        "volume": "100000",
+        "liquidity": "50000",
+    })
+}
+
+fn book(market: &FakeMarket) -> Value {
+    let level = |price: f64| json!({"price": format!("{:.2}", price), "size": format!("{}", BOOK_DEPTH)});
+    json!({
+        "market": market.condition_id,
+        "asset_id": market.asset,
+        "timestamp": chrono::Utc::now().timestamp_millis().to_string(),
+        "hash": "",
+        "bids": [level(market.bid())],
+        "asks": [level(market.price)],
+        "min_order_size": format!("{}", market.min_order_size),
+        "neg_risk": false,
+        "tick_size": "0.01",
+        "last_trade_price": format!("{:.2}", market.price),
+    })
+}
+
+/// Fills a posted order in full and moves the wallet's holding and balance.
+fn fill(stack: &mut Stack, body: &Value) -> (u16, Value) {
+    let order = body.get("order").cloned().unwrap_or(Value::Null);
+    let amount = |key: &str| {
+        order
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(0.0)
+            / 1e6
+    };
+    let side = order
+        .get("side")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_uppercase();
+    let (maker, taker) = (amount("makerAmount"), amount("takerAmount"));
+    let (tokens, usd) = if side == "BUY" {
+        (taker, maker)
+    } else {
+        (maker, taker)
+    };
+    let asset = order
+        .get("tokenId")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_string();
+    let Some(market) = stack.markets.get(&asset).cloned() else {
+        return (400, json!({"error": "unknown token id"}));
+    };
+    let wallet = stack.wallet.clone();
+    let holding = stack
+        .holdings
+        .entry(wallet)
+        .or_default()
+        .entry(asset.clone())
+        .or_insert(Holding {
+            market,
+            size: 0.0,
+            avg_price: 0.0,
+        });
+    if side == "BUY" {
+        let cost = holding.size * holding.avg_price + usd;
+        holding.size += tokens;
+        holding.avg_price = cost / holding.size;
+        stack.usdc_balance -= usd;
+    } else {
+        holding.size = (holding.size - tokens).max(0.0);
+        stack.usdc_balance += usd;
+    }
+    let submission = Submission {
+        asset,
+        side,
+        tokens,
+        usd,
+        order_type: body
+            .get("orderType")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string(),
+        fee_rate_bps: order
+            .get("feeRateBps")
+            .and_then(Value::as_str)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0),
+    };
+    let id = order_id(&order).unwrap_or_else(|| format!("0x{:064x}", stack.submissions.len() + 1));
+    stack.orders.insert(id.clone(), open_order(&id, &order, &submission));
+    stack.submissions.push(submission);
+    (
+        200,
+        json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": id,
+            "makingAmount": format!("{}", maker),
+            "takingAmount": format!("{}", taker),
+            "status": "matched",
+            "transactionsHashes": [],
+            "tradeIds": [],
+        }),
+    )
+}
+
+/// The id the CLOB files a posted order under, worked out the way the bot does.
+fn order_id(order: &Value) -> Option<String> {
+    use alloy::primitives::{Address, U256};
+    let text = |key: &str| order.get(key).and_then(Value::as_str);
+    let uint = |key: &str| text(key).and_then(|v| v.parse::<U256>().ok());
+    let address = |key: &str| text(key).and_then(|v| v.parse::<Address>().ok());
+    let mut parsed = polymarket_client_sdk::clob::types::Order::default();
+    parsed.salt = U256::from(order.get("salt").and_then(Value::as_u64)?);
+    parsed.maker = address("maker")?;
+    parsed.signer = address("signer")?;
+    parsed.taker = address("taker")?;
+    parsed.tokenId = uint("tokenId")?;
+    parsed.makerAmount = uint("makerAmount")?;
+    parsed.takerAmount = uint("takerAmount")?;
+    parsed.expiration = uint("expiration")?;
+    parsed.nonce = uint("nonce")?;
+    parsed.feeRateBps = uint("feeRateBps")?;
+    parsed.side = match text("side")? {
+        "BUY" => 0,
+        _ => 1,
+    };
+    parsed.signatureType = order.get("signatureType").and_then(Value::as_u64)? as u8;
+    crate::order_intents::order_id(&parsed, polymarket_client_sdk::POLYGON, false)
+}
+
+/// A filled order as `GET /data/order/{id}` reports it.
+fn open_order(id: &str, order: &Value, submission: &Submission) -> Value {
+    let price = if submission.tokens > 0.0 {
+        submission.usd / submission.tokens
+    } else {
+        0.0
+    };
+    json!({
+        "id": id,
+        "status": "MATCHED",
+        "owner": "00000000-0000-4000-8000-000000000000",
+        "maker_address": order.get("maker").cloned().unwrap_or(Value::Null),
+        "market": format!("0x{:064x}", 0),
+        "asset_id": submission.asset,
+        "side": submission.side,
+        "original_size": format!("{}", submission.tokens),
+        "size_matched": format!("{}", submission.tokens),
+        "price": format!("{}", price),
+        "associate_trades": [],
+        "outcome": "Yes",
+        "created_at": chrono::Utc::now().timestamp(),
+        "expiration": "0",
+        "order_type": submission.order_type,
+    })
+}
+
+/// A 32-byte ABI word.
+fn word(value: u128) -> String {
+    format!("0x{:064x}", value)
+}
+
+fn rpc(stack: &Stack, body: &Value) -> Value {
+    let id = body.get("id").cloned().unwrap_or(json!(1));
+    let call = body.pointer("/params/0");
+    let data = call
+        .and_then(|c| c.get("data").or_else(|| c.get("input")))
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .to_lowercase();
+    let result = match body.get("method").and_then(Value::as_str).unwrap_or("") {
+        "eth_getCode" => json!("0x"),
+        "eth_chainId" => json!("0x89"),
+        "eth_blockNumber" => json!("0x1"),
+        "eth_call" if data.starts_with("0x313ce567") => json!(word(6)),
+        "eth_call" if data.starts_with("0x70a08231") => {
+            json!(word((stack.usdc_balance.max(0.0) * 1e6) as u128))
+        }
+        "eth_call" if data.starts_with("0xdd62ed3e") => json!(format!("0x{}", "f".repeat(64))),
+        // isApprovedForAll: the CTF approvals are in place.
+        "eth_call" if data.starts_with("0xe985e9c5") => json!(word(1)),
+        "eth_call" => json!(word(0)),
+        other => {
+            return json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": format!("{} not supported", other)},
+            })
+        }
+    };
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+async fn wait_until(timeout: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while !done() {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    true
+}
+
+/// Settings pointing the bot at the fakes, tracking `traders`, with state and logs under
+/// `dir`. Scenarios add their own on top before parsing with `EnvConfig::from_vars`.
+pub fn config_vars(
+    polymarket: &FakePolymarket,
+    rtds: &FakeRtds,
+    traders: &[&str],
+    dir: &Path,
+) -> HashMap<String, String> {
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    [
+        ("USER_ADDRESSES", traders.join(",")),
+        ("PROXY_WALLET", PROXY_WALLET.to_string()),
+        ("PRIVATE_KEY", PRIVATE_KEY.to_string()),
+        ("CLOB_HTTP_URL", format!("{}/", polymarket.url())),
+        ("CLOB_WS_URL", "ws://127.0.0.1:9/ws".to_string()),
+        ("RPC_URL", polymarket.rpc_url()),
+        ("USDC_CONTRACT_ADDRESS", USDC_CONTRACT.to_string()),
+        ("DATA_API_URL", polymarket.url()),
+        ("GAMMA_API_URL", polymarket.url()),
+        ("RTDS_URL", rtds.url()),
+        ("STATE_DIR", path("state")),
+        ("TRADE_LOG_PATH", path("trades.jsonl")),
+        ("MALFORMED_LOG_PATH", path("malformed_activity.jsonl")),
+        ("DRY_RUN", "false".to_string()),
+        ("NETWORK_RETRY_LIMIT", "1".to_string()),
+        // One fake answers every API, so a per-host limit would only slow the tests down.
+        ("REQUEST_RATE_LIMIT_PER_SEC", "0".to_string()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect()
+}
+
+/// What the executor copies with (clients, signer and state) against the fakes, without
+/// starting its loop: for harnesses that drive the copy path themselves.
+pub async fn executor_context(config: EnvConfig) -> Result<ExecutorContext> {
+    let http_client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.request_timeout_ms))
+        .build()?;
+    let (clob_client, signer) = create_clob_client(&config)
+        .await
+        .context("CLOB client against the fake stack")?;
+    market_overrides::init(&config);
+    rate_limit::init(config.request_rate_limit_per_sec);
+    let state = ExecutorState::new(&config, RpcClient::from_config(&config)?);
+    Ok(ExecutorContext {
+        config: Arc::new(config),
+        http_client: Arc::new(http_client),
+        clob_client: Arc::new(clob_client),
+        signer: Arc::new(tokio::sync::Mutex::new(signer)),
+        state,
+    })
+}
+
+/// The executor and monitor running against the fakes.
+pub struct TestBot {
+    pub config: EnvConfig,
+    monitor: TradeMonitorHandle,
+    executor: TradeExecutorHandle,
+}
+
+impl TestBot {
+    /// Starts the bot the way `main` does, minus the startup checks.
+    pub async fn start(config: EnvConfig) -> Result<Self> {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.request_timeout_ms))
+            .build()?;
+        let (clob_client, signer) = create_clob_client(&config)
+            .await
+            .context("CLOB client against the fake stack")?;
+        market_overrides::init(&config);
+        let (tx, rx) = tokio::sync::mpsc::channel::<(RtdsActivity, String)>(100);
+        let executor = run_trade_executor(
+            Arc::new(config.clone()),
+            Arc::new(http_client.clone()),
+            RpcClient::from_config(&config)?,
+            Arc::new(clob_client),
+            Arc::new(tokio::sync::Mutex::new(signer)),
+            rx,
+            CancellationToken::new(),
+        )
+        .await?;
+        let monitor = run_trade_monitor(
+        &config,
+        &http_client,
+        executor.balance_cache(),
+        tx,
+        CancellationToken::new(),
+    ).await?;
+        Ok(Self {
+            config,
+            monitor,
+            executor,
+        })
+    }
+
+    /// The journal rows written so far.
+    pub async fn journal(&self) -> Vec<JournalEntry> {
+        flush_journal().await;
+        read_journal(Path::new(&self.config.trade_log_path)).unwrap_or_default()
+    }
+
+    /// Waits until the journal has `rows` rows and returns them (fewer on timeout).
+    pub async fn wait_for_journal(&self, rows: usize, timeout: Duration) -> Vec<JournalEntry> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let entries = self.journal().await;
+            if entries.len() >= rows || tokio::time::Instant::now() >= deadline {
+                return entries;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Stops the bot like `main` on Ctrl-C: the monitor first, then the executor once it
+    /// has drained its queue and its helpers, then the journal and the rest.
+    pub async fn shutdown(self) -> Vec<JournalEntry> {
+        self.monitor.stop().await;
+        self.executor.stop().await;
+        let _ = tokio::time::timeout(Duration::from_secs(5), flush_journal()).await;
+        supervisor().shutdown().await;
+        read_journal(Path::new(&self.config.trade_log_path)).unwrap_or_default()
+    }
+}
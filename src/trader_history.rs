@@ -0,0 +1,255 @@
+//! Startup check that every tracked address has some Polymarket history. A typo'd address
+//! passes hex validation, but the bot would monitor the dead wallet forever without a single
+//! copy. Addresses found with history are remembered and not looked up again.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config::EnvConfig;
+use crate::utils::{load_json, save_json, state_path, DataApiClient, Logger};
+
+const HISTORY_FILE: &str = "trader_history.json";
+/// Differing hex digits up to which a known address is suggested as the intended one.
+const DID_YOU_MEAN_MAX_DIGITS: usize = 3;
+
+/// Tracked addresses the last check found without any history, for the traders panel.
+static NO_HISTORY: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The lookups the check needs, so tests can stand in for the data API.
+pub trait TraderData: Sync {
+    fn has_activity(&self, address: &str) -> impl Future<Output = Result<bool>> + Send;
+    fn has_positions(&self, address: &str) -> impl Future<Output = Result<bool>> + Send;
+}
+
+impl TraderData for DataApiClient {
+    async fn has_activity(&self, address: &str) -> Result<bool> {
+        Ok(DataApiClient::has_activity(self, address).await?)
+    }
+
+    async fn has_positions(&self, address: &str) -> Result<bool> {
+        Ok(DataApiClient::has_positions(self, address).await?)
+    }
+}
+
+/// Addresses confirmed to have history, with when they were checked. Persisted so restarts
+/// skip them and later typos can be compared against them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HistoryBook {
+    confirmed: BTreeMap<String, i64>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl HistoryBook {
+    pub fn load(state_dir: &str) -> Self {
+        let path = state_path(state_dir, HISTORY_FILE);
+        let mut book: HistoryBook = load_json(&path).unwrap_or_default();
+        book.path = Some(path);
+        book
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn is_confirmed(&self, address: &str) -> bool {
+        self.confirmed.contains_key(&address.to_lowercase())
+    }
+
+    fn confirm(&mut self, address: &str, now: i64) {
+        self.confirmed.insert(address.to_lowercase(), now);
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist trader history checks: {}", e));
+            }
+        }
+    }
+
+    /// A confirmed address that differs from `address` in only a few hex digits.
+    pub fn did_you_mean(&self, address: &str) -> Option<&str> {
+        let address = address.to_lowercase();
+        self.confirmed
+            .keys()
+            .filter(|known| known.len() == address.len() && **known != address)
+            .map(|known| {
+                let diff = known.chars().zip(address.chars()).filter(|(a, b)| a != b).count();
+                (diff, known)
+            })
+            .filter(|(diff, _)| *diff <= DID_YOU_MEAN_MAX_DIGITS)
+            .min_by_key(|(diff, _)| *diff)
+            .map(|(_, known)| known.as_str())
+    }
+}
+
+/// An address the check found without any activity or positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmptyTrader {
+    pub address: String,
+    pub did_you_mean: Option<String>,
+}
+
+/// Looks up every address the book hasn't confirmed yet. Failed lookups count as unknown,
+/// not empty, so an API outage doesn't flag every trader.
+pub async fn check_history<D: TraderData>(
+    data: &D,
+    book: &mut HistoryBook,
+    addresses: &[String],
+    now: i64,
+) -> Vec<EmptyTrader> {
+    let mut empty = Vec::new();
+    for address in addresses {
+        if book.is_confirmed(address) {
+            continue;
+        }
+        let found = match data.has_activity(address).await {
+            Ok(true) => Ok(true),
+            Ok(false) => data.has_positions(address).await,
+            Err(e) => Err(e),
+        };
+        match found {
+            Ok(true) => book.confirm(address, now),
+            Ok(false) => empty.push(EmptyTrader {
+                address: address.to_lowercase(),
+                did_you_mean: book.did_you_mean(address).map(str::to_string),
+            }),
+            Err(e) => Logger::warning(&format!(
+                "Could not check {} for Polymarket history: {}",
+                Logger::format_address(address),
+                e
+            )),
+        }
+    }
+    empty
+}
+
+/// Whether the last check found `address` without history.
+pub fn has_no_history(address: &str) -> bool {
+    NO_HISTORY
+        .lock()
+        .map(|set| set.contains(&address.to_lowercase()))
+        .unwrap_or(false)
+}
+
+/// Checks the tracked addresses at startup and warns about any without history; with
+/// `REQUIRE_TRADER_HISTORY` the bot refuses to start instead.
+pub async fn check_traders(config: &EnvConfig, http_client: &reqwest::Client) -> Result<()> {
+    let mut book = HistoryBook::load(&config.state_dir);
+    let data = DataApiClient::from_config(config, http_client);
+    let now = chrono::Utc::now().timestamp();
+    let empty = check_history(&data, &mut book, &config.user_addresses, now).await;
+    if let Ok(mut set) = NO_HISTORY.lock() {
+        *set = empty.iter().map(|t| t.address.clone()).collect();
+    }
+    for trader in &empty {
+        let hint = trader
+            .did_you_mean
+            .as_deref()
+            .map(|known| format!(" Did you mean {}?", known))
+            .unwrap_or_default();
+        Logger::error(&format!(
+            "{} has no Polymarket activity or positions; possible typo in USER_ADDRESSES.{}",
+            trader.address, hint
+        ));
+    }
+    if config.require_trader_history && !empty.is_empty() {
+        anyhow::bail!(
+            "{} tracked address(es) have no Polymarket history (REQUIRE_TRADER_HISTORY=true)",
+            empty.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const ACTIVE: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+    const HOLDER: &str = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+    /// `ACTIVE` with two digits swapped.
+    const TYPO: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5cb6";
+
+    /// Data API stand-in: the activity and position sets, counting lookups.
+    #[derive(Default)]
+    struct MockApi {
+        activity: HashSet<&'static str>,
+        positions: HashSet<&'static str>,
+        down: bool,
+        lookups: AtomicU32,
+    }
+
+    impl TraderData for MockApi {
+        async fn has_activity(&self, address: &str) -> Result<bool> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            if self.down {
+                anyhow::bail!("data API unavailable");
+            }
+            Ok(self.activity.contains(address))
+        }
+
+        async fn has_positions(&self, address: &str) -> Result<bool> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            Ok(self.positions.contains(address))
+        }
+    }
+
+    fn addresses(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn empty_history_is_flagged_with_a_suggestion() {
+        let api = MockApi {
+            activity: [ACTIVE].into(),
+            positions: [HOLDER].into(),
+            ..Default::default()
+        };
+        let mut book = HistoryBook::in_memory();
+        let empty = check_history(&api, &mut book, &addresses(&[ACTIVE, HOLDER]), 1_000).await;
+        assert!(empty.is_empty());
+        assert!(book.is_confirmed(HOLDER));
+
+        let empty = check_history(&api, &mut book, &addresses(&[TYPO]), 1_000).await;
+        assert_eq!(
+            empty,
+            vec![EmptyTrader {
+                address: TYPO.to_string(),
+                did_you_mean: Some(ACTIVE.to_string()),
+            }]
+        );
+        assert_eq!(book.did_you_mean(HOLDER), None);
+    }
+
+    #[tokio::test]
+    async fn confirmed_addresses_are_not_looked_up_again() {
+        let api = MockApi {
+            activity: [ACTIVE].into(),
+            ..Default::default()
+        };
+        let mut book = HistoryBook::in_memory();
+        check_history(&api, &mut book, &addresses(&[ACTIVE, TYPO]), 1_000).await;
+        // ACTIVE: one lookup; TYPO: activity and positions.
+        assert_eq!(api.lookups.load(Ordering::SeqCst), 3);
+        let empty = check_history(&api, &mut book, &addresses(&[ACTIVE, TYPO]), 2_000).await;
+        assert_eq!(empty.len(), 1);
+        assert_eq!(api.lookups.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_are_not_reported_as_empty() {
+        let api = MockApi {
+            down: true,
+            ..Default::default()
+        };
+        let mut book = HistoryBook::in_memory();
+        let empty = check_history(&api, &mut book, &addresses(&[ACTIVE, TYPO]), 1_000).await;
+        assert!(empty.is_empty());
+        assert!(!book.is_confirmed(ACTIVE));
+    }
+}
@@ -0,0 +1,165 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+use crate::status;
+use crate::types::UserPosition;
+
+/// Each tracked wallet's total position value, kept fresh by the portfolio refresh task and by
+/// the positions fetches the executor already makes for a signal.
+static VALUES: RwLock<Option<HashMap<String, CachedValue>>> = RwLock::new(None);
+static READS: Mutex<ReadStats> = Mutex::new(ReadStats {
+    reads: 0,
+    stale: 0,
+    missing: 0,
+    last_age_secs: None,
+    max_age_secs: 0,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedValue {
+    pub value_usd: f64,
+    pub updated_at: i64,
+}
+
+/// A cache lookup. Consumers decide what a stale or missing value means for them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortfolioRead {
+    Fresh(f64),
+    Stale { value_usd: f64, age_secs: i64 },
+    Missing,
+}
+
+/// How old cached values were when they were read.
+#[derive(Debug, Clone, Copy, Default)]
+struct ReadStats {
+    reads: u64,
+    stale: u64,
+    missing: u64,
+    last_age_secs: Option<i64>,
+    max_age_secs: i64,
+}
+
+pub fn positions_value(positions: &[UserPosition]) -> f64 {
+    positions
+        .iter()
+        .map(|p| p.current_value.unwrap_or(0.0))
+        .sum()
+}
+
+/// Stores `value_usd` for `address` unless a newer value is already cached.
+pub fn record(address: &str, value_usd: f64, now: i64) {
+    let Ok(mut guard) = VALUES.write() else {
+        return;
+    };
+    let values = guard.get_or_insert_with(HashMap::new);
+    let entry = values.entry(address.to_lowercase()).or_insert(CachedValue {
+        value_usd,
+        updated_at: now,
+    });
+    if now >= entry.updated_at {
+        *entry = CachedValue {
+            value_usd,
+            updated_at: now,
+        };
+    }
+}
+
+/// Caches the value of a positions list just fetched for `address` and returns it.
+pub fn record_positions(address: &str, positions: &[UserPosition], now: i64) -> f64 {
+    let value = positions_value(positions);
+    record(address, value, now);
+    value
+}
+
+/// The cached value for `address`, fresh when at most `max_age_secs` old.
+pub fn read(address: &str, max_age_secs: u64, now: i64) -> PortfolioRead {
+    let cached = VALUES
+        .read()
+        .ok()
+        .and_then(|g| g.as_ref()?.get(&address.to_lowercase()).copied());
+    let result = match cached {
+        None => PortfolioRead::Missing,
+        Some(c) if now - c.updated_at > max_age_secs as i64 => PortfolioRead::Stale {
+            value_usd: c.value_usd,
+            age_secs: now - c.updated_at,
+        },
+        Some(c) => PortfolioRead::Fresh(c.value_usd),
+    };
+    if let Ok(mut stats) = READS.lock() {
+        stats.reads += 1;
+        match result {
+            PortfolioRead::Missing => stats.missing += 1,
+            PortfolioRead::Stale { .. } => stats.stale += 1,
+            PortfolioRead::Fresh(_) => {}
+        }
+        if let Some(c) = cached {
+            let age = (now - c.updated_at).max(0);
+            stats.last_age_secs = Some(age);
+            stats.max_age_secs = stats.max_age_secs.max(age);
+        }
+    }
+    result
+}
+
+/// Adds the cache to `/status`: each wallet's value and age, and the age of values at read
+/// time.
+pub fn register_status() {
+    status::register("trader_portfolio", || {
+        let now = chrono::Utc::now().timestamp();
+        let wallets: serde_json::Map<String, serde_json::Value> = VALUES
+            .read()
+            .ok()
+            .and_then(|g| g.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(address, c)| {
+                (
+                    address,
+                    json!({ "value_usd": c.value_usd, "age_secs": now - c.updated_at }),
+                )
+            })
+            .collect();
+        let reads = READS.lock().map(|s| *s).unwrap_or_default();
+        json!({
+            "wallets": wallets,
+            "reads": reads.reads,
+            "stale_reads": reads.stale,
+            "missing_reads": reads.missing,
+            "last_read_age_secs": reads.last_age_secs,
+            "max_read_age_secs": reads.max_age_secs,
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(value: f64) -> UserPosition {
+        serde_json::from_value(serde_json::json!({ "currentValue": value })).unwrap()
+    }
+
+    #[test]
+    fn reads_turn_stale_past_the_max_age() {
+        let address = "0xPortfolioStale";
+        record(address, 250.0, 1_000);
+        assert_eq!(read(address, 60, 1_060), PortfolioRead::Fresh(250.0));
+        assert_eq!(
+            read(address, 60, 1_061),
+            PortfolioRead::Stale {
+                value_usd: 250.0,
+                age_secs: 61
+            }
+        );
+        assert_eq!(read("0xportfolionever", 60, 1_000), PortfolioRead::Missing);
+    }
+
+    #[test]
+    fn an_older_value_does_not_replace_a_newer_one() {
+        let address = "0xportfolioorder";
+        record_positions(address, &[position(10.0), position(5.5)], 2_000);
+        record(address, 99.0, 1_500);
+        assert_eq!(read(address, 60, 2_000), PortfolioRead::Fresh(15.5));
+    }
+}
@@ -0,0 +1,221 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::alerts::{self, AlertKind};
+use crate::skip_reason::{Skip, SkipReason};
+use crate::utils::{record_transition, Logger, StateTransition};
+
+/// What the bot may trade right now. Every new order, copy or housekeeping, asks `check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TradingState {
+    Active,
+    /// No new exposure; SELLs are still copied so positions can be closed.
+    BuysPaused(String),
+    FullyPaused(String),
+    /// Shutting down: no new orders, loops finish their current iteration.
+    Draining,
+}
+
+impl fmt::Display for TradingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradingState::Active => write!(f, "active"),
+            TradingState::BuysPaused(reason) => write!(f, "BUYs paused ({})", reason),
+            TradingState::FullyPaused(reason) => write!(f, "fully paused ({})", reason),
+            TradingState::Draining => write!(f, "draining"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseLevel {
+    Buys,
+    All,
+}
+
+impl PauseLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PauseLevel::Buys => "buys",
+            PauseLevel::All => "all",
+        }
+    }
+}
+
+/// Pauses are held per source, so one source resuming never lifts another's pause; the
+/// strongest hold decides the state.
+#[derive(Default)]
+struct Machine {
+    holds: BTreeMap<&'static str, (PauseLevel, String)>,
+    draining: bool,
+}
+
+impl Machine {
+    fn state(&self) -> TradingState {
+        if self.draining {
+            return TradingState::Draining;
+        }
+        // `max_by_key` keeps the last maximum, so reversing lets the first source win ties.
+        let strongest = self.holds.values().rev().max_by_key(|(level, _)| *level);
+        match strongest {
+            None => TradingState::Active,
+            Some((PauseLevel::Buys, reason)) => TradingState::BuysPaused(reason.clone()),
+            Some((PauseLevel::All, reason)) => TradingState::FullyPaused(reason.clone()),
+        }
+    }
+
+    fn status(&self) -> Value {
+        let holds: Vec<Value> = self
+            .holds
+            .iter()
+            .map(|(source, (level, reason))| {
+                json!({ "source": source, "level": level.as_str(), "reason": reason })
+            })
+            .collect();
+        json!({ "state": self.state().to_string(), "holds": holds })
+    }
+}
+
+static MACHINE: Mutex<Machine> = Mutex::new(Machine {
+    holds: BTreeMap::new(),
+    draining: false,
+});
+
+/// Applies `change` and, if the effective state moved, logs and journals the transition.
+fn transition(change: impl FnOnce(&mut Machine)) {
+    let Ok(mut machine) = MACHINE.lock() else {
+        return;
+    };
+    let before = machine.state();
+    change(&mut machine);
+    let after = machine.state();
+    drop(machine);
+    if before == after {
+        return;
+    }
+    record_transition(&StateTransition {
+        timestamp: chrono::Utc::now().timestamp(),
+        from: before.to_string(),
+        to: after.to_string(),
+    });
+    let line = format!("Trading state: {} -> {}", before, after);
+    match after {
+        TradingState::Active => Logger::success(&line),
+        TradingState::Draining => Logger::info(&line),
+        _ => {
+            Logger::warning(&line);
+            alerts::critical(AlertKind::TradingPaused, &line);
+        }
+    }
+}
+
+/// Holds a pause for `source` (e.g. `"foreign_activity"`), replacing its previous hold.
+pub fn pause(source: &'static str, level: PauseLevel, reason: &str) {
+    transition(|m| {
+        m.holds.insert(source, (level, reason.to_string()));
+    });
+}
+
+/// Releases `source`'s pause; pauses held by other sources stay.
+pub fn resume(source: &'static str) {
+    transition(|m| {
+        m.holds.remove(source);
+    });
+}
+
+pub fn set_draining(draining: bool) {
+    transition(|m| m.draining = draining);
+}
+
+pub fn current() -> TradingState {
+    MACHINE
+        .lock()
+        .map(|m| m.state())
+        .unwrap_or(TradingState::Active)
+}
+
+/// Pauses currently held, by source.
+pub fn holds() -> Vec<(&'static str, PauseLevel, String)> {
+    MACHINE
+        .lock()
+        .map(|m| {
+            m.holds
+                .iter()
+                .map(|(source, (level, reason))| (*source, *level, reason.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The state and the pauses behind it, as `/status` shows them.
+pub fn status_json() -> Value {
+    MACHINE.lock().map(|m| m.status()).unwrap_or(Value::Null)
+}
+
+pub fn is_draining() -> bool {
+    current() == TradingState::Draining
+}
+
+/// `Err` when a new order on `side` is not allowed in the current state.
+pub fn check(side: &str) -> Result<(), Skip> {
+    match current() {
+        TradingState::Active => Ok(()),
+        TradingState::BuysPaused(_) if side != "BUY" => Ok(()),
+        TradingState::BuysPaused(reason) => Err(Skip::new(SkipReason::paused("buys"), reason)),
+        TradingState::FullyPaused(reason) => Err(Skip::new(SkipReason::paused("all"), reason)),
+        TradingState::Draining => Err(SkipReason::paused("draining").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_source_resuming_keeps_another_pause() {
+        let mut m = Machine::default();
+        m.holds.insert("a", (PauseLevel::Buys, "low balance".to_string()));
+        m.holds.insert("b", (PauseLevel::Buys, "foreign trade".to_string()));
+        m.holds.remove("a");
+        assert_eq!(m.state(), TradingState::BuysPaused("foreign trade".to_string()));
+        m.holds.remove("b");
+        assert_eq!(m.state(), TradingState::Active);
+    }
+
+    #[test]
+    fn strongest_hold_wins_and_draining_overrides() {
+        let mut m = Machine::default();
+        m.holds.insert("a", (PauseLevel::Buys, "x".to_string()));
+        m.holds.insert("b", (PauseLevel::All, "y".to_string()));
+        assert_eq!(m.state(), TradingState::FullyPaused("y".to_string()));
+        m.draining = true;
+        assert_eq!(m.state(), TradingState::Draining);
+    }
+
+    #[test]
+    fn first_source_wins_ties() {
+        let mut m = Machine::default();
+        m.holds.insert("a", (PauseLevel::Buys, "first".to_string()));
+        m.holds.insert("b", (PauseLevel::Buys, "second".to_string()));
+        assert_eq!(m.state(), TradingState::BuysPaused("first".to_string()));
+    }
+
+    #[test]
+    fn status_shows_the_state_and_its_holds() {
+        let mut m = Machine::default();
+        m.holds.insert("status_api", (PauseLevel::All, "by hand".to_string()));
+        m.holds.insert("watchdog", (PauseLevel::Buys, "foreign trade".to_string()));
+        assert_eq!(
+            m.status(),
+            json!({
+                "state": "fully paused (by hand)",
+                "holds": [
+                    { "source": "status_api", "level": "all", "reason": "by hand" },
+                    { "source": "watchdog", "level": "buys", "reason": "foreign trade" },
+                ],
+            })
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Trial mode for newly added traders (`TRIAL_DAYS` / `TRIAL_COPIES`): their BUY copies are
+//! scaled down and capped until the trial ends, then the trial's realized PnL decides whether
+//! they graduate to normal sizing or stay restricted until I decide, through the status API
+//! (`POST /trial/<trader>/extend|graduate|drop`).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::attribution::closed_lots;
+use crate::config::TrialConfig;
+use crate::status;
+use crate::utils::{load_json, save_json, JournalEntry, Logger};
+
+const TRIAL_FILE: &str = "trader_trials.json";
+
+pub type SharedTrials = Arc<Mutex<TrialBook>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrialStatus {
+    /// Copied with trial restrictions until the first of its bounds is reached.
+    Active,
+    /// Copied normally.
+    Graduated,
+    /// The trial lost money: restrictions stay until the trader is graduated or dropped.
+    Held,
+    /// No new positions are copied; exits still are.
+    Dropped,
+}
+
+impl TrialStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrialStatus::Active => "active",
+            TrialStatus::Graduated => "graduated",
+            TrialStatus::Held => "held",
+            TrialStatus::Dropped => "dropped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraderTrial {
+    pub status: TrialStatus,
+    pub started_at: i64,
+    /// BUY copies placed during the trial.
+    pub copies: u32,
+    /// Bounds fixed when the trial starts (or is extended); `None` when that bound is off.
+    pub ends_at: Option<i64>,
+    pub max_copies: Option<u32>,
+    /// Realized PnL of the lots the trial opened and closed, as of the review.
+    #[serde(default)]
+    pub pnl_usd: Option<f64>,
+}
+
+impl TraderTrial {
+    /// Trial sizing and cap apply.
+    pub fn is_restricted(&self) -> bool {
+        matches!(self.status, TrialStatus::Active | TrialStatus::Held)
+    }
+
+    /// An active trial that reached its day or copy bound.
+    pub fn is_due(&self, now: i64) -> bool {
+        self.status == TrialStatus::Active
+            && (self.ends_at.is_some_and(|end| now >= end)
+                || self.max_copies.is_some_and(|max| self.copies >= max))
+    }
+
+    /// Short status for the traders panel.
+    pub fn label(&self, now: i64) -> String {
+        match self.status {
+            TrialStatus::Active => {
+                let mut parts = vec!["trial".to_string()];
+                if let Some(max) = self.max_copies {
+                    parts.push(format!("{}/{} copies", self.copies, max));
+                }
+                if let Some(end) = self.ends_at {
+                    parts.push(format!("{:.0}d left", (end - now).max(0) as f64 / 86_400.0));
+                }
+                parts.join(" ")
+            }
+            TrialStatus::Held => format!("trial held (PnL ${:.2})", self.pnl_usd.unwrap_or(0.0)),
+            TrialStatus::Dropped => "dropped".to_string(),
+            TrialStatus::Graduated => String::new(),
+        }
+    }
+}
+
+/// A decision on a trial, taken through the status API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrialCommand {
+    Extend { days: u64, copies: u32 },
+    Graduate,
+    Drop,
+}
+
+impl TrialCommand {
+    /// Reads `<trader>/extend[/<days>/<copies>]`, `<trader>/graduate` or `<trader>/drop`; an
+    /// extension without bounds runs the configured trial again.
+    pub fn parse(path: &str, config: &TrialConfig) -> Result<(String, Self), String> {
+        let usage = "expected /trial/<trader>/extend[/<days>/<copies>], /graduate or /drop";
+        let parts: Vec<&str> = path.split('/').collect();
+        let command = match parts[1..] {
+            ["extend"] => TrialCommand::Extend {
+                days: config.days,
+                copies: config.copies,
+            },
+            ["extend", days, copies] => TrialCommand::Extend {
+                days: days.parse().map_err(|_| format!("invalid days {}", days))?,
+                copies: copies.parse().map_err(|_| format!("invalid copies {}", copies))?,
+            },
+            ["graduate"] => TrialCommand::Graduate,
+            ["drop"] => TrialCommand::Drop,
+            _ => return Err(usage.to_string()),
+        };
+        Ok((parts[0].trim().to_lowercase(), command))
+    }
+}
+
+/// Trial state per logical trader, persisted across restarts. Traders the bot has seen trade
+/// before trials existed are recorded as graduated.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrialBook {
+    traders: HashMap<String, TraderTrial>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl TrialBook {
+    pub fn load(state_dir: &str) -> Self {
+        let path = crate::utils::state_path(state_dir, TRIAL_FILE);
+        let mut book: TrialBook = load_json(&path).unwrap_or_default();
+        book.path = Some(path);
+        book
+    }
+
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, trader: &str) -> Option<&TraderTrial> {
+        self.traders.get(trader)
+    }
+
+    pub fn traders(&self) -> impl Iterator<Item = (&String, &TraderTrial)> {
+        self.traders.iter()
+    }
+
+    /// Records a trader the book hasn't seen: on trial when new, graduated when `established`
+    /// (already traded before trials were turned on). Returns true when a trial started.
+    pub fn enroll(
+        &mut self,
+        trader: &str,
+        config: &TrialConfig,
+        now: i64,
+        established: bool,
+    ) -> bool {
+        if self.traders.contains_key(trader) {
+            return false;
+        }
+        let status = if established {
+            TrialStatus::Graduated
+        } else {
+            TrialStatus::Active
+        };
+        self.traders.insert(
+            trader.to_string(),
+            TraderTrial {
+                status,
+                started_at: now,
+                copies: 0,
+                ends_at: (config.days > 0).then(|| now + config.days as i64 * 86_400),
+                max_copies: (config.copies > 0).then_some(config.copies),
+                pnl_usd: None,
+            },
+        );
+        self.persist();
+        !established
+    }
+
+    /// Counts a BUY copy placed under trial restrictions.
+    pub fn record_copy(&mut self, trader: &str) {
+        let Some(trial) = self.traders.get_mut(trader) else {
+            return;
+        };
+        if trial.status == TrialStatus::Active {
+            trial.copies += 1;
+            self.persist();
+        }
+    }
+
+    /// Ends a due trial: graduated when `pnl_usd` isn't negative, held otherwise. Returns the
+    /// new status, or `None` when the trial isn't due.
+    pub fn review(&mut self, trader: &str, pnl_usd: f64, now: i64) -> Option<TrialStatus> {
+        let trial = self.traders.get_mut(trader)?;
+        if !trial.is_due(now) {
+            return None;
+        }
+        trial.pnl_usd = Some(pnl_usd);
+        trial.status = if pnl_usd >= 0.0 {
+            TrialStatus::Graduated
+        } else {
+            TrialStatus::Held
+        };
+        let status = trial.status;
+        self.persist();
+        Some(status)
+    }
+
+    /// Runs the trial (again) for `days` more days and `copies` more copies; bounds that are
+    /// off stay off. Works on active and held trials.
+    pub fn extend(&mut self, trader: &str, days: u64, copies: u32, now: i64) -> Result<()> {
+        let trial = self.restricted_mut(trader)?;
+        trial.status = TrialStatus::Active;
+        trial.ends_at = trial.ends_at.map(|end| end.max(now) + days as i64 * 86_400);
+        trial.max_copies = trial.max_copies.map(|max| max.max(trial.copies) + copies);
+        self.persist();
+        Ok(())
+    }
+
+    /// Lifts the restrictions now, whatever the trial has shown so far.
+    pub fn graduate(&mut self, trader: &str) -> Result<()> {
+        self.restricted_mut(trader)?.status = TrialStatus::Graduated;
+        self.persist();
+        Ok(())
+    }
+
+    /// Stops copying new positions from the trader.
+    pub fn drop_trader(&mut self, trader: &str) -> Result<()> {
+        let Some(trial) = self.traders.get_mut(trader) else {
+            anyhow::bail!("No trial recorded for {}", trader);
+        };
+        trial.status = TrialStatus::Dropped;
+        self.persist();
+        Ok(())
+    }
+
+    pub fn apply(&mut self, trader: &str, command: TrialCommand, now: i64) -> Result<()> {
+        match command {
+            TrialCommand::Extend { days, copies } => self.extend(trader, days, copies, now),
+            TrialCommand::Graduate => self.graduate(trader),
+            TrialCommand::Drop => self.drop_trader(trader),
+        }
+    }
+
+    fn restricted_mut(&mut self, trader: &str) -> Result<&mut TraderTrial> {
+        match self.traders.get_mut(trader) {
+            Some(trial) if trial.is_restricted() => Ok(trial),
+            Some(trial) => anyhow::bail!("{} is {}, not on trial", trader, trial.status.as_str()),
+            None => anyhow::bail!("No trial recorded for {}", trader),
+        }
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = save_json(path, self) {
+                Logger::warning(&format!("Failed to persist trader trials: {}", e));
+            }
+        }
+    }
+}
+
+/// Accepts trial decisions on the status API; each is saved with the book. Answers with the
+/// trader's trial as it now stands.
+pub fn register(trials: SharedTrials, config: TrialConfig) {
+    status::register_async_action("trial", move |rest| {
+        let trials = trials.clone();
+        let parsed = TrialCommand::parse(&rest, &config);
+        async move {
+            let (trader, command) = parsed?;
+            let mut book = trials.lock().await;
+            book.apply(&trader, command, chrono::Utc::now().timestamp())
+                .map_err(|e| e.to_string())?;
+            Logger::info(&format!(
+                "Trial of {} changed through the status API: {:?}",
+                Logger::format_address(&trader),
+                command
+            ));
+            Ok(serde_json::json!({ "trader": trader, "trial": book.get(&trader) }))
+        }
+    });
+}
+
+/// Realized PnL of `trader`'s lots opened since `since` and already sold (see
+/// `attribution::closed_lots`). Positions still open or redeemed don't count.
+pub fn trial_pnl(entries: &[JournalEntry], trader: &str, since: i64) -> f64 {
+    let rows: Vec<JournalEntry> = entries
+        .iter()
+        .filter(|e| e.trader == trader && e.timestamp >= since)
+        .cloned()
+        .collect();
+    closed_lots(&rows).iter().map(|lot| lot.pnl()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{JournalStatus, JOURNAL_SCHEMA_VERSION};
+
+    const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+    const DAY: i64 = 86_400;
+    const T0: i64 = 1_700_000_000;
+
+    fn config(days: u64, copies: u32) -> TrialConfig {
+        TrialConfig {
+            days,
+            copies,
+            size_multiplier: 0.5,
+            max_order_size_usd: 5.0,
+        }
+    }
+
+    fn on_trial(days: u64, copies: u32) -> TrialBook {
+        let mut book = TrialBook::in_memory();
+        assert!(book.enroll(TRADER, &config(days, copies), T0, false));
+        book
+    }
+
+    fn status(book: &TrialBook) -> TrialStatus {
+        book.get(TRADER).unwrap().status
+    }
+
+    #[test]
+    fn established_traders_are_grandfathered() {
+        let mut book = TrialBook::in_memory();
+        assert!(!book.enroll(TRADER, &config(7, 10), T0, true));
+        assert_eq!(status(&book), TrialStatus::Graduated);
+        assert!(!book.get(TRADER).unwrap().is_restricted());
+        // Enrolling again never restarts a trial.
+        assert!(!book.enroll(TRADER, &config(7, 10), T0 + DAY, false));
+        assert_eq!(status(&book), TrialStatus::Graduated);
+    }
+
+    #[test]
+    fn copy_bound_ends_the_trial_before_the_day_bound() {
+        let mut book = on_trial(7, 2);
+        book.record_copy(TRADER);
+        assert!(!book.get(TRADER).unwrap().is_due(T0 + DAY));
+        assert_eq!(book.review(TRADER, 3.0, T0 + DAY), None);
+
+        book.record_copy(TRADER);
+        assert!(book.get(TRADER).unwrap().is_due(T0 + DAY));
+        assert_eq!(book.review(TRADER, 3.0, T0 + DAY), Some(TrialStatus::Graduated));
+        assert!(!book.get(TRADER).unwrap().is_restricted());
+    }
+
+    #[test]
+    fn day_bound_ends_the_trial_without_copies() {
+        let book = on_trial(7, 10);
+        let trial = book.get(TRADER).unwrap();
+        assert!(!trial.is_due(T0 + 7 * DAY - 1));
+        assert!(trial.is_due(T0 + 7 * DAY));
+        assert_eq!(trial.label(T0 + 2 * DAY), "trial 0/10 copies 5d left");
+    }
+
+    #[test]
+    fn losing_trial_is_held_until_decided() {
+        let mut book = on_trial(0, 1);
+        book.record_copy(TRADER);
+        assert_eq!(book.review(TRADER, -0.01, T0), Some(TrialStatus::Held));
+        let trial = book.get(TRADER).unwrap();
+        assert!(trial.is_restricted());
+        assert!(!trial.is_due(T0 + 30 * DAY));
+        // Held trials don't count further copies or get reviewed again.
+        book.record_copy(TRADER);
+        assert_eq!(book.get(TRADER).unwrap().copies, 1);
+        assert_eq!(book.review(TRADER, 10.0, T0 + DAY), None);
+
+        book.graduate(TRADER).unwrap();
+        assert_eq!(status(&book), TrialStatus::Graduated);
+        assert!(book.graduate(TRADER).is_err());
+    }
+
+    #[test]
+    fn extend_reopens_a_held_trial_from_now() {
+        let mut book = on_trial(7, 3);
+        for _ in 0..3 {
+            book.record_copy(TRADER);
+        }
+        assert_eq!(book.review(TRADER, -4.0, T0 + DAY), Some(TrialStatus::Held));
+
+        let now = T0 + 10 * DAY;
+        book.extend(TRADER, 5, 2, now).unwrap();
+        let trial = book.get(TRADER).unwrap();
+        assert_eq!(trial.status, TrialStatus::Active);
+        assert_eq!(trial.ends_at, Some(now + 5 * DAY));
+        assert_eq!(trial.max_copies, Some(5));
+        assert!(!trial.is_due(now));
+
+        book.record_copy(TRADER);
+        book.record_copy(TRADER);
+        assert_eq!(book.review(TRADER, 1.0, now + DAY), Some(TrialStatus::Graduated));
+    }
+
+    #[test]
+    fn dropped_trader_stays_dropped() {
+        let mut book = on_trial(7, 10);
+        book.drop_trader(TRADER).unwrap();
+        let trial = book.get(TRADER).unwrap();
+        assert_eq!(trial.status, TrialStatus::Dropped);
+        assert!(!trial.is_restricted());
+        assert!(!trial.is_due(T0 + 30 * DAY));
+        assert!(book.extend(TRADER, 7, 0, T0).is_err());
+        assert!(book.drop_trader("0xunknown").is_err());
+    }
+
+    #[test]
+    fn trial_sizing_scales_before_the_caps() {
+        let config = crate::config::test_config(&[
+            ("COPY_STRATEGY", "FIXED"),
+            ("COPY_SIZE", "8"),
+            ("TRIAL_COPIES", "5"),
+        ]);
+        let tc = config.trial().cloned().unwrap();
+        let strategy = &config.copy_strategy_config;
+        let mut inputs = crate::config::SizingInputs {
+            trial_multiplier: Some(tc.size_multiplier),
+            ..Default::default()
+        };
+        inputs.cap_order_size(strategy, tc.max_order_size_usd);
+        let calc = crate::config::calculate_order_size(strategy, &inputs, 100.0, 1_000.0, 0.0);
+        let trial = calc.steps.iter().find(|s| s.name == "trial").unwrap();
+        assert_eq!((trial.input_usd, trial.output_usd), (8.0, 4.0));
+        assert_eq!(calc.final_amount, 4.0);
+
+        inputs.trial_multiplier = Some(1.0);
+        let calc = crate::config::calculate_order_size(strategy, &inputs, 100.0, 1_000.0, 0.0);
+        assert_eq!(calc.final_amount, 5.0);
+    }
+
+    #[test]
+    fn commands_parse_from_the_action_path() {
+        let tc = config(7, 10);
+        let upper = TRADER.to_uppercase().replace("0X", "0x");
+        assert_eq!(
+            TrialCommand::parse(&format!("{}/extend", upper), &tc),
+            Ok((TRADER.to_string(), TrialCommand::Extend { days: 7, copies: 10 }))
+        );
+        assert_eq!(
+            TrialCommand::parse(&format!("{}/extend/3/0", TRADER), &tc).unwrap().1,
+            TrialCommand::Extend { days: 3, copies: 0 }
+        );
+        assert_eq!(
+            TrialCommand::parse(&format!("{}/drop", TRADER), &tc).unwrap().1,
+            TrialCommand::Drop
+        );
+        for bad in ["graduate", "0xabc/extend/3", "0xabc/extend/x/1", "0xabc/pause"] {
+            assert!(TrialCommand::parse(bad, &tc).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn decisions_are_saved_with_the_book() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().to_str().unwrap();
+        let mut book = TrialBook::load(state_dir);
+        book.enroll(TRADER, &config(0, 1), T0, false);
+        book.record_copy(TRADER);
+        book.review(TRADER, -1.0, T0);
+        book.apply(TRADER, TrialCommand::Graduate, T0).unwrap();
+
+        let saved = TrialBook::load(state_dir);
+        assert_eq!(saved.get(TRADER).unwrap().status, TrialStatus::Graduated);
+        assert!(book.apply(TRADER, TrialCommand::Extend { days: 1, copies: 1 }, T0).is_err());
+    }
+
+    fn row(timestamp: i64, side: &str, usd: f64, tokens: f64) -> JournalEntry {
+        serde_json::from_value(serde_json::json!({
+            "schema_version": JOURNAL_SCHEMA_VERSION,
+            "timestamp": timestamp,
+            "status": JournalStatus::Executed,
+            "trader": TRADER,
+            "asset": "123",
+            "side": side,
+            "my_usd": usd,
+            "my_tokens": tokens,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn trial_pnl_counts_lots_opened_during_the_trial() {
+        let entries = vec![
+            row(T0 - DAY, "BUY", 10.0, 20.0),
+            row(T0 - DAY + 60, "SELL", 5.0, 20.0),
+            row(T0 + 60, "BUY", 4.0, 10.0),
+            row(T0 + 120, "SELL", 6.0, 10.0),
+            row(T0 + 180, "BUY", 3.0, 5.0),
+        ];
+        assert!((trial_pnl(&entries, TRADER, T0) - 2.0).abs() < 1e-9);
+        assert_eq!(trial_pnl(&entries, "0xother", T0), 0.0);
+    }
+}
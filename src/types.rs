@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::config::UsdcSizePreference;
+use crate::skip_reason::SkipReason;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserActivity {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -39,7 +42,7 @@ impl UserActivity {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserPosition {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
@@ -71,7 +74,17 @@ pub struct UserPosition {
     pub negative_risk: Option<bool>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A trader's activity as RTDS sends it. What the numbers mean depends on `type`:
+///
+/// | type                           | `size`          | `price`        | `usdcSize`             |
+/// |--------------------------------|-----------------|----------------|------------------------|
+/// | `TRADE` (or absent)            | shares          | USDC per share | USDC, ≈ size × price   |
+/// | `SPLIT`, `MERGE`, `CONVERSION` | shares          | absent or 0    | USDC (1 per share set) |
+/// | `REDEEM`                       | shares redeemed | absent or 0    | USDC paid out          |
+/// | `REWARD`                       | USDC            | absent or 0    | USDC                   |
+///
+/// Read the numbers through `shares`, `price` and `usd_value`, which only accept trades.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RtdsActivity {
     pub proxy_wallet: Option<String>,
@@ -79,7 +92,9 @@ pub struct RtdsActivity {
     pub condition_id: Option<String>,
     #[serde(rename = "type")]
     pub activity_type: Option<String>,
+    /// Shares for trades; see the table above for other types.
     pub size: Option<f64>,
+    /// USDC per share, trades only.
     pub price: Option<f64>,
     pub asset: Option<String>,
     pub side: Option<String>,
@@ -91,10 +106,545 @@ pub struct RtdsActivity {
     pub outcome: Option<String>,
     pub name: Option<String>,
     pub transaction_hash: Option<String>,
+    /// USDC notional as sent in the payload, when present; see `usd_value`.
+    #[serde(default, rename = "usdcSize")]
+    pub reported_usdc_size: Option<f64>,
+    /// Sent by the reconnect backfill rather than received live (see `backfill`).
+    #[serde(skip)]
+    pub backfilled: bool,
+}
+
+/// Prices this close to 0 or 1 are treated as out of range.
+const PRICE_EPSILON: f64 = 1e-6;
+/// Reported USDC this far off size × price is a broken payload, not fees or rounding.
+const USDC_SIZE_GROSS_MISMATCH: f64 = 0.5;
+/// 2020-01-01: anything older is a corrupt timestamp rather than an old trade.
+const MIN_SANE_TIMESTAMP: i64 = 1_577_836_800;
+const MAX_FUTURE_SKEW_SECS: i64 = 300;
+
+/// The activity `type`, which decides the units of `size` (see `RtdsActivity`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Trade,
+    Split,
+    Merge,
+    Conversion,
+    Redeem,
+    Reward,
+    Other,
+}
+
+impl ActivityKind {
+    /// A missing type is a trade: the RTDS `trades` topic doesn't always repeat it.
+    pub fn parse(activity_type: Option<&str>) -> Self {
+        match activity_type.map(|t| t.trim().to_uppercase()).as_deref() {
+            None | Some("TRADE") => Self::Trade,
+            Some("SPLIT") => Self::Split,
+            Some("MERGE") => Self::Merge,
+            Some("CONVERSION") => Self::Conversion,
+            Some("REDEEM") => Self::Redeem,
+            Some("REWARD") => Self::Reward,
+            Some(_) => Self::Other,
+        }
+    }
+}
+
+/// The trade's USD notional from both sources in the payload and the one sizing uses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsdcNotional {
+    /// `usdcSize` as sent, when present.
+    pub reported: Option<f64>,
+    /// size × price.
+    pub derived: f64,
+    pub chosen: f64,
+}
+
+impl UsdcNotional {
+    /// Whether `reported` is off `derived` by more than `tolerance` (a fraction, at least $0.01).
+    pub fn diverges(&self, tolerance: f64) -> bool {
+        self.reported
+            .is_some_and(|r| (r - self.derived).abs() > (self.derived * tolerance).max(0.01))
+    }
 }
 
 impl RtdsActivity {
-    pub fn usdc_size(&self) -> f64 {
-        self.size.unwrap_or(0.0) * self.price.unwrap_or(0.0)
+    pub fn kind(&self) -> ActivityKind {
+        ActivityKind::parse(self.activity_type.as_deref())
+    }
+
+    /// Dedupe key for the executor. One transaction can hold several fills across outcome
+    /// tokens, so the hash alone would drop every fill after the first.
+    pub fn dedupe_key(&self, trader: &str) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            trader,
+            self.transaction_hash.as_deref().unwrap_or(""),
+            self.asset.as_deref().unwrap_or(""),
+            self.side.as_deref().unwrap_or("").to_uppercase(),
+            self.size.unwrap_or(0.0)
+        )
+    }
+
+    fn require_trade(&self) -> Result<(), String> {
+        match self.kind() {
+            ActivityKind::Trade => Ok(()),
+            _ => Err(format!(
+                "{} activity is not a trade",
+                self.activity_type.as_deref().unwrap_or("?")
+            )),
+        }
+    }
+
+    /// Shares traded.
+    pub fn shares(&self) -> Result<f64, String> {
+        self.require_trade()?;
+        let size = self.size.ok_or("missing size")?;
+        if !size.is_finite() || size < 0.0 {
+            return Err(format!("negative or invalid size {}", size));
+        }
+        Ok(size)
+    }
+
+    /// USDC per share, strictly inside (0, 1).
+    pub fn price(&self) -> Result<f64, String> {
+        self.require_trade()?;
+        let price = self.price.ok_or("missing price")?;
+        if !price.is_finite() || price <= PRICE_EPSILON || price >= 1.0 - PRICE_EPSILON {
+            return Err(format!("price {} outside (0, 1)", price));
+        }
+        Ok(price)
+    }
+
+    /// Both USD notionals, with the one `preference` picks as `chosen`. Without `usdcSize`
+    /// in the payload size × price is used whatever the preference. A `usdcSize` grossly at
+    /// odds with size × price (e.g. shares sent as dollars) is an error; smaller drift is
+    /// left to `UsdcNotional::diverges`.
+    pub fn usd_value(&self, preference: UsdcSizePreference) -> Result<UsdcNotional, String> {
+        let shares = self.shares()?;
+        let price = self.price()?;
+        let derived = shares * price;
+        let reported = self.reported_usdc_size;
+        if let Some(r) = reported {
+            if !r.is_finite() || r < 0.0 {
+                return Err(format!("negative or invalid usdcSize {}", r));
+            }
+            if (r - derived).abs() > (derived * USDC_SIZE_GROSS_MISMATCH).max(0.01) {
+                return Err(format!(
+                    "usdcSize {:.4} inconsistent with size {} × price {} = {:.4}",
+                    r, shares, price, derived
+                ));
+            }
+        }
+        let chosen = match (reported, preference) {
+            (None, _) | (Some(_), UsdcSizePreference::Derived) => derived,
+            (Some(r), UsdcSizePreference::Reported) => r,
+            (Some(r), UsdcSizePreference::Conservative) => r.min(derived),
+        };
+        Ok(UsdcNotional {
+            reported,
+            derived,
+            chosen,
+        })
+    }
+
+    /// Rejects payloads whose numbers would break sizing: anything but a trade, prices
+    /// outside (0, 1), negative sizes, a `usdcSize` grossly at odds with size × price, and
+    /// timestamps before 2020 or in the future.
+    pub fn validate(&self, now_secs: i64) -> Result<(), String> {
+        self.usd_value(UsdcSizePreference::Derived)?;
+        let ts = self.timestamp.ok_or("missing timestamp")?;
+        let ts_secs = if ts > 1_000_000_000_000 {
+            ts / 1000
+        } else {
+            ts
+        };
+        if ts_secs < MIN_SANE_TIMESTAMP || ts_secs > now_secs + MAX_FUTURE_SKEW_SECS {
+            return Err(format!("timestamp {} out of range", ts));
+        }
+        Ok(())
+    }
+}
+
+/// What the bot knows about one market, keyed by condition id: the order parameters from
+/// the CLOB and the descriptive fields and size figures from Gamma. See `metadata_cache`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketMetadata {
+    pub condition_id: String,
+    pub question: Option<String>,
+    pub slug: Option<String>,
+    pub event_slug: Option<String>,
+    pub category: Option<String>,
+    /// Scheduled end as Gamma sends it; see `resolution::parse_end_date`.
+    pub end_date: Option<String>,
+    pub closed: bool,
+    pub neg_risk: bool,
+    /// Tick reported for the market; see `order_templates::TickRegime` for the extremes.
+    pub tick_size: Option<f64>,
+    pub min_order_size: Option<f64>,
+    pub fee_rate_bps: Option<u64>,
+    pub volume_usd: Option<f64>,
+    pub open_interest_usd: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    /// CLOB token ids, in outcome order.
+    pub token_ids: Vec<String>,
+}
+
+/// Version of the `BotEventEnvelope` schema; bumped only when a field changes meaning or goes
+/// away. New fields and events don't bump it.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// The trader's trade an event is about.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EventTrade {
+    /// The logical trader (`TRADER_GROUPS` id or address).
+    pub trader: String,
+    pub transaction_hash: Option<String>,
+    pub side: Option<String>,
+    pub asset: Option<String>,
+    pub condition_id: Option<String>,
+    pub title: Option<String>,
+    pub outcome: Option<String>,
+}
+
+impl EventTrade {
+    pub fn new(trader: &str, trade: &UserActivity) -> Self {
+        Self {
+            trader: trader.to_string(),
+            transaction_hash: trade.transaction_hash.clone(),
+            side: trade.side.clone(),
+            asset: trade.asset.clone(),
+            condition_id: trade.condition_id.clone(),
+            title: trade.title.clone(),
+            outcome: trade.outcome.clone(),
+        }
+    }
+}
+
+/// A lifecycle event for `EVENT_WEBHOOK_URL`, tagged by `event`. Receivers parse these, so
+/// fields are only ever added.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BotEvent {
+    /// A trader's trade reached the executor.
+    TradeDetected {
+        #[serde(flatten)]
+        trade: EventTrade,
+        trader_usd: Option<f64>,
+        price: Option<f64>,
+    },
+    /// A copy filled, in full or in part.
+    OrderPlaced {
+        #[serde(flatten)]
+        trade: EventTrade,
+        my_usd: f64,
+        my_tokens: f64,
+        price: f64,
+    },
+    /// A signal that was not copied; `skip_reason` is the journal's code.
+    OrderSkipped {
+        #[serde(flatten)]
+        trade: EventTrade,
+        skip_reason: SkipReason,
+        detail: String,
+    },
+    /// The copy's orders failed or were all rejected.
+    OrderFailed {
+        #[serde(flatten)]
+        trade: EventTrade,
+        error: String,
+    },
+    /// RTDS is connected again after a drop.
+    MonitorReconnected { attempts: u32, down_secs: i64 },
+    Shutdown { reason: String },
+}
+
+/// What is POSTed: the event plus when and by which bot.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BotEventEnvelope {
+    pub schema_version: u32,
+    /// Unix seconds.
+    pub timestamp: i64,
+    /// `JOURNAL_INSTANCE_ID`, so several bots can share an endpoint.
+    pub instance_id: String,
+    /// The build that sent it (`build_info::VERSION` and `GIT_HASH`).
+    pub version: &'static str,
+    pub commit: &'static str,
+    #[serde(flatten)]
+    pub event: BotEvent,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_760_000_000;
+
+    fn activity(price: f64, size: f64, timestamp: i64) -> RtdsActivity {
+        RtdsActivity {
+            price: Some(price),
+            size: Some(size),
+            timestamp: Some(timestamp),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sane_activity_passes() {
+        assert_eq!(activity(0.42, 100.0, NOW).validate(NOW), Ok(()));
+        // Millisecond timestamps are accepted too.
+        assert_eq!(activity(0.42, 100.0, NOW * 1000).validate(NOW), Ok(()));
+    }
+
+    #[test]
+    fn price_outside_zero_one_is_rejected() {
+        for price in [0.0, 1.0, 1.5, -0.2, f64::NAN] {
+            assert!(activity(price, 100.0, NOW).validate(NOW).is_err(), "{}", price);
+        }
+    }
+
+    #[test]
+    fn negative_or_invalid_size_is_rejected() {
+        for size in [-1.0, f64::INFINITY, f64::NAN] {
+            assert!(activity(0.5, size, NOW).validate(NOW).is_err(), "{}", size);
+        }
+    }
+
+    #[test]
+    fn timestamp_before_2020_is_rejected() {
+        assert!(activity(0.5, 10.0, MIN_SANE_TIMESTAMP - 1).validate(NOW).is_err());
+        assert!(activity(0.5, 10.0, MIN_SANE_TIMESTAMP).validate(NOW).is_ok());
+    }
+
+    #[test]
+    fn timestamp_more_than_five_minutes_ahead_is_rejected() {
+        assert!(activity(0.5, 10.0, NOW + 301).validate(NOW).is_err());
+        assert!(activity(0.5, 10.0, NOW + 300).validate(NOW).is_ok());
+    }
+
+    #[test]
+    fn usdc_size_must_match_size_times_price() {
+        let mut a = activity(0.5, 100.0, NOW);
+        a.reported_usdc_size = Some(50.5);
+        assert!(a.validate(NOW).is_ok());
+        a.reported_usdc_size = Some(80.0);
+        assert!(a.validate(NOW).is_err());
+    }
+
+    #[test]
+    fn fills_sharing_a_transaction_are_deduped_apart() {
+        use crate::processed_trades::ProcessedTrades;
+
+        let fill = |asset: &str| RtdsActivity {
+            asset: Some(asset.to_string()),
+            side: Some("BUY".to_string()),
+            transaction_hash: Some("0xabc".to_string()),
+            ..activity(0.5, 10.0, NOW)
+        };
+        let (yes, no) = (fill("111"), fill("222"));
+        let mut processed = ProcessedTrades::in_memory(24);
+        assert!(processed.record(&yes.dedupe_key("0xtrader"), NOW));
+        assert!(processed.record(&no.dedupe_key("0xtrader"), NOW));
+        // A re-delivery of either is still a duplicate.
+        assert!(!processed.record(&fill("111").dedupe_key("0xtrader"), NOW));
+    }
+
+    /// An RTDS trade payload: 150 shares at 0.59 (size × price = $88.50).
+    fn payload(usdc_size: Option<f64>) -> RtdsActivity {
+        let mut json = serde_json::json!({
+            "proxyWallet": "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b",
+            "timestamp": 1_760_000_000,
+            "conditionId": "0xc1",
+            "type": "TRADE",
+            "size": 150.0,
+            "price": 0.59,
+            "asset": "123",
+            "side": "BUY",
+            "transactionHash": "0x9a",
+        });
+        if let Some(usd) = usdc_size {
+            json["usdcSize"] = usd.into();
+        }
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn agreement() {
+        let activity = payload(Some(88.5));
+        assert!(activity.validate(NOW).is_ok());
+        let n = activity.usd_value(UsdcSizePreference::Conservative).unwrap();
+        assert!(!n.diverges(0.02));
+        assert!((n.chosen - 88.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn small_drift_sizes_from_the_smaller_unless_told_otherwise() {
+        // A fee-sized gap: within tolerance, so nothing is logged, but sizing still picks.
+        let activity = payload(Some(88.2));
+        assert!(activity.validate(NOW).is_ok());
+        let n = activity.usd_value(UsdcSizePreference::Conservative).unwrap();
+        assert!(!n.diverges(0.02));
+        assert_eq!(n.chosen, 88.2);
+        assert_eq!(activity.usd_value(UsdcSizePreference::Reported).unwrap().chosen, 88.2);
+        let derived = activity.usd_value(UsdcSizePreference::Derived).unwrap();
+        assert!((derived.chosen - 88.5).abs() < 1e-9);
+
+        // Above it the drift is flagged; the conservative pick is now size × price.
+        let activity = payload(Some(95.0));
+        assert!(activity.validate(NOW).is_ok());
+        let n = activity.usd_value(UsdcSizePreference::Conservative).unwrap();
+        assert!(n.diverges(0.02));
+        assert!((n.chosen - 88.5).abs() < 1e-9);
+        assert_eq!(n.reported, Some(95.0));
+    }
+
+    #[test]
+    fn gross_mismatch_is_rejected() {
+        // usdcSize in shares rather than dollars.
+        let activity = payload(Some(150.0));
+        assert!(activity.validate(NOW).unwrap_err().contains("inconsistent"));
+        assert!(activity
+            .usd_value(UsdcSizePreference::Conservative)
+            .unwrap_err()
+            .contains("inconsistent"));
+    }
+
+    #[test]
+    fn missing_usdc_size_falls_back_to_size_times_price() {
+        let activity = payload(None);
+        for preference in [
+            UsdcSizePreference::Conservative,
+            UsdcSizePreference::Reported,
+            UsdcSizePreference::Derived,
+        ] {
+            let n = activity.usd_value(preference).unwrap();
+            assert_eq!(n.reported, None);
+            assert!((n.chosen - 88.5).abs() < 1e-9);
+        }
+        assert!(!activity
+            .usd_value(UsdcSizePreference::Reported)
+            .unwrap()
+            .diverges(0.0));
+    }
+
+    fn envelope(event: BotEvent) -> serde_json::Value {
+        serde_json::to_value(BotEventEnvelope {
+            schema_version: EVENT_SCHEMA_VERSION,
+            timestamp: NOW,
+            instance_id: "bot-1".to_string(),
+            version: "0.1.0",
+            commit: "3f2a9c1d04be",
+            event,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn bot_events_serialize_to_the_published_shape() {
+        let trade = EventTrade::new(
+            "0xabc",
+            &UserActivity {
+                transaction_hash: Some("0xtx".to_string()),
+                side: Some("BUY".to_string()),
+                asset: Some("123".to_string()),
+                condition_id: Some("0xc".to_string()),
+                title: Some("Will it rain?".to_string()),
+                outcome: Some("Yes".to_string()),
+                ..Default::default()
+            },
+        );
+        let trade_fields = serde_json::json!({
+            "schema_version": 1,
+            "timestamp": NOW,
+            "instance_id": "bot-1",
+            "version": "0.1.0",
+            "commit": "3f2a9c1d04be",
+            "trader": "0xabc",
+            "transaction_hash": "0xtx",
+            "side": "BUY",
+            "asset": "123",
+            "condition_id": "0xc",
+            "title": "Will it rain?",
+            "outcome": "Yes",
+        });
+        let with = |event: &str, extra: serde_json::Value| {
+            let mut expected = trade_fields.clone();
+            expected["event"] = event.into();
+            for (key, value) in extra.as_object().unwrap() {
+                expected[key] = value.clone();
+            }
+            expected
+        };
+
+        let detected = BotEvent::TradeDetected {
+            trade: trade.clone(),
+            trader_usd: Some(40.0),
+            price: Some(0.4),
+        };
+        assert_eq!(
+            envelope(detected),
+            with("trade_detected", serde_json::json!({ "trader_usd": 40.0, "price": 0.4 }))
+        );
+        let placed = BotEvent::OrderPlaced {
+            trade: trade.clone(),
+            my_usd: 4.0,
+            my_tokens: 10.0,
+            price: 0.4,
+        };
+        assert_eq!(
+            envelope(placed),
+            with(
+                "order_placed",
+                serde_json::json!({ "my_usd": 4.0, "my_tokens": 10.0, "price": 0.4 })
+            )
+        );
+        let skipped = BotEvent::OrderSkipped {
+            trade: trade.clone(),
+            skip_reason: SkipReason::filtered("price_band"),
+            detail: "price 0.97 above 0.95".to_string(),
+        };
+        assert_eq!(
+            envelope(skipped),
+            with(
+                "order_skipped",
+                serde_json::json!({
+                    "skip_reason": "filtered:price_band",
+                    "detail": "price 0.97 above 0.95",
+                })
+            )
+        );
+        let failed = BotEvent::OrderFailed {
+            trade,
+            error: "rejected".to_string(),
+        };
+        assert_eq!(
+            envelope(failed),
+            with("order_failed", serde_json::json!({ "error": "rejected" }))
+        );
+
+        let header = serde_json::json!({
+            "schema_version": 1,
+            "timestamp": NOW,
+            "instance_id": "bot-1",
+            "version": "0.1.0",
+            "commit": "3f2a9c1d04be",
+        });
+        let reconnected = BotEvent::MonitorReconnected {
+            attempts: 3,
+            down_secs: 42,
+        };
+        let mut expected = header.clone();
+        expected["event"] = "monitor_reconnected".into();
+        expected["attempts"] = 3.into();
+        expected["down_secs"] = 42.into();
+        assert_eq!(envelope(reconnected), expected);
+        let mut expected = header;
+        expected["event"] = "shutdown".into();
+        expected["reason"] = "signal".into();
+        assert_eq!(
+            envelope(BotEvent::Shutdown {
+                reason: "signal".to_string()
+            }),
+            expected
+        );
     }
 }
@@ -0,0 +1,182 @@
+//! USDC allowance toward the exchanges, checked at startup. BUYs pay from the proxy wallet's
+//! USDC through the exchange contracts, and without an allowance the first copy fails with a
+//! bare balance/allowance rejection. An EOA wallet gets the approval submitted on the spot; a
+//! Safe or proxy wallet has to approve from its own UI, and the bot won't start until it has
+//! (unless `ALLOW_UNAPPROVED=true`).
+
+use alloy::primitives::U256;
+use alloy::providers::ProviderBuilder;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::sol;
+use anyhow::{Context, Result};
+
+use crate::config::EnvConfig;
+use crate::ctf_approval::{is_eoa, operators};
+use crate::utils::{get_usdc_allowance, Logger, RpcClient};
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+}
+
+/// Exchange contracts `owner` has given less than `min_usd` of USDC allowance, with what they
+/// have.
+pub async fn short_allowances(
+    rpc_url: &str,
+    usdc_contract: &str,
+    owner: &str,
+    min_usd: f64,
+) -> Result<Vec<(&'static str, f64)>> {
+    let mut short = Vec::new();
+    for (name, spender) in operators() {
+        let allowance =
+            get_usdc_allowance(rpc_url, usdc_contract, owner, &spender.to_string()).await?;
+        if allowance < min_usd {
+            short.push((name, allowance));
+        }
+    }
+    Ok(short)
+}
+
+fn describe(short: &[(&'static str, f64)]) -> String {
+    short
+        .iter()
+        .map(|(name, allowance)| format!("{} (${:.2})", name, allowance))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Step-by-step approval for a Safe or proxy wallet, which this key can't approve for.
+fn instructions(wallet: &str, usdc_contract: &str, short: &[(&'static str, f64)]) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "PROXY_WALLET {} is a Safe/proxy wallet, so the USDC approval has to come from the wallet itself:",
+            wallet.trim()
+        ),
+        "  1. Easiest: log in on polymarket.com with this wallet and enable trading (asked on the first deposit or trade).".to_string(),
+        format!(
+            "  2. Or from the Safe (app.safe.global → New transaction → Contract interaction), on USDC {} call approve(spender, {}) for each of:",
+            usdc_contract.trim(),
+            U256::MAX
+        ),
+    ];
+    for (name, address) in operators() {
+        if short.iter().any(|(n, _)| *n == name) {
+            lines.push(format!("       {}: {}", name, address));
+        }
+    }
+    lines.push("Then restart the bot; make health-check shows the allowance.".to_string());
+    lines
+}
+
+/// Startup check. Missing allowances are approved for an EOA wallet (the wallet pays gas) and
+/// explained for a Safe; either way the bot refuses to start without them unless
+/// `ALLOW_UNAPPROVED=true`. A dry run places no orders, so it only warns.
+pub async fn ensure_allowance(config: &EnvConfig, signer: &PrivateKeySigner) -> Result<()> {
+    let short = match short_allowances(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+        config.min_usdc_allowance_usd,
+    )
+    .await
+    {
+        Ok(short) if short.is_empty() => return Ok(()),
+        Ok(short) => short,
+        Err(e) => {
+            Logger::warning(&format!("Could not check the USDC allowance: {:#}", e));
+            return Ok(());
+        }
+    };
+    let list = describe(&short);
+    if config.dry_run {
+        Logger::warning(&format!(
+            "USDC not approved for {}: live BUYs would be rejected (DRY_RUN places none)",
+            list
+        ));
+        return Ok(());
+    }
+    if is_eoa(config, signer) {
+        match approve_all(config, signer, &short).await {
+            Ok(()) => {
+                Logger::success(&format!("USDC approved for {}", list));
+                return Ok(());
+            }
+            Err(e) => Logger::error(&format!(
+                "USDC approval failed (the wallet needs POL for gas): {:#}",
+                e
+            )),
+        }
+    } else {
+        Logger::warning(&format!("USDC not approved for {}: copied BUYs will fail.", list));
+        for line in instructions(&config.proxy_wallet, &config.usdc_contract_address, &short) {
+            Logger::warning(&line);
+        }
+    }
+    if config.allow_unapproved {
+        Logger::warning("ALLOW_UNAPPROVED=true: starting anyway; BUYs are rejected until USDC is approved");
+        return Ok(());
+    }
+    anyhow::bail!(
+        "USDC allowance missing for {} (set ALLOW_UNAPPROVED=true to start anyway)",
+        list
+    )
+}
+
+async fn approve_all(
+    config: &EnvConfig,
+    signer: &PrivateKeySigner,
+    short: &[(&'static str, f64)],
+) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .wallet(signer.clone())
+        .connect_provider(RpcClient::from_config(config)?.provider().clone());
+    let usdc = IERC20::new(
+        config
+            .usdc_contract_address
+            .trim()
+            .parse()
+            .context("Invalid USDC_CONTRACT_ADDRESS")?,
+        &provider,
+    );
+    for (name, spender) in operators() {
+        if !short.iter().any(|(n, _)| *n == name) {
+            continue;
+        }
+        Logger::info(&format!("Submitting USDC approve for {}...", name));
+        let receipt = usdc
+            .approve(spender, U256::MAX)
+            .send()
+            .await
+            .with_context(|| format!("approval for {} not sent", name))?
+            .get_receipt()
+            .await
+            .with_context(|| format!("approval for {} not confirmed", name))?;
+        if !receipt.status() {
+            anyhow::bail!("approval for {} reverted ({})", name, receipt.transaction_hash);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_instructions_name_each_unapproved_contract() {
+        let usdc = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+        let short = [("CTF Exchange", 0.0), ("Neg Risk Adapter", 5.0)];
+        let lines = instructions(" 0xabc ", usdc, &short);
+        let text = lines.join("\n");
+        assert!(text.contains("PROXY_WALLET 0xabc is"), "{}", text);
+        assert!(text.contains(usdc) && text.contains(&U256::MAX.to_string()), "{}", text);
+        for (name, address) in operators() {
+            let listed = text.contains(&format!("{}: {}", name, address));
+            assert_eq!(listed, name != "Neg Risk CTF Exchange", "{}", name);
+        }
+        assert_eq!(describe(&short), "CTF Exchange ($0.00), Neg Risk Adapter ($5.00)");
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::config::EnvConfig;
+use crate::utils::{Logger, RpcClient};
+
+/// Where a `CachedBalance` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceSource {
+    /// Read from the chain for this call.
+    Rpc,
+    /// Read within the TTL by an earlier call.
+    Cache,
+    /// The RPC call failed; this is the last value it returned.
+    Stale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedBalance {
+    pub usd: f64,
+    /// Seconds since it was read from the chain.
+    pub age_secs: u64,
+    pub source: BalanceSource,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    usd: f64,
+    at: Instant,
+    /// An order went out since, so the next call reads the chain again.
+    invalidated: bool,
+}
+
+/// `PROXY_WALLET`'s USDC balance, read from the chain at most once per
+/// `BALANCE_CACHE_TTL_SECS`: a burst of fills from one trader would otherwise make an
+/// `eth_call` ahead of every copy. Clones share the cached value.
+#[derive(Clone)]
+pub struct BalanceCache {
+    rpc: Arc<RpcClient>,
+    usdc_contract: String,
+    wallet: String,
+    ttl: Duration,
+    reading: Arc<RwLock<Option<Reading>>>,
+}
+
+impl BalanceCache {
+    pub fn new(rpc: Arc<RpcClient>, usdc_contract: &str, wallet: &str, ttl: Duration) -> Self {
+        Self {
+            rpc,
+            usdc_contract: usdc_contract.to_string(),
+            wallet: wallet.to_string(),
+            ttl,
+            reading: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn from_config(config: &EnvConfig, rpc: Arc<RpcClient>) -> Self {
+        Self::new(
+            rpc,
+            &config.usdc_contract_address,
+            &config.proxy_wallet,
+            Duration::from_secs(config.balance_cache_ttl_secs),
+        )
+    }
+
+    /// The cached balance while it is within the TTL, otherwise a fresh one. When the RPC
+    /// call fails the last value is returned as `Stale`; an error only when there is none.
+    pub async fn get(&self) -> Result<CachedBalance> {
+        self.load(false, || self.fetch()).await
+    }
+
+    /// Reads the chain whatever the cache holds, for orders that would spend most of it.
+    pub async fn force_refresh(&self) -> Result<CachedBalance> {
+        self.load(true, || self.fetch()).await
+    }
+
+    /// Called once an order is placed: the next `get` reads the chain. The old value stays
+    /// as the fallback for a failing RPC.
+    pub async fn invalidate(&self) {
+        if let Some(reading) = self.reading.write().await.as_mut() {
+            reading.invalidated = true;
+        }
+    }
+
+    async fn fetch(&self) -> Result<f64> {
+        self.rpc
+            .usdc_balance(&self.usdc_contract, &self.wallet)
+            .await
+    }
+
+    async fn load<F>(&self, force: bool, fetch: impl FnOnce() -> F) -> Result<CachedBalance>
+    where
+        F: Future<Output = Result<f64>>,
+    {
+        let fresh = |r: &Reading| !force && !r.invalidated && r.at.elapsed() < self.ttl;
+        let current = *self.reading.read().await;
+        if let Some(r) = current.filter(fresh) {
+            return Ok(cached(r, BalanceSource::Cache));
+        }
+        // Held across the call so a burst of trades waits for one read instead of each
+        // making its own.
+        let mut reading = self.reading.write().await;
+        if let Some(r) = reading.filter(fresh) {
+            return Ok(cached(r, BalanceSource::Cache));
+        }
+        match fetch().await {
+            Ok(usd) => {
+                let r = Reading {
+                    usd,
+                    at: Instant::now(),
+                    invalidated: false,
+                };
+                *reading = Some(r);
+                Ok(cached(r, BalanceSource::Rpc))
+            }
+            Err(e) => {
+                let Some(r) = *reading else {
+                    return Err(e);
+                };
+                let stale = cached(r, BalanceSource::Stale);
+                Logger::warning(&format!(
+                    "USDC balance RPC failed ({:#}); using ${:.2} from {}s ago",
+                    e, stale.usd, stale.age_secs
+                ));
+                Ok(stale)
+            }
+        }
+    }
+}
+
+fn cached(reading: Reading, source: BalanceSource) -> CachedBalance {
+    CachedBalance {
+        usd: reading.usd,
+        age_secs: reading.at.elapsed().as_secs(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(ttl_secs: u64) -> BalanceCache {
+        BalanceCache::new(
+            RpcClient::for_url("http://127.0.0.1:9").unwrap(),
+            "0xusdc",
+            "0xwallet",
+            Duration::from_secs(ttl_secs),
+        )
+    }
+
+    #[tokio::test]
+    async fn reads_within_the_ttl_come_from_the_cache() {
+        let cache = cache(60);
+        let first = cache.load(false, || async { Ok(100.0) }).await.unwrap();
+        assert_eq!((first.usd, first.source), (100.0, BalanceSource::Rpc));
+        let second = cache.load(false, || async { Ok(50.0) }).await.unwrap();
+        assert_eq!((second.usd, second.source), (100.0, BalanceSource::Cache));
+
+        let forced = cache.load(true, || async { Ok(50.0) }).await.unwrap();
+        assert_eq!((forced.usd, forced.source), (50.0, BalanceSource::Rpc));
+
+        cache.invalidate().await;
+        let after_order = cache.load(false, || async { Ok(40.0) }).await.unwrap();
+        assert_eq!(
+            (after_order.usd, after_order.source),
+            (40.0, BalanceSource::Rpc)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failing_rpc_falls_back_to_the_last_value() {
+        let cache = cache(0);
+        let rpc_down = || async { Err(anyhow::anyhow!("rpc down")) };
+        assert!(cache.load(false, rpc_down).await.is_err());
+        cache.load(false, || async { Ok(80.0) }).await.unwrap();
+        let stale = cache.load(false, rpc_down).await.unwrap();
+        assert_eq!((stale.usd, stale.source), (80.0, BalanceSource::Stale));
+    }
+}
@@ -0,0 +1,194 @@
+use serde::de::DeserializeOwned;
+
+use crate::config::EnvConfig;
+use crate::types::{UserActivity, UserPosition};
+use crate::utils::{fetch_data, FetchError};
+
+/// `DATA_API_URL` when the config hasn't set it.
+pub const DEFAULT_DATA_API_URL: &str = "https://data-api.polymarket.com";
+/// Rows `get_activity` asks for; the data API's own maximum.
+const ACTIVITY_PAGE_LIMIT: usize = 500;
+/// A wallet that never trades: its positions are a cheap, always-valid query for `ping`.
+const PING_WALLET: &str = "0x0000000000000000000000000000000000000000";
+
+/// The Polymarket data API (`DATA_API_URL`): positions and activity of any wallet. Every
+/// call goes through `fetch_data`, so it shares its retries, backoff and rate limit. Rows
+/// that don't parse are dropped rather than failing the whole list. Cheap to clone.
+#[derive(Clone)]
+pub struct DataApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    timeout_ms: u64,
+    retry_limit: u32,
+}
+
+impl DataApiClient {
+    pub fn new(http: reqwest::Client, base_url: &str, timeout_ms: u64, retry_limit: u32) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeout_ms,
+            retry_limit,
+        }
+    }
+
+    pub fn from_config(config: &EnvConfig, http: &reqwest::Client) -> Self {
+        Self::new(
+            http.clone(),
+            &config.data_api_url,
+            config.request_timeout_ms,
+            config.network_retry_limit,
+        )
+    }
+
+    /// `user`'s open positions.
+    pub async fn get_positions(&self, user: &str) -> Result<Vec<UserPosition>, FetchError> {
+        self.list(&format!("positions?user={}", user)).await
+    }
+
+    /// `user`'s trades at or after `since` (unix seconds), newest first.
+    pub async fn get_activity(
+        &self,
+        user: &str,
+        since: i64,
+    ) -> Result<Vec<UserActivity>, FetchError> {
+        self.list(&format!(
+            "activity?user={}&type=TRADE&start={}&limit={}",
+            user, since, ACTIVITY_PAGE_LIMIT
+        ))
+        .await
+    }
+
+    /// `user`'s latest `limit` trades, newest first.
+    pub async fn get_trades(
+        &self,
+        user: &str,
+        limit: usize,
+    ) -> Result<Vec<UserActivity>, FetchError> {
+        self.list(&format!("activity?user={}&type=TRADE&limit={}", user, limit))
+            .await
+    }
+
+    /// Whether `user` has any activity at all, trades or otherwise.
+    pub async fn has_activity(&self, user: &str) -> Result<bool, FetchError> {
+        self.any(&format!("activity?user={}&limit=1", user)).await
+    }
+
+    /// Whether `user` holds any position.
+    pub async fn has_positions(&self, user: &str) -> Result<bool, FetchError> {
+        self.any(&format!("positions?user={}&limit=1", user)).await
+    }
+
+    /// Succeeds when the data API answers a query, for the startup and health checks.
+    pub async fn ping(&self) -> Result<(), FetchError> {
+        self.get(&format!("positions?user={}&limit=1", PING_WALLET))
+            .await
+            .map(|_| ())
+    }
+
+    async fn get(&self, path: &str) -> Result<serde_json::Value, FetchError> {
+        let url = format!("{}/{}", self.base_url, path);
+        fetch_data(&self.http, &url, self.timeout_ms, self.retry_limit).await
+    }
+
+    async fn list<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>, FetchError> {
+        let data = self.get(path).await?;
+        Ok(match data {
+            serde_json::Value::Array(rows) => rows
+                .into_iter()
+                .filter_map(|row| serde_json::from_value(row).ok())
+                .collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    async fn any(&self, path: &str) -> Result<bool, FetchError> {
+        let data = self.get(path).await?;
+        Ok(data.as_array().is_some_and(|rows| !rows.is_empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Query, State};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    type Seen = Arc<Mutex<Vec<String>>>;
+
+    /// A data API serving one position and one trade for `0xtrader`, recording the queries.
+    async fn mock() -> (DataApiClient, Seen) {
+        async fn positions(
+            State(seen): State<Seen>,
+            Query(q): Query<HashMap<String, String>>,
+        ) -> axum::Json<serde_json::Value> {
+            seen.lock().unwrap().push(format!("positions {:?}", q.get("user")));
+            axum::Json(match q.get("user").map(String::as_str) {
+                Some("0xtrader") => serde_json::json!([
+                    { "asset": "1", "conditionId": "0xc1", "size": 10.0, "currentValue": 6.5 },
+                    "not a position",
+                ]),
+                _ => serde_json::json!([]),
+            })
+        }
+        async fn activity(
+            State(seen): State<Seen>,
+            Query(q): Query<HashMap<String, String>>,
+        ) -> axum::Json<serde_json::Value> {
+            let mut keys: Vec<_> = q.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            keys.sort();
+            seen.lock().unwrap().push(format!("activity {}", keys.join("&")));
+            axum::Json(match q.get("user").map(String::as_str) {
+                Some("0xtrader") => serde_json::json!([
+                    { "type": "TRADE", "timestamp": 1700000000, "side": "BUY", "usdcSize": 5.0 },
+                ]),
+                _ => serde_json::json!([]),
+            })
+        }
+        let seen = Seen::default();
+        let app = axum::Router::new()
+            .route("/positions", axum::routing::get(positions))
+            .route("/activity", axum::routing::get(activity))
+            .with_state(seen.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (DataApiClient::new(reqwest::Client::new(), &url, 2_000, 1), seen)
+    }
+
+    #[tokio::test]
+    async fn rows_come_back_typed_and_bad_rows_are_dropped() {
+        let (client, seen) = mock().await;
+        let positions = client.get_positions("0xtrader").await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].condition_id.as_deref(), Some("0xc1"));
+        assert_eq!(positions[0].current_value, Some(6.5));
+
+        let trades = client.get_activity("0xtrader", 1_699_999_000).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].usdc_size, Some(5.0));
+        assert!(client.get_trades("0xnobody", 50).await.unwrap().is_empty());
+
+        assert!(client.has_positions("0xtrader").await.unwrap());
+        assert!(!client.has_activity("0xnobody").await.unwrap());
+        client.ping().await.unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert!(seen.contains(
+            &"activity limit=500&start=1699999000&type=TRADE&user=0xtrader".to_string()
+        ));
+        assert!(seen.contains(&"activity limit=50&type=TRADE&user=0xnobody".to_string()));
+    }
+
+    #[tokio::test]
+    async fn errors_keep_the_fetch_error() {
+        let (client, _) = mock().await;
+        let broken = DataApiClient {
+            base_url: format!("{}/missing", client.base_url),
+            ..client
+        };
+        let err = broken.ping().await.unwrap_err();
+        assert_eq!(err.status(), Some(reqwest::StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::build_info;
+use crate::config::EnvConfig;
+use crate::supervisor::{supervisor, STOP_LAST};
+use crate::types::{BotEvent, BotEventEnvelope, EVENT_SCHEMA_VERSION};
+use crate::utils::Logger;
+
+/// Events waiting to be delivered; more are dropped.
+const QUEUE: usize = 1_000;
+const ATTEMPTS: u32 = 4;
+/// `sha256=<hex HMAC-SHA256 of the body>`, keyed with `EVENT_WEBHOOK_SECRET`.
+pub const SIGNATURE_HEADER: &str = "X-Bot-Signature";
+
+/// `EVENT_WEBHOOK_URL`: every lifecycle event POSTed as one JSON document. Events queue for
+/// their own task, so a slow or unreachable endpoint never holds up the executor.
+#[derive(Clone)]
+pub struct EventWebhook {
+    http: reqwest::Client,
+    url: String,
+    secret: Option<String>,
+    instance_id: String,
+    /// First retry delay; doubles on each further attempt.
+    retry_delay: Duration,
+}
+
+/// The signature header's value for `body`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+impl EventWebhook {
+    pub fn from_config(config: &EnvConfig) -> Option<Self> {
+        let url = config.event_webhook_url.clone()?;
+        Some(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_millis(config.request_timeout_ms))
+                .build()
+                .unwrap_or_default(),
+            url,
+            secret: config.event_webhook_secret.clone(),
+            instance_id: config.journal_instance_id.clone(),
+            retry_delay: Duration::from_secs(1),
+        })
+    }
+
+    fn body(&self, event: BotEvent) -> Result<String> {
+        Ok(serde_json::to_string(&BotEventEnvelope {
+            schema_version: EVENT_SCHEMA_VERSION,
+            timestamp: chrono::Utc::now().timestamp(),
+            instance_id: self.instance_id.clone(),
+            version: build_info::VERSION,
+            commit: build_info::GIT_HASH,
+            event,
+        })?)
+    }
+
+    /// POSTs one event with a few retries.
+    pub async fn deliver(&self, event: BotEvent) -> Result<()> {
+        let body = self.body(event)?;
+        let signature = self.secret.as_deref().map(|s| sign(s, body.as_bytes()));
+        let mut last_err = None;
+        for attempt in 0..ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(self.retry_delay * (1 << (attempt - 1))).await;
+            }
+            let mut req = self
+                .http
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                req = req.header(SIGNATURE_HEADER, signature);
+            }
+            match req.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err
+            .map(anyhow::Error::from)
+            .unwrap_or_else(|| anyhow::anyhow!("event webhook delivery failed")))
+    }
+}
+
+static WEBHOOK: OnceLock<(EventWebhook, mpsc::Sender<BotEvent>)> = OnceLock::new();
+
+/// Starts the delivery task when `EVENT_WEBHOOK_URL` is set.
+pub fn start(config: &EnvConfig) {
+    let Some(webhook) = EventWebhook::from_config(config) else {
+        return;
+    };
+    let (tx, rx) = mpsc::channel(QUEUE);
+    if WEBHOOK.set((webhook.clone(), tx)).is_err() {
+        return;
+    }
+    Logger::info(&format!("Lifecycle events POSTed to {}", webhook.url));
+    let rx = Arc::new(Mutex::new(rx));
+    supervisor().spawn("event-webhook", STOP_LAST, 5, move || {
+        let (webhook, rx) = (webhook.clone(), rx.clone());
+        async move {
+            let mut rx = rx.lock().await;
+            while let Some(event) = rx.recv().await {
+                if let Err(e) = webhook.deliver(event).await {
+                    Logger::warning(&format!("Event webhook delivery dropped: {}", e));
+                }
+            }
+            Ok(())
+        }
+    });
+}
+
+/// Queues `event`; dropped when the webhook is off or backed up.
+pub fn emit(event: BotEvent) {
+    if let Some((_, tx)) = WEBHOOK.get() {
+        let _ = tx.try_send(event);
+    }
+}
+
+/// Delivers `event` right away, for the last one before the process exits.
+pub async fn emit_now(event: BotEvent) {
+    if let Some((webhook, _)) = WEBHOOK.get() {
+        if let Err(e) = webhook.deliver(event).await {
+            Logger::warning(&format!("Event webhook delivery dropped: {}", e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hex_hmac_sha256_of_the_body() {
+        // RFC 4231 test case 2.
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -1,64 +1,307 @@
-use anyhow::Result;
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use std::time::Duration;
 
+use crate::utils::{jitter_unit, rate_limit, Logger};
+
+/// Wait before the first retry; it doubles with each attempt after that.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between two attempts, from the doubling or from `Retry-After`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// A backoff delay is stretched by up to this fraction, so clients that failed together
+/// don't all retry together.
+const RETRY_JITTER: f64 = 0.5;
+
+/// Why `fetch_data` gave up. Timeouts, 429s and 5xx are transient and were retried up to the
+/// retry limit; other 4xx and undecodable bodies are returned on the first attempt.
+#[derive(Debug, thiserror::Error)]
+pub enum FetchError {
+    #[error("timed out after {attempts} attempts ({url})")]
+    Timeout { url: String, attempts: u32 },
+    #[error("network error after {attempts} attempts ({url}): {source}")]
+    Network {
+        url: String,
+        attempts: u32,
+        source: reqwest::Error,
+    },
+    #[error("HTTP {status} after {attempts} attempts ({url})")]
+    Status {
+        url: String,
+        status: StatusCode,
+        attempts: u32,
+    },
+    #[error("invalid JSON from {url}: {source}")]
+    Decode { url: String, source: reqwest::Error },
+}
+
+impl FetchError {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout { .. })
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// The endpoint was down, slow or rate limiting: the same request may well succeed
+    /// later. A 4xx other than 429 or a malformed body won't.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout { .. } | Self::Network { .. } => true,
+            Self::Status { status, .. } => retryable_status(*status),
+            Self::Decode { .. } => false,
+        }
+    }
+}
+
+fn retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
 fn is_network_error(error: &reqwest::Error) -> bool {
-    error.is_timeout()
-        || error.is_connect()
-        || error.is_request()
-        || error.status().is_none()
+    error.is_timeout() || error.is_connect() || error.is_request() || error.status().is_none()
+}
+
+/// The wait before retry `attempt + 1`: `base` doubled per attempt up to `MAX_RETRY_DELAY`,
+/// stretched by `RETRY_JITTER` times `unit` (in `[0, 1)`).
+fn backoff(base: Duration, attempt: u32, unit: f64) -> Duration {
+    let doubled = base.saturating_mul(1u32 << (attempt - 1).min(16));
+    let jitter = 1.0 + RETRY_JITTER * unit.clamp(0.0, 1.0);
+    doubled.min(MAX_RETRY_DELAY).mul_f64(jitter)
 }
 
+/// `Retry-After` as delay-seconds or an HTTP date, capped at `MAX_RETRY_DELAY`.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    let wait = match value.parse::<u64>() {
+        Ok(secs) => Duration::from_secs(secs),
+        Err(_) => {
+            let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (at.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or(Duration::ZERO)
+        }
+    };
+    Some(wait.min(MAX_RETRY_DELAY))
+}
+
+/// GETs `url` as JSON, trying up to `retry_limit` times. Network errors, timeouts, 429 and
+/// 5xx are retried with exponential backoff and jitter, or after the server's `Retry-After`;
+/// anything else fails at once. Every attempt first waits its turn in the host's
+/// `rate_limit` bucket.
 pub async fn fetch_data(
     client: &Client,
     url: &str,
     timeout_ms: u64,
     retry_limit: u32,
-) -> Result<serde_json::Value> {
-    let retry_delay_ms = 1000u64;
-    
-    for attempt in 1..=retry_limit {
-        match client
+) -> Result<serde_json::Value, FetchError> {
+    fetch_with_backoff(client, url, timeout_ms, retry_limit, RETRY_BASE_DELAY).await
+}
+
+async fn fetch_with_backoff(
+    client: &Client,
+    url: &str,
+    timeout_ms: u64,
+    retry_limit: u32,
+    base_delay: Duration,
+) -> Result<serde_json::Value, FetchError> {
+    let attempts = retry_limit.max(1);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let last = attempt == attempts;
+        let wait = backoff(base_delay, attempt, jitter_unit());
+        rate_limit::acquire(url).await;
+        let sent = client
             .get(url)
-            .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
+            .header(
+                "User-Agent",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36",
+            )
             .timeout(Duration::from_millis(timeout_ms))
             .send()
-            .await
-        {
+            .await;
+        let (wait, cause) = match sent {
             Ok(resp) if resp.status().is_success() => {
-                return Ok(resp.json().await?);
+                return resp.json().await.map_err(|source| FetchError::Decode {
+                    url: url.to_string(),
+                    source,
+                });
             }
             Ok(resp) => {
-                if attempt == retry_limit {
-                    return Err(anyhow::anyhow!("HTTP {} after {} attempts", resp.status(), retry_limit));
+                let status = resp.status();
+                if !retryable_status(status) || last {
+                    return Err(FetchError::Status {
+                        url: url.to_string(),
+                        status,
+                        attempts: attempt,
+                    });
                 }
+                let wait = retry_after(resp.headers()).unwrap_or(wait);
+                (wait, format!("HTTP {}", status.as_u16()))
             }
-            Err(e) => {
-                let is_last_attempt = attempt == retry_limit;
-                
-                if is_network_error(&e) && !is_last_attempt {
-                    let delay_ms = retry_delay_ms * 2u64.pow(attempt - 1);
-                    eprintln!(
-                        "⚠️  Network error (attempt {}/{}), retrying in {:.1}s...",
-                        attempt,
-                        retry_limit,
-                        delay_ms as f64 / 1000.0
-                    );
-                    tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                    continue;
-                }
-                
-                if is_last_attempt && is_network_error(&e) {
-                    eprintln!(
-                        "❌ Network timeout after {} attempts - {}",
-                        retry_limit,
-                        e
-                    );
-                }
-                return Err(e.into());
+            Err(e) if e.is_timeout() && last => {
+                Logger::error(&format!("Network timeout after {} attempts - {}", attempt, e));
+                return Err(FetchError::Timeout {
+                    url: url.to_string(),
+                    attempts: attempt,
+                });
             }
-        }
+            Err(e) if !is_network_error(&e) || last => {
+                return Err(FetchError::Network {
+                    url: url.to_string(),
+                    attempts: attempt,
+                    source: e,
+                });
+            }
+            Err(e) if e.is_timeout() => (wait, "Timeout".to_string()),
+            Err(_) => (wait, "Network error".to_string()),
+        };
+        Logger::warning(&format!(
+            "{} (attempt {}/{}), retrying in {:.1}s...",
+            cause,
+            attempt,
+            attempts,
+            wait.as_secs_f64()
+        ));
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, HeaderMap};
+    use axum::response::IntoResponse;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    const TEST_DELAY: Duration = Duration::from_millis(1);
+
+    /// A local server counting the requests it gets and answering each with `respond`.
+    async fn mock<F, R>(respond: F) -> (String, Arc<AtomicU32>)
+    where
+        F: Fn(u32) -> R + Clone + Send + Sync + 'static,
+        R: IntoResponse,
+    {
+        let hits = Arc::new(AtomicU32::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                let response = respond(n).into_response();
+                async move { response }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, hits)
+    }
+
+    async fn fetch(url: &str, timeout_ms: u64) -> Result<serde_json::Value, FetchError> {
+        fetch_with_backoff(&Client::new(), url, timeout_ms, 3, TEST_DELAY).await
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_one_succeeds() {
+        let (url, hits) = mock(|n| match n {
+            1 | 2 => (StatusCode::SERVICE_UNAVAILABLE, "busy").into_response(),
+            _ => axum::Json(serde_json::json!({ "ok": true })).into_response(),
+        })
+        .await;
+        assert_eq!(fetch(&url, 2_000).await.unwrap()["ok"], true);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limits_use_every_attempt_and_keep_the_status() {
+        let (url, hits) = mock(|_| StatusCode::TOO_MANY_REQUESTS).await;
+        let err = fetch(&url, 2_000).await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert!(err.is_transient());
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_waited_out() {
+        let (url, hits) = mock(|n| match n {
+            1 => (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, "1")],
+                "slow down",
+            )
+                .into_response(),
+            _ => axum::Json(serde_json::json!([])).into_response(),
+        })
+        .await;
+        let started = Instant::now();
+        fetch(&url, 2_000).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn other_client_errors_fail_fast() {
+        let (url, hits) = mock(|_| StatusCode::NOT_FOUND).await;
+        let err = fetch(&url, 2_000).await.unwrap_err();
+        assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+        assert!(!err.is_transient());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retried_then_reported_as_such() {
+        let hits = Arc::new(AtomicU32::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "late"
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let err = fetch(&url, 50).await.unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn undecodable_bodies_are_not_retried() {
+        let (url, hits) = mock(|_| "<html>not json</html>").await;
+        let err = fetch(&url, 2_000).await.unwrap_err();
+        assert!(matches!(err, FetchError::Decode { .. }), "{}", err);
+        assert!(!err.is_transient());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        let base = Duration::from_secs(1);
+        assert_eq!(backoff(base, 1, 0.0), Duration::from_secs(1));
+        assert_eq!(backoff(base, 3, 0.0), Duration::from_secs(4));
+        assert_eq!(backoff(base, 3, 0.5), Duration::from_secs(5));
+        assert_eq!(backoff(base, 40, 0.0), MAX_RETRY_DELAY);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(header::RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(MAX_RETRY_DELAY));
+        headers.insert(
+            header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
     }
-    
-    Err(anyhow::anyhow!("Failed after {} attempts", retry_limit))
 }
@@ -0,0 +1,100 @@
+use crate::config::EnvConfig;
+use crate::metadata_cache::parse_gamma;
+use crate::types::MarketMetadata;
+use crate::utils::{fetch_data, FetchError};
+
+/// The Polymarket Gamma API (`GAMMA_API_URL`): a market's record with its tick size, minimum
+/// order size, neg-risk flag, end date and liquidity. Calls go through `fetch_data`; most
+/// lookups should go through `metadata_cache`, which keeps what this returns for a TTL.
+#[derive(Clone)]
+pub struct GammaClient {
+    http: reqwest::Client,
+    base_url: String,
+    timeout_ms: u64,
+    retry_limit: u32,
+}
+
+impl GammaClient {
+    pub fn new(http: reqwest::Client, base_url: &str, timeout_ms: u64, retry_limit: u32) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeout_ms,
+            retry_limit,
+        }
+    }
+
+    /// One attempt per lookup: metadata only refines an order, so a slow Gamma shouldn't hold
+    /// the copy up through a round of retries.
+    pub fn from_config(config: &EnvConfig, http: &reqwest::Client) -> Self {
+        Self::new(http.clone(), &config.gamma_api_url, config.request_timeout_ms, 1)
+    }
+
+    /// The market with `condition_id`, `None` when Gamma doesn't know it.
+    pub async fn market(&self, condition_id: &str) -> Result<Option<MarketMetadata>, FetchError> {
+        self.first(&format!("markets?condition_ids={}", condition_id))
+            .await
+    }
+
+    /// The market whose slug is `slug`, as in its polymarket.com URL.
+    pub async fn market_by_slug(&self, slug: &str) -> Result<Option<MarketMetadata>, FetchError> {
+        self.first(&format!("markets?slug={}", slug)).await
+    }
+
+    async fn first(&self, path: &str) -> Result<Option<MarketMetadata>, FetchError> {
+        let url = format!("{}/{}", self.base_url, path);
+        let data = fetch_data(&self.http, &url, self.timeout_ms, self.retry_limit).await?;
+        Ok(data
+            .as_array()
+            .and_then(|markets| markets.first())
+            .and_then(parse_gamma))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use std::collections::HashMap;
+
+    /// A Gamma serving one fine-tick neg-risk market, found by condition id or slug.
+    async fn mock() -> GammaClient {
+        async fn markets(Query(q): Query<HashMap<String, String>>) -> axum::Json<serde_json::Value> {
+            let wanted = q.get("condition_ids") == Some(&"0x77aa".to_string())
+                || q.get("slug") == Some(&"snow-in-miami-on-christmas".to_string());
+            axum::Json(if wanted {
+                serde_json::json!([{
+                    "conditionId": "0x77aa",
+                    "slug": "snow-in-miami-on-christmas",
+                    "endDate": "2025-12-26T05:00:00Z",
+                    "liquidityNum": 1520.5,
+                    "negRisk": true,
+                    "orderPriceMinTickSize": 0.001,
+                    "orderMinSize": 5
+                }])
+            } else {
+                serde_json::json!([])
+            })
+        }
+        let app = axum::Router::new().route("/markets", axum::routing::get(markets));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        GammaClient::new(reqwest::Client::new(), &url, 2_000, 1)
+    }
+
+    #[tokio::test]
+    async fn markets_are_found_by_condition_id_or_slug() {
+        let gamma = mock().await;
+        let market = gamma.market("0x77aa").await.unwrap().unwrap();
+        assert_eq!(market.tick_size, Some(0.001));
+        assert_eq!(market.min_order_size, Some(5.0));
+        assert_eq!(market.end_date.as_deref(), Some("2025-12-26T05:00:00Z"));
+        assert_eq!(market.liquidity_usd, Some(1520.5));
+        assert!(market.neg_risk);
+
+        let by_slug = gamma.market_by_slug("snow-in-miami-on-christmas").await;
+        assert_eq!(by_slug.unwrap(), Some(market));
+        assert_eq!(gamma.market("0xunknown").await.unwrap(), None);
+    }
+}
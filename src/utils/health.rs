@@ -1,5 +1,17 @@
+use alloy::providers::Provider;
+use alloy::signers::local::PrivateKeySigner;
 use anyhow::Result;
 use serde::Serialize;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::config::{EnvConfig, MonitorMode};
+use crate::utils::{create_clob_client, get_usdc_balance, DataApiClient, RpcClient};
+use crate::{ctf_approval, usdc_approval};
+
+/// Below this much POL the signer can't be counted on to pay for an approval.
+const LOW_GAS_POL: f64 = 0.05;
 
 #[derive(Debug, Serialize)]
 pub struct HealthCheckResult {
@@ -8,11 +20,42 @@ pub struct HealthCheckResult {
     pub timestamp: i64,
 }
 
+impl HealthCheckResult {
+    /// `name: message` for each check in error, for the degraded-startup notification.
+    pub fn failures(&self) -> Vec<String> {
+        let c = &self.checks;
+        [
+            ("RPC", &c.rpc.status, &c.rpc.message),
+            ("Balance", &c.balance.status, &c.balance.message),
+            ("Polymarket API", &c.polymarket_api.status, &c.polymarket_api.message),
+            ("CTF approval", &c.ctf_approval.status, &c.ctf_approval.message),
+            ("USDC allowance", &c.usdc_allowance.status, &c.usdc_allowance.message),
+            ("CLOB auth", &c.clob_auth.status, &c.clob_auth.message),
+            ("RTDS WebSocket", &c.websocket.status, &c.websocket.message),
+            ("Gas (POL)", &c.gas.status, &c.gas.message),
+        ]
+        .into_iter()
+        .filter(|(_, status, _)| status.as_str() == "error")
+        .map(|(name, _, message)| format!("{}: {}", name, message))
+        .collect()
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct HealthChecks {
     pub rpc: CheckResult,
     pub balance: BalanceCheckResult,
     pub polymarket_api: CheckResult,
+    /// Outcome token (CTF) approvals needed to SELL; a warning, never unhealthy on its own.
+    pub ctf_approval: CheckResult,
+    /// USDC allowance toward the exchanges, needed to BUY.
+    pub usdc_allowance: CheckResult,
+    /// CLOB API key derivation with `PRIVATE_KEY`.
+    pub clob_auth: CheckResult,
+    /// RTDS, where the monitor hears about the traders' trades.
+    pub websocket: CheckResult,
+    /// The signer's POL, which pays for approvals; a warning, never unhealthy on its own.
+    pub gas: CheckResult,
 }
 
 #[derive(Debug, Serialize)]
@@ -29,15 +72,71 @@ pub struct BalanceCheckResult {
     pub balance: Option<f64>,
 }
 
+/// The full system check: gathers the balance, data API, approval and allowance results and
+/// runs `perform_health_check` with them.
+pub async fn run_health_check(config: &EnvConfig) -> HealthCheckResult {
+    let balance = get_usdc_balance(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+    )
+    .await;
+    let polymarket_ok = DataApiClient::from_config(config, &reqwest::Client::new())
+        .ping()
+        .await
+        .is_ok();
+    let ctf_missing = ctf_approval::missing_approvals(&config.rpc_url, &config.proxy_wallet).await;
+    let usdc_short = usdc_approval::short_allowances(
+        &config.rpc_url,
+        &config.usdc_contract_address,
+        &config.proxy_wallet,
+        config.min_usdc_allowance_usd,
+    )
+    .await;
+    perform_health_check(config, balance, polymarket_ok, ctf_missing, usdc_short).await
+}
+
+/// Runs the RPC, CLOB auth, WebSocket and gas checks, each under its own `REQUEST_TIMEOUT_MS`,
+/// and folds in the results the caller already has.
 pub async fn perform_health_check(
-    rpc_url: &str,
+    config: &EnvConfig,
     balance: Result<f64, anyhow::Error>,
     polymarket_ok: bool,
+    ctf_missing: Result<Vec<&'static str>>,
+    usdc_short: Result<Vec<(&'static str, f64)>>,
 ) -> HealthCheckResult {
-    let (rpc_status, rpc_msg) = match check_rpc(rpc_url).await {
-        Ok(()) => ("ok".to_string(), "RPC endpoint responding".to_string()),
+    let limit = Duration::from_millis(config.request_timeout_ms);
+    let (rpc, auth, websocket, gas) = tokio::join!(
+        within(limit, check_rpc(&config.rpc_url)),
+        within(limit, async { create_clob_client(config).await.map(|_| ()) }),
+        within(limit, check_websocket(&config.rtds_url)),
+        within(limit, signer_gas(config)),
+    );
+    let (rpc_status, rpc_msg) = match rpc {
+        Ok(endpoint) => ("ok".to_string(), format!("RPC endpoint responding: {}", endpoint)),
         Err(e) => ("error".to_string(), format!("RPC check failed: {}", e)),
     };
+    let (auth_status, auth_msg) = match auth {
+        Ok(()) => ("ok", "Authenticated with PRIVATE_KEY".to_string()),
+        Err(e) => ("error", format!("CLOB authentication failed: {:#}", e)),
+    };
+    let (ws_status, ws_msg) = match (websocket, config.monitor_mode) {
+        (_, MonitorMode::Polling) => ("ok", "Not used (MONITOR_MODE=POLLING)".to_string()),
+        (Ok(()), _) => ("ok", "RTDS reachable".to_string()),
+        (Err(e), MonitorMode::Auto) => (
+            "warning",
+            format!("RTDS unreachable, trades will be polled: {:#}", e),
+        ),
+        (Err(e), MonitorMode::Websocket) => ("error", format!("RTDS unreachable: {:#}", e)),
+    };
+    let (gas_status, gas_msg) = match gas {
+        Ok(pol) if pol >= LOW_GAS_POL => ("ok", format!("{:.4} POL for approvals", pol)),
+        Ok(pol) => (
+            "warning",
+            format!("{:.4} POL: approvals sent from this key will fail", pol),
+        ),
+        Err(e) => ("warning", format!("Gas balance check failed: {:#}", e)),
+    };
 
     let (balance_status, balance_msg, balance_val) = match balance {
         Ok(b) if b > 0.0 => {
@@ -58,8 +157,42 @@ pub async fn perform_health_check(
         "API check failed"
     };
 
-    let healthy =
-        rpc_status == "ok" && balance_status != "error" && pm_status == "ok";
+    let (ctf_status, ctf_msg) = match ctf_missing {
+        Ok(missing) if missing.is_empty() => {
+            ("ok", "Outcome tokens approved for the exchanges".to_string())
+        }
+        Ok(missing) => (
+            "warning",
+            format!(
+                "Not approved for {}: SELLs will fail (AUTO_APPROVE_CTF=true fixes EOA wallets)",
+                missing.join(", ")
+            ),
+        ),
+        Err(e) => ("warning", format!("Approval check failed: {}", e)),
+    };
+
+    let (usdc_status, usdc_msg) = match usdc_short {
+        Ok(short) if short.is_empty() => ("ok", "USDC approved for the exchanges".to_string()),
+        Ok(short) => (
+            "error",
+            format!(
+                "Not approved for {}: BUYs will fail (EOA wallets are approved at startup)",
+                short
+                    .iter()
+                    .map(|(name, allowance)| format!("{} (${:.2})", name, allowance))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ),
+        Err(e) => ("warning", format!("Allowance check failed: {}", e)),
+    };
+
+    let healthy = rpc_status == "ok"
+        && balance_status != "error"
+        && pm_status == "ok"
+        && usdc_status != "error"
+        && auth_status == "ok"
+        && ws_status != "error";
 
     HealthCheckResult {
         healthy,
@@ -77,29 +210,61 @@ pub async fn perform_health_check(
                 status: pm_status.to_string(),
                 message: pm_msg.to_string(),
             },
+            ctf_approval: CheckResult {
+                status: ctf_status.to_string(),
+                message: ctf_msg,
+            },
+            usdc_allowance: CheckResult {
+                status: usdc_status.to_string(),
+                message: usdc_msg,
+            },
+            clob_auth: CheckResult {
+                status: auth_status.to_string(),
+                message: auth_msg,
+            },
+            websocket: CheckResult {
+                status: ws_status.to_string(),
+                message: ws_msg,
+            },
+            gas: CheckResult {
+                status: gas_status.to_string(),
+                message: gas_msg,
+            },
         },
         timestamp: chrono::Utc::now().timestamp_millis(),
     }
 }
 
-async fn check_rpc(rpc_url: &str) -> Result<()> {
-    let body = serde_json::json!({
-        "jsonrpc": "2.0",
-        "method": "eth_blockNumber",
-        "params": [],
-        "id": 1
-    });
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(rpc_url)
-        .json(&body)
-        .timeout(std::time::Duration::from_secs(5))
-        .send()
+/// `check`, or an error once `limit` passes, so one hung endpoint can't stall the rest.
+async fn within<T>(limit: Duration, check: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(limit, check)
+        .await
+        .map_err(|_| anyhow::anyhow!("no answer within {}ms", limit.as_millis()))?
+}
+
+/// Opens a WebSocket to `url` and closes it again.
+async fn check_websocket(url: &str) -> Result<()> {
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await?;
+    ws.close(None).await.ok();
+    Ok(())
+}
+
+/// The signing key's native balance, in POL.
+async fn signer_gas(config: &EnvConfig) -> Result<f64> {
+    let signer = PrivateKeySigner::from_str(&format!("0x{}", config.private_key))
+        .map_err(|e| anyhow::anyhow!("Invalid private key: {}", e))?;
+    let wei = RpcClient::from_config(config)?
+        .provider()
+        .get_balance(signer.address())
         .await?;
-    let json: serde_json::Value = resp.json().await?;
-    if json.get("result").is_some() {
-        Ok(())
-    } else {
-        anyhow::bail!("Invalid RPC response")
+    Ok(f64::from(wei) / 1e18)
+}
+
+/// Asks for the block number; the endpoint that answered, as it may have failed over.
+async fn check_rpc(rpc_url: &str) -> Result<String> {
+    let rpc = RpcClient::for_url(rpc_url)?;
+    if let Err(e) = rpc.provider().get_block_number().await {
+        anyhow::bail!("{} (last tried {})", e, rpc.active_endpoint());
     }
+    Ok(rpc.active_endpoint())
 }
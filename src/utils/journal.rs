@@ -0,0 +1,788 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use crate::alerts::{self, AlertKind};
+use crate::config::{EnvConfig, SizingStep};
+use crate::position_action::PositionAction;
+use crate::prefetch::PrefetchReport;
+use crate::skip_reason::SkipReason;
+use crate::supervisor::{supervisor, STOP_LAST};
+use crate::utils::{Logger, MarketContext, RemoteJournal, REMOTE_BATCH_SIZE};
+
+/// How often a partial batch is pushed to the remote journal.
+const REMOTE_FLUSH_INTERVAL_SECS: u64 = 10;
+/// How long a live row waits for buffer space before its fallback applies.
+const CRITICAL_SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+static DROPPED_ROWS: AtomicU64 = AtomicU64::new(0);
+static DISK_FULL: AtomicBool = AtomicBool::new(false);
+static ACTIVE: OnceLock<Journal> = OnceLock::new();
+
+/// Bumped whenever fields are added; new fields must be optional so older rows stay readable.
+/// Each bump adds its field to `ADDED_FIELDS`.
+pub const JOURNAL_SCHEMA_VERSION: u32 = 17;
+
+/// The field each schema version added and the JSON value rows written before it get.
+const ADDED_FIELDS: &[(u32, &str, &str)] = &[
+    (2, "market", "null"),
+    (3, "degraded_balance", "false"),
+    (4, "rebalance_id", "null"),
+    (5, "time_to_end_secs", "null"),
+    (6, "rule_trace", "[]"),
+    (7, "trader_member", "null"),
+    (8, "shadow", "false"),
+    (9, "sizing_steps", "[]"),
+    (10, "trader_usd_reported", "null"),
+    (11, "trader_usd_derived", "null"),
+    (12, "manual", "false"),
+    (13, "prefetch", "null"),
+    (14, "skip_reason", "null"),
+    (15, "position_action", "null"),
+    (16, "order_ids", "[]"),
+    (17, "sizing_reasoning", "null"),
+];
+
+/// Parses one journal line from any schema version. Older rows get the fields added since
+/// and are upgraded to the current version; rows from a newer build keep their version and
+/// lose only the fields this build doesn't know.
+pub fn parse_row(line: &str) -> anyhow::Result<JournalEntry> {
+    let mut row: serde_json::Value = serde_json::from_str(line)?;
+    let obj = row
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("journal row is not an object"))?;
+    let version = obj
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    if version < JOURNAL_SCHEMA_VERSION {
+        for (added_in, field, default) in ADDED_FIELDS {
+            if *added_in > version && !obj.contains_key(*field) {
+                obj.insert(field.to_string(), serde_json::from_str(default)?);
+            }
+        }
+        obj.insert("schema_version".to_string(), JOURNAL_SCHEMA_VERSION.into());
+    }
+    Ok(serde_json::from_value(row)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalStatus {
+    Executed,
+    Skipped,
+}
+
+/// One line of the trade journal: a copy that executed or a signal that was skipped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub schema_version: u32,
+    pub timestamp: i64,
+    pub status: JournalStatus,
+    /// Logical trader: the `TRADER_GROUPS` id, or the address for ungrouped traders.
+    pub trader: String,
+    /// The member address that traded, when `trader` is a group id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader_member: Option<String>,
+    pub slug: Option<String>,
+    pub condition_id: Option<String>,
+    pub asset: Option<String>,
+    pub side: Option<String>,
+    /// What the trade did to the trader's position (open, add, reduce, close).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position_action: Option<PositionAction>,
+    /// The trader's USD amount sizing used (`USDC_SIZE_PREFERENCE`).
+    pub trader_usd: Option<f64>,
+    /// `usdcSize` as sent in the activity payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader_usd_reported: Option<f64>,
+    /// size × price from the activity payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trader_usd_derived: Option<f64>,
+    pub my_usd: f64,
+    pub my_tokens: f64,
+    pub tx_hash: Option<String>,
+    pub reason: Option<String>,
+    /// Code of the `SkipReason` on skipped rows; `reason` is its text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<SkipReason>,
+    /// Sized against a cached balance while the RPC was down.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded_balance: bool,
+    /// Present when `JOURNAL_MARKET_CONTEXT=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market: Option<MarketContext>,
+    /// Shared by every leg of a neg-risk rebalance plan.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rebalance_id: Option<String>,
+    /// Seconds from the copy to the market's scheduled end, on executed copies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_to_end_secs: Option<i64>,
+    /// Skip rules evaluated for this trade, as `rule:allow|modify|skip` in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rule_trace: Vec<String>,
+    /// Decision of the `SHADOW_CONFIG_FILE` config for the same signal; never traded.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shadow: bool,
+    /// Input and output of every sizing stage, on executed BUYs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sizing_steps: Vec<SizingStep>,
+    /// Copy started by hand (`manual_copy`) instead of by a trader signal.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manual: bool,
+    /// Which of a new market's lookups made `MARKET_PREFETCH_DEADLINE_MS`, with latencies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefetch: Option<PrefetchReport>,
+    /// CLOB ids of the orders that filled, on executed copies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order_ids: Vec<String>,
+    /// How the copy was sized (`OrderSizeCalculation::reasoning`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sizing_reasoning: Option<String>,
+}
+
+impl JournalEntry {
+    /// Executed live copies: losing one would hide a real position from every report.
+    fn is_critical(&self) -> bool {
+        self.status == JournalStatus::Executed && !self.shadow
+    }
+
+    /// Average fill price, on rows that filled.
+    pub fn fill_price(&self) -> Option<f64> {
+        (self.my_tokens > 0.0).then(|| self.my_usd / self.my_tokens)
+    }
+}
+
+/// Columns of the `JOURNAL_CSV_PATH` mirror.
+pub const CSV_HEADER: &str = "timestamp,status,trader,trader_member,slug,condition_id,side,\
+trader_usd,my_usd,my_tokens,fill_price,order_ids,tx_hash,skip_reason,reason,sizing_reasoning";
+
+/// Quotes `field` when it holds a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `entry` as one `CSV_HEADER` line.
+pub fn csv_line(entry: &JournalEntry) -> String {
+    let number = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+    let status = match entry.status {
+        JournalStatus::Executed => "executed",
+        JournalStatus::Skipped => "skipped",
+    };
+    [
+        entry.timestamp.to_string(),
+        status.to_string(),
+        entry.trader.clone(),
+        entry.trader_member.clone().unwrap_or_default(),
+        entry.slug.clone().unwrap_or_default(),
+        entry.condition_id.clone().unwrap_or_default(),
+        entry.side.clone().unwrap_or_default(),
+        number(entry.trader_usd),
+        entry.my_usd.to_string(),
+        entry.my_tokens.to_string(),
+        number(entry.fill_price()),
+        entry.order_ids.join(" "),
+        entry.tx_hash.clone().unwrap_or_default(),
+        entry.skip_reason.as_ref().map(|r| r.code()).unwrap_or_default(),
+        entry.reason.clone().unwrap_or_default(),
+        entry.sizing_reasoning.clone().unwrap_or_default(),
+    ]
+    .iter()
+    .map(|f| csv_field(f))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+/// Where journal rows go on disk: `TRADE_LOG_PATH`, the optional CSV mirror, and whether both
+/// move aside at the first write of a new UTC day (`JOURNAL_ROTATE_DAILY`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalFiles {
+    pub path: PathBuf,
+    pub csv_path: Option<PathBuf>,
+    pub rotate_daily: bool,
+}
+
+impl JournalFiles {
+    /// Only the JSONL journal at `path`, never rotated.
+    pub fn jsonl(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            csv_path: None,
+            rotate_daily: false,
+        }
+    }
+
+    pub fn from_config(config: &EnvConfig) -> Self {
+        Self {
+            path: PathBuf::from(&config.trade_log_path),
+            csv_path: config.journal_csv_path.as_ref().map(PathBuf::from),
+            rotate_daily: config.journal_rotate_daily,
+        }
+    }
+
+    /// Appends `entry` to the journal and the CSV mirror, first rotating files last written
+    /// before `now`'s UTC day.
+    fn append(&self, entry: &JournalEntry, now: i64) -> anyhow::Result<()> {
+        if self.rotate_daily {
+            rotate_if_stale(&self.path, now)?;
+        }
+        append_line(&self.path, &serde_json::to_string(entry)?)?;
+        if let Some(csv_path) = &self.csv_path {
+            if self.rotate_daily {
+                rotate_if_stale(csv_path, now)?;
+            }
+            if std::fs::metadata(csv_path).map_or(true, |m| m.len() == 0) {
+                append_line(csv_path, CSV_HEADER)?;
+            }
+            append_line(csv_path, &csv_line(entry))?;
+        }
+        Ok(())
+    }
+}
+
+/// A trading state transition, as the state log beside the journal records it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub timestamp: i64,
+    pub from: String,
+    pub to: String,
+}
+
+/// The trading state log for the journal at `path`: `trades.jsonl` -> `trades.jsonl.state.jsonl`.
+pub fn state_log_path(path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.state.jsonl", path.display()))
+}
+
+/// Appends `transition` to the state log of the journal started by `Journal::spawn`, if any.
+/// Written directly rather than through the buffer: transitions are rare and never dropped.
+pub fn record_transition(transition: &StateTransition) {
+    let Some(journal) = ACTIVE.get() else {
+        return;
+    };
+    let path = state_log_path(&journal.files.path);
+    let written = serde_json::to_string(transition)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| append_line(&path, &line));
+    if let Err(e) = written {
+        Logger::warning(&format!("Failed to journal the trading state change: {}", e));
+    }
+}
+
+/// Where `path` goes when rotated out on `day`: `trades.jsonl` -> `trades.2026-10-16.jsonl`.
+pub fn rotated_path(path: &Path, day: chrono::NaiveDate) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, day, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, day),
+    };
+    path.with_file_name(name)
+}
+
+/// Files `path` was rotated into, oldest first.
+pub fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut days: Vec<(chrono::NaiveDate, PathBuf)> = read_dir
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let stem = path.file_stem()?.to_string_lossy().into_owned();
+            let day = match path.extension() {
+                Some(ext) => name
+                    .strip_prefix(&format!("{}.", stem))?
+                    .strip_suffix(&format!(".{}", ext.to_string_lossy()))?
+                    .to_string(),
+                None => name.strip_prefix(&format!("{}.", stem))?.to_string(),
+            };
+            let day = chrono::NaiveDate::parse_from_str(&day, "%Y-%m-%d").ok()?;
+            Some((day, e.path()))
+        })
+        .collect();
+    days.sort();
+    days.into_iter().map(|(_, p)| p).collect()
+}
+
+/// Moves `path` aside when it was last written on a UTC day before `now`'s.
+fn rotate_if_stale(path: &Path, now: i64) -> anyhow::Result<()> {
+    let Ok(meta) = std::fs::metadata(path) else {
+        return Ok(());
+    };
+    let written = chrono::DateTime::<chrono::Utc>::from(meta.modified()?).date_naive();
+    let today = chrono::DateTime::from_timestamp(now, 0)
+        .unwrap_or_default()
+        .date_naive();
+    let target = rotated_path(path, written);
+    if written < today && !target.exists() {
+        std::fs::rename(path, target)?;
+    }
+    Ok(())
+}
+
+/// Executed and skipped rows of one day, for the shutdown summary.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalDay {
+    pub executed: usize,
+    pub skipped: usize,
+    pub bought_usd: f64,
+    pub sold_usd: f64,
+    /// Skipped rows by `SkipReason` code.
+    pub skips: BTreeMap<String, usize>,
+}
+
+impl JournalDay {
+    /// Totals over the live (non-shadow) rows from `since` on.
+    pub fn from_entries(entries: &[JournalEntry], since: i64) -> Self {
+        let mut day = Self::default();
+        for entry in entries.iter().filter(|e| !e.shadow && e.timestamp >= since) {
+            match entry.status {
+                JournalStatus::Executed => {
+                    day.executed += 1;
+                    if entry.side.as_deref() == Some("SELL") {
+                        day.sold_usd += entry.my_usd;
+                    } else {
+                        day.bought_usd += entry.my_usd;
+                    }
+                }
+                JournalStatus::Skipped => {
+                    day.skipped += 1;
+                    let code = entry
+                        .skip_reason
+                        .as_ref()
+                        .map(|r| r.code())
+                        .unwrap_or_else(|| "other".to_string());
+                    *day.skips.entry(code).or_default() += 1;
+                }
+            }
+        }
+        day
+    }
+
+    /// Today's (UTC) rows in the live journal at `path`; rotation leaves earlier days behind.
+    pub fn today(path: &Path) -> anyhow::Result<Self> {
+        let since = chrono::Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .map(|t| t.and_utc().timestamp())
+            .unwrap_or_default();
+        let entries = crate::utils::read_journal_file(path)?;
+        Ok(Self::from_entries(&entries, since))
+    }
+
+    /// E.g. `Journal today: 12 copied ($140.00 bought, $35.50 sold), 30 skipped (stale 20,
+    /// filtered:price_band 10)`.
+    pub fn describe(&self) -> String {
+        let mut line = format!(
+            "Journal today: {} copied (${:.2} bought, ${:.2} sold), {} skipped",
+            self.executed, self.bought_usd, self.sold_usd, self.skipped
+        );
+        if !self.skips.is_empty() {
+            let mut skips: Vec<(&String, &usize)> = self.skips.iter().collect();
+            skips.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            let parts: Vec<String> = skips.iter().map(|(c, n)| format!("{} {}", c, n)).collect();
+            line.push_str(&format!(" ({})", parts.join(", ")));
+        }
+        line
+    }
+}
+
+enum JournalMsg {
+    Entry(Box<JournalEntry>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the journal writer task. The buffer holds at most `JOURNAL_BUFFER_ROWS` rows;
+/// when it is full, shadow rows are dropped (see `dropped_rows`), live rows wait briefly, and
+/// executed rows that still don't fit are written directly.
+#[derive(Clone)]
+pub struct Journal {
+    tx: mpsc::Sender<JournalMsg>,
+    files: Arc<JournalFiles>,
+}
+
+impl Journal {
+    /// Starts the local writer and, when `remote` is set, a task that forwards every
+    /// locally written row to it in batches.
+    pub fn spawn(files: JournalFiles, remote: Option<RemoteJournal>, capacity: usize) -> Self {
+        let (tx, rx) = mpsc::channel::<JournalMsg>(capacity.max(1));
+        let rx = Arc::new(Mutex::new(rx));
+        let remote_tx = remote.map(|remote| {
+            let (remote_tx, remote_rx) = mpsc::channel::<JournalEntry>(capacity.max(1));
+            let remote_rx = Arc::new(Mutex::new(remote_rx));
+            supervisor().spawn("journal-remote", STOP_LAST, 5, move || {
+                forward_entries(remote.clone(), remote_rx.clone())
+            });
+            remote_tx
+        });
+        let files = Arc::new(files);
+        let writer_files = files.clone();
+        supervisor().spawn("journal-writer", STOP_LAST, 5, move || {
+            write_entries(writer_files.clone(), rx.clone(), remote_tx.clone())
+        });
+        let journal = Self { tx, files };
+        let _ = ACTIVE.set(journal.clone());
+        journal
+    }
+
+    pub async fn record(&self, entry: JournalEntry) {
+        if entry.shadow {
+            if self
+                .tx
+                .try_send(JournalMsg::Entry(Box::new(entry)))
+                .is_err()
+            {
+                DROPPED_ROWS.fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+        let entry = match self
+            .tx
+            .send_timeout(JournalMsg::Entry(Box::new(entry)), CRITICAL_SEND_TIMEOUT)
+            .await
+        {
+            Ok(()) => return,
+            Err(SendTimeoutError::Timeout(JournalMsg::Entry(entry)))
+            | Err(SendTimeoutError::Closed(JournalMsg::Entry(entry))) => *entry,
+            Err(_) => return,
+        };
+        if !entry.is_critical() {
+            DROPPED_ROWS.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        // The writer is stuck or gone: write the row here rather than lose it. It is not
+        // forwarded to the remote sink; `journal backfill-remote` can resend it.
+        Logger::warning("Journal buffer full - writing executed row directly");
+        let files = self.files.clone();
+        let now = chrono::Utc::now().timestamp();
+        let result = tokio::task::spawn_blocking(move || files.append(&entry, now))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|r| r);
+        if let Err(e) = result {
+            report_write_error(&e);
+        }
+    }
+
+    /// Rows buffered for the writer and not yet written.
+    pub fn queued(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Waits until every row recorded before the call is on disk.
+    pub async fn flush(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.tx.send(JournalMsg::Flush(done_tx)).await.is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// Flushes the journal started by `Journal::spawn`, if any. Awaited on shutdown before the
+/// writer task is stopped, and by anything about to read the journal file.
+pub async fn flush_journal() {
+    if let Some(journal) = ACTIVE.get() {
+        journal.flush().await;
+    }
+}
+
+/// Rows dropped because the buffer was full (shadow rows, then skipped rows that timed out).
+pub fn dropped_rows() -> u64 {
+    DROPPED_ROWS.load(Ordering::Relaxed)
+}
+
+fn report_write_error(e: &anyhow::Error) {
+    let disk_full = e
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|io| io.kind() == std::io::ErrorKind::StorageFull);
+    if !disk_full {
+        Logger::warning(&format!("Failed to write trade journal: {}", e));
+    } else if !DISK_FULL.swap(true, Ordering::SeqCst) {
+        let line = format!("disk full - trade journal rows are not being written ({})", e);
+        Logger::error(&format!("CRITICAL: {}", line));
+        alerts::critical(AlertKind::DiskFull, &line);
+    }
+}
+
+async fn write_entries(
+    files: Arc<JournalFiles>,
+    rx: Arc<Mutex<mpsc::Receiver<JournalMsg>>>,
+    remote_tx: Option<mpsc::Sender<JournalEntry>>,
+) -> anyhow::Result<()> {
+    let mut rx = rx.lock().await;
+    while let Some(msg) = rx.recv().await {
+        let entry = match msg {
+            JournalMsg::Entry(entry) => *entry,
+            JournalMsg::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+        if let Some(remote_tx) = &remote_tx {
+            // A full remote queue means the endpoint is far behind; the row is still on
+            // disk for `journal backfill-remote`.
+            let _ = remote_tx.try_send(entry.clone());
+        }
+        let files = files.clone();
+        let now = chrono::Utc::now().timestamp();
+        let result = tokio::task::spawn_blocking(move || files.append(&entry, now))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|r| r);
+        match result {
+            Ok(()) => {
+                if DISK_FULL.swap(false, Ordering::SeqCst) {
+                    Logger::success("Trade journal writable again");
+                }
+            }
+            Err(e) => report_write_error(&e),
+        }
+    }
+    Ok(())
+}
+
+/// Batches rows for the remote sink: a batch goes out when full or when the flush interval
+/// elapses, so a quiet bot still drains its spill file once the endpoint is back.
+async fn forward_entries(
+    remote: RemoteJournal,
+    rx: Arc<Mutex<mpsc::Receiver<JournalEntry>>>,
+) -> anyhow::Result<()> {
+    let mut rx = rx.lock().await;
+    let mut batch = Vec::new();
+    let mut ticker =
+        tokio::time::interval(std::time::Duration::from_secs(REMOTE_FLUSH_INTERVAL_SECS));
+    loop {
+        tokio::select! {
+            entry = rx.recv() => match entry {
+                Some(entry) => {
+                    batch.push(entry);
+                    if batch.len() >= REMOTE_BATCH_SIZE {
+                        remote.deliver(std::mem::take(&mut batch)).await;
+                    }
+                }
+                None => {
+                    remote.deliver(std::mem::take(&mut batch)).await;
+                    return Ok(());
+                }
+            },
+            _ = ticker.tick() => remote.deliver(std::mem::take(&mut batch)).await,
+        }
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_ROW: &str = r#"{"schema_version":1,"timestamp":1760000000,"status":"executed","trader":"0xabc","slug":"fed-cut","condition_id":"0xc1","asset":"123","side":"BUY","trader_usd":88.45,"my_usd":8.84,"my_tokens":15.25,"tx_hash":"0x9a","reason":null}"#;
+
+    #[test]
+    fn v1_row_is_upgraded_with_defaults() {
+        let entry = parse_row(V1_ROW).unwrap();
+        assert_eq!(entry.schema_version, JOURNAL_SCHEMA_VERSION);
+        assert_eq!(entry.status, JournalStatus::Executed);
+        assert!(entry.market.is_none());
+        assert!(!entry.degraded_balance);
+    }
+
+    #[test]
+    fn row_without_version_is_treated_as_v1() {
+        let line = V1_ROW.replace(r#""schema_version":1,"#, "");
+        assert_eq!(
+            parse_row(&line).unwrap().schema_version,
+            JOURNAL_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn current_row_round_trips() {
+        let mut entry = parse_row(V1_ROW).unwrap();
+        entry.reason = Some("balance".to_string());
+        let back = parse_row(&serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(back.reason.as_deref(), Some("balance"));
+        assert_eq!(back.schema_version, JOURNAL_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn newer_row_keeps_its_version_and_ignores_unknown_fields() {
+        let line = V1_ROW
+            .replace(r#""schema_version":1"#, r#""schema_version":99"#)
+            .replace(
+                r#""reason":null"#,
+                r#""reason":null,"from_the_future":true"#,
+            );
+        assert_eq!(parse_row(&line).unwrap().schema_version, 99);
+    }
+
+    #[test]
+    fn every_version_after_the_first_adds_a_field() {
+        let versions: Vec<u32> = ADDED_FIELDS.iter().map(|(v, _, _)| *v).collect();
+        assert_eq!(versions, (2..=JOURNAL_SCHEMA_VERSION).collect::<Vec<_>>());
+    }
+
+    fn read_rows(path: &std::path::Path) -> Vec<JournalEntry> {
+        std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| parse_row(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_buffer_drops_shadow_rows_and_writes_executed_ones_directly() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        // A writer that never drains: the one buffered row keeps the channel full.
+        let (tx, _rx) = mpsc::channel(1);
+        let journal = Journal {
+            tx,
+            files: Arc::new(JournalFiles::jsonl(&path)),
+        };
+        let mut queued = parse_row(V1_ROW).unwrap();
+        queued.status = JournalStatus::Skipped;
+        journal.record(queued).await;
+
+        let dropped_before = dropped_rows();
+        let mut shadow = parse_row(V1_ROW).unwrap();
+        shadow.shadow = true;
+        journal.record(shadow).await;
+        assert_eq!(dropped_rows(), dropped_before + 1);
+        assert!(read_rows(&path).is_empty());
+
+        // Waits out CRITICAL_SEND_TIMEOUT, then appends to the file itself.
+        journal.record(parse_row(V1_ROW).unwrap()).await;
+        let rows = read_rows(&path);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].status, JournalStatus::Executed);
+        assert!(!rows[0].shadow);
+    }
+
+    #[test]
+    fn csv_rows_quote_free_text_and_match_the_header() {
+        let mut entry = parse_row(V1_ROW).unwrap();
+        entry.order_ids = vec!["0xo1".to_string(), "0xo2".to_string()];
+        entry.sizing_reasoning = Some("10% of $88.45, \"capped\"".to_string());
+        let line = csv_line(&entry);
+        assert!(
+            line.starts_with("1760000000,executed,0xabc,,fed-cut,0xc1,BUY,88.45,8.84,15.25,"),
+            "{}",
+            line
+        );
+        assert!(line.contains(",0xo1 0xo2,0x9a,,,"), "{}", line);
+        let quoted = r#""10% of $88.45, ""capped""""#;
+        assert!(line.ends_with(quoted), "{}", line);
+        let columns = line.replace(quoted, "x").split(',').count();
+        assert_eq!(columns, CSV_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn daily_rotation_moves_yesterdays_files_aside_and_reads_keep_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = JournalFiles {
+            path: dir.path().join("trades.jsonl"),
+            csv_path: Some(dir.path().join("trades.csv")),
+            rotate_daily: true,
+        };
+        let yesterday = chrono::Utc::now() - chrono::Duration::days(1);
+        let mut old = parse_row(V1_ROW).unwrap();
+        old.tx_hash = Some("0xold".to_string());
+        files.append(&old, yesterday.timestamp()).unwrap();
+        for path in [&files.path, files.csv_path.as_ref().unwrap()] {
+            std::fs::File::options()
+                .append(true)
+                .open(path)
+                .unwrap()
+                .set_modified(yesterday.into())
+                .unwrap();
+        }
+
+        let mut new = parse_row(V1_ROW).unwrap();
+        new.tx_hash = Some("0xnew".to_string());
+        files.append(&new, chrono::Utc::now().timestamp()).unwrap();
+        let day = yesterday.date_naive();
+        assert_eq!(rotated_files(&files.path), vec![rotated_path(&files.path, day)]);
+        assert_eq!(read_rows(&files.path).len(), 1);
+        let csv = dir.path().join(format!("trades.{}.csv", day));
+        assert_eq!(std::fs::read_to_string(csv).unwrap().lines().count(), 2);
+        let today = std::fs::read_to_string(files.csv_path.as_ref().unwrap()).unwrap();
+        assert_eq!(today.lines().next(), Some(CSV_HEADER));
+
+        let hashes: Vec<Option<String>> = crate::utils::read_journal(&files.path)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.tx_hash)
+            .collect();
+        assert_eq!(hashes, [Some("0xold".to_string()), Some("0xnew".to_string())]);
+    }
+
+    #[test]
+    fn day_totals_count_copies_and_group_skips() {
+        let row = |timestamp: i64, status: JournalStatus, side: &str, usd: f64| JournalEntry {
+            timestamp,
+            status,
+            side: Some(side.to_string()),
+            my_usd: usd,
+            skip_reason: (status == JournalStatus::Skipped).then_some(SkipReason::Stale),
+            ..parse_row(V1_ROW).unwrap()
+        };
+        let mut shadow = row(200, JournalStatus::Executed, "BUY", 50.0);
+        shadow.shadow = true;
+        let entries = [
+            row(50, JournalStatus::Executed, "BUY", 99.0),
+            row(100, JournalStatus::Executed, "BUY", 10.0),
+            row(110, JournalStatus::Executed, "SELL", 4.5),
+            row(120, JournalStatus::Skipped, "BUY", 0.0),
+            row(130, JournalStatus::Skipped, "SELL", 0.0),
+            shadow,
+        ];
+        let day = JournalDay::from_entries(&entries, 100);
+        assert_eq!((day.executed, day.skipped), (2, 2));
+        assert_eq!((day.bought_usd, day.sold_usd), (10.0, 4.5));
+        assert_eq!(
+            day.describe(),
+            "Journal today: 2 copied ($10.00 bought, $4.50 sold), 2 skipped (stale 2)"
+        );
+    }
+
+    #[tokio::test]
+    async fn flush_returns_once_earlier_rows_are_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+        let (tx, rx) = mpsc::channel(16);
+        let files = Arc::new(JournalFiles::jsonl(&path));
+        tokio::spawn(write_entries(files.clone(), Arc::new(Mutex::new(rx)), None));
+        let journal = Journal { tx, files };
+        for _ in 0..3 {
+            journal.record(parse_row(V1_ROW).unwrap()).await;
+        }
+        journal.flush().await;
+        assert_eq!(read_rows(&path).len(), 3);
+    }
+}
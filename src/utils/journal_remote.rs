@@ -0,0 +1,292 @@
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::EnvConfig;
+use super::journal::{parse_row, rotated_files};
+use crate::utils::{state_path, JournalEntry, Logger};
+
+/// Rows per POST, both for live batches and when draining the spill file.
+pub const REMOTE_BATCH_SIZE: usize = 100;
+const REMOTE_ATTEMPTS: u32 = 3;
+const SPILL_FILE: &str = "journal_spill.jsonl";
+
+/// Secondary journal sink: POSTs JSON arrays of rows (each tagged with `instance_id`) to
+/// `JOURNAL_REMOTE_URL`. Batches that can't be delivered are spilled to disk and re-sent first
+/// once the endpoint recovers. The local journal stays authoritative.
+#[derive(Clone)]
+pub struct RemoteJournal {
+    http: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    instance_id: String,
+    spill_path: PathBuf,
+    /// First retry delay; doubles on each further attempt.
+    retry_delay: Duration,
+}
+
+impl RemoteJournal {
+    pub fn from_config(config: &EnvConfig) -> Option<Self> {
+        let url = config.journal_remote_url.clone()?;
+        Some(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_millis(config.request_timeout_ms))
+                .build()
+                .unwrap_or_default(),
+            url,
+            token: config.journal_remote_token.clone(),
+            instance_id: config.journal_instance_id.clone(),
+            spill_path: state_path(&config.state_dir, SPILL_FILE),
+            retry_delay: Duration::from_secs(2),
+        })
+    }
+
+    /// Sends one batch with a few retries.
+    pub async fn push(&self, entries: &[JournalEntry]) -> Result<()> {
+        let rows: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|e| {
+                let mut row = serde_json::to_value(e).unwrap_or_default();
+                if let Some(obj) = row.as_object_mut() {
+                    obj.insert("instance_id".to_string(), self.instance_id.clone().into());
+                }
+                row
+            })
+            .collect();
+        let mut last_err = None;
+        for attempt in 0..REMOTE_ATTEMPTS {
+            if attempt > 0 {
+                tokio::time::sleep(self.retry_delay * (1 << (attempt - 1))).await;
+            }
+            let mut req = self.http.post(&self.url).json(&rows);
+            if let Some(token) = &self.token {
+                req = req.bearer_auth(token);
+            }
+            match req.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err
+            .map(anyhow::Error::from)
+            .unwrap_or_else(|| anyhow::anyhow!("remote journal push failed")))
+    }
+
+    /// Delivers a live batch, draining any earlier spill first. Never returns an error:
+    /// whatever can't be sent ends up in the spill file.
+    pub async fn deliver(&self, entries: Vec<JournalEntry>) {
+        if self.spill_path.exists() {
+            if let Err(e) = self.drain_spill().await {
+                Logger::warning(&format!("Remote journal still unavailable: {}", e));
+                self.spill(&entries);
+                return;
+            }
+        }
+        if entries.is_empty() {
+            return;
+        }
+        if let Err(e) = self.push(&entries).await {
+            Logger::warning(&format!(
+                "Remote journal push failed, spilling {} rows: {}",
+                entries.len(),
+                e
+            ));
+            self.spill(&entries);
+        }
+    }
+
+    /// Re-sends spilled rows in batches; the spill file is removed once all of them went through.
+    pub async fn drain_spill(&self) -> Result<()> {
+        let pending = read_file(&self.spill_path)?;
+        for batch in pending.chunks(REMOTE_BATCH_SIZE) {
+            self.push(batch).await?;
+        }
+        std::fs::remove_file(&self.spill_path)?;
+        if !pending.is_empty() {
+            Logger::info(&format!(
+                "Remote journal recovered: sent {} spilled rows",
+                pending.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn spill(&self, entries: &[JournalEntry]) {
+        let result = (|| -> Result<()> {
+            if let Some(dir) = self.spill_path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.spill_path)?;
+            for entry in entries {
+                writeln!(file, "{}", serde_json::to_string(entry)?)?;
+            }
+            Ok(())
+        })();
+        if let Err(e) = result {
+            Logger::warning(&format!("Failed to spill remote journal rows: {}", e));
+        }
+    }
+}
+
+/// Reads the journal at `path` with the days `JOURNAL_ROTATE_DAILY` moved aside before it,
+/// oldest first.
+pub fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+    let mut entries = Vec::new();
+    for rotated in rotated_files(path) {
+        entries.extend(read_file(&rotated)?);
+    }
+    entries.extend(read_file(path)?);
+    Ok(entries)
+}
+
+/// Reads one journal file written by any schema version, skipping lines that don't parse.
+pub fn read_file(path: &Path) -> Result<Vec<JournalEntry>> {
+    let file = match std::fs::File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(std::io::BufReader::new(file)
+        .lines()
+        .map_while(|l| l.ok())
+        .filter_map(|l| parse_row(&l).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A stand-in endpoint: answers every POST with `status` and records the rows and the
+    /// `Authorization` header it received.
+    #[derive(Clone, Default)]
+    struct MockSink {
+        status: Arc<AtomicU16>,
+        batches: Arc<Mutex<Vec<serde_json::Value>>>,
+        auth: Arc<Mutex<Option<String>>>,
+    }
+
+    impl MockSink {
+        async fn start(status: u16) -> (Self, String) {
+            let sink = MockSink::default();
+            sink.status.store(status, Ordering::SeqCst);
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/rows", listener.local_addr().unwrap());
+            let server = sink.clone();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let sink = server.clone();
+                    tokio::spawn(async move {
+                        let (head, body) = read_request(&mut stream).await;
+                        let status = sink.status.load(Ordering::SeqCst);
+                        if status == 200 {
+                            sink.batches.lock().unwrap().push(serde_json::from_str(&body).unwrap());
+                        }
+                        *sink.auth.lock().unwrap() = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("authorization: "))
+                            .map(str::to_string);
+                        let response = format!(
+                            "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                            status
+                        );
+                        let _ = stream.write_all(response.as_bytes()).await;
+                    });
+                }
+            });
+            (sink, url)
+        }
+
+        fn rows_received(&self) -> Vec<String> {
+            self.batches
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|b| b.as_array().cloned().unwrap_or_default())
+                .map(|row| row["tx_hash"].as_str().unwrap_or_default().to_string())
+                .collect()
+        }
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> (String, String) {
+        let mut raw = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            raw.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&raw).to_string();
+            if let Some(split) = text.find("\r\n\r\n") {
+                let head = text[..split].to_lowercase();
+                let len: usize = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("content-length: "))
+                    .and_then(|v| v.trim().parse().ok())
+                    .unwrap_or(0);
+                if raw.len() >= split + 4 + len || n == 0 {
+                    return (head, text[split + 4..].to_string());
+                }
+            }
+            if n == 0 {
+                return (text, String::new());
+            }
+        }
+    }
+
+    fn remote(url: String, dir: &Path) -> RemoteJournal {
+        RemoteJournal {
+            http: reqwest::Client::new(),
+            url,
+            token: Some("secret".to_string()),
+            instance_id: "bot-1".to_string(),
+            spill_path: dir.join(SPILL_FILE),
+            retry_delay: Duration::from_millis(1),
+        }
+    }
+
+    fn row(tx: &str) -> JournalEntry {
+        parse_row(&format!(
+            r#"{{"schema_version":1,"timestamp":1760000000,"status":"executed","trader":"0xabc","slug":"fed-cut","condition_id":"0xc1","asset":"123","side":"BUY","trader_usd":10.0,"my_usd":1.0,"my_tokens":2.0,"tx_hash":"{}","reason":null}}"#,
+            tx
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rows_are_tagged_and_authenticated() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sink, url) = MockSink::start(200).await;
+        remote(url, dir.path()).deliver(vec![row("0x1")]).await;
+
+        let batches = sink.batches.lock().unwrap().clone();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0][0]["instance_id"], "bot-1");
+        assert_eq!(sink.auth.lock().unwrap().as_deref(), Some("bearer secret"));
+        assert!(!dir.path().join(SPILL_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn failed_batches_spill_and_are_sent_first_on_recovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let (sink, url) = MockSink::start(503).await;
+        let remote = remote(url, dir.path());
+
+        remote.deliver(vec![row("0x1"), row("0x2")]).await;
+        remote.deliver(vec![row("0x3")]).await;
+        let spilled = read_file(&dir.path().join(SPILL_FILE)).unwrap();
+        assert_eq!(spilled.len(), 3);
+        assert!(sink.rows_received().is_empty());
+
+        sink.status.store(200, Ordering::SeqCst);
+        remote.deliver(vec![row("0x4")]).await;
+        assert_eq!(sink.rows_received(), vec!["0x1", "0x2", "0x3", "0x4"]);
+        assert!(!dir.path().join(SPILL_FILE).exists());
+    }
+}
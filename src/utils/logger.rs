@@ -1,16 +1,252 @@
+use serde_json::{json, Value};
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::sync::{Mutex, OnceLock};
 
 use super::theme::{self, colors, icons};
+use crate::balance::BalanceBreakdown;
+use crate::build_info;
+use crate::types::UserPosition;
+
+/// Signals for the same trader, asset and side this close together are one order matched
+/// against several counterparties, and are shown as one.
+const BURST_WINDOW_SECS: i64 = 5;
+
+/// The run of identical signals currently being collapsed in the activity feed.
+#[derive(Debug, Clone, PartialEq)]
+struct SignalBurst {
+    trader: String,
+    asset: String,
+    side: String,
+    fills: usize,
+    total_usd: f64,
+    last_at: i64,
+}
+
+static BURST: Mutex<Option<SignalBurst>> = Mutex::new(None);
+
+/// What one signal did to the current burst.
+#[derive(Debug, PartialEq)]
+enum BurstStep {
+    /// It continued the burst.
+    Continued,
+    /// It started a new burst; `closed` is the previous one if it had several fills.
+    Started { closed: Option<SignalBurst> },
+}
+
+/// Folds a signal into `burst`: same trader, asset and side within `BURST_WINDOW_SECS` of the
+/// last fill continues it, anything else closes it and starts a new one.
+fn absorb_signal(
+    burst: &mut Option<SignalBurst>,
+    trader: String,
+    asset: String,
+    side: String,
+    amount: f64,
+    now: i64,
+) -> BurstStep {
+    if let Some(b) = burst.as_mut() {
+        if b.trader == trader
+            && b.asset == asset
+            && b.side == side
+            && now - b.last_at <= BURST_WINDOW_SECS
+        {
+            b.fills += 1;
+            b.total_usd += amount;
+            b.last_at = now;
+            return BurstStep::Continued;
+        }
+    }
+    let closed = burst.take().filter(|b| b.fills > 1);
+    *burst = Some(SignalBurst {
+        trader,
+        asset,
+        side,
+        fills: 1,
+        total_usd: amount,
+        last_at: now,
+    });
+    BurstStep::Started { closed }
+}
+
+impl SignalBurst {
+    /// The line printed for each fill after the first.
+    fn fill_line(&self, amount: f64) -> String {
+        format!(
+            "{} +${:.2} same order from {}: {} fills, ${:.2} total",
+            icons::ARROW,
+            amount,
+            Logger::format_address(&self.trader),
+            self.fills,
+            self.total_usd
+        )
+    }
+
+    /// The line logged once the burst is over.
+    fn summary_line(&self) -> String {
+        format!(
+            "Order from {} done: {} {} fills, ${:.2} total",
+            Logger::format_address(&self.trader),
+            self.side,
+            self.fills,
+            self.total_usd
+        )
+    }
+}
+
+/// `LOG_FORMAT`: colored text for a terminal, or one JSON object per line for log shippers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_uppercase().as_str() {
+            "HUMAN" | "" => Some(LogFormat::Human),
+            "JSON" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// From `LOG_FORMAT` in the environment, for logging before the config is loaded.
+    pub fn from_env() -> Self {
+        std::env::var("LOG_FORMAT")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+}
+
+/// Set once at startup by `Logger::init`; human until then.
+static FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+/// `text` without terminal color codes.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end at their first letter.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// One JSON log line: `level`, `timestamp` and `message`, then `fields`.
+fn json_line(level: &str, message: &str, fields: Value) -> String {
+    let mut line = json!({
+        "level": level,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "message": strip_ansi(message),
+    });
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    line.to_string()
+}
+
+/// One executed copy with the day's running totals, for `Logger::copy_summary`.
+#[derive(Debug, Clone)]
+pub struct CopySummary {
+    /// Executed copies today, this one included.
+    pub number: usize,
+    pub side: String,
+    pub outcome: Option<String>,
+    pub usd: f64,
+    pub price: f64,
+    pub deployed_usd: f64,
+    pub skipped: usize,
+    pub balance: Option<f64>,
+}
+
+/// `1204.4` -> `$1,204`.
+fn whole_dollars(amount: f64) -> String {
+    let digits = format!("{:.0}", amount.abs());
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}${}", if amount < -0.5 { "-" } else { "" }, grouped)
+}
+
+/// `Copy #14 today | $23.50 BUY YES @ 41¢ | day: $312 deployed, 2 skipped, balance $1,204`.
+/// `color` adds the terminal styling; plain is for the log file and notifications.
+pub fn format_copy_summary(summary: &CopySummary, color: bool) -> String {
+    let paint = |code: theme::Color| if color { code.code() } else { "" };
+    let cents = summary.price * 100.0;
+    let price = if cents >= 1.0 {
+        format!("{:.0}¢", cents)
+    } else {
+        format!("{:.1}¢", cents)
+    };
+    let outcome = summary
+        .outcome
+        .as_deref()
+        .map(|o| format!(" {}", o.to_uppercase()))
+        .unwrap_or_default();
+    let balance = summary
+        .balance
+        .map(|b| format!(", balance {}", whole_dollars(b)))
+        .unwrap_or_default();
+    format!(
+        "{}Copy #{} today{} | {}${:.2} {}{} @ {}{} | {}day: {} deployed, {} skipped{}{}",
+        paint(colors::BOLD),
+        summary.number,
+        paint(colors::RESET),
+        paint(colors::SUCCESS),
+        summary.usd,
+        summary.side,
+        outcome,
+        price,
+        paint(colors::RESET),
+        paint(colors::MUTED),
+        whole_dollars(summary.deployed_usd),
+        summary.skipped,
+        balance,
+        paint(colors::RESET)
+    )
+}
 
 pub struct Logger;
 
 impl Logger {
+    /// Picks the output format for the rest of the run; later calls are ignored.
+    pub fn init(format: LogFormat) {
+        let _ = FORMAT.set(format);
+    }
+
+    pub fn is_json() -> bool {
+        FORMAT.get() == Some(&LogFormat::Json)
+    }
+
+    /// Prints a JSON line; warnings and errors go to stderr like their colored versions.
+    fn emit(level: &str, message: &str, fields: Value) {
+        let line = json_line(level, message, fields);
+        if level == "warn" || level == "error" {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
     fn log_dir() -> std::path::PathBuf {
         std::env::current_dir().unwrap_or_default().join("logs")
     }
 
-    fn log_file() -> std::path::PathBuf {
+    /// Today's log file.
+    pub fn log_file() -> std::path::PathBuf {
         let date = chrono::Utc::now().format("%Y-%m-%d");
         Self::log_dir().join(format!("bot-{}.log", date))
     }
@@ -24,77 +260,113 @@ impl Logger {
 
     fn write_file(msg: &str) {
         Self::ensure_log_dir();
-        if let Ok(mut f) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(Self::log_file())
-        {
+        let path = Self::log_file();
+        let new_file = !path.exists();
+        if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(&path) {
+            if new_file {
+                // First line of every log file: which build wrote it.
+                let header = serde_json::json!({ "log_header": { "build": build_info::to_json() } });
+                let _ = writeln!(f, "{}", header);
+            }
             let _ = writeln!(f, "[{}] {}", chrono::Utc::now().to_rfc3339(), msg);
         }
     }
 
     pub fn info(msg: &str) {
-        println!(
-            "{} {} {}{} {}",
-            colors::ACCENT,
-            icons::INFO,
-            colors::RESET,
-            colors::MUTED,
-            msg
-        );
+        if Self::is_json() {
+            Self::emit("info", msg, json!({}));
+        } else {
+            println!(
+                "{} {} {}{} {}",
+                colors::ACCENT,
+                icons::INFO,
+                colors::RESET,
+                colors::MUTED,
+                msg
+            );
+        }
         Self::write_file(&format!("INFO: {}", msg));
     }
 
     pub fn success(msg: &str) {
-        println!(
-            "{} {} {}{} {}",
-            colors::SUCCESS,
-            icons::OK,
-            colors::RESET,
-            colors::MINT,
-            msg
-        );
+        if Self::is_json() {
+            Self::emit("info", msg, json!({ "kind": "success" }));
+        } else {
+            println!(
+                "{} {} {}{} {}",
+                colors::SUCCESS,
+                icons::OK,
+                colors::RESET,
+                colors::MINT,
+                msg
+            );
+        }
         Self::write_file(&format!("SUCCESS: {}", msg));
     }
 
     pub fn warning(msg: &str) {
-        eprintln!(
-            "{} {} {}{} {}",
-            colors::WARN,
-            icons::WARN,
-            colors::RESET,
-            colors::WARN,
-            msg
-        );
+        if Self::is_json() {
+            Self::emit("warn", msg, json!({}));
+        } else {
+            eprintln!(
+                "{} {} {}{} {}",
+                colors::WARN,
+                icons::WARN,
+                colors::RESET,
+                colors::WARN,
+                msg
+            );
+        }
         Self::write_file(&format!("WARNING: {}", msg));
     }
 
     pub fn error(msg: &str) {
-        eprintln!(
-            "{} {} {}{} {}",
-            colors::ERROR,
-            icons::ERR,
-            colors::RESET,
-            colors::ERROR,
-            msg
-        );
+        if Self::is_json() {
+            Self::emit("error", msg, json!({}));
+        } else {
+            eprintln!(
+                "{} {} {}{} {}",
+                colors::ERROR,
+                icons::ERR,
+                colors::RESET,
+                colors::ERROR,
+                msg
+            );
+        }
         Self::write_file(&format!("ERROR: {}", msg));
     }
 
+    /// An empty line between blocks of terminal output.
+    pub fn blank() {
+        if !Self::is_json() {
+            println!();
+        }
+    }
+
     pub fn separator() {
+        if Self::is_json() {
+            return;
+        }
         println!("{}{} {}", colors::DIM, "─".repeat(72), colors::RESET);
     }
 
     pub fn header(title: &str) {
+        if Self::is_json() {
+            Self::emit("info", title, json!({ "kind": "header" }));
+            Self::write_file(&format!("HEADER: {}", title));
+            return;
+        }
         let width = 70usize;
         let pad_left = (width - 2 - title.len()) / 2;
         let pad_right = width - 2 - title.len() - pad_left;
         let title_line = format!(
-            "{}│{}{}{}{}{}│{}",
+            "{}│{}{}{}{}{}{}{}│{}",
             colors::BOX,
             colors::RESET,
             " ".repeat(pad_left),
-            format!("{}{}{}", colors::ACCENT_BOLD, title, colors::RESET),
+            colors::ACCENT_BOLD,
+            title,
+            colors::RESET,
             " ".repeat(pad_right),
             colors::BOX,
             colors::RESET
@@ -116,7 +388,29 @@ impl Logger {
         }
     }
 
-    pub fn startup(traders: &[String], my_wallet: &str) {
+    /// `traders` are logical traders with their member addresses (see `TRADER_GROUPS`).
+    pub fn startup(traders: &[(String, Vec<String>)], my_wallet: &str) {
+        let masked = if my_wallet.len() >= 42 {
+            format!(
+                "{}•••{}",
+                &my_wallet[..6],
+                &my_wallet[my_wallet.len() - 4..]
+            )
+        } else {
+            my_wallet.to_string()
+        };
+        if Self::is_json() {
+            let traders: Vec<Value> = traders
+                .iter()
+                .map(|(id, members)| json!({ "trader": id, "addresses": members }))
+                .collect();
+            Self::emit(
+                "info",
+                &format!("Copying {} traders", traders.len()),
+                json!({ "kind": "startup", "traders": traders, "wallet": masked }),
+            );
+            return;
+        }
         println!();
         for (i, line) in theme::BANNER.iter().enumerate() {
             let color = if i < 3 {
@@ -145,27 +439,37 @@ impl Logger {
             colors::RESET,
             colors::BOX
         );
-        for (i, addr) in traders.iter().enumerate() {
+        for (i, (id, members)) in traders.iter().enumerate() {
+            let grouped = members.len() != 1 || members[0] != *id;
             println!(
                 "{}│{}    {}. {} {}│{}{}",
                 colors::BOX,
                 colors::RESET,
                 i + 1,
-                Self::format_address(addr),
+                if grouped {
+                    id.clone()
+                } else {
+                    Self::format_address(id)
+                },
                 colors::MUTED,
                 colors::BOX,
                 colors::RESET
             );
+            if grouped {
+                for member in members {
+                    println!(
+                        "{}│{}       {} {} {}│{}{}",
+                        colors::BOX,
+                        colors::RESET,
+                        icons::ARROW,
+                        Self::format_address(member),
+                        colors::MUTED,
+                        colors::BOX,
+                        colors::RESET
+                    );
+                }
+            }
         }
-        let masked = if my_wallet.len() >= 42 {
-            format!(
-                "{}•••{}",
-                &my_wallet[..6],
-                &my_wallet[my_wallet.len() - 4..]
-            )
-        } else {
-            my_wallet.to_string()
-        };
         println!(
             "{}│{}  {} Your vault {} {}│{}{}",
             colors::BOX,
@@ -181,6 +485,9 @@ impl Logger {
     }
 
     pub fn waiting(trader_count: usize, extra: Option<&str>) {
+        if Self::is_json() {
+            return;
+        }
         let ts = chrono::Local::now().format("%H:%M:%S");
         let msg = match extra {
             Some(e) => format!(
@@ -201,19 +508,46 @@ impl Logger {
     }
 
     pub fn clear_line() {
+        if Self::is_json() {
+            return;
+        }
         print!("\r{}\r", " ".repeat(100));
         let _ = std::io::stdout().flush();
     }
 
     pub fn money(amount: f64) -> String {
+        if Self::is_json() {
+            return format!("$ {:.2}", amount);
+        }
         format!("{}$ {:.2}{}", colors::GOLD, amount, colors::RESET)
     }
 
     pub fn field(label: &str, value: &str) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("{}: {}", label, value),
+                json!({ "kind": "field", "label": label, "value": value }),
+            );
+            return;
+        }
         println!("  {} {} {} {}", colors::MUTED, label, colors::ACCENT, value);
     }
 
     pub fn health_line(label: &str, status: &str, message: &str) {
+        if Self::is_json() {
+            let level = match status {
+                "ok" => "info",
+                "warning" => "warn",
+                _ => "error",
+            };
+            Self::emit(
+                level,
+                &format!("{}: {}", label, message),
+                json!({ "kind": "health", "check": label, "status": status }),
+            );
+            return;
+        }
         let (icon, color) = match status {
             "ok" => (icons::OK, colors::SUCCESS),
             "warning" => (icons::WARN, colors::WARN),
@@ -231,10 +565,20 @@ impl Logger {
         );
     }
 
+    /// Prints a detected trade. Repeats of the same order within `BURST_WINDOW_SECS` get one
+    /// line each with the running fill count; the journal still records every signal.
     pub fn trade(trader_address: &str, action: &str, details: TradeDetails) {
+        if Self::is_json() {
+            // Every signal gets its own line; collapsing bursts is for the terminal.
+            Self::trade_json(trader_address, action, &details);
+            return;
+        }
+        if Self::collapse_into_burst(trader_address, &details) {
+            return;
+        }
         println!();
         println!("{}{}", colors::HIGHLIGHT, "─".repeat(70));
-        println!("{}📊 NEW TRADE DETECTED{}", format!("{}{}", colors::HIGHLIGHT, colors::BOLD), colors::RESET);
+        println!("{}{}{} NEW TRADE DETECTED{}", colors::HIGHLIGHT, colors::BOLD, icons::CHART, colors::RESET);
         println!("{}Trader: {}{}", colors::MUTED, Self::format_address(trader_address), colors::RESET);
         println!("{}Action: {}{}{}", colors::MUTED, colors::RESET, action, colors::RESET);
         if let Some(asset) = &details.asset {
@@ -250,7 +594,7 @@ impl Logger {
         if let Some(price) = details.price {
             println!("{}Price:  {}{}{}", colors::MUTED, colors::ACCENT, price, colors::RESET);
         }
-        if let Some(slug) = details.event_slug.or(details.slug) {
+        if let Some(slug) = details.event_slug.as_ref().or(details.slug.as_ref()) {
             let market_url = format!("https://polymarket.com/event/{}", slug);
             println!("{}Market: {}{}{}", colors::MUTED, colors::ACCENT, market_url, colors::RESET);
         }
@@ -261,6 +605,10 @@ impl Logger {
         println!("{}{}{}", colors::HIGHLIGHT, "─".repeat(70), colors::RESET);
         println!();
 
+        Self::write_file(&Self::trade_log(trader_address, action, &details));
+    }
+
+    fn trade_log(trader_address: &str, action: &str, details: &TradeDetails) -> String {
         let mut trade_log = format!("TRADE: {} - {}", Self::format_address(trader_address), action);
         if let Some(side) = &details.side {
             trade_log.push_str(&format!(" | Side: {}", side));
@@ -277,29 +625,138 @@ impl Logger {
         if let Some(tx_hash) = &details.transaction_hash {
             trade_log.push_str(&format!(" | TX: {}", tx_hash));
         }
+        trade_log
+    }
+
+    fn trade_json(trader_address: &str, action: &str, details: &TradeDetails) {
+        let trade_log = Self::trade_log(trader_address, action, details);
+        Self::emit(
+            "info",
+            trade_log.trim_start_matches("TRADE: "),
+            json!({
+                "kind": "trade",
+                "trader": trader_address.to_lowercase(),
+                "action": action,
+                "asset": details.asset,
+                "side": details.side,
+                "usd_size": details.amount,
+                "price": details.price,
+                "market": details.title,
+                "slug": details.event_slug.as_ref().or(details.slug.as_ref()),
+                "tx_hash": details.transaction_hash,
+            }),
+        );
         Self::write_file(&trade_log);
     }
 
+    /// Folds the signal into the current burst if it continues it, printing the running total.
+    /// Otherwise closes the previous burst (with a summary if it had several fills) and starts
+    /// a new one; the caller then prints the full trade block.
+    fn collapse_into_burst(trader_address: &str, details: &TradeDetails) -> bool {
+        let Ok(mut burst) = BURST.lock() else {
+            return false;
+        };
+        let step = absorb_signal(
+            &mut burst,
+            trader_address.to_lowercase(),
+            details.asset.clone().unwrap_or_default(),
+            details.side.clone().unwrap_or_default(),
+            details.amount.unwrap_or(0.0),
+            chrono::Utc::now().timestamp(),
+        );
+        match step {
+            BurstStep::Continued => {
+                if let Some(b) = burst.as_ref() {
+                    let line = b.fill_line(details.amount.unwrap_or(0.0));
+                    println!("{}{}{}", colors::MUTED, line, colors::RESET);
+                    Self::write_file(&format!("TRADE (collapsed): {}", line));
+                }
+                true
+            }
+            BurstStep::Started { closed } => {
+                if let Some(prev) = closed {
+                    Self::info(&prev.summary_line());
+                }
+                false
+            }
+        }
+    }
+
     pub fn balance(my_balance: f64, trader_balance: f64, trader_address: &str) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!(
+                    "Capital: mine ${:.2}, trader ${:.2}",
+                    my_balance, trader_balance
+                ),
+                json!({
+                    "kind": "balance",
+                    "my_balance": my_balance,
+                    "trader_balance": trader_balance,
+                    "trader": trader_address.to_lowercase(),
+                }),
+            );
+            return;
+        }
         println!("{}Capital (USDC + Positions):{}", colors::MUTED, colors::RESET);
         println!(
-            "{}  Your total capital:   {}$ {:.2}{}",
+            "{}  Your total capital:   {}{}$ {:.2}{}",
             colors::MUTED,
-            format!("{}{}", colors::SUCCESS, colors::BOLD),
+            colors::SUCCESS,
+            colors::BOLD,
             my_balance,
             colors::RESET
         );
         println!(
-            "{}  Trader total capital: {}$ {:.2} ({}){}",
+            "{}  Trader total capital: {}{}$ {:.2} ({}){}",
             colors::MUTED,
-            format!("{}{}", colors::ACCENT, colors::BOLD),
+            colors::ACCENT,
+            colors::BOLD,
             trader_balance,
             Self::format_address(trader_address),
             colors::RESET
         );
     }
 
+    /// One compact line after each executed copy.
+    pub fn copy_summary(summary: &CopySummary) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format_copy_summary(summary, false),
+                json!({
+                    "kind": "copy",
+                    "number": summary.number,
+                    "side": summary.side,
+                    "outcome": summary.outcome,
+                    "usd_size": summary.usd,
+                    "price": summary.price,
+                    "deployed_usd": summary.deployed_usd,
+                    "skipped": summary.skipped,
+                    "balance": summary.balance,
+                }),
+            );
+        } else {
+            println!("{} {} {}", colors::SUCCESS, icons::OK, format_copy_summary(summary, true));
+        }
+        Self::write_file(&format!("COPY: {}", format_copy_summary(summary, false)));
+    }
+
     pub fn order_result(success: bool, message: &str) {
+        if Self::is_json() {
+            Self::emit(
+                if success { "info" } else { "error" },
+                message,
+                json!({ "kind": "order", "success": success }),
+            );
+            Self::write_file(&format!(
+                "ORDER {}: {}",
+                if success { "SUCCESS" } else { "FAILED" },
+                message
+            ));
+            return;
+        }
         if success {
             println!(
                 "{} {} {}Order executed:{} {}",
@@ -326,33 +783,87 @@ impl Logger {
     pub fn my_positions(
         wallet: &str,
         count: usize,
-        top_positions: &[serde_json::Value],
+        top_positions: &[UserPosition],
         overall_pnl: f64,
         total_value: f64,
         initial_value: f64,
-        current_balance: f64,
+        balance: &BalanceBreakdown,
     ) {
+        if Self::is_json() {
+            let top: Vec<Value> = top_positions
+                .iter()
+                .take(5)
+                .map(|pos| {
+                    json!({
+                        "title": pos.title,
+                        "outcome": pos.outcome,
+                        "value": pos.current_value,
+                        "pnl_percent": pos.percent_pnl,
+                        "avg_price": pos.avg_price,
+                        "cur_price": pos.cur_price,
+                    })
+                })
+                .collect();
+            Self::emit(
+                "info",
+                &format!(
+                    "{} open positions worth ${:.2}, ${:.2} cash",
+                    count, total_value, balance.wallet
+                ),
+                json!({
+                    "kind": "positions",
+                    "wallet": wallet.to_lowercase(),
+                    "count": count,
+                    "cash": balance.wallet,
+                    "free": balance.free,
+                    "locked": balance.locked,
+                    "reserve": balance.reserve,
+                    "total_portfolio": balance.wallet + total_value,
+                    "invested": initial_value,
+                    "current_value": total_value,
+                    "pnl_percent": overall_pnl,
+                    "top": top,
+                }),
+            );
+            return;
+        }
         println!();
         println!(
-            "{}💼 YOUR POSITIONS{}",
-            format!("{}{}", colors::HIGHLIGHT, colors::BOLD),
+            "{}{}{} YOUR POSITIONS{}",
+            colors::HIGHLIGHT,
+            colors::BOLD,
+            icons::BRIEFCASE,
             colors::RESET
         );
         println!("{}   Wallet: {}{}", colors::MUTED, Self::format_address(wallet), colors::RESET);
         println!();
 
-        let total_portfolio = current_balance + total_value;
+        let total_portfolio = balance.wallet + total_value;
         println!(
-            "{}   💰 Available Cash:    {}$ {:.2}{}",
+            "{}   {} Available Cash:    {}{}$ {:.2}{}",
             colors::MUTED,
-            format!("{}{}", colors::WARN, colors::BOLD),
-            current_balance,
+            icons::MONEY,
+            colors::WARN,
+            colors::BOLD,
+            balance.wallet,
             colors::RESET
         );
+        if balance.locked > 0.0 || balance.reserve > 0.0 {
+            println!(
+                "{}      Free $ {:.2} · locked in orders $ {:.2} · reserve $ {:.2}{}",
+                colors::MUTED,
+                balance.free,
+                balance.locked,
+                balance.reserve,
+                colors::RESET
+            );
+        }
         println!(
-            "{}   📊 Total Portfolio:   {}$ {:.2}{}",
+            "{}   {} Total Portfolio:   {}{}$ {:.2}{}",
             colors::MUTED,
-            format!("{}{}", colors::ACCENT, colors::BOLD),
+            icons::CHART,
+            colors::ACCENT,
+            colors::BOLD,
             total_portfolio,
             colors::RESET
         );
@@ -362,8 +873,9 @@ impl Logger {
         } else {
             println!();
             println!(
-                "{}   📈 Open Positions:    {}{} position{}{}",
+                "{}   {} Open Positions:    {}{} position{}{}",
                 colors::MUTED,
+                icons::CHART_UP,
                 colors::SUCCESS,
                 count,
                 if count > 1 { "s" } else { "" },
@@ -399,32 +911,14 @@ impl Logger {
             );
 
             if !top_positions.is_empty() {
-                println!("{}   🔝 Top Positions:{}", colors::MUTED, colors::RESET);
+                println!("{}   {} Top Positions:{}", colors::MUTED, icons::TOP, colors::RESET);
                 for pos in top_positions.iter().take(5) {
-                    let percent_pnl = pos
-                        .get("percentPnl")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let current_value = pos
-                        .get("currentValue")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let avg_price = pos
-                        .get("avgPrice")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let cur_price = pos
-                        .get("curPrice")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let outcome = pos
-                        .get("outcome")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    let title = pos
-                        .get("title")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
+                    let percent_pnl = pos.percent_pnl.unwrap_or(0.0);
+                    let current_value = pos.current_value.unwrap_or(0.0);
+                    let avg_price = pos.avg_price.unwrap_or(0.0);
+                    let cur_price = pos.cur_price.unwrap_or(0.0);
+                    let outcome = pos.outcome.as_deref().unwrap_or("Unknown");
+                    let title = pos.title.as_deref().unwrap_or("Unknown");
                     let title_display = if title.len() > 45 {
                         format!("{}...", &title[..45])
                     } else {
@@ -470,13 +964,210 @@ impl Logger {
         println!();
     }
 
+    pub fn dust_line(count: usize, value: f64) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("Dust: {} positions worth ${:.2} (hidden)", count, value),
+                json!({ "kind": "dust", "count": count, "value": value }),
+            );
+            return;
+        }
+        println!(
+            "{}   {} Dust: {} position{} worth $ {:.2} (hidden){}",
+            colors::MUTED,
+            icons::BROOM,
+            count,
+            if count == 1 { "" } else { "s" },
+            value,
+            colors::RESET
+        );
+        println!();
+    }
+
+    /// What the previous run left behind (see `handover`).
+    pub fn handover(offline_secs: i64, lines: &[String]) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("Handover from last run: {}", lines.join(" | ")),
+                json!({ "kind": "handover", "offline_secs": offline_secs, "lines": lines }),
+            );
+            return;
+        }
+        println!(
+            "{}{} HANDOVER FROM LAST RUN{} {}(stopped {:.0}m ago){}",
+            colors::ACCENT,
+            icons::HANDSHAKE,
+            colors::RESET,
+            colors::MUTED,
+            offline_secs.max(0) as f64 / 60.0,
+            colors::RESET
+        );
+        for line in lines {
+            println!("{}   {}{}", colors::MUTED, line, colors::RESET);
+        }
+        println!();
+    }
+
+    /// Portfolio buckets over their concentration limits (see `concentration`).
+    pub fn concentration(lines: &[String]) {
+        if Self::is_json() {
+            Self::emit(
+                "warn",
+                &format!("Portfolio concentration: {}", lines.join(" | ")),
+                json!({ "kind": "concentration", "lines": lines }),
+            );
+            Self::write_file(&format!("CONCENTRATION: {}", lines.join(" | ")));
+            return;
+        }
+        println!(
+            "{}{} PORTFOLIO CONCENTRATION{} {}(advisory, nothing is traded){}",
+            colors::WARN,
+            icons::SCALES,
+            colors::RESET,
+            colors::MUTED,
+            colors::RESET
+        );
+        for line in lines {
+            println!("{}   {}{}", colors::MUTED, line, colors::RESET);
+        }
+        println!();
+        Self::write_file(&format!("CONCENTRATION: {}", lines.join(" | ")));
+    }
+
+    /// A market where the position cap is blocking the trader's adds.
+    pub fn capped_line(title: &str, ignored_usd: f64) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("{} - capped, ${:.2} of trader adds ignored", title, ignored_usd),
+                json!({ "kind": "capped", "market": title, "ignored_usd": ignored_usd }),
+            );
+            return;
+        }
+        println!(
+            "{}   {} {} - capped, $ {:.2} of trader adds ignored{}",
+            colors::MUTED,
+            icons::CAP,
+            title,
+            ignored_usd,
+            colors::RESET
+        );
+    }
+
+    /// My note on a held market (`market set <id> note ...`).
+    pub fn market_note_line(title: &str, note: &str) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("{} - {}", title, note),
+                json!({ "kind": "market_note", "market": title, "note": note }),
+            );
+            return;
+        }
+        println!(
+            "{}   {} {} - {}{}",
+            colors::MUTED,
+            icons::MEMO,
+            title,
+            note,
+            colors::RESET
+        );
+    }
+
+    /// A position build's progress toward its target, or how it ended.
+    pub fn build_line(title: &str, progress: &str, ended: Option<&str>) {
+        if Self::is_json() {
+            Self::emit(
+                "info",
+                &format!("{} - {} {}", title, progress, ended.unwrap_or("building")),
+                json!({ "kind": "build", "market": title, "progress": progress, "ended": ended }),
+            );
+            return;
+        }
+        let (color, state) = match ended {
+            Some(reason) => (colors::MUTED, reason.to_string()),
+            None => (colors::ACCENT, "building".to_string()),
+        };
+        println!(
+            "{}   {} {} - {}{} {}{}",
+            colors::MUTED,
+            icons::BRICKS,
+            title,
+            color,
+            progress,
+            state,
+            colors::RESET
+        );
+    }
+
+    /// A holding's share of its market's open interest; shares over the
+    /// `MAX_MARKET_SHARE_PERCENT` limit are flagged as hard to exit.
+    pub fn market_share_line(title: &str, share: Option<f64>, max_share: f64) {
+        if Self::is_json() {
+            let illiquid = share.is_some_and(|s| s > max_share);
+            Self::emit(
+                if illiquid { "warn" } else { "info" },
+                &format!(
+                    "{} - {}",
+                    title,
+                    share.map_or("OI unknown".to_string(), |s| format!("{:.1}% of OI", s * 100.0))
+                ),
+                json!({
+                    "kind": "market_share",
+                    "market": title,
+                    "share": share,
+                    "max_share": max_share,
+                    "illiquid": illiquid,
+                }),
+            );
+            return;
+        }
+        let (color, figure) = match share {
+            Some(s) if s > max_share => {
+                (colors::WARN, format!("{:.1}% of OI - illiquid", s * 100.0))
+            }
+            Some(s) => (colors::MUTED, format!("{:.1}% of OI", s * 100.0)),
+            None => (colors::MUTED, "OI unknown".to_string()),
+        };
+        println!(
+            "{}   {} {} - {}{}{}",
+            colors::MUTED,
+            icons::RULER,
+            title,
+            color,
+            figure,
+            colors::RESET
+        );
+    }
+
     pub fn traders_positions(
         traders: &[String],
         position_counts: &[usize],
-        position_details: &[Vec<serde_json::Value>],
+        position_details: &[Vec<UserPosition>],
         profitabilities: &[f64],
+        labels: &[String],
     ) {
-        println!("{}📈 TRADERS YOU'RE COPYING{}", colors::ACCENT, colors::RESET);
+        if Self::is_json() {
+            for (i, addr) in traders.iter().enumerate() {
+                let positions = position_counts.get(i).copied().unwrap_or(0);
+                let pnl = profitabilities.get(i).copied().unwrap_or(0.0);
+                Self::emit(
+                    "info",
+                    &format!("{}: {} positions, PnL {:+.1}%", addr, positions, pnl),
+                    json!({
+                        "kind": "trader_positions",
+                        "trader": addr.to_lowercase(),
+                        "positions": positions,
+                        "pnl_percent": pnl,
+                        "label": labels.get(i).filter(|l| !l.is_empty()),
+                    }),
+                );
+            }
+            return;
+        }
+        println!("{}{} TRADERS YOU'RE COPYING{}", colors::ACCENT, icons::CHART_UP, colors::RESET);
         for (i, addr) in traders.iter().enumerate() {
             let pos_count = position_counts.get(i).copied().unwrap_or(0);
             let pnl = profitabilities.get(i).copied().unwrap_or(0.0);
@@ -486,43 +1177,31 @@ impl Logger {
             } else {
                 colors::ERROR
             };
+            let label = labels
+                .get(i)
+                .filter(|l| !l.is_empty())
+                .map(|l| format!("{} · {}", colors::MUTED, l))
+                .unwrap_or_default();
             println!(
-                "{}   {}: {} positions · {}PnL {}{:.1}%{}",
+                "{}   {}: {} positions · {}PnL {}{:.1}%{}{}",
                 colors::MUTED,
                 Self::format_address(addr),
                 pos_count,
                 pnl_color,
                 pnl_sign,
                 pnl,
+                label,
                 colors::RESET
             );
 
             if let Some(details) = position_details.get(i) {
                 for pos in details.iter().take(3) {
-                    let percent_pnl = pos
-                        .get("percentPnl")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let current_value = pos
-                        .get("currentValue")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let avg_price = pos
-                        .get("avgPrice")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let cur_price = pos
-                        .get("curPrice")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0);
-                    let outcome = pos
-                        .get("outcome")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
-                    let title = pos
-                        .get("title")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown");
+                    let percent_pnl = pos.percent_pnl.unwrap_or(0.0);
+                    let current_value = pos.current_value.unwrap_or(0.0);
+                    let avg_price = pos.avg_price.unwrap_or(0.0);
+                    let cur_price = pos.cur_price.unwrap_or(0.0);
+                    let outcome = pos.outcome.as_deref().unwrap_or("Unknown");
+                    let title = pos.title.as_deref().unwrap_or("Unknown");
                     let title_display = if title.len() > 40 {
                         format!("{}...", &title[..40])
                     } else {
@@ -580,3 +1259,130 @@ pub struct TradeDetails {
     pub transaction_hash: Option<String>,
     pub title: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADER: &str = "0x1111111111111111111111111111111111111111";
+
+    fn signal(
+        burst: &mut Option<SignalBurst>,
+        asset: &str,
+        side: &str,
+        amount: f64,
+        now: i64,
+    ) -> BurstStep {
+        absorb_signal(
+            burst,
+            TRADER.to_string(),
+            asset.to_string(),
+            side.to_string(),
+            amount,
+            now,
+        )
+    }
+
+    #[test]
+    fn repeats_within_the_window_collapse_with_a_running_count() {
+        let mut burst = None;
+        assert_eq!(
+            signal(&mut burst, "123", "BUY", 10.0, 100),
+            BurstStep::Started { closed: None }
+        );
+        assert_eq!(
+            signal(&mut burst, "123", "BUY", 5.0, 103),
+            BurstStep::Continued
+        );
+        assert_eq!(
+            signal(&mut burst, "123", "BUY", 2.5, 107),
+            BurstStep::Continued
+        );
+        let b = burst.as_ref().unwrap();
+        assert_eq!((b.fills, b.total_usd), (3, 17.5));
+        assert!(b
+            .fill_line(2.5)
+            .ends_with("+$2.50 same order from 0x111111…1111: 3 fills, $17.50 total"));
+    }
+
+    #[test]
+    fn a_quiet_gap_closes_the_burst_with_a_summary() {
+        let mut burst = None;
+        signal(&mut burst, "123", "BUY", 10.0, 100);
+        signal(&mut burst, "123", "BUY", 5.0, 102);
+        let BurstStep::Started {
+            closed: Some(closed),
+        } = signal(&mut burst, "123", "BUY", 1.0, 102 + BURST_WINDOW_SECS + 1)
+        else {
+            panic!("the gap should start a new burst");
+        };
+        assert_eq!(
+            closed.summary_line(),
+            "Order from 0x111111…1111 done: BUY 2 fills, $15.00 total"
+        );
+        assert_eq!(burst.as_ref().unwrap().fills, 1);
+    }
+
+    #[test]
+    fn a_side_or_asset_change_starts_a_new_burst() {
+        let mut burst = None;
+        signal(&mut burst, "123", "BUY", 10.0, 100);
+        signal(&mut burst, "123", "BUY", 10.0, 101);
+        assert!(matches!(
+            signal(&mut burst, "123", "SELL", 10.0, 102),
+            BurstStep::Started { closed: Some(_) }
+        ));
+        // A single fill has nothing to summarise.
+        assert_eq!(
+            signal(&mut burst, "456", "SELL", 10.0, 103),
+            BurstStep::Started { closed: None }
+        );
+    }
+
+    #[test]
+    fn json_lines_are_plain_single_line_objects() {
+        let line = json_line(
+            "warn",
+            &format!("{}\nsecond line {}", Logger::money(2.5), colors::RESET),
+            json!({ "kind": "trade", "usd_size": 2.5 }),
+        );
+        assert!(!line.contains('\n') && !line.contains('\x1b'), "{}", line);
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["level"], "warn");
+        assert_eq!(parsed["kind"], "trade");
+        assert_eq!(parsed["usd_size"], 2.5);
+        assert_eq!(parsed["message"], "$ 2.50\nsecond line ");
+        assert!(parsed["timestamp"].as_str().is_some());
+
+        assert_eq!(LogFormat::parse("json"), Some(LogFormat::Json));
+        assert_eq!(LogFormat::parse(""), Some(LogFormat::Human));
+        assert_eq!(LogFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn copy_summary_groups_dollars_and_prints_cents() {
+        let mut summary = CopySummary {
+            number: 14,
+            side: "BUY".to_string(),
+            outcome: Some("Yes".to_string()),
+            usd: 23.5,
+            price: 0.41,
+            deployed_usd: 312.0,
+            skipped: 2,
+            balance: Some(1204.4),
+        };
+        assert_eq!(
+            format_copy_summary(&summary, false),
+            "Copy #14 today | $23.50 BUY YES @ 41¢ | day: $312 deployed, 2 skipped, balance $1,204"
+        );
+        summary.price = 0.004;
+        summary.outcome = None;
+        summary.balance = None;
+        assert_eq!(
+            format_copy_summary(&summary, false),
+            "Copy #14 today | $23.50 BUY @ 0.4¢ | day: $312 deployed, 2 skipped"
+        );
+        assert_eq!(whole_dollars(1_234_567.0), "$1,234,567");
+        assert_eq!(whole_dollars(-1500.0), "-$1,500");
+    }
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::EnvConfig;
+use crate::utils::fetch_data;
+
+/// Depth is summed over price levels within this distance of the best quote.
+const DEPTH_BAND: f64 = 0.02;
+
+/// Order book and price state of an asset at the moment a copy was executed or skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketContext {
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    /// Shares bid within 2¢ of the best bid.
+    pub bid_depth: f64,
+    /// Shares offered within 2¢ of the best ask.
+    pub ask_depth: f64,
+    pub last_trade_price: Option<f64>,
+    pub price_change_1h: Option<f64>,
+}
+
+fn parse_levels(book: &serde_json::Value, side: &str) -> Vec<(f64, f64)> {
+    book.get(side)
+        .and_then(|l| l.as_array())
+        .map(|levels| {
+            levels
+                .iter()
+                .filter_map(|l| {
+                    let price = l.get("price")?.as_str()?.parse().ok()?;
+                    let size = l.get("size")?.as_str()?.parse().ok()?;
+                    Some((price, size))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn depth_near(levels: &[(f64, f64)], best: Option<f64>) -> f64 {
+    let Some(best) = best else {
+        return 0.0;
+    };
+    levels
+        .iter()
+        .filter(|(p, _)| (p - best).abs() <= DEPTH_BAND + 1e-9)
+        .map(|(_, s)| s)
+        .sum()
+}
+
+impl MarketContext {
+    /// The book half of the context, from a `/book` response.
+    pub fn from_book(book: &serde_json::Value) -> Self {
+        let bids = parse_levels(book, "bids");
+        let asks = parse_levels(book, "asks");
+        let best_bid = bids.iter().map(|l| l.0).reduce(f64::max);
+        let best_ask = asks.iter().map(|l| l.0).reduce(f64::min);
+        Self {
+            best_bid,
+            best_ask,
+            spread: best_bid.zip(best_ask).map(|(b, a)| a - b),
+            bid_depth: depth_near(&bids, best_bid),
+            ask_depth: depth_near(&asks, best_ask),
+            ..Self::default()
+        }
+    }
+
+    /// This book with `prices`' last trade and 1-hour change.
+    pub fn with_prices(self, prices: &MarketContext) -> Self {
+        Self {
+            last_trade_price: prices.last_trade_price,
+            price_change_1h: prices.price_change_1h,
+            ..self
+        }
+    }
+}
+
+async fn get(http_client: &reqwest::Client, config: &EnvConfig, url: String) -> Option<serde_json::Value> {
+    fetch_data(http_client, &url, config.request_timeout_ms, 1)
+        .await
+        .ok()
+}
+
+/// The book half of the context from the CLOB, empty if the request fails.
+pub async fn fetch_book_context(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> MarketContext {
+    let base = config.clob_http_url.trim_end_matches('/');
+    get(http_client, config, format!("{}/book?token_id={}", base, asset))
+        .await
+        .map(|book| MarketContext::from_book(&book))
+        .unwrap_or_default()
+}
+
+/// The last trade and 1-hour change from the CLOB's last-trade and price-history endpoints,
+/// fetched concurrently. The book fields are left empty.
+pub async fn fetch_price_context(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> MarketContext {
+    let base = config.clob_http_url.trim_end_matches('/');
+    let (last, history) = tokio::join!(
+        get(http_client, config, format!("{}/last-trade-price?token_id={}", base, asset)),
+        get(
+            http_client,
+            config,
+            format!("{}/prices-history?market={}&interval=1h&fidelity=1", base, asset)
+        ),
+    );
+    let mut ctx = MarketContext::default();
+    if let Some(last) = last {
+        ctx.last_trade_price = last.get("price").and_then(|p| {
+            p.as_str()
+                .and_then(|s| s.parse().ok())
+                .or_else(|| p.as_f64())
+        });
+    }
+    if let Some(history) = history {
+        let points: Vec<f64> = history
+            .get("history")
+            .and_then(|h| h.as_array())
+            .map(|h| h.iter().filter_map(|p| p.get("p")?.as_f64()).collect())
+            .unwrap_or_default();
+        if let (Some(first), Some(last)) = (points.first(), points.last()) {
+            ctx.price_change_1h = Some(last - first);
+        }
+    }
+    ctx
+}
+
+/// Builds the whole context, its parts concurrently. Each part is best-effort: a failed
+/// request leaves its fields empty rather than failing the snapshot.
+pub async fn fetch_market_context(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    asset: &str,
+) -> MarketContext {
+    let (book, prices) = tokio::join!(
+        fetch_book_context(http_client, config, asset),
+        fetch_price_context(http_client, config, asset),
+    );
+    book.with_prices(&prices)
+}
@@ -1,54 +1,180 @@
 use anyhow::Result;
 use alloy::signers::local::PrivateKeySigner;
 use polymarket_client_sdk::clob::Client as ClobClient;
-use polymarket_client_sdk::auth::state::Authenticated;
+use polymarket_client_sdk::auth::state::{Authenticated, State};
 use polymarket_client_sdk::auth::Normal;
-use polymarket_client_sdk::clob::types::{OrderType as SdkOrderType, Amount, Side};
+use polymarket_client_sdk::clob::types::{OrderType as SdkOrderType, Amount, Side, SignedOrder};
+use polymarket_client_sdk::clob::types::response::PostOrderResponse;
+use polymarket_client_sdk::POLYGON;
 use polymarket_client_sdk::types::Decimal;
+use std::future::Future;
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::config::{get_trade_multiplier, EnvConfig};
-use crate::types::{UserActivity, UserPosition};
-use crate::utils::{fetch_data, Logger};
+use crate::chaos::{Chaos, ChaosGateway};
+use crate::config::{EmptyBookPolicy, EnvConfig, SizingInputs, SlippageAction};
+use crate::metadata_cache;
+use crate::order_intents::{self, OrderIntent, Outcome};
+use crate::order_templates::{self, OrderTemplate, TickRegime};
+use crate::position_action::FULL_EXIT_REMAINDER;
+use crate::profiling::{self, StageGuard};
+use crate::resting_orders::{self, OrderEvent, RestingOrder, TraderQuote};
+use crate::watchdog;
+use crate::types::{MarketMetadata, UserActivity, UserPosition};
+use crate::utils::{fetch_data, Logger, MarketContext};
 
 const MIN_ORDER_SIZE_USD: f64 = 1.0;
-const MIN_ORDER_SIZE_TOKENS: f64 = 1.0;
+pub(crate) const MIN_ORDER_SIZE_TOKENS: f64 = 1.0;
+
+/// What actually went through for one copied trade, summed over all partial orders.
+#[derive(Debug, Clone, Default)]
+pub struct OrderFill {
+    pub tokens: f64,
+    pub usd: f64,
+    /// CLOB ids of the orders that went through.
+    pub order_ids: Vec<String>,
+    /// Nothing filled, but a resting order or a position build was left working; its fills
+    /// are reported as they come in.
+    pub resting: bool,
+    /// Nothing was placed: the book had moved past `MAX_SLIPPAGE_PERCENT` from the trader's
+    /// price.
+    pub slipped: bool,
+    /// The exchange turned down the last order and no more were tried: `RETRY_LIMIT` failed
+    /// attempts, or a balance/allowance rejection.
+    pub rejected: bool,
+    /// The book as the first order read it, before any of ours moved it.
+    pub book: Option<MarketContext>,
+}
 
-fn extract_order_error(response: &serde_json::Value) -> Option<String> {
-    if response.is_null() {
-        return None;
+/// What every order is placed with: the config, the CLOB client and the signer, and the
+/// HTTP client for book and market lookups.
+#[derive(Clone, Copy)]
+pub struct OrderContext<'a> {
+    pub config: &'a EnvConfig,
+    pub clob_client: &'a ClobClient<Authenticated<Normal>>,
+    pub signer: &'a PrivateKeySigner,
+    pub http_client: &'a reqwest::Client,
+    /// `CHAOS_MODE`'s injector, which every post goes through when it is on.
+    pub chaos: Option<&'a Chaos>,
+    /// This copy's figures for the BUY strategy's sizing; the default for sells.
+    pub sizing: SizingInputs,
+}
+
+impl OrderContext<'_> {
+    async fn post(&self, signed: SignedOrder) -> Result<PostOrderResponse> {
+        let gateway = ChaosGateway {
+            inner: self.clob_client,
+            chaos: self.chaos,
+        };
+        gateway.post(signed).await
     }
+}
+
+/// Where signed orders go: the CLOB, or a stand-in wrapping it.
+pub trait OrderGateway: Sync {
+    fn post(&self, order: SignedOrder) -> impl Future<Output = Result<PostOrderResponse>> + Send;
+}
 
-    if let Some(s) = response.as_str() {
-        return Some(s.to_string());
+impl OrderGateway for ClobClient<Authenticated<Normal>> {
+    async fn post(&self, order: SignedOrder) -> Result<PostOrderResponse> {
+        Ok(self.post_order(order).await?)
     }
+}
 
-    if let Some(obj) = response.as_object() {
-        if let Some(error_val) = obj.get("error") {
-            if let Some(s) = error_val.as_str() {
-                return Some(s.to_string());
-            }
-            if let Some(nested) = error_val.as_object() {
-                if let Some(err) = nested.get("error").and_then(|v| v.as_str()) {
-                    return Some(err.to_string());
-                }
-                if let Some(msg) = nested.get("message").and_then(|v| v.as_str()) {
-                    return Some(msg.to_string());
-                }
-            }
+/// What an order on one market needs besides size and price: taken from the market's
+/// order template when one is cached, worked out on the spot otherwise.
+struct OrderTerms {
+    token_id: alloy::primitives::U256,
+    template: Option<OrderTemplate>,
+    /// Gamma's tick for the market, for when there is no template.
+    market_tick: Option<f64>,
+}
+
+impl OrderTerms {
+    fn for_asset(asset: &str, market: Option<&MarketMetadata>) -> Result<Self> {
+        let template = order_templates::get(asset);
+        let token_id = match template {
+            Some(t) => t.token_id,
+            None => order_templates::parse_token_id(asset)?,
+        };
+        let market_tick = market
+            .and_then(|m| m.tick_size)
+            .filter(|t| *t > 0.0 && *t < 1.0);
+        Ok(Self {
+            token_id,
+            template,
+            market_tick,
+        })
+    }
+
+    /// The template's tick, else Gamma's, else cent ticks.
+    fn ticks(&self) -> TickRegime {
+        match (self.template, self.market_tick) {
+            (Some(t), _) => t.ticks(),
+            (None, Some(tick)) => TickRegime::new(tick),
+            (None, None) => TickRegime::default(),
         }
+    }
+
+    /// `price` on the market's tick for its price regime, checked before it goes into an
+    /// order so a mis-rounded price is never sent.
+    fn price(&self, price: f64) -> Result<Decimal> {
+        let ticks = self.ticks();
+        let formatted = ticks.format_price(price);
+        ticks.check_price(price, formatted.parse()?)?;
+        Decimal::from_str(&formatted).map_err(|e| anyhow::anyhow!("{}", e))
+    }
 
-        if let Some(s) = obj.get("errorMsg").and_then(|v| v.as_str()) {
-            return Some(s.to_string());
+    /// Pushes the template, if any, into the client ahead of building an order at `price`.
+    fn apply<S: State>(&self, clob_client: &ClobClient<S>, price: f64) {
+        if let Some(template) = self.template {
+            template.apply(clob_client, price);
         }
+    }
+
+    /// Times order assembly and signing, split by template hit or miss.
+    fn stage(&self) -> StageGuard {
+        profiling::stage(if self.template.is_some() {
+            "order_build_template"
+        } else {
+            "order_build_full"
+        })
+    }
+}
+
+/// Smallest order the market takes, in tokens: Gamma's `orderMinSize` when known, never
+/// below `MIN_ORDER_SIZE_TOKENS`.
+fn min_order_tokens(market: Option<&MarketMetadata>) -> f64 {
+    market
+        .and_then(|m| m.min_order_size)
+        .unwrap_or(0.0)
+        .max(MIN_ORDER_SIZE_TOKENS)
+}
 
-        if let Some(s) = obj.get("message").and_then(|v| v.as_str()) {
-            return Some(s.to_string());
+/// The market's Gamma record, for its tick and minimum size. A failed lookup is logged and
+/// the order goes ahead on the CLOB template, or cent ticks and a one-token minimum.
+pub(crate) async fn market_metadata(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+    condition_id: Option<&str>,
+) -> Option<MarketMetadata> {
+    let condition_id = condition_id.filter(|c| !c.is_empty())?;
+    match metadata_cache::lookup(http_client, config, condition_id).await {
+        Ok(market) => Some(market),
+        Err(e) => {
+            Logger::warning(&format!(
+                "Market metadata for {} unavailable ({:#}); using the default tick and minimum size",
+                condition_id, e
+            ));
+            None
         }
     }
+}
 
-    None
+/// `tokens` rounded down to the CLOB's lot size (0.01 token): the SDK refuses finer sizes,
+/// and rounding up could sell more than is held.
+pub(crate) fn lot_size(tokens: f64) -> f64 {
+    (tokens * 100.0 + 1e-9).floor() / 100.0
 }
 
 fn is_insufficient_balance_or_allowance_error(message: Option<&str>) -> bool {
@@ -59,73 +185,408 @@ fn is_insufficient_balance_or_allowance_error(message: Option<&str>) -> bool {
     lower.contains("not enough balance") || lower.contains("allowance")
 }
 
+/// Share of their position the trader just sold: `sold` tokens against what they held
+/// before the sale (`held_after + sold`). Nothing, or dust, left is a full exit.
+pub(crate) fn trader_sell_fraction(sold: f64, held_after: Option<f64>) -> f64 {
+    if sold.is_nan() || sold <= 0.0 {
+        return 0.0;
+    }
+    let held_after = held_after.unwrap_or(0.0).max(0.0);
+    let before = held_after + sold;
+    if held_after / before < FULL_EXIT_REMAINDER {
+        1.0
+    } else {
+        sold / before
+    }
+}
+
+/// Best bid and ask when the book is crossed (bid >= ask), which only happens with stale or
+/// corrupt data; neither side's price can be trusted then.
+fn crossed_book(book: &serde_json::Value) -> Option<(f64, f64)> {
+    let best = |side: &str, pick: fn(f64, f64) -> f64| {
+        book.get(side)?
+            .as_array()?
+            .iter()
+            .filter_map(|l| l.get("price")?.as_str()?.parse::<f64>().ok())
+            .reduce(pick)
+    };
+    let bid = best("bids", f64::max)?;
+    let ask = best("asks", f64::min)?;
+    (bid >= ask).then_some((bid, ask))
+}
+
+/// `(price, size)` of each level on one side of a book.
+fn book_levels(levels: &[serde_json::Value]) -> Vec<(f64, f64)> {
+    levels
+        .iter()
+        .filter_map(|l| {
+            let price: f64 = l.get("price")?.as_str()?.parse().ok()?;
+            let size: f64 = l.get("size")?.as_str()?.parse().ok()?;
+            Some((price, size))
+        })
+        .collect()
+}
+
+/// How much worse than the trader's price `book_price` is, in percent: above it for a BUY,
+/// below it for a SELL. Negative when the book is better.
+fn slippage_percent(buy: bool, trader_price: f64, book_price: f64) -> f64 {
+    let worse = if buy {
+        book_price - trader_price
+    } else {
+        trader_price - book_price
+    };
+    worse / trader_price * 100.0
+}
+
+/// The worst price `MAX_SLIPPAGE_PERCENT` lets a copy take, or `None` when the guard is off
+/// or the trade has no usable price to compare against.
+fn slippage_limit(config: &EnvConfig, buy: bool, trader_price: Option<f64>) -> Option<f64> {
+    if config.max_slippage_percent <= 0.0 {
+        return None;
+    }
+    let price = trader_price.filter(|p| *p > 0.0 && *p < 1.0)?;
+    let factor = config.max_slippage_percent / 100.0;
+    Some(if buy {
+        price * (1.0 + factor)
+    } else {
+        price * (1.0 - factor)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SlippageCheck {
+    Within,
+    /// Only this much (USD for a BUY, tokens for a SELL) is on the book within the limit.
+    Shrink(f64),
+    Skip(String),
+}
+
+/// The slippage guard's call on an order of `amount` (USD for a BUY, tokens for a SELL)
+/// against one side of the book, before anything is placed. The best level past `limit`
+/// always skips; too little within it skips or shrinks per `SLIPPAGE_ACTION`.
+fn check_slippage(
+    action: SlippageAction,
+    buy: bool,
+    trader_price: f64,
+    limit: f64,
+    levels: &[(f64, f64)],
+    amount: f64,
+    min_amount: f64,
+) -> SlippageCheck {
+    let within = |price: f64| if buy { price <= limit + 1e-9 } else { price >= limit - 1e-9 };
+    let best = levels
+        .iter()
+        .map(|l| l.0)
+        .reduce(if buy { f64::min } else { f64::max });
+    let Some(best) = best else {
+        return SlippageCheck::Within;
+    };
+    let (side, unit) = if buy { ("ask", "$") } else { ("bid", "") };
+    if !within(best) {
+        return SlippageCheck::Skip(format!(
+            "best {} ${:.4} is {:.1}% worse than the trader's ${:.4} (max {:.1}%)",
+            side,
+            best,
+            slippage_percent(buy, trader_price, best),
+            trader_price,
+            (limit - trader_price).abs() / trader_price * 100.0
+        ));
+    }
+    let depth: f64 = levels
+        .iter()
+        .filter(|l| within(l.0))
+        .map(|l| if buy { l.0 * l.1 } else { l.1 })
+        .sum();
+    let depth = if buy { depth } else { lot_size(depth) };
+    if depth >= amount {
+        return SlippageCheck::Within;
+    }
+    let short = format!(
+        "only {}{:.2}{} on the book within ${:.4} (wanted {}{:.2})",
+        unit,
+        depth,
+        if buy { "" } else { " tokens" },
+        limit,
+        unit,
+        amount
+    );
+    if action == SlippageAction::Skip {
+        return SlippageCheck::Skip(format!("{} (SLIPPAGE_ACTION=SKIP)", short));
+    }
+    if depth < min_amount {
+        return SlippageCheck::Skip(format!("{}, below the order minimum", short));
+    }
+    SlippageCheck::Shrink(depth)
+}
+
+/// The price to rest an empty-book order at, or why no order is placed.
+fn resting_price(
+    policy: EmptyBookPolicy,
+    trader_price: Option<f64>,
+    tokens: f64,
+    min_tokens: f64,
+) -> std::result::Result<f64, String> {
+    if policy == EmptyBookPolicy::Skip {
+        return Err("skipping (EMPTY_BOOK_POLICY=SKIP)".to_string());
+    }
+    let Some(price) = trader_price.filter(|p| *p > 0.0 && *p < 1.0) else {
+        return Err("no usable trader price, skipping".to_string());
+    };
+    if tokens < min_tokens {
+        return Err(format!(
+            "{:.2} tokens is below the market's {} token minimum for a resting order",
+            tokens, min_tokens
+        ));
+    }
+    Ok(price)
+}
+
+/// Places a GTD limit order good until `expires_at`, on `market`'s tick when there is no
+/// template. The inner error is the exchange's rejection message, or the market's minimum
+/// size when `tokens` is under it.
+pub(crate) async fn place_limit_order(
+    order: &OrderContext<'_>,
+    asset: &str,
+    market: Option<&MarketMetadata>,
+    side: Side,
+    tokens: f64,
+    price: f64,
+    expires_at: i64,
+) -> Result<std::result::Result<String, String>> {
+    let min_tokens = min_order_tokens(market);
+    if tokens < min_tokens {
+        return Ok(Err(format!(
+            "{:.2} tokens is below the market's {} token minimum",
+            tokens, min_tokens
+        )));
+    }
+    let OrderContext {
+        clob_client,
+        signer,
+        ..
+    } = *order;
+    let exp = chrono::DateTime::from_timestamp(expires_at, 0)
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))?;
+    let terms = OrderTerms::for_asset(asset, market)?;
+    let decimal_size =
+        Decimal::from_str(&format!("{:.2}", tokens)).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let decimal_price = terms.price(price)?;
+    let build_timer = terms.stage();
+    terms.apply(clob_client, price);
+    let unsigned = clob_client
+        .limit_order()
+        .token_id(terms.token_id)
+        .size(decimal_size)
+        .price(decimal_price)
+        .side(side)
+        .order_type(SdkOrderType::GTD)
+        .expiration(exp)
+        .build()
+        .await?;
+    let signed = clob_client.sign(signer, unsigned).await?;
+    drop(build_timer);
+    let resp = order.post(signed).await?;
+    Ok(match resp.error_msg.filter(|m| !m.is_empty()) {
+        Some(msg) => Err(msg),
+        None => Ok(resp.order_id),
+    })
+}
+
+/// The intent for a copy order of `tokens` (`usd`) at `price`, before it has an id.
+fn intent(trade: &UserActivity, trader: &str, side: &str, tokens: f64, usd: f64, price: f64) -> OrderIntent {
+    OrderIntent {
+        order_id: String::new(),
+        copy_id: trade.transaction_hash.clone().unwrap_or_default(),
+        trader: trader.to_lowercase(),
+        asset: trade.asset.clone().unwrap_or_default(),
+        side: side.to_string(),
+        tokens,
+        usd,
+        price,
+        created_at: chrono::Utc::now().timestamp(),
+    }
+}
+
+/// Posts a copy order behind the order intent log: the intent is on disk before the order
+/// leaves, and the outcome once the CLOB answers. A transport error leaves the intent pending
+/// for startup to look up.
+async fn submit(
+    order: &OrderContext<'_>,
+    signed: SignedOrder,
+    mut intent: OrderIntent,
+) -> Result<PostOrderResponse> {
+    let OrderContext {
+        config,
+        clob_client,
+        signer,
+        ..
+    } = *order;
+    let neg_risk = match order_templates::get(&intent.asset) {
+        Some(template) => template.neg_risk,
+        None => clob_client.neg_risk(signed.order.tokenId).await?.neg_risk,
+    };
+    let chain_id = signer.chain_id().unwrap_or(POLYGON);
+    intent.order_id = order_intents::order_id(&signed.order, chain_id, neg_risk)
+        .ok_or_else(|| anyhow::anyhow!("No exchange contract for chain {}", chain_id))?;
+    order_intents::record_intent(&config.state_dir, &intent)?;
+    let resp = order.post(signed).await?;
+    let outcome = match resp.error_msg.as_deref().filter(|m| !m.is_empty()) {
+        Some(msg) => Outcome::Rejected {
+            reason: msg.to_string(),
+        },
+        None => Outcome::Filled {
+            tokens: intent.tokens,
+            usd: intent.usd,
+        },
+    };
+    order_intents::record_outcome(&config.state_dir, &intent.order_id, outcome);
+    Ok(resp)
+}
+
+/// Applies `EMPTY_BOOK_POLICY` when the side we would take from has no resting orders:
+/// either skip, or rest a GTD limit order at the trader's price. A resting order never falls
+/// back to a market order; its fills are reported by the resting-order poller. With
+/// `FOLLOW_TRADER_CANCELS` the size already at our price in `book` is kept with the order as
+/// the trader's, for the poller to follow their cancel. Returns whether an order was placed.
+async fn handle_empty_book(
+    order: &OrderContext<'_>,
+    trade: &UserActivity,
+    book: &serde_json::Value,
+    market: Option<&MarketMetadata>,
+    side: Side,
+    tokens: f64,
+    trader: &str,
+) -> Result<bool> {
+    let config = order.config;
+    let (side_label, empty_side) = match side {
+        Side::Buy => ("BUY", "asks"),
+        _ => ("SELL", "bids"),
+    };
+    let min_tokens = min_order_tokens(market);
+    let price = match resting_price(config.empty_book_policy, trade.price, tokens, min_tokens) {
+        Ok(price) => price,
+        Err(reason) => {
+            Logger::warning(&format!("No {} in order book - {}", empty_side, reason));
+            return Ok(false);
+        }
+    };
+    let asset = trade.asset.as_deref().unwrap_or("");
+    // The CLOB rejects GTD expirations less than a minute out, so the TTL starts after that.
+    let expires_at =
+        chrono::Utc::now().timestamp() + 60 + config.empty_book_order_ttl_secs as i64;
+    let order_id = match place_limit_order(
+        order,
+        asset,
+        market,
+        side,
+        tokens,
+        price,
+        expires_at,
+    )
+    .await?
+    {
+        Ok(order_id) => order_id,
+        Err(msg) => {
+            Logger::warning(&format!("Resting {} order rejected: {}", side_label, msg));
+            return Ok(false);
+        }
+    };
+    Logger::info(&format!(
+        "No {} in order book - resting {} {:.2} @ ${:.2} for {}s (EMPTY_BOOK_POLICY=LIMIT)",
+        empty_side, side_label, tokens, price, config.empty_book_order_ttl_secs
+    ));
+    let resting = RestingOrder {
+        copy_id: trade
+            .transaction_hash
+            .clone()
+            .unwrap_or_else(|| order_id.clone()),
+        order_id,
+        asset: asset.to_string(),
+        side: side_label.to_string(),
+        trader: trader.to_lowercase(),
+        price,
+        size: tokens,
+        placed_at: chrono::Utc::now().timestamp(),
+        expires_at,
+        condition_id: trade.condition_id.clone(),
+        filled_tokens: 0.0,
+        matched_before: 0.0,
+        quote: config.follow_trader_cancels.then(|| TraderQuote {
+            tokens: resting_orders::level_tokens(book, side_label, price),
+            ..TraderQuote::default()
+        }),
+    };
+    resting_orders::report(&resting, &OrderEvent::Placed);
+    resting_orders::record(&config.state_dir, resting);
+    Ok(true)
+}
+
 pub async fn post_order(
-    config: &EnvConfig,
-    clob_client: &ClobClient<Authenticated<Normal>>,
+    order: &OrderContext<'_>,
     condition: &str,
     my_position: Option<&UserPosition>,
     user_position: Option<&UserPosition>,
     trade: &UserActivity,
     my_balance: f64,
-    _user_balance: f64,
     user_address: &str,
-    http_client: &reqwest::Client,
-    signer: &mut PrivateKeySigner,
-) -> Result<()> {
-    match condition {
-        "merge" => {
-            execute_merge_strategy(config, trade, my_position, user_address, clob_client, http_client, signer).await?;
-        }
-        "buy" => {
-            execute_buy_strategy(config, trade, my_position, my_balance, user_address, clob_client, http_client, signer).await?;
-        }
+) -> Result<OrderFill> {
+    let fill = match condition {
+        "merge" => execute_merge_strategy(order, trade, my_position, user_address).await?,
+        "buy" => execute_buy_strategy(order, trade, my_position, my_balance, user_address).await?,
         "sell" => {
-            execute_sell_strategy(config, trade, my_position, user_position, user_address, clob_client, http_client, signer).await?;
+            execute_sell_strategy(order, trade, my_position, user_position, user_address).await?
         }
         _ => {
             Logger::error(&format!("Unknown condition: {}", condition));
+            OrderFill::default()
         }
-    }
-    Ok(())
+    };
+    Ok(fill)
 }
 
 async fn execute_merge_strategy(
-    config: &EnvConfig,
+    order: &OrderContext<'_>,
     trade: &UserActivity,
     my_position: Option<&UserPosition>,
     user_address: &str,
-    clob_client: &ClobClient<Authenticated<Normal>>,
-    http_client: &reqwest::Client,
-    signer: &mut PrivateKeySigner,
-) -> Result<()> {
+) -> Result<OrderFill> {
+    let OrderContext {
+        config,
+        clob_client,
+        signer,
+        http_client,
+        ..
+    } = *order;
     Logger::info("Executing MERGE strategy...");
     
     let my_position = match my_position {
         Some(p) => p,
         None => {
             Logger::warning("No position to merge");
-            return Ok(());
+            return Ok(OrderFill::default());
         }
     };
 
     let asset = trade.asset.as_deref().unwrap_or("");
     if asset.is_empty() {
         Logger::warning("No asset specified");
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
+    let market = market_metadata(http_client, config, trade.condition_id.as_deref()).await;
+    let min_tokens = min_order_tokens(market.as_ref());
     let mut remaining = my_position.size.unwrap_or(0.0);
 
-    if remaining < MIN_ORDER_SIZE_TOKENS {
+    if remaining < min_tokens {
         Logger::warning(&format!(
             "Position size ({:.2} tokens) too small to merge - skipping",
             remaining
         ));
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
     let mut retry = 0u32;
-    let mut abort_due_to_funds = false;
+    let mut fill = OrderFill::default();
 
     while remaining > 0.0 && retry < config.retry_limit {
         let book_url = format!(
@@ -140,7 +601,8 @@ async fn execute_merge_strategy(
             config.network_retry_limit,
         )
         .await?;
-        
+        fill.book.get_or_insert_with(|| MarketContext::from_book(&book));
+
         let bids = book
             .get("bids")
             .and_then(|b| b.as_array())
@@ -151,19 +613,10 @@ async fn execute_merge_strategy(
             break;
         }
 
-        let best_bid = bids
+        let levels = book_levels(bids);
+        let best_bid = levels
             .iter()
-            .filter_map(|b| {
-                let price: f64 = b
-                    .get("price")
-                    .and_then(|p| p.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                let size: f64 = b
-                    .get("size")
-                    .and_then(|s| s.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                Some((price, size))
-            })
+            .copied()
             .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
         let (price, size) = match best_bid {
@@ -176,37 +629,34 @@ async fn execute_merge_strategy(
 
         Logger::info(&format!("Best bid: {} @ ${:.4}", size, price));
 
-        let sell_amount = if remaining <= size {
+        let sell_amount = lot_size(if remaining <= size {
             remaining
         } else {
             size
-        };
+        });
+        if sell_amount <= 0.0 {
+            break;
+        }
 
-        let exp_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 90;
-        let exp = chrono::DateTime::from_timestamp(exp_secs as i64, 0)
-            .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))?;
-        let token_id = alloy::primitives::U256::from_str_radix(
-            asset.trim_start_matches("0x"),
-            16,
-        )
-        .or_else(|_| alloy::primitives::U256::from_str(&asset))?;
-        let decimal_size = Decimal::from_str(&format!("{:.4}", sell_amount))
+        let terms = OrderTerms::for_asset(asset, market.as_ref())?;
+        let decimal_size = Decimal::from_str(&format!("{:.2}", sell_amount))
             .map_err(|e| anyhow::anyhow!("{}", e))?;
-        let decimal_price =
-            Decimal::from_str(&format!("{:.2}", price))
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-        let order = clob_client
+        let decimal_price = terms.price(price)?;
+        let build_timer = terms.stage();
+        terms.apply(clob_client, price);
+        let unsigned = clob_client
             .limit_order()
-            .token_id(token_id)
+            .token_id(terms.token_id)
             .size(decimal_size)
             .price(decimal_price)
             .side(Side::Sell)
             .order_type(SdkOrderType::FOK)
-            .expiration(exp)
             .build()
             .await?;
-        let signed = clob_client.sign(&signer, order).await?;
-        let resp = clob_client.post_order(signed).await?;
+        let signed = clob_client.sign(signer, unsigned).await?;
+        drop(build_timer);
+        let copy = intent(trade, user_address, "SELL", sell_amount, sell_amount * price, price);
+        let resp = submit(order, signed, copy).await?;
 
         let error_msg = resp.error_msg.as_deref();
 
@@ -216,10 +666,13 @@ async fn execute_merge_strategy(
                 true,
                 &format!("Sold {:.2} tokens at ${:.4}", sell_amount, price),
             );
+            fill.tokens += sell_amount;
+            fill.usd += sell_amount * price;
+            fill.order_ids.push(resp.order_id.clone());
+            watchdog::note_bot_fill(asset, "SELL", sell_amount);
             remaining -= sell_amount;
         } else {
             if is_insufficient_balance_or_allowance_error(error_msg) {
-                abort_due_to_funds = true;
                 Logger::warning(&format!(
                     "Order rejected: {}",
                     error_msg.unwrap_or("Insufficient balance or allowance")
@@ -227,6 +680,8 @@ async fn execute_merge_strategy(
                 Logger::warning(
                     "Skipping remaining attempts. Top up funds or check allowance.",
                 );
+                fill.rejected = true;
+                crate::ctf_approval::hint_after_sell_rejection();
                 break;
             }
             retry += 1;
@@ -238,20 +693,26 @@ async fn execute_merge_strategy(
             ));
         }
     }
+    fill.rejected |= retry >= config.retry_limit;
 
-    Ok(())
+    Ok(fill)
 }
 
 async fn execute_buy_strategy(
-    config: &EnvConfig,
+    order: &OrderContext<'_>,
     trade: &UserActivity,
     my_position: Option<&UserPosition>,
     my_balance: f64,
     user_address: &str,
-    clob_client: &ClobClient<Authenticated<Normal>>,
-    http_client: &reqwest::Client,
-    signer: &mut PrivateKeySigner,
-) -> Result<()> {
+) -> Result<OrderFill> {
+    let OrderContext {
+        config,
+        clob_client,
+        signer,
+        http_client,
+        sizing,
+        ..
+    } = *order;
     Logger::info("Executing BUY strategy...");
     Logger::info(&format!("Your balance: ${:.2}", my_balance));
     Logger::info(&format!("Trader bought: ${:.2}", trade.usdc_size.unwrap_or(0.0)));
@@ -259,7 +720,7 @@ async fn execute_buy_strategy(
     let asset = trade.asset.as_deref().unwrap_or("");
     if asset.is_empty() {
         Logger::warning("No asset specified");
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
     let current_position_value = my_position
@@ -268,6 +729,7 @@ async fn execute_buy_strategy(
 
     let order_calc = crate::config::calculate_order_size(
         &config.copy_strategy_config,
+        &sizing,
         trade.usdc_size.unwrap_or(0.0),
         my_balance,
         current_position_value,
@@ -280,15 +742,20 @@ async fn execute_buy_strategy(
         if order_calc.below_minimum {
             Logger::warning("💡 Increase COPY_SIZE or wait for larger trades");
         }
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
+    let market = market_metadata(http_client, config, trade.condition_id.as_deref()).await;
+    let min_tokens = min_order_tokens(market.as_ref());
+
     let mut remaining = order_calc.final_amount;
     let mut available_balance = my_balance;
+    let max_price = slippage_limit(config, true, trade.price);
+    let mut slippage_checked = false;
 
     let mut retry = 0u32;
-    let mut abort_due_to_funds = false;
     let mut total_bought_tokens = 0.0;
+    let mut fill = OrderFill::default();
 
     while remaining > 0.0 && retry < config.retry_limit {
         let book_url = format!(
@@ -303,30 +770,43 @@ async fn execute_buy_strategy(
             config.network_retry_limit,
         )
         .await?;
-        
+        fill.book.get_or_insert_with(|| MarketContext::from_book(&book));
+
         let asks = book
             .get("asks")
             .and_then(|a| a.as_array())
             .ok_or_else(|| anyhow::anyhow!("No asks"))?;
 
         if asks.is_empty() {
-            Logger::warning("No asks available in order book");
+            if fill.tokens <= 0.0 {
+                let tokens = trade.price.map(|p| remaining / p).unwrap_or(0.0);
+                fill.resting = handle_empty_book(
+                    order,
+                    trade,
+                    &book,
+                    market.as_ref(),
+                    Side::Buy,
+                    tokens,
+                    user_address,
+                )
+                .await?;
+            } else {
+                Logger::warning("No asks available in order book");
+            }
+            break;
+        }
+        if let Some((bid, ask)) = crossed_book(&book) {
+            Logger::warning(&format!(
+                "Crossed book (bid ${:.4} >= ask ${:.4}) - prices unreliable, skipping",
+                bid, ask
+            ));
             break;
         }
 
-        let best_ask = asks
+        let levels = book_levels(asks);
+        let best_ask = levels
             .iter()
-            .filter_map(|a| {
-                let price: f64 = a
-                    .get("price")
-                    .and_then(|p| p.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                let size: f64 = a
-                    .get("size")
-                    .and_then(|s| s.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                Some((price, size))
-            })
+            .copied()
             .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
         let (best_price, best_size) = match best_ask {
@@ -339,6 +819,46 @@ async fn execute_buy_strategy(
 
         Logger::info(&format!("Best ask: {} @ ${:.4}", best_size, best_price));
 
+        if let Some((limit, trader_price)) = max_price.zip(trade.price) {
+            if !slippage_checked {
+                slippage_checked = true;
+                match check_slippage(
+                    config.slippage_action,
+                    true,
+                    trader_price,
+                    limit,
+                    &levels,
+                    remaining,
+                    MIN_ORDER_SIZE_USD,
+                ) {
+                    SlippageCheck::Within => Logger::info(&format!(
+                        "🛡️  Slippage guard: best ask is {:.1}% from the trader's ${:.4}, within {:.1}%",
+                        slippage_percent(true, trader_price, best_price),
+                        trader_price,
+                        config.max_slippage_percent
+                    )),
+                    SlippageCheck::Shrink(usd) => {
+                        Logger::warning(&format!(
+                            "🛡️  Slippage guard: shrinking ${:.2} to ${:.2}, what the book has within ${:.4}",
+                            remaining, usd, limit
+                        ));
+                        remaining = usd;
+                    }
+                    SlippageCheck::Skip(why) => {
+                        Logger::warning(&format!("🛡️  Slippage guard: {}, skipping", why));
+                        fill.slipped = true;
+                        break;
+                    }
+                }
+            } else if best_price > limit + 1e-9 {
+                Logger::warning(&format!(
+                    "🛡️  Slippage guard: best ask ${:.4} is past the ${:.4} limit, stopping",
+                    best_price, limit
+                ));
+                break;
+            }
+        }
+
         if remaining < MIN_ORDER_SIZE_USD {
             Logger::info(&format!(
                 "Remaining amount (${:.2}) below minimum - completing trade",
@@ -358,12 +878,20 @@ async fn execute_buy_strategy(
             break;
         }
 
+        if order_size / best_price < min_tokens {
+            Logger::info(&format!(
+                "Order size ({:.2} tokens) below the market's {} token minimum - completing trade",
+                order_size / best_price,
+                min_tokens
+            ));
+            break;
+        }
+
         if available_balance < order_size {
             Logger::warning(&format!(
                 "Insufficient balance: Need ${:.2} but only have ${:.2}",
                 order_size, available_balance
             ));
-            abort_due_to_funds = true;
             break;
         }
 
@@ -375,25 +903,25 @@ async fn execute_buy_strategy(
         let exp_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 90;
         let exp = chrono::DateTime::from_timestamp(exp_secs as i64, 0)
             .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))?;
-        let token_id = alloy::primitives::U256::from_str_radix(
-            asset.trim_start_matches("0x"),
-            16,
-        )
-        .or_else(|_| alloy::primitives::U256::from_str(&asset))?;
+        let terms = OrderTerms::for_asset(asset, market.as_ref())?;
         let decimal_amount =
             Decimal::from_str(&format!("{:.2}", order_size))
                 .map_err(|e| anyhow::anyhow!("Decimal: {}", e))?;
-        let order = clob_client
+        let build_timer = terms.stage();
+        terms.apply(clob_client, best_price);
+        let unsigned = clob_client
             .market_order()
-            .token_id(token_id)
+            .token_id(terms.token_id)
             .amount(Amount::usdc(decimal_amount)?)
             .side(Side::Buy)
             .order_type(SdkOrderType::FOK)
             .expiration(exp)
             .build()
             .await?;
-        let signed = clob_client.sign(&signer, order).await?;
-        let resp = clob_client.post_order(signed).await?;
+        let signed = clob_client.sign(signer, unsigned).await?;
+        drop(build_timer);
+        let copy = intent(trade, user_address, "BUY", order_size / best_price, order_size, best_price);
+        let resp = submit(order, signed, copy).await?;
 
         let error_msg = resp.error_msg.as_deref();
 
@@ -401,6 +929,10 @@ async fn execute_buy_strategy(
             retry = 0;
             let tokens_bought = order_size / best_price;
             total_bought_tokens += tokens_bought;
+            fill.tokens += tokens_bought;
+            fill.usd += order_size;
+            fill.order_ids.push(resp.order_id.clone());
+            watchdog::note_bot_fill(asset, "BUY", tokens_bought);
             Logger::order_result(
                 true,
                 &format!(
@@ -412,7 +944,6 @@ async fn execute_buy_strategy(
             available_balance -= order_size;
         } else {
             if is_insufficient_balance_or_allowance_error(error_msg) {
-                abort_due_to_funds = true;
                 Logger::warning(&format!(
                     "Order rejected: {}",
                     error_msg.unwrap_or("Insufficient balance or allowance")
@@ -420,6 +951,7 @@ async fn execute_buy_strategy(
                 Logger::warning(
                     "Skipping remaining attempts. Top up funds or check allowance.",
                 );
+                fill.rejected = true;
                 break;
             }
             retry += 1;
@@ -431,6 +963,7 @@ async fn execute_buy_strategy(
             ));
         }
     }
+    fill.rejected |= retry >= config.retry_limit;
 
     if total_bought_tokens > 0.0 {
         Logger::info(&format!(
@@ -439,103 +972,75 @@ async fn execute_buy_strategy(
         ));
     }
 
-    Ok(())
+    Ok(fill)
 }
 
 async fn execute_sell_strategy(
-    config: &EnvConfig,
+    order: &OrderContext<'_>,
     trade: &UserActivity,
     my_position: Option<&UserPosition>,
     user_position: Option<&UserPosition>,
     user_address: &str,
-    clob_client: &ClobClient<Authenticated<Normal>>,
-    http_client: &reqwest::Client,
-    signer: &mut PrivateKeySigner,
-) -> Result<()> {
+) -> Result<OrderFill> {
+    let OrderContext {
+        config,
+        clob_client,
+        signer,
+        http_client,
+        ..
+    } = *order;
     Logger::info("Executing SELL strategy...");
 
-    let my_position = match my_position {
-        Some(p) => p,
-        None => {
-            Logger::warning("No position to sell");
-            return Ok(());
-        }
+    let condition = trade.condition_id.as_deref().unwrap_or("this market");
+    let Some(my_position) = my_position.filter(|p| p.size.unwrap_or(0.0) > 0.0) else {
+        Logger::warning(&format!(
+            "Skipping SELL: you hold no position in {}, nothing to mirror",
+            condition
+        ));
+        return Ok(OrderFill::default());
     };
 
     let asset = trade.asset.as_deref().unwrap_or("");
     if asset.is_empty() {
         Logger::warning("No asset specified");
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
-    let total_bought_tokens = my_position.size.unwrap_or(0.0);
+    let market = market_metadata(http_client, config, trade.condition_id.as_deref()).await;
+    let min_tokens = min_order_tokens(market.as_ref());
 
-    if total_bought_tokens > 0.0 {
-        Logger::info(&format!(
-            "📊 Current position: {:.2} tokens",
-            total_bought_tokens
-        ));
-    }
+    let my_tokens = my_position.size.unwrap_or(0.0);
+    Logger::info(&format!("📊 Current position: {:.2} tokens", my_tokens));
 
-    let mut remaining = if user_position.is_none() {
+    // Sized from the trader's position, not their notional: selling $2,000 of a $4,000
+    // position sells half of mine whatever mine is worth.
+    let sold = trade.size.unwrap_or(0.0);
+    let held_after = user_position.and_then(|p| p.size);
+    let fraction = trader_sell_fraction(sold, held_after);
+    if fraction >= 1.0 {
         Logger::info(&format!(
-            "Trader closed entire position → Selling all your {:.2} tokens",
-            my_position.size.unwrap_or(0.0)
+            "Trader exited their position → Selling all your {:.2} tokens",
+            my_tokens
         ));
-        my_position.size.unwrap_or(0.0)
     } else {
-        let up = user_position.unwrap();
-        let trader_sell_percent = trade.size.unwrap_or(0.0)
-            / (up.size.unwrap_or(0.0) + trade.size.unwrap_or(0.0));
-        let trader_position_before = up.size.unwrap_or(0.0) + trade.size.unwrap_or(0.0);
-
-        Logger::info(&format!(
-            "Position comparison: Trader has {:.2} tokens, You have {:.2} tokens",
-            trader_position_before,
-            my_position.size.unwrap_or(0.0)
-        ));
         Logger::info(&format!(
-            "Trader selling: {:.2} tokens ({:.2}% of their position)",
-            trade.size.unwrap_or(0.0),
-            trader_sell_percent * 100.0
+            "Trader sold {:.2} of {:.2} tokens ({:.2}%) → selling {:.2} of your {:.2} tokens",
+            sold,
+            held_after.unwrap_or(0.0) + sold,
+            fraction * 100.0,
+            my_tokens * fraction,
+            my_tokens
         ));
+    }
+    let mut remaining = my_tokens * fraction;
 
-        let base_sell_size = {
-            Logger::info(&format!(
-                "Calculating from current position: {:.2} × {:.2}% = {:.2} tokens",
-                my_position.size.unwrap_or(0.0),
-                trader_sell_percent * 100.0,
-                my_position.size.unwrap_or(0.0) * trader_sell_percent
-            ));
-            my_position.size.unwrap_or(0.0) * trader_sell_percent
-        };
-
-        let multiplier = get_trade_multiplier(
-            &config.copy_strategy_config,
-            trade.usdc_size.unwrap_or(0.0),
-        );
-        let calculated = base_sell_size * multiplier;
-
-        if (multiplier - 1.0).abs() > 1e-9 {
-            Logger::info(&format!(
-                "Applying {}x multiplier (based on trader's ${:.2} order): {:.2} → {:.2} tokens",
-                multiplier,
-                trade.usdc_size.unwrap_or(0.0),
-                base_sell_size,
-                calculated
-            ));
-        }
-
-        calculated
-    };
-
-    if remaining < MIN_ORDER_SIZE_TOKENS {
+    if remaining < min_tokens {
         Logger::warning(&format!(
             "❌ Cannot execute: Sell amount {:.2} tokens below minimum ({:.2} token)",
-            remaining, MIN_ORDER_SIZE_TOKENS
+            remaining, min_tokens
         ));
         Logger::warning("💡 This happens when position sizes are too small or mismatched");
-        return Ok(());
+        return Ok(OrderFill::default());
     }
 
     if remaining > my_position.size.unwrap_or(0.0) {
@@ -551,9 +1056,12 @@ async fn execute_sell_strategy(
         remaining = my_position.size.unwrap_or(0.0);
     }
 
+    let min_price = slippage_limit(config, false, trade.price);
+    let mut slippage_checked = false;
+
     let mut retry = 0u32;
-    let mut abort_due_to_funds = false;
     let mut total_sold_tokens = 0.0;
+    let mut fill = OrderFill::default();
 
     while remaining > 0.0 && retry < config.retry_limit {
         let book_url = format!(
@@ -568,30 +1076,42 @@ async fn execute_sell_strategy(
             config.network_retry_limit,
         )
         .await?;
-        
+        fill.book.get_or_insert_with(|| MarketContext::from_book(&book));
+
         let bids = book
             .get("bids")
             .and_then(|b| b.as_array())
             .ok_or_else(|| anyhow::anyhow!("No bids"))?;
 
         if bids.is_empty() {
-            Logger::warning("No bids available in order book");
+            if fill.tokens <= 0.0 {
+                fill.resting = handle_empty_book(
+                    order,
+                    trade,
+                    &book,
+                    market.as_ref(),
+                    Side::Sell,
+                    remaining,
+                    user_address,
+                )
+                .await?;
+            } else {
+                Logger::warning("No bids available in order book");
+            }
+            break;
+        }
+        if let Some((bid, ask)) = crossed_book(&book) {
+            Logger::warning(&format!(
+                "Crossed book (bid ${:.4} >= ask ${:.4}) - prices unreliable, skipping",
+                bid, ask
+            ));
             break;
         }
 
-        let best_bid = bids
+        let levels = book_levels(bids);
+        let best_bid = levels
             .iter()
-            .filter_map(|b| {
-                let price: f64 = b
-                    .get("price")
-                    .and_then(|p| p.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                let size: f64 = b
-                    .get("size")
-                    .and_then(|s| s.as_str())
-                    .and_then(|s| s.parse().ok())?;
-                Some((price, size))
-            })
+            .copied()
             .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
 
         let (price, size) = match best_bid {
@@ -604,7 +1124,47 @@ async fn execute_sell_strategy(
 
         Logger::info(&format!("Best bid: {} @ ${:.4}", size, price));
 
-        if remaining < MIN_ORDER_SIZE_TOKENS {
+        if let Some((limit, trader_price)) = min_price.zip(trade.price) {
+            if !slippage_checked {
+                slippage_checked = true;
+                match check_slippage(
+                    config.slippage_action,
+                    false,
+                    trader_price,
+                    limit,
+                    &levels,
+                    remaining,
+                    min_tokens,
+                ) {
+                    SlippageCheck::Within => Logger::info(&format!(
+                        "🛡️  Slippage guard: best bid is {:.1}% from the trader's ${:.4}, within {:.1}%",
+                        slippage_percent(false, trader_price, price),
+                        trader_price,
+                        config.max_slippage_percent
+                    )),
+                    SlippageCheck::Shrink(tokens) => {
+                        Logger::warning(&format!(
+                            "🛡️  Slippage guard: shrinking {:.2} to {:.2} tokens, what the book takes within ${:.4}",
+                            remaining, tokens, limit
+                        ));
+                        remaining = tokens;
+                    }
+                    SlippageCheck::Skip(why) => {
+                        Logger::warning(&format!("🛡️  Slippage guard: {}, skipping", why));
+                        fill.slipped = true;
+                        break;
+                    }
+                }
+            } else if price < limit - 1e-9 {
+                Logger::warning(&format!(
+                    "🛡️  Slippage guard: best bid ${:.4} is past the ${:.4} limit, stopping",
+                    price, limit
+                ));
+                break;
+            }
+        }
+
+        if remaining < min_tokens {
             Logger::info(&format!(
                 "Remaining amount ({:.2} tokens) below minimum - completing trade",
                 remaining
@@ -612,9 +1172,9 @@ async fn execute_sell_strategy(
             break;
         }
 
-        let sell_amount = remaining.min(size);
+        let sell_amount = lot_size(remaining.min(size));
 
-        if sell_amount < MIN_ORDER_SIZE_TOKENS {
+        if sell_amount < min_tokens {
             Logger::info(&format!(
                 "Order amount ({:.2} tokens) below minimum - completing trade",
                 sell_amount
@@ -622,37 +1182,35 @@ async fn execute_sell_strategy(
             break;
         }
 
-        let exp_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 90;
-        let exp = chrono::DateTime::from_timestamp(exp_secs as i64, 0)
-            .ok_or_else(|| anyhow::anyhow!("Invalid timestamp"))?;
-        let token_id = alloy::primitives::U256::from_str_radix(
-            asset.trim_start_matches("0x"),
-            16,
-        )
-        .or_else(|_| alloy::primitives::U256::from_str(&asset))?;
-        let decimal_size = Decimal::from_str(&format!("{:.4}", sell_amount))
+        let terms = OrderTerms::for_asset(asset, market.as_ref())?;
+        let decimal_size = Decimal::from_str(&format!("{:.2}", sell_amount))
             .map_err(|e| anyhow::anyhow!("{}", e))?;
-        let decimal_price =
-            Decimal::from_str(&format!("{:.2}", price))
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-        let order = clob_client
+        let decimal_price = terms.price(price)?;
+        let build_timer = terms.stage();
+        terms.apply(clob_client, price);
+        let unsigned = clob_client
             .limit_order()
-            .token_id(token_id)
+            .token_id(terms.token_id)
             .size(decimal_size)
             .price(decimal_price)
             .side(Side::Sell)
             .order_type(SdkOrderType::FOK)
-            .expiration(exp)
             .build()
             .await?;
-        let signed = clob_client.sign(&signer, order).await?;
-        let resp = clob_client.post_order(signed).await?;
+        let signed = clob_client.sign(signer, unsigned).await?;
+        drop(build_timer);
+        let copy = intent(trade, user_address, "SELL", sell_amount, sell_amount * price, price);
+        let resp = submit(order, signed, copy).await?;
 
         let error_msg = resp.error_msg.as_deref();
 
         if resp.error_msg.as_ref().map(|s| s.is_empty()).unwrap_or(true) {
             retry = 0;
             total_sold_tokens += sell_amount;
+            fill.tokens += sell_amount;
+            watchdog::note_bot_fill(asset, "SELL", sell_amount);
+            fill.usd += sell_amount * price;
+            fill.order_ids.push(resp.order_id.clone());
             Logger::order_result(
                 true,
                 &format!("Sold {:.2} tokens at ${:.4}", sell_amount, price),
@@ -660,7 +1218,6 @@ async fn execute_sell_strategy(
             remaining -= sell_amount;
         } else {
             if is_insufficient_balance_or_allowance_error(error_msg) {
-                abort_due_to_funds = true;
                 Logger::warning(&format!(
                     "Order rejected: {}",
                     error_msg.unwrap_or("Insufficient balance or allowance")
@@ -668,6 +1225,8 @@ async fn execute_sell_strategy(
                 Logger::warning(
                     "Skipping remaining attempts. Top up funds or check allowance.",
                 );
+                fill.rejected = true;
+                crate::ctf_approval::hint_after_sell_rejection();
                 break;
             }
             retry += 1;
@@ -679,7 +1238,204 @@ async fn execute_sell_strategy(
             ));
         }
     }
+    fill.rejected |= retry >= config.retry_limit;
+
+    if total_sold_tokens > 0.0 {
+        Logger::info(&format!("📝 Sold: {:.2} tokens", total_sold_tokens));
+    }
 
-    Ok(())
+    Ok(fill)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_config;
+    use serde_json::json;
+
+    fn terms(tick_size: f64) -> OrderTerms {
+        OrderTerms {
+            token_id: alloy::primitives::U256::from(1u8),
+            template: Some(OrderTemplate {
+                token_id: alloy::primitives::U256::from(1u8),
+                tick_size,
+                neg_risk: false,
+                fee_rate_bps: 0,
+            }),
+            market_tick: None,
+        }
+    }
+
+    fn terms_with(tick_size: f64, market: &MarketMetadata) -> OrderTerms {
+        OrderTerms {
+            market_tick: market.tick_size,
+            ..terms(tick_size)
+        }
+    }
+
+    fn book(bids: &[&str], asks: &[&str]) -> serde_json::Value {
+        let levels = |prices: &[&str]| -> Vec<serde_json::Value> {
+            prices
+                .iter()
+                .map(|p| json!({ "price": p, "size": "100" }))
+                .collect()
+        };
+        json!({ "bids": levels(bids), "asks": levels(asks) })
+    }
+
+    #[test]
+    fn sell_sizes_round_down_to_the_lot_size() {
+        assert_eq!(lot_size(5.0), 5.0);
+        assert_eq!(lot_size(3.33333), 3.33);
+        assert_eq!(lot_size(0.999), 0.99);
+        assert_eq!(lot_size(0.004), 0.0);
+        assert_eq!(format!("{:.2}", lot_size(1.0 / 3.0 * 3.0)), "1.00");
+    }
+
+    #[test]
+    fn crossed_book_is_detected() {
+        assert_eq!(
+            crossed_book(&book(&["0.40", "0.55"], &["0.52", "0.60"])),
+            Some((0.55, 0.52))
+        );
+        assert_eq!(crossed_book(&book(&["0.40", "0.45"], &["0.52"])), None);
+        assert_eq!(crossed_book(&book(&[], &["0.52"])), None);
+    }
+
+    #[test]
+    fn slippage_is_measured_against_the_side_being_taken() {
+        let config = test_config(&[("MAX_SLIPPAGE_PERCENT", "10")]);
+        // A BUY pays up to 10% above the trader; a SELL takes down to 10% below.
+        let max = slippage_limit(&config, true, Some(0.50)).unwrap();
+        let min = slippage_limit(&config, false, Some(0.50)).unwrap();
+        assert!((max - 0.55).abs() < 1e-9 && (min - 0.45).abs() < 1e-9);
+        assert!((slippage_percent(true, 0.50, 0.60) - 20.0).abs() < 1e-9);
+        assert!((slippage_percent(false, 0.50, 0.40) - 20.0).abs() < 1e-9);
+        assert!(slippage_percent(false, 0.50, 0.60) < 0.0);
+
+        assert_eq!(slippage_limit(&config, true, None), None);
+        let off = test_config(&[]);
+        assert_eq!(slippage_limit(&off, true, Some(0.50)), None);
+    }
+
+    #[test]
+    fn slippage_guard_skips_or_shrinks() {
+        let asks = [(0.52, 10.0), (0.54, 10.0), (0.60, 100.0)];
+        let check = |action, usd| check_slippage(action, true, 0.50, 0.55, &asks, usd, 1.0);
+        // $10.60 of asks sit within $0.55.
+        assert_eq!(check(SlippageAction::Skip, 10.0), SlippageCheck::Within);
+        assert!(matches!(check(SlippageAction::Skip, 20.0), SlippageCheck::Skip(_)));
+        match check(SlippageAction::Shrink, 20.0) {
+            SlippageCheck::Shrink(usd) => assert!((usd - 10.6).abs() < 1e-9),
+            other => panic!("{:?}", other),
+        }
+
+        // The best bid already past the limit skips whatever the action.
+        let bids = [(0.44, 100.0), (0.40, 100.0)];
+        for action in [SlippageAction::Skip, SlippageAction::Shrink] {
+            let SlippageCheck::Skip(why) =
+                check_slippage(action, false, 0.50, 0.45, &bids, 10.0, 1.0)
+            else {
+                panic!("a bid past the limit must skip");
+            };
+            assert!(why.contains("12.0% worse"), "{}", why);
+        }
+        let bids = [(0.48, 5.0), (0.40, 100.0)];
+        assert_eq!(
+            check_slippage(SlippageAction::Shrink, false, 0.50, 0.45, &bids, 10.0, 1.0),
+            SlippageCheck::Shrink(5.0)
+        );
+    }
+
+    #[test]
+    fn skip_policy_never_rests_an_order() {
+        assert!(
+            resting_price(EmptyBookPolicy::Skip, Some(0.4), 50.0, MIN_ORDER_SIZE_TOKENS).is_err()
+        );
+    }
+
+    #[test]
+    fn limit_policy_rests_at_the_trader_price() {
+        assert_eq!(
+            resting_price(EmptyBookPolicy::Limit, Some(0.4), 50.0, MIN_ORDER_SIZE_TOKENS),
+            Ok(0.4)
+        );
+    }
+
+    #[test]
+    fn limit_policy_needs_a_usable_price_and_size() {
+        for price in [None, Some(0.0), Some(1.0)] {
+            assert!(
+                resting_price(EmptyBookPolicy::Limit, price, 50.0, MIN_ORDER_SIZE_TOKENS).is_err()
+            );
+        }
+        let too_small = MIN_ORDER_SIZE_TOKENS / 2.0;
+        assert!(resting_price(
+            EmptyBookPolicy::Limit,
+            Some(0.4),
+            too_small,
+            MIN_ORDER_SIZE_TOKENS
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn extreme_prices_build_on_the_fine_tick() {
+        // The market reported cent ticks before it ran to either end.
+        let cent = terms(0.01);
+        for (price, expected) in [
+            (0.004, "0.004"),
+            (0.049, "0.049"),
+            (0.951, "0.951"),
+            (0.996, "0.996"),
+        ] {
+            // BUY: rests at the trader's price on an empty book.
+            let rest =
+                resting_price(EmptyBookPolicy::Limit, Some(price), 10.0, MIN_ORDER_SIZE_TOKENS)
+                    .unwrap();
+            assert_eq!(cent.price(rest).unwrap().to_string(), expected);
+            // SELL: a FOK at the best bid, quoted on the fine tick.
+            assert_eq!(cent.price(price).unwrap().to_string(), expected);
+            // The cent price the market reported its tick in would be a different order.
+            let naive = format!("{:.2}", price).parse::<f64>().unwrap();
+            assert!(cent.template.unwrap().ticks().check_price(price, naive).is_err());
+        }
+        // Without a template, the same regime applies.
+        let bare = OrderTerms::for_asset("1", None).unwrap();
+        assert_eq!(bare.price(0.004).unwrap().to_string(), "0.004");
+        assert_eq!(bare.price(0.587).unwrap().to_string(), "0.59");
+    }
+
+    #[test]
+    fn gamma_fills_in_the_tick_and_minimum_size() {
+        let market = MarketMetadata {
+            tick_size: Some(0.001),
+            min_order_size: Some(5.0),
+            ..Default::default()
+        };
+        let terms = OrderTerms::for_asset("1", Some(&market)).unwrap();
+        assert_eq!(terms.price(0.5874).unwrap().to_string(), "0.587");
+        // A template read from the CLOB still wins.
+        assert_eq!(terms_with(0.01, &market).price(0.5874).unwrap().to_string(), "0.59");
+
+        let min = min_order_tokens(Some(&market));
+        assert_eq!(min, 5.0);
+        assert_eq!(min_order_tokens(None), MIN_ORDER_SIZE_TOKENS);
+        assert!(resting_price(EmptyBookPolicy::Limit, Some(0.4), 3.0, min).is_err());
+        assert_eq!(resting_price(EmptyBookPolicy::Limit, Some(0.4), 5.0, min), Ok(0.4));
+    }
+
+    #[test]
+    fn sells_mirror_the_share_of_the_traders_position() {
+        // $2,000 of a $4,000 position: half, whatever my position is worth.
+        assert_eq!(trader_sell_fraction(2_000.0, Some(2_000.0)), 0.5);
+        assert_eq!(trader_sell_fraction(100.0, Some(300.0)), 0.25);
+        // Nothing or dust left: a full exit.
+        assert_eq!(trader_sell_fraction(500.0, None), 1.0);
+        assert_eq!(trader_sell_fraction(500.0, Some(0.0)), 1.0);
+        assert_eq!(trader_sell_fraction(500.0, Some(2.0)), 1.0);
+        // No size on the signal: nothing to mirror.
+        assert_eq!(trader_sell_fraction(0.0, Some(10.0)), 0.0);
+        assert_eq!(trader_sell_fraction(f64::NAN, Some(10.0)), 0.0);
+    }
+}
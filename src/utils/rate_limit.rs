@@ -0,0 +1,190 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::status;
+
+/// `REQUEST_RATE_LIMIT_PER_SEC` when the config hasn't set it.
+pub const DEFAULT_REQUESTS_PER_SEC: f64 = 5.0;
+
+static LIMITER: LazyLock<RateLimiter> =
+    LazyLock::new(|| RateLimiter::new(DEFAULT_REQUESTS_PER_SEC));
+
+/// Sets the process-wide limit (`REQUEST_RATE_LIMIT_PER_SEC`; 0 turns it off) and lists
+/// each host's bucket under `/status`.
+pub fn init(requests_per_sec: f64) {
+    LIMITER.set_rate(requests_per_sec);
+    status::register("rate_limit", || LIMITER.status());
+}
+
+/// Waits for a token from `url`'s host bucket; what `fetch_data` does before every request.
+pub async fn acquire(url: &str) {
+    LIMITER.acquire(url).await
+}
+
+/// Token buckets per host: a busy data API doesn't hold back CLOB requests, and the other
+/// way round. Each refills at the configured rate up to a second's worth of requests.
+/// Callers past an empty bucket queue behind each other rather than erroring: a token is
+/// taken on arrival and the bucket may go negative, which is how long the caller waits.
+pub struct RateLimiter {
+    inner: Mutex<Limits>,
+}
+
+struct Limits {
+    /// 0 when off.
+    per_sec: f64,
+    buckets: HashMap<String, Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    waited: u64,
+    wait_total: Duration,
+    wait_max: Duration,
+}
+
+impl Bucket {
+    fn refill(&mut self, per_sec: f64, now: Instant) {
+        let earned = now.duration_since(self.refilled).as_secs_f64() * per_sec;
+        self.tokens = (self.tokens + earned).min(burst(per_sec));
+        self.refilled = now;
+    }
+
+    /// How long a request arriving now would wait.
+    fn wait_now(&self, per_sec: f64) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / per_sec)
+    }
+}
+
+fn burst(per_sec: f64) -> f64 {
+    per_sec.max(1.0)
+}
+
+/// `host[:port]` of `url`, or the whole of it when it doesn't parse.
+fn host_key(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_sec: f64) -> Self {
+        Self {
+            inner: Mutex::new(Limits {
+                per_sec: requests_per_sec.max(0.0),
+                buckets: HashMap::new(),
+            }),
+        }
+    }
+
+    fn set_rate(&self, requests_per_sec: f64) {
+        let mut limits = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        limits.per_sec = requests_per_sec.max(0.0);
+        limits.buckets.clear();
+    }
+
+    pub async fn acquire(&self, url: &str) {
+        let wait = self.reserve(url, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Takes a token from `url`'s bucket and returns how long to wait before using it.
+    fn reserve(&self, url: &str, now: Instant) -> Duration {
+        let mut limits = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let per_sec = limits.per_sec;
+        if per_sec <= 0.0 {
+            return Duration::ZERO;
+        }
+        let bucket = limits
+            .buckets
+            .entry(host_key(url))
+            .or_insert_with(|| Bucket {
+                tokens: burst(per_sec),
+                refilled: now,
+                waited: 0,
+                wait_total: Duration::ZERO,
+                wait_max: Duration::ZERO,
+            });
+        bucket.refill(per_sec, now);
+        let wait = bucket.wait_now(per_sec);
+        bucket.tokens -= 1.0;
+        if !wait.is_zero() {
+            bucket.waited += 1;
+            bucket.wait_total += wait;
+            bucket.wait_max = bucket.wait_max.max(wait);
+        }
+        wait
+    }
+
+    /// The `/status` section: the limit, and per host the wait a request would face now and
+    /// what waiting it has cost so far.
+    fn status(&self) -> serde_json::Value {
+        let mut limits = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let per_sec = limits.per_sec;
+        let now = Instant::now();
+        let hosts: serde_json::Map<String, serde_json::Value> = limits
+            .buckets
+            .iter_mut()
+            .map(|(host, bucket)| {
+                bucket.refill(per_sec, now);
+                let value = json!({
+                    "current_wait_ms": bucket.wait_now(per_sec).as_millis() as u64,
+                    "waited_requests": bucket.waited,
+                    "wait_ms_total": bucket.wait_total.as_millis() as u64,
+                    "wait_ms_max": bucket.wait_max.as_millis() as u64,
+                });
+                (host.clone(), value)
+            })
+            .collect();
+        json!({ "requests_per_sec": per_sec, "hosts": hosts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA_API: &str = "https://data-api.polymarket.com/positions?user=0x1";
+    const CLOB: &str = "https://clob.polymarket.com/book?token_id=1";
+
+    #[test]
+    fn an_empty_bucket_queues_callers_at_the_rate() {
+        let limiter = RateLimiter::new(2.0);
+        let start = Instant::now();
+        assert_eq!(limiter.reserve(DATA_API, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(DATA_API, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(DATA_API, start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(DATA_API, start), Duration::from_millis(1000));
+        // Another host has its own bucket.
+        assert_eq!(limiter.reserve(CLOB, start), Duration::ZERO);
+        // Refilled by the time the queue has drained.
+        let later = start + Duration::from_secs(3);
+        assert_eq!(limiter.reserve(DATA_API, later), Duration::ZERO);
+
+        let status = limiter.status();
+        let data_api = &status["hosts"]["data-api.polymarket.com"];
+        assert_eq!(data_api["waited_requests"], 2);
+        assert_eq!(data_api["wait_ms_max"], 1000);
+    }
+
+    #[test]
+    fn zero_turns_it_off() {
+        let limiter = RateLimiter::new(0.0);
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(DATA_API, now), Duration::ZERO);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, U256, U64};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::client::ClientBuilder;
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol;
+use alloy::sol_types::SolCall;
+use alloy::transports::http::{reqwest, Http};
+use alloy::transports::layers::RetryBackoffLayer;
+use alloy::transports::{BoxTransport, Transport, TransportError, TransportFut, TransportResult};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tower::{Layer, Service};
+
+use crate::config::EnvConfig;
+use crate::utils::Logger;
+
+sol! {
+    interface IERC20Read {
+        function balanceOf(address owner) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function decimals() external view returns (uint8);
+    }
+}
+
+/// Per HTTP request; each retry gets its own.
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+/// Retries of a rate-limited or unavailable (429/503) request, backing off from
+/// `RETRY_INITIAL_BACKOFF_MS`, before the endpoint counts as failed.
+const RPC_MAX_RETRIES: u32 = 3;
+const RETRY_INITIAL_BACKOFF_MS: u64 = 500;
+/// The compute units per second the retry layer budgets for; alloy's default.
+const RPC_COMPUTE_UNITS_PER_SECOND: u64 = 330;
+/// How long an endpoint that failed a call is passed over.
+const RPC_COOLDOWN: Duration = Duration::from_secs(60);
+/// How often endpoints ahead of the active one in `RPC_URL` are re-probed.
+pub const RPC_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// Assumed when `decimals()` can't be read: USDC's.
+const DEFAULT_DECIMALS: u8 = 6;
+
+/// One client per `RPC_URL` for the process, so the free functions below share its
+/// connection pool and failover state with the executor's.
+static CLIENTS: LazyLock<Mutex<HashMap<String, Arc<RpcClient>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The Polygon JSON-RPC connection: an alloy provider over one pooled HTTP client, with
+/// `RPC_TIMEOUT` and a retry layer for rate limits. `RPC_URL` may list several endpoints,
+/// comma-separated in order of preference; a call that fails on the active one moves on to
+/// the next (see `Endpoints`).
+pub struct RpcClient {
+    url: String,
+    endpoints: Arc<Endpoints>,
+    provider: DynProvider,
+    /// `decimals()` by token contract; it never changes.
+    decimals: Mutex<HashMap<Address, u8>>,
+}
+
+impl RpcClient {
+    /// `rpc_url` is `RPC_URL`: one endpoint or a comma-separated list.
+    pub fn new(rpc_url: &str) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(RPC_TIMEOUT)
+            .build()
+            .context("RPC HTTP client")?;
+        let mut list = Vec::new();
+        let mut is_local = true;
+        for url in rpc_url.split(',').map(str::trim).filter(|u| !u.is_empty()) {
+            let parsed: url::Url = url
+                .parse()
+                .with_context(|| format!("Invalid RPC_URL entry {}", url))?;
+            let label = match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => anyhow::bail!("Invalid RPC_URL entry {}", url),
+            };
+            let transport = Http::with_client(http.clone(), parsed);
+            is_local &= transport.guess_local();
+            let retrying = RetryBackoffLayer::new(
+                RPC_MAX_RETRIES,
+                RETRY_INITIAL_BACKOFF_MS,
+                RPC_COMPUTE_UNITS_PER_SECOND,
+            )
+            .layer(transport);
+            list.push(Endpoint {
+                label,
+                transport: retrying.boxed(),
+                down_until: Mutex::new(None),
+            });
+        }
+        anyhow::ensure!(!list.is_empty(), "RPC_URL lists no endpoint");
+        let endpoints = Arc::new(Endpoints {
+            list,
+            active: AtomicUsize::new(0),
+        });
+        let client = ClientBuilder::default().transport(Failover(endpoints.clone()), is_local);
+        Ok(Self {
+            url: rpc_url.trim().to_string(),
+            endpoints,
+            provider: ProviderBuilder::default().connect_client(client).erased(),
+            decimals: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The process-wide client for `rpc_url`, created on first use.
+    pub fn for_url(rpc_url: &str) -> Result<Arc<Self>> {
+        let mut clients = CLIENTS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(rpc_url.trim()) {
+            return Ok(client.clone());
+        }
+        let client = Arc::new(Self::new(rpc_url)?);
+        clients.insert(client.url.clone(), client.clone());
+        Ok(client)
+    }
+
+    pub fn from_config(config: &EnvConfig) -> Result<Arc<Self>> {
+        Self::for_url(&config.rpc_url)
+    }
+
+    /// The endpoint calls go to now, e.g. `polygon-rpc.com (2 of 3)`. Only the host is shown:
+    /// provider URLs often carry an API key in the path.
+    pub fn active_endpoint(&self) -> String {
+        let endpoints = &self.endpoints.list;
+        let active = self.endpoints.active.load(Ordering::SeqCst);
+        if endpoints.len() == 1 {
+            return endpoints[0].label.clone();
+        }
+        format!(
+            "{} ({} of {})",
+            endpoints[active].label,
+            active + 1,
+            endpoints.len()
+        )
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.list.len()
+    }
+
+    /// Probes the endpoints `RPC_URL` prefers over the active one once their cooldown is
+    /// over, and moves back to the first that answers. Run every `RPC_PROBE_INTERVAL`.
+    pub async fn reprobe(&self) {
+        self.endpoints.reprobe().await
+    }
+
+    /// For contract bindings and for stacking a wallet on top:
+    /// `ProviderBuilder::new().wallet(signer).connect_provider(rpc.provider().clone())`.
+    pub fn provider(&self) -> &DynProvider {
+        &self.provider
+    }
+
+    /// Whether `address` has code deployed, i.e. is a contract wallet rather than an EOA.
+    pub async fn is_contract(&self, address: &str) -> Result<bool> {
+        let code = self.provider.get_code_at(parse_address(address)?).await?;
+        Ok(code.iter().any(|b| *b != 0))
+    }
+
+    /// `address`'s balance of the ERC-20 `contract` in whole tokens, and the token's decimals.
+    pub async fn erc20_balance(&self, contract: &str, address: &str) -> Result<(f64, u8)> {
+        let contract = parse_address(contract)?;
+        let call = IERC20Read::balanceOfCall {
+            owner: parse_address(address)?,
+        };
+        let raw = self.read_u256(contract, call.abi_encode()).await?;
+        let decimals = self.decimals(contract).await;
+        Ok((to_tokens(raw, decimals), decimals))
+    }
+
+    /// What `spender` may move of `owner`'s `contract` tokens, in whole tokens; an unlimited
+    /// (`U256::MAX`) approval is `f64::INFINITY`.
+    pub async fn erc20_allowance(
+        &self,
+        contract: &str,
+        owner: &str,
+        spender: &str,
+    ) -> Result<(f64, u8)> {
+        let contract = parse_address(contract)?;
+        let call = IERC20Read::allowanceCall {
+            owner: parse_address(owner)?,
+            spender: parse_address(spender)?,
+        };
+        let raw = self.read_u256(contract, call.abi_encode()).await?;
+        let decimals = self.decimals(contract).await;
+        Ok((to_tokens(raw, decimals), decimals))
+    }
+
+    pub async fn usdc_balance(&self, usdc_contract: &str, address: &str) -> Result<f64> {
+        Ok(self.erc20_balance(usdc_contract, address).await?.0)
+    }
+
+    /// The token's `decimals()`, `DEFAULT_DECIMALS` when it can't be read.
+    async fn decimals(&self, contract: Address) -> u8 {
+        if let Some(decimals) = self.cached_decimals(contract) {
+            return decimals;
+        }
+        let call = IERC20Read::decimalsCall {}.abi_encode();
+        match self.read_u256(contract, call).await {
+            Ok(raw) if raw > U256::ZERO && raw <= U256::from(u8::MAX) => {
+                let decimals = raw.to::<u8>();
+                self.decimals
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(contract, decimals);
+                decimals
+            }
+            _ => DEFAULT_DECIMALS,
+        }
+    }
+
+    fn cached_decimals(&self, contract: Address) -> Option<u8> {
+        let decimals = self.decimals.lock().unwrap_or_else(|e| e.into_inner());
+        decimals.get(&contract).copied()
+    }
+
+    /// An `eth_call` returning one word. An empty result (no contract at `to`) reads as zero.
+    async fn read_u256(&self, to: Address, data: Vec<u8>) -> Result<U256> {
+        let tx = TransactionRequest::default()
+            .with_to(to)
+            .with_input(Bytes::from(data));
+        let out = self.provider.call(tx).await?;
+        decode_u256(&out)
+    }
+}
+
+/// `RPC_URL`'s endpoints. Calls go to the active one; when it fails (a transport error or a
+/// timeout, or rate limiting that outlasted the retries) it is put in a `RPC_COOLDOWN` and the
+/// call moves on to the next endpoint not cooling down. JSON-RPC errors such as a revert are
+/// answers, not failures.
+struct Endpoints {
+    list: Vec<Endpoint>,
+    active: AtomicUsize,
+}
+
+struct Endpoint {
+    /// Host and port, for logs.
+    label: String,
+    transport: BoxTransport,
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Endpoint {
+    fn is_down(&self, now: Instant) -> bool {
+        let down_until = self.down_until.lock().unwrap_or_else(|e| e.into_inner());
+        down_until.is_some_and(|t| t > now)
+    }
+
+    fn set_down(&self, until: Option<Instant>) {
+        *self.down_until.lock().unwrap_or_else(|e| e.into_inner()) = until;
+    }
+
+    async fn probe(&self) -> TransportResult<U64> {
+        let client = ClientBuilder::default().transport(self.transport.clone(), false);
+        client.request_noparams("eth_blockNumber").await
+    }
+}
+
+impl Endpoints {
+    async fn send(&self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        let mut attempts = 0;
+        loop {
+            let active = self.active.load(Ordering::SeqCst);
+            let mut transport = self.list[active].transport.clone();
+            let err = match transport.call(request.clone()).await {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+            attempts += 1;
+            if self.list.len() == 1 {
+                return Err(err);
+            }
+            self.fail_over(active, &err);
+            if attempts >= self.list.len() {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Cools `failed` down and makes the next endpoint that isn't cooling down active; the
+    /// next in line when all are.
+    fn fail_over(&self, failed: usize, err: &TransportError) {
+        let now = Instant::now();
+        self.list[failed].set_down(Some(now + RPC_COOLDOWN));
+        let n = self.list.len();
+        let next = (1..n)
+            .map(|k| (failed + k) % n)
+            .find(|&i| !self.list[i].is_down(now))
+            .unwrap_or((failed + 1) % n);
+        // Concurrent calls failing on the same endpoint move on once.
+        let moved = self
+            .active
+            .compare_exchange(failed, next, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok();
+        if moved {
+            Logger::warning(&format!(
+                "RPC {} failed ({}); switching to {} and skipping {} for {}s",
+                self.list[failed].label,
+                err,
+                self.list[next].label,
+                self.list[failed].label,
+                RPC_COOLDOWN.as_secs()
+            ));
+        }
+    }
+
+    async fn reprobe(&self) {
+        let active = self.active.load(Ordering::SeqCst);
+        for (i, endpoint) in self.list.iter().enumerate().take(active) {
+            if endpoint.is_down(Instant::now()) {
+                continue;
+            }
+            match endpoint.probe().await {
+                Ok(_) => {
+                    endpoint.set_down(None);
+                    self.active.store(i, Ordering::SeqCst);
+                    Logger::info(&format!(
+                        "RPC {} is answering again; switching back to it",
+                        endpoint.label
+                    ));
+                    return;
+                }
+                Err(_) => endpoint.set_down(Some(Instant::now() + RPC_COOLDOWN)),
+            }
+        }
+    }
+}
+
+/// The transport under the provider: hands every request to `Endpoints::send`.
+#[derive(Clone)]
+struct Failover(Arc<Endpoints>);
+
+impl Service<RequestPacket> for Failover {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let endpoints = self.0.clone();
+        Box::pin(async move { endpoints.send(request).await })
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address> {
+    address
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid address {}", address))
+}
+
+fn decode_u256(out: &[u8]) -> Result<U256> {
+    if out.is_empty() {
+        return Ok(U256::ZERO);
+    }
+    anyhow::ensure!(out.len() >= 32, "Short RPC result ({} bytes)", out.len());
+    Ok(U256::from_be_slice(&out[..32]))
+}
+
+/// `raw` base units in whole tokens; `U256::MAX` (an unlimited approval) is infinite.
+fn to_tokens(raw: U256, decimals: u8) -> f64 {
+    if raw == U256::MAX {
+        return f64::INFINITY;
+    }
+    f64::from(raw) / 10_f64.powi(decimals as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_words_and_scales_by_decimals() {
+        let mut word = [0u8; 32];
+        word[28..].copy_from_slice(&12_500_000u32.to_be_bytes());
+        let raw = decode_u256(&word).unwrap();
+        assert_eq!(to_tokens(raw, 6), 12.5);
+        assert_eq!(decode_u256(&[]).unwrap(), U256::ZERO);
+        assert!(decode_u256(&[1, 2, 3]).is_err());
+        assert_eq!(to_tokens(U256::MAX, 6), f64::INFINITY);
+        assert_eq!(to_tokens(U256::from(10).pow(U256::from(18)), 18), 1.0);
+    }
+
+    #[test]
+    fn clients_are_shared_per_url() {
+        let a = RpcClient::for_url("http://127.0.0.1:9").unwrap();
+        let b = RpcClient::for_url(" http://127.0.0.1:9 ").unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(RpcClient::for_url("not a url").is_err());
+    }
+
+    /// A JSON-RPC endpoint answering every call with `result`.
+    async fn fake_endpoint(result: &'static str) -> String {
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::post(
+                move |axum::Json(req): axum::Json<serde_json::Value>| async move {
+                    axum::Json(
+                        serde_json::json!({"jsonrpc": "2.0", "id": req["id"], "result": result}),
+                    )
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn a_dead_endpoint_fails_over_to_the_next() {
+        let live =
+            fake_endpoint("0x0000000000000000000000000000000000000000000000000000000000bebc20")
+                .await;
+        let rpc = RpcClient::new(&format!("http://127.0.0.1:9, {}", live)).unwrap();
+        assert_eq!(rpc.active_endpoint(), "127.0.0.1:9 (1 of 2)");
+        let wallet = "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+        let balance = rpc.usdc_balance(wallet, wallet).await.unwrap();
+        assert_eq!(balance, 12.5);
+        assert!(rpc.active_endpoint().ends_with("(2 of 2)"));
+        // The dead one is cooling down, so a re-probe leaves the live one active.
+        rpc.reprobe().await;
+        assert!(rpc.active_endpoint().ends_with("(2 of 2)"));
+    }
+}
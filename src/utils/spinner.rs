@@ -1,6 +1,8 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
+use super::theme::color_enabled;
+
 static SPINNER_INDEX: AtomicUsize = AtomicUsize::new(0);
 
 pub enum SpinnerStyle {
@@ -18,6 +20,7 @@ pub enum SpinnerStyle {
 impl SpinnerStyle {
     pub fn frames(&self) -> &'static [&'static str] {
         match self {
+            SpinnerStyle::Clock | SpinnerStyle::Moon if !color_enabled() => &["|", "/", "-", "\\"],
             SpinnerStyle::Bars => &[
                 "▰▱▱▱▱▱▱",
                 "▰▰▱▱▱▱▱",
@@ -103,7 +106,10 @@ impl Spinner {
         let frames = self.style.frames();
         let frame = frames[idx % frames.len()];
         let current_idx = idx % frames.len();
-        
+        if !color_enabled() {
+            return frame.to_string();
+        }
+
         match self.style {
             SpinnerStyle::Gradient => {
                 let colors = [51, 87, 123, 159, 195, 159, 123, 87];
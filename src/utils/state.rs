@@ -0,0 +1,36 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+pub fn state_path(state_dir: &str, file_name: &str) -> PathBuf {
+    Path::new(state_dir).join(file_name)
+}
+
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            super::Logger::warning(&format!(
+                "Ignoring unreadable state file {}: {}",
+                path.display(),
+                e
+            ));
+            None
+        }
+    }
+}
+
+/// Writes via a temp file and rename so a crash mid-write never leaves a truncated file behind.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(value)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
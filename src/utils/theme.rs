@@ -1,53 +1,132 @@
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Unset until first asked; then `COLOR_ON` or `COLOR_OFF`.
+static COLOR: AtomicU8 = AtomicU8::new(0);
+const COLOR_ON: u8 = 1;
+const COLOR_OFF: u8 = 2;
+
+/// Colors and emoji icons are on for a terminal, and off when `NO_COLOR` is set to anything
+/// but an empty string or when stdout is piped to a file or journald.
+fn color_choice(no_color: Option<&str>, stdout_is_terminal: bool) -> bool {
+    no_color.is_none_or(str::is_empty) && stdout_is_terminal
+}
+
+/// Whether output is styled, decided from the environment on first use.
+pub fn color_enabled() -> bool {
+    match COLOR.load(Ordering::Relaxed) {
+        COLOR_ON => true,
+        COLOR_OFF => false,
+        _ => {
+            let no_color = std::env::var("NO_COLOR").ok();
+            let on = color_choice(no_color.as_deref(), std::io::stdout().is_terminal());
+            set_color_enabled(on);
+            on
+        }
+    }
+}
+
+/// Overrides the detection for the rest of the run.
+pub fn set_color_enabled(on: bool) {
+    COLOR.store(if on { COLOR_ON } else { COLOR_OFF }, Ordering::Relaxed);
+}
+
+/// An escape sequence that prints as nothing when colors are off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(&'static str);
+
+impl Color {
+    pub fn code(self) -> &'static str {
+        if color_enabled() {
+            self.0
+        } else {
+            ""
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A symbol with the ASCII it falls back to when colors are off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Icon(&'static str, &'static str);
+
+impl Icon {
+    pub fn symbol(self) -> &'static str {
+        if color_enabled() {
+            self.0
+        } else {
+            self.1
+        }
+    }
+}
+
+impl fmt::Display for Icon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
 pub mod colors {
-    pub const RESET: &str = "\x1b[0m";
-    pub const BOLD: &str = "\x1b[1m";
-    pub const DIM: &str = "\x1b[2m";
-    pub const ITALIC: &str = "\x1b[3m";
-    pub const UNDERLINE: &str = "\x1b[4m";
-    pub const BLINK: &str = "\x1b[5m";
-
-    pub const ACCENT: &str = "\x1b[38;5;51m";
-    pub const ACCENT_BOLD: &str = "\x1b[1;38;5;51m";
-    pub const ACCENT_DIM: &str = "\x1b[2;38;5;51m";
-    pub const ACCENT_BG: &str = "\x1b[48;5;51m";
+    use super::Color;
+
+    pub const RESET: Color = Color("\x1b[0m");
+    pub const BOLD: Color = Color("\x1b[1m");
+    pub const DIM: Color = Color("\x1b[2m");
+    pub const ITALIC: Color = Color("\x1b[3m");
+    pub const UNDERLINE: Color = Color("\x1b[4m");
+    pub const BLINK: Color = Color("\x1b[5m");
+
+    pub const ACCENT: Color = Color("\x1b[38;5;51m");
+    pub const ACCENT_BOLD: Color = Color("\x1b[1;38;5;51m");
+    pub const ACCENT_DIM: Color = Color("\x1b[2;38;5;51m");
+    pub const ACCENT_BG: Color = Color("\x1b[48;5;51m");
     
-    pub const MINT: &str = "\x1b[38;5;85m";
-    pub const MINT_BOLD: &str = "\x1b[1;38;5;85m";
+    pub const MINT: Color = Color("\x1b[38;5;85m");
+    pub const MINT_BOLD: Color = Color("\x1b[1;38;5;85m");
     
-    pub const SUCCESS: &str = "\x1b[38;5;46m";
-    pub const SUCCESS_BOLD: &str = "\x1b[1;38;5;46m";
-    pub const SUCCESS_BG: &str = "\x1b[48;5;46m";
+    pub const SUCCESS: Color = Color("\x1b[38;5;46m");
+    pub const SUCCESS_BOLD: Color = Color("\x1b[1;38;5;46m");
+    pub const SUCCESS_BG: Color = Color("\x1b[48;5;46m");
     
-    pub const WARN: &str = "\x1b[38;5;214m";
-    pub const WARN_BOLD: &str = "\x1b[1;38;5;214m";
-    pub const WARN_BG: &str = "\x1b[48;5;214m";
+    pub const WARN: Color = Color("\x1b[38;5;214m");
+    pub const WARN_BOLD: Color = Color("\x1b[1;38;5;214m");
+    pub const WARN_BG: Color = Color("\x1b[48;5;214m");
     
-    pub const ERROR: &str = "\x1b[38;5;196m";
-    pub const ERROR_BOLD: &str = "\x1b[1;38;5;196m";
-    pub const ERROR_BG: &str = "\x1b[48;5;196m";
+    pub const ERROR: Color = Color("\x1b[38;5;196m");
+    pub const ERROR_BOLD: Color = Color("\x1b[1;38;5;196m");
+    pub const ERROR_BG: Color = Color("\x1b[48;5;196m");
     
-    pub const MUTED: &str = "\x1b[38;5;245m";
-    pub const MUTED_DIM: &str = "\x1b[2;38;5;245m";
+    pub const MUTED: Color = Color("\x1b[38;5;245m");
+    pub const MUTED_DIM: Color = Color("\x1b[2;38;5;245m");
     
-    pub const HIGHLIGHT: &str = "\x1b[38;5;213m";
-    pub const HIGHLIGHT_BOLD: &str = "\x1b[1;38;5;213m";
+    pub const HIGHLIGHT: Color = Color("\x1b[38;5;213m");
+    pub const HIGHLIGHT_BOLD: Color = Color("\x1b[1;38;5;213m");
     
-    pub const GOLD: &str = "\x1b[38;5;220m";
-    pub const GOLD_BOLD: &str = "\x1b[1;38;5;220m";
+    pub const GOLD: Color = Color("\x1b[38;5;220m");
+    pub const GOLD_BOLD: Color = Color("\x1b[1;38;5;220m");
     
-    pub const BOX: &str = "\x1b[38;5;33m";
-    pub const BOX_DIM: &str = "\x1b[2;38;5;33m";
+    pub const BOX: Color = Color("\x1b[38;5;33m");
+    pub const BOX_DIM: Color = Color("\x1b[2;38;5;33m");
     
-    pub const CYAN: &str = "\x1b[38;5;87m";
-    pub const BLUE: &str = "\x1b[38;5;39m";
-    pub const PURPLE: &str = "\x1b[38;5;129m";
-    pub const GREEN: &str = "\x1b[38;5;82m";
-    pub const YELLOW: &str = "\x1b[38;5;226m";
-    pub const ORANGE: &str = "\x1b[38;5;208m";
-    pub const RED: &str = "\x1b[38;5;203m";
-    pub const PINK: &str = "\x1b[38;5;211m";
+    pub const CYAN: Color = Color("\x1b[38;5;87m");
+    pub const BLUE: Color = Color("\x1b[38;5;39m");
+    pub const PURPLE: Color = Color("\x1b[38;5;129m");
+    pub const GREEN: Color = Color("\x1b[38;5;82m");
+    pub const YELLOW: Color = Color("\x1b[38;5;226m");
+    pub const ORANGE: Color = Color("\x1b[38;5;208m");
+    pub const RED: Color = Color("\x1b[38;5;203m");
+    pub const PINK: Color = Color("\x1b[38;5;211m");
     
     pub fn gradient(text: &str, start_color: u8, end_color: u8) -> String {
+        if !super::color_enabled() {
+            return text.to_string();
+        }
         let chars: Vec<char> = text.chars().collect();
         let len = chars.len();
         if len == 0 {
@@ -62,33 +141,45 @@ pub mod colors {
 }
 
 pub mod icons {
-    pub const INFO: &str = "ℹ";
-    pub const OK: &str = "✓";
-    pub const WARN: &str = "⚠";
-    pub const ERR: &str = "✗";
-    pub const ARROW: &str = "▶";
-    pub const ARROW_RIGHT: &str = "→";
-    pub const ARROW_LEFT: &str = "←";
-    pub const DOT: &str = "•";
-    pub const TRADE: &str = "◆";
-    pub const VAULT: &str = "◇";
-    pub const STAR: &str = "★";
-    pub const DIAMOND: &str = "♦";
-    pub const CIRCLE: &str = "●";
-    pub const SQUARE: &str = "■";
-    pub const TRIANGLE: &str = "▲";
-    pub const CHECK: &str = "✔";
-    pub const CROSS: &str = "✘";
-    pub const PLUS: &str = "＋";
-    pub const MINUS: &str = "－";
-    pub const MONEY: &str = "💰";
-    pub const CHART: &str = "📊";
-    pub const ROCKET: &str = "🚀";
-    pub const FIRE: &str = "🔥";
-    pub const SPARKLES: &str = "✨";
-    pub const LIGHTNING: &str = "⚡";
-    pub const SHIELD: &str = "🛡";
-    pub const TARGET: &str = "🎯";
+    use super::Icon;
+
+    pub const INFO: Icon = Icon("ℹ", "i");
+    pub const OK: Icon = Icon("✓", "+");
+    pub const WARN: Icon = Icon("⚠", "!");
+    pub const ERR: Icon = Icon("✗", "x");
+    pub const ARROW: Icon = Icon("▶", ">");
+    pub const ARROW_RIGHT: Icon = Icon("→", "->");
+    pub const ARROW_LEFT: Icon = Icon("←", "<-");
+    pub const DOT: Icon = Icon("•", "-");
+    pub const TRADE: Icon = Icon("◆", "*");
+    pub const VAULT: Icon = Icon("◇", "o");
+    pub const STAR: Icon = Icon("★", "*");
+    pub const DIAMOND: Icon = Icon("♦", "<>");
+    pub const CIRCLE: Icon = Icon("●", "o");
+    pub const SQUARE: Icon = Icon("■", "#");
+    pub const TRIANGLE: Icon = Icon("▲", "^");
+    pub const CHECK: Icon = Icon("✔", "+");
+    pub const CROSS: Icon = Icon("✘", "x");
+    pub const PLUS: Icon = Icon("＋", "+");
+    pub const MINUS: Icon = Icon("－", "-");
+    pub const MONEY: Icon = Icon("💰", "$");
+    pub const CHART: Icon = Icon("📊", "#");
+    pub const ROCKET: Icon = Icon("🚀", "^");
+    pub const FIRE: Icon = Icon("🔥", "!");
+    pub const SPARKLES: Icon = Icon("✨", "*");
+    pub const LIGHTNING: Icon = Icon("⚡", "!");
+    pub const SHIELD: Icon = Icon("🛡", "#");
+    pub const TARGET: Icon = Icon("🎯", "@");
+    pub const BRIEFCASE: Icon = Icon("💼", "#");
+    pub const CHART_UP: Icon = Icon("📈", "^");
+    pub const TOP: Icon = Icon("🔝", "^");
+    pub const BROOM: Icon = Icon("🧹", "~");
+    pub const HANDSHAKE: Icon = Icon("🤝", "<>");
+    pub const SCALES: Icon = Icon("⚖️", "=");
+    pub const CAP: Icon = Icon("🧢", "|");
+    pub const MEMO: Icon = Icon("📝", "-");
+    pub const BRICKS: Icon = Icon("🧱", "#");
+    pub const RULER: Icon = Icon("📐", "%");
 }
 
 pub fn panel_top(width: usize) -> String {
@@ -153,3 +244,16 @@ pub fn banner_gradient() -> Vec<String> {
         format!("{}{}{}", color, line, colors::RESET)
     }).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_color_or_a_pipe_turns_styling_off() {
+        assert!(color_choice(None, true));
+        assert!(color_choice(Some(""), true));
+        assert!(!color_choice(Some("1"), true));
+        assert!(!color_choice(None, false));
+    }
+}
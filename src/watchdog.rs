@@ -0,0 +1,173 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+use crate::alerts::{self, AlertKind};
+use crate::config::EnvConfig;
+use crate::ledger::SharedLedger;
+use crate::position_builder;
+use crate::resting_orders;
+use crate::trading_state::{self, PauseLevel};
+use crate::types::UserActivity;
+use crate::utils::{DataApiClient, Logger};
+
+/// Market orders can fill a little off the size we computed from the book.
+const SIZE_TOLERANCE: f64 = 0.05;
+/// Bot fills older than this can no longer be matched and are dropped.
+const BOT_FILL_RETENTION_SECS: i64 = 3600;
+/// Latest wallet trades each poll compares against the bot's fills.
+const WALLET_TRADES: usize = 100;
+
+#[derive(Debug, Clone)]
+struct BotFill {
+    asset: String,
+    side: &'static str,
+    tokens_left: f64,
+    at: i64,
+}
+
+static BOT_FILLS: Mutex<VecDeque<BotFill>> = Mutex::new(VecDeque::new());
+
+/// Records an order the bot itself filled, so the watchdog can tell it apart from foreign
+/// activity on the wallet.
+pub fn note_bot_fill(asset: &str, side: &'static str, tokens: f64) {
+    if let Ok(mut fills) = BOT_FILLS.lock() {
+        let now = chrono::Utc::now().timestamp();
+        fills.retain(|f| now - f.at <= BOT_FILL_RETENTION_SECS && f.tokens_left > 0.0);
+        fills.push_back(BotFill {
+            asset: asset.to_string(),
+            side,
+            tokens_left: tokens,
+            at: now,
+        });
+    }
+}
+
+/// Consumes a matching bot fill for an observed wallet trade.
+fn claim_bot_fill(asset: &str, side: &str, tokens: f64, ts: i64, grace_secs: i64) -> bool {
+    let Ok(mut fills) = BOT_FILLS.lock() else {
+        return false;
+    };
+    let matched = fills.iter_mut().find(|f| {
+        f.asset == asset
+            && f.side == side
+            && (f.at - ts).abs() <= grace_secs
+            && f.tokens_left >= tokens * (1.0 - SIZE_TOLERANCE)
+    });
+    match matched {
+        Some(fill) => {
+            fill.tokens_left = (fill.tokens_left - tokens).max(0.0);
+            true
+        }
+        None => false,
+    }
+}
+
+fn activity_key(a: &UserActivity) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        a.transaction_hash.as_deref().unwrap_or(""),
+        a.asset.as_deref().unwrap_or(""),
+        a.side.as_deref().unwrap_or(""),
+        a.size.unwrap_or(0.0)
+    )
+}
+
+pub(crate) async fn fetch_wallet_trades(
+    http_client: &reqwest::Client,
+    config: &EnvConfig,
+) -> anyhow::Result<Vec<UserActivity>> {
+    Ok(DataApiClient::from_config(config, http_client)
+        .get_trades(&config.proxy_wallet, WALLET_TRADES)
+        .await?)
+}
+
+/// Cross-checks the proxy wallet's trades against the bot's own fills. Trades younger than
+/// the grace window are re-checked on the next poll, since our own fill may not be recorded
+/// yet. Everything already on the wallet at the first poll is treated as known.
+#[derive(Default)]
+pub struct WalletWatchdog {
+    seen: HashSet<String>,
+    baselined: bool,
+}
+
+impl WalletWatchdog {
+    pub async fn poll(
+        &mut self,
+        config: &EnvConfig,
+        http_client: &reqwest::Client,
+        ledger: &SharedLedger,
+    ) -> anyhow::Result<()> {
+        let mut trades = fetch_wallet_trades(http_client, config).await?;
+        if !self.baselined {
+            self.seen.extend(trades.iter().map(activity_key));
+            self.baselined = true;
+            return Ok(());
+        }
+        let grace = config.wallet_watchdog_grace_secs as i64;
+        let now = chrono::Utc::now().timestamp();
+        trades.sort_by_key(|t| t.timestamp.unwrap_or(0));
+        for trade in trades {
+            let key = activity_key(&trade);
+            if self.seen.contains(&key) {
+                continue;
+            }
+            let (Some(asset), Some(side)) = (trade.asset.as_deref(), trade.side.as_deref()) else {
+                self.seen.insert(key);
+                continue;
+            };
+            let ts = trade.timestamp.unwrap_or(now);
+            if claim_bot_fill(asset, side, trade.size.unwrap_or(0.0), ts, grace)
+                || resting_orders::covers(&config.state_dir, asset, side)
+                || position_builder::covers(&config.state_dir, asset, side)
+            {
+                self.seen.insert(key);
+            } else if now - ts >= grace {
+                self.seen.insert(key);
+                on_foreign_trade(config, ledger, &trade).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn on_foreign_trade(config: &EnvConfig, ledger: &SharedLedger, trade: &UserActivity) {
+    let asset = trade.asset.as_deref().unwrap_or("");
+    let side = trade.side.as_deref().unwrap_or("?");
+    let tokens = trade.size.unwrap_or(0.0);
+    let usd = trade
+        .usdc_size
+        .unwrap_or(tokens * trade.price.unwrap_or(0.0));
+    let message = format!(
+        "wallet {} traded without a bot decision: {} {:.2} tokens (${:.2}) of {} (tx {})",
+        Logger::format_address(&config.proxy_wallet),
+        side,
+        tokens,
+        usd,
+        trade
+            .title
+            .as_deref()
+            .or(trade.slug.as_deref())
+            .unwrap_or(asset),
+        trade.transaction_hash.as_deref().unwrap_or("?")
+    );
+    Logger::error(&format!("CRITICAL: {}", message));
+    alerts::critical(AlertKind::ForeignWalletActivity, &message);
+    {
+        let mut ledger = ledger.lock().await;
+        if side == "BUY" {
+            let cid = trade.condition_id.as_deref().unwrap_or("");
+            ledger.record_buy(asset, cid, None, tokens, usd);
+        } else {
+            ledger.record_sell(asset, tokens);
+        }
+    }
+    // BUYs stay paused until a restart, after the wallet has been checked; SELLs are still
+    // copied so existing positions can be closed.
+    if config.pause_on_foreign_activity {
+        trading_state::pause(
+            "foreign_activity",
+            PauseLevel::Buys,
+            "foreign wallet activity; check the wallet and key, then restart the bot",
+        );
+    }
+}
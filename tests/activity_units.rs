@@ -0,0 +1,63 @@
+//! Units of `RtdsActivity` numbers by activity type, pinned against payloads in the shape RTDS
+//! sends them: trades normalize to shares, price and USD; every other type is rejected rather
+//! than read as a trade.
+
+use polymarket_copy_rust::config::UsdcSizePreference;
+use polymarket_copy_rust::RtdsActivity;
+use serde::Deserialize;
+
+const PAYLOADS: &str = include_str!("data/activity_payloads.jsonl");
+const NOW: i64 = 1_760_000_100;
+
+#[derive(Deserialize)]
+struct Expected {
+    shares: f64,
+    price: f64,
+    usd: f64,
+}
+
+#[derive(Deserialize)]
+struct Case {
+    name: String,
+    payload: serde_json::Value,
+    #[serde(default)]
+    expect: Option<Expected>,
+    #[serde(default)]
+    rejected: Option<String>,
+}
+
+fn cases() -> Vec<Case> {
+    PAYLOADS
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).expect("fixture line"))
+        .collect()
+}
+
+#[test]
+fn payloads_normalize_by_type() {
+    let cases = cases();
+    assert!(cases.iter().any(|c| c.payload["side"] == "BUY" && c.expect.is_some()));
+    assert!(cases.iter().any(|c| c.payload["side"] == "SELL" && c.expect.is_some()));
+    assert!(cases.iter().any(|c| c.rejected.is_some()));
+    for case in cases {
+        let activity: RtdsActivity =
+            serde_json::from_value(case.payload).unwrap_or_else(|e| panic!("{}: {}", case.name, e));
+        let usd = activity.usd_value(UsdcSizePreference::Conservative);
+        match (case.expect, case.rejected) {
+            (Some(expect), None) => {
+                assert!(activity.validate(NOW).is_ok(), "{}", case.name);
+                assert_eq!(activity.shares(), Ok(expect.shares), "{}", case.name);
+                assert_eq!(activity.price(), Ok(expect.price), "{}", case.name);
+                let usd = usd.unwrap_or_else(|e| panic!("{}: {}", case.name, e));
+                assert!((usd.chosen - expect.usd).abs() < 1e-9, "{}: {:?}", case.name, usd);
+            }
+            (None, Some(reason)) => {
+                let err = activity.validate(NOW).expect_err(&case.name);
+                assert!(err.contains(&reason), "{}: {}", case.name, err);
+                assert!(usd.is_err(), "{}", case.name);
+            }
+            _ => panic!("{}: needs exactly one of expect / rejected", case.name),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! `EnvConfig::from_env` reads the environment and nothing else: with `PROXY_WALLET` set it
+//! must not make a single outbound request. Every HTTP client honours `HTTP(S)_PROXY`, so
+//! pointing those at a local listener catches any request, whatever its destination.
+//!
+//! Its own test binary, since it sets process-wide environment variables.
+
+use std::net::TcpListener;
+use std::time::Duration;
+
+use polymarket_copy_rust::EnvConfig;
+
+const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+#[tokio::test]
+async fn from_env_makes_no_network_calls() {
+    let trap = TcpListener::bind("127.0.0.1:0").unwrap();
+    trap.set_nonblocking(true).unwrap();
+    let proxy = format!("http://{}", trap.local_addr().unwrap());
+    for key in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy"] {
+        std::env::set_var(key, &proxy);
+    }
+    for key in ["NO_PROXY", "no_proxy", "PRIVATE_KEY_FILE"] {
+        std::env::remove_var(key);
+    }
+    for (key, value) in [
+        ("USER_ADDRESSES", "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b"),
+        ("PROXY_WALLET", "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"),
+        ("PRIVATE_KEY", PRIVATE_KEY),
+        ("CLOB_HTTP_URL", "https://clob.polymarket.com"),
+        ("CLOB_WS_URL", "wss://ws-subscriptions-clob.polymarket.com/ws"),
+        ("RPC_URL", "https://polygon-rpc.com"),
+        (
+            "USDC_CONTRACT_ADDRESS",
+            "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174",
+        ),
+    ] {
+        std::env::set_var(key, value);
+    }
+
+    let config = EnvConfig::from_env().await.unwrap();
+    assert_eq!(config.private_key, PRIVATE_KEY);
+    assert_eq!(
+        config.proxy_wallet,
+        "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+    );
+
+    // Give a stray fire-and-forget request time to connect.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    match trap.accept() {
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+        Ok((_, peer)) => panic!("from_env made an outbound request (via {})", peer),
+        Err(e) => panic!("proxy trap failed: {}", e),
+    }
+}
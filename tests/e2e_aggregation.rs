@@ -0,0 +1,44 @@
+//! End to end against the fake stack: with `TRADE_AGGREGATION_ENABLED` a trader's fills in
+//! one market are held for the window and copied as one order.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn fills_in_a_window_are_copied_as_one_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 160.0);
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("TRADE_AGGREGATION_ENABLED".into(), "true".into());
+    vars.insert("TRADE_AGGREGATION_WINDOW_SECONDS".into(), "2".into());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    rtds.push_trade(trade(TRADER, &market, "BUY", 100.0, "0x01", now));
+    rtds.push_trade(trade(TRADER, &market, "BUY", 60.0, "0x02", now));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(polymarket.submissions().is_empty(), "copied before the window closed");
+
+    assert!(polymarket.wait_for_submissions(1, WAIT).await, "bucket never flushed");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let rows = bot.shutdown().await;
+
+    // $50 + $30 of fills, copied at 10%.
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 1, "{:?}", orders);
+    assert!((orders[0].usd - 8.0).abs() < 0.02, "{:?}", orders[0]);
+    assert_eq!(rows.len(), 1, "{:#?}", rows);
+    assert!((rows[0].my_usd - 8.0).abs() < 0.02);
+}
@@ -0,0 +1,67 @@
+//! End to end against the fake stack with `DRY_RUN=true` and a position build a live run
+//! left in the state dir: the build is not worked, so no slice order reaches the CLOB.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::position_builder::Build;
+use polymarket_copy_rust::testkit::{
+    config_vars, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn dry_run_leaves_saved_builds_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    let build = Build {
+        id: "0xlive".to_string(),
+        asset: market.asset.clone(),
+        condition_id: market.condition_id.clone(),
+        trader: TRADER.to_string(),
+        title: Some(market.title.clone()),
+        outcome: Some(market.outcome.clone()),
+        target_usd: 50.0,
+        filled_usd: 0.0,
+        filled_tokens: 0.0,
+        anchor_price: market.price,
+        started_at: chrono::Utc::now().timestamp(),
+        open_order: None,
+        ended_at: None,
+        end_reason: None,
+    };
+    let state = dir.path().join("state");
+    std::fs::create_dir_all(&state).unwrap();
+    std::fs::write(
+        state.join("position_builds.json"),
+        serde_json::to_string(&vec![build]).unwrap(),
+    )
+    .unwrap();
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("DRY_RUN".to_string(), "true".to_string());
+    vars.insert("POSITION_BUILD_MODE".to_string(), "PROGRESSIVE".to_string());
+    vars.insert("BUILD_INTERVAL_SECS".to_string(), "1".to_string());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+
+    // A live builder would have placed its first slice within a round or two.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    bot.shutdown().await;
+    assert!(polymarket.submissions().is_empty());
+    let touched: Vec<String> = polymarket
+        .requests()
+        .into_iter()
+        .filter(|r| r.contains("/order") || r.contains("/book"))
+        .collect();
+    assert!(touched.is_empty(), "{:?}", touched);
+    let saved: Vec<Build> = serde_json::from_str(
+        &std::fs::read_to_string(state.join("position_builds.json")).unwrap(),
+    )
+    .unwrap();
+    assert!(saved[0].is_active() && saved[0].open_order.is_none());
+}
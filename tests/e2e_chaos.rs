@@ -0,0 +1,71 @@
+//! End to end against the fake stack with `CHAOS_MODE` on and a fixed seed: the RTDS stream
+//! drops under the bot and market lookups fail, and every trade is still journaled exactly
+//! once, the dropped ones by the backfill after each reconnect.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(30);
+const TRADES: usize = 8;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn no_journaled_signal_is_lost_to_injected_faults() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let markets = [FakeMarket::new(1, 0.50), FakeMarket::new(2, 0.40)];
+    for market in &markets {
+        polymarket.add_market(market);
+        polymarket.set_position(TRADER, market, 1_000.0);
+    }
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    for (key, value) in [
+        ("DRY_RUN", "true"),
+        ("CHAOS_MODE", "true"),
+        ("CHAOS_SEED", "3"),
+        ("CHAOS_MAX_LATENCY_MS", "50"),
+        ("CHAOS_REQUEST_FAILURE_RATE", "0.3"),
+        ("CHAOS_DISCONNECT_RATE", "0.25"),
+        ("CHAOS_MALFORMED_RATE", "0"),
+        ("RTDS_MAX_BACKOFF_SECS", "1"),
+    ] {
+        vars.insert(key.to_string(), value.to_string());
+    }
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    // Trades carry their real time: a backfill reaches back no further than the first
+    // connection.
+    tokio::time::sleep(Duration::from_millis(1_100)).await;
+
+    // The data API has every trade, as it would; the stream may drop any of them.
+    for i in 0..TRADES {
+        let tx = format!("0x{:02x}", i);
+        let now = chrono::Utc::now().timestamp();
+        let record = trade(TRADER, &markets[i % 2], "BUY", 10.0, &tx, now);
+        polymarket.add_activity(TRADER, record.clone());
+        rtds.push_trade(record);
+        let rows = bot.wait_for_journal(i + 1, WAIT).await;
+        assert_eq!(rows.len(), i + 1, "{} was never journaled: {:#?}", tx, rows);
+    }
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let rows = bot.shutdown().await;
+
+    assert!(rtds.subscriptions() > 1, "seed 3 should have dropped the stream");
+    assert_eq!(rows.len(), TRADES, "{:#?}", rows);
+    for i in 0..TRADES {
+        let tx = format!("0x{:02x}", i);
+        let copies: Vec<_> = rows.iter().filter(|r| r.tx_hash.as_deref() == Some(&tx)).collect();
+        assert_eq!(copies.len(), 1, "{}: {:#?}", tx, rows);
+        // Copied as far as a dry run goes.
+        assert_eq!(copies[0].status, JournalStatus::Skipped);
+        let reason = copies[0].reason.clone().unwrap_or_default();
+        assert!(reason.contains("would BUY"), "{}: {}", tx, reason);
+    }
+    assert!(polymarket.submissions().is_empty());
+}
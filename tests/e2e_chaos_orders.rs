@@ -0,0 +1,84 @@
+//! End to end against the fake stack: orders posted through chaos mode's gateway are lost on
+//! the way out or lose their answer, and the next start still settles every one of them
+//! into the journal without posting any twice.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::chaos::Chaos;
+use polymarket_copy_rust::config::{ChaosConfig, SizingInputs};
+use polymarket_copy_rust::order_intents;
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::types::UserActivity;
+use polymarket_copy_rust::utils::{create_clob_client, post_order, JournalStatus, OrderContext};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+const ORDERS: usize = 10;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn orders_lost_to_injected_faults_are_settled_on_startup() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 1_000.0);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let config = EnvConfig::from_vars(&vars).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    let chaos = Chaos::new(ChaosConfig {
+        seed: 11,
+        max_latency_ms: 20,
+        request_failure_rate: 0.5,
+        disconnect_rate: 0.0,
+        malformed_rate: 0.0,
+    });
+    let http_client = reqwest::Client::new();
+    let (clob_client, signer) = create_clob_client(&config).await.unwrap();
+    let order = OrderContext {
+        config: &config,
+        clob_client: &clob_client,
+        signer: &signer,
+        http_client: &http_client,
+        chaos: Some(&chaos),
+        sizing: SizingInputs::default(),
+    };
+    let mut failed = Vec::new();
+    for i in 0..ORDERS {
+        let tx = format!("0x{:02x}", i);
+        let activity: UserActivity =
+            serde_json::from_value(trade(TRADER, &market, "BUY", 10.0, &tx, now + i as i64)).unwrap();
+        if post_order(&order, "buy", None, None, &activity, 1_000.0, TRADER).await.is_err() {
+            failed.push(tx);
+        }
+    }
+    let posted = polymarket.submissions().len();
+    let answered = ORDERS - failed.len();
+    assert!(posted > answered, "seed 11 should lose an answer: {} posted", posted);
+    assert!(posted < ORDERS, "seed 11 should lose an order on the way out");
+    assert_eq!(order_intents::pending(&config.state_dir).len(), failed.len());
+
+    // The next start journals each unanswered order as filled or lost, and posts none again.
+    let bot = TestBot::start(config.clone()).await.unwrap();
+    let rows = bot.wait_for_journal(failed.len(), WAIT).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let rows_after = bot.shutdown().await;
+    assert_eq!(rows.len(), failed.len(), "{:#?}", rows);
+    assert_eq!(rows_after.len(), failed.len(), "{:#?}", rows_after);
+    for tx in &failed {
+        let copies: Vec<_> = rows_after.iter().filter(|r| r.tx_hash.as_ref() == Some(tx)).collect();
+        assert_eq!(copies.len(), 1, "{}: {:#?}", tx, rows_after);
+        if copies[0].status != JournalStatus::Executed {
+            let reason = copies[0].reason.as_deref().unwrap_or("");
+            assert!(reason.contains("never reached"), "{}: {}", tx, reason);
+        }
+    }
+    let filled = rows_after.iter().filter(|r| r.status == JournalStatus::Executed).count();
+    assert_eq!(answered + filled, posted, "{:#?}", rows_after);
+    assert!(order_intents::pending(&config.state_dir).is_empty());
+    assert_eq!(polymarket.submissions().len(), posted);
+}
@@ -0,0 +1,101 @@
+//! End to end against the fake stack: trades pushed over RTDS are copied, sized and journaled
+//! by the real monitor and executor, and shutdown drains what is still queued. One bot per
+//! test binary (see `testkit`), so the scenarios run in sequence on it.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::trading_state::{self, TradingState};
+use polymarket_copy_rust::utils::{state_log_path, JournalEntry, JournalStatus, StateTransition};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() < 0.02
+}
+
+fn row<'a>(rows: &'a [JournalEntry], tx: &str) -> &'a JournalEntry {
+    rows.iter()
+        .find(|r| r.tx_hash.as_deref() == Some(tx))
+        .unwrap_or_else(|| panic!("no journal row for {}: {:#?}", tx, rows))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn copies_sizes_filters_and_drains_trades() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let (m1, m2) = (FakeMarket::new(1, 0.50), FakeMarket::new(2, 0.40));
+    polymarket.add_market(&m1);
+    polymarket.add_market(&m2);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    // A $50 BUY is copied at the default 10%.
+    polymarket.set_position(TRADER, &m1, 100.0);
+    rtds.push_trade(trade(TRADER, &m1, "BUY", 100.0, "0x01", now));
+    let rows = bot.wait_for_journal(1, WAIT).await;
+    let bought = row(&rows, "0x01");
+    assert_eq!(bought.status, JournalStatus::Executed);
+    assert!(close(bought.my_usd, 5.0), "{:?}", bought);
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 1);
+    assert_eq!((orders[0].side.as_str(), orders[0].asset.as_str()), ("BUY", m1.asset.as_str()));
+    assert!(close(orders[0].usd, 5.0) && close(orders[0].tokens, 10.0), "{:?}", orders);
+    assert!(close(polymarket.position(PROXY_WALLET, &m1.asset), 10.0));
+
+    // Selling half of their position sells half of mine.
+    polymarket.set_position(TRADER, &m1, 50.0);
+    rtds.push_trade(trade(TRADER, &m1, "SELL", 50.0, "0x02", now + 1));
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    assert_eq!(row(&rows, "0x02").status, JournalStatus::Executed);
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[1].side, "SELL");
+    assert!(close(orders[1].tokens, 5.0), "{:?}", orders[1]);
+    assert!(close(polymarket.position(PROXY_WALLET, &m1.asset), 5.0));
+
+    // 10% of a $2,000 BUY is trimmed to MAX_ORDER_SIZE_USD ($100).
+    polymarket.set_position(TRADER, &m2, 5_000.0);
+    rtds.push_trade(trade(TRADER, &m2, "BUY", 5_000.0, "0x03", now + 2));
+    let rows = bot.wait_for_journal(3, WAIT).await;
+    assert!(close(row(&rows, "0x03").my_usd, 100.0));
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 3);
+    assert!(close(orders[2].usd, 100.0), "{:?}", orders[2]);
+
+    // A signal older than TOO_OLD_TIMESTAMP (24h) is dropped without a row or an order; the
+    // fresh one after it shows it was read.
+    polymarket.set_position(TRADER, &m1, 90.0);
+    rtds.push_trade(trade(TRADER, &m1, "BUY", 40.0, "0x04", now - 2 * 86_400));
+    rtds.push_trade(trade(TRADER, &m1, "BUY", 40.0, "0x05", now + 3));
+    let rows = bot.wait_for_journal(4, WAIT).await;
+    assert_eq!(row(&rows, "0x05").status, JournalStatus::Executed);
+    assert!(rows.iter().all(|r| r.tx_hash.as_deref() != Some("0x04")));
+    assert_eq!(polymarket.submissions().len(), 4);
+
+    // Trades still queued when shutdown starts are copied before the executor stops.
+    polymarket.set_order_latency(Duration::from_millis(300));
+    for (i, tx) in ["0x06", "0x07", "0x08"].iter().enumerate() {
+        rtds.push_trade(trade(TRADER, &m1, "BUY", 40.0, tx, now + 4 + i as i64));
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let rows = bot.shutdown().await;
+    for tx in ["0x06", "0x07", "0x08"] {
+        assert_eq!(row(&rows, tx).status, JournalStatus::Executed);
+    }
+    assert_eq!(rows.len(), 7);
+    assert_eq!(polymarket.submissions().len(), 7);
+    // Once the queue is drained, trading goes to draining, which ends the helper loops.
+    assert_eq!(trading_state::current(), TradingState::Draining);
+    // ...and the transition is journaled beside the trades.
+    let log = std::fs::read_to_string(state_log_path(&dir.path().join("trades.jsonl"))).unwrap();
+    let last: StateTransition = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    assert_eq!((last.from.as_str(), last.to.as_str()), ("active", "draining"));
+}
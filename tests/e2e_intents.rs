@@ -0,0 +1,83 @@
+//! End to end against the fake stack: a copy order whose response never arrives is left as
+//! a pending intent, and the next start settles it from the CLOB into the journal before
+//! copying anything new. The "crashed" run posts through `post_order` directly, since a
+//! test binary only gets one bot.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::config::SizingInputs;
+use polymarket_copy_rust::order_intents;
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, OrderFailure, TestBot,
+    PROXY_WALLET,
+};
+use polymarket_copy_rust::types::UserActivity;
+use polymarket_copy_rust::utils::{create_clob_client, post_order, JournalStatus, OrderContext};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() < 0.02
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn in_flight_orders_are_settled_on_startup() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 100.0);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let config = EnvConfig::from_vars(&vars).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    // The crashed run: one order fills but its response is lost, one never gets there.
+    let http_client = reqwest::Client::new();
+    let (clob_client, signer) = create_clob_client(&config).await.unwrap();
+    let order = OrderContext {
+        config: &config,
+        clob_client: &clob_client,
+        signer: &signer,
+        http_client: &http_client,
+        chaos: None,
+        sizing: SizingInputs::default(),
+    };
+    for (failure, tx) in [(OrderFailure::DropResponse, "0x01"), (OrderFailure::DropRequest, "0x02")] {
+        polymarket.set_order_failure(Some(failure));
+        let activity: UserActivity =
+            serde_json::from_value(trade(TRADER, &market, "BUY", 100.0, tx, now)).unwrap();
+        let posted = post_order(&order, "buy", None, None, &activity, 1_000.0, TRADER).await;
+        assert!(posted.is_err(), "an unanswered order can't have succeeded");
+    }
+    polymarket.set_order_failure(None);
+    assert_eq!(polymarket.submissions().len(), 1);
+    let pending = order_intents::pending(&config.state_dir);
+    assert_eq!(pending.len(), 2, "{:#?}", pending);
+    assert!(close(pending[0].usd, 5.0), "{:#?}", pending);
+
+    // The next start journals both before anything else, without posting either again.
+    let bot = TestBot::start(config.clone()).await.unwrap();
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    assert_eq!(rows.len(), 2, "{:#?}", rows);
+    let filled = rows.iter().find(|r| r.tx_hash.as_deref() == Some("0x01")).unwrap();
+    assert_eq!(filled.status, JournalStatus::Executed);
+    assert!(close(filled.my_tokens, 10.0) && close(filled.my_usd, 5.0), "{:?}", filled);
+    let lost = rows.iter().find(|r| r.tx_hash.as_deref() == Some("0x02")).unwrap();
+    assert_eq!(lost.status, JournalStatus::Skipped);
+    assert!(lost.reason.as_deref().unwrap_or("").contains("never reached"), "{:?}", lost);
+    assert!(order_intents::pending(&config.state_dir).is_empty());
+    assert_eq!(polymarket.submissions().len(), 1);
+
+    // Settling again finds nothing, and new trades copy as usual.
+    assert!(order_intents::resolve(&config.state_dir, &clob_client).await.is_empty());
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    rtds.push_trade(trade(TRADER, &market, "BUY", 100.0, "0x03", now + 1));
+    let rows = bot.wait_for_journal(3, WAIT).await;
+    let rows_after = bot.shutdown().await;
+    assert_eq!(rows.len(), 3, "{:#?}", rows);
+    assert_eq!(rows_after[2].status, JournalStatus::Executed);
+    assert_eq!(polymarket.submissions().len(), 2);
+}
@@ -0,0 +1,63 @@
+//! End to end against the fake stack: `POST /copy-position` on the status API copies a
+//! trader's existing position through the normal pipeline, sized as if they had just bought
+//! it, and journals the copy as manual.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use polymarket_copy_rust::status_api;
+use polymarket_copy_rust::testkit::{
+    config_vars, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const TOKEN: &str = "s3cret-token";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn copy_position_places_the_sized_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 1_000.0);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("STATUS_API_ADDR".to_string(), listener.local_addr().unwrap().to_string());
+    vars.insert("STATUS_API_TOKEN".to_string(), TOKEN.to_string());
+    let config = EnvConfig::from_vars(&vars).unwrap();
+    let router = status_api::router(Arc::new(config.clone()), TOKEN);
+    tokio::spawn(status_api::serve(listener, router));
+    let bot = TestBot::start(config.clone()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+
+    // Half of the trader's $500 position, copied at 10%.
+    let path = format!("/copy-position/{}/{}/0.5", TRADER, market.condition_id);
+    let response = status_api::post_action(&config, &path).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "executed", "{}", body);
+    assert!((body["usd"].as_f64().unwrap() - 25.0).abs() < 0.02, "{}", body);
+
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].asset, market.asset);
+    assert_eq!(orders[0].side, "BUY");
+    assert!((orders[0].usd - 25.0).abs() < 0.02, "{:?}", orders[0]);
+
+    let untracked = format!(
+        "/copy-position/{}/{}",
+        "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23", market.condition_id
+    );
+    let rejected = status_api::post_action(&config, &untracked).await.unwrap();
+    assert_eq!(rejected.status(), 400);
+
+    let rows = bot.shutdown().await;
+    assert_eq!(rows.len(), 1, "{:#?}", rows);
+    assert_eq!(rows[0].status, JournalStatus::Executed);
+    assert!(rows[0].manual);
+    assert_eq!(polymarket.submissions().len(), 1);
+}
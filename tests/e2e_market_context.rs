@@ -0,0 +1,52 @@
+//! End to end against the fake stack with `JOURNAL_MARKET_CONTEXT` on: the journaled book is
+//! the one the order read, not a second fetch, and a skip still records the market.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn market_context_reuses_the_order_book() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let (m1, m2) = (FakeMarket::new(1, 0.50), FakeMarket::new(2, 0.40));
+    polymarket.add_market(&m1);
+    polymarket.add_market(&m2);
+    polymarket.set_position(TRADER, &m1, 1_000.0);
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("JOURNAL_MARKET_CONTEXT".to_string(), "true".to_string());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+    let book_reads = || polymarket.requests().iter().filter(|r| r.starts_with("GET /book")).count();
+
+    // The first copy in the market may prefetch its book; the second only reads it for its
+    // order, once for the slippage check and once in the SDK pricing the market order.
+    rtds.push_trade(trade(TRADER, &m1, "BUY", 100.0, "0x01", now));
+    bot.wait_for_journal(1, WAIT).await;
+    let before = book_reads();
+    rtds.push_trade(trade(TRADER, &m1, "BUY", 100.0, "0x02", now + 1));
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    assert_eq!(book_reads() - before, 2, "{:#?}", polymarket.requests());
+    assert_eq!(rows[1].status, JournalStatus::Executed);
+    let market = rows[1].market.clone().expect("market context");
+    assert_eq!(market.best_ask, Some(0.50));
+    assert_eq!(market.last_trade_price, Some(0.50));
+
+    // Selling what I don't hold places nothing; the journal reads the book itself.
+    rtds.push_trade(trade(TRADER, &m2, "SELL", 100.0, "0x03", now + 2));
+    let rows = bot.wait_for_journal(3, WAIT).await;
+    bot.shutdown().await;
+    assert_eq!(rows[2].status, JournalStatus::Skipped, "{:#?}", rows[2]);
+    let market = rows[2].market.clone().expect("market context");
+    assert_eq!(market.best_ask, Some(0.40));
+    assert_eq!(market.last_trade_price, Some(0.40));
+}
@@ -0,0 +1,91 @@
+//! Against the fake CLOB: a fee change picked up by the order template refresh reaches the
+//! next signed order, rather than the fee the SDK client cached on the first one, and a BUY
+//! under the market's Gamma minimum size is never sent.
+
+use polymarket_copy_rust::config::SizingInputs;
+use polymarket_copy_rust::order_templates;
+use polymarket_copy_rust::testkit::{config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, PROXY_WALLET};
+use polymarket_copy_rust::types::UserActivity;
+use polymarket_copy_rust::utils::{create_clob_client, post_order, OrderContext};
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn refreshed_fee_reaches_the_built_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 1_000.0);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let config = EnvConfig::from_vars(&vars).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    let http_client = reqwest::Client::new();
+    let (clob_client, signer) = create_clob_client(&config).await.unwrap();
+    let order = OrderContext {
+        config: &config,
+        clob_client: &clob_client,
+        signer: &signer,
+        http_client: &http_client,
+        chaos: None,
+        sizing: SizingInputs::default(),
+    };
+    order_templates::warm(&http_client, &config, &market.asset).await;
+    for (fee, tx) in [(0, "0x01"), (30, "0x02")] {
+        polymarket.set_fee_rate_bps(fee);
+        order_templates::refresh_all(&http_client, &config).await;
+        assert_eq!(order_templates::get(&market.asset).unwrap().fee_rate_bps, fee);
+        let activity: UserActivity =
+            serde_json::from_value(trade(TRADER, &market, "BUY", 10.0, tx, now)).unwrap();
+        post_order(&order, "buy", None, None, &activity, 1_000.0, TRADER).await.unwrap();
+    }
+    let fees: Vec<u64> = polymarket.submissions().iter().map(|s| s.fee_rate_bps).collect();
+    assert_eq!(fees, vec![0, 30]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn buys_under_the_markets_minimum_size_are_not_sent() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    // $5 at 0.50 buys 10 tokens: under the first market's 20, over the second's 5. Gamma
+    // lookups are cached per process, so these markets aren't the other test's.
+    let strict = FakeMarket {
+        min_order_size: 20.0,
+        ..FakeMarket::new(11, 0.50)
+    };
+    let lenient = FakeMarket {
+        min_order_size: 5.0,
+        ..FakeMarket::new(12, 0.50)
+    };
+    polymarket.add_market(&strict);
+    polymarket.add_market(&lenient);
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("COPY_STRATEGY".to_string(), "FIXED".to_string());
+    vars.insert("COPY_SIZE".to_string(), "5".to_string());
+    let config = EnvConfig::from_vars(&vars).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    let http_client = reqwest::Client::new();
+    let (clob_client, signer) = create_clob_client(&config).await.unwrap();
+    let order = OrderContext {
+        config: &config,
+        clob_client: &clob_client,
+        signer: &signer,
+        http_client: &http_client,
+        chaos: None,
+        sizing: SizingInputs::default(),
+    };
+    let mut filled = Vec::new();
+    for (market, tx) in [(&strict, "0x01"), (&lenient, "0x02")] {
+        let activity: UserActivity =
+            serde_json::from_value(trade(TRADER, market, "BUY", 40.0, tx, now)).unwrap();
+        let fill = post_order(&order, "buy", None, None, &activity, 1_000.0, TRADER).await.unwrap();
+        filled.push(fill.tokens);
+    }
+    assert_eq!(filled, vec![0.0, 10.0]);
+    assert_eq!(polymarket.submissions().len(), 1);
+}
@@ -0,0 +1,46 @@
+//! End to end against the fake stack with `DRY_RUN=true`: nothing reaches the CLOB, but the
+//! order a copy would have placed is journaled and kept as a simulated position, so a later
+//! SELL mirrors it.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn dry_run_trades_against_simulated_positions() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("DRY_RUN".to_string(), "true".to_string());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    polymarket.set_position(TRADER, &market, 100.0);
+    rtds.push_trade(trade(TRADER, &market, "BUY", 100.0, "0x01", now));
+    let rows = bot.wait_for_journal(1, WAIT).await;
+    assert_eq!(rows[0].status, JournalStatus::Skipped);
+    let reason = rows[0].reason.clone().unwrap_or_default();
+    assert!(reason.contains("would BUY 10.00 tokens"), "{}", reason);
+
+    // The wallet holds nothing, but the simulated 10 tokens are there to sell half of.
+    polymarket.set_position(TRADER, &market, 50.0);
+    rtds.push_trade(trade(TRADER, &market, "SELL", 50.0, "0x02", now + 1));
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    let rows_after = bot.shutdown().await;
+    assert_eq!(rows.len(), 2, "{:#?}", rows);
+    let reason = rows[1].reason.clone().unwrap_or_default();
+    assert!(reason.contains("would SELL 5.00 tokens"), "{}", reason);
+    assert_eq!(rows_after.len(), 2);
+    assert!(polymarket.submissions().is_empty());
+}
@@ -0,0 +1,57 @@
+//! End to end against the fake stack: a trade made while RTDS was down is backfilled on
+//! reconnect, and a trade seen before the drop is copied once however often it arrives.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(30);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn trades_are_copied_once_across_a_reconnect() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 200.0);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    let seen = trade(TRADER, &market, "BUY", 100.0, "0x0a", now);
+    let missed = trade(TRADER, &market, "BUY", 100.0, "0x0b", now + 1);
+    rtds.push_trade(seen.clone());
+    assert_eq!(bot.wait_for_journal(1, WAIT).await.len(), 1);
+
+    // The data API has both; only the second was made while the stream was down.
+    polymarket.add_activity(TRADER, seen.clone());
+    polymarket.add_activity(TRADER, missed);
+    rtds.drop_connections();
+    assert!(rtds.wait_for_subscriptions(2, WAIT).await, "bot never reconnected");
+    // RTDS replaying the first trade after the reconnect changes nothing.
+    rtds.push_trade(seen);
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let rows_after = bot.shutdown().await;
+
+    assert_eq!(rows.len(), 2, "{:#?}", rows);
+    assert_eq!(rows_after.len(), 2, "{:#?}", rows_after);
+    for tx in ["0x0a", "0x0b"] {
+        let copies: Vec<_> = rows_after
+            .iter()
+            .filter(|r| r.tx_hash.as_deref() == Some(tx))
+            .collect();
+        assert_eq!(copies.len(), 1, "{}: {:#?}", tx, rows_after);
+        assert_eq!(copies[0].status, JournalStatus::Executed);
+    }
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 2, "{:?}", orders);
+    assert!(orders.iter().all(|o| o.side == "BUY"));
+}
@@ -0,0 +1,52 @@
+//! End to end against the fake stack: one transaction can hold fills in several outcome
+//! tokens, and each of them is copied rather than deduped away behind the first.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn fills_sharing_a_transaction_are_all_copied() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let yes = FakeMarket::new(1, 0.50);
+    let no = FakeMarket {
+        asset: format!("{}9", yes.asset),
+        outcome: "No".to_string(),
+        ..yes.clone()
+    };
+    polymarket.add_market(&yes);
+    polymarket.add_market(&no);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    polymarket.set_position(TRADER, &yes, 100.0);
+    polymarket.set_position(TRADER, &no, 100.0);
+    rtds.push_trade(trade(TRADER, &yes, "BUY", 100.0, "0xab", now));
+    rtds.push_trade(trade(TRADER, &no, "BUY", 100.0, "0xab", now));
+    // A re-delivery of a fill is still a duplicate.
+    rtds.push_trade(trade(TRADER, &yes, "BUY", 100.0, "0xab", now));
+    assert!(polymarket.wait_for_submissions(2, WAIT).await);
+    let rows = bot.wait_for_journal(2, WAIT).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let rows_after = bot.shutdown().await;
+
+    assert_eq!(rows.len(), 2, "{:#?}", rows);
+    assert!(rows.iter().all(|r| r.status == JournalStatus::Executed), "{:#?}", rows);
+    assert_eq!(rows_after.len(), 2, "{:#?}", rows_after);
+    let mut assets: Vec<String> = polymarket.submissions().into_iter().map(|s| s.asset).collect();
+    assets.sort();
+    let mut expected = vec![yes.asset.clone(), no.asset.clone()];
+    expected.sort();
+    assert_eq!(assets, expected);
+}
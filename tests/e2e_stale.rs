@@ -0,0 +1,43 @@
+//! End to end against the fake stack: pings keep a quiet RTDS connection alive, and one that
+//! stops answering is dropped and reconnected, after which trades are copied again.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(30);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn a_stalled_connection_is_reconnected() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    let market = FakeMarket::new(1, 0.50);
+    polymarket.add_market(&market);
+    polymarket.set_position(TRADER, &market, 100.0);
+    let mut vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    vars.insert("RTDS_PING_INTERVAL_SECS".to_string(), "1".to_string());
+    vars.insert("RTDS_STALE_SECS".to_string(), "3".to_string());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+
+    // No trades for longer than the staleness window: the Pongs keep the connection up.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert_eq!(rtds.subscriptions(), 1, "a quiet but live connection was dropped");
+
+    rtds.stall_connections();
+    assert!(rtds.wait_for_subscriptions(2, WAIT).await, "bot never reconnected");
+    rtds.push_trade(trade(TRADER, &market, "BUY", 100.0, "0x0c", chrono::Utc::now().timestamp()));
+    let rows = bot.wait_for_journal(1, WAIT).await;
+    let rows_after = bot.shutdown().await;
+
+    assert_eq!(rows.len(), 1, "{:#?}", rows);
+    assert_eq!(rows_after[0].tx_hash.as_deref(), Some("0x0c"));
+    assert_eq!(rows_after[0].status, JournalStatus::Executed);
+    assert_eq!(polymarket.submissions().len(), 1);
+}
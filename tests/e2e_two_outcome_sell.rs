@@ -0,0 +1,66 @@
+//! End to end against the fake stack: when the trader and the bot both hold both outcomes of
+//! a market, a SELL is sized from the sold outcome's positions alone.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::testkit::{
+    config_vars, trade, FakeMarket, FakePolymarket, FakeRtds, TestBot, PROXY_WALLET,
+};
+use polymarket_copy_rust::utils::JournalStatus;
+use polymarket_copy_rust::EnvConfig;
+
+const TRADER: &str = "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b";
+const WAIT: Duration = Duration::from_secs(20);
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.05
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn sell_is_sized_from_the_sold_outcome() {
+    let dir = tempfile::tempdir().unwrap();
+    let polymarket = FakePolymarket::start(PROXY_WALLET, 1_000.0).await.unwrap();
+    let rtds = FakeRtds::start().await.unwrap();
+    // The No token is listed first in both position lists.
+    let no = FakeMarket {
+        outcome: "No".to_string(),
+        ..FakeMarket::new(1, 0.40)
+    };
+    let yes = FakeMarket {
+        asset: format!("{}9", no.asset),
+        outcome: "Yes".to_string(),
+        price: 0.50,
+        ..no.clone()
+    };
+    polymarket.add_market(&no);
+    polymarket.add_market(&yes);
+    let vars = config_vars(&polymarket, &rtds, &[TRADER], dir.path());
+    let bot = TestBot::start(EnvConfig::from_vars(&vars).unwrap()).await.unwrap();
+    assert!(rtds.wait_for_subscriptions(1, WAIT).await, "bot never subscribed");
+    let now = chrono::Utc::now().timestamp();
+
+    // Both outcomes are copied at 10%: 20 No and 10 Yes.
+    polymarket.set_position(TRADER, &no, 200.0);
+    rtds.push_trade(trade(TRADER, &no, "BUY", 200.0, "0x01", now));
+    bot.wait_for_journal(1, WAIT).await;
+    polymarket.set_position(TRADER, &yes, 100.0);
+    rtds.push_trade(trade(TRADER, &yes, "BUY", 100.0, "0x02", now + 1));
+    bot.wait_for_journal(2, WAIT).await;
+    assert!(close(polymarket.position(PROXY_WALLET, &no.asset), 20.0));
+    assert!(close(polymarket.position(PROXY_WALLET, &yes.asset), 10.0));
+
+    // Selling half of their Yes sells half of my Yes; No takes no part in the fraction.
+    polymarket.set_position(TRADER, &yes, 50.0);
+    rtds.push_trade(trade(TRADER, &yes, "SELL", 50.0, "0x03", now + 2));
+    let rows = bot.wait_for_journal(3, WAIT).await;
+    let rows_after = bot.shutdown().await;
+
+    let sold = rows.iter().find(|r| r.tx_hash.as_deref() == Some("0x03")).unwrap();
+    assert_eq!(sold.status, JournalStatus::Executed, "{:#?}", rows_after);
+    let orders = polymarket.submissions();
+    assert_eq!(orders.len(), 3, "{:?}", orders);
+    assert_eq!((orders[2].side.as_str(), orders[2].asset.as_str()), ("SELL", yes.asset.as_str()));
+    assert!(close(orders[2].tokens, 5.0), "{:?}", orders[2]);
+    assert!(close(polymarket.position(PROXY_WALLET, &yes.asset), 5.0));
+    assert!(close(polymarket.position(PROXY_WALLET, &no.asset), 20.0));
+}
@@ -0,0 +1,64 @@
+//! Throughput regression thresholds: the copy path, against the fake Polymarket stack, keeps
+//! up with a busy event's signal rate, and a slow order gateway shows up as queue pressure.
+
+use std::time::Duration;
+
+use polymarket_copy_rust::loadtest::{self, LoadProfile};
+
+/// 10% of the trader's size, capped at $100 an order.
+const SIZING: &[(&str, &str)] = &[
+    ("COPY_STRATEGY", "PERCENTAGE"),
+    ("COPY_SIZE", "10"),
+    ("MAX_ORDER_SIZE_USD", "100"),
+    ("MIN_ORDER_SIZE_USD", "1"),
+];
+
+fn profile(rate_per_sec: f64, secs: u64, latency_ms: u64) -> LoadProfile {
+    LoadProfile {
+        rate_per_sec,
+        duration: Duration::from_secs(secs),
+        gateway_latency: Duration::from_millis(latency_ms),
+        ..LoadProfile::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn keeps_up_with_a_busy_event() {
+    let report = loadtest::run(&profile(10.0, 5, 25), SIZING).await.unwrap();
+    let lines = report.lines().join("\n");
+    assert_eq!(report.sent, 50, "{}", lines);
+    assert_eq!(report.processed, 50, "{}", lines);
+    assert!(report.kept_up(), "{}", lines);
+    assert!(report.orders > 0, "{}", lines);
+    assert!(
+        report.journal_high_water < report.journal_capacity,
+        "{}",
+        lines
+    );
+    assert_eq!(report.journal_dropped, 0, "{}", lines);
+    let post = report
+        .hot_path
+        .iter()
+        .find(|(name, _)| *name == "post_order");
+    assert!(post.is_some_and(|(_, s)| s.count > 0), "{}", lines);
+    assert!(report.stage("copy").unwrap().count > 0);
+    assert!(report.queue_high_water <= 5, "{}", lines);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn a_slow_gateway_backs_up_the_queue() {
+    // 400ms per order caps the consumer under 2.5/s, well under the 20/s offered.
+    let profile = LoadProfile {
+        queue_capacity: 10,
+        ..profile(20.0, 2, 400)
+    };
+    let report = loadtest::run(&profile, SIZING).await.unwrap();
+    let lines = report.lines().join("\n");
+    assert!(!report.kept_up(), "{}", lines);
+    assert_eq!(report.queue_high_water, profile.queue_capacity, "{}", lines);
+    assert!(
+        report.stage("queue_wait").unwrap().p90 > Duration::from_secs(1),
+        "{}",
+        lines
+    );
+}
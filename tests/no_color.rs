@@ -0,0 +1,75 @@
+//! With `NO_COLOR` set (or stdout piped) the output is plain text: no escape sequences from the
+//! banner, panels, log lines or spinner, and ASCII in place of the emoji icons.
+//!
+//! Its own test binary, since it switches colors off process-wide.
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+use polymarket_copy_rust::utils::theme::{self, colors, icons};
+use polymarket_copy_rust::utils::Spinner;
+
+const ESC: char = '\x1b';
+
+#[test]
+fn startup_output_has_no_escape_bytes_with_no_color() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_polymarket-copy-rust"))
+        .current_dir(dir.path())
+        .env_clear()
+        .env("PATH", std::env::var("PATH").unwrap_or_default())
+        .env("HOME", dir.path())
+        .env("NO_COLOR", "1")
+        .env("DRY_RUN", "true")
+        .env("USER_ADDRESSES", "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b")
+        .env("PROXY_WALLET", "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23")
+        .env(
+            "PRIVATE_KEY",
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        )
+        .env("CLOB_HTTP_URL", "http://127.0.0.1:9")
+        .env("CLOB_WS_URL", "ws://127.0.0.1:9")
+        .env("RPC_URL", "http://127.0.0.1:9")
+        .env("USDC_CONTRACT_ADDRESS", "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174")
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .env("HTTP_PROXY", "http://127.0.0.1:9")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Banner, startup panel, dry-run header and the first log lines; stop before the network.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut output = String::new();
+    while !output.contains("Running system check") {
+        if stdout.read_line(&mut output).unwrap() == 0 {
+            break;
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    stdout.read_to_string(&mut output).unwrap();
+    child.stderr.take().unwrap().read_to_string(&mut output).unwrap();
+
+    assert!(output.contains("Alpha sources (1)"), "{}", output);
+    assert!(output.contains("╭") && output.contains("Build:"), "{}", output);
+    assert!(!output.contains(ESC), "escape bytes in:\n{:?}", output);
+    assert!(!output.contains("▶") && !output.contains("ℹ"), "{}", output);
+}
+
+#[test]
+fn theme_and_spinner_render_plain_when_colors_are_off() {
+    theme::set_color_enabled(false);
+    let mut rendered = theme::banner_gradient();
+    rendered.push(theme::panel_top(20));
+    rendered.push(theme::panel_bottom(20));
+    rendered.push(theme::separator_line(20, "double"));
+    rendered.push(colors::gradient("Polymarket", 51, 195));
+    rendered.push(format!("{}{} done{}", colors::SUCCESS, icons::OK, colors::RESET));
+    rendered.push(Spinner::default().frame_colored());
+    for line in &rendered {
+        assert!(!line.contains(ESC), "{:?}", line);
+    }
+    assert_eq!(rendered[rendered.len() - 2], "+ done");
+    assert_eq!(icons::ROCKET.to_string(), "^");
+}
@@ -0,0 +1,88 @@
+//! A captured RTDS session replays to the same executor input every time: the same trades,
+//! in the same order, at the same offsets from the start.
+
+use polymarket_copy_rust::monitor::replay_frames;
+use polymarket_copy_rust::rtds_capture;
+use polymarket_copy_rust::RtdsActivity;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+const SESSION: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/rtds_session.jsonl");
+
+const TRACKED: &[&str] = &[
+    "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b",
+    "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23",
+];
+
+fn describe(offset_ms: u128, activity: &RtdsActivity, trader: &str) -> String {
+    format!(
+        "+{}ms {} {} {} x{} @ {} ts={} tx={}",
+        offset_ms,
+        trader,
+        activity.side.as_deref().unwrap_or("?"),
+        activity.slug.as_deref().unwrap_or("?"),
+        activity.size.unwrap_or_default(),
+        activity.price.unwrap_or_default(),
+        activity.timestamp.unwrap_or_default(),
+        activity.transaction_hash.as_deref().unwrap_or("?"),
+    )
+}
+
+/// Replays the session into a fresh executor channel and describes what arrived, and when.
+async fn replay(speed: f64) -> Vec<String> {
+    let frames = rtds_capture::load(SESSION).expect("fixture loads");
+    let traders: Vec<String> = TRACKED.iter().map(|a| a.to_string()).collect();
+    let (tx, mut rx) = mpsc::channel::<(RtdsActivity, String)>(16);
+    let started = Instant::now();
+    let received = tokio::spawn(async move {
+        let mut lines = Vec::new();
+        while let Some((activity, trader)) = rx.recv().await {
+            lines.push(describe(started.elapsed().as_millis(), &activity, &trader));
+        }
+        lines
+    });
+    let malformed = tempfile::tempdir().expect("tempdir");
+    let malformed_log = malformed.path().join("malformed.jsonl");
+    // Replayed at the capture's own clock, so trade timestamps are left as recorded.
+    let sent = replay_frames(
+        &frames,
+        speed,
+        frames[0].received_at_ms,
+        &traders,
+        malformed_log.to_str().unwrap(),
+        &tx,
+        &CancellationToken::new(),
+    )
+    .await;
+    drop(tx);
+    let lines = received.await.expect("receiver");
+    assert_eq!(lines.len(), sent);
+    lines
+}
+
+#[tokio::test(start_paused = true)]
+async fn replays_are_identical() {
+    let first = replay(1.0).await;
+    let second = replay(1.0).await;
+    assert_eq!(first, second);
+    // The untracked trader, the heartbeat and the non-trade topic are not forwarded.
+    assert_eq!(first.len(), 3);
+    let offsets: Vec<&str> = first.iter().map(|l| l.split(' ').next().unwrap()).collect();
+    assert_eq!(offsets, vec!["+1200ms", "+4700ms", "+10450ms"]);
+    assert!(first[1].contains("SELL btc-above-100k-friday x80 @ 0.61 ts=1760000004500"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn speed_scales_the_gaps_only() {
+    let normal = replay(1.0).await;
+    let fast = replay(2.0).await;
+    let strip = |lines: &[String]| -> Vec<String> {
+        lines
+            .iter()
+            .map(|l| l.split_once(' ').unwrap().1.to_string())
+            .collect()
+    };
+    assert_eq!(strip(&normal), strip(&fast));
+    assert!(fast[2].starts_with("+5225ms"), "{}", fast[2]);
+}
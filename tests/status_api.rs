@@ -0,0 +1,217 @@
+//! The remote status API: the bearer token guards every route, `/trades` and `/positions`
+//! serve what the executor and the refresh recorded, and `/pause` holds a trading state pause
+//! until `/resume`. Registered admin actions answer `POST /<name>/<rest>` only with the token;
+//! the localhost endpoint serves `GET /status` and nothing else.
+//!
+//! Its own test binary, since pausing is process-wide.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use polymarket_copy_rust::advisories::{self, AdvisorySeverity};
+use polymarket_copy_rust::status;
+use polymarket_copy_rust::status_api::{self, CopiedTrade};
+use polymarket_copy_rust::trading_state;
+use polymarket_copy_rust::types::{UserActivity, UserPosition};
+use polymarket_copy_rust::EnvConfig;
+
+const TOKEN: &str = "s3cret-token";
+
+fn config() -> EnvConfig {
+    let vars: HashMap<String, String> = [
+        ("USER_ADDRESSES", "0x7c3db723f1d4d8cb9c550095203b686cb11e5c6b"),
+        ("PROXY_WALLET", "0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"),
+        (
+            "PRIVATE_KEY",
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        ),
+        ("CLOB_HTTP_URL", "http://127.0.0.1:9"),
+        ("CLOB_WS_URL", "ws://127.0.0.1:9"),
+        ("RPC_URL", "http://127.0.0.1:9"),
+        ("USDC_CONTRACT_ADDRESS", "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174"),
+        ("STATUS_API_ADDR", "127.0.0.1:0"),
+        ("STATUS_API_TOKEN", TOKEN),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    EnvConfig::from_vars(&vars).unwrap()
+}
+
+fn copied(hash: &str, usd: f64) -> CopiedTrade {
+    let trade = UserActivity {
+        transaction_hash: Some(hash.to_string()),
+        side: Some("BUY".to_string()),
+        title: Some("Will it rain?".to_string()),
+        usdc_size: Some(usd * 10.0),
+        ..Default::default()
+    };
+    CopiedTrade {
+        reasoning: Some(format!("10% of trader's ${:.2}", usd * 10.0)),
+        ..CopiedTrade::new("whale", &trade, usd, usd * 2.0)
+    }
+}
+
+#[tokio::test]
+async fn routes_need_the_token_and_pause_holds_until_resume() {
+    let config = Arc::new(config());
+    let router = status_api::router(config, TOKEN);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(status_api::serve(listener, router));
+    let http = reqwest::Client::new();
+    let get = |path: &str, token: Option<&str>| {
+        let mut req = http.get(format!("{}{}", base, path));
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        req.send()
+    };
+
+    assert_eq!(get("/trades", None).await.unwrap().status(), 401);
+    assert_eq!(get("/trades", Some("s3cret-tokem")).await.unwrap().status(), 401);
+    let pause = http.post(format!("{}/pause", base)).send().await.unwrap();
+    assert_eq!(pause.status(), 401);
+    assert!(trading_state::check("SELL").is_ok());
+
+    assert_eq!(get("/positions", Some(TOKEN)).await.unwrap().status(), 503);
+    status_api::set_positions(vec![UserPosition {
+        asset: Some("123".to_string()),
+        size: Some(20.0),
+        ..Default::default()
+    }]);
+    let positions: serde_json::Value =
+        get("/positions", Some(TOKEN)).await.unwrap().json().await.unwrap();
+    assert_eq!(positions["positions"][0]["asset"], "123");
+    assert_eq!(positions["positions"][0]["size"], 20.0);
+
+    for (i, usd) in [1.0, 2.0, 3.0].into_iter().enumerate() {
+        status_api::record_copy(copied(&format!("0x0{}", i), usd));
+    }
+    let trades: serde_json::Value =
+        get("/trades?limit=2", Some(TOKEN)).await.unwrap().json().await.unwrap();
+    let trades = trades.as_array().unwrap();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0]["transaction_hash"], "0x02");
+    assert_eq!(trades[0]["price"], 0.5);
+    assert_eq!(trades[0]["reasoning"], "10% of trader's $30.00");
+    assert_eq!(trades[1]["transaction_hash"], "0x01");
+
+    let paused: serde_json::Value = http
+        .post(format!("{}/pause", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(paused["trading_state"].as_str().unwrap().starts_with("fully paused"));
+    assert!(trading_state::check("SELL").is_err());
+    let resumed = http
+        .post(format!("{}/resume", base))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resumed.status(), 200);
+    assert!(trading_state::check("BUY").is_ok());
+}
+
+#[tokio::test]
+async fn registered_actions_need_the_token() {
+    status::register_action("test_cap", |rest| match rest.split_once('/') {
+        Some((id, "25")) => Ok(serde_json::json!({ "market": id, "cap": 25 })),
+        _ => Err("bad cap".to_string()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut config = config();
+    config.status_api_addr = Some(addr);
+    tokio::spawn(status_api::serve(listener, status_api::router(Arc::new(config.clone()), TOKEN)));
+    let http = reqwest::Client::new();
+
+    // A cross-site form post carries no bearer token.
+    let forged = http
+        .post(format!("http://{}/test_cap/0xc1/25", addr))
+        .header("Origin", "https://evil.example")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forged.status(), 401);
+
+    let sent = status_api::post_action(&config, "/test_cap/0xc1/25").await.unwrap();
+    assert_eq!(sent.status(), 200);
+    let body: serde_json::Value = sent.json().await.unwrap();
+    assert_eq!(body["market"], "0xc1");
+    let rejected = status_api::post_action(&config, "/test_cap/0xc1/x").await.unwrap();
+    assert_eq!(rejected.status(), 400);
+    let missing = status_api::post_action(&config, "/nothing/here").await.unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn advisories_are_acknowledged_only_with_the_token() {
+    let dir = tempfile::tempdir().unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut config = config();
+    config.status_api_addr = Some(addr);
+    config.state_dir = dir.path().to_string_lossy().into_owned();
+    advisories::init(&config);
+    advisories::raise("old_build", AdvisorySeverity::Info, "old build", serde_json::json!({}));
+    tokio::spawn(status_api::serve(listener, status_api::router(Arc::new(config.clone()), TOKEN)));
+
+    let forged = reqwest::Client::new()
+        .post(format!("http://{}/advisories/old_build/ack", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forged.status(), 401);
+    assert!(status::snapshot()["advisories"][0]["acknowledged_at"].is_null());
+
+    let acked = status_api::post_action(&config, "/advisories/old_build/ack").await.unwrap();
+    assert_eq!(acked.status(), 200);
+    assert!(status::snapshot()["advisories"][0]["acknowledged_at"].is_i64());
+}
+
+#[tokio::test]
+async fn the_local_endpoint_serves_status_and_no_actions() {
+    status::publish("test_fixed", serde_json::json!({ "max": 3 }));
+    status::register_action("test_local", |_| Ok(serde_json::json!({ "done": true })));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let local = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, status_api::local_router()).await });
+    let remote_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let remote = format!("http://{}", remote_listener.local_addr().unwrap());
+    let router = status_api::router(Arc::new(config()), TOKEN);
+    tokio::spawn(status_api::serve(remote_listener, router));
+    let http = reqwest::Client::new();
+
+    let doc: serde_json::Value = http
+        .get(format!("{}/status", local))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(doc["test_fixed"]["max"], 3);
+    let other = http.get(format!("{}/other", local)).send().await.unwrap();
+    assert_eq!(other.status(), 404);
+    let action = http.post(format!("{}/test_local/x", local)).send().await.unwrap();
+    assert_eq!(action.status(), 404);
+
+    let unauthorized = http.get(format!("{}/status", remote)).send().await.unwrap();
+    assert_eq!(unauthorized.status(), 401);
+    let doc: serde_json::Value = http
+        .get(format!("{}/status", remote))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(doc["test_fixed"]["max"], 3);
+}